@@ -0,0 +1,97 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+use ibapi::replay::replay_local;
+use ibapi::wrapper::LocalWrapper;
+
+// Each iteration replays a batch of copies of the same message, so that the cost of setting up a
+// replay is spread across many messages.
+const BATCH: usize = 1_000;
+
+struct Sink;
+
+impl LocalWrapper for Sink {}
+
+fn fields(fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|&field| field.to_owned()).collect()
+}
+
+// A full day of one-minute bars during regular trading hours.
+fn historical_data() -> Vec<String> {
+    let mut fields = fields(&[
+        "17",
+        "9",
+        "20240102 09:30:00 US/Eastern",
+        "20240102 16:00:00 US/Eastern",
+        "390",
+    ]);
+    for minute in 0..390 {
+        fields.extend([
+            format!(
+                "20240102 {:02}:{:02}:00 US/Eastern",
+                9 + (30 + minute) / 60,
+                (30 + minute) % 60
+            ),
+            "189.42".to_owned(),
+            "189.55".to_owned(),
+            "189.31".to_owned(),
+            "189.47".to_owned(),
+            "1523.5".to_owned(),
+            "189.44".to_owned(),
+            "412".to_owned(),
+        ]);
+    }
+    fields
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to build the runtime");
+    let mut messages = vec![
+        (
+            "tick_price_msg",
+            fields(&["1", "6", "3", "1", "189.47", "300", "3"]),
+            BATCH,
+        ),
+        (
+            "tick_by_tick_msg",
+            fields(&[
+                "99",
+                "7",
+                "1",
+                "1704205800",
+                "189.47",
+                "100",
+                "0",
+                "ISLAND",
+                "",
+            ]),
+            BATCH,
+        ),
+        ("historical_data_msg", historical_data(), BATCH / 100),
+    ];
+    if cfg!(feature = "depth") {
+        messages.push((
+            "market_depth_l2_msg",
+            fields(&["13", "1", "8", "0", "NSDQ", "1", "1", "189.46", "400", "0"]),
+            BATCH,
+        ));
+    }
+
+    let mut group = c.benchmark_group("decode");
+    let mut wrapper = Sink;
+    for (name, message, batch) in messages {
+        group.throughput(Throughput::Elements(batch as u64));
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || vec![message.clone(); batch],
+                |batch| runtime.block_on(replay_local(&mut wrapper, batch)),
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
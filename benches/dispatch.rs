@@ -0,0 +1,86 @@
+use std::future::Future;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use ibapi::client::{ActiveClient, Builder, Dispatch};
+use ibapi::requests::UnknownPolicy;
+use ibapi::testing::{MockConnection, MockServer};
+use ibapi::tick::{Class, Price};
+use ibapi::wrapper::Wrapper;
+use tokio::sync::mpsc;
+
+// Each iteration sends a burst of ticks and waits until the wrapper has received all of them.
+const BURST: usize = 1_000;
+
+// Reports every completed burst of ticks.
+struct Counter {
+    ticks: usize,
+    tx: mpsc::UnboundedSender<()>,
+}
+
+impl Wrapper for Counter {
+    fn price_data(&mut self, _req_id: i64, _price: Class<Price>) -> impl Future + Send {
+        self.ticks += 1;
+        if self.ticks.is_multiple_of(BURST) {
+            let _ = self.tx.send(());
+        }
+        async {}
+    }
+}
+
+async fn connect(
+    dispatch: Dispatch,
+) -> (ActiveClient, MockConnection, mpsc::UnboundedReceiver<()>) {
+    let builder = Builder::manual(4002, None)
+        .with_dispatch(dispatch)
+        .with_unknown_requests(UnknownPolicy::Dispatch);
+    let (client_end, server_end) = ibapi::transport::in_memory(1 << 20);
+    let server = MockServer::new();
+    let (client, connection) = tokio::join!(
+        builder.connect_with(client_end, 0),
+        server.handshake(server_end)
+    );
+    let (tx, rx) = mpsc::unbounded_channel();
+    let client = client
+        .expect("Failed to connect to the mock server")
+        .disaggregated(Counter { ticks: 0, tx })
+        .await;
+    (client, connection.expect("Failed to accept the client"), rx)
+}
+
+fn bench_dispatch(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .expect("Failed to build the runtime");
+    let burst = vec![["1", "6", "3", "4", "189.47", "300", "0"]; BURST];
+
+    let mut group = c.benchmark_group("dispatch");
+    group.throughput(Throughput::Elements(BURST as u64));
+    for (name, dispatch) in [
+        ("poll", Dispatch::Poll),
+        ("batched_16", Dispatch::Batched(16)),
+        ("batched_256", Dispatch::Batched(256)),
+    ] {
+        let (client, mut connection, mut rx) = runtime.block_on(connect(dispatch));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    connection
+                        .send_all(&burst)
+                        .await
+                        .expect("Failed to send the ticks");
+                    rx.recv().await.expect("The client loop stopped");
+                });
+            });
+        });
+        runtime
+            .block_on(client.disconnect())
+            .expect("Failed to disconnect");
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_dispatch);
+criterion_main!(benches);
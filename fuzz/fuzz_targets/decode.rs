@@ -0,0 +1,37 @@
+//! Feeds arbitrary messages through the decoding logic of a live client, which must reject any
+//! malformed message without panicking.
+//!
+//! Each input is a single message in the format of `seeds.txt`, whose messages make a good
+//! starting corpus:
+//!
+//! ```sh
+//! mkdir -p fuzz/corpus/decode
+//! grep -v '^#' fuzz/seeds.txt | split -l 1 - fuzz/corpus/decode/seed-
+//! cargo +nightly fuzz run decode
+//! ```
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use ibapi::{replay, wrapper::LocalWrapper};
+use libfuzzer_sys::fuzz_target;
+
+struct Sink;
+
+impl LocalWrapper for Sink {}
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("the runtime should build")
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let message = String::from_utf8_lossy(data);
+    let fields = replay::parse_message(message.strip_suffix('\n').unwrap_or(&message));
+    runtime().block_on(replay::replay_local(&mut Sink, [fields]));
+});
@@ -40,9 +40,8 @@ pub fn impl_debug_trait(ast: &mut ItemTrait) {
                 block: syn::Block {
                     brace_token: syn::token::Brace::default(),
                     stmts: vec![
-                        syn::parse(quote! { eprint!("{}", #prefix); }.into()).unwrap(),
-                        syn::parse(quote! { dbg!((#args)); }.into()).unwrap(),
-                        // syn::parse(quote! { return ().into(); }.into()).unwrap()
+                        syn::parse(quote! { tracing::debug!("{} {:?}", #prefix, (#args)); }.into())
+                            .unwrap(),
                     ],
                 },
             }),
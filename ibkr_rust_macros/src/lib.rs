@@ -9,11 +9,13 @@ mod send_trait;
 mod variant_value;
 
 #[allow(clippy::missing_panics_doc)]
-#[proc_macro_derive(Security)]
+#[proc_macro_derive(Security, attributes(security))]
 pub fn security_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
 
-    security::impl_security(&ast).into()
+    security::impl_security(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
 #[allow(clippy::missing_panics_doc)]
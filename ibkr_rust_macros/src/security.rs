@@ -1,290 +1,277 @@
-use std::collections::HashSet;
-
 use proc_macro2::TokenStream;
-use quote::quote;
-#[allow(clippy::enum_glob_use)]
-use SecType::*;
-use syn::{Ident, parse_str};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, LitStr};
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
-enum SecType {
-    Forex,
-    Crypto,
-    Stock,
-    Index,
-    SecFuture,
-    SecOption,
-    Commodity,
-}
+// The IBKR security types that can be derived, each with its variant of `ContractType` and
+// whether `ibapi` defines a built-in contract (and a variant of `Contract`) for it.
+const SEC_TYPES: [(&str, &str, bool); 13] = [
+    ("CASH", "Forex", true),
+    ("CRYPTO", "Crypto", true),
+    ("STK", "Stock", true),
+    ("IND", "Index", true),
+    ("FUT", "SecFuture", true),
+    ("OPT", "SecOption", true),
+    ("CMDTY", "Commodity", true),
+    ("CFD", "Cfd", false),
+    ("FOP", "FutureSecOption", false),
+    ("BOND", "Bond", false),
+    ("FUND", "MutualFund", false),
+    ("WAR", "Warrant", false),
+    ("IOPT", "StructuredProduct", false),
+];
 
-impl SecType {
-    #[inline]
-    const fn as_str(self) -> &'static str {
-        match self {
-            Forex => "Forex",
-            Crypto => "Crypto",
-            Stock => "Stock",
-            Index => "Index",
-            SecFuture => "SecFuture",
-            SecOption => "SecOption",
-            Commodity => "Commodity",
-        }
-    }
+#[derive(Default)]
+struct Options {
+    sec_type: Option<LitStr>,
+    has_expiry: bool,
+    has_multiplier: bool,
+    has_strike: bool,
+    has_primary_exchange: bool,
+    no_trading_class: bool,
 }
 
-impl From<&str> for SecType {
-    fn from(s: &str) -> Self {
-        match s {
-            "Forex" => Forex,
-            "Crypto" => Crypto,
-            "Stock" => Stock,
-            "Index" => Index,
-            "SecFuture" => SecFuture,
-            "SecOption" => SecOption,
-            "Commodity" => Commodity,
-            _ => panic!("Invalid Security name {s}."),
+impl Options {
+    fn parse(ast: &DeriveInput) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in ast.attrs.iter().filter(|a| a.path().is_ident("security")) {
+            attr.parse_nested_meta(|meta| {
+                let flag = if meta.path.is_ident("sec_type") {
+                    options.sec_type = Some(meta.value()?.parse()?);
+                    return Ok(());
+                } else if meta.path.is_ident("has_expiry") {
+                    &mut options.has_expiry
+                } else if meta.path.is_ident("has_multiplier") {
+                    &mut options.has_multiplier
+                } else if meta.path.is_ident("has_strike") {
+                    &mut options.has_strike
+                } else if meta.path.is_ident("has_primary_exchange") {
+                    &mut options.has_primary_exchange
+                } else if meta.path.is_ident("no_trading_class") {
+                    &mut options.no_trading_class
+                } else {
+                    return Err(meta.error(
+                        "expected `sec_type`, `has_expiry`, `has_multiplier`, `has_strike`, \
+                         `has_primary_exchange`, or `no_trading_class`",
+                    ));
+                };
+                *flag = true;
+                Ok(())
+            })?;
         }
+        Ok(options)
     }
 }
 
-impl From<&SecType> for &'static str {
-    fn from(value: &SecType) -> Self {
-        value.as_str()
-    }
-}
-
-impl From<&Ident> for SecType {
-    fn from(value: &Ident) -> Self {
-        let s = value.to_string();
-        s.as_str().into()
-    }
-}
-
-const CONTRACTS: [SecType; 7] = [Forex, Crypto, Stock, Index, SecFuture, SecOption, Commodity];
+// A variant of an enum of securities, which wraps the struct that holds its fields, along with the
+// option right that it represents, if any.
+type Variant = (Ident, Option<LitStr>);
 
-fn impl_try_from_other_contracts(name: &Ident) -> TokenStream {
-    let idents = CONTRACTS
-        .into_iter()
-        .filter_map(|c| {
-            if c == name.into() {
-                None
-            } else {
-                parse_str(c.as_str()).unwrap()
+fn variants(ast: &DeriveInput) -> syn::Result<Option<Vec<Variant>>> {
+    let Data::Enum(data) = &ast.data else {
+        return Ok(None);
+    };
+    data.variants
+        .iter()
+        .map(|variant| {
+            if !matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1) {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "each variant of a security must wrap a single struct that holds its fields",
+                ));
             }
-        })
-        .collect::<HashSet<Ident>>()
-        .into_iter();
-
-    quote! {
-        #(impl TryFrom<#idents> for #name {
-            type Error = UnexpectedSecurityType;
-
-            fn try_from(_: #idents) -> Result<Self, Self::Error> {
-                Err(UnexpectedSecurityType {
-                    expected: ContractType::#name,
-                    found: ContractType::#idents,
-                })
+            let mut right = None;
+            for attr in variant
+                .attrs
+                .iter()
+                .filter(|a| a.path().is_ident("security"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("right") {
+                        right = Some(meta.value()?.parse()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `right`"))
+                    }
+                })?;
             }
-        })*
-    }
+            Ok((variant.ident.clone(), right))
+        })
+        .collect::<syn::Result<_>>()
+        .map(Some)
 }
 
-fn impl_into_contract(name: &Ident) -> TokenStream {
-    let idents = CONTRACTS
+fn impl_conversions(name: &Ident, contract_type: &Ident, builtin: bool) -> TokenStream {
+    let others = SEC_TYPES
         .into_iter()
-        .filter_map(|c| {
-            if c == name.into() {
-                None
-            } else {
-                parse_str(c.as_str()).unwrap()
+        .filter(|&(_, c, b)| b && !(builtin && *contract_type == c))
+        .map(|(_, c, _)| format_ident!("{c}"))
+        .collect::<Vec<_>>();
+
+    let contract_impls = if builtin {
+        quote! {
+            impl From<#name> for ::ibapi::contract::Contract {
+                fn from(value: #name) -> Self {
+                    Self::#name(value)
+                }
             }
-        })
-        .collect::<HashSet<Ident>>()
-        .into_iter();
 
-    quote! {
-        impl From<#name> for Contract {
-            fn from(value: #name) -> Self {
-                Self::#name(value)
+            impl TryFrom<::ibapi::contract::Contract> for #name {
+                type Error = ::ibapi::contract::UnexpectedSecurityType;
+
+                fn try_from(value: ::ibapi::contract::Contract) -> Result<Self, Self::Error> {
+                    match value {
+                        ::ibapi::contract::Contract::#name(t) => Ok(t),
+                        #(::ibapi::contract::Contract::#others(_) => Err(
+                            ::ibapi::contract::UnexpectedSecurityType::new(
+                                ::ibapi::contract::ContractType::#name,
+                                ::ibapi::contract::ContractType::#others,
+                            )
+                        )),*
+                    }
+                }
             }
         }
+    } else {
+        quote! {
+            impl TryFrom<::ibapi::contract::Contract> for #name {
+                type Error = ::ibapi::contract::UnexpectedSecurityType;
 
-        impl TryFrom<Contract> for #name {
-            type Error = UnexpectedSecurityType;
-
-            fn try_from(value: Contract) -> Result<Self, Self::Error> {
-                match value {
-                    Contract::#name(t) => Ok(t),
-                    #(Contract::#idents(_) => Err(
-                        UnexpectedSecurityType {
-                            expected: ContractType::#name,
-                            found: ContractType::#idents
-                        }
-                    )),*
+                fn try_from(value: ::ibapi::contract::Contract) -> Result<Self, Self::Error> {
+                    Err(::ibapi::contract::UnexpectedSecurityType::new(
+                        ::ibapi::contract::ContractType::#contract_type,
+                        ::ibapi::contract::Security::contract_type(&value),
+                    ))
                 }
             }
         }
+    };
+
+    quote! {
+        #(impl TryFrom<::ibapi::contract::#others> for #name {
+            type Error = ::ibapi::contract::UnexpectedSecurityType;
+
+            fn try_from(_: ::ibapi::contract::#others) -> Result<Self, Self::Error> {
+                Err(::ibapi::contract::UnexpectedSecurityType::new(
+                    ::ibapi::contract::ContractType::#contract_type,
+                    ::ibapi::contract::ContractType::#others,
+                ))
+            }
+        })*
+
+        #contract_impls
     }
 }
 
 #[allow(clippy::module_name_repetitions, clippy::too_many_lines)]
-pub fn impl_security(ast: &syn::DeriveInput) -> TokenStream {
+pub fn impl_security(ast: &DeriveInput) -> syn::Result<TokenStream> {
     let name = &ast.ident;
-    let s_name: SecType = name.into();
-
-    let contract_id = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { self.contract_id },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.contract_id
-            }
-        },
+    let options = Options::parse(ast)?;
+    let Some(sec_type) = options.sec_type else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "expected a `#[security(sec_type = \"...\")]` attribute",
+        ));
     };
-    let symbol = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { self.symbol.as_str() },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.symbol.as_str()
-            }
-        },
-    };
-    let security_type = match s_name {
-        Forex => "CASH",
-        Crypto => "Crypto",
-        Stock => "STK",
-        Index => "IND",
-        SecFuture => "FUT",
-        SecOption => "OPT",
-        Commodity => "CMDTY",
-    };
-    let expiration_date = match s_name {
-        Forex | Crypto | Stock | Index | Commodity => {
-            quote! { None::<NaiveDate> }
-        }
-        SecFuture => quote! { Some(self.expiration_date) },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => Some(inner.expiration_date)
-            }
-        },
-    };
-    let strike = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { None::<f64> },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => Some(inner.strike)
-            }
-        },
-    };
-    let right = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { None::<&str> },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(_) => Some("C"),
-                SecOption::Put(_) => Some("P"),
-            }
-        },
+    let Some((_, contract_type, builtin)) = SEC_TYPES
+        .into_iter()
+        .find(|(s, _, _)| s.eq_ignore_ascii_case(&sec_type.value()))
+    else {
+        return Err(syn::Error::new_spanned(
+            &sec_type,
+            "expected one of CASH, CRYPTO, STK, IND, FUT, OPT, CMDTY, CFD, FOP, BOND, FUND, \
+             WAR, or IOPT",
+        ));
     };
-    let multiplier = match s_name {
-        Forex | Crypto | Stock | Index | Commodity => {
-            quote! { None::<u32> }
-        }
-        SecFuture => quote! { Some(self.multiplier) },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => Some(inner.multiplier)
+    if builtin && name != contract_type {
+        return Err(syn::Error::new_spanned(
+            &sec_type,
+            format!(
+                "{} securities are represented by the built-in `ibapi::contract::{contract_type}`",
+                sec_type.value()
+            ),
+        ));
+    }
+    let contract_type = format_ident!("{contract_type}");
+    let variants = variants(ast)?;
+
+    // Every field is read through `inner`, which is either the security itself or the struct
+    // wrapped by each variant of an enum.
+    let field = |expr: TokenStream| match &variants {
+        None => quote! {{
+            let inner = self;
+            #expr
+        }},
+        Some(variants) => {
+            let idents = variants.iter().map(|(ident, _)| ident);
+            quote! {
+                match self {
+                    #(Self::#idents(inner))|* => #expr
+                }
             }
-        },
-    };
-    let exchange = match s_name {
-        Forex | Stock | Index | SecFuture | Commodity => {
-            quote! { self.exchange }
         }
-        Crypto => quote! { Routing::Primary(Primary::PaxosCryptoExchange) },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.exchange
-            }
-        },
-    };
-    let primary_exchange = match s_name {
-        Forex | Crypto | Index | SecFuture | SecOption | Commodity => quote! { None::<Primary> },
-        Stock => quote! { Some(self.primary_exchange) },
     };
-    let currency = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { self.currency },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.currency
-            }
-        },
-    };
-    let local_symbol = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => {
-            quote! { self.local_symbol.as_str() }
+    let optional = |enabled: bool, expr: TokenStream, ty: TokenStream| {
+        if enabled {
+            field(quote! { Some(#expr) })
+        } else {
+            quote! { None::<#ty> }
         }
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.local_symbol.as_str()
-            }
-        },
     };
-    let min_tick = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { self.min_tick },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.min_tick
+
+    let contract_id = field(quote! { inner.contract_id });
+    let symbol = field(quote! { inner.symbol.as_str() });
+    let expiration_date = optional(
+        options.has_expiry,
+        quote! { inner.expiration_date },
+        quote! { ::ibapi::__private::chrono::NaiveDate },
+    );
+    let strike = optional(options.has_strike, quote! { inner.strike }, quote! { f64 });
+    let right = match &variants {
+        Some(variants) if variants.iter().any(|(_, right)| right.is_some()) => {
+            let arms = variants.iter().map(|(ident, right)| match right {
+                Some(right) => quote! { Self::#ident(_) => Some(#right) },
+                None => quote! { Self::#ident(_) => None },
+            });
+            quote! {
+                match self {
+                    #(#arms),*
+                }
             }
-        },
-    };
-    let trading_class = match s_name {
-        Forex | Crypto | Stock | SecFuture | Commodity => {
-            quote! { Some(self.trading_class.as_str()) }
         }
-        Index => quote! { None::<&str> },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => Some(inner.trading_class.as_str())
-            }
-        },
-    };
-    let long_name = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => {
-            quote! { self.long_name.as_str() }
-        }
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => inner.long_name.as_str()
-            }
-        },
-    };
-    let order_types = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { &self.order_types },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => &inner.order_types
-            }
-        },
-    };
-    let valid_exchanges = match s_name {
-        Forex | Crypto | Stock | Index | SecFuture | Commodity => quote! { &self.valid_exchanges },
-        SecOption => quote! {
-            match self {
-                SecOption::Call(inner) | SecOption::Put(inner) => &inner.valid_exchanges
-            }
-        },
+        _ => quote! { None::<&'static str> },
     };
+    let multiplier = optional(
+        options.has_multiplier,
+        quote! { inner.multiplier },
+        quote! { u32 },
+    );
+    let exchange = field(quote! { inner.exchange });
+    let primary_exchange = optional(
+        options.has_primary_exchange,
+        quote! { inner.primary_exchange },
+        quote! { ::ibapi::exchange::Primary },
+    );
+    let currency = field(quote! { inner.currency });
+    let local_symbol = field(quote! { inner.local_symbol.as_str() });
+    let min_tick = field(quote! { inner.min_tick });
+    let trading_class = optional(
+        !options.no_trading_class,
+        quote! { inner.trading_class.as_str() },
+        quote! { &str },
+    );
+    let long_name = field(quote! { inner.long_name.as_str() });
+    let order_types = field(quote! { &inner.order_types });
+    let valid_exchanges = field(quote! { &inner.valid_exchanges });
 
-    let try_from_impl = impl_try_from_other_contracts(name);
-    let into_contract_impl = impl_into_contract(name);
+    let conversions = impl_conversions(name, &contract_type, builtin);
 
-    let gen_tokens = quote! {
-        impl crate::contract::indicators::Valid for #name {
-            fn as_out_msg(&self) -> crate::contract::indicators::SecurityOutMsg<'_> {
-                crate::contract::indicators::SecurityOutMsg {
+    Ok(quote! {
+        impl ::ibapi::contract::indicators::Valid for #name {
+            fn as_out_msg(&self) -> ::ibapi::contract::indicators::SecurityOutMsg<'_> {
+                ::ibapi::contract::indicators::SecurityOutMsg {
                     contract_id: #contract_id,
                     symbol: #symbol,
-                    security_type: #security_type,
+                    security_type: #sec_type,
                     expiration_date: #expiration_date,
                     strike: #strike,
                     right: #right,
@@ -298,11 +285,16 @@ pub fn impl_security(ast: &syn::DeriveInput) -> TokenStream {
             }
         }
 
-        impl serde::Serialize for #name {
-            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        impl ::ibapi::__private::serde::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::ibapi::__private::serde::Serializer,
+            {
+                use ::ibapi::__private::serde::ser::SerializeStruct;
+
                 let mut state = serializer.serialize_struct("Contract", 14)?;
                 state.serialize_field("contract_id", &#contract_id)?;
-                state.serialize_field("security_type", &#security_type)?;
+                state.serialize_field("security_type", &#sec_type)?;
                 state.serialize_field("symbol", &#symbol)?;
                 state.serialize_field("long_name", &#long_name)?;
                 state.serialize_field("min_tick", &#min_tick)?;
@@ -319,9 +311,9 @@ pub fn impl_security(ast: &syn::DeriveInput) -> TokenStream {
             }
         }
 
-        impl Security for #name {
+        impl ::ibapi::contract::Security for #name {
             #[inline]
-            fn contract_id(&self) -> ContractId {
+            fn contract_id(&self) -> ::ibapi::contract::ContractId {
                 #contract_id
             }
             #[inline]
@@ -333,7 +325,7 @@ pub fn impl_security(ast: &syn::DeriveInput) -> TokenStream {
                 #symbol
             }
             #[inline]
-            fn currency(&self) -> Currency {
+            fn currency(&self) -> ::ibapi::currency::Currency {
                 #currency
             }
             #[inline]
@@ -349,18 +341,15 @@ pub fn impl_security(ast: &syn::DeriveInput) -> TokenStream {
                 #order_types
             }
             #[inline]
-            fn valid_exchanges(&self) -> &Vec<Routing> {
+            fn valid_exchanges(&self) -> &Vec<::ibapi::exchange::Routing> {
                 #valid_exchanges
             }
             #[inline]
-            fn contract_type(&self) -> ContractType {
-                ContractType::#name
+            fn contract_type(&self) -> ::ibapi::contract::ContractType {
+                ::ibapi::contract::ContractType::#contract_type
             }
         }
 
-        #try_from_impl
-
-        #into_contract_impl
-    };
-    gen_tokens
+        #conversions
+    })
 }
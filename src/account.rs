@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Formatter;
 use std::num::{ParseFloatError, ParseIntError};
 use std::str::{FromStr, ParseBoolError};
@@ -6,6 +7,10 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::currency::{Currency, ParseCurrencyError};
+#[cfg(feature = "client")]
+use crate::payload::Pnl;
+#[cfg(feature = "client")]
+use crate::requests::{category, RequestId};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "attribute", content = "data")]
@@ -169,6 +174,16 @@ pub enum Attribute {
     WarrantValue(f64, Denomination),
     /// To check projected margin requirements under Portfolio Margin model.
     WhatIfPMEnabled(bool),
+    /// An attribute that does not (yet) have a dedicated variant, or whose value is empty. The raw
+    /// fields are passed through unparsed so that such attributes are never dropped.
+    Unknown {
+        /// The name of the attribute, as sent by the API.
+        name: String,
+        /// The unparsed value of the attribute.
+        value: String,
+        /// The unparsed currency associated with the attribute, which may be empty.
+        currency: String,
+    },
 }
 
 #[derive(Debug, Clone, Error)]
@@ -294,6 +309,282 @@ impl FromStr for Group {
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Aggregates the account attributes of several
+/// [`crate::client::Client::req_account_updates_multi`] subscriptions per model code, as
+/// returned by [`crate::client::Client::req_account_updates_by_model`].
+///
+/// Pass each call of [`crate::wrapper::LocalWrapper::account_attribute_multi`] and
+/// [`crate::wrapper::LocalWrapper::account_attribute_multi_end`] to the method of the same name.
+/// The latest values of every account are kept separately, and the aggregates of a model are the
+/// sums over its accounts. Attributes that are reported without a model code are kept under the
+/// model code of the subscription that received them.
+pub struct ModelAccounts {
+    models: HashMap<i64, String>,
+    accounts: BTreeMap<String, BTreeMap<String, ModelAccount>>,
+    loaded: HashSet<i64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ModelAccount {
+    net_liquidation: Option<f64>,
+    excess_liquidity: Option<f64>,
+    cash: HashMap<Denomination, f64>,
+    realized_pnl: Option<f64>,
+    unrealized_pnl: Option<f64>,
+}
+
+impl ModelAccounts {
+    #[inline]
+    #[must_use]
+    /// Create a new, empty [`ModelAccounts`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    /// Aggregate the attributes of a subscription under a model code.
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the [`crate::client::Client::req_account_updates_multi`]
+    ///   subscription.
+    /// * `model_code` - The model code of the subscription.
+    pub fn register(&mut self, req_id: i64, model_code: String) {
+        self.accounts.entry(model_code.clone()).or_default();
+        self.models.insert(req_id, model_code);
+    }
+
+    /// Record an account attribute. Only the net liquidation value, excess liquidity, cash
+    /// balances, and P&L are kept.
+    ///
+    /// # Returns
+    /// The model code under which the attribute was recorded, if the attribute belongs to a
+    /// registered subscription and changed a value.
+    pub fn account_attribute_multi(
+        &mut self,
+        req_id: i64,
+        attribute: Attribute,
+        account_number: String,
+        model_code: Option<String>,
+    ) -> Option<String> {
+        let registered = self.models.get(&req_id)?;
+        let model_code = model_code.unwrap_or_else(|| registered.clone());
+        let account = self
+            .accounts
+            .entry(model_code.clone())
+            .or_default()
+            .entry(account_number)
+            .or_default();
+        let changed = match attribute {
+            Attribute::NetLiquidation(Segment::Total(value), _) => {
+                account.net_liquidation.replace(value) != Some(value)
+            }
+            Attribute::ExcessLiquidity(Segment::Total(value), _) => {
+                account.excess_liquidity.replace(value) != Some(value)
+            }
+            Attribute::CashBalance(value, denomination) => {
+                account.cash.insert(denomination, value) != Some(value)
+            }
+            Attribute::RealizedPnL(value, Denomination::Base) => {
+                account.realized_pnl.replace(value) != Some(value)
+            }
+            Attribute::UnrealizedPnL(value, Denomination::Base) => {
+                account.unrealized_pnl.replace(value) != Some(value)
+            }
+            _ => false,
+        };
+        changed.then_some(model_code)
+    }
+
+    #[inline]
+    /// Record that the initial download of a subscription has completed.
+    pub fn account_attribute_multi_end(&mut self, req_id: i64) {
+        if self.models.contains_key(&req_id) {
+            self.loaded.insert(req_id);
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return `true` once the initial download of every registered subscription has completed.
+    pub fn is_loaded(&self) -> bool {
+        self.models
+            .keys()
+            .all(|req_id| self.loaded.contains(req_id))
+    }
+
+    #[inline]
+    /// Iterate over every model code, in order.
+    pub fn models(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    #[inline]
+    /// Iterate over the accounts that have reported attributes for a model, in order.
+    pub fn accounts(&self, model_code: &str) -> impl Iterator<Item = &str> {
+        self.accounts
+            .get(model_code)
+            .into_iter()
+            .flat_map(|accounts| accounts.keys().map(String::as_str))
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the net liquidation value of a model, summed over its accounts.
+    pub fn net_liquidation(&self, model_code: &str) -> Option<f64> {
+        self.sum(model_code, |account| account.net_liquidation)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the excess liquidity of a model, summed over its accounts.
+    pub fn excess_liquidity(&self, model_code: &str) -> Option<f64> {
+        self.sum(model_code, |account| account.excess_liquidity)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the cash balance of a model in a given denomination, summed over its accounts.
+    pub fn cash(&self, model_code: &str, denomination: Denomination) -> Option<f64> {
+        self.sum(model_code, |account| {
+            account.cash.get(&denomination).copied()
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the realized P&L of a model in the base currency, summed over its accounts.
+    pub fn realized_pnl(&self, model_code: &str) -> Option<f64> {
+        self.sum(model_code, |account| account.realized_pnl)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the unrealized P&L of a model in the base currency, summed over its accounts.
+    pub fn unrealized_pnl(&self, model_code: &str) -> Option<f64> {
+        self.sum(model_code, |account| account.unrealized_pnl)
+    }
+
+    // The sum of a value over the accounts of a model that have reported it.
+    fn sum(&self, model_code: &str, value: impl Fn(&ModelAccount) -> Option<f64>) -> Option<f64> {
+        self.accounts
+            .get(model_code)?
+            .values()
+            .filter_map(value)
+            .reduce(|total, value| total + value)
+    }
+}
+
+#[cfg(feature = "client")]
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Consolidates the P&L of several accounts, each from its own [`crate::client::Client::req_pnl`]
+/// subscription, as returned by [`crate::client::Client::req_pnl_rollup`].
+///
+/// Pass each call of [`crate::wrapper::LocalWrapper::pnl`] to [`PnlRollup::pnl`]. The latest P&L
+/// of every account is kept separately, and the consolidated values are the sums over the
+/// accounts that have reported them. Values that IBKR reports as unset are left out of the sums.
+pub struct PnlRollup {
+    req_ids: BTreeMap<String, RequestId<category::Pnl>>,
+    accounts: HashMap<i64, String>,
+    pnl: BTreeMap<String, Pnl>,
+}
+
+#[cfg(feature = "client")]
+impl PnlRollup {
+    #[inline]
+    #[must_use]
+    /// Create a new, empty [`PnlRollup`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    /// Consolidate the P&L of a subscription under an account number.
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the [`crate::client::Client::req_pnl`] subscription.
+    /// * `account_number` - The account number of the subscription.
+    pub fn register(&mut self, req_id: RequestId<category::Pnl>, account_number: String) {
+        self.accounts.insert(req_id.get(), account_number.clone());
+        self.req_ids.insert(account_number, req_id);
+    }
+
+    /// Record the P&L of an account.
+    ///
+    /// # Returns
+    /// The account number of the subscription, if it is registered and the P&L changed.
+    pub fn pnl(&mut self, req_id: i64, pnl: Pnl) -> Option<&str> {
+        let account_number = self.accounts.get(&req_id)?;
+        (self.pnl.insert(account_number.clone(), pnl) != Some(pnl))
+            .then_some(account_number.as_str())
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return `true` once every registered account has reported its P&L.
+    pub fn is_loaded(&self) -> bool {
+        self.req_ids
+            .keys()
+            .all(|account_number| self.pnl.contains_key(account_number))
+    }
+
+    #[inline]
+    /// Iterate over the account number and request ID of every subscription, in order of account
+    /// number.
+    pub fn req_ids(&self) -> impl Iterator<Item = (&str, RequestId<category::Pnl>)> {
+        self.req_ids
+            .iter()
+            .map(|(account_number, &req_id)| (account_number.as_str(), req_id))
+    }
+
+    #[inline]
+    /// Iterate over the latest P&L of every account that has reported it, in order of account
+    /// number.
+    pub fn accounts(&self) -> impl Iterator<Item = (&str, Pnl)> {
+        self.pnl
+            .iter()
+            .map(|(account_number, &pnl)| (account_number.as_str(), pnl))
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the latest P&L of an account, if it has reported it.
+    pub fn account(&self, account_number: &str) -> Option<Pnl> {
+        self.pnl.get(account_number).copied()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the daily P&L, summed over the accounts.
+    pub fn daily(&self) -> Option<f64> {
+        self.sum(|pnl| pnl.daily)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the unrealized P&L, summed over the accounts.
+    pub fn unrealized(&self) -> Option<f64> {
+        self.sum(|pnl| pnl.unrealized)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the realized P&L, summed over the accounts.
+    pub fn realized(&self) -> Option<f64> {
+        self.sum(|pnl| pnl.realized)
+    }
+
+    // The sum of a value over the accounts that have reported it. IBKR reports an unset value as
+    // `f64::MAX`.
+    fn sum(&self, value: impl Fn(&Pnl) -> f64) -> Option<f64> {
+        self.pnl
+            .values()
+            .map(value)
+            .filter(|value| *value < f64::MAX)
+            .reduce(|total, value| total + value)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(tag = "segment")]
 /// The intra-account segments of various values.
@@ -308,7 +599,7 @@ pub enum Segment<T> {
     Security(T),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 /// The denomination of a given value.
 pub enum Denomination {
@@ -382,6 +673,75 @@ pub enum TagValue {
     Float(Tag, f64),
     /// A tag whose value is a float (f64), Currency pair
     Currency(Tag, f64, Currency),
+    /// A row of the per-currency ledger requested with a [`Ledger`]. Most ledger values are
+    /// numeric, but some (ex. `AccountOrGroup` and `RealCurrency`) are not, so the value is passed
+    /// through unparsed.
+    Ledger {
+        /// The name of the ledger row, as sent by the API (ex. `CashBalance`).
+        tag: String,
+        /// The unparsed value of the row.
+        value: String,
+        /// The currency of the ledger to which the row belongs.
+        denomination: Denomination,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Selects the per-currency ledger rows to include in a
+/// [`crate::client::Client::req_account_summary`] request, in addition to its [`Tag`]s.
+pub enum Ledger {
+    /// The ledger in the account's base currency (`$LEDGER`).
+    Base,
+    /// The ledger in a specific currency (`$LEDGER:CURRENCY`).
+    Specific(Currency),
+    /// The ledgers in every currency held by the account (`$LEDGER:ALL`).
+    All,
+}
+
+impl std::fmt::Display for Ledger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Base => write!(f, "$LEDGER"),
+            Self::Specific(currency) => write!(f, "$LEDGER:{currency}"),
+            Self::All => write!(f, "$LEDGER:ALL"),
+        }
+    }
+}
+
+impl Serialize for Ledger {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+// The list of tags in an account summary request, where a ledger is requested with one more tag.
+#[cfg(feature = "client")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SummaryTags<'a> {
+    pub(crate) tags: &'a [Tag],
+    pub(crate) ledger: Option<Ledger>,
+}
+
+#[cfg(feature = "client")]
+impl Serialize for SummaryTags<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.tags.len() + 1))?;
+        for tag in self.tags {
+            seq.serialize_element(tag)?;
+        }
+        if let Some(ledger) = &self.ledger {
+            seq.serialize_element(ledger)?;
+        }
+        seq.end()
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -4,27 +4,31 @@ use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::{io::AsyncReadExt, net::TcpStream, sync::mpsc};
-use tokio::net::tcp::OwnedReadHalf;
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
-    account::Tag,
+    account::{Group, Ledger, ModelAccounts, PnlRollup, SummaryTags, Tag},
     comm::Writer,
     constants, decode,
     execution::Filter,
-    order::{Executable, Order},
+    frame::Frame,
+    limits::Kind,
+    requests::{category, Kind as RequestKind, RequestId},
+    order::{Executable, ExerciseAction, Order, PlacedOrder},
     payload::ExchangeId,
+    queue,
     reader::Reader,
+    transport::{self, ReadHalf, Transport, WriteHalf},
 };
-use crate::contract::{ContractId, Query, Security};
+use crate::contract::{ContractId, Query, SecFuture, SecOption, Security};
 use crate::decode::DecodeError;
 use crate::exchange::Routing;
 use crate::market_data::{
     histogram, historical_bar, historical_ticks, live_bar, live_data, live_ticks,
     updating_historical_bar,
 };
-use crate::message::{In, Out, ToClient, ToWrapper};
+use crate::message::{Awaited, In, Inbox, Out, Shared, ToClient, ToWrapper};
 use crate::wrapper::{
     CancelToken, Initializer, LocalInitializer, LocalWrapper, Recurring, Wrapper,
 };
@@ -41,11 +45,21 @@ struct Ports {
     gateway_paper: u16,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Restarts {
+    #[serde(alias = "Tws")]
+    tws: Option<crate::restart::Window>,
+    #[serde(alias = "Gateway")]
+    gateway: Option<crate::restart::Window>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct Config {
     address: std::net::Ipv4Addr,
     #[serde(alias = "Ports")]
     ports: Ports,
+    #[serde(default, alias = "Restart")]
+    restart: Restarts,
 }
 
 #[derive(Debug, Error)]
@@ -124,6 +138,502 @@ impl std::fmt::Display for Host {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The direction in which a message observed by a [`MessageTap`] was travelling.
+pub enum Direction {
+    /// A message received from IBKR's trading systems.
+    Inbound,
+    /// A message sent to IBKR's trading systems.
+    Outbound,
+}
+
+type TapFn = dyn Fn(Direction, &[String]) + Send + Sync;
+
+#[derive(Clone)]
+/// A hook that observes every raw message sent to or received from IBKR's trading systems.
+///
+/// Each message is passed to the tap as its individual fields, exactly as they are split before
+/// decoding (inbound) or after serialization (outbound). This makes a tap useful for logging,
+/// capturing sessions for later replay, and debugging messages that fail to decode. A tap is
+/// installed by calling [`Builder::with_message_tap`].
+///
+/// The tap is called on the reader and writer tasks, so it should return quickly.
+pub struct MessageTap(std::sync::Arc<TapFn>);
+
+impl MessageTap {
+    #[inline]
+    /// Create a new tap that calls `f` with every message.
+    ///
+    /// # Arguments
+    /// * `f` - The function to call with the direction and fields of every message.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(Direction, &[String]) + Send + Sync + 'static,
+    {
+        Self(std::sync::Arc::new(f))
+    }
+
+    #[must_use]
+    #[inline]
+    /// Create a new tap that sends every message over a channel.
+    ///
+    /// Messages are silently discarded once the receiving half of the channel has been dropped.
+    ///
+    /// # Arguments
+    /// * `tx` - The sending half of the channel.
+    pub fn from_channel(tx: mpsc::UnboundedSender<(Direction, Vec<String>)>) -> Self {
+        Self::new(move |direction, fields| {
+            let _ = tx.send((direction, fields.to_vec()));
+        })
+    }
+
+    #[inline]
+    pub(crate) fn observe(&self, direction: Direction, fields: &[String]) {
+        (self.0)(direction, fields);
+    }
+}
+
+impl std::fmt::Debug for MessageTap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MessageTap")
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A structured record of a single outgoing message, as produced by an [`OutgoingLog`].
+///
+/// The [`std::fmt::Display`] implementation renders the record in a FIX-like `position=value`
+/// format (ex. `0=49|1=1`), which makes it easy to compare the serialized fields of a message
+/// against the layout expected by the API.
+pub struct OutgoingRecord {
+    /// The outgoing message identifier (the first field of the message).
+    pub message_id: String,
+    /// All fields of the message, including the message identifier.
+    pub fields: Vec<String>,
+}
+
+impl std::fmt::Display for OutgoingRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            write!(f, "{i}={field}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Determines where an [`OutgoingRecord`] of every message sent by the [`Client`] is delivered.
+/// Outgoing logging is enabled by calling [`Builder::with_outgoing_log`].
+pub enum OutgoingLog {
+    /// Emit each record as a [`tracing`] event at the `DEBUG` level with the target
+    /// `ibapi::outgoing`.
+    Tracing,
+    /// Send each record over a channel. Records are silently discarded once the receiving half of
+    /// the channel has been dropped.
+    Channel(mpsc::UnboundedSender<OutgoingRecord>),
+}
+
+impl OutgoingLog {
+    #[inline]
+    pub(crate) fn record(&self, fields: Vec<String>) {
+        let record = OutgoingRecord {
+            message_id: fields.first().cloned().unwrap_or_default(),
+            fields,
+        };
+        match self {
+            Self::Tracing => tracing::debug!(
+                target: "ibapi::outgoing",
+                message_id = %record.message_id,
+                message = %record,
+                "Outgoing message"
+            ),
+            Self::Channel(tx) => {
+                let _ = tx.send(record);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Determines what happens when a message arrives from IBKR's trading systems while the queue of
+/// messages waiting to be handled by the wrapper is full.
+pub enum OverflowPolicy {
+    #[default]
+    /// Wait until the wrapper takes a message from the queue. No messages are lost, but the
+    /// reader stops reading from the socket until there is room.
+    Block,
+    /// Discard the oldest message in the queue to make room for the new one.
+    DropOldest,
+    /// Replace a queued price, size, generic, or string tick with the same request ID and tick type
+    /// by the newer one. All other messages wait for room as in [`OverflowPolicy::Block`].
+    CoalesceMarketData,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Determines how the client loop takes incoming messages from the reader queue and passes them
+/// to the wrapper.
+pub enum Dispatch {
+    #[default]
+    /// Handle one message per loop iteration and yield to the runtime when none are waiting, so
+    /// that [`crate::wrapper::Recurring::cycle`] is called continuously even when the connection
+    /// is idle.
+    Poll,
+    /// Handle up to the given number of waiting messages per loop iteration and sleep when none
+    /// are waiting, until the next message arrives or for at most [`Dispatch::IDLE_CYCLE`]. This
+    /// avoids spinning while idle, calls [`crate::wrapper::Recurring::cycle`] once per batch
+    /// rather than once per message, and still calls it at least once per [`Dispatch::IDLE_CYCLE`]
+    /// while the connection is idle.
+    Batched(usize),
+}
+
+impl Dispatch {
+    /// The longest time that the client loop sleeps without a message under
+    /// [`Dispatch::Batched`] before calling [`crate::wrapper::Recurring::cycle`].
+    pub const IDLE_CYCLE: std::time::Duration = std::time::Duration::from_millis(1);
+
+    #[inline]
+    fn batch_size(self) -> usize {
+        match self {
+            Self::Poll => 1,
+            Self::Batched(n) => n.max(1),
+        }
+    }
+
+    #[inline]
+    async fn idle(self) {
+        match self {
+            Self::Poll => tokio::task::yield_now().await,
+            Self::Batched(_) => tokio::time::sleep(Self::IDLE_CYCLE).await,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Periodically checks that the connection to IBKR's trading systems is alive by requesting the
+/// current time and waiting for the reply.
+///
+/// The round trip of each reply is passed to [`crate::wrapper::LocalWrapper::heartbeat`]. These
+/// replies are not passed to [`crate::wrapper::LocalWrapper::current_time`]. If no message of any
+/// kind arrives within the timeout of a request, the connection is considered stalled:
+/// [`crate::wrapper::LocalWrapper::connection_stalled`] is called and the cancel token installed
+/// with [`Watchdog::with_cancel`], if any, is cancelled.
+///
+/// Unless the client is disconnected by the cancel token, monitoring continues after a stall: the
+/// next request is sent one interval later, and every request that goes unanswered is reported
+/// as another stall. The requests are sent like any other, so they are passed to the
+/// [`MessageTap`] and [`OutgoingLog`] of the client, if any.
+pub struct Watchdog {
+    interval: std::time::Duration,
+    timeout: std::time::Duration,
+    cancel: Option<CancelToken>,
+}
+
+impl Watchdog {
+    #[must_use]
+    #[inline]
+    /// Create a new [`Watchdog`].
+    ///
+    /// # Arguments
+    /// * `interval` - The time between consecutive requests. A request is only sent once the
+    ///   previous one has been answered.
+    /// * `timeout` - The time without any incoming message after a request after which the
+    ///   connection is considered stalled.
+    pub const fn new(interval: std::time::Duration, timeout: std::time::Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            cancel: None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Cancel a token when the connection is considered stalled, ex. the token passed to
+    /// [`Client::local`] to disconnect the client.
+    ///
+    /// # Arguments
+    /// * `token` - The token to cancel.
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+}
+
+// The state of a `Watchdog` in the client loop. The pings are sent with a handle to the client's
+// `Writer`, because the `Client` itself is not available to the loop of `Client::disaggregated`.
+#[derive(Debug)]
+struct Heartbeat {
+    watchdog: Watchdog,
+    restart: Option<crate::restart::Window>,
+    writer: Writer,
+    next_ping: tokio::time::Instant,
+    sent: Option<tokio::time::Instant>,
+    last_message: tokio::time::Instant,
+}
+
+impl Heartbeat {
+    #[inline]
+    fn new(options: &Options, writer: &Writer) -> Option<Self> {
+        let now = tokio::time::Instant::now();
+        options.watchdog.as_ref().map(|watchdog| Self {
+            watchdog: watchdog.clone(),
+            restart: options.restart,
+            writer: writer.handle(),
+            next_ping: now + watchdog.interval,
+            sent: None,
+            last_message: now,
+        })
+    }
+
+    #[inline]
+    fn deadline(&self) -> tokio::time::Instant {
+        match self.sent {
+            None => self.next_ping,
+            Some(sent) => sent.max(self.last_message) + self.watchdog.timeout,
+        }
+    }
+
+    // Completes when `Heartbeat::beat` should be called. This only sleeps, so that it can be
+    // cancelled by the other branches of the client loop without interrupting a write.
+    #[inline]
+    async fn due(heartbeat: Option<&Self>) {
+        match heartbeat.map(Self::deadline) {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    // Send a ping, or return the silence since the last ping or message if its timeout expired,
+    // in which case the next ping is scheduled one interval later. While requests are paused for a
+    // restart, the connection is expected to drop, so no ping is sent and no stall is reported.
+    async fn beat(heartbeat: Option<&mut Self>) -> Option<std::time::Duration> {
+        let this = heartbeat?;
+        let now = tokio::time::Instant::now();
+        if this
+            .restart
+            .is_some_and(|restart| restart.is_paused(chrono::Utc::now()))
+        {
+            this.sent = None;
+            this.next_ping = now + this.watchdog.interval;
+            return None;
+        }
+        this.next_ping = now + this.watchdog.interval;
+        if let Some(sent) = this.sent.take() {
+            if let Some(cancel) = &this.watchdog.cancel {
+                cancel.cancel();
+            }
+            return Some(now - sent.max(this.last_message));
+        }
+        let sent = async {
+            this.writer.add_body((Out::ReqCurrentTime, 1))?;
+            this.writer.send().await
+        };
+        if let Err(e) = sent.await {
+            error!(error = %e, "Failed to send watchdog request");
+        }
+        this.sent = Some(now);
+        None
+    }
+
+    // Record an incoming message and return the round trip if it is the reply to a ping.
+    #[inline]
+    fn observe(&mut self, frame: &Frame) -> Option<std::time::Duration> {
+        let now = tokio::time::Instant::now();
+        self.last_message = now;
+        let sent = self.sent?;
+        if !matches!(frame.first().and_then(|t| t.parse().ok()), Some(In::CurrentTime)) {
+            return None;
+        }
+        self.sent = None;
+        Some(now - sent)
+    }
+}
+
+// The client loop's side of `Builder::with_delayed_data_retry`, which sends a market data request
+// again with delayed data after it fails for lack of a subscription.
+#[derive(Debug)]
+struct Retry {
+    writer: Writer,
+}
+
+impl Retry {
+    // The error code for a market data request without a subscription.
+    const NO_SUBSCRIPTION: &'static str = "354";
+
+    #[inline]
+    fn new(options: &Options, writer: &Writer) -> Option<Self> {
+        options.delayed_data_retry.then(|| Self {
+            writer: writer.handle(),
+        })
+    }
+
+    // Send a request again with delayed data if the frame reports that it has no subscription,
+    // returning whether the frame was handled.
+    async fn observe(retry: Option<&mut Self>, frame: &Frame, shared: &Shared) -> bool {
+        let Some(this) = retry else { return false };
+        let mut fields = frame.fields();
+        if !matches!(fields.next().and_then(|t| t.parse().ok()), Some(In::ErrMsg)) {
+            return false;
+        }
+        let (Some(req_id), Some(Self::NO_SUBSCRIPTION)) = (fields.nth(1), fields.next()) else {
+            return false;
+        };
+        let Some(request) = req_id.parse().ok().and_then(|id| shared.take_retry(id)) else {
+            return false;
+        };
+        let sent = async {
+            this.writer
+                .add_body((Out::ReqMarketDataType, 1, live_data::Class::Delayed))?;
+            this.writer.send().await?;
+            this.writer.add_body(crate::comm::Serialized(&request))?;
+            this.writer.send().await
+        };
+        if let Err(e) = sent.await {
+            error!(error = %e, req_id, "Failed to retry market data request with delayed data");
+            return false;
+        }
+        true
+    }
+}
+
+// The state of a restart `Window` in the client loop, which tells the wrapper when requests are
+// paused and when the restart begins.
+#[derive(Debug)]
+struct Restart {
+    window: crate::restart::Window,
+    next: Option<crate::restart::Period>,
+    pending: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RestartEvent {
+    Pending(crate::restart::Period),
+    Started(crate::restart::Period),
+}
+
+impl Restart {
+    #[inline]
+    fn new(options: &Options) -> Option<Self> {
+        options.restart.map(|window| Self {
+            window,
+            next: window.next_after(chrono::Utc::now()),
+            pending: false,
+        })
+    }
+
+    // Completes when `Restart::advance` should be called.
+    #[inline]
+    async fn due(restart: Option<&Self>) {
+        let Some(at) = restart.and_then(|this| {
+            this.next.map(|period| {
+                if this.pending {
+                    period.start
+                } else {
+                    period.pause
+                }
+            })
+        }) else {
+            return std::future::pending().await;
+        };
+        let wait = (at - chrono::Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+    }
+
+    // Move to the next step of the current restart, which is followed by the next restart.
+    #[inline]
+    fn advance(restart: Option<&mut Self>) -> Option<RestartEvent> {
+        let this = restart?;
+        let period = this.next?;
+        if this.pending {
+            this.next = this.window.next_after(period.end);
+            this.pending = false;
+            Some(RestartEvent::Started(period))
+        } else {
+            this.pending = true;
+            Some(RestartEvent::Pending(period))
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The number of messages waiting in one of the channels between a [`Client`] and the loop that
+/// decodes incoming messages, as reported by [`Client::get_wrapper_channel_usage`] and
+/// [`Client::get_client_channel_usage`].
+pub struct ChannelUsage {
+    /// The number of messages waiting in the channel.
+    pub len: usize,
+    /// The maximum number of messages that can wait in the channel.
+    pub capacity: usize,
+}
+
+impl ChannelUsage {
+    #[must_use]
+    #[inline]
+    /// Return the fraction of the channel's capacity that is in use, between 0 and 1. A channel
+    /// whose usage stays near 1 is causing its senders to wait.
+    pub fn utilization(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        {
+            self.len as f64 / self.capacity as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    tap: Option<MessageTap>,
+    outgoing_log: Option<OutgoingLog>,
+    contract_cache: Option<crate::contract::Cache>,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
+    to_wrapper_capacity: usize,
+    to_client_capacity: usize,
+    dispatch: Dispatch,
+    validate_orders: bool,
+    market_data_class: Option<live_data::Class>,
+    delayed_data_retry: bool,
+    combined_quotes: bool,
+    proxy: Option<transport::Proxy>,
+    #[cfg(feature = "rustls")]
+    tls: Option<transport::Tls>,
+    watchdog: Option<Watchdog>,
+    limits: crate::limits::Limits,
+    unknown_requests: crate::requests::UnknownPolicy,
+    restart: Option<crate::restart::Window>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            tap: None,
+            outgoing_log: None,
+            contract_cache: None,
+            queue_capacity: constants::FROM_READER_CHANNEL_SIZE,
+            overflow_policy: OverflowPolicy::default(),
+            to_wrapper_capacity: constants::TO_WRAPPER_CHANNEL_SIZE,
+            to_client_capacity: constants::TO_CLIENT_CHANNEL_SIZE,
+            dispatch: Dispatch::default(),
+            validate_orders: false,
+            market_data_class: None,
+            delayed_data_retry: false,
+            combined_quotes: false,
+            proxy: None,
+            #[cfg(feature = "rustls")]
+            tls: None,
+            watchdog: None,
+            limits: crate::limits::Limits::new(),
+            unknown_requests: crate::requests::UnknownPolicy::default(),
+            restart: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Inner {
     ConfigFile {
@@ -157,18 +667,56 @@ pub enum ConnectionError {
     )]
     /// Occurs if required buffer size exceeds `usize::MAX`
     InvalidBufferSize,
+    #[error(
+        "Failed to initiate connection to IBKR API: Client ID {0} is already connected in this session."
+    )]
+    /// Occurs if a [`Session`] already has a client with the requested client ID
+    DuplicateClientId(i64),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Error)]
+/// An error type for potential failure of [`Client::req_place_order`] and
+/// [`Client::req_modify_order`]
+pub enum PlaceOrderError {
+    #[error("Failed to place order: {0}")]
+    /// The order failed validation, so nothing was sent
+    Validation(#[from] crate::order::OrderValidationError),
+    #[error("Failed to place order: {0}")]
+    /// IO error when writing the outgoing message
+    Io(#[from] std::io::Error),
+}
+
+impl From<PlaceOrderError> for std::io::Error {
+    fn from(value: PlaceOrderError) -> Self {
+        match value {
+            PlaceOrderError::Validation(e) => Self::new(std::io::ErrorKind::InvalidInput, e),
+            PlaceOrderError::Io(e) => e,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 /// Facilitates the creation of a new connection to IBKR's trading systems.
 ///
 /// Each connection requires a TCP port and address with which to connect to the appropriate IBKR
 /// platform. This information is communicated by either: 1) Manually specifying the parameters in
 /// [`Builder::manual`] or 2) Automatically looking them up in the config.toml file by specifying a
 ///  [`Mode`] and [`Host`] in [`Builder::from_config_file`].
-pub struct Builder(Inner);
+pub struct Builder(Inner, Options);
 
 impl Builder {
+    /// The number of messages held by the queue of incoming messages unless it is configured with
+    /// [`Builder::with_reader_queue`].
+    pub const DEFAULT_READER_QUEUE_CAPACITY: usize = constants::FROM_READER_CHANNEL_SIZE;
+
+    /// The number of messages held by the wrapper channel unless it is configured with
+    /// [`Builder::with_channel_capacities`].
+    pub const DEFAULT_WRAPPER_CHANNEL_CAPACITY: usize = constants::TO_WRAPPER_CHANNEL_SIZE;
+
+    /// The number of messages held by the client channel unless it is configured with
+    /// [`Builder::with_channel_capacities`].
+    pub const DEFAULT_CLIENT_CHANNEL_CAPACITY: usize = constants::TO_CLIENT_CHANNEL_SIZE;
+
     #[inline]
     /// Creates a new [`Builder`] from a mode, host, and (optionally) a path to "config.toml"
     ///
@@ -190,8 +738,18 @@ impl Builder {
             AsRef::<std::path::Path>::as_ref,
         );
         let config = Config::new(path)?;
+        let restart = match host {
+            Host::Tws => config.restart.tws,
+            Host::Gateway => config.restart.gateway,
+        };
 
-        Ok(Self(Inner::ConfigFile { mode, host, config }))
+        Ok(Self(
+            Inner::ConfigFile { mode, host, config },
+            Options {
+                restart,
+                ..Options::default()
+            },
+        ))
     }
 
     #[must_use]
@@ -202,10 +760,282 @@ impl Builder {
     /// * `port` - The TCP port with which to connect to IBKR's trading systems.
     /// * `address` - The IP address with which to connect to IBKR's trading systems.
     pub fn manual(port: u16, address: Option<std::net::Ipv4Addr>) -> Self {
-        Self(Inner::Manual {
-            port,
-            address: address.unwrap_or(std::net::Ipv4Addr::LOCALHOST),
-        })
+        Self(
+            Inner::Manual {
+                port,
+                address: address.unwrap_or(std::net::Ipv4Addr::LOCALHOST),
+            },
+            Options::default(),
+        )
+    }
+
+    #[cfg(feature = "rustls")]
+    #[must_use]
+    #[inline]
+    /// Creates a new [`Builder`] that connects over TLS to a host name and port, for TWS or IB
+    /// Gateway running on another host behind a TLS-terminating tunnel (ex. stunnel).
+    ///
+    /// [`Builder::connect`] looks up the IPv4 address of `host`, which the [`Client`] then
+    /// reports, and verifies the tunnel's certificate against `host`. A proxy installed with
+    /// [`Builder::with_proxy`] is instead asked to connect to `host` by name, so that no lookup
+    /// is made locally (ex. for internal or `.onion` names), and the [`Client`] reports
+    /// [`std::net::Ipv4Addr::UNSPECIFIED`].
+    ///
+    /// # Arguments
+    /// * `port` - The TCP port of the tunnel.
+    /// * `host` - The host name of the tunnel, which must match its certificate.
+    /// * `tls_config` - The TLS configuration, including the trusted root certificates.
+    pub fn manual_tls(
+        port: u16,
+        host: impl Into<String>,
+        tls_config: std::sync::Arc<rustls::ClientConfig>,
+    ) -> Self {
+        Self(
+            Inner::Manual {
+                port,
+                address: std::net::Ipv4Addr::UNSPECIFIED,
+            },
+            Options {
+                tls: Some(transport::Tls::new(host.into(), tls_config)),
+                ..Options::default()
+            },
+        )
+    }
+
+    #[must_use]
+    #[inline]
+    /// Install a [`MessageTap`] that observes every raw message sent and received by the
+    /// [`Client`] created by this builder.
+    ///
+    /// # Arguments
+    /// * `tap` - The tap with which to observe messages.
+    pub fn with_message_tap(mut self, tap: MessageTap) -> Self {
+        self.1.tap = Some(tap);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Record every outgoing message sent by the [`Client`] created by this builder as an
+    /// [`OutgoingRecord`].
+    ///
+    /// # Arguments
+    /// * `log` - Where to deliver the records.
+    pub fn with_outgoing_log(mut self, log: OutgoingLog) -> Self {
+        self.1.outgoing_log = Some(log);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Install a [`crate::contract::Cache`] that is consulted by [`crate::contract::new`] before
+    /// a contract query is sent. The cache is kept when the client disconnects, so it is reused by
+    /// a client created from the returned [`Builder`].
+    ///
+    /// # Arguments
+    /// * `cache` - The cache, which may already contain contracts.
+    pub fn with_contract_cache(mut self, cache: crate::contract::Cache) -> Self {
+        self.1.contract_cache = Some(cache);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Configure the queue that holds incoming messages until they are handled by the wrapper.
+    ///
+    /// By default, the queue holds [`Builder::DEFAULT_READER_QUEUE_CAPACITY`] messages and uses
+    /// [`OverflowPolicy::Block`].
+    ///
+    /// # Arguments
+    /// * `capacity` - The maximum number of messages waiting in the queue (at least 1).
+    /// * `policy` - What to do when a message arrives while the queue is full.
+    pub fn with_reader_queue(mut self, capacity: usize, policy: OverflowPolicy) -> Self {
+        self.1.queue_capacity = capacity;
+        self.1.overflow_policy = policy;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Configure the channels between the [`Client`] and the loop that decodes incoming messages.
+    ///
+    /// The wrapper channel carries notices from the client to the loop (ex. that a tick-by-tick
+    /// request expects historical ticks), and the client channel carries responses that the client
+    /// awaits (ex. the contracts returned by [`crate::contract::new`]). When a channel is full,
+    /// its sender waits, so a small channel can stall a busy client or loop. The current usage of
+    /// each channel is reported by [`Client::get_wrapper_channel_usage`] and
+    /// [`Client::get_client_channel_usage`].
+    ///
+    /// By default, the wrapper channel holds [`Builder::DEFAULT_WRAPPER_CHANNEL_CAPACITY`] messages
+    /// and the client channel holds [`Builder::DEFAULT_CLIENT_CHANNEL_CAPACITY`] messages.
+    ///
+    /// # Arguments
+    /// * `to_wrapper` - The maximum number of messages waiting in the wrapper channel (at least 1).
+    /// * `to_client` - The maximum number of messages waiting in the client channel (at least 1).
+    pub fn with_channel_capacities(mut self, to_wrapper: usize, to_client: usize) -> Self {
+        self.1.to_wrapper_capacity = to_wrapper.max(1);
+        self.1.to_client_capacity = to_client.max(1);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Configure how the client loop passes incoming messages to the wrapper.
+    ///
+    /// By default, the client uses [`Dispatch::Poll`].
+    ///
+    /// # Arguments
+    /// * `dispatch` - The dispatch mode.
+    pub fn with_dispatch(mut self, dispatch: Dispatch) -> Self {
+        self.1.dispatch = dispatch;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Check every order with [`crate::order::Order::validate`] before it is sent by
+    /// [`Client::req_place_order`] or [`Client::req_modify_order`].
+    ///
+    /// By default, orders are sent without any client-side validation.
+    pub fn with_order_validation(mut self) -> Self {
+        self.1.validate_orders = true;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Set the market data class of the session, which is requested once, immediately before the
+    /// first [`Client::req_market_data`] request.
+    ///
+    /// This is a one-shot session default: it is sent a single time, and calling
+    /// [`Client::req_market_data_type`] at any point replaces it for the rest of the session. To
+    /// fall back to delayed data only for the requests that need it, use
+    /// [`Builder::with_delayed_data_retry`] instead.
+    ///
+    /// With [`live_data::Class::Delayed`], IBKR sends delayed data for securities without a live
+    /// subscription and still sends live data for those with one. The class that each request
+    /// actually receives is reported by [`crate::wrapper::LocalWrapper::market_data_class`].
+    ///
+    /// By default, no class is requested and IBKR sends [`live_data::Class::Live`] data.
+    pub fn with_market_data_class(mut self, class: live_data::Class) -> Self {
+        self.1.market_data_class = Some(class);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Retry each [`Client::req_market_data`] request that fails with error 354 (no market data
+    /// subscription) with delayed data.
+    ///
+    /// When the error arrives, the client loop requests [`live_data::Class::Delayed`] data and
+    /// sends the failed request again with the same request ID. Each request is retried at most
+    /// once, and the error is only passed to [`crate::wrapper::LocalWrapper::error`] if the retry
+    /// cannot be sent. The class that the request finally receives is reported by
+    /// [`crate::wrapper::LocalWrapper::market_data_class`].
+    ///
+    /// Since delayed data is requested for the whole session, later requests also receive delayed
+    /// data for securities without a live subscription, and live data for those with one.
+    ///
+    /// By default, requests are not retried.
+    pub fn with_delayed_data_retry(mut self) -> Self {
+        self.1.delayed_data_retry = true;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Pass each bid and ask price from [`Client::req_market_data`] to
+    /// [`crate::wrapper::LocalWrapper::bid_ask_quote`] together with its size, rather than to
+    /// separate [`crate::wrapper::LocalWrapper::price_data`] and
+    /// [`crate::wrapper::LocalWrapper::size_data`] callbacks.
+    ///
+    /// Sizes that IBKR sends without a price are still passed to
+    /// [`crate::wrapper::LocalWrapper::size_data`].
+    ///
+    /// By default, prices and sizes are passed separately.
+    pub fn with_combined_quotes(mut self) -> Self {
+        self.1.combined_quotes = true;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Connect to TWS or IB Gateway through a SOCKS5 or HTTP `CONNECT` proxy.
+    ///
+    /// The proxy is asked to connect to the port and address of the [`Builder`] (or to the host
+    /// name of a builder created with `Builder::manual_tls`, which the proxy looks up), after which
+    /// [`Builder::connect`] performs the usual handshake through it. The proxy is not used by
+    /// [`Builder::connect_with`].
+    ///
+    /// # Arguments
+    /// * `proxy` - The proxy through which to connect.
+    pub fn with_proxy(mut self, proxy: transport::Proxy) -> Self {
+        self.1.proxy = Some(proxy);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Monitor the connection with a [`Watchdog`] once the client loop is started.
+    ///
+    /// By default, the connection is not monitored.
+    ///
+    /// # Arguments
+    /// * `watchdog` - The watchdog that determines how often the connection is checked.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.1.watchdog = Some(watchdog);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Enforce the market data entitlements of the account on the client side.
+    ///
+    /// Requests that would exceed any of the `limits` fail with a
+    /// [`crate::limits::LimitExceeded`] error before they are sent, instead of being rejected by
+    /// TWS. By default, no limits are enforced.
+    ///
+    /// # Arguments
+    /// * `limits` - The maximum number of concurrent subscriptions of each kind.
+    pub fn with_limits(mut self, limits: crate::limits::Limits) -> Self {
+        self.1.limits = limits;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Set what happens to callbacks whose request ID matches no outstanding request, such as
+    /// data that arrives after its subscription was cancelled. By default, a warning is logged
+    /// and the callback is still passed to the wrapper.
+    ///
+    /// The outstanding requests can be inspected with [`Client::get_outstanding_requests`].
+    ///
+    /// # Arguments
+    /// * `policy` - The handling of callbacks for unknown or finished requests.
+    pub fn with_unknown_requests(mut self, policy: crate::requests::UnknownPolicy) -> Self {
+        self.1.unknown_requests = policy;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Expect TWS or IB Gateway to restart during a recurring [`crate::restart::Window`].
+    ///
+    /// From shortly before each restart until the end of the window, requests fail with a
+    /// [`crate::restart::Paused`] error instead of being lost with the connection, the
+    /// [`Watchdog`] does not consider the connection stalled, and the wrapper is told about the
+    /// restart by [`crate::wrapper::LocalWrapper::restart_pending`] and
+    /// [`crate::wrapper::LocalWrapper::restart_started`]. A client created with
+    /// [`Builder::local_with_restarts`] also reconnects after each restart.
+    ///
+    /// By default, the window is read from the `[Restart]` table of the config file of
+    /// [`Builder::from_config_file`], if any, and otherwise no restarts are expected.
+    ///
+    /// # Arguments
+    /// * `window` - The restart window configured in TWS or IB Gateway.
+    pub fn with_restart_window(mut self, window: crate::restart::Window) -> Self {
+        self.1.restart = Some(window);
+        self
     }
 
     /// Initiates a connection to IBKR's trading systems and returns a [`Client`].
@@ -217,8 +1047,11 @@ impl Builder {
     /// This function will error if any of the following occurs:
     /// 1) An error occurs while initiating a TCP connection on the port and address specified in
     ///    either [`Builder::manual`] or in the "config.toml" file specified in
-    ///    [`Builder::from_config_file`].
-    /// 2) An error occurs while reading or writing the handshake message that initiates a
+    ///    [`Builder::from_config_file`], or while negotiating that connection with the proxy
+    ///    installed with [`Builder::with_proxy`].
+    /// 2) An error occurs while looking up the host or during the TLS handshake of a builder
+    ///    created with `Builder::manual_tls`.
+    /// 3) An error occurs while reading or writing the handshake message that initiates a
     ///    connection with IBKR's trading systems.
     ///
     /// # Returns
@@ -228,7 +1061,114 @@ impl Builder {
         &self,
         client_id: i64,
     ) -> Result<Client<indicators::Inactive>, ConnectionError> {
-        let (mode, host, port, address) = match self.0 {
+        let (_, _, port, address) = self.endpoint();
+        #[cfg(feature = "rustls")]
+        if let Some(tls) = &self.1.tls {
+            // A proxy is given the host name, so that it is not looked up locally.
+            let (address, stream) = match &self.1.proxy {
+                Some(proxy) => (address, proxy.connect(tls.host(), port).await?),
+                None => {
+                    let address = tls.resolve(port).await?;
+                    (address, TcpStream::connect((address, port)).await?)
+                }
+            };
+            let stream = tls.connect(stream).await?;
+            let mut client = self.connect_with(stream, client_id).await?;
+            client.address = address;
+            return Ok(client);
+        }
+        let stream = match &self.1.proxy {
+            Some(proxy) => proxy.connect(&address.to_string(), port).await?,
+            None => TcpStream::connect((address, port)).await?,
+        };
+        let (reader, writer) = transport::split_tcp(stream);
+        self.handshake(reader, writer, client_id).await
+    }
+
+    /// Initiates a connection to IBKR's trading systems over a given [`Transport`] and returns
+    /// a [`Client`].
+    ///
+    /// The port and address reported by the [`Client`] are those of the [`Builder`], even though
+    /// no TCP connection is made to them.
+    ///
+    /// # Arguments
+    /// * `transport` - A connected stream to TWS, IB Gateway, or a proxy of either (ex. one end of
+    ///   [`transport::in_memory`]).
+    /// * `client_id` - A unique ID for IBKR's systems to distinguish between clients
+    ///
+    /// # Errors
+    /// This function will error if an error occurs while reading or writing the handshake message
+    /// that initiates a connection with IBKR's trading systems.
+    ///
+    /// # Returns
+    /// An inactive [`Client`] that will become active upon calling [`Client::local`] or
+    /// [`Client::remote`].
+    pub async fn connect_with<T: Transport>(
+        &self,
+        transport: T,
+        client_id: i64,
+    ) -> Result<Client<indicators::Inactive>, ConnectionError> {
+        let (reader, writer) = transport::split(transport);
+        self.handshake(reader, writer, client_id).await
+    }
+
+    /// Connects a client and runs its loop with [`Client::local`] until `disconnect_token` is
+    /// cancelled, reconnecting the client after each restart of the [`crate::restart::Window`]
+    /// installed with [`Builder::with_restart_window`].
+    ///
+    /// When a restart begins, the client disconnects and its wrapper is dropped. Once the window
+    /// is over, a new client is connected with the same `client_id`, its wrapper is built from a
+    /// clone of `init`, and [`crate::wrapper::LocalWrapper::restart_complete`] is called. While
+    /// the platform does not yet accept connections, a new connection is attempted every 10
+    /// seconds.
+    ///
+    /// # Arguments
+    /// * `client_id` - A unique ID for IBKR's systems to distinguish between clients
+    /// * `init` - A [`LocalInitializer`], which builds the wrapper of every connection.
+    /// * `disconnect_token` - The token that disconnects the client and ends the loop.
+    ///
+    /// # Errors
+    /// Returns any error returned by the first [`Builder::connect`], or by a client loop or its
+    /// disconnection.
+    ///
+    /// # Returns
+    /// A [`Builder`] with which to reconnect.
+    pub async fn local_with_restarts<I: LocalInitializer + Clone>(
+        &self,
+        client_id: i64,
+        init: I,
+        disconnect_token: CancelToken,
+    ) -> Result<Self, ConnectionError> {
+        let mut client = self.connect(client_id).await?;
+        let mut resumed = None;
+        loop {
+            let (builder, restarted) = client
+                .run_local(init.clone(), Some(disconnect_token.clone()), true, resumed)
+                .await?;
+            let Some(period) = restarted else {
+                return Ok(builder);
+            };
+            resumed = restarted;
+            let mut wait = (period.end - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            client = loop {
+                tokio::select! {
+                    () = tokio::time::sleep(wait) => (),
+                    () = disconnect_token.cancelled() => return Ok(builder),
+                }
+                match builder.connect(client_id).await {
+                    Ok(client) => break client,
+                    Err(e) => warn!(error = %e, "Failed to reconnect after a restart"),
+                }
+                wait = constants::RECONNECT_INTERVAL;
+            };
+        }
+    }
+
+    #[inline]
+    fn endpoint(&self) -> (Option<Mode>, Option<Host>, u16, std::net::Ipv4Addr) {
+        match self.0 {
             Inner::ConfigFile { mode, host, config } => (
                 Some(mode),
                 Some(host),
@@ -241,11 +1181,17 @@ impl Builder {
                 config.address,
             ),
             Inner::Manual { port, address } => (None, None, port, address),
-        };
-
-        let (mut reader, writer) = TcpStream::connect((address, port)).await?.into_split();
+        }
+    }
 
-        let mut writer = Writer::new(writer);
+    async fn handshake(
+        &self,
+        mut reader: ReadHalf,
+        writer: WriteHalf,
+        client_id: i64,
+    ) -> Result<Client<indicators::Inactive>, ConnectionError> {
+        let (mode, host, port, address) = self.endpoint();
+        let mut writer = Writer::new(writer, self.1.tap.clone(), self.1.outgoing_log.clone());
         writer.add_prefix("API\0")?;
         writer.add_body(format!(
             "v{}..{}",
@@ -254,11 +1200,12 @@ impl Builder {
         ))?;
         writer.send().await?;
 
-        let mut buf = bytes::BytesMut::with_capacity(
+        let mut buf = vec![
+            0;
             usize::try_from(reader.read_u32().await?)
-                .map_err(|_| ConnectionError::InvalidBufferSize)?,
-        );
-        reader.read_buf(&mut buf).await?;
+                .map_err(|_| ConnectionError::InvalidBufferSize)?
+        ];
+        reader.read_exact(&mut buf).await?;
         let resp = buf.into_iter().map(char::from).collect::<String>();
         let mut params = resp.split('\0');
 
@@ -290,9 +1237,11 @@ impl Builder {
             server_version,
             conn_time,
             writer,
+            options: self.1.clone(),
             status: indicators::Inactive { reader },
         };
         client.start_api().await?;
+        client.writer.pause_during(self.1.restart);
 
         Ok(client)
     }
@@ -309,421 +1258,453 @@ pub type ActiveClient = Client<indicators::Active>;
 type IntoActive = (
     Client<indicators::Active>,
     mpsc::Sender<ToClient>,
-    mpsc::Receiver<ToWrapper>,
-    mpsc::Receiver<Vec<String>>,
-    std::collections::VecDeque<Vec<String>>,
+    Inbox,
+    queue::Receiver,
+    std::collections::VecDeque<Frame>,
 );
 
 type LoopParams = (
-    mpsc::Receiver<Vec<String>>,
+    queue::Receiver,
     mpsc::Sender<ToClient>,
-    mpsc::Receiver<ToWrapper>,
-    std::collections::VecDeque<Vec<String>>,
+    Inbox,
+    std::collections::VecDeque<Frame>,
 );
 
 #[inline]
 #[allow(clippy::too_many_lines)]
 #[tracing::instrument(skip(remote), level = tracing::Level::DEBUG)]
-async fn decode_msg_remote<W>(
-    fields: Vec<String>,
+pub(crate) async fn decode_msg_remote<W>(
+    frame: Frame,
     remote: &mut W,
     tx: &mut mpsc::Sender<ToClient>,
-    rx: &mut mpsc::Receiver<ToWrapper>,
+    rx: &mut Inbox,
 ) where
     W: Wrapper,
 {
-    let status = match fields.first() {
+    #[cfg(feature = "metrics")]
+    {
+        let metrics = rx.shared().metrics();
+        metrics.record_message(frame.first().unwrap_or_default());
+        metrics.record_response(&frame);
+    }
+    if !rx.shared().observe_request(&frame) {
+        return;
+    }
+    let status = match frame.first() {
         None => Err(DecodeError::MissingData {
             field_name: "In-message identifier",
         }
         .with_context("None")),
         Some(s) => match s.parse() {
-            Ok(In::TickPrice) => decode::Remote::tick_price_msg(&mut fields.into_iter(), remote)
+            Ok(In::TickPrice) => decode::Remote::tick_price_msg(&mut frame.fields(), remote, rx)
                 .await
                 .map_err(|e| e.with_context("tick price msg")),
-            Ok(In::TickSize) => decode::Remote::tick_size_msg(&mut fields.into_iter(), remote)
+            Ok(In::TickSize) => decode::Remote::tick_size_msg(&mut frame.fields(), remote, rx)
                 .await
                 .map_err(|e| e.with_context("tick size msg")),
             Ok(In::OrderStatus) => {
-                decode::Remote::order_status_msg(&mut fields.into_iter(), remote)
+                decode::Remote::order_status_msg(&mut frame.fields(), remote, rx)
                     .await
                     .map_err(|e| e.with_context("order status msg"))
             }
-            Ok(In::ErrMsg) => decode::Remote::err_msg_msg(&mut fields.into_iter(), remote)
+            Ok(In::ErrMsg) => decode::Remote::err_msg_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("err msg msg")),
-            Ok(In::OpenOrder) => decode::Remote::open_order_msg(&mut fields.into_iter(), remote)
+            Ok(In::OpenOrder) => decode::Remote::open_order_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("open order msg")),
-            Ok(In::AcctValue) => decode::Remote::acct_value_msg(&mut fields.into_iter(), remote)
+            Ok(In::AcctValue) => decode::Remote::acct_value_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("acct value msg")),
             Ok(In::PortfolioValue) => {
-                decode::Remote::portfolio_value_msg(&mut fields.into_iter(), remote)
+                decode::Remote::portfolio_value_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("portfolio value msg"))
             }
             Ok(In::AcctUpdateTime) => {
-                decode::Remote::acct_update_time_msg(&mut fields.into_iter(), remote)
+                decode::Remote::acct_update_time_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("acct update time msg"))
             }
             Ok(In::NextValidId) => {
-                decode::Remote::next_valid_id_msg(&mut fields.into_iter(), remote, tx, rx)
+                decode::Remote::next_valid_id_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("next valid id msg"))
             }
             Ok(In::ContractData) => {
-                decode::Remote::contract_data_msg(&mut fields.into_iter(), remote, tx, rx)
+                decode::Remote::contract_data_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("contract data msg"))
             }
             Ok(In::ExecutionData) => {
-                decode::Remote::execution_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::execution_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("execution data msg"))
             }
+            #[cfg(feature = "depth")]
             Ok(In::MarketDepth) => {
-                decode::Remote::market_depth_msg(&mut fields.into_iter(), remote)
+                decode::Remote::market_depth_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("market depth msg"))
             }
+            #[cfg(feature = "depth")]
             Ok(In::MarketDepthL2) => {
-                decode::Remote::market_depth_l2_msg(&mut fields.into_iter(), remote)
+                decode::Remote::market_depth_l2_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("market depth l2 msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::NewsBulletins) => {
-                decode::Remote::news_bulletins_msg(&mut fields.into_iter(), remote)
+                decode::Remote::news_bulletins_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("news bulletins msg"))
             }
             Ok(In::ManagedAccts) => {
-                decode::Remote::managed_accts_msg(&mut fields.into_iter(), remote, tx, rx)
+                decode::Remote::managed_accts_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("managed accoSts msg"))
             }
-            Ok(In::ReceiveFa) => decode::Remote::receive_fa_msg(&mut fields.into_iter(), remote)
+            #[cfg(feature = "fa")]
+            Ok(In::ReceiveFa) => decode::Remote::receive_fa_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("receive fa msg")),
             Ok(In::HistoricalData) => {
-                decode::Remote::historical_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("historical data msg"))
             }
             Ok(In::BondContractData) => {
-                decode::Remote::bond_contract_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::bond_contract_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("bond contract data msg"))
             }
+            #[cfg(feature = "scanner")]
             Ok(In::ScannerParameters) => {
-                decode::Remote::scanner_parameters_msg(&mut fields.into_iter(), remote)
+                decode::Remote::scanner_parameters_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("scanner parameters msg"))
             }
+            #[cfg(feature = "scanner")]
             Ok(In::ScannerData) => {
-                decode::Remote::scanner_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::scanner_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("scanner data msg"))
             }
             Ok(In::TickOptionComputation) => {
-                decode::Remote::tick_option_computation_msg(&mut fields.into_iter(), remote)
+                decode::Remote::tick_option_computation_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("tick option computation msg"))
             }
             Ok(In::TickGeneric) => {
-                decode::Remote::tick_generic_msg(&mut fields.into_iter(), remote)
+                decode::Remote::tick_generic_msg(&mut frame.fields(), remote, rx)
                     .await
                     .map_err(|e| e.with_context("tick generic msg"))
             }
-            Ok(In::TickString) => decode::Remote::tick_string_msg(&mut fields.into_iter(), remote)
+            Ok(In::TickString) => decode::Remote::tick_string_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("tick string msg")),
-            Ok(In::TickEfp) => decode::Remote::tick_efp_msg(&mut fields.into_iter(), remote)
+            Ok(In::TickEfp) => decode::Remote::tick_efp_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("tick efp msg")),
             Ok(In::CurrentTime) => {
-                decode::Remote::current_time_msg(&mut fields.into_iter(), remote)
+                decode::Remote::current_time_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("current time msg"))
             }
             Ok(In::RealTimeBars) => {
-                decode::Remote::real_time_bars_msg(&mut fields.into_iter(), remote)
+                decode::Remote::real_time_bars_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("real time bars msg"))
             }
             Ok(In::FundamentalData) => {
-                decode::Remote::fundamental_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::fundamental_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("fundamental data msg"))
             }
             Ok(In::ContractDataEnd) => {
-                decode::Remote::contract_data_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::contract_data_end_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("contract data end msg"))
             }
             Ok(In::OpenOrderEnd) => {
-                decode::Remote::open_order_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::open_order_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("open order end msg"))
             }
             Ok(In::AcctDownloadEnd) => {
-                decode::Remote::acct_download_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::acct_download_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("acct download end msg"))
             }
             Ok(In::ExecutionDataEnd) => {
-                decode::Remote::execution_data_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::execution_data_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("execution data end msg"))
             }
             Ok(In::DeltaNeutralValidation) => {
-                decode::Remote::delta_neutral_validation_msg(&mut fields.into_iter(), remote)
+                decode::Remote::delta_neutral_validation_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("delta neutral validation msg"))
             }
             Ok(In::TickSnapshotEnd) => {
-                decode::Remote::tick_snapshot_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::tick_snapshot_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("tick snapshot end msg"))
             }
             Ok(In::MarketDataType) => {
-                decode::Remote::market_data_type_msg(&mut fields.into_iter(), remote)
+                decode::Remote::market_data_type_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("market data type msg"))
             }
             Ok(In::CommissionReport) => {
-                decode::Remote::commission_report_msg(&mut fields.into_iter(), remote)
+                decode::Remote::commission_report_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("commission report msg"))
             }
             Ok(In::PositionData) => {
-                decode::Remote::position_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::position_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("position data msg"))
             }
             Ok(In::PositionEnd) => {
-                decode::Remote::position_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::position_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("position end msg"))
             }
             Ok(In::AccountSummary) => {
-                decode::Remote::account_summary_msg(&mut fields.into_iter(), remote)
+                decode::Remote::account_summary_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("account summary msg"))
             }
             Ok(In::AccountSummaryEnd) => {
-                decode::Remote::account_summary_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::account_summary_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("account summary end msg"))
             }
             Ok(In::VerifyMessageApi) => {
-                decode::Remote::verify_message_api_msg(&mut fields.into_iter(), remote)
+                decode::Remote::verify_message_api_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("verify message api msg"))
             }
             Ok(In::VerifyCompleted) => {
-                decode::Remote::verify_completed_msg(&mut fields.into_iter(), remote)
+                decode::Remote::verify_completed_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("verify completed msg"))
             }
             Ok(In::DisplayGroupList) => {
-                decode::Remote::display_group_list_msg(&mut fields.into_iter(), remote)
+                decode::Remote::display_group_list_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("display group list msg"))
             }
             Ok(In::DisplayGroupUpdated) => {
-                decode::Remote::display_group_updated_msg(&mut fields.into_iter(), remote)
+                decode::Remote::display_group_updated_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("display group updated msg"))
             }
             Ok(In::VerifyAndAuthMessageApi) => {
-                decode::Remote::verify_and_auth_message_api_msg(&mut fields.into_iter(), remote)
+                decode::Remote::verify_and_auth_message_api_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("verify and auth message api msg"))
             }
             Ok(In::VerifyAndAuthCompleted) => {
-                decode::Remote::verify_and_auth_completed_msg(&mut fields.into_iter(), remote)
+                decode::Remote::verify_and_auth_completed_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("verify and auth completed msg"))
             }
             Ok(In::PositionMulti) => {
-                decode::Remote::position_multi_msg(&mut fields.into_iter(), remote)
+                decode::Remote::position_multi_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("position multi msg"))
             }
             Ok(In::PositionMultiEnd) => {
-                decode::Remote::position_multi_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::position_multi_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("position multi end msg"))
             }
             Ok(In::AccountUpdateMulti) => {
-                decode::Remote::account_update_multi_msg(&mut fields.into_iter(), remote)
+                decode::Remote::account_update_multi_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("account update multi msg"))
             }
             Ok(In::AccountUpdateMultiEnd) => {
-                decode::Remote::account_update_multi_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::account_update_multi_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("account update multi end msg"))
             }
             Ok(In::SecurityDefinitionOptionParameter) => {
                 decode::Remote::security_definition_option_parameter_msg(
-                    &mut fields.into_iter(),
+                    &mut frame.fields(),
                     remote,
+                    tx,
+                    rx,
                 )
                 .await
                 .map_err(|e| e.with_context("security definition option parameter msg"))
             }
             Ok(In::SecurityDefinitionOptionParameterEnd) => {
                 decode::Remote::security_definition_option_parameter_end_msg(
-                    &mut fields.into_iter(),
+                    &mut frame.fields(),
                     remote,
+                    tx,
+                    rx,
                 )
                 .await
                 .map_err(|e| e.with_context("security definition option parameter end msg"))
             }
             Ok(In::SoftDollarTiers) => {
-                decode::Remote::soft_dollar_tiers_msg(&mut fields.into_iter(), remote)
+                decode::Remote::soft_dollar_tiers_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("soft dollar tiers msg"))
             }
             Ok(In::FamilyCodes) => {
-                decode::Remote::family_codes_msg(&mut fields.into_iter(), remote)
+                decode::Remote::family_codes_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("family codes msg"))
             }
             Ok(In::SymbolSamples) => {
-                decode::Remote::symbol_samples_msg(&mut fields.into_iter(), remote)
+                decode::Remote::symbol_samples_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("symbol samples msg"))
             }
+            #[cfg(feature = "depth")]
             Ok(In::MktDepthExchanges) => {
-                decode::Remote::mkt_depth_exchanges_msg(&mut fields.into_iter(), remote)
+                decode::Remote::mkt_depth_exchanges_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("mkt depth exchanges msg"))
             }
             Ok(In::TickReqParams) => {
-                decode::Remote::tick_req_params_msg(&mut fields.into_iter(), remote)
+                decode::Remote::tick_req_params_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("tick req params msg"))
             }
             Ok(In::SmartComponents) => {
-                decode::Remote::smart_components_msg(&mut fields.into_iter(), remote)
+                decode::Remote::smart_components_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("smart components msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::NewsArticle) => {
-                decode::Remote::news_article_msg(&mut fields.into_iter(), remote)
+                decode::Remote::news_article_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("news article msg"))
             }
-            Ok(In::TickNews) => decode::Remote::tick_news_msg(&mut fields.into_iter(), remote)
+            #[cfg(feature = "news")]
+            Ok(In::TickNews) => decode::Remote::tick_news_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("tick news msg")),
+            #[cfg(feature = "news")]
             Ok(In::NewsProviders) => {
-                decode::Remote::news_providers_msg(&mut fields.into_iter(), remote)
+                decode::Remote::news_providers_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("news providers msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::HistoricalNews) => {
-                decode::Remote::historical_news_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_news_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical news msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::HistoricalNewsEnd) => {
-                decode::Remote::historical_news_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_news_end_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical news end msg"))
             }
             Ok(In::HeadTimestamp) => {
-                decode::Remote::head_timestamp_msg(&mut fields.into_iter(), remote)
+                decode::Remote::head_timestamp_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("head timestamp msg"))
             }
             Ok(In::HistogramData) => {
-                decode::Remote::histogram_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::histogram_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("histogram data msg"))
             }
             Ok(In::HistoricalDataUpdate) => {
-                decode::Remote::historical_data_update_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_data_update_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("historical data update msg"))
             }
             Ok(In::RerouteMktDataReq) => {
-                decode::Remote::reroute_mkt_data_req_msg(&mut fields.into_iter(), remote)
+                decode::Remote::reroute_mkt_data_req_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("reroute mkt data req msg"))
             }
+            #[cfg(feature = "depth")]
             Ok(In::RerouteMktDepthReq) => {
-                decode::Remote::reroute_mkt_depth_req_msg(&mut fields.into_iter(), remote)
+                decode::Remote::reroute_mkt_depth_req_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("reroute mkt depth req msg"))
             }
-            Ok(In::MarketRule) => decode::Remote::market_rule_msg(&mut fields.into_iter(), remote)
+            Ok(In::MarketRule) => decode::Remote::market_rule_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("market rule msg")),
-            Ok(In::Pnl) => decode::Remote::pnl_msg(&mut fields.into_iter(), remote)
+            Ok(In::Pnl) => decode::Remote::pnl_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("pnl msg")),
-            Ok(In::PnlSingle) => decode::Remote::pnl_single_msg(&mut fields.into_iter(), remote)
+            Ok(In::PnlSingle) => decode::Remote::pnl_single_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("pnl single msg")),
             Ok(In::HistoricalTicks) => {
-                decode::Remote::historical_ticks_midpoint_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_ticks_midpoint_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical ticks msg"))
             }
             Ok(In::HistoricalTicksBidAsk) => {
-                decode::Remote::historical_ticks_bid_ask_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_ticks_bid_ask_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical ticks bid ask msg"))
             }
             Ok(In::HistoricalTicksLast) => {
-                decode::Remote::historical_ticks_last_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_ticks_last_msg(&mut frame.fields(), remote, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical ticks last msg"))
             }
-            Ok(In::TickByTick) => decode::Remote::tick_by_tick_msg(&mut fields.into_iter(), remote)
+            Ok(In::TickByTick) => decode::Remote::tick_by_tick_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("tick by tick msg")),
-            Ok(In::OrderBound) => decode::Remote::order_bound_msg(&mut fields.into_iter(), remote)
+            Ok(In::OrderBound) => decode::Remote::order_bound_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("order bound msg")),
             Ok(In::CompletedOrder) => {
-                decode::Remote::completed_order_msg(&mut fields.into_iter(), remote)
+                decode::Remote::completed_order_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("completed order msg"))
             }
             Ok(In::CompletedOrdersEnd) => {
-                decode::Remote::completed_orders_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::completed_orders_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("completed orders end msg"))
             }
+            #[cfg(feature = "fa")]
             Ok(In::ReplaceFaEnd) => {
-                decode::Remote::replace_fa_end_msg(&mut fields.into_iter(), remote)
+                decode::Remote::replace_fa_end_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("replace fa end msg"))
             }
+            #[cfg(feature = "wsh")]
             Ok(In::WshMetaData) => {
-                decode::Remote::wsh_meta_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::wsh_meta_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("wsh meta data msg"))
             }
+            #[cfg(feature = "wsh")]
             Ok(In::WshEventData) => {
-                decode::Remote::wsh_event_data_msg(&mut fields.into_iter(), remote)
+                decode::Remote::wsh_event_data_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("wsh event data msg"))
             }
             Ok(In::HistoricalSchedule) => {
-                decode::Remote::historical_schedule_msg(&mut fields.into_iter(), remote)
+                decode::Remote::historical_schedule_msg(&mut frame.fields(), remote)
                     .await
                     .map_err(|e| e.with_context("historical schedule msg"))
             }
-            Ok(In::UserInfo) => decode::Remote::user_info_msg(&mut fields.into_iter(), remote)
+            Ok(In::UserInfo) => decode::Remote::user_info_msg(&mut frame.fields(), remote)
                 .await
                 .map_err(|e| e.with_context("user info msg")),
+            // Messages that belong to a subsystem whose feature is disabled are dropped.
+            #[allow(unreachable_patterns)]
+            Ok(_) => Ok(()),
             Err(e) => Err(DecodeError::Other(e.0).with_context("invalid in msg")),
         },
     };
@@ -731,7 +1712,16 @@ async fn decode_msg_remote<W>(
         Ok(()) => (),
         Err(e) => {
             tokio::task::yield_now().await;
-            error!("Error in decoding incoming message from API. Error message: {e}");
+            #[cfg(feature = "metrics")]
+            rx.shared().metrics().record_decode_failure();
+            let report = e.into_report(frame.to_vec());
+            error!(
+                kind = %report.kind,
+                message_id = report.fields.first().map(String::as_str),
+                error = %report.error,
+                "Error in decoding incoming message from API"
+            );
+            remote.decode_error(report).await;
         }
     }
 }
@@ -739,388 +1729,420 @@ async fn decode_msg_remote<W>(
 #[inline]
 #[allow(clippy::too_many_lines)]
 #[tracing::instrument(skip(local), level = tracing::Level::DEBUG)]
-async fn decode_msg_local<W>(
-    fields: Vec<String>,
+pub(crate) async fn decode_msg_local<W>(
+    frame: Frame,
     local: &mut W,
     tx: &mut mpsc::Sender<ToClient>,
-    rx: &mut mpsc::Receiver<ToWrapper>,
+    rx: &mut Inbox,
 ) where
     W: LocalWrapper,
 {
-    let status = match fields.first() {
+    #[cfg(feature = "metrics")]
+    {
+        let metrics = rx.shared().metrics();
+        metrics.record_message(frame.first().unwrap_or_default());
+        metrics.record_response(&frame);
+    }
+    if !rx.shared().observe_request(&frame) {
+        return;
+    }
+    let status = match frame.first() {
         None => Err(DecodeError::MissingData {
             field_name: "In-message identifier",
         }
         .with_context("None")),
         Some(s) => match s.parse() {
-            Ok(In::TickPrice) => decode::Local::tick_price_msg(&mut fields.into_iter(), local)
+            Ok(In::TickPrice) => decode::Local::tick_price_msg(&mut frame.fields(), local, rx)
                 .await
                 .map_err(|e| e.with_context("tick price msg")),
-            Ok(In::TickSize) => decode::Local::tick_size_msg(&mut fields.into_iter(), local)
+            Ok(In::TickSize) => decode::Local::tick_size_msg(&mut frame.fields(), local, rx)
                 .await
                 .map_err(|e| e.with_context("tick size msg")),
-            Ok(In::OrderStatus) => decode::Local::order_status_msg(&mut fields.into_iter(), local)
+            Ok(In::OrderStatus) => decode::Local::order_status_msg(&mut frame.fields(), local, rx)
                 .await
                 .map_err(|e| e.with_context("order status msg")),
-            Ok(In::ErrMsg) => decode::Local::err_msg_msg(&mut fields.into_iter(), local)
+            Ok(In::ErrMsg) => decode::Local::err_msg_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("err msg msg")),
-            Ok(In::OpenOrder) => decode::Local::open_order_msg(&mut fields.into_iter(), local)
+            Ok(In::OpenOrder) => decode::Local::open_order_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("open order msg")),
-            Ok(In::AcctValue) => decode::Local::acct_value_msg(&mut fields.into_iter(), local)
+            Ok(In::AcctValue) => decode::Local::acct_value_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("acct value msg")),
             Ok(In::PortfolioValue) => {
-                decode::Local::portfolio_value_msg(&mut fields.into_iter(), local)
+                decode::Local::portfolio_value_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("portfolio value msg"))
             }
             Ok(In::AcctUpdateTime) => {
-                decode::Local::acct_update_time_msg(&mut fields.into_iter(), local)
+                decode::Local::acct_update_time_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("acct update time msg"))
             }
             Ok(In::NextValidId) => {
-                decode::Local::next_valid_id_msg(&mut fields.into_iter(), local, tx, rx)
+                decode::Local::next_valid_id_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("next valid id msg"))
             }
             Ok(In::ContractData) => {
-                decode::Local::contract_data_msg(&mut fields.into_iter(), local, tx, rx)
+                decode::Local::contract_data_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("contract data msg"))
             }
             Ok(In::ExecutionData) => {
-                decode::Local::execution_data_msg(&mut fields.into_iter(), local)
+                decode::Local::execution_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("execution data msg"))
             }
-            Ok(In::MarketDepth) => decode::Local::market_depth_msg(&mut fields.into_iter(), local)
+            #[cfg(feature = "depth")]
+            Ok(In::MarketDepth) => decode::Local::market_depth_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("market depth msg")),
+            #[cfg(feature = "depth")]
             Ok(In::MarketDepthL2) => {
-                decode::Local::market_depth_l2_msg(&mut fields.into_iter(), local)
+                decode::Local::market_depth_l2_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("market depth l2 msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::NewsBulletins) => {
-                decode::Local::news_bulletins_msg(&mut fields.into_iter(), local)
+                decode::Local::news_bulletins_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("news bulletins msg"))
             }
             Ok(In::ManagedAccts) => {
-                decode::Local::managed_accts_msg(&mut fields.into_iter(), local, tx, rx)
+                decode::Local::managed_accts_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("managed accounts msg"))
             }
-            Ok(In::ReceiveFa) => decode::Local::receive_fa_msg(&mut fields.into_iter(), local)
+            #[cfg(feature = "fa")]
+            Ok(In::ReceiveFa) => decode::Local::receive_fa_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("receive fa msg")),
             Ok(In::HistoricalData) => {
-                decode::Local::historical_data_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("historical data msg"))
             }
             Ok(In::BondContractData) => {
-                decode::Local::bond_contract_data_msg(&mut fields.into_iter(), local)
+                decode::Local::bond_contract_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("bond contract data msg"))
             }
+            #[cfg(feature = "scanner")]
             Ok(In::ScannerParameters) => {
-                decode::Local::scanner_parameters_msg(&mut fields.into_iter(), local)
+                decode::Local::scanner_parameters_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("scanner parameters msg"))
             }
-            Ok(In::ScannerData) => decode::Local::scanner_data_msg(&mut fields.into_iter(), local)
+            #[cfg(feature = "scanner")]
+            Ok(In::ScannerData) => decode::Local::scanner_data_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("scanner data msg")),
             Ok(In::TickOptionComputation) => {
-                decode::Local::tick_option_computation_msg(&mut fields.into_iter(), local)
+                decode::Local::tick_option_computation_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("tick option computation msg"))
             }
-            Ok(In::TickGeneric) => decode::Local::tick_generic_msg(&mut fields.into_iter(), local)
+            Ok(In::TickGeneric) => decode::Local::tick_generic_msg(&mut frame.fields(), local, rx)
                 .await
                 .map_err(|e| e.with_context("tick generic msg")),
-            Ok(In::TickString) => decode::Local::tick_string_msg(&mut fields.into_iter(), local)
+            Ok(In::TickString) => decode::Local::tick_string_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("tick string msg")),
-            Ok(In::TickEfp) => decode::Local::tick_efp_msg(&mut fields.into_iter(), local)
+            Ok(In::TickEfp) => decode::Local::tick_efp_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("tick efp msg")),
-            Ok(In::CurrentTime) => decode::Local::current_time_msg(&mut fields.into_iter(), local)
+            Ok(In::CurrentTime) => decode::Local::current_time_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("current time msg")),
             Ok(In::RealTimeBars) => {
-                decode::Local::real_time_bars_msg(&mut fields.into_iter(), local)
+                decode::Local::real_time_bars_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("real time bars msg"))
             }
             Ok(In::FundamentalData) => {
-                decode::Local::fundamental_data_msg(&mut fields.into_iter(), local)
+                decode::Local::fundamental_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("fundamental data msg"))
             }
             Ok(In::ContractDataEnd) => {
-                decode::Local::contract_data_end_msg(&mut fields.into_iter(), local)
+                decode::Local::contract_data_end_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("contract data end msg"))
             }
             Ok(In::OpenOrderEnd) => {
-                decode::Local::open_order_end_msg(&mut fields.into_iter(), local)
+                decode::Local::open_order_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("open order end msg"))
             }
             Ok(In::AcctDownloadEnd) => {
-                decode::Local::acct_download_end_msg(&mut fields.into_iter(), local)
+                decode::Local::acct_download_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("acct download end msg"))
             }
             Ok(In::ExecutionDataEnd) => {
-                decode::Local::execution_data_end_msg(&mut fields.into_iter(), local)
+                decode::Local::execution_data_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("execution data end msg"))
             }
             Ok(In::DeltaNeutralValidation) => {
-                decode::Local::delta_neutral_validation_msg(&mut fields.into_iter(), local)
+                decode::Local::delta_neutral_validation_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("delta neutral validation msg"))
             }
             Ok(In::TickSnapshotEnd) => {
-                decode::Local::tick_snapshot_end_msg(&mut fields.into_iter(), local)
+                decode::Local::tick_snapshot_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("tick snapshot end msg"))
             }
             Ok(In::MarketDataType) => {
-                decode::Local::market_data_type_msg(&mut fields.into_iter(), local)
+                decode::Local::market_data_type_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("market data type msg"))
             }
             Ok(In::CommissionReport) => {
-                decode::Local::commission_report_msg(&mut fields.into_iter(), local)
+                decode::Local::commission_report_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("commission report msg"))
             }
             Ok(In::PositionData) => {
-                decode::Local::position_data_msg(&mut fields.into_iter(), local)
+                decode::Local::position_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("position data msg"))
             }
-            Ok(In::PositionEnd) => decode::Local::position_end_msg(&mut fields.into_iter(), local)
+            Ok(In::PositionEnd) => decode::Local::position_end_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("position end msg")),
             Ok(In::AccountSummary) => {
-                decode::Local::account_summary_msg(&mut fields.into_iter(), local)
+                decode::Local::account_summary_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("account summary msg"))
             }
             Ok(In::AccountSummaryEnd) => {
-                decode::Local::account_summary_end_msg(&mut fields.into_iter(), local)
+                decode::Local::account_summary_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("account summary end msg"))
             }
             Ok(In::VerifyMessageApi) => {
-                decode::Local::verify_message_api_msg(&mut fields.into_iter(), local)
+                decode::Local::verify_message_api_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("verify message api msg"))
             }
             Ok(In::VerifyCompleted) => {
-                decode::Local::verify_completed_msg(&mut fields.into_iter(), local)
+                decode::Local::verify_completed_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("verify completed msg"))
             }
             Ok(In::DisplayGroupList) => {
-                decode::Local::display_group_list_msg(&mut fields.into_iter(), local)
+                decode::Local::display_group_list_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("display group list msg"))
             }
             Ok(In::DisplayGroupUpdated) => {
-                decode::Local::display_group_updated_msg(&mut fields.into_iter(), local)
+                decode::Local::display_group_updated_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("display group updated msg"))
             }
             Ok(In::VerifyAndAuthMessageApi) => {
-                decode::Local::verify_and_auth_message_api_msg(&mut fields.into_iter(), local)
+                decode::Local::verify_and_auth_message_api_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("verify and auth message api msg"))
             }
             Ok(In::VerifyAndAuthCompleted) => {
-                decode::Local::verify_and_auth_completed_msg(&mut fields.into_iter(), local)
+                decode::Local::verify_and_auth_completed_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("verify and auth completed msg"))
             }
             Ok(In::PositionMulti) => {
-                decode::Local::position_multi_msg(&mut fields.into_iter(), local)
+                decode::Local::position_multi_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("position multi msg"))
             }
             Ok(In::PositionMultiEnd) => {
-                decode::Local::position_multi_end_msg(&mut fields.into_iter(), local)
+                decode::Local::position_multi_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("position multi end msg"))
             }
             Ok(In::AccountUpdateMulti) => {
-                decode::Local::account_update_multi_msg(&mut fields.into_iter(), local)
+                decode::Local::account_update_multi_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("account update multi msg"))
             }
             Ok(In::AccountUpdateMultiEnd) => {
-                decode::Local::account_update_multi_end_msg(&mut fields.into_iter(), local)
+                decode::Local::account_update_multi_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("account update multi end msg"))
             }
             Ok(In::SecurityDefinitionOptionParameter) => {
                 decode::Local::security_definition_option_parameter_msg(
-                    &mut fields.into_iter(),
+                    &mut frame.fields(),
                     local,
+                    tx,
+                    rx,
                 )
                 .await
                 .map_err(|e| e.with_context("security definition option parameter msg"))
             }
             Ok(In::SecurityDefinitionOptionParameterEnd) => {
                 decode::Local::security_definition_option_parameter_end_msg(
-                    &mut fields.into_iter(),
+                    &mut frame.fields(),
                     local,
+                    tx,
+                    rx,
                 )
                 .await
                 .map_err(|e| e.with_context("security definition option parameter end msg"))
             }
             Ok(In::SoftDollarTiers) => {
-                decode::Local::soft_dollar_tiers_msg(&mut fields.into_iter(), local)
+                decode::Local::soft_dollar_tiers_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("soft dollar tiers msg"))
             }
-            Ok(In::FamilyCodes) => decode::Local::family_codes_msg(&mut fields.into_iter(), local)
+            Ok(In::FamilyCodes) => decode::Local::family_codes_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("family codes msg")),
             Ok(In::SymbolSamples) => {
-                decode::Local::symbol_samples_msg(&mut fields.into_iter(), local)
+                decode::Local::symbol_samples_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("symbol samples msg"))
             }
+            #[cfg(feature = "depth")]
             Ok(In::MktDepthExchanges) => {
-                decode::Local::mkt_depth_exchanges_msg(&mut fields.into_iter(), local)
+                decode::Local::mkt_depth_exchanges_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("mkt depth exchanges msg"))
             }
             Ok(In::TickReqParams) => {
-                decode::Local::tick_req_params_msg(&mut fields.into_iter(), local)
+                decode::Local::tick_req_params_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("tick req params msg"))
             }
             Ok(In::SmartComponents) => {
-                decode::Local::smart_components_msg(&mut fields.into_iter(), local)
+                decode::Local::smart_components_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("smart components msg"))
             }
-            Ok(In::NewsArticle) => decode::Local::news_article_msg(&mut fields.into_iter(), local)
+            #[cfg(feature = "news")]
+            Ok(In::NewsArticle) => decode::Local::news_article_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("news article msg")),
-            Ok(In::TickNews) => decode::Local::tick_news_msg(&mut fields.into_iter(), local)
+            #[cfg(feature = "news")]
+            Ok(In::TickNews) => decode::Local::tick_news_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("tick news msg")),
+            #[cfg(feature = "news")]
             Ok(In::NewsProviders) => {
-                decode::Local::news_providers_msg(&mut fields.into_iter(), local)
+                decode::Local::news_providers_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("news providers msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::HistoricalNews) => {
-                decode::Local::historical_news_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_news_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical news msg"))
             }
+            #[cfg(feature = "news")]
             Ok(In::HistoricalNewsEnd) => {
-                decode::Local::historical_news_end_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_news_end_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical news end msg"))
             }
             Ok(In::HeadTimestamp) => {
-                decode::Local::head_timestamp_msg(&mut fields.into_iter(), local)
+                decode::Local::head_timestamp_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("head timestamp msg"))
             }
             Ok(In::HistogramData) => {
-                decode::Local::histogram_data_msg(&mut fields.into_iter(), local)
+                decode::Local::histogram_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("histogram data msg"))
             }
             Ok(In::HistoricalDataUpdate) => {
-                decode::Local::historical_data_update_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_data_update_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("historical data update msg"))
             }
             Ok(In::RerouteMktDataReq) => {
-                decode::Local::reroute_mkt_data_req_msg(&mut fields.into_iter(), local)
+                decode::Local::reroute_mkt_data_req_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("reroute mkt data req msg"))
             }
+            #[cfg(feature = "depth")]
             Ok(In::RerouteMktDepthReq) => {
-                decode::Local::reroute_mkt_depth_req_msg(&mut fields.into_iter(), local)
+                decode::Local::reroute_mkt_depth_req_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("reroute mkt depth req msg"))
             }
-            Ok(In::MarketRule) => decode::Local::market_rule_msg(&mut fields.into_iter(), local)
+            Ok(In::MarketRule) => decode::Local::market_rule_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("market rule msg")),
-            Ok(In::Pnl) => decode::Local::pnl_msg(&mut fields.into_iter(), local)
+            Ok(In::Pnl) => decode::Local::pnl_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("pnl msg")),
-            Ok(In::PnlSingle) => decode::Local::pnl_single_msg(&mut fields.into_iter(), local)
+            Ok(In::PnlSingle) => decode::Local::pnl_single_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("pnl single msg")),
             Ok(In::HistoricalTicks) => {
-                decode::Local::historical_ticks_midpoint_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_ticks_midpoint_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical ticks msg"))
             }
             Ok(In::HistoricalTicksBidAsk) => {
-                decode::Local::historical_ticks_bid_ask_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_ticks_bid_ask_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical ticks bid ask msg"))
             }
             Ok(In::HistoricalTicksLast) => {
-                decode::Local::historical_ticks_last_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_ticks_last_msg(&mut frame.fields(), local, tx, rx)
                     .await
                     .map_err(|e| e.with_context("historical ticks last msg"))
             }
-            Ok(In::TickByTick) => decode::Local::tick_by_tick_msg(&mut fields.into_iter(), local)
+            Ok(In::TickByTick) => decode::Local::tick_by_tick_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("tick by tick msg")),
-            Ok(In::OrderBound) => decode::Local::order_bound_msg(&mut fields.into_iter(), local)
+            Ok(In::OrderBound) => decode::Local::order_bound_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("order bound msg")),
             Ok(In::CompletedOrder) => {
-                decode::Local::completed_order_msg(&mut fields.into_iter(), local)
+                decode::Local::completed_order_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("completed order msg"))
             }
             Ok(In::CompletedOrdersEnd) => {
-                decode::Local::completed_orders_end_msg(&mut fields.into_iter(), local)
+                decode::Local::completed_orders_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("completed orders end msg"))
             }
+            #[cfg(feature = "fa")]
             Ok(In::ReplaceFaEnd) => {
-                decode::Local::replace_fa_end_msg(&mut fields.into_iter(), local)
+                decode::Local::replace_fa_end_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("replace fa end msg"))
             }
-            Ok(In::WshMetaData) => decode::Local::wsh_meta_data_msg(&mut fields.into_iter(), local)
+            #[cfg(feature = "wsh")]
+            Ok(In::WshMetaData) => decode::Local::wsh_meta_data_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("wsh meta data msg")),
+            #[cfg(feature = "wsh")]
             Ok(In::WshEventData) => {
-                decode::Local::wsh_event_data_msg(&mut fields.into_iter(), local)
+                decode::Local::wsh_event_data_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("wsh event data msg"))
             }
             Ok(In::HistoricalSchedule) => {
-                decode::Local::historical_schedule_msg(&mut fields.into_iter(), local)
+                decode::Local::historical_schedule_msg(&mut frame.fields(), local)
                     .await
                     .map_err(|e| e.with_context("historical schedule msg"))
             }
-            Ok(In::UserInfo) => decode::Local::user_info_msg(&mut fields.into_iter(), local)
+            Ok(In::UserInfo) => decode::Local::user_info_msg(&mut frame.fields(), local)
                 .await
                 .map_err(|e| e.with_context("user info msg")),
+            // Messages that belong to a subsystem whose feature is disabled are dropped.
+            #[allow(unreachable_patterns)]
+            Ok(_) => Ok(()),
             Err(e) => Err(DecodeError::Other(e.0).with_context("invalid in msg")),
         },
     };
@@ -1128,17 +2150,26 @@ async fn decode_msg_local<W>(
         Ok(()) => (),
         Err(e) => {
             tokio::task::yield_now().await;
-            error!("Error in decoding incoming message from API. Error message: {e}");
+            #[cfg(feature = "metrics")]
+            rx.shared().metrics().record_decode_failure();
+            let report = e.into_report(frame.to_vec());
+            error!(
+                kind = %report.kind,
+                message_id = report.fields.first().map(String::as_str),
+                error = %report.error,
+                "Error in decoding incoming message from API"
+            );
+            local.decode_error(report).await;
         }
     }
 }
 
 pub(crate) mod indicators {
-    use std::collections::HashSet;
+    use std::sync::Arc;
 
-    use tokio::{net::tcp::OwnedReadHalf, sync::mpsc, task::JoinHandle};
+    use tokio::{sync::mpsc, task::JoinHandle};
 
-    use crate::message::{ToClient, ToWrapper};
+    use crate::message::{Shared, ToClient, ToWrapper};
 
     use super::Reader;
 
@@ -1146,7 +2177,7 @@ pub(crate) mod indicators {
 
     #[derive(Debug)]
     pub struct Inactive {
-        pub(crate) reader: OwnedReadHalf,
+        pub(crate) reader: crate::transport::ReadHalf,
     }
 
     impl Status for Inactive {}
@@ -1157,9 +2188,14 @@ pub(crate) mod indicators {
         pub(crate) disconnect: super::CancelToken,
         pub(crate) tx: mpsc::Sender<ToWrapper>,
         pub(crate) rx: mpsc::Receiver<ToClient>,
-        pub(crate) managed_accounts: HashSet<String>,
-        pub(crate) order_id: core::ops::RangeFrom<i64>,
+        pub(crate) shared: Arc<Shared>,
         pub(crate) req_id: core::ops::RangeFrom<i64>,
+        pub(crate) limits: crate::limits::Tracker,
+        // The accounts reported when the client connected.
+        pub(crate) managed_accounts: std::collections::HashSet<String>,
+        // The request IDs of the active SMART depth subscriptions.
+        #[cfg(feature = "depth")]
+        pub(crate) smart_depth: std::collections::HashSet<i64>,
     }
 
     impl Status for Active {}
@@ -1192,6 +2228,7 @@ pub struct Client<C: indicators::Status> {
     server_version: u32,
     conn_time: chrono::DateTime<Tz>,
     writer: Writer,
+    options: Options,
     status: C,
 }
 
@@ -1248,18 +2285,89 @@ impl<S: indicators::Status> Client<S> {
     pub const fn get_server_version(&self) -> u32 {
         self.server_version
     }
+
+    #[cfg(feature = "metrics")]
+    #[inline]
+    /// Return the counters and request latencies of this client, which are maintained by its
+    /// client loop.
+    pub fn metrics(&self) -> &crate::metrics::Registry {
+        self.writer.metrics()
+    }
+
+    #[inline]
+    /// Return the contract cache installed with [`Builder::with_contract_cache`], if any.
+    pub const fn get_contract_cache(&self) -> Option<&crate::contract::Cache> {
+        self.options.contract_cache.as_ref()
+    }
+
+    #[inline]
+    /// Return a mutable reference to the contract cache installed with
+    /// [`Builder::with_contract_cache`], if any.
+    pub fn get_contract_cache_mut(&mut self) -> Option<&mut crate::contract::Cache> {
+        self.options.contract_cache.as_mut()
+    }
+}
+
+// Pass an incoming message to the wrapper, or to `LocalWrapper::heartbeat` if it is the reply to a
+// watchdog request. Every current time reply updates the estimated offset of the server's clock.
+#[inline]
+async fn handle_local<W: LocalWrapper>(
+    frame: Frame,
+    heartbeat: &mut Option<Heartbeat>,
+    retry: &mut Option<Retry>,
+    wrapper: &mut W,
+    tx: &mut mpsc::Sender<ToClient>,
+    rx: &mut Inbox,
+) {
+    let round_trip = heartbeat.as_mut().and_then(|h| h.observe(&frame));
+    rx.shared()
+        .sync_clock(&frame, chrono::Utc::now(), round_trip);
+    if Retry::observe(retry.as_mut(), &frame, rx.shared()).await {
+        return;
+    }
+    match round_trip {
+        Some(round_trip) => {
+            wrapper.heartbeat(round_trip).await;
+        }
+        None => decode_msg_local(frame, wrapper, tx, rx).await,
+    }
+}
+
+#[inline]
+async fn handle_remote<W: Wrapper>(
+    frame: Frame,
+    heartbeat: &mut Option<Heartbeat>,
+    retry: &mut Option<Retry>,
+    wrapper: &mut W,
+    tx: &mut mpsc::Sender<ToClient>,
+    rx: &mut Inbox,
+) {
+    let round_trip = heartbeat.as_mut().and_then(|h| h.observe(&frame));
+    rx.shared()
+        .sync_clock(&frame, chrono::Utc::now(), round_trip);
+    if Retry::observe(retry.as_mut(), &frame, rx.shared()).await {
+        return;
+    }
+    match round_trip {
+        Some(round_trip) => {
+            wrapper.heartbeat(round_trip).await;
+        }
+        None => decode_msg_remote(frame, wrapper, tx, rx).await,
+    }
 }
 
 #[inline]
 fn spawn_reader_thread(
-    rdr: OwnedReadHalf,
-) -> (CancelToken, mpsc::Receiver<Vec<String>>, JoinHandle<Reader>) {
+    rdr: ReadHalf,
+    options: &Options,
+) -> (CancelToken, queue::Receiver, JoinHandle<Reader>) {
     let disconnect = CancelToken::new();
-    let (tx, rx) = mpsc::channel(constants::FROM_READER_CHANNEL_SIZE);
+    let (tx, rx) = queue::channel(options.queue_capacity, options.overflow_policy);
+    let tap = options.tap.clone();
 
     let r_disconnect = disconnect.clone();
     let r_thread = tokio::spawn(async move {
-        let reader = Reader::new(rdr, tx, r_disconnect);
+        let reader = Reader::new(rdr, tx, r_disconnect, tap);
         reader.run().await
     });
     (disconnect, rx, r_thread)
@@ -1268,21 +2376,35 @@ fn spawn_reader_thread(
 #[inline]
 fn spawn_temp_contract_thread(
     cancel_token: CancelToken,
-    mut rx_reader: mpsc::Receiver<Vec<String>>,
-    mut backlog: std::collections::VecDeque<Vec<String>>,
+    mut rx_reader: queue::Receiver,
+    mut backlog: std::collections::VecDeque<Frame>,
     mut tx: mpsc::Sender<ToClient>,
-    mut rx: mpsc::Receiver<ToWrapper>,
+    mut rx: Inbox,
 ) -> JoinHandle<LoopParams> {
     tokio::spawn(async move {
         loop {
             tokio::select! {
                 biased;
-                Some(fields) = rx_reader.recv() => {
-                     let _ = match fields.first().and_then(|t| t.parse().ok()) {
-                        Some(In::ContractData) => decode::decode_contract_no_wrapper(&mut fields.into_iter(), &mut tx, &mut rx).await.map_err(|e| e.with_context("contract data msg")),
-                        Some(_) => { backlog.push_back(fields); Ok(()) },
+                Some(frame) = rx_reader.recv() => {
+                     let status = match frame.first().and_then(|t| t.parse().ok()) {
+                        Some(In::ContractData) => match decode::decode_contract_no_wrapper(&mut frame.fields(), &mut tx, &mut rx).await {
+                            Ok(Some(_)) => { backlog.push_back(frame); Ok(()) },
+                            status => status.map(|_| ()).map_err(|e| e.with_context("contract data msg")),
+                        },
+                        Some(In::ContractDataEnd) => match decode::decode_contract_end_no_wrapper(&mut frame.fields(), &mut tx, &mut rx).await {
+                            Ok(Some(_)) => { backlog.push_back(frame); Ok(()) },
+                            status => status.map(|_| ()).map_err(|e| e.with_context("contract data end msg")),
+                        },
+                        Some(In::HeadTimestamp) => match decode::decode_head_timestamp_no_wrapper(&mut frame.fields(), &mut tx, &mut rx).await {
+                            Ok(Some(_)) => { backlog.push_back(frame); Ok(()) },
+                            status => status.map(|_| ()).map_err(|e| e.with_context("head timestamp msg")),
+                        },
+                        Some(_) => { backlog.push_back(frame); Ok(()) },
                         None => Ok(()),
                     };
+                    if let Err(e) = status {
+                        error!(error = %e, "Error in decoding contract data during client initialization");
+                    }
                 }
                 () = cancel_token.cancelled() => { break; },
             }
@@ -1307,28 +2429,24 @@ impl Client<indicators::Inactive> {
     }
 
     async fn into_active(self) -> IntoActive {
-        let (disconnect, mut rx_reader, r_thread) = spawn_reader_thread(self.status.reader);
+        let (disconnect, mut rx_reader, r_thread) =
+            spawn_reader_thread(self.status.reader, &self.options);
 
         let mut backlog = std::collections::VecDeque::new();
         let (mut managed_accounts, mut valid_id) = (None, None);
         while managed_accounts.is_none() || valid_id.is_none() {
-            if let Some(fields) = rx_reader.recv().await {
-                match fields.first().and_then(|t| t.parse().ok()) {
+            if let Some(frame) = rx_reader.recv().await {
+                match frame.first().and_then(|t| t.parse().ok()) {
                     Some(In::ManagedAccts) => {
-                        managed_accounts = Some(
-                            fields
-                                .into_iter()
-                                .skip(2)
-                                .filter(|v| v.as_str() != "")
-                                .collect::<std::collections::HashSet<String>>(),
-                        );
+                        managed_accounts =
+                            decode::decode_managed_accounts(&mut frame.fields()).ok();
                     }
                     Some(In::NextValidId) => {
-                        valid_id = decode::nth(&mut fields.into_iter(), 2, "valid_id")
+                        valid_id = decode::nth(&mut frame.fields(), 2, "valid_id")
                             .ok()
                             .and_then(|t| t.parse::<i64>().ok());
                     }
-                    Some(_) => backlog.push_back(fields),
+                    Some(_) => backlog.push_back(frame),
                     None => (),
                 }
             }
@@ -1339,9 +2457,15 @@ impl Client<indicators::Inactive> {
                 "The loop should only exit if a valid set of accounts and id are received."
             )
         };
-        let (client_tx, wrapper_rx) =
-            mpsc::channel::<ToWrapper>(constants::TO_WRAPPER_CHANNEL_SIZE);
-        let (wrapper_tx, client_rx) = mpsc::channel::<ToClient>(constants::TO_CLIENT_CHANNEL_SIZE);
+        let (client_tx, wrapper_rx) = mpsc::channel::<ToWrapper>(self.options.to_wrapper_capacity);
+        let (wrapper_tx, client_rx) = mpsc::channel::<ToClient>(self.options.to_client_capacity);
+        let shared = Shared::new(valid_id, managed_accounts.clone())
+            .with_unknown_requests(self.options.unknown_requests);
+        #[cfg(feature = "metrics")]
+        let shared = shared.with_metrics(std::sync::Arc::clone(self.writer.metrics()));
+        let shared = std::sync::Arc::new(shared);
+        let combined_quotes = self.options.combined_quotes;
+        let limits = crate::limits::Tracker::new(self.options.limits);
 
         let client = Client {
             mode: self.mode,
@@ -1352,17 +2476,27 @@ impl Client<indicators::Inactive> {
             server_version: self.server_version,
             conn_time: self.conn_time,
             writer: self.writer,
+            options: self.options,
             status: indicators::Active {
                 r_thread,
                 disconnect,
                 tx: client_tx,
                 rx: client_rx,
-                managed_accounts,
-                order_id: valid_id..,
+                shared: std::sync::Arc::clone(&shared),
                 req_id: 0_i64..,
+                limits,
+                managed_accounts,
+                #[cfg(feature = "depth")]
+                smart_depth: std::collections::HashSet::new(),
             },
         };
-        (client, wrapper_tx, wrapper_rx, rx_reader, backlog)
+        (
+            client,
+            wrapper_tx,
+            Inbox::new(wrapper_rx, shared).with_combined_quotes(combined_quotes),
+            rx_reader,
+            backlog,
+        )
     }
 
     /// Initiates the main message loop and spawns all helper threads to manage the application.
@@ -1385,27 +2519,75 @@ impl Client<indicators::Inactive> {
         init: I,
         disconnect_token: Option<CancelToken>,
     ) -> Result<Builder, std::io::Error> {
+        self.run_local(init, disconnect_token, false, None)
+            .await
+            .map(|(builder, _)| builder)
+    }
+
+    // The loop of `Client::local`. A `supervised` loop also disconnects when a restart begins and
+    // returns the restart's period, and first tells the wrapper about the restart after which it
+    // was `resumed`, if any.
+    async fn run_local<I: LocalInitializer>(
+        self,
+        init: I,
+        disconnect_token: Option<CancelToken>,
+        supervised: bool,
+        resumed: Option<crate::restart::Period>,
+    ) -> Result<(Builder, Option<crate::restart::Period>), std::io::Error> {
         let (mut client, tx, rx, rx_reader, backlog) = self.into_active().await;
+        let dispatch = client.options.dispatch;
         let temp = CancelToken::new();
         let con_fut = spawn_temp_contract_thread(temp.clone(), rx_reader, backlog, tx, rx);
 
         let disconnect_token = disconnect_token.unwrap_or_else(|| client.status.disconnect.clone());
+        let mut heartbeat = Heartbeat::new(&client.options, &client.writer);
+        let mut retry = Retry::new(&client.options, &client.writer);
+        let mut restart = Restart::new(&client.options);
+        let mut restarted = None;
         let mut wrapper =
             LocalInitializer::build(init, &mut client, disconnect_token.clone()).await;
         temp.cancel();
         drop(temp);
         let (mut rx_reader, mut tx, mut rx, mut backlog) = con_fut.await?;
-        while let Some(fields) = backlog.pop_front() {
-            decode_msg_local(fields, &mut wrapper, &mut tx, &mut rx).await;
+        if let Some(period) = resumed {
+            LocalWrapper::restart_complete(&mut wrapper, period).await;
+        }
+        while let Some(frame) = backlog.pop_front() {
+            decode_msg_local(frame, &mut wrapper, &mut tx, &mut rx).await;
         }
         drop(backlog);
         loop {
             tokio::select! {
                 biased;
-                Some(fields) = rx_reader.recv() => {
-                    decode_msg_local(fields, &mut wrapper, &mut tx, &mut rx).await;
+                Some(frame) = rx_reader.recv() => {
+                    #[cfg(feature = "metrics")]
+                    rx.shared().metrics().record_queue_depth(rx_reader.len());
+                    handle_local(frame, &mut heartbeat, &mut retry, &mut wrapper, &mut tx, &mut rx).await;
+                    for _ in 1..dispatch.batch_size() {
+                        let Some(frame) = rx_reader.try_recv() else { break };
+                        handle_local(frame, &mut heartbeat, &mut retry, &mut wrapper, &mut tx, &mut rx).await;
+                    }
+                },
+                () = Heartbeat::due(heartbeat.as_ref()) => {
+                    if let Some(silence) = Heartbeat::beat(heartbeat.as_mut()).await {
+                        LocalWrapper::connection_stalled(&mut wrapper, silence).await;
+                    }
                 },
-                () = tokio::task::yield_now() => (),
+                () = Restart::due(restart.as_ref()) => match Restart::advance(restart.as_mut()) {
+                    Some(RestartEvent::Pending(period)) => {
+                        LocalWrapper::restart_pending(&mut wrapper, period).await;
+                    }
+                    Some(RestartEvent::Started(period)) => {
+                        LocalWrapper::restart_started(&mut wrapper, period).await;
+                        if supervised {
+                            info!("Client loop disconnecting for a restart");
+                            restarted = Some(period);
+                            break
+                        }
+                    }
+                    None => (),
+                },
+                () = dispatch.idle() => (),
                 () = disconnect_token.cancelled() => {
                     info!("Client loop disconnecting");
                     break
@@ -1414,7 +2596,7 @@ impl Client<indicators::Inactive> {
             crate::wrapper::LocalRecurring::cycle(&mut wrapper).await;
         }
         drop(wrapper);
-        client.disconnect().await
+        Ok((client.disconnect().await?, restarted))
     }
 
     /// Initiates the main message loop and spawns all helper threads to manage the application.
@@ -1428,28 +2610,52 @@ impl Client<indicators::Inactive> {
     #[tracing::instrument(skip(init), level = tracing::Level::DEBUG)]
     pub async fn remote<I: Initializer + 'static>(self, init: I) -> CancelToken {
         let (mut client, tx, rx, rx_reader, backlog) = self.into_active().await;
+        let dispatch = client.options.dispatch;
 
         let temp = CancelToken::new();
         let con_fut = spawn_temp_contract_thread(temp.clone(), rx_reader, backlog, tx, rx);
 
         let break_loop = client.status.disconnect.clone();
         let break_loop_inner = break_loop.clone();
+        let mut heartbeat = Heartbeat::new(&client.options, &client.writer);
+        let mut retry = Retry::new(&client.options, &client.writer);
+        let mut restart = Restart::new(&client.options);
         tokio::spawn(async move {
             let mut wrapper = Initializer::build(init, &mut client, break_loop_inner.clone()).await;
             temp.cancel();
             drop(temp);
             let (mut rx_reader, mut tx, mut rx, mut backlog) = con_fut.await?;
-            while let Some(fields) = backlog.pop_front() {
-                decode_msg_remote(fields, &mut wrapper, &mut tx, &mut rx).await;
+            while let Some(frame) = backlog.pop_front() {
+                decode_msg_remote(frame, &mut wrapper, &mut tx, &mut rx).await;
             }
             drop(backlog);
             loop {
                 tokio::select! {
                     biased;
-                    Some(fields) = rx_reader.recv() => {
-                        decode_msg_remote(fields, &mut wrapper, &mut tx, &mut rx).await;
+                    Some(frame) = rx_reader.recv() => {
+                        #[cfg(feature = "metrics")]
+                        rx.shared().metrics().record_queue_depth(rx_reader.len());
+                        handle_remote(frame, &mut heartbeat, &mut retry, &mut wrapper, &mut tx, &mut rx).await;
+                        for _ in 1..dispatch.batch_size() {
+                            let Some(frame) = rx_reader.try_recv() else { break };
+                            handle_remote(frame, &mut heartbeat, &mut retry, &mut wrapper, &mut tx, &mut rx).await;
+                        }
+                    },
+                    () = Heartbeat::due(heartbeat.as_ref()) => {
+                        if let Some(silence) = Heartbeat::beat(heartbeat.as_mut()).await {
+                            Wrapper::connection_stalled(&mut wrapper, silence).await;
+                        }
+                    },
+                    () = Restart::due(restart.as_ref()) => match Restart::advance(restart.as_mut()) {
+                        Some(RestartEvent::Pending(period)) => {
+                            Wrapper::restart_pending(&mut wrapper, period).await;
+                        }
+                        Some(RestartEvent::Started(period)) => {
+                            Wrapper::restart_started(&mut wrapper, period).await;
+                        }
+                        None => (),
                     },
-                    () = tokio::task::yield_now() => (),
+                    () = dispatch.idle() => (),
                     () = break_loop_inner.cancelled() => {
                         info!("Client loop: disconnecting");
                         break
@@ -1477,10 +2683,14 @@ impl Client<indicators::Inactive> {
         mut wrapper: W,
     ) -> Client<indicators::Active> {
         let (client, mut tx, mut rx, mut rx_reader, mut backlog) = self.into_active().await;
+        let dispatch = client.options.dispatch;
         let c_loop_disconnect = client.status.disconnect.clone();
+        let mut heartbeat = Heartbeat::new(&client.options, &client.writer);
+        let mut retry = Retry::new(&client.options, &client.writer);
+        let mut restart = Restart::new(&client.options);
 
-        while let Some(fields) = backlog.pop_front() {
-            decode_msg_remote(fields, &mut wrapper, &mut tx, &mut rx).await;
+        while let Some(frame) = backlog.pop_front() {
+            decode_msg_remote(frame, &mut wrapper, &mut tx, &mut rx).await;
         }
         drop(backlog);
         tokio::spawn(async move {
@@ -1488,10 +2698,30 @@ impl Client<indicators::Inactive> {
             loop {
                 tokio::select! {
                     biased;
-                    Some(fields) = rx_reader.recv() => {
-                        decode_msg_remote(fields, &mut wrapper, &mut tx, &mut rx).await;
+                    Some(frame) = rx_reader.recv() => {
+                        #[cfg(feature = "metrics")]
+                        rx.shared().metrics().record_queue_depth(rx_reader.len());
+                        handle_remote(frame, &mut heartbeat, &mut retry, &mut wrapper, &mut tx, &mut rx).await;
+                        for _ in 1..dispatch.batch_size() {
+                            let Some(frame) = rx_reader.try_recv() else { break };
+                            handle_remote(frame, &mut heartbeat, &mut retry, &mut wrapper, &mut tx, &mut rx).await;
+                        }
                     },
-                    () = tokio::task::yield_now() => (),
+                    () = Heartbeat::due(heartbeat.as_ref()) => {
+                        if let Some(silence) = Heartbeat::beat(heartbeat.as_mut()).await {
+                            Wrapper::connection_stalled(&mut wrapper, silence).await;
+                        }
+                    },
+                    () = Restart::due(restart.as_ref()) => match Restart::advance(restart.as_mut()) {
+                        Some(RestartEvent::Pending(period)) => {
+                            Wrapper::restart_pending(&mut wrapper, period).await;
+                        }
+                        Some(RestartEvent::Started(period)) => {
+                            Wrapper::restart_started(&mut wrapper, period).await;
+                        }
+                        None => (),
+                    },
+                    () = dispatch.idle() => (),
                     () = c_loop_disconnect.cancelled() => {info!("Client loop: disconnecting"); break},
                 }
             }
@@ -1503,21 +2733,21 @@ impl Client<indicators::Inactive> {
 
 type ReqResult = Result<(), std::io::Error>;
 type IdResult = Result<i64, std::io::Error>;
+type RequestIdResult<C> = Result<RequestId<C>, std::io::Error>;
 
 impl Client<indicators::Active> {
     // ====================================================
     // === Methods That Return Attributes of the Client ===
     // ====================================================
 
-    // Don't worry about the "allow": This function will NEVER panic
     #[inline]
-    #[allow(clippy::missing_panics_doc, clippy::unwrap_used)]
-    /// Get the next valid *order* ID, as determined by the client's internal counter
+    /// Get the next valid *order* ID, as determined by the client's internal counter. The counter
+    /// is advanced whenever IBKR reports a larger next valid ID (ex. after [`Client::req_ids`]).
     ///
     /// # Returns
     /// The next valid order ID
     fn get_next_order_id(&mut self) -> i64 {
-        self.status.order_id.next().unwrap()
+        self.status.shared.next_order_id()
     }
 
     // Don't worry about the "allow": This function will NEVER panic
@@ -1531,9 +2761,21 @@ impl Client<indicators::Active> {
         self.status.req_id.next().unwrap()
     }
 
+    #[inline]
+    // Get the next valid request ID and record it as an outstanding request of `kind`, which must
+    // happen before the request is sent so that no callback is missed. Nothing is recorded while
+    // requests are paused for a restart, as the request could not be sent anyway.
+    fn register_next_req_id(&mut self, kind: RequestKind) -> Result<i64, std::io::Error> {
+        self.writer.check_paused()?;
+        let req_id = self.get_next_req_id();
+        self.status.shared.register_request(req_id, kind);
+        Ok(req_id)
+    }
+
     #[inline]
     #[must_use]
-    /// Get the set of accounts managed by the client
+    /// Get the set of accounts managed by the client when it connected. Accounts added since then
+    /// are returned by [`Client::get_current_managed_accounts`].
     ///
     /// # Returns
     /// A reference to the set of the client's managed accounts
@@ -1541,13 +2783,138 @@ impl Client<indicators::Active> {
         &self.status.managed_accounts
     }
 
+    #[inline]
+    #[must_use]
+    /// Get the set of accounts currently managed by the client. The set is kept up to date with
+    /// every managed accounts message, including those requested with
+    /// [`Client::req_managed_accounts`].
+    ///
+    /// # Returns
+    /// A copy of the current set of the client's managed accounts
+    pub fn get_current_managed_accounts(&self) -> std::collections::HashSet<String> {
+        self.status.shared.managed_accounts().clone()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the current usage of the channel that carries notices from the client to the loop
+    /// that decodes incoming messages. Its capacity is set by [`Builder::with_channel_capacities`].
+    pub fn get_wrapper_channel_usage(&self) -> ChannelUsage {
+        let capacity = self.status.tx.max_capacity();
+        ChannelUsage {
+            len: capacity - self.status.tx.capacity(),
+            capacity,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the current usage of the channel that carries responses from the loop that decodes
+    /// incoming messages to the client. Its capacity is set by
+    /// [`Builder::with_channel_capacities`].
+    pub fn get_client_channel_usage(&self) -> ChannelUsage {
+        ChannelUsage {
+            len: self.status.rx.len(),
+            capacity: self.status.rx.max_capacity(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the number of active subscriptions of a given kind, along with the limit set by
+    /// [`Builder::with_limits`]. A subscription is active from the moment it is requested until it
+    /// is cancelled through the client, even if TWS rejects it in the meantime.
+    pub fn get_subscription_usage(&self, kind: crate::limits::Kind) -> crate::limits::Usage {
+        self.status.limits.usage(kind)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the outstanding requests of the client, keyed by request ID. A request is outstanding
+    /// from the moment it is sent until it is cancelled through the client or, for one-off
+    /// requests such as historical bars, until its last callback arrives.
+    ///
+    /// Callbacks for request IDs that are not outstanding are handled according to
+    /// [`Builder::with_unknown_requests`].
+    pub fn get_outstanding_requests(
+        &self,
+    ) -> std::collections::BTreeMap<i64, crate::requests::Kind> {
+        self.status.shared.outstanding_requests()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the estimated offset of the server's clock from the local clock, which is positive if
+    /// the server's clock is ahead.
+    ///
+    /// The offset is estimated from the replies to [`Client::req_current_time`] and to the
+    /// requests of a [`Watchdog`], the latter of which also account for the round trip. As the
+    /// server reports its time to the second, the estimate is accurate to about half a second.
+    ///
+    /// # Returns
+    /// The latest estimate, or [`None`] if no current time reply has been received.
+    pub fn get_server_time_offset(&self) -> Option<chrono::TimeDelta> {
+        self.status.shared.clock_offset()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the current time according to the server's clock, which is the local time corrected by
+    /// [`Client::get_server_time_offset`], or the local time if no offset has been estimated.
+    ///
+    /// # Returns
+    /// The estimated current server time.
+    pub fn server_time_now(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now() + self.get_server_time_offset().unwrap_or_default()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Create a [`crate::order::TimeInForce::GoodTillDate`] that expires a given time from now
+    /// according to the server's clock, so that an order is not rejected, nor kept working for
+    /// longer than intended, because the local clock is skewed.
+    ///
+    /// # Arguments
+    /// * `duration` - The time from now, as returned by [`Client::server_time_now`], after which
+    ///   the order expires.
+    /// * `timezone` - The timezone in which the expiry is sent.
+    pub fn good_till_date_in(
+        &self,
+        duration: chrono::TimeDelta,
+        timezone: chrono_tz::Tz,
+    ) -> crate::order::TimeInForce {
+        crate::order::TimeInForce::GoodTillDate(
+            (self.server_time_now() + duration).with_timezone(&timezone),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    /// Create a [`crate::order::TimeInForce::GoodAfterTime`] that becomes active a given time from
+    /// now according to the server's clock.
+    ///
+    /// # Arguments
+    /// * `duration` - The time from now, as returned by [`Client::server_time_now`], after which
+    ///   the order becomes active.
+    /// * `timezone` - The timezone in which the activation time is sent.
+    pub fn good_after_time_in(
+        &self,
+        duration: chrono::TimeDelta,
+        timezone: chrono_tz::Tz,
+    ) -> crate::order::TimeInForce {
+        crate::order::TimeInForce::GoodAfterTime(
+            (self.server_time_now() + duration).with_timezone(&timezone),
+        )
+    }
+
     // ===================================
     // === Methods That Make API Calls ===
     // ===================================
 
     // === General Functions ===
 
-    /// Request the current time from the server.
+    /// Request the current time from the server. The reply also updates the estimate of
+    /// [`Client::get_server_time_offset`].
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
@@ -1558,7 +2925,23 @@ impl Client<indicators::Active> {
         self.writer.send().await
     }
 
-    /// Requests the accounts to which the logged user has access to.
+    /// Requests the next valid order ID. The ID is delivered to
+    /// [`crate::wrapper::LocalWrapper::next_valid_id`], and the client's internal order ID counter
+    /// is advanced to it if necessary, which matters when orders are also placed by another client
+    /// or manually in TWS.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    pub async fn req_ids(&mut self) -> ReqResult {
+        const VERSION: u8 = 1;
+
+        self.writer.add_body((Out::ReqIds, VERSION, 1))?;
+        self.writer.send().await
+    }
+
+    /// Requests the accounts to which the logged user has access to. The accounts are delivered to
+    /// [`crate::wrapper::LocalWrapper::managed_accounts`], and the set returned by
+    /// [`Client::get_current_managed_accounts`] is updated to match.
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
@@ -1634,10 +3017,185 @@ impl Client<indicators::Active> {
         self.writer.send().await
     }
 
+    /// Subscribes to position updates for a given account and / or model. All positions are sent
+    /// initially, and then only updates as positions change.
+    ///
+    /// # Arguments
+    /// * `account_number` - The account number for which to subscribe to position data (optional
+    ///   when a `model_code` is provided)
+    /// * `model_code` - The model code for which to subscribe to position data (optional)
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message. Additionally, returns an
+    /// error if a provided `account_number` is not in the client's managed accounts.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_positions_multi(
+        &mut self,
+        account_number: Option<String>,
+        model_code: Option<String>,
+    ) -> RequestIdResult<category::PositionsMulti> {
+        const VERSION: u8 = 1;
+        let req_id = self.register_next_req_id(RequestKind::PositionsMulti)?;
+        if let Some(acct_num) = &account_number {
+            check_valid_account(self, acct_num)?;
+        }
+
+        self.writer.add_body((
+            Out::ReqPositionsMulti,
+            VERSION,
+            req_id,
+            account_number,
+            model_code,
+        ))?;
+        self.writer.send().await?;
+        Ok(RequestId::new(req_id))
+    }
+
+    /// Cancels a previous position subscription request made with [`Client::req_positions_multi`].
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the [`Client::req_positions_multi`] subscription to cancel.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    pub async fn cancel_positions_multi(
+        &mut self,
+        req_id: RequestId<category::PositionsMulti>,
+    ) -> ReqResult {
+        const VERSION: u8 = 1;
+
+        self.writer
+            .add_body((Out::CancelPositionsMulti, VERSION, req_id))?;
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
+    }
+
+    /// Creates a subscription to account and portfolio information for a given account and / or
+    /// model.
+    ///
+    /// # Arguments
+    /// * `account_number` - The account number for which to subscribe to account data (optional
+    ///   when a `model_code` is provided)
+    /// * `model_code` - The model code for which to subscribe to account data (optional)
+    /// * `ledger_and_nlv` - When `true`, only the account's ledger (cash balances) and net
+    ///   liquidation value are returned.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message. Additionally, returns an
+    /// error if a provided `account_number` is not in the client's managed accounts.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_account_updates_multi(
+        &mut self,
+        account_number: Option<String>,
+        model_code: Option<String>,
+        ledger_and_nlv: bool,
+    ) -> RequestIdResult<category::AccountUpdatesMulti> {
+        const VERSION: u8 = 1;
+        let req_id = self.register_next_req_id(RequestKind::AccountUpdatesMulti)?;
+        if let Some(acct_num) = &account_number {
+            check_valid_account(self, acct_num)?;
+        }
+
+        self.writer.add_body((
+            Out::ReqAccountUpdatesMulti,
+            VERSION,
+            req_id,
+            account_number,
+            model_code,
+            ledger_and_nlv,
+        ))?;
+        self.writer.send().await?;
+        Ok(RequestId::new(req_id))
+    }
+
+    /// Creates a [`Client::req_account_updates_multi`] subscription for each of several models,
+    /// so that the account attributes of each model can be aggregated.
+    ///
+    /// # Arguments
+    /// * `group` - With [`Group::All`], every account in each model is included. With
+    ///   [`Group::Name`], only the given account is included.
+    /// * `model_codes` - The model codes for which to subscribe to account data.
+    /// * `ledger_and_nlv` - When `true`, only the accounts' ledgers (cash balances) and net
+    ///   liquidation values are returned.
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Client::req_account_updates_multi`]. Subscriptions that
+    /// were created before the error are cancelled.
+    ///
+    /// # Returns
+    /// A [`ModelAccounts`] in which every subscription is registered under its model code, and to
+    /// which the account attribute callbacks can be passed.
+    pub async fn req_account_updates_by_model<I>(
+        &mut self,
+        group: Group,
+        model_codes: I,
+        ledger_and_nlv: bool,
+    ) -> Result<ModelAccounts, std::io::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let account_number = match group {
+            Group::All => None,
+            Group::Name(account_number) => Some(account_number),
+        };
+        let mut models = ModelAccounts::new();
+        let mut req_ids = Vec::new();
+        for model_code in model_codes {
+            let model_code = model_code.into();
+            match self
+                .req_account_updates_multi(
+                    account_number.clone(),
+                    Some(model_code.clone()),
+                    ledger_and_nlv,
+                )
+                .await
+            {
+                Ok(req_id) => {
+                    models.register(req_id.get(), model_code);
+                    req_ids.push(req_id);
+                }
+                Err(e) => {
+                    for req_id in req_ids {
+                        self.cancel_account_updates_multi(req_id).await?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(models)
+    }
+
+    /// Cancels an existing subscription created by [`Client::req_account_updates_multi`].
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the [`Client::req_account_updates_multi`] subscription to cancel.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    pub async fn cancel_account_updates_multi(
+        &mut self,
+        req_id: RequestId<category::AccountUpdatesMulti>,
+    ) -> ReqResult {
+        const VERSION: u8 = 1;
+
+        self.writer
+            .add_body((Out::CancelAccountUpdatesMulti, VERSION, req_id))?;
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
+    }
+
     /// Creates subscription for real time daily P&L and unrealized P&L updates.
     ///
     /// # Arguments
     /// * `account_number` - The account number with which to create the subscription.
+    /// * `model_code` - The model code with which to scope the subscription (optional).
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message. Additionally, returns an
@@ -1645,14 +3203,18 @@ impl Client<indicators::Active> {
     ///
     /// # Returns
     /// The unique ID associated with the request.
-    pub async fn req_pnl(&mut self, account_number: &String) -> IdResult {
-        let req_id = self.get_next_req_id();
+    pub async fn req_pnl(
+        &mut self,
+        account_number: &String,
+        model_code: Option<String>,
+    ) -> RequestIdResult<category::Pnl> {
+        let req_id = self.register_next_req_id(RequestKind::Pnl)?;
         check_valid_account(self, account_number)?;
 
         self.writer
-            .add_body((Out::ReqPnl, req_id, account_number, None::<()>))?;
+            .add_body((Out::ReqPnl, req_id, account_number, model_code))?;
         self.writer.send().await?;
-        Ok(req_id)
+        Ok(RequestId::new(req_id))
     }
 
     /// Cancel subscription for real-time updates created by [`Client::req_pnl`]
@@ -1662,9 +3224,60 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_pnl(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_pnl(&mut self, req_id: RequestId<category::Pnl>) -> ReqResult {
         self.writer.add_body((Out::CancelPnl, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
+    }
+
+    /// Creates a [`Client::req_pnl`] subscription for every managed account, so that the P&L of
+    /// the accounts can be consolidated.
+    ///
+    /// # Arguments
+    /// * `model_code` - The model code with which to scope every subscription (optional).
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Client::req_pnl`]. Subscriptions that were created before
+    /// the error are cancelled.
+    ///
+    /// # Returns
+    /// A [`PnlRollup`] in which every subscription is registered under its account number, and to
+    /// which the P&L callbacks can be passed.
+    pub async fn req_pnl_rollup(
+        &mut self,
+        model_code: Option<String>,
+    ) -> Result<PnlRollup, std::io::Error> {
+        let mut accounts = self
+            .get_current_managed_accounts()
+            .into_iter()
+            .collect::<Vec<_>>();
+        accounts.sort_unstable();
+        let mut rollup = PnlRollup::new();
+        for account_number in accounts {
+            match self.req_pnl(&account_number, model_code.clone()).await {
+                Ok(req_id) => rollup.register(req_id, account_number),
+                Err(e) => {
+                    self.cancel_pnl_rollup(rollup).await?;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(rollup)
+    }
+
+    /// Cancel every subscription made by [`Client::req_pnl_rollup`].
+    ///
+    /// # Arguments
+    /// * `rollup` - The subscriptions to cancel.
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Client::cancel_pnl`].
+    pub async fn cancel_pnl_rollup(&mut self, rollup: PnlRollup) -> ReqResult {
+        for (_, req_id) in rollup.req_ids() {
+            self.cancel_pnl(req_id).await?;
+        }
+        Ok(())
     }
 
     /// Creates subscription for real time daily P&L and unrealized P&L updates, but only for a
@@ -1674,6 +3287,7 @@ impl Client<indicators::Active> {
     /// * `account_number` - The account number with which to create the subscription.
     /// * `contract_id` - The contract ID to create a subscription to changes for a specific
     ///   security
+    /// * `model_code` - The model code with which to scope the subscription (optional).
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message. Additionally, returns an
@@ -1685,19 +3299,20 @@ impl Client<indicators::Active> {
         &mut self,
         account_number: &String,
         contract_id: ContractId,
-    ) -> IdResult {
-        let req_id = self.get_next_req_id();
+        model_code: Option<String>,
+    ) -> RequestIdResult<category::SinglePositionPnl> {
+        let req_id = self.register_next_req_id(RequestKind::SinglePositionPnl)?;
         check_valid_account(self, account_number)?;
 
         self.writer.add_body((
             Out::ReqPnlSingle,
             req_id,
             account_number,
-            None::<()>,
+            model_code,
             contract_id,
         ))?;
         self.writer.send().await?;
-        Ok(req_id)
+        Ok(RequestId::new(req_id))
     }
 
     /// Cancel subscription for real-time updates created by [`Client::req_single_position_pnl`]
@@ -1707,9 +3322,14 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_pnl_single(&mut self, req_id: i64) -> ReqResult {
-        self.writer.add_body((Out::CancelPnl, req_id))?;
-        self.writer.send().await
+    pub async fn cancel_pnl_single(
+        &mut self,
+        req_id: RequestId<category::SinglePositionPnl>,
+    ) -> ReqResult {
+        self.writer.add_body((Out::CancelPnlSingle, req_id))?;
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
     }
 
     /// Request completed orders.
@@ -1728,21 +3348,39 @@ impl Client<indicators::Active> {
     /// information as is shown in the TWS Account Summary tab.
     ///
     /// # Arguments
+    /// * `group` - The accounts to summarize: [`Group::All`] for every account, or the name of a
+    ///   Financial Advisor group that has already been created in TWS Global Configuration.
     /// * `tags` - The list of data tags to include in the subscription.
+    /// * `ledger` - The per-currency ledger rows to include in the subscription, if any. These
+    ///   are delivered as [`crate::account::TagValue::Ledger`] values.
     ///
     /// # Returns
     /// The unique ID associated with the request.
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn req_account_summary(&mut self, tags: &Vec<Tag>) -> IdResult {
+    pub async fn req_account_summary(
+        &mut self,
+        group: &Group,
+        tags: &[Tag],
+        ledger: Option<Ledger>,
+    ) -> RequestIdResult<category::AccountSummary> {
         const VERSION: u8 = 1;
-        let req_id = self.get_next_req_id();
+        let req_id = self.register_next_req_id(RequestKind::AccountSummary)?;
+        let group = match group {
+            Group::All => "All",
+            Group::Name(name) => name.as_str(),
+        };
 
-        self.writer
-            .add_body((Out::ReqAccountSummary, VERSION, req_id, "All", tags))?;
+        self.writer.add_body((
+            Out::ReqAccountSummary,
+            VERSION,
+            req_id,
+            group,
+            SummaryTags { tags, ledger },
+        ))?;
         self.writer.send().await?;
-        Ok(req_id)
+        Ok(RequestId::new(req_id))
     }
 
     /// Cancel an existing account summary subscription created by [`Client::req_account_summary`].
@@ -1752,15 +3390,21 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_account_summary(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_account_summary(
+        &mut self,
+        req_id: RequestId<category::AccountSummary>,
+    ) -> ReqResult {
         const VERSION: u8 = 1;
 
         self.writer
             .add_body((Out::CancelAccountSummary, VERSION, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
     }
 
-    /// Request user info details for the user associated with the calling client.
+    /// Request user info details for the user associated with the calling client. The details are
+    /// delivered to [`crate::wrapper::LocalWrapper::user_info`].
     ///
     /// # Returns
     /// The unique ID associated with the request.
@@ -1768,7 +3412,7 @@ impl Client<indicators::Active> {
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
     pub async fn req_user_info(&mut self) -> IdResult {
-        let req_id = self.get_next_req_id();
+        let req_id = self.register_next_req_id(RequestKind::UserInfo)?;
 
         self.writer.add_body((Out::ReqUserInfo, req_id))?;
         self.writer.send().await?;
@@ -1788,12 +3432,14 @@ impl Client<indicators::Active> {
     /// * `bar_size` - The size of each individual bar.
     /// * `data` - The type of data that to return (price, volume, volatility, etc.).
     /// * `regular_trading_hours_only` - When [`true`], only return bars from regular trading hours.
+    /// * `date_format` - The format in which IBKR sends the datetime of each bar.
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
     ///
     /// # Returns
     /// The unique ID associated with the request.
+    #[allow(clippy::too_many_arguments)]
     pub async fn req_historical_bar<S, D>(
         &mut self,
         security: &S,
@@ -1802,12 +3448,13 @@ impl Client<indicators::Active> {
         bar_size: historical_bar::Size,
         data: D,
         regular_trading_hours_only: bool,
+        date_format: historical_bar::DateFormat,
     ) -> IdResult
     where
         S: Security,
         D: historical_bar::DataType<S>,
     {
-        let id = self.get_next_req_id();
+        let id = self.register_next_req_id(RequestKind::HistoricalBar)?;
 
         self.writer.add_body((
             Out::ReqHistoricalData,
@@ -1819,7 +3466,59 @@ impl Client<indicators::Active> {
             duration,
             regular_trading_hours_only,
             data,
-            1,
+            date_format,
+            false,
+            None::<()>,
+        ))?;
+        self.writer.send().await?;
+        Ok(id)
+    }
+
+    /// Request historical bar data for the continuous future (CONTFUT) on the same underlying as
+    /// `future`, which stitches together consecutive expirations into a single series. See
+    /// [`historical_bar`] for types and traits that are used in this function.
+    ///
+    /// IBKR does not accept an end date for continuous futures, so bars always end at the present.
+    ///
+    /// # Arguments
+    /// * `future` - Any future on the underlying for which to request data.
+    /// * `duration` - The duration for which historical data be returned (i.e. the difference
+    ///   between the first bar's datetime and the last bar's datetime).
+    /// * `bar_size` - The size of each individual bar.
+    /// * `data` - The type of data that to return (price, volume, volatility, etc.).
+    /// * `regular_trading_hours_only` - When [`true`], only return bars from regular trading hours.
+    /// * `date_format` - The format in which IBKR sends the datetime of each bar.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_continuous_future_historical_bar<D>(
+        &mut self,
+        future: &SecFuture,
+        duration: historical_bar::Duration,
+        bar_size: historical_bar::Size,
+        data: D,
+        regular_trading_hours_only: bool,
+        date_format: historical_bar::DateFormat,
+    ) -> IdResult
+    where
+        D: historical_bar::DataType<SecFuture>,
+    {
+        let id = self.register_next_req_id(RequestKind::HistoricalBar)?;
+
+        self.writer.add_body((
+            Out::ReqHistoricalData,
+            id,
+            future.as_continuous_out_msg(),
+            false,
+            historical_bar::EndDateTime::Present,
+            bar_size,
+            duration,
+            regular_trading_hours_only,
+            data,
+            date_format,
             false,
             None::<()>,
         ))?;
@@ -1850,12 +3549,12 @@ impl Client<indicators::Active> {
         bar_size: updating_historical_bar::Size,
         data: D,
         regular_trading_hours_only: bool,
-    ) -> IdResult
+    ) -> RequestIdResult<category::UpdatingHistoricalBar>
     where
         S: Security,
         D: updating_historical_bar::DataType<S>,
     {
-        let id = self.get_next_req_id();
+        let id = self.register_next_req_id(RequestKind::UpdatingHistoricalBar)?;
 
         self.writer.add_body((
             Out::ReqHistoricalData,
@@ -1872,7 +3571,7 @@ impl Client<indicators::Active> {
             None::<()>,
         ))?;
         self.writer.send().await?;
-        Ok(id)
+        Ok(RequestId::new(id))
     }
 
     /// Cancel an existing [`historical_bar`] data request.
@@ -1882,12 +3581,17 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_updating_historical_bar(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_updating_historical_bar(
+        &mut self,
+        req_id: RequestId<category::UpdatingHistoricalBar>,
+    ) -> ReqResult {
         const VERSION: u8 = 1;
 
         self.writer
             .add_body((Out::CancelHistoricalData, VERSION, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
     }
 
     /// Request the earliest available data point for a given security and data type.
@@ -1908,13 +3612,108 @@ impl Client<indicators::Active> {
         security: &S,
         data: D,
         regular_trading_hours_only: bool,
-    ) -> IdResult
+    ) -> RequestIdResult<category::HeadTimestamp>
     where
         S: Security,
         D: historical_ticks::DataType<S>,
     {
-        let id = self.get_next_req_id();
+        let id = self.register_next_req_id(RequestKind::HeadTimestamp)?;
+        self.send_head_timestamp(id, security, data, regular_trading_hours_only)
+            .await?;
+        Ok(RequestId::new(id))
+    }
+
+    /// Request the earliest available data point for each of several securities and wait for the
+    /// responses.
+    ///
+    /// Unlike [`Client::req_head_timestamp`], the responses are not passed to
+    /// [`crate::wrapper::LocalWrapper::head_timestamp`]. Any request that has not been answered
+    /// when `timeout` elapses (ex. because IBKR reported an error for it) is cancelled and left
+    /// out of the result, as is any security for which no request was made before then.
+    ///
+    /// # Arguments
+    /// * `securities` - The securities for which to make the requests.
+    /// * `data` - The data for which to make the requests.
+    /// * `regular_trading_hours_only` - When [`true`], only consider data from regular trading
+    ///   hours.
+    /// * `timeout` - How long to make the requests and wait for every response.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing messages. Responses to requests
+    /// that were made before the error are then passed to the wrapper.
+    ///
+    /// # Returns
+    /// The earliest available data point of each security that answered in time, keyed by its
+    /// contract ID.
+    pub async fn req_head_timestamps<S, D>(
+        &mut self,
+        securities: &[S],
+        data: D,
+        regular_trading_hours_only: bool,
+        timeout: std::time::Duration,
+    ) -> Result<std::collections::HashMap<ContractId, chrono::DateTime<chrono::Utc>>, std::io::Error>
+    where
+        S: Security,
+        D: historical_ticks::DataType<S> + Copy,
+    {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut pending = std::collections::HashMap::with_capacity(securities.len());
+        for security in securities {
+            // A message that is only partly written cannot be abandoned, so the timeout is checked
+            // between requests rather than during them.
+            if tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            let sent = match self.register_next_req_id(RequestKind::HeadTimestamp) {
+                Ok(id) => {
+                    self.status.shared.await_response(Awaited::HeadTimestamp, id);
+                    pending.insert(id, security.contract_id());
+                    self.send_head_timestamp(id, security, data, regular_trading_hours_only)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = sent {
+                for id in pending.into_keys() {
+                    self.status.shared.release_response(Awaited::HeadTimestamp, id);
+                    self.status.shared.finish_request(id);
+                }
+                return Err(e);
+            }
+        }
+
+        let mut timestamps = std::collections::HashMap::with_capacity(pending.len());
+        while !pending.is_empty() {
+            match tokio::time::timeout_at(deadline, self.status.rx.recv()).await {
+                Ok(Some(ToClient::HeadTimestamp(id, timestamp))) => {
+                    if let Some(contract_id) = pending.remove(&id) {
+                        timestamps.insert(contract_id, timestamp);
+                    }
+                }
+                Ok(Some(_)) => (),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        for id in pending.into_keys() {
+            self.status.shared.release_response(Awaited::HeadTimestamp, id);
+            self.cancel_head_timestamp(RequestId::new(id)).await?;
+        }
+        Ok(timestamps)
+    }
 
+    #[inline]
+    async fn send_head_timestamp<S, D>(
+        &mut self,
+        id: i64,
+        security: &S,
+        data: D,
+        regular_trading_hours_only: bool,
+    ) -> ReqResult
+    where
+        S: Security,
+        D: historical_ticks::DataType<S>,
+    {
         self.writer.add_body((
             Out::ReqHeadTimestamp,
             id,
@@ -1924,8 +3723,7 @@ impl Client<indicators::Active> {
             data,
             2,
         ))?;
-        self.writer.send().await?;
-        Ok(id)
+        self.writer.send().await
     }
 
     /// Cancel an existing [`Client::req_head_timestamp`] data request.
@@ -1935,9 +3733,14 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_head_timestamp(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_head_timestamp(
+        &mut self,
+        req_id: RequestId<category::HeadTimestamp>,
+    ) -> ReqResult {
         self.writer.add_body((Out::CancelHeadTimestamp, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
     }
 
     /// Request a histogram of historical data.
@@ -1957,11 +3760,11 @@ impl Client<indicators::Active> {
         security: &S,
         regular_trading_hours_only: bool,
         duration: histogram::Duration,
-    ) -> IdResult
+    ) -> RequestIdResult<category::HistogramData>
     where
         S: Security,
     {
-        let id = self.get_next_req_id();
+        let id = self.register_next_req_id(RequestKind::HistogramData)?;
 
         self.writer.add_body((
             Out::ReqHistogramData,
@@ -1972,7 +3775,7 @@ impl Client<indicators::Active> {
             duration,
         ))?;
         self.writer.send().await?;
-        Ok(id)
+        Ok(RequestId::new(id))
     }
 
     /// Cancel an existing [`histogram`] data request.
@@ -1982,14 +3785,22 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_histogram_data(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_histogram_data(
+        &mut self,
+        req_id: RequestId<category::HistogramData>,
+    ) -> ReqResult {
         self.writer.add_body((Out::CancelHistogramData, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
     }
 
     /// Request historical ticks for a given security. See [`historical_ticks`] for
     /// types and traits that are used in this function.
     ///
+    /// IBKR returns at most 1,000 ticks per request. Every tick in a longer period can be
+    /// requested page by page with [`historical_ticks::HistoricalTickIterator`].
+    ///
     /// # Arguments
     /// * `security` - The security for which to request data.
     /// * `timestamp` - The first/last datetime for which data will be returned.
@@ -2014,8 +3825,86 @@ impl Client<indicators::Active> {
         S: Security,
         D: historical_ticks::DataType<S>,
     {
-        let id = self.get_next_req_id();
+        let id = self.register_next_req_id(RequestKind::HistoricalTicks)?;
+        self.send_historical_ticks(
+            id,
+            security,
+            timestamp,
+            number_of_ticks,
+            data,
+            regular_trading_hours_only,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    // Request a single page of historical ticks for `historical_ticks::HistoricalTickIterator`
+    // and wait for it, rather than passing it to the wrapper.
+    pub(crate) async fn req_historical_ticks_page<S, D>(
+        &mut self,
+        security: &S,
+        timestamp: historical_ticks::TimeStamp,
+        number_of_ticks: historical_ticks::NumberOfTicks,
+        data: D,
+        regular_trading_hours_only: bool,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<crate::payload::TickData>, std::io::Error>
+    where
+        S: Security,
+        D: historical_ticks::DataType<S>,
+    {
+        let id = self.register_next_req_id(RequestKind::HistoricalTicks)?;
+        self.status
+            .tx
+            .send(ToWrapper::AwaitHistoricalTicks(id))
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+        self.send_historical_ticks(
+            id,
+            security,
+            timestamp,
+            number_of_ticks,
+            data,
+            regular_trading_hours_only,
+        )
+        .await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            match tokio::time::timeout_at(deadline, self.status.rx.recv()).await {
+                Ok(Some(ToClient::HistoricalTicks(req_id, ticks))) if req_id == id => {
+                    return Ok(ticks);
+                }
+                Ok(Some(_)) => (),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        self.status
+            .tx
+            .send(ToWrapper::ReleaseHistoricalTicks(id))
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+        self.status.shared.finish_request(id);
+        Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("Historical ticks request {id} was not answered in time."),
+        ))
+    }
 
+    #[inline]
+    async fn send_historical_ticks<S, D>(
+        &mut self,
+        id: i64,
+        security: &S,
+        timestamp: historical_ticks::TimeStamp,
+        number_of_ticks: historical_ticks::NumberOfTicks,
+        data: D,
+        regular_trading_hours_only: bool,
+    ) -> ReqResult
+    where
+        S: Security,
+        D: historical_ticks::DataType<S>,
+    {
         self.writer.add_body((
             Out::ReqHistoricalTicks,
             id,
@@ -2028,8 +3917,7 @@ impl Client<indicators::Active> {
             None::<()>,
             None::<()>,
         ))?;
-        self.writer.send().await?;
-        Ok(id)
+        self.writer.send().await
     }
 
     // === Live Market Data ===
@@ -2047,6 +3935,8 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
+    /// Returns a [`crate::limits::LimitExceeded`] error if a streaming request would exceed the
+    /// market data lines set by [`Builder::with_limits`].
     ///
     /// # Returns
     /// The unique ID associated with the request.
@@ -2056,15 +3946,26 @@ impl Client<indicators::Active> {
         additional_data: Vec<D>,
         refresh_type: live_data::RefreshType,
         use_regulatory_snapshot: bool,
-    ) -> IdResult
+    ) -> RequestIdResult<category::MarketData>
     where
         S: Security,
         D: live_data::DataType<S>,
     {
         const VERSION: u8 = 11;
-        let id = self.get_next_req_id();
+        let streaming = refresh_type == live_data::RefreshType::Streaming;
+        if streaming {
+            check_limit(self, Kind::MarketData)?;
+        }
+        if let Some(class) = self.options.market_data_class {
+            self.req_market_data_type(class).await?;
+        }
+        let id = self.register_next_req_id(if streaming {
+            RequestKind::MarketData
+        } else {
+            RequestKind::MarketDataSnapshot
+        })?;
 
-        self.writer.add_body((
+        let body = (
             Out::ReqMktData,
             VERSION,
             id,
@@ -2074,9 +3975,24 @@ impl Client<indicators::Active> {
             refresh_type,
             use_regulatory_snapshot,
             None::<()>,
-        ))?;
-        self.writer.send().await?;
-        Ok(id)
+        );
+        let retry = self
+            .options
+            .delayed_data_retry
+            .then(|| crate::comm::fields(&body))
+            .transpose()?;
+        self.writer.add_body(body)?;
+        if let Some(fields) = retry {
+            self.status.shared.cache_retry(id, fields);
+        }
+        if let Err(e) = self.writer.send().await {
+            self.status.shared.take_retry(id);
+            return Err(e);
+        }
+        if streaming {
+            self.status.limits.track(Kind::MarketData, id);
+        }
+        Ok(RequestId::new(id))
     }
 
     /// Cancel an open streaming data connection with a given `req_id`.
@@ -2086,12 +4002,85 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_market_data(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_market_data(
+        &mut self,
+        req_id: RequestId<category::MarketData>,
+    ) -> ReqResult {
         const VERSION: u8 = 2;
 
         self.writer
             .add_body((Out::CancelMktData, VERSION, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        self.status.limits.release(Kind::MarketData, req_id.get());
+        Ok(())
+    }
+
+    /// Request live data for each of several securities, such as the securities of a watchlist.
+    ///
+    /// Each security is requested as if by [`Client::req_market_data`], without a regulatory
+    /// snapshot, and a security that appears more than once is only requested once. If any
+    /// request fails, the subscriptions made so far are cancelled before the error is returned.
+    ///
+    /// # Arguments
+    /// * `securities` - The securities for which to request data.
+    /// * `additional_data` - The type of data to return for every security (`RealTimeVolume`,
+    ///   `MarkPrice`, etc.).
+    /// * `refresh_type` - How often to refresh the data (a one-time snapshot or a continuous
+    ///   streaming connection)
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Client::req_market_data`], including a
+    /// [`crate::limits::LimitExceeded`] error if the subscriptions would exceed the market data
+    /// lines set by [`Builder::with_limits`].
+    ///
+    /// # Returns
+    /// A [`live_data::Watchlist`] that maps each contract ID to the ID of its request.
+    pub async fn req_market_data_bulk<S, D>(
+        &mut self,
+        securities: &[S],
+        additional_data: &[D],
+        refresh_type: live_data::RefreshType,
+    ) -> Result<live_data::Watchlist, std::io::Error>
+    where
+        S: Security,
+        D: live_data::DataType<S> + Clone,
+    {
+        let mut watchlist = live_data::Watchlist::default();
+        for security in securities {
+            let contract_id = security.contract_id();
+            if watchlist.req_id(contract_id).is_some() {
+                continue;
+            }
+            let id = match self
+                .req_market_data(security, additional_data.to_vec(), refresh_type, false)
+                .await
+            {
+                Ok(id) => id,
+                Err(e) => {
+                    if refresh_type == live_data::RefreshType::Streaming {
+                        self.cancel_market_data_bulk(watchlist).await?;
+                    }
+                    return Err(e);
+                }
+            };
+            watchlist.insert(contract_id, id);
+        }
+        Ok(watchlist)
+    }
+
+    /// Cancel every subscription made by [`Client::req_market_data_bulk`].
+    ///
+    /// # Arguments
+    /// * `watchlist` - The subscriptions to cancel.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing messages.
+    pub async fn cancel_market_data_bulk(&mut self, watchlist: live_data::Watchlist) -> ReqResult {
+        for (_, req_id) in watchlist.iter() {
+            self.cancel_market_data(req_id).await?;
+        }
+        Ok(())
     }
 
     /// Set the market data variant for all succeeding `Client::req_market_data` requests.
@@ -2103,6 +4092,8 @@ impl Client<indicators::Active> {
     /// Returns any error encountered while writing the outgoing message.
     pub async fn req_market_data_type(&mut self, variant: live_data::Class) -> ReqResult {
         const VERSION: u8 = 1;
+        // Once a class has been chosen, the session default is no longer requested.
+        self.options.market_data_class = None;
 
         self.writer
             .add_body((Out::ReqMarketDataType, VERSION, variant))?;
@@ -2127,13 +4118,13 @@ impl Client<indicators::Active> {
         security: &S,
         data: D,
         regular_trading_hours_only: bool,
-    ) -> IdResult
+    ) -> RequestIdResult<category::RealTimeBars>
     where
         S: Security,
         D: live_bar::DataType<S>,
     {
         const VERSION: u8 = 3;
-        let id = self.get_next_req_id();
+        let id = self.register_next_req_id(RequestKind::RealTimeBars)?;
 
         self.writer.add_body((
             Out::ReqRealTimeBars,
@@ -2146,7 +4137,7 @@ impl Client<indicators::Active> {
             None::<()>,
         ))?;
         self.writer.send().await?;
-        Ok(id)
+        Ok(RequestId::new(id))
     }
 
     /// Cancel an existing real-time bars subscription.
@@ -2156,12 +4147,17 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_real_time_bars(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_real_time_bars(
+        &mut self,
+        req_id: RequestId<category::RealTimeBars>,
+    ) -> ReqResult {
         const VERSION: u8 = 1;
 
         self.writer
             .add_body((Out::CancelRealTimeBars, VERSION, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
     }
 
     // === Live Tick-by-Tick Data ===
@@ -2172,11 +4168,15 @@ impl Client<indicators::Active> {
     /// * `security` - The security for which to request data.
     /// * `tick_data` - The type of data to return.
     /// * `number_of_historical_ticks` - The number of historical ticks to return before the live
-    ///   data.
+    ///   data. These are received in a single batch by
+    ///   [`crate::wrapper::LocalWrapper::tick_by_tick_history`], and every tick after it by
+    ///   [`crate::wrapper::LocalWrapper::live_tick`].
     /// * `ignore_size` - Ignore the size parameter in the returned ticks when set to [`true`].
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
+    /// Returns a [`crate::limits::LimitExceeded`] error if the request would exceed the tick-by-tick
+    /// streams set by [`Builder::with_limits`].
     ///
     /// # Returns
     /// The unique ID associated with the request.
@@ -2186,13 +4186,21 @@ impl Client<indicators::Active> {
         tick_data: D,
         number_of_historical_ticks: live_ticks::NumberOfTicks,
         ignore_size: bool,
-    ) -> IdResult
+    ) -> RequestIdResult<category::TickByTick>
     where
         S: Security,
         D: live_ticks::DataType<S>,
     {
-        let id = self.get_next_req_id();
+        check_limit(self, Kind::TickByTick)?;
+        let id = self.register_next_req_id(RequestKind::TickByTick)?;
 
+        if number_of_historical_ticks != live_ticks::NumberOfTicks::new(0) {
+            self.status
+                .tx
+                .send(ToWrapper::TickByTickHistory(id))
+                .await
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+        }
         self.writer.add_body((
             Out::ReqTickByTickData,
             id,
@@ -2202,7 +4210,8 @@ impl Client<indicators::Active> {
             ignore_size,
         ))?;
         self.writer.send().await?;
-        Ok(id)
+        self.status.limits.track(Kind::TickByTick, id);
+        Ok(RequestId::new(id))
     }
 
     /// Cancel an existing tick-by-tick data subscription.
@@ -2212,23 +4221,38 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_tick_by_tick_data(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_tick_by_tick_data(
+        &mut self,
+        req_id: RequestId<category::TickByTick>,
+    ) -> ReqResult {
         self.writer.add_body((Out::CancelTickByTickData, req_id))?;
-        self.writer.send().await
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        self.status.limits.release(Kind::TickByTick, req_id.get());
+        Ok(())
     }
 
     // === Market Depth ===
 
+    #[cfg(feature = "depth")]
     /// Request market depth data for a given security.
     ///
     /// # Arguments
     /// * `security` - The security for which to return the market depth data.
     /// * `number_of_rows` - The maximum number of rows in the returned limit order book.
-    /// * `smart_depth` - When `true`, return the [`crate::exchange::Primary`] exchange holding the
-    ///      order, otherwise return the [`crate::payload::market_depth::Mpid`] associated with each entry.
+    /// * `smart_depth` - When `true`, return the aggregate book of the exchanges that make up
+    ///   SMART, along with the [`crate::exchange::Primary`] exchange holding each order. Otherwise,
+    ///   return the book of a single exchange, along with the
+    ///   [`crate::payload::market_depth::Mpid`] associated with each entry.
+    /// * `exchange` - The exchange whose book to return, in place of the exchange of `security`.
+    ///   When [`None`], the exchange of `security` is used.
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
+    /// Returns an error of kind [`std::io::ErrorKind::InvalidInput`] if `smart_depth` is `true`
+    /// and an `exchange` is given, since the SMART book spans every exchange.
+    /// Returns a [`crate::limits::LimitExceeded`] error if the request would exceed the depth
+    /// subscriptions set by [`Builder::with_limits`].
     ///
     /// # Returns
     /// The unique ID associated with the request.
@@ -2237,26 +4261,45 @@ impl Client<indicators::Active> {
         security: &S,
         number_of_rows: u32,
         smart_depth: bool,
-    ) -> IdResult
+        exchange: Option<crate::exchange::Primary>,
+    ) -> RequestIdResult<category::MarketDepth>
     where
         S: Security,
     {
         const VERSION: u8 = 5;
-        let id = self.get_next_req_id();
+        if smart_depth {
+            if let Some(exchange) = exchange {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("SMART depth cannot be requested from a single exchange: {exchange}"),
+                ));
+            }
+        }
+        check_limit(self, Kind::Depth)?;
+        let id = self.register_next_req_id(RequestKind::MarketDepth)?;
 
+        let mut contract = security.as_out_msg();
+        if let Some(exchange) = exchange {
+            contract.exchange = Routing::Primary(exchange);
+        }
         self.writer.add_body((
             Out::ReqMktDepth,
             VERSION,
             id,
-            security.as_out_msg(),
+            contract,
             number_of_rows,
             smart_depth,
             None::<()>,
         ))?;
         self.writer.send().await?;
-        Ok(id)
+        self.status.limits.track(Kind::Depth, id);
+        if smart_depth {
+            self.status.smart_depth.insert(id);
+        }
+        Ok(RequestId::new(id))
     }
 
+    #[cfg(feature = "depth")]
     /// Request exchanges available for market depth.
     ///
     /// # Errors
@@ -2266,6 +4309,7 @@ impl Client<indicators::Active> {
         self.writer.send().await
     }
 
+    #[cfg(feature = "depth")]
     /// Cancel a market depth subscription for a given `req_id`.
     ///
     /// # Arguments
@@ -2273,32 +4317,227 @@ impl Client<indicators::Active> {
     ///
     /// # Errors
     /// Returns any error encountered while writing the outgoing message.
-    pub async fn cancel_market_depth(&mut self, req_id: i64) -> ReqResult {
+    pub async fn cancel_market_depth(
+        &mut self,
+        req_id: RequestId<category::MarketDepth>,
+    ) -> ReqResult {
         const VERSION: u8 = 1;
 
+        // The cancellation of a SMART depth subscription must say so.
+        let smart_depth = self.status.smart_depth.contains(&req_id.get());
         self.writer
-            .add_body((Out::CancelMktDepth, VERSION, req_id))?;
-        self.writer.send().await
+            .add_body((Out::CancelMktDepth, VERSION, req_id, smart_depth))?;
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        self.status.limits.release(Kind::Depth, req_id.get());
+        self.status.smart_depth.remove(&req_id.get());
+        Ok(())
     }
 
     /// Request exchanges comprising the aggregate SMART exchange
     ///
     /// # Arguments
-    /// * `exchange_id` - The identifier containing information about the component exchanges, which
-    ///   is attained from an initial market data callback.
+    /// * `exchange_id` - The identifier containing information about the component exchanges, which
+    ///   is attained from an initial market data callback.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_smart_components(&mut self, exchange_id: ExchangeId) -> IdResult {
+        let id = self.register_next_req_id(RequestKind::SmartComponents)?;
+
+        self.writer
+            .add_body((Out::ReqSmartComponents, id, exchange_id))?;
+        self.writer.send().await?;
+        Ok(id)
+    }
+
+    // === Market scanners ===
+
+    #[cfg(feature = "scanner")]
+    /// Request the XML document that lists the instruments, location codes, scan codes, and
+    /// filters available to market scanners. The document is delivered to
+    /// [`crate::wrapper::LocalWrapper::scanner_parameters`] and can be parsed with
+    /// [`crate::scanner::Parameters::parse`].
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    pub async fn req_scanner_parameters(&mut self) -> ReqResult {
+        const VERSION: u8 = 1;
+
+        self.writer.add_body((Out::ReqScannerParameters, VERSION))?;
+        self.writer.send().await
+    }
+
+    #[cfg(feature = "scanner")]
+    /// Subscribe to a market scanner. Each scan is delivered to
+    /// [`crate::wrapper::LocalWrapper::scanner_data`].
+    ///
+    /// # Arguments
+    /// * `subscription` - The instrument, location code, scan code, and filters of the scanner.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_scanner_subscription(
+        &mut self,
+        subscription: &crate::scanner::Subscription,
+    ) -> RequestIdResult<category::ScannerSubscription> {
+        let id = self.register_next_req_id(RequestKind::ScannerSubscription)?;
+
+        self.writer.add_body((
+            Out::ReqScannerSubscription,
+            id,
+            subscription.as_out_msg(),
+        ))?;
+        self.writer.send().await?;
+        Ok(RequestId::new(id))
+    }
+
+    #[cfg(feature = "scanner")]
+    /// Cancel a market scanner subscription for a given `req_id`.
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the [`Client::req_scanner_subscription`] subscription to cancel.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    pub async fn cancel_scanner_subscription(
+        &mut self,
+        req_id: RequestId<category::ScannerSubscription>,
+    ) -> ReqResult {
+        const VERSION: u8 = 1;
+
+        self.writer
+            .add_body((Out::CancelScannerSubscription, VERSION, req_id))?;
+        self.writer.send().await?;
+        self.status.shared.finish_request(req_id.get());
+        Ok(())
+    }
+
+    // === News ===
+
+    #[cfg(feature = "news")]
+    /// Request the historical headlines about a contract. Each headline is delivered to
+    /// [`crate::wrapper::LocalWrapper::historical_news`], followed by a call to
+    /// [`crate::wrapper::LocalWrapper::historical_news_end`] that indicates whether older
+    /// headlines are available.
+    ///
+    /// IBKR returns at most [`crate::news::MAX_HEADLINES`] headlines per request. Older headlines
+    /// can be requested with [`crate::news::Query::next_page`], or all headlines since a given
+    /// time with [`Client::req_historical_news_until`].
+    ///
+    /// # Arguments
+    /// * `query` - The contract, providers, and period of the headlines.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_historical_news(&mut self, query: &crate::news::Query) -> IdResult {
+        let id = self.register_next_req_id(RequestKind::HistoricalNews)?;
+        self.send_historical_news(id, query).await?;
+        Ok(id)
+    }
+
+    #[cfg(feature = "news")]
+    /// Request every historical headline about a contract that was published after `since`, and
+    /// wait for the responses.
+    ///
+    /// The headlines are requested page by page, continuing each request from the earliest
+    /// headline of the previous one, until IBKR reports that no older headlines are available or
+    /// `since` is reached. Unlike [`Client::req_historical_news`], the headlines are not passed to
+    /// [`crate::wrapper::LocalWrapper::historical_news`].
+    ///
+    /// # Arguments
+    /// * `query` - The contract, providers, and end of the headlines. Any start of the query is
+    ///   replaced by `since`.
+    /// * `since` - The exclusive start of the requested period.
+    /// * `timeout` - How long to wait for each page.
     ///
     /// # Errors
-    /// Returns any error encountered while writing the outgoing message.
+    /// Returns any error encountered while writing the outgoing messages or communicating with the
+    /// loop that decodes incoming messages. Returns an error of kind
+    /// [`std::io::ErrorKind::TimedOut`] if a page is not received within `timeout` (ex. because
+    /// IBKR reported an error for it).
     ///
     /// # Returns
-    /// The unique ID associated with the request.
-    pub async fn req_smart_components(&mut self, exchange_id: ExchangeId) -> IdResult {
-        let id = self.get_next_req_id();
+    /// The headlines, starting with the most recent. Each article appears once.
+    pub async fn req_historical_news_until(
+        &mut self,
+        query: &crate::news::Query,
+        since: chrono::DateTime<chrono::Utc>,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<crate::payload::Headline>, std::io::Error> {
+        let mut query = query.clone().with_start(since);
+        let mut headlines = Vec::new();
+        let mut article_ids = std::collections::HashSet::new();
+        loop {
+            let id = self.register_next_req_id(RequestKind::HistoricalNews)?;
+            self.status
+                .tx
+                .send(ToWrapper::AwaitHistoricalNews(id))
+                .await
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+            self.send_historical_news(id, &query).await?;
+
+            let deadline = tokio::time::Instant::now() + timeout;
+            let mut page = Vec::new();
+            let has_more = loop {
+                match tokio::time::timeout_at(deadline, self.status.rx.recv()).await {
+                    Ok(Some(ToClient::HistoricalNews(req_id, headline))) if req_id == id => {
+                        page.push(headline);
+                    }
+                    Ok(Some(ToClient::HistoricalNewsEnd(req_id, has_more))) if req_id == id => {
+                        break Some(has_more);
+                    }
+                    Ok(Some(_)) => (),
+                    Ok(None) | Err(_) => break None,
+                }
+            };
+            let Some(has_more) = has_more else {
+                self.status
+                    .tx
+                    .send(ToWrapper::ReleaseHistoricalNews(id))
+                    .await
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+                self.status.shared.finish_request(id);
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("Historical news request {id} was not answered in time."),
+                ));
+            };
+
+            let next = query.next_page(&page);
+            let mut progress = false;
+            for headline in page {
+                if article_ids.insert(headline.article_id.clone()) {
+                    headlines.push(headline);
+                    progress = true;
+                }
+            }
+            match next {
+                // A page made up of headlines that were already received would be requested
+                // again and again.
+                Some(next) if has_more && progress => query = next,
+                _ => break,
+            }
+        }
+        headlines.sort_by_key(|headline| std::cmp::Reverse(headline.time));
+        Ok(headlines)
+    }
 
+    #[cfg(feature = "news")]
+    #[inline]
+    async fn send_historical_news(&mut self, id: i64, query: &crate::news::Query) -> ReqResult {
         self.writer
-            .add_body((Out::ReqSmartComponents, id, exchange_id))?;
-        self.writer.send().await?;
-        Ok(id)
+            .add_body((Out::ReqHistoricalNews, id, query.as_out_msg()))?;
+        self.writer.send().await
     }
 
     // === Orders and order management ===
@@ -2310,27 +4549,35 @@ impl Client<indicators::Active> {
     /// * `order` - The order to execute.
     ///
     /// # Errors
-    /// Returns any error encountered while writing the outgoing message.
+    /// Returns [`PlaceOrderError::Io`] if an error is encountered while writing the outgoing
+    /// message. If the client was built with [`Builder::with_order_validation`], also returns
+    /// [`PlaceOrderError::Validation`] if the order fails validation, in which case nothing is
+    /// sent.
     ///
     /// # Returns
-    /// The unique ID associated with the request.
-    pub async fn req_place_order<S, E>(&mut self, order: &Order<'_, S, E>) -> IdResult
+    /// A [`PlacedOrder`] that follows the order's status as it is received.
+    pub async fn req_place_order<S, E>(
+        &mut self,
+        order: &Order<'_, S, E>,
+    ) -> Result<PlacedOrder<crate::order::state::Submitted>, PlaceOrderError>
     where
         S: Security,
         E: Executable<S>,
     {
+        check_order(self, order)?;
         let id = self.get_next_order_id();
+        let status = self.status.shared.track_order(id);
 
-        self.writer.add_body((
-            Out::PlaceOrder,
-            id,
-            order.get_security().as_out_msg(),
-            None::<()>,
-            None::<()>,
-            order,
-        ))?;
-        self.writer.send().await?;
-        Ok(id)
+        let sent = async {
+            self.writer.add_body(order.as_out_msg(id))?;
+            self.writer.send().await
+        }
+        .await;
+        if let Err(e) = sent {
+            self.status.shared.untrack_order(id);
+            return Err(e.into());
+        }
+        Ok(PlacedOrder::new(id, status))
     }
 
     /// Modify an order.
@@ -2341,23 +4588,25 @@ impl Client<indicators::Active> {
     /// * `id` - The original order's ID.
     ///
     /// # Errors
-    /// Returns any error encountered while writing the outgoing message.
+    /// Returns [`PlaceOrderError::Io`] if an error is encountered while writing the outgoing
+    /// message. If the client was built with [`Builder::with_order_validation`], also returns
+    /// [`PlaceOrderError::Validation`] if the order fails validation, in which case nothing is
+    /// sent.
     ///
     /// # Returns
     /// The unique ID associated with the request.
-    pub async fn req_modify_order<S, E>(&mut self, order: &Order<'_, S, E>, id: i64) -> IdResult
+    pub async fn req_modify_order<S, E>(
+        &mut self,
+        order: &Order<'_, S, E>,
+        id: i64,
+    ) -> Result<i64, PlaceOrderError>
     where
         S: Security,
         E: Executable<S>,
     {
-        self.writer.add_body((
-            Out::PlaceOrder,
-            id,
-            order.get_security().as_out_msg(),
-            None::<()>,
-            None::<()>,
-            order,
-        ))?;
+        check_order(self, order)?;
+
+        self.writer.add_body(order.as_out_msg(id))?;
         self.writer.send().await?;
         Ok(id)
     }
@@ -2431,6 +4680,62 @@ impl Client<indicators::Active> {
         self.writer.send().await
     }
 
+    /// Exercise options or let them lapse.
+    ///
+    /// # Arguments
+    /// * `option` - The option to exercise or let lapse.
+    /// * `action` - Whether to exercise the options or let them lapse.
+    /// * `quantity` - The number of contracts.
+    /// * `account_number` - The account holding the options.
+    /// * `override_action` - When [`true`], the action is taken even if it disagrees with IBKR's
+    ///   automatic exercise rules (ex. exercising an out-of-the-money option at expiration).
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message. Also returns an error if
+    /// `account_number` is not one of the client's managed accounts.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_exercise_options(
+        &mut self,
+        option: &SecOption,
+        action: ExerciseAction,
+        quantity: u32,
+        account_number: &str,
+        override_action: bool,
+    ) -> IdResult {
+        const VERSION: u8 = 2;
+        check_valid_account(self, account_number)?;
+        let id = self.get_next_req_id();
+
+        self.writer.add_body((
+            Out::ExerciseOptions,
+            VERSION,
+            id,
+            (
+                option.contract_id(),
+                option.symbol(),
+                option.contract_type(),
+                option.expiration_date().format("%Y%m%d").to_string(),
+                option.strike(),
+                char::from(option.class()),
+                option.multiplier(),
+                option.exchange(),
+                option.currency(),
+                option.local_symbol(),
+                option.trading_class(),
+            ),
+            action,
+            quantity,
+            account_number,
+            override_action,
+            // The manual order time, which is only used for orders placed by a human operator.
+            None::<()>,
+        ))?;
+        self.writer.send().await?;
+        Ok(id)
+    }
+
     // === Executions ===
 
     /// Request execution all execution reports that fit the criteria specified in the `filter`.
@@ -2445,7 +4750,7 @@ impl Client<indicators::Active> {
     /// Returns any error encountered while writing the outgoing message.
     pub async fn req_executions(&mut self, filter: Filter) -> IdResult {
         const VERSION: u8 = 3;
-        let req_id = self.get_next_req_id();
+        let req_id = self.register_next_req_id(RequestKind::Executions)?;
 
         self.writer
             .add_body((Out::ReqExecutions, VERSION, req_id, filter))?;
@@ -2455,15 +4760,98 @@ impl Client<indicators::Active> {
 
     // === Contract Creation ===
 
+    /// Request the expirations and strikes of every option on an underlying. One
+    /// [`crate::payload::OptionParameters`] for each exchange and trading class is delivered to
+    /// [`crate::wrapper::LocalWrapper::option_parameters`], followed by a call to
+    /// [`crate::wrapper::LocalWrapper::option_parameters_end`].
+    ///
+    /// To create the options themselves, see [`crate::contract::option_chain`].
+    ///
+    /// # Arguments
+    /// * `underlying` - The security on which the options are written.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing message.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    pub async fn req_option_parameters<S: Security>(&mut self, underlying: &S) -> IdResult {
+        let id = self.register_next_req_id(RequestKind::OptionParameters)?;
+        self.send_option_parameters(id, underlying).await?;
+        Ok(id)
+    }
+
     #[inline]
-    pub(crate) async fn send_contract_query(&mut self, query: Query) -> Result<(), std::io::Error> {
-        const VERSION: u8 = 8;
-        let req_id = self.get_next_req_id();
+    async fn send_option_parameters<S: Security>(&mut self, id: i64, underlying: &S) -> ReqResult {
+        let underlying = underlying.as_out_msg();
+        self.writer.add_body((
+            Out::ReqSecDefOptParams,
+            id,
+            underlying.symbol,
+            None::<()>,
+            underlying.security_type,
+            underlying.contract_id,
+        ))?;
+        self.writer.send().await
+    }
+
+    // Request the option parameters of an underlying and wait for every response. The parameters
+    // are not passed to the wrapper.
+    pub(crate) async fn recv_option_parameters<S: Security>(
+        &mut self,
+        underlying: &S,
+        timeout: std::time::Duration,
+    ) -> Result<Vec<crate::payload::OptionParameters>, std::io::Error> {
+        let id = self.register_next_req_id(RequestKind::OptionParameters)?;
         self.status
             .tx
-            .send(ToWrapper::ContractQuery((query, req_id)))
+            .send(ToWrapper::AwaitOptionParameters(id))
             .await
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+        self.send_option_parameters(id, underlying).await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut params = Vec::new();
+        loop {
+            match tokio::time::timeout_at(deadline, self.status.rx.recv()).await {
+                Ok(Some(ToClient::OptionParameters(req_id, p))) if req_id == id => params.push(p),
+                Ok(Some(ToClient::OptionParametersEnd(req_id))) if req_id == id => {
+                    break Ok(params);
+                }
+                Ok(Some(_)) => (),
+                Ok(None) | Err(_) => {
+                    self.status
+                        .tx
+                        .send(ToWrapper::ReleaseOptionParameters(id))
+                        .await
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+                    self.status.shared.finish_request(id);
+                    break Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!("Option parameters request {id} was not answered in time."),
+                    ));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) async fn send_contract_query(&mut self, query: Query) -> IdResult {
+        let req_id = self.register_next_req_id(RequestKind::ContractData)?;
+        // The contracts are awaited before the query is sent, so that none of them reach the
+        // wrapper instead of `recv_contract_query`.
+        self.status.shared.await_response(Awaited::ContractData, req_id);
+        if let Err(e) = self.write_contract_query(req_id, query).await {
+            self.status.shared.release_response(Awaited::ContractData, req_id);
+            self.status.shared.finish_request(req_id);
+            return Err(e);
+        }
+        Ok(req_id)
+    }
+
+    #[inline]
+    async fn write_contract_query(&mut self, req_id: i64, query: Query) -> ReqResult {
+        const VERSION: u8 = 8;
 
         match query {
             Query::IbContractId(contract_id, routing) => {
@@ -2490,18 +4878,62 @@ impl Client<indicators::Active> {
                     None::<()>,
                 ))?;
             }
+            Query::Specification {
+                symbol,
+                contract_type,
+                exchange,
+                currency,
+                expiration_date,
+                strike,
+                right,
+            } => {
+                self.writer.add_body((
+                    Out::ReqContractData,
+                    VERSION,
+                    req_id,
+                    None::<()>,
+                    symbol,
+                    contract_type,
+                    expiration_date.map(|d| d.format("%Y%m%d").to_string()),
+                    strike,
+                    right.map(char::from),
+                    None::<()>,
+                    exchange,
+                    None::<()>,
+                    currency,
+                    [None::<()>; 6],
+                ))?;
+            }
         }
 
-        self.writer.send().await?;
-        Ok(())
+        self.writer.send().await
     }
 
     #[inline]
-    pub(crate) async fn recv_contract_query(&mut self) -> Option<crate::contract::Contract> {
-        if let Some(ToClient::NewContract(c)) = self.status.rx.recv().await {
-            Some(c)
-        } else {
-            None
+    pub(crate) async fn recv_contract_query(
+        &mut self,
+        req_id: i64,
+    ) -> Option<Vec<Result<crate::contract::Contract, crate::contract::ContractError>>> {
+        let mut responses = Vec::new();
+        loop {
+            // Responses to an earlier query that was abandoned before its end message arrived are
+            // discarded.
+            let Some(response) = self.status.rx.recv().await else {
+                self.status.shared.release_response(Awaited::ContractData, req_id);
+                return None;
+            };
+            match response {
+                ToClient::ContractData(id, response) if id == req_id => responses.push(response),
+                ToClient::ContractEnd(id) if id == req_id => break Some(responses),
+                ToClient::ContractData(..)
+                | ToClient::ContractEnd(_)
+                | ToClient::HeadTimestamp(..)
+                | ToClient::OptionParameters(..)
+                | ToClient::OptionParametersEnd(_)
+                | ToClient::HistoricalTicks(..) => (),
+                #[cfg(feature = "news")]
+                ToClient::HistoricalNews(..) | ToClient::HistoricalNewsEnd(..) => (),
+            }
         }
     }
 
@@ -2515,25 +4947,216 @@ impl Client<indicators::Active> {
     /// Returns any error encountered while flushing and shutting down the outgoing buffer.
     ///
     /// # Returns
-    /// A [`Builder`] with the same port and address as the existing client.
+    /// A [`Builder`] with the same port, address, and [`MessageTap`] as the existing client.
     pub async fn disconnect(mut self) -> Result<Builder, std::io::Error> {
         self.writer.flush().await?;
         self.writer.shutdown().await?;
         self.status.disconnect.cancel();
         self.status.r_thread.await?;
-        Ok(Builder(Inner::Manual {
-            port: self.port,
-            address: self.address,
-        }))
+        Ok(Builder(
+            Inner::Manual {
+                port: self.port,
+                address: self.address,
+            },
+            self.options,
+        ))
     }
 }
 
+#[derive(Debug)]
+/// Manages several [`Client`]s, each with its own client ID, that connect to the same IBKR
+/// platform and are shut down together.
+///
+/// IBKR applies several limits (ex. message rates and market data lines) per connection, so heavy
+/// users often split their work across clients, for instance one client with ID 0 for orders and
+/// others for market data. Every client in a session is created with
+/// [`Client::disaggregated`] from the same [`Builder`].
+///
+/// A [`crate::contract::Cache`] installed on the builder is shared by every client: it is moved
+/// into whichever client is borrowed with [`Session::client_mut`], so contracts received by one
+/// client are available to the others.
+pub struct Session {
+    builder: Builder,
+    clients: std::collections::BTreeMap<i64, Client<indicators::Active>>,
+    contract_cache: Option<crate::contract::Cache>,
+    cache_holder: Option<i64>,
+}
+
+impl Session {
+    #[must_use]
+    #[inline]
+    /// Creates a new [`Session`] without any connected clients.
+    ///
+    /// # Arguments
+    /// * `builder` - The builder from which every client in the session is connected.
+    pub fn new(mut builder: Builder) -> Self {
+        let contract_cache = builder.1.contract_cache.take();
+        Self {
+            builder,
+            clients: std::collections::BTreeMap::new(),
+            contract_cache,
+            cache_holder: None,
+        }
+    }
+
+    /// Connects a new client and starts its message loop.
+    ///
+    /// # Arguments
+    /// * `client_id` - A unique ID for IBKR's systems to distinguish between clients
+    /// * `wrapper` - A [`Wrapper`] that defines how incoming data for this client should be
+    ///   handled.
+    ///
+    /// # Errors
+    /// Returns [`ConnectionError::DuplicateClientId`] if the session already has a client with
+    /// `client_id`, as well as any error returned by [`Builder::connect`].
+    ///
+    /// # Returns
+    /// The newly connected client.
+    pub async fn connect<W: Wrapper + Send + 'static>(
+        &mut self,
+        client_id: i64,
+        wrapper: W,
+    ) -> Result<&mut Client<indicators::Active>, ConnectionError> {
+        if self.clients.contains_key(&client_id) {
+            return Err(ConnectionError::DuplicateClientId(client_id));
+        }
+        let mut client = self
+            .builder
+            .connect(client_id)
+            .await?
+            .disaggregated(wrapper)
+            .await;
+        client.options.contract_cache = self.reclaim_contract_cache();
+        self.cache_holder = Some(client_id);
+        Ok(self.clients.entry(client_id).or_insert(client))
+    }
+
+    #[inline]
+    /// Return the IDs of every connected client, in ascending order.
+    pub fn client_ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.clients.keys().copied()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the client with ID `client_id`, if it is connected. The client may not hold the
+    /// shared contract cache; use [`Session::client_mut`] or [`Session::get_contract_cache`]
+    /// instead.
+    pub fn client(&self, client_id: i64) -> Option<&Client<indicators::Active>> {
+        self.clients.get(&client_id)
+    }
+
+    /// Return the client with ID `client_id`, if it is connected, after moving the shared contract
+    /// cache into it.
+    pub fn client_mut(&mut self, client_id: i64) -> Option<&mut Client<indicators::Active>> {
+        if self.cache_holder != Some(client_id) && self.clients.contains_key(&client_id) {
+            let cache = self.reclaim_contract_cache();
+            if let Some(client) = self.clients.get_mut(&client_id) {
+                client.options.contract_cache = cache;
+                self.cache_holder = Some(client_id);
+            }
+        }
+        self.clients.get_mut(&client_id)
+    }
+
+    #[must_use]
+    /// Return the contract cache shared by every client in the session, if any.
+    pub fn get_contract_cache(&self) -> Option<&crate::contract::Cache> {
+        match self.cache_holder.and_then(|id| self.clients.get(&id)) {
+            Some(client) => client.get_contract_cache(),
+            None => self.contract_cache.as_ref(),
+        }
+    }
+
+    fn reclaim_contract_cache(&mut self) -> Option<crate::contract::Cache> {
+        match self
+            .cache_holder
+            .take()
+            .and_then(|id| self.clients.get_mut(&id))
+        {
+            Some(client) => client.options.contract_cache.take(),
+            None => self.contract_cache.take(),
+        }
+    }
+
+    /// Disconnects a single client, leaving every other client connected.
+    ///
+    /// # Arguments
+    /// * `client_id` - The ID of the client to disconnect.
+    ///
+    /// # Errors
+    /// Returns any error encountered by [`Client::disconnect`].
+    ///
+    /// # Returns
+    /// `true` if a client with `client_id` was connected.
+    pub async fn disconnect_client(&mut self, client_id: i64) -> Result<bool, std::io::Error> {
+        if self.cache_holder == Some(client_id) {
+            self.contract_cache = self.reclaim_contract_cache();
+        }
+        match self.clients.remove(&client_id) {
+            Some(client) => client.disconnect().await.map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Disconnects every client in the session. Each client is disconnected even if an earlier
+    /// one fails.
+    ///
+    /// # Errors
+    /// Returns the first error encountered by [`Client::disconnect`].
+    ///
+    /// # Returns
+    /// The [`Builder`] from which the session was created, with the shared contract cache
+    /// installed, so that the session can be recreated if necessary.
+    pub async fn disconnect(mut self) -> Result<Builder, std::io::Error> {
+        let contract_cache = self.reclaim_contract_cache();
+        let mut result = Ok(());
+        for (_, client) in std::mem::take(&mut self.clients) {
+            if let Err(e) = client.disconnect().await {
+                result = result.and(Err(e));
+            }
+        }
+        result?;
+        self.builder.1.contract_cache = contract_cache;
+        Ok(self.builder)
+    }
+}
+
+#[inline]
+fn check_limit(client: &Client<indicators::Active>, kind: Kind) -> Result<(), std::io::Error> {
+    client
+        .status
+        .limits
+        .check(kind)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::QuotaExceeded, e))
+}
+
+#[inline]
+fn check_order<S, E>(
+    client: &Client<indicators::Active>,
+    order: &Order<'_, S, E>,
+) -> Result<(), crate::order::OrderValidationError>
+where
+    S: Security,
+    E: Executable<S>,
+{
+    if client.options.validate_orders {
+        order.validate()?;
+    }
+    Ok(())
+}
+
 #[inline]
 fn check_valid_account(
     client: &Client<indicators::Active>,
     account_number: &str,
 ) -> Result<(), std::io::Error> {
-    if client.status.managed_accounts.contains(account_number) {
+    if client
+        .status
+        .shared
+        .managed_accounts()
+        .contains(account_number)
+    {
         Ok(())
     } else {
         Err(std::io::Error::new(
@@ -2542,3 +5165,357 @@ fn check_valid_account(
         ))
     }
 }
+
+/// Contains a synchronous facade over an [`ActiveClient`], for applications that do not run an
+/// async runtime (ex. GUI tools or bindings to other languages).
+///
+/// A [`blocking::Client`] owns a multithreaded runtime on which the message loop runs. Its
+/// wrapper passes callbacks to the thread that owns the client as events of any type `E`, which
+/// are consumed with [`blocking::Client::events`] and similar iterators.
+///
+/// Only the most common requests have a blocking method: the current time, account updates and
+/// positions, live market data (including its type and bulk watchlists), historical bars, order
+/// placement, modification and cancellation, open orders, and executions. Every other request of
+/// [`ActiveClient`] (ex. contract details, historical ticks, market depth, news, scanners, and
+/// the financial advisor requests) is made with [`blocking::Client::block_on`].
+pub mod blocking {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use crate::contract::Security;
+    use crate::execution::Filter;
+    use crate::market_data::{historical_bar, live_data};
+    use crate::order::{state, Executable, Order, PlacedOrder};
+    use crate::requests::{category, RequestId};
+    use crate::wrapper::Wrapper;
+
+    use super::{ActiveClient, Builder, ConnectionError, PlaceOrderError};
+
+    #[derive(Debug, Clone)]
+    /// The sending end of a [`Client`]'s events, which a wrapper uses to pass its callbacks to the
+    /// thread that owns the [`Client`].
+    pub struct EventSender<E>(mpsc::Sender<E>);
+
+    impl<E> EventSender<E> {
+        #[inline]
+        /// Send an event to the [`Client`].
+        ///
+        /// # Returns
+        /// `false` if the [`Client`] has been dropped, in which case the event is discarded.
+        pub fn send(&self, event: E) -> bool {
+            self.0.send(event).is_ok()
+        }
+    }
+
+    #[derive(Debug)]
+    /// A synchronous client, whose methods block the current thread until the corresponding
+    /// [`ActiveClient`] method completes.
+    ///
+    /// These methods must not be called from within an async runtime.
+    pub struct Client<E> {
+        runtime: tokio::runtime::Runtime,
+        client: ActiveClient,
+        events: mpsc::Receiver<E>,
+    }
+
+    impl<E> Client<E> {
+        /// Connect to IBKR's trading systems and start the message loop.
+        ///
+        /// # Arguments
+        /// * `builder` - The builder with which to connect.
+        /// * `client_id` - A unique ID for IBKR's systems to distinguish between clients.
+        /// * `make_wrapper` - Creates the wrapper, which should pass the callbacks that it cares
+        ///   about to the provided [`EventSender`].
+        ///
+        /// # Errors
+        /// Returns an error if the runtime cannot be created or if any error returned by
+        /// [`Builder::connect`] occurs.
+        pub fn connect<W, F>(
+            builder: &Builder,
+            client_id: i64,
+            make_wrapper: F,
+        ) -> Result<Self, ConnectionError>
+        where
+            W: Wrapper + Send + 'static,
+            F: FnOnce(EventSender<E>) -> W,
+        {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?;
+            let (tx, events) = mpsc::channel();
+            let wrapper = make_wrapper(EventSender(tx));
+            let client = runtime.block_on(async {
+                Ok::<_, ConnectionError>(
+                    builder
+                        .connect(client_id)
+                        .await?
+                        .disaggregated(wrapper)
+                        .await,
+                )
+            })?;
+            Ok(Self {
+                runtime,
+                client,
+                events,
+            })
+        }
+
+        #[inline]
+        #[must_use]
+        /// Get a reference to the underlying [`ActiveClient`].
+        pub fn as_async(&self) -> &ActiveClient {
+            &self.client
+        }
+
+        /// Run any method of the underlying [`ActiveClient`] to completion, which covers the
+        /// requests without a blocking version on [`Client`].
+        ///
+        /// # Arguments
+        /// * `f` - The async closure to run (ex. `async |c| c.req_ids().await`).
+        pub fn block_on<T>(&mut self, f: impl AsyncFnOnce(&mut ActiveClient) -> T) -> T {
+            self.runtime.block_on(f(&mut self.client))
+        }
+
+        #[inline]
+        /// Iterate over the events sent by the wrapper, blocking until each one arrives. The
+        /// iterator ends once the message loop stops.
+        pub fn events(&self) -> mpsc::Iter<'_, E> {
+            self.events.iter()
+        }
+
+        #[inline]
+        /// Iterate over the events that the wrapper has already sent, without blocking.
+        pub fn try_events(&self) -> mpsc::TryIter<'_, E> {
+            self.events.try_iter()
+        }
+
+        #[inline]
+        /// Wait for the next event sent by the wrapper.
+        ///
+        /// # Arguments
+        /// * `timeout` - The maximum amount of time to wait.
+        ///
+        /// # Returns
+        /// The event, or [`None`] if no event arrives before `timeout` elapses or the message
+        /// loop has stopped.
+        pub fn next_event_timeout(&self, timeout: Duration) -> Option<E> {
+            self.events.recv_timeout(timeout).ok()
+        }
+
+        /// Blocking version of [`ActiveClient::req_current_time`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_current_time`].
+        pub fn req_current_time(&mut self) -> Result<(), std::io::Error> {
+            self.runtime.block_on(self.client.req_current_time())
+        }
+
+        /// Blocking version of [`ActiveClient::req_account_updates`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_account_updates`].
+        pub fn req_account_updates(
+            &mut self,
+            account_number: Option<String>,
+        ) -> Result<(), std::io::Error> {
+            self.runtime
+                .block_on(self.client.req_account_updates(account_number))
+        }
+
+        /// Blocking version of [`ActiveClient::req_positions`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_positions`].
+        pub fn req_positions(&mut self) -> Result<(), std::io::Error> {
+            self.runtime.block_on(self.client.req_positions())
+        }
+
+        /// Blocking version of [`ActiveClient::cancel_positions`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::cancel_positions`].
+        pub fn cancel_positions(&mut self) -> Result<(), std::io::Error> {
+            self.runtime.block_on(self.client.cancel_positions())
+        }
+
+        /// Blocking version of [`ActiveClient::req_market_data_type`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_market_data_type`].
+        pub fn req_market_data_type(
+            &mut self,
+            variant: live_data::Class,
+        ) -> Result<(), std::io::Error> {
+            self.runtime
+                .block_on(self.client.req_market_data_type(variant))
+        }
+
+        /// Blocking version of [`ActiveClient::req_market_data`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_market_data`].
+        pub fn req_market_data<S, D>(
+            &mut self,
+            security: &S,
+            additional_data: Vec<D>,
+            refresh_type: live_data::RefreshType,
+            use_regulatory_snapshot: bool,
+        ) -> Result<RequestId<category::MarketData>, std::io::Error>
+        where
+            S: Security,
+            D: live_data::DataType<S>,
+        {
+            self.runtime.block_on(self.client.req_market_data(
+                security,
+                additional_data,
+                refresh_type,
+                use_regulatory_snapshot,
+            ))
+        }
+
+        /// Blocking version of [`ActiveClient::cancel_market_data`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::cancel_market_data`].
+        pub fn cancel_market_data(
+            &mut self,
+            req_id: RequestId<category::MarketData>,
+        ) -> Result<(), std::io::Error> {
+            self.runtime
+                .block_on(self.client.cancel_market_data(req_id))
+        }
+
+        /// Blocking version of [`ActiveClient::req_market_data_bulk`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_market_data_bulk`].
+        pub fn req_market_data_bulk<S, D>(
+            &mut self,
+            securities: &[S],
+            additional_data: &[D],
+            refresh_type: live_data::RefreshType,
+        ) -> Result<live_data::Watchlist, std::io::Error>
+        where
+            S: Security,
+            D: live_data::DataType<S> + Clone,
+        {
+            self.runtime.block_on(self.client.req_market_data_bulk(
+                securities,
+                additional_data,
+                refresh_type,
+            ))
+        }
+
+        /// Blocking version of [`ActiveClient::cancel_market_data_bulk`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::cancel_market_data_bulk`].
+        pub fn cancel_market_data_bulk(
+            &mut self,
+            watchlist: live_data::Watchlist,
+        ) -> Result<(), std::io::Error> {
+            self.runtime
+                .block_on(self.client.cancel_market_data_bulk(watchlist))
+        }
+
+        /// Blocking version of [`ActiveClient::req_historical_bar`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_historical_bar`].
+        #[allow(clippy::too_many_arguments)]
+        pub fn req_historical_bar<S, D>(
+            &mut self,
+            security: &S,
+            end_date_time: historical_bar::EndDateTime,
+            duration: historical_bar::Duration,
+            bar_size: historical_bar::Size,
+            data: D,
+            regular_trading_hours_only: bool,
+            date_format: historical_bar::DateFormat,
+        ) -> Result<i64, std::io::Error>
+        where
+            S: Security,
+            D: historical_bar::DataType<S>,
+        {
+            self.runtime.block_on(self.client.req_historical_bar(
+                security,
+                end_date_time,
+                duration,
+                bar_size,
+                data,
+                regular_trading_hours_only,
+                date_format,
+            ))
+        }
+
+        /// Blocking version of [`ActiveClient::req_place_order`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_place_order`].
+        pub fn req_place_order<S, Ex>(
+            &mut self,
+            order: &Order<'_, S, Ex>,
+        ) -> Result<PlacedOrder<state::Submitted>, PlaceOrderError>
+        where
+            S: Security,
+            Ex: Executable<S>,
+        {
+            self.runtime.block_on(self.client.req_place_order(order))
+        }
+
+        /// Blocking version of [`ActiveClient::req_modify_order`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_modify_order`].
+        pub fn req_modify_order<S, Ex>(
+            &mut self,
+            order: &Order<'_, S, Ex>,
+            id: i64,
+        ) -> Result<i64, PlaceOrderError>
+        where
+            S: Security,
+            Ex: Executable<S>,
+        {
+            self.runtime
+                .block_on(self.client.req_modify_order(order, id))
+        }
+
+        /// Blocking version of [`ActiveClient::cancel_order`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::cancel_order`].
+        pub fn cancel_order(&mut self, id: i64) -> Result<(), std::io::Error> {
+            self.runtime.block_on(self.client.cancel_order(id))
+        }
+
+        /// Blocking version of [`ActiveClient::req_open_orders`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_open_orders`].
+        pub fn req_open_orders(&mut self) -> Result<(), std::io::Error> {
+            self.runtime.block_on(self.client.req_open_orders())
+        }
+
+        /// Blocking version of [`ActiveClient::req_executions`].
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::req_executions`].
+        pub fn req_executions(&mut self, filter: Filter) -> Result<i64, std::io::Error> {
+            self.runtime.block_on(self.client.req_executions(filter))
+        }
+
+        /// Disconnect from IBKR's trading systems and shut down the runtime.
+        ///
+        /// # Errors
+        /// Returns any error returned by [`ActiveClient::disconnect`].
+        ///
+        /// # Returns
+        /// A [`Builder`] with which to reconnect.
+        pub fn disconnect(self) -> Result<Builder, std::io::Error> {
+            let Self {
+                runtime, client, ..
+            } = self;
+            runtime.block_on(client.disconnect())
+        }
+    }
+}
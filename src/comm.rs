@@ -3,32 +3,66 @@ use std::io::{Error, Write};
 
 use serde::Serialize;
 
+use crate::client::{Direction, MessageTap, OutgoingLog};
+
 #[derive(Debug)]
 pub(crate) struct Writer {
     buf: Vec<u8>,
     offset: Option<usize>,
-    inner: tokio::net::tcp::OwnedWriteHalf,
+    inner: crate::transport::WriteHalf,
+    tap: Option<MessageTap>,
+    log: Option<OutgoingLog>,
+    restart: Option<crate::restart::Window>,
+    #[cfg(feature = "metrics")]
+    metrics: std::sync::Arc<crate::metrics::Registry>,
 }
 
 impl Writer {
     #[inline]
     /// Create a new `Message` with the default capacity specified as [`constants::OUT_MESSAGE_SIZE`]
-    pub(crate) fn new(writer: tokio::net::tcp::OwnedWriteHalf) -> Self {
-        Self::with_capacity(writer, crate::constants::OUT_MESSAGE_SIZE)
+    pub(crate) fn new(
+        writer: crate::transport::WriteHalf,
+        tap: Option<MessageTap>,
+        log: Option<OutgoingLog>,
+    ) -> Self {
+        Self::with_capacity(writer, crate::constants::OUT_MESSAGE_SIZE, tap, log)
     }
 
     #[inline]
     /// Create a new `Message` with the specified capacity.
-    pub(crate) fn with_capacity(writer: tokio::net::tcp::OwnedWriteHalf, cap: usize) -> Self {
+    pub(crate) fn with_capacity(
+        writer: crate::transport::WriteHalf,
+        cap: usize,
+        tap: Option<MessageTap>,
+        log: Option<OutgoingLog>,
+    ) -> Self {
         let buf = Vec::with_capacity(cap);
 
         Self {
             buf,
             offset: None,
             inner: writer,
+            tap,
+            log,
+            restart: None,
+            #[cfg(feature = "metrics")]
+            metrics: std::sync::Arc::default(),
         }
     }
 
+    #[cfg(feature = "metrics")]
+    #[inline]
+    /// The metrics of the client that owns this writer, which are shared with its handles.
+    pub(crate) fn metrics(&self) -> &std::sync::Arc<crate::metrics::Registry> {
+        &self.metrics
+    }
+
+    #[inline]
+    /// Refuse to send messages while requests are paused for a restart of TWS or IB Gateway.
+    pub(crate) fn pause_during(&mut self, restart: Option<crate::restart::Window>) {
+        self.restart = restart;
+    }
+
     #[inline]
     pub(crate) fn add_prefix(&mut self, prefix: &str) -> Result<(), Error> {
         self.buf.write_all(prefix.as_bytes())?;
@@ -43,9 +77,14 @@ impl Writer {
     /// This function will panic if the length of the message overflows by exceeding a length of 2^32 - 1 bytes.
     pub(crate) fn add_body<T: Serialize>(&mut self, body: T) -> Result<(), Error> {
         const LENGTH_PREFIX: &[u8] = b"\0\0\0\0";
+        #[cfg(feature = "metrics")]
+        let start = self.buf.len() + LENGTH_PREFIX.len();
         self.buf.write_all(LENGTH_PREFIX)?;
 
-        body.serialize(&mut *self)?;
+        let mut buf = ser::Buffer(std::mem::take(&mut self.buf));
+        let res = body.serialize(&mut buf);
+        self.buf = buf.0;
+        res?;
         let (len, offset) = match self.offset {
             Some(o) => (self.buf.len() - o - LENGTH_PREFIX.len(), o),
             None => (self.buf.len() - LENGTH_PREFIX.len(), 0),
@@ -57,30 +96,130 @@ impl Writer {
                 .expect("Overflow: Message length exceeds the max of 2³² - 1 bytes.")
                 .to_be_bytes(),
         );
+        #[cfg(feature = "metrics")]
+        self.metrics.record_request(&self.buf[start..]);
 
         Ok(())
     }
 
+    #[inline]
+    /// Fail with a [`crate::restart::Paused`] error if requests are currently paused for a restart
+    /// of TWS or IB Gateway.
+    pub(crate) fn check_paused(&self) -> Result<(), Error> {
+        let now = chrono::Utc::now();
+        match self
+            .restart
+            .and_then(|restart| restart.next_after(now))
+            .filter(|period| period.pause <= now)
+        {
+            Some(period) => Err(Error::new(
+                std::io::ErrorKind::WouldBlock,
+                crate::restart::Paused {
+                    resumes: period.end,
+                },
+            )),
+            None => Ok(()),
+        }
+    }
+
     #[inline]
     pub(crate) async fn send(&mut self) -> Result<(), Error> {
-        tokio::io::AsyncWriteExt::write_all(&mut self.inner, &self.buf).await?;
+        if let Err(e) = self.check_paused() {
+            self.buf.clear();
+            self.offset = None;
+            return Err(e);
+        }
+        if self.tap.is_some() || self.log.is_some() {
+            for fields in outgoing_messages(&self.buf[self.offset.unwrap_or(0)..]) {
+                if let Some(tap) = &self.tap {
+                    tap.observe(Direction::Outbound, &fields);
+                }
+                if let Some(log) = &self.log {
+                    log.record(fields);
+                }
+            }
+        }
+        self.inner.write_all(&self.buf).await?;
         self.buf.clear();
         self.offset = None;
 
         Ok(())
     }
 
+    #[inline]
+    /// A second writer on the same transport, with its own buffer but the same tap, log, and
+    /// restart window as this one.
+    pub(crate) fn handle(&self) -> Self {
+        let mut writer = Self::new(self.inner.clone(), self.tap.clone(), self.log.clone());
+        writer.pause_during(self.restart);
+        #[cfg(feature = "metrics")]
+        {
+            writer.metrics = std::sync::Arc::clone(&self.metrics);
+        }
+        writer
+    }
+
     #[inline]
     pub(crate) async fn flush(&mut self) -> Result<(), Error> {
-        tokio::io::AsyncWriteExt::flush(&mut self.inner).await
+        self.inner.flush().await
     }
 
     #[inline]
     pub(crate) async fn shutdown(&mut self) -> Result<(), Error> {
-        tokio::io::AsyncWriteExt::shutdown(&mut self.inner).await
+        self.inner.shutdown().await
+    }
+}
+
+#[inline]
+/// Split each length-prefixed message in `buf` into its fields.
+fn outgoing_messages(mut buf: &[u8]) -> impl Iterator<Item = Vec<String>> + '_ {
+    std::iter::from_fn(move || {
+        let (len, rest) = buf.split_first_chunk::<4>()?;
+        let len = usize::try_from(u32::from_be_bytes(*len))
+            .unwrap_or(usize::MAX)
+            .min(rest.len());
+        let (msg, rest) = rest.split_at(len);
+        buf = rest;
+        Some(
+            msg.split(|b| *b == 0)
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .collect(),
+        )
+    })
+}
+
+#[derive(Debug)]
+/// The fields of a message that has already been serialized with [`fields`], which are written
+/// again exactly as they are.
+pub(crate) struct Serialized<'a>(pub(crate) &'a [String]);
+
+impl Serialize for Serialized<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeTuple;
+
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for field in self.0 {
+            tuple.serialize_element(field)?;
+        }
+        tuple.end()
     }
 }
 
+#[inline]
+/// Serialize a message body into the fields that would be sent, without the length prefix.
+pub(crate) fn fields<T: Serialize>(body: T) -> Result<Vec<String>, SerializeMessageError> {
+    let mut buf = ser::Buffer::default();
+    body.serialize(&mut buf)?;
+    // Every field is terminated by a NUL, so the last one is not followed by an empty field.
+    let Some(buf) = buf.0.strip_suffix(b"\0") else {
+        return Ok(Vec::new());
+    };
+    Ok(buf
+        .split(|b| *b == 0)
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect())
+}
+
 #[derive(Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub(crate) struct SerializeMessageError(String);
 
@@ -126,7 +265,11 @@ pub(crate) mod ser {
         Serialize, Serializer,
     };
 
-    use super::{SerializeMessageError, Writer};
+    use super::SerializeMessageError;
+
+    #[derive(Debug, Default)]
+    /// A buffer into which a message body is serialized as NUL-terminated fields.
+    pub(crate) struct Buffer(pub(crate) Vec<u8>);
 
     #[inline]
     fn serialize_int<I: itoa::Integer>(buf: &mut Vec<u8>, int: I) -> Result<(), std::io::Error> {
@@ -138,15 +281,26 @@ pub(crate) mod ser {
     }
 
     #[inline]
-    fn serialize_float<F: ryu::Float>(buf: &mut Vec<u8>, float: F) -> Result<(), std::io::Error> {
+    fn serialize_float<F>(buf: &mut Vec<u8>, float: F) -> Result<(), std::io::Error>
+    where
+        F: ryu::Float + std::fmt::Display,
+    {
         let mut temp = ryu::Buffer::new();
-        buf.write_all(temp.format(float).as_bytes())?;
+        let formatted = temp.format(float);
+        // Magnitudes below 1e-5, such as small crypto prices, are written in plain decimal
+        // notation. Large magnitudes keep exponent notation, which TWS expects for the `f64::MAX`
+        // that marks an unset field.
+        if formatted.contains("e-") {
+            write!(buf, "{float}")?;
+        } else {
+            buf.write_all(formatted.as_bytes())?;
+        }
         buf.write_all(b"\0")?;
 
         Ok(())
     }
 
-    impl Serializer for &mut Writer {
+    impl Serializer for &mut Buffer {
         type Ok = ();
         type Error = SerializeMessageError;
         type SerializeSeq = Self;
@@ -159,68 +313,68 @@ pub(crate) mod ser {
 
         #[inline]
         fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-            self.buf.write_all(if v { b"1\0" } else { b"0\0" })?;
+            self.0.write_all(if v { b"1\0" } else { b"0\0" })?;
 
             Ok(())
         }
 
         #[inline]
         fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-            serialize_int(&mut self.buf, v)?;
+            serialize_int(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-            serialize_float(&mut self.buf, v)?;
+            serialize_float(&mut self.0, v)?;
             Ok(())
         }
 
         #[inline]
         fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-            serialize_float(&mut self.buf, v)?;
+            serialize_float(&mut self.0, v)?;
             Ok(())
         }
 
@@ -228,7 +382,7 @@ pub(crate) mod ser {
         fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
             let mut temp = [0; 5];
             v.encode_utf8(&mut temp);
-            self.buf.write_all(&temp[..=v.len_utf8()])?;
+            self.0.write_all(&temp[..=v.len_utf8()])?;
 
             Ok(())
         }
@@ -240,15 +394,15 @@ pub(crate) mod ser {
 
         #[inline]
         fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-            self.buf.write_all(v)?;
-            self.buf.write_all(b"\0")?;
+            self.0.write_all(v)?;
+            self.0.write_all(b"\0")?;
 
             Ok(())
         }
 
         #[inline]
         fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-            self.buf.write_all(b"\0")?;
+            self.0.write_all(b"\0")?;
 
             Ok(())
         }
@@ -363,7 +517,7 @@ pub(crate) mod ser {
         }
     }
 
-    impl SerializeSeq for &mut Writer {
+    impl SerializeSeq for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -373,18 +527,18 @@ pub(crate) mod ser {
             T: ?Sized + Serialize,
         {
             value.serialize(&mut **self)?;
-            self.buf.splice(self.buf.len() - 1..self.buf.len(), *b",");
+            self.0.splice(self.0.len() - 1..self.0.len(), *b",");
             Ok(())
         }
 
         #[inline]
         fn end(self) -> Result<Self::Ok, Self::Error> {
-            self.buf.splice(self.buf.len() - 1..self.buf.len(), *b"\0");
+            self.0.splice(self.0.len() - 1..self.0.len(), *b"\0");
             Ok(())
         }
     }
 
-    impl SerializeTuple for &mut Writer {
+    impl SerializeTuple for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -402,7 +556,7 @@ pub(crate) mod ser {
         }
     }
 
-    impl SerializeTupleStruct for &mut Writer {
+    impl SerializeTupleStruct for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -420,7 +574,7 @@ pub(crate) mod ser {
         }
     }
 
-    impl SerializeTupleVariant for &mut Writer {
+    impl SerializeTupleVariant for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -438,7 +592,7 @@ pub(crate) mod ser {
         }
     }
 
-    impl SerializeMap for &mut Writer {
+    impl SerializeMap for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -464,7 +618,7 @@ pub(crate) mod ser {
         }
     }
 
-    impl SerializeStruct for &mut Writer {
+    impl SerializeStruct for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -482,7 +636,7 @@ pub(crate) mod ser {
         }
     }
 
-    impl SerializeStructVariant for &mut Writer {
+    impl SerializeStructVariant for &mut Buffer {
         type Ok = <Self as Serializer>::Ok;
         type Error = <Self as Serializer>::Error;
 
@@ -501,3 +655,47 @@ pub(crate) mod ser {
         }
     }
 }
+
+#[test]
+fn test_outgoing_messages() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let log = OutgoingLog::Channel(tx);
+    for fields in outgoing_messages(b"\0\0\0\x0549\x001\0\0\0\0\x0361\0") {
+        log.record(fields);
+    }
+    let record = rx.try_recv().unwrap();
+    assert_eq!(record.message_id, "49");
+    assert_eq!(record.fields, vec!["49", "1", ""]);
+    assert_eq!(record.to_string(), "0=49|1=1|2=");
+    assert_eq!(rx.try_recv().unwrap().fields, vec!["61", ""]);
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_serialize_numbers() -> Result<(), Error> {
+    let (transport, _peer) = crate::transport::in_memory(64);
+    let (_, write_half) = crate::transport::split(transport);
+    let mut writer = Writer::new(write_half, None, None);
+    writer.add_body((
+        0.000_000_01_f64,
+        -2.5e-7_f32,
+        189.47_f64,
+        f64::MAX,
+        "0.00000001".parse::<crate::quantity::Quantity>().unwrap(),
+        crate::quantity::Quantity::try_from(1.5e-8_f64).unwrap(),
+    ))?;
+    let fields = outgoing_messages(&writer.buf).next().unwrap();
+    assert_eq!(
+        fields,
+        vec![
+            "0.00000001",
+            "-0.00000025",
+            "189.47",
+            "1.7976931348623157e308",
+            "0.00000001",
+            "0.000000015",
+            ""
+        ]
+    );
+    Ok(())
+}
@@ -4,4 +4,5 @@ pub const TO_CLIENT_CHANNEL_SIZE: usize = 10;
 pub const TO_WRAPPER_CHANNEL_SIZE: usize = 10;
 pub const FROM_READER_CHANNEL_SIZE: usize = 20;
 pub const OUT_MESSAGE_SIZE: usize = 512;
+pub const RECONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
 pub const ORDER_TUPLE_SIZE: usize = 98;
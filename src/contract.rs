@@ -3,8 +3,8 @@ use std::fmt::{Debug, Formatter};
 use std::hash::Hash;
 
 use chrono::NaiveDate;
-use ibapi_macros::{make_getters, Security};
-use serde::{Deserialize, Deserializer, ser::SerializeStruct, Serialize, Serializer};
+use ibapi_macros::make_getters;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use crate::{
@@ -89,12 +89,12 @@ impl Contract {
     /// Attempt to get the inner security's exchange.
     ///
     /// # Returns
-    /// The inner security's exchange, `None` if the field doesn't exist (for a [`Crypto`] contract)
+    /// The inner security's exchange. Every contract type currently has one, so this is always
+    /// `Some`.
     pub fn exchange(&self) -> Option<Routing> {
         match_poly!(self;
-            Contract::SecOption(s) | Contract::Forex(s) | Contract::Index(s) |
+            Contract::SecOption(s) | Contract::Forex(s) | Contract::Index(s) | Contract::Crypto(s) |
             Contract::SecFuture(s) | Contract::Commodity(s) | Contract::Stock(s) => Some(s.exchange()),
-            Contract::Crypto(_) => None,
         )
     }
 
@@ -112,6 +112,28 @@ impl Contract {
         )
     }
 
+    #[inline]
+    #[must_use]
+    /// Get the inner security's trading hours, which are empty if the contract was not received
+    /// from the API.
+    pub fn trading_hours(&self) -> &[TradingSession] {
+        match_poly!(self;
+            Contract::SecOption(s) | Contract::Forex(s) | Contract::Index(s) | Contract::Crypto(s) |
+            Contract::SecFuture(s) | Contract::Commodity(s) | Contract::Stock(s) => s.trading_hours(),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the inner security's liquid hours, during which it usually trades with the most
+    /// volume. These are empty if the contract was not received from the API.
+    pub fn liquid_hours(&self) -> &[TradingSession] {
+        match_poly!(self;
+            Contract::SecOption(s) | Contract::Forex(s) | Contract::Index(s) | Contract::Crypto(s) |
+            Contract::SecFuture(s) | Contract::Commodity(s) | Contract::Stock(s) => s.liquid_hours(),
+        )
+    }
+
     #[inline]
     #[must_use]
     /// Attempt to get the inner security's multiplier.
@@ -289,19 +311,25 @@ impl Security for Contract {
     }
 }
 
+#[cfg(feature = "client")]
 /// Create a new contract based on the unique IBKR contract ID. These contract IDs can be found
 /// either in the Trader Workstation software or online at the
 /// [IBKR Contract Information Center](https://contract.ibkr.info/v3.10/index.php).
 ///
 /// # Arguments
 /// * `client` - The client with which to send the validation request.
-/// * `contract_id` - The IBKR contract ID corresponding to the contract that will be created.
+/// * `query` - The query that identifies the contract that will be created.
 ///
 /// # Errors
 /// Returns any error encountered while writing the query string to the outgoing buffer, while
 /// sending the creation signal to the client loop thread, or while receiving the complete contract
 /// from the client loop thread. Additionally, this function will error if the contract does not
-/// match the generic type specified in the function call.
+/// match the generic type specified in the function call, or if every contract received was
+/// rejected (in which case the first [`ContractError`] is returned).
+///
+/// If the client has a [`Cache`] that already holds a matching contract, no request is sent. If
+/// the query matches more than one contract, the first valid one received is returned; use
+/// [`new_all`] to receive every match.
 ///
 /// # Returns
 /// A fully-defined contract that can be used for market data, placing orders, etc.
@@ -309,17 +337,325 @@ pub async fn new<S: Security>(
     client: &mut crate::client::ActiveClient,
     query: Query,
 ) -> Result<S, NewSecurityError> {
-    client.send_contract_query(query).await?;
-    client
-        .recv_contract_query()
-        .await
-        .ok_or(NewSecurityError::BadResponse)?
+    let cached = client
+        .get_contract_cache()
+        .and_then(|cache| cache.get(&query))
+        .cloned();
+    let contract = match cached {
+        Some(contract) => contract,
+        None => {
+            let mut responses = query_contracts(client, query).await?.into_iter();
+            let first = responses.next().ok_or(NewSecurityError::BadResponse)?;
+            match first {
+                Ok(contract) => contract,
+                Err(e) => responses.find_map(Result::ok).ok_or(e)?,
+            }
+        }
+    };
+    contract
         .try_into()
         .map_err(|e: <S as TryFrom<Contract>>::Error| {
             NewSecurityError::UnexpectedSecurityType(e.into())
         })
 }
 
+#[cfg(feature = "client")]
+/// Create every contract that matches a query. This is most useful with a
+/// [`Query::Specification`], which may match many contracts.
+///
+/// # Arguments
+/// * `client` - The client with which to send the validation request.
+/// * `query` - The query that identifies the contracts that will be created.
+///
+/// # Errors
+/// Returns any error encountered while writing the query string to the outgoing buffer, while
+/// sending the creation signal to the client loop thread, or while receiving the complete
+/// contracts from the client loop thread. Additionally, this function will error if no contract
+/// matches the query.
+///
+/// The [`Cache`] is never consulted, since it cannot know whether it holds every match, but every
+/// valid contract that is received is inserted into it.
+///
+/// # Returns
+/// One entry for every contract the API sent in response to the query, in the order they were
+/// received. A contract that could not be decoded, that does not answer the query, or that does
+/// not match the generic type specified in the function call is returned as a [`ContractError`]
+/// without affecting the others.
+pub async fn new_all<S: Security>(
+    client: &mut crate::client::ActiveClient,
+    query: Query,
+) -> Result<Vec<Result<S, ContractError>>, NewSecurityError> {
+    let responses = query_contracts(client, query).await?;
+    if responses.is_empty() {
+        return Err(NewSecurityError::BadResponse);
+    }
+    Ok(responses
+        .into_iter()
+        .map(|response| {
+            response?
+                .try_into()
+                .map_err(|e: <S as TryFrom<Contract>>::Error| {
+                    ContractError::UnexpectedSecurityType(e.into())
+                })
+        })
+        .collect())
+}
+
+#[cfg(feature = "client")]
+async fn query_contracts(
+    client: &mut crate::client::ActiveClient,
+    query: Query,
+) -> Result<Vec<Result<Contract, ContractError>>, NewSecurityError> {
+    let req_id = client.send_contract_query(query.clone()).await?;
+    let responses = client
+        .recv_contract_query(req_id)
+        .await
+        .ok_or(NewSecurityError::BadResponse)?
+        .into_iter()
+        .map(|response| response.and_then(|contract| query.check(contract)))
+        .collect::<Vec<_>>();
+    if let Some(cache) = client.get_contract_cache_mut() {
+        for contract in responses.iter().flatten() {
+            match query {
+                Query::Figi(figi) => cache.insert_figi(figi, contract.clone()),
+                Query::IbContractId(..) | Query::Specification { .. } => {
+                    cache.insert(contract.clone());
+                }
+            }
+        }
+    }
+    Ok(responses)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+/// An error type describing why a single contract received in response to a [`Query`] was
+/// rejected. Other contracts received for the same query are unaffected.
+pub enum ContractError {
+    /// The contract data could not be decoded.
+    #[error("Failed to decode contract data. Cause: {0}")]
+    Decode(String),
+    /// The contract ID does not match the [`Query::IbContractId`].
+    #[error("Unexpected contract ID. Expected {expected:?}. Found {found:?}")]
+    UnexpectedContractId {
+        /// The contract ID in the query
+        expected: ContractId,
+        /// The contract ID that was actually found
+        found: ContractId,
+    },
+    /// The routing exchange does not match the query.
+    #[error("Unexpected routing exchange. Expected {expected:?}. Found {found:?}")]
+    UnexpectedRouting {
+        /// The routing exchange in the query
+        expected: Routing,
+        /// The routing exchange that was actually found
+        found: Option<Routing>,
+    },
+    /// The contract type does not match the query, or the generic type of [`new_all`].
+    #[error("{0}")]
+    UnexpectedSecurityType(#[from] UnexpectedSecurityType),
+}
+
+#[cfg(feature = "client")]
+/// Create every listed future on an underlying, sorted by expiration date, so that a rolling
+/// strategy can pick the next contract without handling the contract query itself.
+///
+/// # Arguments
+/// * `client` - The client with which to send the query.
+/// * `symbol` - The symbol of the underlying (ex. `ES`).
+/// * `exchange` - The exchange on which the futures are listed.
+///
+/// # Errors
+/// Returns any error encountered by [`new_all`].
+///
+/// # Returns
+/// Every future that was received, from the nearest expiration to the furthest. A contract that
+/// could not be decoded is omitted.
+pub async fn futures_chain(
+    client: &mut crate::client::ActiveClient,
+    symbol: &str,
+    exchange: Routing,
+) -> Result<Vec<SecFuture>, NewSecurityError> {
+    let query = Query::Specification {
+        symbol: symbol.to_owned(),
+        contract_type: ContractType::SecFuture,
+        exchange,
+        currency: None,
+        expiration_date: None,
+        strike: None,
+        right: None,
+    };
+    let mut chain = new_all::<SecFuture>(client, query)
+        .await?
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>();
+    chain.sort_by_key(|future| future.expiration_date);
+    Ok(chain)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Selects the options of an [`option_chain`] whose strikes and expirations lie within bands
+/// around the current price of the underlying.
+pub struct ChainFilter {
+    spot: f64,
+    strike_band: f64,
+    expirations: usize,
+    expiration_window: Option<(NaiveDate, NaiveDate)>,
+}
+
+impl ChainFilter {
+    #[must_use]
+    #[inline]
+    /// Create a new [`ChainFilter`] that selects the strikes within 10% of `spot` on the 3
+    /// nearest expirations.
+    ///
+    /// # Arguments
+    /// * `spot` - The current price of the underlying.
+    pub const fn new(spot: f64) -> Self {
+        Self {
+            spot,
+            strike_band: 0.1,
+            expirations: 3,
+            expiration_window: None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Set how far from the spot price a strike may lie.
+    ///
+    /// # Arguments
+    /// * `strike_band` - The largest distance between a strike and the spot price, as a fraction
+    ///   of the spot price (ex. 0.05 for 5%).
+    pub const fn with_strike_band(mut self, strike_band: f64) -> Self {
+        self.strike_band = strike_band;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Set how many expirations are selected, starting from the nearest.
+    ///
+    /// # Arguments
+    /// * `expirations` - The largest number of expirations.
+    pub const fn with_expirations(mut self, expirations: usize) -> Self {
+        self.expirations = expirations;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Only select the expirations between two dates, inclusive.
+    ///
+    /// # Arguments
+    /// * `first` - The earliest expiration date.
+    /// * `last` - The latest expiration date.
+    pub const fn with_expiration_window(mut self, first: NaiveDate, last: NaiveDate) -> Self {
+        self.expiration_window = Some((first, last));
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return whether a strike lies within the strike band around the spot price.
+    pub fn contains_strike(&self, strike: f64) -> bool {
+        (strike - self.spot).abs() <= self.spot * self.strike_band
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return whether an expiration date lies within the expiration window, if any.
+    pub fn contains_expiration(&self, expiration_date: NaiveDate) -> bool {
+        self.expiration_window
+            .is_none_or(|(first, last)| (first..=last).contains(&expiration_date))
+    }
+
+    #[cfg(feature = "client")]
+    // The selected expirations among those that are listed, from the nearest to the furthest.
+    fn select_expirations(&self, listed: impl IntoIterator<Item = NaiveDate>) -> Vec<NaiveDate> {
+        let mut expirations = listed
+            .into_iter()
+            .filter(|&expiration_date| self.contains_expiration(expiration_date))
+            .collect::<Vec<_>>();
+        expirations.sort_unstable();
+        expirations.dedup();
+        expirations.truncate(self.expirations);
+        expirations
+    }
+}
+
+#[cfg(feature = "client")]
+/// Create the options on a stock whose strikes and expirations lie around the current price, so
+/// that they are ready for market data requests.
+///
+/// The expirations and strikes that are listed for the stock are requested with
+/// [`crate::client::Client::req_option_parameters`]. Those listed for SMART routing are preferred,
+/// and each selected expiration is then resolved with a single [`new_all`] query.
+///
+/// # Arguments
+/// * `client` - The client with which to send the requests.
+/// * `underlying` - The stock on which the options are written.
+/// * `filter` - Selects the strikes and expirations.
+/// * `timeout` - How long to wait for the option parameters.
+///
+/// # Errors
+/// Returns any error encountered by [`new_all`]. Returns an error of kind
+/// [`std::io::ErrorKind::TimedOut`] if the option parameters are not received within `timeout`
+/// (ex. because IBKR reported an error for the request), and [`NewSecurityError::BadResponse`]
+/// if no options are listed.
+///
+/// # Returns
+/// Every option that was selected, sorted by expiration date, strike, and class. An option that
+/// could not be decoded is omitted.
+pub async fn option_chain(
+    client: &mut crate::client::ActiveClient,
+    underlying: &Stock,
+    filter: ChainFilter,
+    timeout: std::time::Duration,
+) -> Result<Vec<SecOption>, NewSecurityError> {
+    let mut params = client.recv_option_parameters(underlying, timeout).await?;
+    if params.iter().any(|p| p.exchange == "SMART") {
+        params.retain(|p| p.exchange == "SMART");
+    }
+    if params.is_empty() {
+        return Err(NewSecurityError::BadResponse);
+    }
+    let expirations =
+        filter.select_expirations(params.iter().flat_map(|p| p.expirations.iter().copied()));
+
+    let mut chain = Vec::new();
+    for expiration_date in expirations {
+        let query = Query::Specification {
+            symbol: underlying.symbol.clone(),
+            contract_type: ContractType::SecOption,
+            exchange: Routing::Smart,
+            currency: Some(underlying.currency),
+            expiration_date: Some(expiration_date),
+            strike: None,
+            right: None,
+        };
+        chain.extend(
+            new_all::<SecOption>(client, query)
+                .await?
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|option| {
+                    filter.contains_strike(option.strike())
+                        && params
+                            .iter()
+                            .any(|p| p.trading_class == option.trading_class())
+                }),
+        );
+    }
+    chain.sort_by(|a, b| {
+        a.expiration_date()
+            .cmp(&b.expiration_date())
+            .then(a.strike().total_cmp(&b.strike()))
+            .then(a.is_put().cmp(&b.is_put()))
+    });
+    Ok(chain)
+}
+
 #[derive(Debug, Error)]
 /// An error type that is returned if creating a [`new`] [`Security`] fails
 pub enum NewSecurityError {
@@ -332,6 +668,9 @@ pub enum NewSecurityError {
     /// Unexpected security type returned from the IBKR API
     #[error("Invalid contract received from the IBKR API. {0}")]
     UnexpectedSecurityType(#[from] UnexpectedSecurityType),
+    /// Every contract returned from the IBKR API was rejected
+    #[error("Invalid contract received from the IBKR API. {0}")]
+    InvalidContract(#[from] ContractError),
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Error)]
@@ -345,15 +684,81 @@ pub struct UnexpectedSecurityType {
     found: ContractType,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+impl UnexpectedSecurityType {
+    #[must_use]
+    #[inline]
+    /// Create a new [`UnexpectedSecurityType`].
+    ///
+    /// # Arguments
+    /// * `expected` - The contract type that was requested.
+    /// * `found` - The contract type that was actually found.
+    pub const fn new(expected: ContractType, found: ContractType) -> Self {
+        Self { expected, found }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 /// A type used to represent a query for a new contract, which can be made by providing either an
-/// IBKR contract ID, or a FIGI.
+/// IBKR contract ID, a FIGI, or a description of the contract.
 pub enum Query {
     /// An IBKR contract ID with which to make a query. When parsing from a string, the routing field
     /// defaults to [`Routing::Smart`].
     IbContractId(ContractId, Routing),
     /// A FIGI.
     Figi(Figi),
+    /// A description of the contract. Unlike the other variants, a specification may match more
+    /// than one contract (ex. every option on an underlying that expires on a given date), so it is
+    /// best used with [`new_all`].
+    Specification {
+        /// The contract's symbol.
+        symbol: String,
+        /// The contract's type.
+        contract_type: ContractType,
+        /// The contract's routing exchange.
+        exchange: Routing,
+        /// The contract's currency, if it is needed to tell contracts apart.
+        currency: Option<Currency>,
+        /// The expiration date of a derivative contract.
+        expiration_date: Option<NaiveDate>,
+        /// The strike price of an option contract.
+        strike: Option<f64>,
+        /// The class (right) of an option contract.
+        right: Option<SecOptionClass>,
+    },
+}
+
+impl Query {
+    #[cfg(feature = "client")]
+    /// Check that a contract received from the API actually answers this query.
+    fn check(&self, contract: Contract) -> Result<Contract, ContractError> {
+        match *self {
+            Self::IbContractId(contract_id, routing) => {
+                if contract.contract_id() != contract_id {
+                    return Err(ContractError::UnexpectedContractId {
+                        expected: contract_id,
+                        found: contract.contract_id(),
+                    });
+                }
+                if contract.exchange().is_some_and(|r| r != routing) {
+                    return Err(ContractError::UnexpectedRouting {
+                        expected: routing,
+                        found: contract.exchange(),
+                    });
+                }
+            }
+            Self::Figi(_) => (),
+            Self::Specification { contract_type, .. } => {
+                if contract.contract_type() != contract_type {
+                    return Err(UnexpectedSecurityType {
+                        expected: contract_type,
+                        found: contract.contract_type(),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(contract)
+    }
 }
 
 impl From<ContractId> for Query {
@@ -398,6 +803,525 @@ impl FromStr for Query {
     }
 }
 
+type SpecificationKey = (String, ContractType, Option<Routing>, Currency);
+
+#[derive(Debug, Clone, Default)]
+/// A store of contracts that have already been received from the API, which allows repeated
+/// [`new`] calls for the same contract to be answered without another round trip to IBKR's
+/// trading systems.
+///
+/// Contracts can be looked up by [`ContractId`], by [`Figi`] (for contracts created from a
+/// [`Query::Figi`]), or by their symbol, contract type, exchange, and currency. When a time-to-live
+/// is set, entries older than the TTL are ignored by every lookup. A cache is installed with
+/// [`crate::client::Builder::with_contract_cache`], and can be kept across sessions with
+/// [`Cache::save`] and [`Cache::load`].
+pub struct Cache {
+    ttl: Option<std::time::Duration>,
+    contracts: std::collections::HashMap<ContractId, (Contract, std::time::Instant)>,
+    figis: std::collections::HashMap<Figi, ContractId>,
+    specifications: std::collections::HashMap<SpecificationKey, ContractId>,
+}
+
+impl Cache {
+    #[must_use]
+    #[inline]
+    /// Create a new, empty cache whose entries never expire.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Create a new, empty cache whose entries expire after `ttl`.
+    ///
+    /// # Arguments
+    /// * `ttl` - How long an entry remains valid after it is inserted.
+    pub fn with_ttl(ttl: std::time::Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    fn specification_key(contract: &Contract) -> SpecificationKey {
+        (
+            contract.symbol().to_owned(),
+            contract.contract_type(),
+            contract.exchange(),
+            contract.currency(),
+        )
+    }
+
+    #[inline]
+    fn is_fresh(&self, inserted: std::time::Instant) -> bool {
+        self.ttl.is_none_or(|ttl| inserted.elapsed() < ttl)
+    }
+
+    /// Insert a contract, replacing any existing entry with the same [`ContractId`].
+    ///
+    /// # Arguments
+    /// * `contract` - The contract to insert.
+    pub fn insert(&mut self, contract: Contract) {
+        let contract_id = contract.contract_id();
+        self.specifications
+            .insert(Self::specification_key(&contract), contract_id);
+        self.contracts
+            .insert(contract_id, (contract, std::time::Instant::now()));
+    }
+
+    /// Insert a contract and associate it with a FIGI.
+    ///
+    /// # Arguments
+    /// * `figi` - The FIGI of the contract.
+    /// * `contract` - The contract to insert.
+    pub fn insert_figi(&mut self, figi: Figi, contract: Contract) {
+        self.figis.insert(figi, contract.contract_id());
+        self.insert(contract);
+    }
+
+    #[must_use]
+    /// Look up the contract that answers a [`Query`].
+    ///
+    /// # Arguments
+    /// * `query` - The query to answer.
+    ///
+    /// # Returns
+    /// The cached contract, or `None` if no fresh entry matches the query. A
+    /// [`Query::IbContractId`] only matches a contract with the same routing exchange, and a
+    /// [`Query::Specification`] never matches, since it may describe more than one contract.
+    pub fn get(&self, query: &Query) -> Option<&Contract> {
+        match query {
+            Query::IbContractId(contract_id, routing) => self
+                .get_by_contract_id(*contract_id)
+                .filter(|contract| contract.exchange().is_none_or(|r| r == *routing)),
+            Query::Figi(figi) => self.get_by_figi(figi),
+            Query::Specification { .. } => None,
+        }
+    }
+
+    #[must_use]
+    /// Look up a contract by its [`ContractId`].
+    ///
+    /// # Arguments
+    /// * `contract_id` - The contract ID.
+    ///
+    /// # Returns
+    /// The cached contract, or `None` if there is no fresh entry.
+    pub fn get_by_contract_id(&self, contract_id: ContractId) -> Option<&Contract> {
+        self.contracts
+            .get(&contract_id)
+            .filter(|(_, inserted)| self.is_fresh(*inserted))
+            .map(|(contract, _)| contract)
+    }
+
+    #[must_use]
+    /// Look up a contract by its [`Figi`].
+    ///
+    /// # Arguments
+    /// * `figi` - The FIGI.
+    ///
+    /// # Returns
+    /// The cached contract, or `None` if there is no fresh entry.
+    pub fn get_by_figi(&self, figi: &Figi) -> Option<&Contract> {
+        self.figis
+            .get(figi)
+            .and_then(|contract_id| self.get_by_contract_id(*contract_id))
+    }
+
+    #[must_use]
+    /// Look up a contract by its symbol, contract type, exchange, and currency.
+    ///
+    /// # Arguments
+    /// * `symbol` - The contract's symbol.
+    /// * `contract_type` - The contract's type.
+    /// * `exchange` - The contract's routing exchange.
+    /// * `currency` - The contract's currency.
+    ///
+    /// # Returns
+    /// The cached contract, or `None` if there is no fresh entry.
+    pub fn get_by_specification(
+        &self,
+        symbol: &str,
+        contract_type: ContractType,
+        exchange: Option<Routing>,
+        currency: Currency,
+    ) -> Option<&Contract> {
+        self.specifications
+            .get(&(symbol.to_owned(), contract_type, exchange, currency))
+            .and_then(|contract_id| self.get_by_contract_id(*contract_id))
+    }
+
+    /// Remove every entry that is older than the cache's time-to-live.
+    pub fn remove_expired(&mut self) {
+        let Some(ttl) = self.ttl else { return };
+        self.contracts
+            .retain(|_, (_, inserted)| inserted.elapsed() < ttl);
+        let contracts = &self.contracts;
+        self.figis.retain(|_, id| contracts.contains_key(id));
+        self.specifications
+            .retain(|_, id| contracts.contains_key(id));
+    }
+
+    /// Remove every entry from the cache.
+    pub fn clear(&mut self) {
+        self.contracts.clear();
+        self.figis.clear();
+        self.specifications.clear();
+    }
+
+    #[must_use]
+    #[inline]
+    /// The number of contracts in the cache, including any that have expired but have not been
+    /// removed.
+    pub fn len(&self) -> usize {
+        self.contracts.len()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Whether the cache contains no contracts.
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty()
+    }
+
+    /// Write every fresh contract in the cache, along with its FIGIs and age, to a TOML file.
+    ///
+    /// # Arguments
+    /// * `path` - The file to write, which is replaced if it already exists.
+    ///
+    /// # Errors
+    /// Returns any error encountered while serializing the contracts or writing the file.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), CacheFileError> {
+        let now = chrono::Utc::now();
+        let contracts = self
+            .contracts
+            .iter()
+            .filter(|(_, (_, inserted))| self.is_fresh(*inserted))
+            .map(|(contract_id, (contract, inserted))| CacheFileEntry {
+                inserted: chrono::Duration::from_std(inserted.elapsed())
+                    .ok()
+                    .and_then(|age| now.checked_sub_signed(age))
+                    .unwrap_or(now),
+                figis: self
+                    .figis
+                    .iter()
+                    .filter(|(_, id)| *id == contract_id)
+                    .map(|(figi, _)| *figi)
+                    .collect(),
+                contract: contract.into(),
+            })
+            .collect();
+        std::fs::write(path, toml::to_string(&CacheFile { contracts })?)?;
+        Ok(())
+    }
+
+    /// Insert every contract from a file written by [`Cache::save`], replacing any existing
+    /// entries with the same [`ContractId`].
+    ///
+    /// Each contract keeps the age it had when it was saved, so this cache's time-to-live applies
+    /// across the save and load: contracts that are already older than the TTL are skipped.
+    ///
+    /// # Arguments
+    /// * `path` - The file to read.
+    ///
+    /// # Errors
+    /// Returns any error encountered while reading the file or parsing its contracts. The cache is
+    /// unchanged if an error occurs.
+    pub fn load(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), CacheFileError> {
+        let file: CacheFile = toml::from_str(std::fs::read_to_string(path)?.as_str())?;
+        let now = chrono::Utc::now();
+        let entries = file
+            .contracts
+            .into_iter()
+            .map(|entry| {
+                let age = (now - entry.inserted).to_std().unwrap_or_default();
+                Ok((age, entry.figis, Contract::try_from(entry.contract)?))
+            })
+            .collect::<Result<Vec<_>, SerializeProxyError>>()?;
+
+        for (age, figis, contract) in entries {
+            if self.ttl.is_some_and(|ttl| age >= ttl) {
+                continue;
+            }
+            let contract_id = contract.contract_id();
+            self.insert(contract);
+            if let Some(entry) = self.contracts.get_mut(&contract_id) {
+                entry.1 = entry.1.checked_sub(age).unwrap_or(entry.1);
+            }
+            self.figis
+                .extend(figis.into_iter().map(|figi| (figi, contract_id)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+/// An error returned when a [`Cache`] cannot be saved to or loaded from a file.
+pub enum CacheFileError {
+    #[error("Failed to read or write contract cache file. Cause: {0}")]
+    /// The OS failed to read or write the file
+    File(#[from] std::io::Error),
+    #[error("Failed to serialize contract cache. Cause: {0}")]
+    /// The contracts could not be written as `TOML`
+    Serialize(#[from] toml::ser::Error),
+    #[error("Failed to parse contract cache file. Cause: {0}")]
+    /// The `TOML` data was invalid or missing
+    Toml(#[from] toml::de::Error),
+    #[error("Invalid contract in contract cache file. Cause: {0}")]
+    /// A contract in the file is missing a field that its contract type requires
+    Contract(#[from] SerializeProxyError),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheFile {
+    contracts: Vec<CacheFileEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheFileEntry {
+    inserted: chrono::DateTime<chrono::Utc>,
+    figis: Vec<Figi>,
+    contract: CachedContract,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+// Unlike a `Proxy`, this keeps every field of the contract details, so a contract loaded from a
+// cache file is identical to the one that was saved.
+struct CachedContract {
+    contract_type: ContractType,
+    contract_id: ContractId,
+    min_tick: f64,
+    symbol: String,
+    currency: Currency,
+    local_symbol: String,
+    long_name: String,
+    exchange: Routing,
+    trading_class: Option<String>,
+    primary_exchange: Option<Primary>,
+    stock_type: Option<String>,
+    sector: Option<String>,
+    expiration_date: Option<NaiveDate>,
+    multiplier: Option<u32>,
+    strike: Option<f64>,
+    option_type: Option<SecOptionClass>,
+    underlying_contract_id: Option<ContractId>,
+    order_types: Vec<String>,
+    valid_exchanges: Vec<Routing>,
+    security_ids: Vec<SecurityId>,
+    trading_hours: Vec<TradingSession>,
+    liquid_hours: Vec<TradingSession>,
+}
+
+impl From<&Contract> for CachedContract {
+    fn from(contract: &Contract) -> Self {
+        let msg = indicators::Valid::as_out_msg(contract);
+        let (stock_type, sector, security_ids, underlying_contract_id) = match contract {
+            Contract::Stock(stock) => (
+                Some(stock.stock_type.clone()),
+                Some(stock.sector.clone()),
+                stock.security_ids.clone(),
+                None,
+            ),
+            Contract::SecFuture(future) => {
+                (None, None, Vec::new(), Some(future.underlying_contract_id))
+            }
+            Contract::SecOption(SecOption::Call(inner) | SecOption::Put(inner)) => (
+                None,
+                Some(inner.sector.clone()),
+                Vec::new(),
+                Some(inner.underlying_contract_id),
+            ),
+            _ => (None, None, Vec::new(), None),
+        };
+        Self {
+            contract_type: contract.contract_type(),
+            contract_id: msg.contract_id,
+            min_tick: contract.min_tick(),
+            symbol: msg.symbol.to_owned(),
+            currency: msg.currency,
+            local_symbol: msg.local_symbol.to_owned(),
+            long_name: contract.long_name().to_owned(),
+            exchange: msg.exchange,
+            trading_class: msg.trading_class.map(ToOwned::to_owned),
+            primary_exchange: msg.primary_exchange,
+            stock_type,
+            sector,
+            expiration_date: msg.expiration_date,
+            multiplier: msg.multiplier,
+            strike: msg.strike,
+            option_type: match contract {
+                Contract::SecOption(SecOption::Call(_)) => Some(SecOptionClass::Call),
+                Contract::SecOption(SecOption::Put(_)) => Some(SecOptionClass::Put),
+                _ => None,
+            },
+            underlying_contract_id,
+            order_types: contract.order_types().clone(),
+            valid_exchanges: contract.valid_exchanges().clone(),
+            security_ids,
+            trading_hours: contract.trading_hours().to_vec(),
+            liquid_hours: contract.liquid_hours().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<CachedContract> for Contract {
+    type Error = SerializeProxyError;
+
+    fn try_from(value: CachedContract) -> Result<Self, Self::Error> {
+        let CachedContract {
+            contract_type,
+            contract_id,
+            min_tick,
+            symbol,
+            currency,
+            local_symbol,
+            long_name,
+            exchange,
+            trading_class,
+            primary_exchange,
+            stock_type,
+            sector,
+            expiration_date,
+            multiplier,
+            strike,
+            option_type,
+            underlying_contract_id,
+            order_types,
+            valid_exchanges,
+            security_ids,
+            trading_hours,
+            liquid_hours,
+        } = value;
+        let trading_class =
+            || trading_class.ok_or(SerializeProxyError::MissingData("trading_class"));
+
+        Ok(match contract_type {
+            ContractType::Stock => Self::Stock(Stock {
+                contract_id,
+                min_tick,
+                symbol,
+                exchange,
+                primary_exchange: primary_exchange
+                    .ok_or(SerializeProxyError::MissingData("primary_exchange"))?,
+                stock_type: stock_type.unwrap_or_default(),
+                security_ids,
+                sector: sector.unwrap_or_default(),
+                trading_class: trading_class()?,
+                currency,
+                local_symbol,
+                long_name,
+                order_types,
+                valid_exchanges,
+                trading_hours,
+                liquid_hours,
+            }),
+            ContractType::Index => Self::Index(Index {
+                contract_id,
+                min_tick,
+                symbol,
+                exchange,
+                currency,
+                local_symbol,
+                long_name,
+                order_types,
+                valid_exchanges,
+                trading_hours,
+                liquid_hours,
+            }),
+            ContractType::Commodity => Self::Commodity(Commodity {
+                contract_id,
+                min_tick,
+                symbol,
+                exchange,
+                trading_class: trading_class()?,
+                currency,
+                local_symbol,
+                long_name,
+                order_types,
+                valid_exchanges,
+                trading_hours,
+                liquid_hours,
+            }),
+            ContractType::Crypto => Self::Crypto(Crypto {
+                contract_id,
+                min_tick,
+                symbol,
+                exchange,
+                trading_class: trading_class()?,
+                currency,
+                local_symbol,
+                long_name,
+                order_types,
+                valid_exchanges,
+                trading_hours,
+                liquid_hours,
+            }),
+            ContractType::Forex => Self::Forex(Forex {
+                contract_id,
+                min_tick,
+                symbol,
+                exchange,
+                trading_class: trading_class()?,
+                currency,
+                local_symbol,
+                long_name,
+                order_types,
+                valid_exchanges,
+                trading_hours,
+                liquid_hours,
+            }),
+            ContractType::SecFuture => Self::SecFuture(SecFuture {
+                contract_id,
+                min_tick,
+                symbol,
+                exchange,
+                multiplier: multiplier.ok_or(SerializeProxyError::MissingData("multiplier"))?,
+                expiration_date: expiration_date
+                    .ok_or(SerializeProxyError::MissingData("expiration_date"))?,
+                trading_class: trading_class()?,
+                underlying_contract_id: underlying_contract_id.unwrap_or(contract_id),
+                currency,
+                local_symbol,
+                long_name,
+                order_types,
+                valid_exchanges,
+                trading_hours,
+                liquid_hours,
+            }),
+            ContractType::SecOption => {
+                let inner = SecOptionInner {
+                    contract_id,
+                    min_tick,
+                    symbol,
+                    exchange,
+                    strike: strike.ok_or(SerializeProxyError::MissingData("strike"))?,
+                    multiplier: multiplier.ok_or(SerializeProxyError::MissingData("multiplier"))?,
+                    expiration_date: expiration_date
+                        .ok_or(SerializeProxyError::MissingData("expiration_date"))?,
+                    underlying_contract_id: underlying_contract_id.unwrap_or(contract_id),
+                    sector: sector.unwrap_or_default(),
+                    trading_class: trading_class()?,
+                    currency,
+                    local_symbol,
+                    long_name,
+                    order_types,
+                    valid_exchanges,
+                    trading_hours,
+                    liquid_hours,
+                };
+                Self::SecOption(
+                    match option_type.ok_or(SerializeProxyError::MissingData("option_type"))? {
+                        SecOptionClass::Call => SecOption::Call(inner),
+                        SecOptionClass::Put => SecOption::Put(inner),
+                    },
+                )
+            }
+            unsupported => return Err(SerializeProxyError::UnsupportedContractType(unsupported)),
+        })
+    }
+}
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 /// A unique identifier used by both IBKR's trading systems and the API to define a specific
@@ -417,7 +1341,7 @@ impl FromStr for ContractId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Identifiers used by the broader industry / regulators to define a specific contract / asset.
 pub enum SecurityId {
     /// For details, see:
@@ -438,7 +1362,9 @@ pub enum SecurityId {
 // === Valid Trait Definition ===
 // =================================
 
-mod indicators {
+#[doc(hidden)]
+// Public only so that the code generated by `#[derive(Security)]` compiles in other crates.
+pub mod indicators {
     use std::convert::Infallible;
 
     use chrono::NaiveDate;
@@ -504,7 +1430,6 @@ mod indicators {
         + TryFrom<SecOption, Error: Into<UnexpectedSecurityType>>
         + TryFrom<Commodity, Error: Into<UnexpectedSecurityType>>
         + TryFrom<Contract, Error: Into<UnexpectedSecurityType>>
-        + Into<Contract>
     {
         fn as_out_msg(&self) -> SecurityOutMsg<'_>;
     }
@@ -530,6 +1455,66 @@ mod indicators {
     }
 }
 
+/// Derive an implementation of [`Security`] for a contract type that is not built into this
+/// crate, so that it can be used wherever a [`Security`] is accepted (ex. to place an order).
+///
+/// The `#[security(...)]` attribute sets the security type that is sent to IBKR, `sec_type`, which
+/// must correspond to a [`ContractType`], along with flags for the optional fields of the type:
+/// * `has_expiry` - An `expiration_date: NaiveDate` field.
+/// * `has_multiplier` - A `multiplier: u32` field.
+/// * `has_strike` - A `strike: f64` field.
+/// * `has_primary_exchange` - A `primary_exchange: Primary` field.
+/// * `no_trading_class` - No `trading_class: String` field.
+///
+/// Every type also needs the fields `contract_id: ContractId`, `min_tick: f64`, `symbol: String`,
+/// `exchange: Routing`, `currency: Currency`, `local_symbol: String`, `long_name: String`,
+/// `order_types: Vec<String>`, and `valid_exchanges: Vec<Routing>`. An enum may be derived
+/// instead, as long as each of its variants wraps a struct with these fields, and
+/// `#[security(right = "C")]` on a variant sets the option right that it represents.
+///
+/// The security types of the built-in contracts (ex. `STK` for [`Stock`]) cannot be derived again.
+/// Since the API only ever creates a [`Contract`], [`new`] returns an [`UnexpectedSecurityType`]
+/// for a derived type, which is instead constructed by the crate that defines it.
+///
+/// ```
+/// use ibapi::contract::{ContractId, ContractType, Security};
+/// use ibapi::currency::Currency;
+/// use ibapi::exchange::Routing;
+///
+/// #[derive(Security)]
+/// #[security(sec_type = "BOND", has_expiry)]
+/// struct Bond {
+///     contract_id: ContractId,
+///     min_tick: f64,
+///     symbol: String,
+///     exchange: Routing,
+///     currency: Currency,
+///     local_symbol: String,
+///     long_name: String,
+///     order_types: Vec<String>,
+///     valid_exchanges: Vec<Routing>,
+///     trading_class: String,
+///     expiration_date: chrono::NaiveDate,
+/// }
+///
+/// let bond = Bond {
+///     contract_id: ContractId(563_592_003),
+///     min_tick: 0.001,
+///     symbol: "IBCID563592003".to_owned(),
+///     exchange: Routing::Smart,
+///     currency: Currency::UsDollar,
+///     local_symbol: "912810TV0".to_owned(),
+///     long_name: "United States Treasury".to_owned(),
+///     order_types: vec!["LMT".to_owned()],
+///     valid_exchanges: vec![Routing::Smart],
+///     trading_class: "GOVT".to_owned(),
+///     expiration_date: chrono::NaiveDate::from_ymd_opt(2053, 11, 15).unwrap(),
+/// };
+/// assert_eq!(bond.contract_type(), ContractType::Bond);
+/// assert_eq!(bond.contract_type().to_string(), "BOND");
+/// ```
+pub use ibapi_macros::Security;
+
 #[doc(alias = "Contract")]
 /// Attributes shared by a tradable contract or asset. All valid contracts implement this trait.
 pub trait Security: indicators::Valid {
@@ -578,6 +1563,167 @@ pub trait Security: indicators::Valid {
     /// # Returns
     /// The security's contract type.
     fn contract_type(&self) -> ContractType;
+    /// Round a price to a multiple of the security's minimum tick size.
+    ///
+    /// Only the minimum tick is considered; exchanges whose market rules vary the tick size with
+    /// the price level may still reject a snapped price.
+    ///
+    /// # Arguments
+    /// * `price` - The price to round.
+    /// * `direction` - The direction in which to round.
+    ///
+    /// # Returns
+    /// The rounded price, or `price` unchanged if the security has no positive minimum tick.
+    fn snap_price(&self, price: f64, direction: TickRounding) -> f64 {
+        snap_price(price, self.min_tick(), direction)
+    }
+    /// Check whether a price is a multiple of the security's minimum tick size.
+    ///
+    /// # Arguments
+    /// * `price` - The price to check.
+    ///
+    /// # Returns
+    /// `true` if the price can be submitted without rounding.
+    fn is_valid_price(&self, price: f64) -> bool {
+        self.check_price(price).is_ok()
+    }
+    /// Check that a price is a multiple of the security's minimum tick size.
+    ///
+    /// A price within a billionth of a tick of a valid price is accepted, so that the
+    /// floating-point error in a computed price (ex. 0.1 + 0.2) does not make it invalid.
+    ///
+    /// # Arguments
+    /// * `price` - The price to check.
+    ///
+    /// # Errors
+    /// Returns an [`InvalidPrice`] listing the nearest valid prices on either side if `price` is
+    /// not a multiple of the minimum tick.
+    ///
+    /// # Returns
+    /// The price, unchanged.
+    fn check_price(&self, price: f64) -> Result<f64, InvalidPrice> {
+        let min_tick = self.min_tick();
+        let (below, above) = (
+            snap_price(price, min_tick, TickRounding::Down),
+            snap_price(price, min_tick, TickRounding::Up),
+        );
+        let tolerance = if min_tick > 0.0 {
+            min_tick * TICK_TOLERANCE
+        } else {
+            0.0
+        };
+        if price.is_finite()
+            && ((price - below).abs() <= tolerance || (above - price).abs() <= tolerance)
+        {
+            Ok(price)
+        } else {
+            Err(InvalidPrice {
+                price,
+                min_tick,
+                below,
+                above,
+            })
+        }
+    }
+    #[cfg(feature = "client")]
+    /// Return the fields with which the security is described in outgoing messages, such as
+    /// market data requests and orders, without sending anything.
+    ///
+    /// The fields are exactly those written to the socket, in order and without their NUL
+    /// terminators. [`crate::order::Order::to_wire_fields`] returns a whole order message.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`std::io::ErrorKind::InvalidData`] if the security cannot be
+    /// serialized.
+    ///
+    /// # Returns
+    /// The security's fields.
+    fn to_wire_fields(&self) -> Result<Vec<String>, std::io::Error> {
+        Ok(crate::comm::fields(self.as_out_msg())?)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+/// The direction in which [`Security::snap_price`] rounds a price.
+pub enum TickRounding {
+    /// Round to the nearest valid price, with ties rounded away from zero.
+    #[default]
+    Nearest,
+    /// Round down to the nearest valid price.
+    Down,
+    /// Round up to the nearest valid price.
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+#[error("Invalid price {price} for a minimum tick of {min_tick}. The nearest valid prices are {below} and {above}.")]
+/// An error type that's returned when a price is not a multiple of a [`Security`]'s minimum tick.
+pub struct InvalidPrice {
+    /// The price that was checked
+    pub price: f64,
+    /// The security's minimum tick size
+    pub min_tick: f64,
+    /// The nearest valid price below `price`
+    pub below: f64,
+    /// The nearest valid price above `price`
+    pub above: f64,
+}
+
+// Prices are compared with a relative tolerance so that the floating-point error in a price that
+// has been computed (ex. 0.1 + 0.2) does not make it invalid.
+const TICK_TOLERANCE: f64 = 1e-9;
+
+#[inline]
+fn snap_price(price: f64, min_tick: f64, direction: TickRounding) -> f64 {
+    if min_tick.is_nan() || min_tick <= 0.0 || !price.is_finite() {
+        return price;
+    }
+    let ticks = price / min_tick;
+    let nearest = ticks.round();
+    let ticks = if (ticks - nearest).abs() <= TICK_TOLERANCE * nearest.abs().max(1.0) {
+        nearest
+    } else {
+        match direction {
+            TickRounding::Nearest => nearest,
+            TickRounding::Down => ticks.floor(),
+            TickRounding::Up => ticks.ceil(),
+        }
+    };
+    // Multiplying by the tick reintroduces floating-point error (ex. 0.01 * 12345.0 is not
+    // 123.45), so the result is rounded to the number of decimals in the tick itself.
+    let scale = (0..=10)
+        .map(|d| 10_f64.powi(d))
+        .find(|scale| {
+            let scaled = min_tick * scale;
+            (scaled - scaled.round()).abs() <= TICK_TOLERANCE * scaled
+        })
+        .unwrap_or(1e10);
+    (ticks * min_tick * scale).round() / scale
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// A single session in the trading or liquid hours of a contract, as listed in its contract
+/// details.
+pub struct TradingSession {
+    /// The time at which the session opens.
+    pub open: chrono::DateTime<chrono::Utc>,
+    /// The time at which the session closes.
+    pub close: chrono::DateTime<chrono::Utc>,
+    /// The trading date of the session, which is the date on which it closes in the contract's
+    /// timezone.
+    pub date: NaiveDate,
+}
+
+impl TradingSession {
+    #[must_use]
+    #[inline]
+    /// Return whether the session is open at `time`.
+    ///
+    /// # Arguments
+    /// * `time` - The time to check.
+    pub fn contains(&self, time: chrono::DateTime<chrono::Utc>) -> bool {
+        self.open <= time && time < self.close
+    }
 }
 
 // =======================================
@@ -585,10 +1731,10 @@ pub trait Security: indicators::Valid {
 // =======================================
 
 macro_rules! make_contract {
-    ($( #[doc = $name_doc:expr] )? $name: ident $(,$trt: ident)?; $($field: ident: $f_type: ty),* $(,)?) => {
-        $( #[doc = $name_doc] )?
+    ($( #[$attr: meta] )* $name: ident $(,$trt: ident)?; $($field: ident: $f_type: ty),* $(,)?) => {
         #[make_getters]
         #[derive(Debug, Clone, PartialEq, $($trt)?)]
+        $( #[$attr] )*
         pub struct $name {
             pub(crate) contract_id: ContractId,
             pub(crate) min_tick: f64,
@@ -599,12 +1745,15 @@ macro_rules! make_contract {
             pub(crate) long_name: String,
             pub(crate) order_types: Vec<String>,
             pub(crate) valid_exchanges: Vec<Routing>,
+            pub(crate) trading_hours: Vec<TradingSession>,
+            pub(crate) liquid_hours: Vec<TradingSession>,
         }
     }
 }
 
 make_contract!(
     /// A [forex contract](https://interactivebrokers.github.io/tws-api/basic_contracts.html#cash), like GBPUSD.
+    #[security(sec_type = "CASH")]
     Forex,
     Security;
     exchange: Routing,
@@ -612,12 +1761,15 @@ make_contract!(
 );
 make_contract!(
     /// A [crypto contract](https://interactivebrokers.github.io/tws-api/basic_contracts.html#crypto), like BTC.
+    #[security(sec_type = "Crypto")]
     Crypto,
     Security;
+    exchange: Routing,
     trading_class: String
 );
 make_contract!(
     /// An [equity contract](https://interactivebrokers.github.io/tws-api/basic_contracts.html#stk), like AAPL.
+    #[security(sec_type = "STK", has_primary_exchange)]
     Stock,
     Security;
     exchange: Routing,
@@ -629,12 +1781,14 @@ make_contract!(
 );
 make_contract!(
     /// An [index](https://interactivebrokers.github.io/tws-api/basic_contracts.html#ind), like SPX.
+    #[security(sec_type = "IND", no_trading_class)]
     Index,
     Security;
     exchange: Routing
 );
 make_contract!(
     /// A [commodity](https://interactivebrokers.github.io/tws-api/basic_contracts.html#Commodities), like XAUUSD.
+    #[security(sec_type = "CMDTY")]
     Commodity,
     Security;
     exchange: Routing,
@@ -642,6 +1796,7 @@ make_contract!(
 );
 make_contract!(
     /// A [futures contract](https://interactivebrokers.github.io/tws-api/basic_contracts.html#fut), like FGBL MAR 23.
+    #[security(sec_type = "FUT", has_expiry, has_multiplier)]
     SecFuture,
     Security;
     exchange: Routing,
@@ -664,14 +1819,34 @@ make_contract!(
 );
 
 #[derive(Debug, Clone, PartialEq, Security)]
+#[security(sec_type = "OPT", has_expiry, has_multiplier, has_strike)]
 /// A [vanilla option contract](https://interactivebrokers.github.io/tws-api/basic_contracts.html#opt), like P BMW  20221216 72 M.
 pub enum SecOption {
+    #[security(right = "C")]
     /// A vanilla call option, defined by the following payoff function: max(S<sub>T</sub> - K, 0)
     Call(SecOptionInner),
+    #[security(right = "P")]
     /// A vanilla put option, defined by the following payoff function: max(K - S<sub>T</sub>, 0)
     Put(SecOptionInner),
 }
 
+impl SecFuture {
+    #[cfg(feature = "client")]
+    #[inline]
+    // The continuous future (CONTFUT) on the same underlying, which stitches together
+    // consecutive expirations. Only the underlying is sent, so that IBKR picks the contracts.
+    pub(crate) fn as_continuous_out_msg(&self) -> indicators::SecurityOutMsg<'_> {
+        indicators::SecurityOutMsg {
+            contract_id: ContractId(0),
+            security_type: "CONTFUT",
+            expiration_date: None,
+            local_symbol: "",
+            trading_class: None,
+            ..indicators::Valid::as_out_msg(self)
+        }
+    }
+}
+
 impl SecOption {
     #[must_use]
     #[inline]
@@ -778,6 +1953,20 @@ impl SecOption {
     pub fn trading_class(&self) -> &str {
         &self.as_inner_ref().trading_class
     }
+
+    #[must_use]
+    #[inline]
+    /// Get a reference to the inner contract's trading hours
+    pub fn trading_hours(&self) -> &Vec<TradingSession> {
+        &self.as_inner_ref().trading_hours
+    }
+
+    #[must_use]
+    #[inline]
+    /// Get a reference to the inner contract's liquid hours
+    pub fn liquid_hours(&self) -> &Vec<TradingSession> {
+        &self.as_inner_ref().liquid_hours
+    }
 }
 
 impl From<(SecOptionClass, SecOptionInner)> for SecOption {
@@ -859,15 +2048,14 @@ pub type NoExchangeProxy<S> = Proxy<S, NoExchange>;
 pub trait ProxyExchange: proxy_indicators::Valid {}
 
 pub(crate) mod proxy_indicators {
-    use crate::decode::DecodeError;
-    use crate::exchange::{Primary, Routing};
+    use crate::exchange::{ParseExchangeError, Primary, Routing};
 
     use super::ProxyExchange;
 
     pub trait Valid: std::fmt::Debug + Send + Sync + Clone {
-        // False positive
-        #[allow(private_interfaces)]
-        fn decode(exch_or_primary: String) -> Result<(Routing, Primary), DecodeError>;
+        fn decode(
+            exch_or_primary: String,
+        ) -> Result<(Routing, Primary), (&'static str, ParseExchangeError)>;
         fn deserialize(
             exch: Option<Routing>,
             primary: Option<Primary>,
@@ -883,10 +2071,10 @@ pub(crate) mod proxy_indicators {
     pub struct NoExchange;
 
     impl Valid for HasExchange {
-        // False positive
-        #[allow(private_interfaces)]
         #[inline]
-        fn decode(exch_or_primary: String) -> Result<(Routing, Primary), DecodeError> {
+        fn decode(
+            exch_or_primary: String,
+        ) -> Result<(Routing, Primary), (&'static str, ParseExchangeError)> {
             Ok((
                 exch_or_primary.parse().map_err(|e| ("exchange", e))?,
                 Primary::InteractiveBrokersDealingSystem,
@@ -911,10 +2099,10 @@ pub(crate) mod proxy_indicators {
     }
 
     impl Valid for NoExchange {
-        // False positive
-        #[allow(private_interfaces)]
         #[inline]
-        fn decode(exch_or_primary: String) -> Result<(Routing, Primary), DecodeError> {
+        fn decode(
+            exch_or_primary: String,
+        ) -> Result<(Routing, Primary), (&'static str, ParseExchangeError)> {
             Ok((
                 Routing::Smart,
                 exch_or_primary
@@ -1009,119 +2197,27 @@ impl From<SecOptionClass> for char {
 }
 
 impl<S: Security + Clone + Debug, E: ProxyExchange> From<Proxy<S, E>> for SerProxyHelp {
-    #[allow(clippy::too_many_lines)]
     fn from(value: Proxy<S, E>) -> Self {
-        let contract_type = value.contract_type();
-        let contract_id = value.contract_id();
-        let currency = value.currency();
-
-        match value.inner.into() {
-            Contract::Stock(stk) => Self {
-                contract_type,
-                contract_id,
-                symbol: stk.symbol,
-                currency,
-                local_symbol: stk.local_symbol,
-                exchange: E::get_exchange(stk.exchange),
-                trading_class: Some(stk.trading_class),
-                primary_exchange: E::get_primary(stk.primary_exchange),
-                expiration_date: None,
-                multiplier: None,
-                strike: None,
-                option_type: None,
-            },
-            Contract::SecOption(opt) => {
-                let option_type = Some(if opt.is_call() {
+        let msg = value.inner.as_out_msg();
+        Self {
+            contract_type: value.contract_type(),
+            contract_id: msg.contract_id,
+            symbol: msg.symbol.to_owned(),
+            currency: msg.currency,
+            local_symbol: msg.local_symbol.to_owned(),
+            exchange: E::get_exchange(msg.exchange),
+            trading_class: msg.trading_class.map(ToOwned::to_owned),
+            primary_exchange: msg.primary_exchange.and_then(E::get_primary),
+            expiration_date: msg.expiration_date,
+            multiplier: msg.multiplier,
+            option_type: msg.right.map(|right| {
+                if right == "C" {
                     SecOptionClass::Call
                 } else {
                     SecOptionClass::Put
-                });
-                let opt = opt.into_inner();
-                Self {
-                    contract_type,
-                    contract_id,
-                    symbol: opt.symbol,
-                    currency,
-                    local_symbol: opt.local_symbol,
-                    exchange: E::get_exchange(opt.exchange),
-                    trading_class: Some(opt.trading_class),
-                    primary_exchange: None,
-                    expiration_date: Some(opt.expiration_date),
-                    multiplier: Some(opt.multiplier),
-                    strike: Some(opt.strike),
-                    option_type,
                 }
-            }
-            Contract::SecFuture(fut) => Self {
-                contract_type,
-                contract_id,
-                symbol: fut.symbol,
-                currency,
-                local_symbol: fut.local_symbol,
-                exchange: E::get_exchange(fut.exchange),
-                trading_class: Some(fut.trading_class),
-                primary_exchange: None,
-                expiration_date: Some(fut.expiration_date),
-                multiplier: Some(fut.multiplier),
-                strike: None,
-                option_type: None,
-            },
-            Contract::Commodity(cmdty) => Self {
-                contract_type,
-                contract_id,
-                symbol: cmdty.symbol,
-                currency,
-                local_symbol: cmdty.local_symbol,
-                exchange: E::get_exchange(cmdty.exchange),
-                trading_class: Some(cmdty.trading_class),
-                primary_exchange: None,
-                expiration_date: None,
-                multiplier: None,
-                strike: None,
-                option_type: None,
-            },
-            Contract::Crypto(crypto) => Self {
-                contract_type,
-                contract_id,
-                symbol: crypto.symbol,
-                currency,
-                local_symbol: crypto.local_symbol,
-                exchange: Some(Routing::Primary(Primary::PaxosCryptoExchange)),
-                trading_class: Some(crypto.trading_class),
-                primary_exchange: None,
-                expiration_date: None,
-                multiplier: None,
-                strike: None,
-                option_type: None,
-            },
-            Contract::Index(ind) => Self {
-                contract_type,
-                contract_id,
-                symbol: ind.symbol,
-                currency,
-                local_symbol: ind.local_symbol,
-                exchange: E::get_exchange(ind.exchange),
-                trading_class: None,
-                primary_exchange: None,
-                expiration_date: None,
-                multiplier: None,
-                strike: None,
-                option_type: None,
-            },
-            Contract::Forex(fx) => Self {
-                contract_type,
-                contract_id,
-                symbol: fx.symbol,
-                currency,
-                local_symbol: fx.local_symbol,
-                exchange: E::get_exchange(fx.exchange),
-                trading_class: Some(fx.trading_class),
-                primary_exchange: None,
-                expiration_date: None,
-                multiplier: None,
-                strike: None,
-                option_type: None,
-            },
+            }),
+            strike: msg.strike,
         }
     }
 }
@@ -1166,6 +2262,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 exchange: exchange.ok_or(SerializeProxyError::MissingData("exchange"))?,
                 sector: String::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             }
             .try_into()
             .map_err(|e: <S as TryFrom<Stock>>::Error| e.into()),
@@ -1179,6 +2277,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 long_name: String::default(),
                 order_types: Vec::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             }
             .try_into()
             .map_err(|e: <S as TryFrom<Index>>::Error| e.into()),
@@ -1194,6 +2294,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 long_name: String::default(),
                 order_types: Vec::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             }
             .try_into()
             .map_err(|e: <S as TryFrom<Commodity>>::Error| e.into()),
@@ -1201,6 +2303,7 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 contract_id,
                 min_tick: f64::default(),
                 symbol,
+                exchange: exchange.ok_or(SerializeProxyError::MissingData("exchange"))?,
                 trading_class: trading_class
                     .ok_or(SerializeProxyError::MissingData("trading_class"))?,
                 currency,
@@ -1208,6 +2311,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 long_name: String::default(),
                 order_types: Vec::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             }
             .try_into()
             .map_err(|e: <S as TryFrom<Crypto>>::Error| e.into()),
@@ -1223,6 +2328,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 long_name: String::default(),
                 order_types: Vec::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             }
             .try_into()
             .map_err(|e: <S as TryFrom<Forex>>::Error| e.into()),
@@ -1242,6 +2349,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                 long_name: String::default(),
                 order_types: Vec::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             }
             .try_into()
             .map_err(|e: <S as TryFrom<SecFuture>>::Error| e.into()),
@@ -1264,6 +2373,8 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
                     long_name: String::default(),
                     order_types: Vec::default(),
                     valid_exchanges: Vec::default(),
+                    trading_hours: Vec::default(),
+                    liquid_hours: Vec::default(),
                 };
                 match option_type.ok_or(SerializeProxyError::MissingData("option_type"))? {
                     SecOptionClass::Call => SecOption::Call(inner),
@@ -1272,6 +2383,7 @@ impl<S: Security + Clone + Debug, E: ProxyExchange> TryFrom<SerProxyHelp> for Pr
             }
             .try_into()
             .map_err(|e: <S as TryFrom<SecOption>>::Error| e.into()),
+            unsupported => return Err(SerializeProxyError::UnsupportedContractType(unsupported)),
         };
 
         Ok(Self {
@@ -1496,21 +2608,33 @@ pub enum ContractType {
     #[serde(rename = "IND")]
     /// An [`Index`] contract.
     Index,
-    //Cfd,
+    #[serde(rename = "CFD")]
+    /// A contract for difference, which has no built-in type (see [`derive@Security`]).
+    Cfd,
     #[serde(rename = "FUT")]
     /// A [`SecFuture`] contract.
     SecFuture,
     #[serde(rename = "OPT")]
     /// A [`SecOption`] contract.
     SecOption,
-    //FutureSecOption,
-    //Bond,
-    //MutualFund,
+    #[serde(rename = "FOP")]
+    /// An option on a future, which has no built-in type (see [`derive@Security`]).
+    FutureSecOption,
+    #[serde(rename = "BOND")]
+    /// A bond, which has no built-in type (see [`derive@Security`]).
+    Bond,
+    #[serde(rename = "FUND")]
+    /// A mutual fund, which has no built-in type (see [`derive@Security`]).
+    MutualFund,
     #[serde(rename = "CMDTY")]
     /// A [`Commodity`] contract.
     Commodity,
-    //Warrant,
-    //StructuredProduct,
+    #[serde(rename = "WAR")]
+    /// A warrant, which has no built-in type (see [`derive@Security`]).
+    Warrant,
+    #[serde(rename = "IOPT")]
+    /// A structured product, which has no built-in type (see [`derive@Security`]).
+    StructuredProduct,
 }
 
 #[derive(Debug, Clone, Error)]
@@ -1520,6 +2644,31 @@ pub enum ContractType {
 /// An error returned when parsing a [`ContractType`] fails.
 pub struct ParseContractTypeError(pub String);
 
+impl ContractType {
+    #[must_use]
+    #[inline]
+    /// Return the maximum number of decimal places that IBKR accepts in the quantity of an order
+    /// for this type of contract, or [`None`] if it does not publish a limit.
+    ///
+    /// Fractional shares are traded in increments of 0.0001, and crypto in increments of
+    /// 0.00000001. Options and futures trade in whole contracts.
+    pub const fn max_quantity_decimals(self) -> Option<u32> {
+        match self {
+            Self::Stock => Some(4),
+            Self::Crypto => Some(8),
+            Self::SecOption | Self::SecFuture | Self::FutureSecOption => Some(0),
+            Self::Forex
+            | Self::Index
+            | Self::Cfd
+            | Self::Bond
+            | Self::MutualFund
+            | Self::Commodity
+            | Self::Warrant
+            | Self::StructuredProduct => None,
+        }
+    }
+}
+
 impl FromStr for ContractType {
     type Err = ParseContractTypeError;
 
@@ -1531,7 +2680,13 @@ impl FromStr for ContractType {
             "IND" => Self::Index,
             "FUT" => Self::SecFuture,
             "OPT" => Self::SecOption,
+            "CFD" => Self::Cfd,
+            "FOP" => Self::FutureSecOption,
+            "BOND" => Self::Bond,
+            "FUND" => Self::MutualFund,
             "CMDTY" => Self::Commodity,
+            "WAR" => Self::Warrant,
+            "IOPT" => Self::StructuredProduct,
             v => return Err(ParseContractTypeError(v.to_owned())),
         })
     }
@@ -1546,7 +2701,13 @@ impl std::fmt::Display for ContractType {
             Self::Index => "IND",
             Self::SecFuture => "FUT",
             Self::SecOption => "OPT",
+            Self::Cfd => "CFD",
+            Self::FutureSecOption => "FOP",
+            Self::Bond => "BOND",
+            Self::MutualFund => "FUND",
             Self::Commodity => "CMDTY",
+            Self::Warrant => "WAR",
+            Self::StructuredProduct => "IOPT",
         };
         write!(f, "{s}")
     }
@@ -1561,4 +2722,133 @@ pub enum SerializeProxyError {
     #[error("Unexpected security type {0}")]
     /// Unexpected security type
     UnexpectedContractType(#[from] UnexpectedSecurityType),
+    #[error("No built-in contract for security type {0}")]
+    /// A security type that has no built-in contract
+    UnsupportedContractType(ContractType),
+}
+
+#[cfg(all(test, feature = "client"))]
+// Apple Inc. common stock routed through SMART, in the TOML format of an `ExchangeProxy`, which
+// tests across the crate deserialize into whichever security or contract type they need.
+pub(crate) const AAPL: &str = r#"
+contract_type = "STK"
+contract_id = 265598
+symbol = "AAPL"
+currency = "USD"
+local_symbol = "AAPL"
+exchange = "SMART"
+trading_class = "NMS"
+"#;
+
+#[test]
+fn test_snap_price() {
+    assert_eq!(snap_price(123.456, 0.01, TickRounding::Nearest), 123.46);
+    assert_eq!(snap_price(123.456, 0.01, TickRounding::Down), 123.45);
+    assert_eq!(snap_price(100.1, 0.25, TickRounding::Up), 100.25);
+    assert_eq!(snap_price(0.1 + 0.2, 0.1, TickRounding::Up), 0.3);
+    assert_eq!(snap_price(4.2, 0.0, TickRounding::Up), 4.2);
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_check_price() -> Result<(), Box<dyn std::error::Error>> {
+    let mut stock = toml::from_str::<ExchangeProxy<Stock>>(AAPL)?.inner;
+    stock.min_tick = 0.01;
+    assert_eq!(stock.check_price(0.1 + 0.2)?, 0.1 + 0.2);
+    assert_eq!(stock.check_price(123.45)?, 123.45);
+    assert!(stock.is_valid_price(100.0));
+    assert_eq!(
+        stock.check_price(123.456),
+        Err(InvalidPrice {
+            price: 123.456,
+            min_tick: 0.01,
+            below: 123.45,
+            above: 123.46,
+        })
+    );
+    assert!(!stock.is_valid_price(f64::NAN));
+
+    // Without a minimum tick, every finite price is valid.
+    stock.min_tick = 0.0;
+    assert!(stock.is_valid_price(123.456));
+    Ok(())
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let contract = toml::from_str::<ExchangeProxy<Contract>>(AAPL)?.inner;
+    let figi: Figi = "BBG000B9XRY4".parse()?;
+    let mut cache = Cache::new();
+    cache.insert_figi(figi, contract.clone());
+
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get_by_contract_id(ContractId(265_598)), Some(&contract));
+    assert_eq!(cache.get_by_figi(&figi), Some(&contract));
+    assert_eq!(
+        cache.get_by_specification(
+            "AAPL",
+            ContractType::Stock,
+            Some(Routing::Smart),
+            Currency::UsDollar
+        ),
+        Some(&contract)
+    );
+    let nyse = Routing::Primary(Primary::NewYorkStockExchange);
+    assert_eq!(
+        cache.get(&Query::IbContractId(ContractId(265_598), Routing::Smart)),
+        Some(&contract)
+    );
+    assert_eq!(cache.get(&Query::IbContractId(ContractId(265_598), nyse)), None);
+
+    // An expired entry is ignored by every lookup until it is removed.
+    let mut expiring = Cache::with_ttl(std::time::Duration::ZERO);
+    expiring.insert_figi(figi, contract);
+    assert_eq!(expiring.get_by_figi(&figi), None);
+    assert_eq!(expiring.len(), 1);
+    expiring.remove_expired();
+    assert!(expiring.is_empty());
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.get_by_figi(&figi), None);
+    Ok(())
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_cache_save_load() -> Result<(), Box<dyn std::error::Error>> {
+    let mut stock = toml::from_str::<ExchangeProxy<Stock>>(AAPL)?.inner;
+    stock.min_tick = 0.01;
+    stock.long_name = "APPLE INC".to_owned();
+    stock.security_ids = vec![SecurityId::Isin("US0378331005".to_owned())];
+    stock.trading_hours = vec![TradingSession {
+        open: "2024-06-03T13:30:00Z".parse()?,
+        close: "2024-06-03T20:00:00Z".parse()?,
+        date: NaiveDate::from_ymd_opt(2024, 6, 3).ok_or("invalid date")?,
+    }];
+    let contract = Contract::Stock(stock);
+    let figi: Figi = "BBG000B9XRY4".parse()?;
+    let mut cache = Cache::new();
+    cache.insert_figi(figi, contract.clone());
+
+    let path = std::env::temp_dir().join(format!("ibapi_cache_{}.toml", std::process::id()));
+    cache.save(&path)?;
+
+    let mut loaded = Cache::new();
+    loaded.load(&path)?;
+    assert_eq!(loaded.get_by_contract_id(ContractId(265_598)), Some(&contract));
+    assert_eq!(loaded.get_by_figi(&figi), Some(&contract));
+
+    // The saved age counts against the time-to-live of the cache that loads it.
+    let mut expired = Cache::with_ttl(std::time::Duration::ZERO);
+    expired.load(&path)?;
+    assert!(expired.is_empty());
+    let mut fresh = Cache::with_ttl(std::time::Duration::from_secs(3600));
+    fresh.load(&path)?;
+    assert_eq!(fresh.get_by_figi(&figi), Some(&contract));
+
+    std::fs::remove_file(&path)?;
+    assert!(matches!(loaded.load(&path), Err(CacheFileError::File(_))));
+    Ok(())
 }
@@ -1,66 +1,62 @@
 use core::str::FromStr;
+#[cfg(feature = "client")]
+use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 // === Type definitions ===
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 /// Represents all the possible currencies available for trading at IBKR.
 pub enum Currency {
-    #[serde(rename = "AUD")]
     /// The Australian Dollar (AUD) is the currency of Australia.
     AustralianDollar,
-    #[serde(rename = "GBP")]
     /// The Pound Sterling (GBP) is the currency of the United Kingdom.
     BritishPound,
-    #[serde(rename = "CAD")]
     /// The Canadian Dollar (CAD) is the currency of Canada.
     CanadianDollar,
-    #[serde(rename = "CNH")]
     /// The Chinese Renminbi (RMB / CNH) is the currency of The People's Republic of China. The
     /// Yuan is the basic unit of the Renminbi.
     ChineseYuan,
-    #[serde(rename = "DKK")]
     /// The Danish Krone (DKK) is the currency of Denmark.
     DanishKrone,
-    #[serde(rename = "EUR")]
     /// The Euro (EUR) is the currency of most countries in the European Union
     Euro,
-    #[serde(rename = "HKD")]
     /// The Hong Kong Dollar (HKD) is the currency of Hong Kong.
     HongKongDollar,
-    #[serde(rename = "INR")]
     /// The Indian Rupee (INR) is the currency of the Republic of India.
     IndianRupee,
-    #[serde(rename = "ILS")]
     /// The Israeli New Shekel (ILS / NIS) is the currency of Israel.
     IsraeliNewShekel,
-    #[serde(rename = "JPY")]
     /// The Japanese Yen (JPY) is the currency of Japan.
     JapaneseYen,
-    #[serde(rename = "KRW")]
     /// The Korean Won (KRW) is the currency of South Korea.
     KoreanWon,
-    #[serde(rename = "MXN")]
     /// The Mexican Peso (MXN) is the currency of Mexico.
     MexicanPeso,
-    #[serde(rename = "NZD")]
     /// The New Zealand Dollar (NZD) is the currency of New Zealand.
     NewZealandDollar,
-    #[serde(rename = "NOK")]
     /// The Norwegian Krone (NOK) is the currency of Norway.
     NorwegianKrone,
-    #[serde(rename = "SEK")]
     /// The Swedish Króna (SEK) is the currency of Sweden.
     SwedishKrona,
-    #[serde(rename = "CHF")]
     /// The Swiss Franc (CHF) is the currency of Switzerland.
     SwissFranc,
-    #[serde(rename = "USD")]
     /// The US Dollar (USD) is the currency of the United States of America.
     UsDollar,
+    /// Any other three-letter currency code that does not have a dedicated variant, such as those
+    /// encountered for exotic FX pairs or new listings.
+    ///
+    /// An [`UnknownCurrency`] can only be created by parsing a code with [`Currency::from_iso`],
+    /// which returns the dedicated variant for every code that has one. A code like "USD" is
+    /// therefore always [`Currency::UsDollar`], and never an unknown currency.
+    Unknown(UnknownCurrency),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The code of a [`Currency::Unknown`], which is stored as upper-case ASCII.
+pub struct UnknownCurrency([u8; 3]);
+
 #[derive(Error, Default, Debug, Clone)]
 #[error(
     "Invalid value encountered when attempting to parse currency. No such currency symbol: {0}"
@@ -68,39 +64,40 @@ pub enum Currency {
 /// An error returned when parsing a [`Currency`] fails.
 pub struct ParseCurrencyError(pub String);
 
-// === Type implementations ===
-
-impl FromStr for Currency {
-    type Err = ParseCurrencyError;
+#[cfg(feature = "client")]
+#[derive(Debug, Clone, PartialEq)]
+/// Converts amounts between currencies at live mid rates, which are maintained from
+/// [`crate::client::Client::req_market_data`] subscriptions to IDEALPRO FX pairs.
+///
+/// After subscribing with [`Converter::subscribe`], pass every
+/// [`crate::wrapper::LocalWrapper::price_data`] callback to [`Converter::price_data`]. The rate of
+/// a currency is the midpoint of the latest bid and ask of its pair with the base currency.
+pub struct Converter {
+    base: Currency,
+    pairs: HashMap<i64, Pair>,
+}
 
-    #[allow(clippy::too_many_lines)]
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.to_uppercase().as_str() {
-            "AUD" => Self::AustralianDollar,
-            "GBP" => Self::BritishPound,
-            "CAD" => Self::CanadianDollar,
-            "CNH" => Self::ChineseYuan,
-            "DKK" => Self::DanishKrone,
-            "EUR" => Self::Euro,
-            "HKD" => Self::HongKongDollar,
-            "INR" => Self::IndianRupee,
-            "ILS" => Self::IsraeliNewShekel,
-            "JPY" => Self::JapaneseYen,
-            "KRW" => Self::KoreanWon,
-            "MXN" => Self::MexicanPeso,
-            "NZD" => Self::NewZealandDollar,
-            "NOK" => Self::NorwegianKrone,
-            "SEK" => Self::SwedishKrona,
-            "CHF" => Self::SwissFranc,
-            "USD" => Self::UsDollar,
-            s => return Err(ParseCurrencyError(s.to_owned())),
-        })
-    }
+#[cfg(feature = "client")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+// The quotes of an FX pair between a currency and the base currency of a `Converter`.
+struct Pair {
+    currency: Currency,
+    // Whether the pair is quoted as units of `currency` per unit of the base currency.
+    inverted: bool,
+    bid: Option<f64>,
+    ask: Option<f64>,
 }
 
-impl std::fmt::Display for Currency {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
+// === Type implementations ===
+
+impl Currency {
+    #[must_use]
+    /// Get the ISO-4217 code of the currency.
+    ///
+    /// # Returns
+    /// The three-letter code that IBKR uses to represent the currency, like "USD".
+    pub fn iso_code(&self) -> &str {
+        match self {
             Self::AustralianDollar => "AUD",
             Self::BritishPound => "GBP",
             Self::CanadianDollar => "CAD",
@@ -118,7 +115,371 @@ impl std::fmt::Display for Currency {
             Self::SwedishKrona => "SEK",
             Self::SwissFranc => "CHF",
             Self::UsDollar => "USD",
+            Self::Unknown(code) => code.as_str(),
+        }
+    }
+
+    /// Create a currency from its ISO-4217 code. The code is case-insensitive.
+    ///
+    /// # Arguments
+    /// * `code` - The three-letter currency code.
+    ///
+    /// # Errors
+    /// Returns an error if `code` is not made up of exactly three ASCII letters.
+    ///
+    /// # Returns
+    /// The matching currency, or [`Currency::Unknown`] if and only if the code has no dedicated
+    /// variant.
+    pub fn from_iso(code: &str) -> Result<Self, ParseCurrencyError> {
+        let upper: [u8; 3] = code
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|b: &[u8; 3]| b.iter().all(u8::is_ascii_alphabetic))
+            .map(|b: [u8; 3]| b.map(|c| c.to_ascii_uppercase()))
+            .ok_or_else(|| ParseCurrencyError(code.to_owned()))?;
+
+        Ok(match &upper {
+            b"AUD" => Self::AustralianDollar,
+            b"GBP" => Self::BritishPound,
+            b"CAD" => Self::CanadianDollar,
+            b"CNH" => Self::ChineseYuan,
+            b"DKK" => Self::DanishKrone,
+            b"EUR" => Self::Euro,
+            b"HKD" => Self::HongKongDollar,
+            b"INR" => Self::IndianRupee,
+            b"ILS" => Self::IsraeliNewShekel,
+            b"JPY" => Self::JapaneseYen,
+            b"KRW" => Self::KoreanWon,
+            b"MXN" => Self::MexicanPeso,
+            b"NZD" => Self::NewZealandDollar,
+            b"NOK" => Self::NorwegianKrone,
+            b"SEK" => Self::SwedishKrona,
+            b"CHF" => Self::SwissFranc,
+            b"USD" => Self::UsDollar,
+            _ => Self::Unknown(UnknownCurrency(upper)),
+        })
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return `true` if the currency is not one of the currencies with a dedicated variant.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_))
+    }
+}
+
+impl UnknownCurrency {
+    #[must_use]
+    #[inline]
+    /// Get the ISO-4217 code of the currency.
+    ///
+    /// # Returns
+    /// The three-letter, upper-case code of the currency.
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).unwrap_or("???")
+    }
+}
+
+#[cfg(feature = "client")]
+impl Converter {
+    #[inline]
+    #[must_use]
+    /// Create a new [`Converter`] with no subscriptions.
+    ///
+    /// # Arguments
+    /// * `base` - The currency into which amounts are converted.
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            pairs: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the currency into which amounts are converted.
+    pub fn base(&self) -> Currency {
+        self.base
+    }
+
+    /// Request streaming market data for the IDEALPRO pair of each currency with the base
+    /// currency. Currencies that are the base currency or are already subscribed are skipped.
+    ///
+    /// Each pair is quoted in the market's convention, in which EUR, GBP, AUD, NZD, USD, CAD, and
+    /// CHF take precedence over other currencies in that order, and JPY is always the quote
+    /// currency. A pair that IDEALPRO does not list cannot be subscribed.
+    ///
+    /// # Arguments
+    /// * `client` - The client with which to send the requests.
+    /// * `currencies` - The currencies to which to subscribe, ex. those of the cash balances and
+    ///   positions of an account.
+    ///
+    /// # Errors
+    /// Returns any error encountered while querying the contract of a pair or writing the
+    /// outgoing messages. Any subscription made before the error remains in place.
+    pub async fn subscribe<I>(
+        &mut self,
+        client: &mut crate::client::ActiveClient,
+        currencies: I,
+    ) -> Result<(), crate::contract::NewSecurityError>
+    where
+        I: IntoIterator<Item = Currency>,
+    {
+        for currency in currencies {
+            if currency == self.base || self.pairs.values().any(|p| p.currency == currency) {
+                continue;
+            }
+            let inverted = precedence(self.base) < precedence(currency);
+            let (symbol, quote) = if inverted {
+                (self.base, currency)
+            } else {
+                (currency, self.base)
+            };
+            let forex: crate::contract::Forex = crate::contract::new(
+                client,
+                crate::contract::Query::Specification {
+                    symbol: symbol.iso_code().to_owned(),
+                    contract_type: crate::contract::ContractType::Forex,
+                    exchange: crate::exchange::Routing::Primary(
+                        crate::exchange::Primary::IbForexPro,
+                    ),
+                    currency: Some(quote),
+                    expiration_date: None,
+                    strike: None,
+                    right: None,
+                },
+            )
+            .await?;
+            let req_id = client
+                .req_market_data(
+                    &forex,
+                    vec![crate::market_data::live_data::Empty],
+                    crate::market_data::live_data::RefreshType::Streaming,
+                    false,
+                )
+                .await?;
+            self.register(req_id, currency, inverted);
+        }
+        Ok(())
+    }
+
+    /// Cancel every subscription and discard the rates received so far.
+    ///
+    /// # Arguments
+    /// * `client` - The client with which to send the cancellations.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing the outgoing messages.
+    pub async fn cancel(
+        &mut self,
+        client: &mut crate::client::ActiveClient,
+    ) -> Result<(), std::io::Error> {
+        for (req_id, _) in self.pairs.drain() {
+            client
+                .cancel_market_data(crate::requests::RequestId::new(req_id))
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    /// Maintain the rate of a currency from an existing market data subscription.
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the subscription to the FX pair of `currency` and the base currency.
+    /// * `currency` - The currency whose rate the pair determines.
+    /// * `inverted` - Whether the pair is quoted as units of `currency` per unit of the base
+    ///   currency (ex. USD.JPY for JPY in a USD converter), rather than the other way around.
+    pub fn register(
+        &mut self,
+        req_id: crate::requests::RequestId<crate::requests::category::MarketData>,
+        currency: Currency,
+        inverted: bool,
+    ) {
+        self.pairs.insert(
+            req_id.get(),
+            Pair {
+                currency,
+                inverted,
+                bid: None,
+                ask: None,
+            },
+        );
+    }
+
+    /// Record a price of one of the converter's pairs. Only bid and ask prices are kept.
+    ///
+    /// # Returns
+    /// The currency whose rate changed, if `req_id` belongs to one of the converter's
+    /// subscriptions.
+    pub fn price_data(
+        &mut self,
+        req_id: i64,
+        price: crate::tick::Class<crate::tick::Price>,
+    ) -> Option<Currency> {
+        let pair = self.pairs.get_mut(&req_id)?;
+        let (crate::tick::Class::Live(price) | crate::tick::Class::Delayed(price)) = price;
+        let (slot, value) = match price {
+            crate::tick::Price::Bid(value) => (&mut pair.bid, value),
+            crate::tick::Price::Ask(value) => (&mut pair.ask, value),
+            _ => return None,
         };
-        write!(f, "{s}")
+        // A price of -1 means that there is no quote on that side.
+        let value = (value > 0.0).then_some(value);
+        (std::mem::replace(slot, value) != value).then_some(pair.currency)
+    }
+
+    #[must_use]
+    /// Return the number of units of the base currency that one unit of a currency is worth.
+    ///
+    /// # Returns
+    /// The rate, which is 1 for the base currency, or [`None`] if the currency has no
+    /// subscription or its pair has not been quoted on both sides.
+    pub fn rate(&self, currency: Currency) -> Option<f64> {
+        if currency == self.base {
+            return Some(1.0);
+        }
+        let pair = self.pairs.values().find(|p| p.currency == currency)?;
+        let mid = (pair.bid? + pair.ask?) / 2.0;
+        Some(if pair.inverted { mid.recip() } else { mid })
     }
+
+    #[inline]
+    #[must_use]
+    /// Convert an amount into the base currency.
+    ///
+    /// # Arguments
+    /// * `amount` - The amount to convert.
+    /// * `currency` - The currency of `amount`.
+    ///
+    /// # Returns
+    /// The amount in the base currency, or [`None`] if the rate of `currency` is not known.
+    pub fn convert(&self, amount: f64, currency: Currency) -> Option<f64> {
+        self.rate(currency).map(|rate| amount * rate)
+    }
+}
+
+#[cfg(feature = "client")]
+#[inline]
+// The order in which currencies take precedence as the base of an FX pair by market convention.
+fn precedence(currency: Currency) -> (u8, Currency) {
+    let rank = match currency {
+        Currency::Euro => 0,
+        Currency::BritishPound => 1,
+        Currency::AustralianDollar => 2,
+        Currency::NewZealandDollar => 3,
+        Currency::UsDollar => 4,
+        Currency::CanadianDollar => 5,
+        Currency::SwissFranc => 6,
+        Currency::JapaneseYen => 8,
+        _ => 7,
+    };
+    (rank, currency)
+}
+
+impl FromStr for Currency {
+    type Err = ParseCurrencyError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_iso(s)
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.iso_code())
+    }
+}
+
+impl std::fmt::Display for UnknownCurrency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.iso_code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn test_currency_round_trip() -> Result<(), ParseCurrencyError> {
+    for code in [
+        "AUD", "GBP", "CAD", "CNH", "DKK", "EUR", "HKD", "INR", "ILS", "JPY", "KRW", "MXN", "NZD",
+        "NOK", "SEK", "CHF", "USD", "ZAR", "SGD",
+    ] {
+        let currency = code.parse::<Currency>()?;
+        assert_eq!(currency.to_string(), code);
+        assert_eq!(Currency::from_iso(currency.iso_code())?, currency);
+    }
+    let koruna = "czk".parse::<Currency>()?;
+    assert!(koruna.is_unknown());
+    assert_eq!(koruna.iso_code(), "CZK");
+    assert_eq!("CZK".parse::<Currency>()?, koruna);
+    // Codes with a dedicated variant are never unknown, however they are written.
+    for code in ["USD", "usd", "Usd"] {
+        let currency = code.parse::<Currency>()?;
+        assert!(!currency.is_unknown());
+        assert_eq!(currency, Currency::UsDollar);
+    }
+    assert!("US".parse::<Currency>().is_err());
+    assert!("US1".parse::<Currency>().is_err());
+    assert!("".parse::<Currency>().is_err());
+    Ok(())
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_converter() {
+    use crate::requests::RequestId;
+    use crate::tick::{Class, Price};
+
+    let mut converter = Converter::new(Currency::UsDollar);
+    converter.register(RequestId::new(1), Currency::Euro, false);
+    converter.register(RequestId::new(2), Currency::JapaneseYen, true);
+    assert!(precedence(Currency::Euro) < precedence(Currency::UsDollar));
+    assert!(precedence(Currency::UsDollar) < precedence(Currency::JapaneseYen));
+
+    assert_eq!(converter.rate(Currency::UsDollar), Some(1.0));
+    assert_eq!(converter.rate(Currency::Euro), None);
+    assert_eq!(
+        converter.price_data(1, Class::Live(Price::Bid(1.25))),
+        Some(Currency::Euro)
+    );
+    assert_eq!(converter.price_data(1, Class::Live(Price::Bid(1.25))), None);
+    assert_eq!(converter.price_data(1, Class::Live(Price::Last(1.5))), None);
+    assert_eq!(converter.price_data(3, Class::Live(Price::Ask(1.75))), None);
+    assert_eq!(converter.rate(Currency::Euro), None);
+    assert_eq!(
+        converter.price_data(1, Class::Delayed(Price::Ask(1.75))),
+        Some(Currency::Euro)
+    );
+    assert_eq!(converter.convert(100.0, Currency::Euro), Some(150.0));
+
+    converter.price_data(2, Class::Live(Price::Bid(127.0)));
+    converter.price_data(2, Class::Live(Price::Ask(129.0)));
+    assert_eq!(
+        converter.convert(1_280.0, Currency::JapaneseYen),
+        Some(10.0)
+    );
+    assert_eq!(converter.convert(1.0, Currency::SwissFranc), None);
+
+    converter.price_data(2, Class::Live(Price::Ask(-1.0)));
+    assert_eq!(converter.rate(Currency::JapaneseYen), None);
 }
@@ -7,39 +7,50 @@ use tracing::warn;
 use crate::{
     currency::Currency,
     exchange::Routing,
-    message::{ToClient, ToWrapper},
+    message::ToClient,
     wrapper,
 };
 use crate::account::{self, ParseAttributeError, Tag, TagValue};
 use crate::contract::{
-    Commodity, Contract, ContractId, ContractType, Crypto, Forex, Index, Proxy, SecFuture,
-    SecOption, SecOptionInner, SecurityId, Stock,
+    Commodity, Contract, ContractError, ContractId, ContractType, Crypto, Forex, Index, Proxy,
+    SecFuture, SecOption, SecOptionInner, SecurityId, Stock, TradingSession,
 };
 use crate::exchange::Primary;
-use crate::execution::{CommissionReport, Exec, Execution, OrderSide, ParseOrderSideError};
+use crate::execution::{
+    CommissionReport, Exec, Execution, Liquidity, OrderSide, ParseOrderSideError,
+};
+use crate::frame::Fields;
 use crate::payload::{
     Bar,
-    BarCore, BidAsk, ExchangeId, Fill, HistogramEntry, Last, market_depth::{CompleteEntry, Entry, Operation}, MarketDataClass, Midpoint,
-    ParsePayloadError, Pnl, PnlSingle, Position, PositionSummary, TickData, Trade,
+    BarCore, BidAsk, DataFarmStatus, ExchangeId, Fill, HistogramEntry, HistoricalSchedule, Last, MarketDataClass, Midpoint,
+    OptionParameters, OrderAmount, ParsePayloadError, Pnl, PnlSingle, Position, PositionSummary, ScheduleSession, TickAttributes,
+    TickData, Trade,
 };
+#[cfg(feature = "depth")]
+use crate::payload::market_depth::{CompleteEntry, Entry, Operation};
+use crate::quantity::{ParseQuantityError, Quantity};
+#[cfg(feature = "news")]
+use crate::payload::Headline;
+#[cfg(feature = "scanner")]
+use crate::payload::ScannerRow;
 use crate::tick::{
-    Accessibility, AuctionData, CalculationResult, Class, Dividends, EtfNav, ExtremeValue, Ipo,
-    MarkPrice, OpenInterest, Period, Price, PriceFactor, QuotingExchanges, Rate, RealTimeVolume,
-    RealTimeVolumeBase, SecOptionCalculationResults, SecOptionCalculations,
+    Accessibility, AuctionData, CalculationResult, Class, Dividend, Dividends, EtfNav, ExtremeValue,
+    FundamentalRatios, Ipo,
+    MarkPrice, OpenInterest, Period, Price, PriceFactor, Quote, QuotingExchanges, Rate,
+    RealTimeVolume, RealTimeVolumeBase, SecOptionCalculationResults, SecOptionCalculations,
     SecOptionCalculationSource, SecOptionVolume, Size, SummaryVolume, TimeStamp, Volatility, Yield,
 };
 
 type Tx = tokio::sync::mpsc::Sender<ToClient>;
-type Rx = tokio::sync::mpsc::Receiver<ToWrapper>;
-type Fields = std::vec::IntoIter<String>;
+type Rx = crate::message::Inbox;
 type DecodeResult = Result<(), DecodeError>;
 
 macro_rules! decode_fields {
     ($fields: expr => $f_name: ident @ $ind: literal: String) => {
-        let $f_name = nth($fields, $ind, stringify!($f_name))?;
+        let $f_name = nth($fields, $ind, stringify!($f_name))?.to_owned();
     };
     ($fields: expr => $f_name: ident @ $ind: literal: Option<$op_f_type: ty>) => {
-        let $f_name = match nth($fields, $ind, stringify!($f_name))?.as_str() {
+        let $f_name = match nth($fields, $ind, stringify!($f_name))? {
             "" => None::<$op_f_type>,
             s => Some(s.parse::<$op_f_type>().map_err(|e| DecodeError::from((stringify!($f_name), e)))?)
         };
@@ -55,23 +66,30 @@ macro_rules! decode_fields {
     };
 }
 
+// Parse the value of a numeric account attribute. Values that are empty make the enclosing function
+// return `Ok(None)`, which `decode_account_attribute` reports as an `Attribute::Unknown`.
+macro_rules! parse_account_value {
+    ($attr_var: ident, $value: expr) => {
+        match normalize_account_value(&$value) {
+            Some(value) => value
+                .parse()
+                .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
+            None => return Ok(None),
+        }
+    };
+}
+
 macro_rules! decode_account_attr {
     ($attr_var: ident, $value: expr, $currency: expr) => {
         account::Attribute::$attr_var(
-            $value
-                .parse()
-                .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
+            parse_account_value!($attr_var, $value),
             $currency
                 .parse()
                 .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
         )
     };
     ($attr_var: ident, $value: expr) => {
-        account::Attribute::$attr_var(
-            $value
-                .parse()
-                .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-        )
+        account::Attribute::$attr_var(parse_account_value!($attr_var, $value))
     };
 }
 
@@ -88,41 +106,25 @@ macro_rules! impl_seg_variants {
     ($root_name: literal, $attr_var: ident, $name: expr, $value: expr, $currency: expr) => {{
         match $name.as_str() {
             concat!($root_name) => account::Attribute::$attr_var(
-                account::Segment::Total(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ),
+                account::Segment::Total(parse_account_value!($attr_var, $value)),
                 $currency
                     .parse()
                     .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
             ),
             concat!($root_name, "-C") => account::Attribute::$attr_var(
-                account::Segment::Commodity(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ),
+                account::Segment::Commodity(parse_account_value!($attr_var, $value)),
                 $currency
                     .parse()
                     .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
             ),
             concat!($root_name, "-P") => account::Attribute::$attr_var(
-                account::Segment::Paxos(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ),
+                account::Segment::Paxos(parse_account_value!($attr_var, $value)),
                 $currency
                     .parse()
                     .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
             ),
             concat!($root_name, "-S") => account::Attribute::$attr_var(
-                account::Segment::Security(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ),
+                account::Segment::Security(parse_account_value!($attr_var, $value)),
                 $currency
                     .parse()
                     .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
@@ -132,32 +134,18 @@ macro_rules! impl_seg_variants {
     }};
     ($root_name: literal, $attr_var: ident, $name: expr, $value: expr) => {{
         match $name.as_str() {
-            stringify!($attr_var) => account::Attribute::$attr_var(account::Segment::Total(
-                $value
-                    .parse()
-                    .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-            )),
-            concat!(stringify!($attr_var), "-C") => {
-                account::Attribute::$attr_var(account::Segment::Commodity(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ))
-            }
-            concat!(stringify!($attr_var), "-P") => {
-                account::Attribute::$attr_var(account::Segment::Paxos(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ))
-            }
-            concat!(stringify!($attr_var), "-S") => {
-                account::Attribute::$attr_var(account::Segment::Security(
-                    $value
-                        .parse()
-                        .map_err(|e| ParseAttributeError::from((stringify!($attr_var), e)))?,
-                ))
-            }
+            stringify!($attr_var) => account::Attribute::$attr_var(
+                account::Segment::Total(parse_account_value!($attr_var, $value)),
+            ),
+            concat!(stringify!($attr_var), "-C") => account::Attribute::$attr_var(
+                account::Segment::Commodity(parse_account_value!($attr_var, $value)),
+            ),
+            concat!(stringify!($attr_var), "-P") => account::Attribute::$attr_var(
+                account::Segment::Paxos(parse_account_value!($attr_var, $value)),
+            ),
+            concat!(stringify!($attr_var), "-S") => account::Attribute::$attr_var(
+                account::Segment::Security(parse_account_value!($attr_var, $value)),
+            ),
             _ => unreachable!(),
         }
     }};
@@ -167,8 +155,9 @@ macro_rules! impl_seg_variants {
 pub trait Local: wrapper::LocalWrapper {
     #[inline]
     fn tick_price_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
@@ -189,6 +178,22 @@ pub trait Local: wrapper::LocalWrapper {
             }
 
             match tick_type {
+                1 | 2 if rx.combined_quotes() => {
+                    let quote = if tick_type == 1 {
+                        Quote::Bid { price, size }
+                    } else {
+                        Quote::Ask { price, size }
+                    };
+                    wrapper.bid_ask_quote(req_id, Class::Live(quote)).await;
+                }
+                66 | 67 if rx.combined_quotes() => {
+                    let quote = if tick_type == 66 {
+                        Quote::Bid { price, size }
+                    } else {
+                        Quote::Ask { price, size }
+                    };
+                    wrapper.bid_ask_quote(req_id, Class::Delayed(quote)).await;
+                }
                 1 | 2 | 4 | 6 | 7 | 9 | 14 => {
                     let (price, size) = match (tick_type, size) {
                         (1, Some(sz)) => (Price::Bid(price), Some(Size::Bid(sz))),
@@ -221,7 +226,10 @@ pub trait Local: wrapper::LocalWrapper {
                     wrapper.extreme_data(req_id, value).await;
                 }
                 35 => {
-                    wrapper.auction(req_id, AuctionData::Price(price)).await;
+                    let auction = AuctionData::Price(price);
+                    wrapper.auction(req_id, auction).await;
+                    let update = rx.shared().update_auction(req_id, auction);
+                    wrapper.auction_update(req_id, update).await;
                 }
                 37 | 79 => {
                     let mark = match tick_type {
@@ -290,8 +298,9 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn tick_size_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
@@ -300,22 +309,23 @@ pub trait Local: wrapper::LocalWrapper {
                     tick_type @ 0: u16,
                     value @ 0: f64
             );
-            Self::decode_generic_tick_msg(req_id, tick_type, value, wrapper).await
+            Self::decode_generic_tick_msg(req_id, tick_type, value, wrapper, rx).await
         }
     }
 
     #[inline]
     fn order_status_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
             fields =>
                 order_id @ 1: i64,
                 status @ 0: String,
-                filled @ 0: f64,
-                remaining @ 0: f64,
+                filled @ 0: Quantity,
+                remaining @ 0: Quantity,
                 average_price @ 0: f64,
                 permanent_id @ 0: i64,
                 parent_id @ 0: i64,
@@ -334,7 +344,7 @@ pub trait Local: wrapper::LocalWrapper {
             } else {
                 Some(market_cap_price)
             };
-            let fill = if filled == 0.0 && average_price == 0.0 && last_price == 0.0 {
+            let fill = if filled.is_zero() && average_price == 0.0 && last_price == 0.0 {
                 None
             } else {
                 Some(Fill {
@@ -359,13 +369,11 @@ pub trait Local: wrapper::LocalWrapper {
                 market_cap_price,
             };
 
-            wrapper
-                .order_status(
-                    (status.as_str(), core)
-                        .try_into()
-                        .map_err(|e| ("order_status", e))?,
-                )
-                .await;
+            let status: crate::payload::OrderStatus = (status.as_str(), core)
+                .try_into()
+                .map_err(|e| ("order_status", e))?;
+            rx.shared().update_order_status(&status);
+            wrapper.order_status(status).await;
 
             Ok(())
         }
@@ -373,7 +381,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     // todo: Implement a proper Error Enum
-    fn err_msg_msg(fields: &mut Fields, wrapper: &mut Self) -> impl Future<Output = DecodeResult> {
+    fn err_msg_msg(fields: &mut Fields<'_>, wrapper: &mut Self) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
                 fields =>
@@ -382,9 +390,13 @@ pub trait Local: wrapper::LocalWrapper {
                     error_string @ 0: String,
                     advanced_order_reject_json @ 0: String
             );
-            wrapper
-                .error(req_id, error_code, error_string, advanced_order_reject_json)
-                .await;
+            if let Some(status) = DataFarmStatus::from_error(error_code, &error_string) {
+                wrapper.data_farm_status(status).await;
+            } else {
+                wrapper
+                    .error(req_id, error_code, error_string, advanced_order_reject_json)
+                    .await;
+            }
             Ok(())
         }
     }
@@ -392,7 +404,7 @@ pub trait Local: wrapper::LocalWrapper {
     #[inline]
     // todo! Many useful fields are missing from this decoding
     fn open_order_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -405,17 +417,24 @@ pub trait Local: wrapper::LocalWrapper {
             )?;
             decode_fields!(
                 fields =>
-                    client_id @ 11: i64,
+                    total_quantity @ 1: Quantity,
+                    order_type @ 0: String,
+                    client_id @ 8: i64,
                     permanent_id @ 0: i64,
-                    parent_id @ 32: i64,
+                    parent_id @ 31: i64,
             );
             let parent_id = if parent_id == 0 {
                 None
             } else {
                 Some(parent_id)
             };
+            let cash_quantity = decode_open_order_cash_quantity(fields, &order_type)?;
+            let amount = match cash_quantity {
+                Some(cash) => OrderAmount::Cash(cash),
+                None => OrderAmount::Quantity(total_quantity),
+            };
             wrapper
-                .open_order(order_id, proxy, client_id, parent_id, permanent_id)
+                .open_order(order_id, proxy, amount, client_id, parent_id, permanent_id)
                 .await;
 
             Ok(())
@@ -424,7 +443,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn acct_value_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -435,227 +454,16 @@ pub trait Local: wrapper::LocalWrapper {
                     currency @ 0: String,
                     account_number @ 0: String
             );
-            let attribute = match name.as_str() {
-                "AccountCode" => account::Attribute::AccountCode(value),
-                "AccountOrGroup" => decode_account_attr!(AccountOrGroup, value, currency),
-                "AccountReady" => decode_account_attr!(AccountReady, value),
-                "AccountType" => account::Attribute::AccountType(value),
-                expand_seg_variants!("AccruedCash") => {
-                    impl_seg_variants!("AccruedCash", AccruedCash, name, value, currency)
-                }
-                expand_seg_variants!("AccruedDividend") => {
-                    impl_seg_variants!("AccruedDividend", AccruedDividend, name, value, currency)
-                }
-                expand_seg_variants!("AvailableFunds") => {
-                    impl_seg_variants!("AvailableFunds", AvailableFunds, name, value, currency)
-                }
-                expand_seg_variants!("Billable") => {
-                    impl_seg_variants!("Billable", Billable, name, value, currency)
-                }
-                "BuyingPower" => decode_account_attr!(BuyingPower, value, currency),
-                "CashBalance" => decode_account_attr!(CashBalance, value, currency),
-                expand_seg_variants!("ColumnPrio") => {
-                    impl_seg_variants!("ColumnPrio", ColumnPrio, name, value)
-                }
-                "CorporateBondValue" => decode_account_attr!(CorporateBondValue, value, currency),
-                "Cryptocurrency" => decode_account_attr!(Cryptocurrency, value, currency),
-                "Currency" => decode_account_attr!(Currency, value),
-                "Cushion" => decode_account_attr!(Cushion, value),
-                "DayTradesRemaining" => decode_account_attr!(DayTradesRemaining, value),
-                "DayTradesRemainingT+1" => decode_account_attr!(DayTradesRemainingTPlus1, value),
-                "DayTradesRemainingT+2" => decode_account_attr!(DayTradesRemainingTPlus2, value),
-                "DayTradesRemainingT+3" => decode_account_attr!(DayTradesRemainingTPlus3, value),
-                "DayTradesRemainingT+4" => decode_account_attr!(DayTradesRemainingTPlus4, value),
-                "DayTradingStatus-S" => account::Attribute::DayTradingStatus(value),
-                expand_seg_variants!("EquityWithLoanValue") => impl_seg_variants!(
-                    "EquityWithLoanValue",
-                    EquityWithLoanValue,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("ExcessLiquidity") => {
-                    impl_seg_variants!("ExcessLiquidity", ExcessLiquidity, name, value, currency)
-                }
-                "ExchangeRate" => decode_account_attr!(ExchangeRate, value, currency),
-                expand_seg_variants!("FullAvailableFunds") => impl_seg_variants!(
-                    "FullAvailableFunds",
-                    FullAvailableFunds,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("FullExcessLiquidity") => impl_seg_variants!(
-                    "FullExcessLiquidity",
-                    FullExcessLiquidity,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("FullInitMarginReq") => impl_seg_variants!(
-                    "FullInitMarginReq",
-                    FullInitMarginReq,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("FullMaintMarginReq") => impl_seg_variants!(
-                    "FullMaintMarginReq",
-                    FullMaintenanceMarginReq,
-                    name,
-                    value,
-                    currency
-                ),
-                "FundValue" => decode_account_attr!(FundValue, value, currency),
-                "FutureOptionValue" => decode_account_attr!(FutureOptionValue, value, currency),
-                "FuturesPNL" => decode_account_attr!(FuturesPnl, value, currency),
-                "FxCashBalance" => decode_account_attr!(FxCashBalance, value, currency),
-                "GrossPositionValue" => decode_account_attr!(GrossPositionValue, value, currency),
-                "GrossPositionValue-S" => {
-                    decode_account_attr!(GrossPositionValueSecurity, value, currency)
-                }
-                expand_seg_variants!("Guarantee") => {
-                    impl_seg_variants!("Guarantee", Guarantee, name, value, currency)
-                }
-                expand_seg_variants!("IncentiveCoupons") => {
-                    impl_seg_variants!("IncentiveCoupons", IncentiveCoupons, name, value, currency)
-                }
-                expand_seg_variants!("IndianStockHaircut") => impl_seg_variants!(
-                    "IndianStockHaircut",
-                    IndianStockHaircut,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("InitMarginReq") => {
-                    impl_seg_variants!("InitMarginReq", InitMarginReq, name, value, currency)
-                }
-                "IssuerOptionValue" => decode_account_attr!(IssuerOptionValue, value, currency),
-                "Leverage-S" => decode_account_attr!(LeverageSecurity, value),
-                "LookAheadNextChange" => decode_account_attr!(LookAheadNextChange, value),
-                expand_seg_variants!("LookAheadAvailableFunds") => impl_seg_variants!(
-                    "LookAheadAvailableFunds",
-                    LookAheadAvailableFunds,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("LookAheadExcessLiquidity") => impl_seg_variants!(
-                    "LookAheadExcessLiquidity",
-                    LookAheadExcessLiquidity,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("LookAheadInitMarginReq") => impl_seg_variants!(
-                    "LookAheadInitMarginReq",
-                    LookAheadInitMarginReq,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("LookAheadMaintMarginReq") => impl_seg_variants!(
-                    "LookAheadMaintMarginReq",
-                    LookAheadMaintenanceMarginReq,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("MaintMarginReq") => impl_seg_variants!(
-                    "MaintMarginReq",
-                    MaintenanceMarginReq,
-                    name,
-                    value,
-                    currency
-                ),
-                "MoneyMarketFundValue" => {
-                    decode_account_attr!(MoneyMarketFundValue, value, currency)
-                }
-                "MutualFundValue" => decode_account_attr!(MutualFundValue, value, currency),
-                "NLVAndMarginInReview" => decode_account_attr!(NlvAndMarginInReview, value),
-                "NetDividend" => decode_account_attr!(NetDividend, value, currency),
-                expand_seg_variants!("NetLiquidation") => {
-                    impl_seg_variants!("NetLiquidation", NetLiquidation, name, value, currency)
-                }
-                "NetLiquidationByCurrency" => {
-                    decode_account_attr!(NetLiquidationByCurrency, value, currency)
-                }
-                "NetLiquidationUncertainty" => {
-                    decode_account_attr!(NetLiquidationUncertainty, value, currency)
-                }
-                "OptionMarketValue" => decode_account_attr!(OptionMarketValue, value, currency),
-                expand_seg_variants!("PASharesValue") => {
-                    impl_seg_variants!("PASharesValue", PaSharesValue, name, value, currency)
-                }
-                expand_seg_variants!("PhysicalCertificateValue") => impl_seg_variants!(
-                    "PhysicalCertificateValue",
-                    PhysicalCertificateValue,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("PostExpirationExcess") => impl_seg_variants!(
-                    "PostExpirationExcess",
-                    PostExpirationExcess,
-                    name,
-                    value,
-                    currency
-                ),
-                expand_seg_variants!("PostExpirationMargin") => impl_seg_variants!(
-                    "PostExpirationMargin",
-                    PostExpirationMargin,
-                    name,
-                    value,
-                    currency
-                ),
-                "PreviousDayEquityWithLoanValue" => {
-                    decode_account_attr!(PreviousDayEquityWithLoanValue, value, currency)
-                }
-                "PreviousDayEquityWithLoanValue-S" => {
-                    decode_account_attr!(PreviousDayEquityWithLoanValueSecurity, value, currency)
-                }
-                "RealCurrency" => decode_account_attr!(RealCurrency, value),
-                "RealizedPnL" => decode_account_attr!(RealizedPnL, value, currency),
-                "RegTEquity" => decode_account_attr!(RegTEquity, value, currency),
-                "RegTEquity-S" => decode_account_attr!(RegTEquitySecurity, value, currency),
-                "RegTMargin" => decode_account_attr!(RegTMargin, value, currency),
-                "RegTMargin-S" => decode_account_attr!(RegTMarginSecurity, value, currency),
-                "SMA" => decode_account_attr!(Sma, value, currency),
-                "SMA-S" => decode_account_attr!(SmaSecurity, value, currency),
-                "StockMarketValue" => decode_account_attr!(StockMarketValue, value, currency),
-                "TBillValue" => decode_account_attr!(TBillValue, value, currency),
-                "TBondValue" => decode_account_attr!(TBondValue, value, currency),
-                "TotalCashBalance" => decode_account_attr!(TotalCashBalance, value, currency),
-                expand_seg_variants!("TotalCashValue") => {
-                    impl_seg_variants!("TotalCashValue", TotalCashValue, name, value, currency)
-                }
-                expand_seg_variants!("TotalDebitCardPendingCharges") => impl_seg_variants!(
-                    "TotalDebitCardPendingCharges",
-                    TotalDebitCardPendingCharges,
-                    name,
-                    value,
-                    currency
-                ),
-                "TradingType-S" => account::Attribute::TradingTypeSecurity(value),
-                "UnrealizedPnL" => decode_account_attr!(UnrealizedPnL, value, currency),
-                "WarrantValue" => decode_account_attr!(WarrantValue, value, currency),
-                "WhatIfPMEnabled" => decode_account_attr!(WhatIfPMEnabled, value),
-                expand_seg_variants!("SegmentTitle") => {
-                    if name.ends_with('C') || name.ends_with('P') || name.ends_with('S') {
-                        return Ok(());
-                    }
-                    return Err(ParseAttributeError::NoSuchAttribute(format!("Unexpected segment title \"{name}\" encountered. This may mandate an API update: currently-supported values are C, P, and S as outlined in the account::Segment type.")).into());
-                }
-                _ => return Err(ParseAttributeError::NoSuchAttribute(name).into()),
-            };
-            wrapper.account_attribute(attribute, account_number).await;
+            if let Some(attribute) = decode_account_attribute(name, value, currency)? {
+                wrapper.account_attribute(attribute, account_number).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn portfolio_value_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -663,7 +471,7 @@ pub trait Local: wrapper::LocalWrapper {
             let proxy = deserialize_contract_proxy(fields)?;
             decode_fields!(
                 fields =>
-                    position @ 0: f64,
+                    position @ 0: Quantity,
                     market_price @ 0: f64,
                     market_value @ 0: f64,
                     average_cost @ 0: f64,
@@ -689,7 +497,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn acct_update_time_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -709,27 +517,40 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn next_valid_id_msg(
-        _fields: &mut Fields,
-        _wrapper: &mut Self,
+        fields: &mut Fields<'_>,
+        wrapper: &mut Self,
         _tx: &mut Tx,
-        _rx: &mut Rx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
-        async move { Ok(()) }
+        async move {
+            decode_fields!(
+                fields =>
+                    valid_id @ 2: i64
+            );
+            rx.shared().sync_order_id(valid_id);
+            wrapper.next_valid_id(valid_id).await;
+            Ok(())
+        }
     }
 
     #[inline]
     fn contract_data_msg(
-        fields: &mut Fields,
-        _wrapper: &mut Self,
+        fields: &mut Fields<'_>,
+        wrapper: &mut Self,
         tx: &mut Tx,
         rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
-        async move { decode_contract_no_wrapper(fields, tx, rx).await }
+        async move {
+            if let Some((req_id, contract)) = decode_contract_no_wrapper(fields, tx, rx).await? {
+                wrapper.contract_data(req_id, contract).await;
+            }
+            Ok(())
+        }
     }
 
     #[inline]
     fn execution_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -745,16 +566,32 @@ pub trait Local: wrapper::LocalWrapper {
                     datetime @ 0: String,
                     account_number @ 0: String,
                     exchange @ 0: Primary,
-                    side @ 0: OrderSide,
-                    quantity @ 0: f64,
+                    side @ 0: String,
+                    quantity @ 0: Quantity,
                     price @ 0: f64,
                     perm_id @ 0: i64,
                     client_id @ 0: i64,
                     liquidation @ 0: u8,
-                    cumulative_quantity @ 0: f64,
+                    cumulative_quantity @ 0: Quantity,
                     average_price @ 0: f64,
-                    pending_price_revision @ 5: u8
+                    ev_rule @ 1: String
+            );
+            decode_fields!(
+                fields => ev_multiplier @ 0: Option<f64>
             );
+            decode_fields!(
+                fields => model_code @ 0: String
+            );
+            decode_fields!(
+                fields => last_liquidity @ 0: Option<i64>
+            );
+            decode_fields!(
+                fields => pending_price_revision @ 0: u8
+            );
+            let short_sale = OrderSide::is_short_sale(&side);
+            let side = side
+                .parse::<OrderSide>()
+                .map_err(|e| DecodeError::from(("side", e)))?;
 
             let (dt, tz) = NaiveDateTime::parse_and_remainder(datetime.as_str(), "%Y%m%d %T ")
                 .map_err(|e| ("datetime", ParseDateTimeError::Parse(e)))?;
@@ -782,6 +619,11 @@ pub trait Local: wrapper::LocalWrapper {
                     cumulative_quantity,
                     average_price,
                     pending_price_revision: pending_price_revision.ne(&0),
+                    short_sale,
+                    ev_rule: Some(ev_rule).filter(|r| !r.is_empty()),
+                    ev_multiplier,
+                    model_code: Some(model_code).filter(|m| !m.is_empty()),
+                    last_liquidity: last_liquidity.and_then(Liquidity::from_code),
                 },
                 side,
             ));
@@ -791,9 +633,10 @@ pub trait Local: wrapper::LocalWrapper {
         }
     }
 
+    #[cfg(feature = "depth")]
     #[inline]
     fn market_depth_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -818,17 +661,21 @@ pub trait Local: wrapper::LocalWrapper {
         }
     }
 
+    #[cfg(feature = "depth")]
     #[inline]
     fn market_depth_l2_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
                 fields =>
                     req_id @ 2: i64,
-                    position  @ 0: u64,
-                    market_maker @ 0: String,
+                    position  @ 0: u64
+            );
+            let market_maker = nth(fields, 0, "market_maker")?;
+            decode_fields!(
+                fields =>
                     operation @ 0: i64,
                     side @ 0: u32,
                     price @ 0: f64,
@@ -837,15 +684,18 @@ pub trait Local: wrapper::LocalWrapper {
             );
             let entry = Entry::try_from((side, position, price, size)).map_err(|e| ("entry", e))?;
             let entry = match is_smart {
-                0 => CompleteEntry::MarketMaker {
-                    market_maker: market_maker
-                        .chars()
-                        .take(4)
-                        .collect::<Vec<char>>()
-                        .try_into()
-                        .map_err(|_| ("market_maker", ParsePayloadError::Mpid))?,
-                    entry,
-                },
+                0 => {
+                    let mut chars = market_maker.chars();
+                    let market_maker =
+                        match [chars.next(), chars.next(), chars.next(), chars.next()] {
+                            [Some(a), Some(b), Some(c), Some(d)] => [a, b, c, d],
+                            _ => Err(("market_maker", ParsePayloadError::Mpid))?,
+                        };
+                    CompleteEntry::MarketMaker {
+                        market_maker,
+                        entry,
+                    }
+                }
                 _ => CompleteEntry::SmartDepth {
                     exchange: market_maker.parse().map_err(|e| ("exchange", e))?,
                     entry,
@@ -859,89 +709,97 @@ pub trait Local: wrapper::LocalWrapper {
         }
     }
 
+    #[cfg(feature = "news")]
     #[inline]
     fn news_bulletins_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
     #[inline]
     fn managed_accts_msg(
-        _fields: &mut Fields,
-        _wrapper: &mut Self,
+        fields: &mut Fields<'_>,
+        wrapper: &mut Self,
         _tx: &mut Tx,
-        _rx: &mut Rx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
-        async move { Ok(()) }
+        async move {
+            let accounts = decode_managed_accounts(fields)?;
+            rx.shared().sync_managed_accounts(accounts.clone());
+            wrapper.managed_accounts(accounts).await;
+            Ok(())
+        }
     }
 
+    #[cfg(feature = "fa")]
     #[inline]
     fn receive_fa_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn historical_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(
-                fields =>
-                    req_id @ 1: i64,
-                    start_date_str @ 0: String,
-                    end_date_str @ 0: String,
-                    count @ 0: usize
-            );
-            let start_datetime = parse_historical_datetime(&start_date_str)
-                .map_err(|e| ("start_datetime", e))?;
-            let end_datetime = parse_historical_datetime(&end_date_str)
-                .map_err(|e| ("end_datetime", e))?;
+            decode_fields!(fields => req_id @ 1: i64);
+            // Daily bars are sent as bare dates in the exchange's timezone, which is only given by
+            // the start and end datetimes.
+            let (start_datetime, tz) =
+                parse_historical_datetime(nth(fields, 0, "start_datetime")?, chrono_tz::UTC)
+                    .map_err(|e| ("start_datetime", e))?;
+            let (end_datetime, tz) =
+                parse_historical_datetime(nth(fields, 0, "end_datetime")?, tz)
+                    .map_err(|e| ("end_datetime", e))?;
+            let count = nth_count(fields, 0, "count", 8)?;
 
             let mut bars = Vec::with_capacity(count);
-            for chunk in fields.collect::<Vec<String>>().chunks(8) {
-                if let [datetime_str, open, high, low, close, volume, wap, trade_count] = chunk {
-                    let datetime = parse_historical_datetime(datetime_str)
-                        .map_err(|e| ("datetime", e))?;
-
-                    let core = BarCore {
-                        datetime,
-                        open: open.parse().map_err(|e| ("open", e))?,
-                        high: high.parse().map_err(|e| ("high", e))?,
-                        low: low.parse().map_err(|e| ("low", e))?,
-                        close: close.parse().map_err(|e| ("close", e))?,
-                    };
-                    let (volume, wap, trade_count) = (
-                        volume.parse().map_err(|e| ("volume", e))?,
-                        wap.parse().map_err(|e| ("wap", e))?,
-                        trade_count.parse::<i64>().map_err(|e| ("trade_count", e))?,
-                    );
-                    let bar = if volume > 0. && wap > 0. && trade_count > 0 {
-                        Bar::Trades(Trade {
-                            bar: core,
-                            volume,
-                            wap,
-                            trade_count: trade_count.try_into().map_err(|_| {
-                                DecodeError::UnexpectedData(
-                                    "trade_count could not be converted to unsigned integer.",
-                                )
-                            })?,
-                        })
-                    } else {
-                        Bar::Ordinary(core)
-                    };
-                    bars.push(bar);
-                }
+            for _ in 0..count {
+                let (datetime, _) = parse_historical_datetime(nth(fields, 0, "datetime")?, tz)
+                    .map_err(|e| ("datetime", e))?;
+                decode_fields!(
+                    fields =>
+                        open @ 0: f64,
+                        high @ 0: f64,
+                        low @ 0: f64,
+                        close @ 0: f64,
+                        volume @ 0: f64,
+                        wap @ 0: f64,
+                        trade_count @ 0: i64
+                );
+                let core = BarCore {
+                    datetime,
+                    open,
+                    high,
+                    low,
+                    close,
+                };
+                let bar = if volume > 0. && wap > 0. && trade_count > 0 {
+                    Bar::Trades(Trade {
+                        bar: core,
+                        volume,
+                        wap,
+                        trade_count: trade_count.try_into().map_err(|_| {
+                            DecodeError::UnexpectedData(
+                                "trade_count could not be converted to unsigned integer.",
+                            )
+                        })?,
+                    })
+                } else {
+                    Bar::Ordinary(core)
+                };
+                bars.push(bar);
             }
             wrapper
                 .historical_bars(req_id, start_datetime, end_datetime, bars)
@@ -952,40 +810,82 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn bond_contract_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "scanner")]
     #[inline]
     fn scanner_parameters_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(fields => xml @ 2: String);
+            wrapper.scanner_parameters(xml).await;
             Ok(())
         }
     }
 
+    #[cfg(feature = "scanner")]
     #[inline]
     fn scanner_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 2: i64
+            );
+            let count = nth_count(fields, 0, "count", 16)?;
+            let mut rows = Vec::with_capacity(count);
+            for _ in 0..count {
+                decode_fields!(
+                    fields =>
+                        rank @ 0: u32,
+                        contract_id @ 0: ContractId,
+                        symbol @ 0: String,
+                        security_type @ 0: String,
+                        exchange @ 3: String,
+                        currency @ 0: String,
+                        local_symbol @ 0: String,
+                        market_name @ 0: String,
+                        trading_class @ 0: String,
+                        distance @ 0: String,
+                        benchmark @ 0: String,
+                        projection @ 0: String,
+                        legs @ 0: String
+                );
+                rows.push(ScannerRow {
+                    rank,
+                    contract_id,
+                    symbol,
+                    security_type,
+                    exchange,
+                    currency,
+                    local_symbol,
+                    market_name,
+                    trading_class,
+                    distance,
+                    benchmark,
+                    projection,
+                    legs,
+                });
+            }
+            wrapper.scanner_data(req_id, rows).await;
             Ok(())
         }
     }
 
     #[inline]
     fn tick_option_computation_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1038,7 +938,11 @@ pub trait Local: wrapper::LocalWrapper {
                     83 => SecOptionCalculationSource::Model(calc),
                     _ => unreachable!(),
                 }),
-                _ => unreachable!(),
+                _ => {
+                    return Err(DecodeError::UnexpectedData(
+                        "Unexpected option calculation tick type.",
+                    ))
+                }
             };
             wrapper.sec_option_computation(req_id, calc).await;
 
@@ -1048,8 +952,9 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn tick_generic_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
@@ -1058,13 +963,13 @@ pub trait Local: wrapper::LocalWrapper {
                     tick_type @ 0: u16,
                     value @ 0: f64
             );
-            Self::decode_generic_tick_msg(req_id, tick_type, value, wrapper).await
+            Self::decode_generic_tick_msg(req_id, tick_type, value, wrapper, rx).await
         }
     }
 
     #[inline]
     fn tick_string_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1157,41 +1062,13 @@ pub trait Local: wrapper::LocalWrapper {
                     };
                     wrapper.real_time_volume(req_id, volume).await;
                 }
+                47 => {
+                    wrapper
+                        .fundamental_ratios(req_id, decode_fundamental_ratios(&value)?)
+                        .await;
+                }
                 59 => {
-                    let mut divs = value.split(',');
-                    let dividends = Dividends {
-                        trailing_year: divs
-                            .next()
-                            .ok_or(DecodeError::MissingData {
-                                field_name: "trailing_year",
-                            })?
-                            .parse()
-                            .map_err(|e| ("trailing_year", e))?,
-                        forward_year: divs
-                            .next()
-                            .ok_or(DecodeError::MissingData {
-                                field_name: "forward_year",
-                            })?
-                            .parse()
-                            .map_err(|e| ("forward_year", e))?,
-                        next_dividend: (
-                            NaiveDate::parse_and_remainder(
-                                divs.next().ok_or(DecodeError::MissingData {
-                                    field_name: "next_dividend",
-                                })?,
-                                "%Y%m%d",
-                            )
-                            .map_err(|e| ("next_dividend", ParseDateTimeError::Parse(e)))?
-                            .0,
-                            divs.next()
-                                .ok_or(DecodeError::MissingData {
-                                    field_name: "next_price",
-                                })?
-                                .parse()
-                                .map_err(|e| ("next_dividend", e))?,
-                        ),
-                    };
-                    wrapper.dividends(req_id, dividends).await;
+                    wrapper.dividends(req_id, decode_dividends(&value)?).await;
                 }
                 62 => {
                     wrapper.news(req_id, value).await;
@@ -1208,7 +1085,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn tick_efp_msg(
-        _fields: &mut Fields,
+        _fields: &mut Fields<'_>,
         _wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1218,7 +1095,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn current_time_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1241,7 +1118,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn real_time_bars_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1286,30 +1163,33 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn fundamental_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn contract_data_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(fields => req_id @ 2: i64);
-            wrapper.contract_data_end(req_id).await;
+            if let Some(req_id) = decode_contract_end_no_wrapper(fields, tx, rx).await? {
+                wrapper.contract_data_end(req_id).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn open_order_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1320,7 +1200,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn acct_download_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1334,7 +1214,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn execution_data_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1349,18 +1229,18 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn delta_neutral_validation_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn tick_snapshot_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1374,7 +1254,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn market_data_type_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1390,7 +1270,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn commission_report_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1421,7 +1301,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn position_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1432,7 +1312,7 @@ pub trait Local: wrapper::LocalWrapper {
             let contract = deserialize_contract_proxy(fields)?;
             decode_fields!(
                 fields =>
-                    position @ 0: f64,
+                    position @ 0: Quantity,
                     average_cost @ 0: f64
             );
             wrapper
@@ -1449,7 +1329,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn position_end_msg(
-        _fields: &mut Fields,
+        _fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1460,7 +1340,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn account_summary_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1468,32 +1348,43 @@ pub trait Local: wrapper::LocalWrapper {
                 fields =>
                     req_id @ 2: i64,
                     account_number @ 0: String,
-                    tag @ 0: Tag,
+                    tag @ 0: String,
                     value @ 0: String,
                     currency @ 0: String
             );
-            let summary = match tag {
-                Tag::AccountType => TagValue::String(Tag::AccountType, value),
-                Tag::Cushion => {
-                    TagValue::Float(Tag::Cushion, value.parse().map_err(|e| ("summary", e))?)
-                }
-                Tag::LookAheadNextChange => TagValue::Int(
-                    Tag::LookAheadNextChange,
-                    value.parse().map_err(|e| ("summary", e))?,
-                ),
-                Tag::HighestSeverity => TagValue::String(Tag::HighestSeverity, value),
-                Tag::DayTradesRemaining => TagValue::Int(
-                    Tag::DayTradesRemaining,
-                    value.parse().map_err(|e| ("summary", e))?,
-                ),
-                Tag::Leverage => {
-                    TagValue::Float(Tag::Leverage, value.parse().map_err(|e| ("summary", e))?)
-                }
-                t => TagValue::Currency(
-                    t,
-                    value.parse().map_err(|e| ("summary", e))?,
-                    currency.parse().map_err(|e| ("summary", e))?,
-                ),
+            // Ledger rows always carry the ledger's currency (or "BASE"), and some share their
+            // name with a tag (ex. "AccruedCash"), so only rows in a specific currency can be
+            // decoded as a tag.
+            let summary = match tag.parse::<Tag>() {
+                Ok(tag) if !currency.eq_ignore_ascii_case("BASE") => match tag {
+                    Tag::AccountType => TagValue::String(Tag::AccountType, value),
+                    Tag::Cushion => {
+                        TagValue::Float(Tag::Cushion, value.parse().map_err(|e| ("summary", e))?)
+                    }
+                    Tag::LookAheadNextChange => TagValue::Int(
+                        Tag::LookAheadNextChange,
+                        value.parse().map_err(|e| ("summary", e))?,
+                    ),
+                    Tag::HighestSeverity => TagValue::String(Tag::HighestSeverity, value),
+                    Tag::DayTradesRemaining => TagValue::Int(
+                        Tag::DayTradesRemaining,
+                        value.parse().map_err(|e| ("summary", e))?,
+                    ),
+                    Tag::Leverage => {
+                        TagValue::Float(Tag::Leverage, value.parse().map_err(|e| ("summary", e))?)
+                    }
+                    t => TagValue::Currency(
+                        t,
+                        value.parse().map_err(|e| ("summary", e))?,
+                        currency.parse().map_err(|e| ("summary", e))?,
+                    ),
+                },
+                Err(e) if currency.is_empty() => return Err(("tag", e).into()),
+                _ => TagValue::Ledger {
+                    tag,
+                    value,
+                    denomination: currency.parse().map_err(|e| ("summary", e))?,
+                },
             };
             wrapper
                 .account_summary(req_id, account_number, summary)
@@ -1504,7 +1395,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn account_summary_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1518,184 +1409,249 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn verify_message_api_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             // This function is deprecated
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn verify_completed_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn display_group_list_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn display_group_updated_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn verify_and_auth_message_api_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn verify_and_auth_completed_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn position_multi_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 2: i64,
+                    account_number @ 0: String
+            );
+            let contract = deserialize_contract_proxy(fields)?;
+            decode_fields!(
+                fields =>
+                    position @ 0: Quantity,
+                    average_cost @ 0: f64,
+                    model_code @ 0: String
+            );
+            let model_code = (!model_code.is_empty()).then_some(model_code);
+            wrapper
+                .position_multi(
+                    req_id,
+                    PositionSummary {
+                        contract,
+                        position,
+                        average_cost,
+                        account_number,
+                    },
+                    model_code,
+                )
+                .await;
             Ok(())
         }
     }
 
     #[inline]
     fn position_multi_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 2: i64
+            );
+            wrapper.position_multi_end(req_id).await;
             Ok(())
         }
     }
 
     #[inline]
     fn account_update_multi_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 2: i64,
+                    account_number @ 0: String,
+                    model_code @ 0: String,
+                    name @ 0: String,
+                    value @ 0: String,
+                    currency @ 0: String
+            );
+            let model_code = (!model_code.is_empty()).then_some(model_code);
+            if let Some(attribute) = decode_account_attribute(name, value, currency)? {
+                wrapper
+                    .account_attribute_multi(req_id, attribute, account_number, model_code)
+                    .await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn account_update_multi_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 2: i64
+            );
+            wrapper.account_attribute_multi_end(req_id).await;
             Ok(())
         }
     }
 
     #[inline]
     fn security_definition_option_parameter_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            if let Some((req_id, params)) =
+                decode_option_parameters_no_wrapper(fields, tx, rx).await?
+            {
+                wrapper.option_parameters(req_id, params).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn security_definition_option_parameter_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 1: i64
+            );
+            if rx.take_awaited_option_parameters(req_id) {
+                tx.send(ToClient::OptionParametersEnd(req_id))
+                    .await
+                    .map_err(Box::new)?;
+            } else {
+                wrapper.option_parameters_end(req_id).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn soft_dollar_tiers_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn family_codes_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn symbol_samples_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "depth")]
     #[inline]
     fn mkt_depth_exchanges_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn tick_req_params_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1715,124 +1671,138 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn smart_components_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "news")]
     #[inline]
     fn news_article_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "news")]
     #[inline]
     fn tick_news_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "news")]
     #[inline]
     fn news_providers_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "news")]
     #[inline]
     fn historical_news_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            if let Some((req_id, headline)) =
+                decode_historical_news_no_wrapper(fields, tx, rx).await?
+            {
+                wrapper.historical_news(req_id, headline).await;
+            }
             Ok(())
         }
     }
 
+    #[cfg(feature = "news")]
     #[inline]
     fn historical_news_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 1: i64,
+                    has_more @ 0: i32
+            );
+            if rx.take_awaited_historical_news(req_id) {
+                tx.send(ToClient::HistoricalNewsEnd(req_id, has_more != 0))
+                    .await
+                    .map_err(Box::new)?;
+            } else {
+                wrapper.historical_news_end(req_id, has_more != 0).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn head_timestamp_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(
-                fields =>
-                    req_id @ 1: i64,
-                    timestamp @ 0: i64
-            );
-            wrapper
-                .head_timestamp(
-                    req_id,
-                    DateTime::from_timestamp(timestamp, 0)
-                        .ok_or(("timestamp", ParseDateTimeError::Timestamp))?,
-                )
-                .await;
+            if let Some((req_id, timestamp)) =
+                decode_head_timestamp_no_wrapper(fields, tx, rx).await?
+            {
+                wrapper.head_timestamp(req_id, timestamp).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn histogram_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(
-                fields =>
-                    req_id @ 1: i64,
-                    num_points @ 0: usize
-            );
-            let mut hist = std::collections::HashMap::with_capacity(num_points);
-            for (bin, chunk) in fields
+            decode_fields!(fields => req_id @ 1: i64);
+            let num_points = nth_count(fields, 0, "num_points", 2)?;
+            let hist = fields
                 .take(num_points * 2)
                 .map(|v| v.parse())
                 .collect::<Result<Vec<f64>, _>>()
                 .map_err(|e| ("chunk", e))?
                 .chunks_exact(2)
-                .enumerate()
-            {
-                if let [price, size] = *chunk {
-                    hist.insert(bin, HistogramEntry { price, size });
-                }
-            }
-            wrapper.histogram(req_id, hist).await;
+                .map(|chunk| HistogramEntry {
+                    price: chunk[0],
+                    size: chunk[1],
+                })
+                .collect::<Vec<_>>();
+            wrapper.histogram(req_id, hist.into()).await;
             Ok(())
         }
     }
 
     #[inline]
     fn historical_data_update_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1849,10 +1819,9 @@ pub trait Local: wrapper::LocalWrapper {
                     volume @ 0: f64
             );
             let core = BarCore {
-                datetime: NaiveDateTime::parse_and_remainder(datetime_str.as_str(), "%Y%m%d %T")
-                    .map_err(|e| ("datetime", ParseDateTimeError::Parse(e)))?
-                    .0
-                    .and_utc(),
+                datetime: parse_historical_datetime(&datetime_str, chrono_tz::UTC)
+                    .map_err(|e| ("datetime", e))?
+                    .0,
                 open,
                 high,
                 low,
@@ -1879,39 +1848,40 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn reroute_mkt_data_req_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "depth")]
     #[inline]
     fn reroute_mkt_depth_req_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn market_rule_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
-    fn pnl_msg(fields: &mut Fields, wrapper: &mut Self) -> impl Future<Output = DecodeResult> {
+    fn pnl_msg(fields: &mut Fields<'_>, wrapper: &mut Self) -> impl Future<Output = DecodeResult> {
         async move {
             decode_fields!(
                 fields =>
@@ -1932,7 +1902,7 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn pnl_single_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -1959,19 +1929,21 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn historical_ticks_midpoint_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(
-                fields =>
-                    req_id @ 1: i64,
-                    tick_count @ 0: usize
-            );
+            decode_fields!(fields => req_id @ 1: i64);
+            // The prefix of a tick-by-tick subscription is released even if its ticks turn out to
+            // be malformed, so that a later batch for the same request is not mistaken for it.
+            let history = rx.take_tick_by_tick_history(req_id);
+            let tick_count = nth_count(fields, 0, "tick_count", 4)?;
             let mut ticks = Vec::with_capacity(tick_count);
             for chunk in fields
                 .take(tick_count * 4)
-                .collect::<Vec<String>>()
+                .collect::<Vec<_>>()
                 .chunks_exact(4)
             {
                 if let [time, _, price, size] = chunk {
@@ -1982,32 +1954,43 @@ pub trait Local: wrapper::LocalWrapper {
                         )
                         .ok_or(("datetime", ParseDateTimeError::Timestamp))?,
                         price: price.parse().map_err(|e| ("price", e))?,
+                        attributes: TickAttributes::default(),
                     }));
                 }
             }
-            wrapper.historical_ticks(req_id, ticks).await;
+            if history {
+                wrapper.tick_by_tick_history(req_id, ticks).await;
+            } else if rx.take_awaited_historical_ticks(req_id) {
+                tx.send(ToClient::HistoricalTicks(req_id, ticks))
+                    .await
+                    .map_err(Box::new)?;
+            } else {
+                wrapper.historical_ticks(req_id, ticks).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn historical_ticks_bid_ask_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(
-                fields =>
-                    req_id @ 1: i64,
-                    tick_count @ 0: usize
-            );
+            decode_fields!(fields => req_id @ 1: i64);
+            // The prefix of a tick-by-tick subscription is released even if its ticks turn out to
+            // be malformed, so that a later batch for the same request is not mistaken for it.
+            let history = rx.take_tick_by_tick_history(req_id);
+            let tick_count = nth_count(fields, 0, "tick_count", 6)?;
             let mut ticks = Vec::with_capacity(tick_count);
             for chunk in fields
                 .take(tick_count * 6)
-                .collect::<Vec<String>>()
+                .collect::<Vec<_>>()
                 .chunks_exact(6)
             {
-                if let [time, _, bid_price, ask_price, bid_size, ask_size] = chunk {
+                if let [time, mask, bid_price, ask_price, bid_size, ask_size] = chunk {
                     ticks.push(TickData::BidAsk(BidAsk {
                         datetime: DateTime::from_timestamp(
                             time.parse().map_err(|e| ("datetime", e))?,
@@ -2018,32 +2001,45 @@ pub trait Local: wrapper::LocalWrapper {
                         ask_price: ask_price.parse().map_err(|e| ("ask_price", e))?,
                         bid_size: bid_size.parse().map_err(|e| ("bid_size", e))?,
                         ask_size: ask_size.parse().map_err(|e| ("ask_size", e))?,
+                        attributes: TickAttributes::from_bid_ask_mask(
+                            mask.parse().map_err(|e| ("mask", e))?,
+                        ),
                     }));
                 }
             }
-            wrapper.historical_ticks(req_id, ticks).await;
+            if history {
+                wrapper.tick_by_tick_history(req_id, ticks).await;
+            } else if rx.take_awaited_historical_ticks(req_id) {
+                tx.send(ToClient::HistoricalTicks(req_id, ticks))
+                    .await
+                    .map_err(Box::new)?;
+            } else {
+                wrapper.historical_ticks(req_id, ticks).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn historical_ticks_last_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
+        tx: &mut Tx,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            decode_fields!(
-                fields =>
-                    req_id @ 1: i64,
-                    tick_count @ 0: usize
-            );
+            decode_fields!(fields => req_id @ 1: i64);
+            // The prefix of a tick-by-tick subscription is released even if its ticks turn out to
+            // be malformed, so that a later batch for the same request is not mistaken for it.
+            let history = rx.take_tick_by_tick_history(req_id);
+            let tick_count = nth_count(fields, 0, "tick_count", 6)?;
             let mut ticks = Vec::with_capacity(tick_count);
             for chunk in fields
                 .take(tick_count * 6)
-                .collect::<Vec<String>>()
+                .collect::<Vec<_>>()
                 .chunks_exact(6)
             {
-                if let [time, _, price, size, exchange, _] = chunk {
+                if let [time, mask, price, size, exchange, _] = chunk {
                     ticks.push(TickData::Last(Last {
                         datetime: DateTime::from_timestamp(
                             time.parse().map_err(|e| ("datetime", e))?,
@@ -2053,17 +2049,28 @@ pub trait Local: wrapper::LocalWrapper {
                         price: price.parse().map_err(|e| ("price", e))?,
                         size: size.parse().map_err(|e| ("size", e))?,
                         exchange: exchange.parse().map_err(|e| ("exchange", e))?,
+                        attributes: TickAttributes::from_last_mask(
+                            mask.parse().map_err(|e| ("mask", e))?,
+                        ),
                     }));
                 }
             }
-            wrapper.historical_ticks(req_id, ticks).await;
+            if history {
+                wrapper.tick_by_tick_history(req_id, ticks).await;
+            } else if rx.take_awaited_historical_ticks(req_id) {
+                tx.send(ToClient::HistoricalTicks(req_id, ticks))
+                    .await
+                    .map_err(Box::new)?;
+            } else {
+                wrapper.historical_ticks(req_id, ticks).await;
+            }
             Ok(())
         }
     }
 
     #[inline]
     fn tick_by_tick_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
@@ -2076,21 +2083,31 @@ pub trait Local: wrapper::LocalWrapper {
             let datetime = DateTime::from_timestamp(timestamp, 0)
                 .ok_or(("datetime", ParseDateTimeError::Timestamp))?;
             let tick = match tick_type {
-                1 | 2 => TickData::Last(Last {
-                    datetime,
-                    price: nth(fields, 0, "price")?.parse().map_err(|e| ("price", e))?,
-                    size: nth(fields, 0, "size")?.parse().map_err(|e| ("size", e))?,
-                    exchange: nth(fields, 1, "exchange")?
-                        .parse()
-                        .map_err(|e| ("exchange", e))?,
-                }),
+                1 | 2 => {
+                    decode_fields!(
+                        fields =>
+                            price @ 0: f64,
+                            size @ 0: f64,
+                            mask @ 0: u32
+                    );
+                    TickData::Last(Last {
+                        datetime,
+                        price,
+                        size,
+                        exchange: nth(fields, 0, "exchange")?
+                            .parse()
+                            .map_err(|e| ("exchange", e))?,
+                        attributes: TickAttributes::from_last_mask(mask),
+                    })
+                }
                 3 => {
                     decode_fields!(
                         fields =>
                             bid_price @ 0: f64,
                             ask_price @ 0: f64,
                             bid_size @ 0: f64,
-                            ask_size @ 0: f64
+                            ask_size @ 0: f64,
+                            mask @ 0: u32
                     );
                     TickData::BidAsk(BidAsk {
                         datetime,
@@ -2098,11 +2115,13 @@ pub trait Local: wrapper::LocalWrapper {
                         ask_price,
                         bid_size,
                         ask_size,
+                        attributes: TickAttributes::from_bid_ask_mask(mask),
                     })
                 }
                 4 => TickData::Midpoint(Midpoint {
                     datetime,
                     price: nth(fields, 0, "price")?.parse().map_err(|e| ("price", e))?,
+                    attributes: TickAttributes::default(),
                 }),
                 _ => Err(DecodeError::UnexpectedData("Unexpected tick type"))?,
             };
@@ -2113,88 +2132,144 @@ pub trait Local: wrapper::LocalWrapper {
 
     #[inline]
     fn order_bound_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    permanent_id @ 1: i64,
+                    api_client_id @ 0: i64,
+                    api_order_id @ 0: i64
+            );
+            wrapper
+                .order_bound(api_client_id, api_order_id, permanent_id)
+                .await;
             Ok(())
         }
     }
 
     #[inline]
     fn completed_order_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn completed_orders_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "fa")]
     #[inline]
     fn replace_fa_end_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "wsh")]
     #[inline]
     fn wsh_meta_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
+    #[cfg(feature = "wsh")]
     #[inline]
     fn wsh_event_data_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            warn!(fields = ?fields.collect::<Vec<_>>(), "Unimplemented incoming message");
             Ok(())
         }
     }
 
     #[inline]
     fn historical_schedule_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 1: i64,
+                    start_date_str @ 0: String,
+                    end_date_str @ 0: String,
+                    time_zone @ 0: String
+            );
+            let count = nth_count(fields, 0, "count", 3)?;
+            let time_zone = time_zone
+                .parse::<chrono_tz::Tz>()
+                .map_err(|e| ("time_zone", ParseDateTimeError::Timezone(e)))?;
+            let start_datetime = parse_schedule_datetime(&start_date_str, time_zone)
+                .map_err(|e| ("start_datetime", e))?;
+            let end_datetime = parse_schedule_datetime(&end_date_str, time_zone)
+                .map_err(|e| ("end_datetime", e))?;
+
+            let mut sessions = Vec::with_capacity(count);
+            for _ in 0..count {
+                let start_str = nth(fields, 0, "session_start_datetime")?;
+                let end_str = nth(fields, 0, "session_end_datetime")?;
+                let reference_date = nth(fields, 0, "reference_date")?;
+                sessions.push(ScheduleSession {
+                    start_datetime: parse_schedule_datetime(start_str, time_zone)
+                        .map_err(|e| ("session_start_datetime", e))?,
+                    end_datetime: parse_schedule_datetime(end_str, time_zone)
+                        .map_err(|e| ("session_end_datetime", e))?,
+                    reference_date: NaiveDate::parse_from_str(reference_date, "%Y%m%d")
+                        .map_err(|e| ("reference_date", ParseDateTimeError::Parse(e)))?,
+                });
+            }
+            wrapper
+                .historical_schedule(
+                    req_id,
+                    HistoricalSchedule {
+                        start_datetime,
+                        end_datetime,
+                        time_zone,
+                        sessions,
+                    },
+                )
+                .await;
             Ok(())
         }
     }
 
     #[inline]
     fn user_info_msg(
-        fields: &mut Fields,
+        fields: &mut Fields<'_>,
         wrapper: &mut Self,
     ) -> impl Future<Output = DecodeResult> {
         async move {
-            warn!("Unimplemented incoming message. Fields: {:?}", &fields);
+            decode_fields!(
+                fields =>
+                    req_id @ 1: i64,
+                    white_branding_id @ 0: String
+            );
+            wrapper.user_info(req_id, white_branding_id).await;
             Ok(())
         }
     }
@@ -2205,6 +2280,7 @@ pub trait Local: wrapper::LocalWrapper {
         tick_type: u16,
         value: f64,
         wrapper: &mut Self,
+        rx: &mut Rx,
     ) -> impl Future<Output = DecodeResult> {
         async move {
             match tick_type {
@@ -2261,6 +2337,8 @@ pub trait Local: wrapper::LocalWrapper {
                         _ => unreachable!(),
                     };
                     wrapper.auction(req_id, auction).await;
+                    let update = rx.shared().update_auction(req_id, auction);
+                    wrapper.auction_update(req_id, update).await;
                 }
                 27 | 28 | 86 => {
                     let open_interest = match tick_type {
@@ -2287,6 +2365,10 @@ pub trait Local: wrapper::LocalWrapper {
                         _ => unreachable!(),
                     };
                     wrapper.accessibility(req_id, access).await;
+                    if tick_type != 49 {
+                        let update = rx.shared().update_short_borrow(req_id, access);
+                        wrapper.short_borrow(req_id, update).await;
+                    }
                 }
                 54 => {
                     wrapper.trade_count(req_id, value).await;
@@ -2333,23 +2415,487 @@ impl<W: wrapper::LocalWrapper> Local for W {}
 impl<W: wrapper::Wrapper> Remote for W {}
 
 #[inline]
-pub(crate) fn nth(
-    fields: &mut Fields,
+pub(crate) fn nth<'a>(
+    fields: &mut Fields<'a>,
     n: usize,
     field_name: &'static str,
-) -> Result<String, DecodeError> {
+) -> Result<&'a str, DecodeError> {
     fields.nth(n).ok_or(DecodeError::MissingData { field_name })
 }
 
 #[inline]
-pub(crate) async fn decode_contract_no_wrapper(
-    fields: &mut Fields,
+// Read the number of repeated items that follow in a message, each of which spans at least `width`
+// fields. A count that the rest of the message cannot hold is rejected, so that nothing is
+// allocated or skipped for fields that never arrive.
+pub(crate) fn nth_count(
+    fields: &mut Fields<'_>,
+    n: usize,
+    field_name: &'static str,
+    width: usize,
+) -> Result<usize, DecodeError> {
+    let count = nth(fields, n, field_name)?
+        .parse::<usize>()
+        .map_err(|e| (field_name, e))?;
+    let remaining = fields.remaining();
+    if count.checked_mul(width).is_some_and(|len| len <= remaining) {
+        Ok(count)
+    } else {
+        Err(DecodeError::CountExceedsMessage {
+            field_name,
+            count,
+            remaining,
+        })
+    }
+}
+
+#[inline]
+// A head timestamp awaited by `Client::req_head_timestamps` is sent to the client. Any other head
+// timestamp is returned so that it can be passed to the wrapper.
+pub(crate) async fn decode_head_timestamp_no_wrapper(
+    fields: &mut Fields<'_>,
     tx: &mut Tx,
     rx: &mut Rx,
-) -> DecodeResult {
+) -> Result<Option<(i64, DateTime<chrono::Utc>)>, DecodeError> {
     decode_fields!(
         fields =>
             req_id @ 1: i64,
+            timestamp @ 0: i64
+    );
+    let timestamp = DateTime::from_timestamp(timestamp, 0)
+        .ok_or(("timestamp", ParseDateTimeError::Timestamp))?;
+    if rx.take_awaited_head_timestamp(req_id) {
+        tx.send(ToClient::HeadTimestamp(req_id, timestamp))
+            .await
+            .map_err(Box::new)?;
+        return Ok(None);
+    }
+    Ok(Some((req_id, timestamp)))
+}
+
+#[inline]
+// Option parameters awaited by `contract::option_chain` are sent to the client. Any others are
+// returned so that they can be passed to the wrapper.
+pub(crate) async fn decode_option_parameters_no_wrapper(
+    fields: &mut Fields<'_>,
+    tx: &mut Tx,
+    rx: &mut Rx,
+) -> Result<Option<(i64, OptionParameters)>, DecodeError> {
+    decode_fields!(
+        fields =>
+            req_id @ 1: i64,
+            exchange @ 0: String,
+            underlying_contract_id @ 0: ContractId,
+            trading_class @ 0: String,
+            multiplier @ 0: String
+    );
+    let expiration_count = nth_count(fields, 0, "expiration_count", 1)?;
+    let expirations = (0..expiration_count)
+        .map(|_| {
+            NaiveDate::parse_from_str(nth(fields, 0, "expiration")?, "%Y%m%d")
+                .map_err(|e| ("expiration", ParseDateTimeError::Parse(e)).into())
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+    let strike_count = nth_count(fields, 0, "strike_count", 1)?;
+    let strikes = (0..strike_count)
+        .map(|_| {
+            nth(fields, 0, "strike")?
+                .parse::<f64>()
+                .map_err(|e| ("strike", e).into())
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+    let params = OptionParameters {
+        exchange,
+        underlying_contract_id,
+        trading_class,
+        multiplier,
+        expirations,
+        strikes,
+    };
+    if rx.awaits_option_parameters(req_id) {
+        tx.send(ToClient::OptionParameters(req_id, params))
+            .await
+            .map_err(Box::new)?;
+        return Ok(None);
+    }
+    Ok(Some((req_id, params)))
+}
+
+#[cfg(feature = "news")]
+#[inline]
+// A headline awaited by `Client::req_historical_news_until` is sent to the client. Any other
+// headline is returned so that it can be passed to the wrapper.
+pub(crate) async fn decode_historical_news_no_wrapper(
+    fields: &mut Fields<'_>,
+    tx: &mut Tx,
+    rx: &mut Rx,
+) -> Result<Option<(i64, Headline)>, DecodeError> {
+    decode_fields!(
+        fields =>
+            req_id @ 1: i64,
+            time @ 0: String,
+            provider_code @ 0: String,
+            article_id @ 0: String,
+            headline @ 0: String
+    );
+    let headline = Headline {
+        time: NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S%.f")
+            .map_err(|e| ("time", ParseDateTimeError::Parse(e)))?
+            .and_utc(),
+        provider_code,
+        article_id,
+        headline,
+    };
+    if rx.awaits_historical_news(req_id) {
+        tx.send(ToClient::HistoricalNews(req_id, headline))
+            .await
+            .map_err(Box::new)?;
+        return Ok(None);
+    }
+    Ok(Some((req_id, headline)))
+}
+
+#[inline]
+// The accounts in a managed accounts message are sent as a single comma-separated field.
+pub(crate) fn decode_managed_accounts(
+    fields: &mut Fields<'_>,
+) -> Result<std::collections::HashSet<String>, DecodeError> {
+    Ok(nth(fields, 2, "accounts_list")?
+        .split(',')
+        .filter(|account| !account.is_empty())
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+// Account values are sent as text, so numeric values may be empty or contain the thousands
+// separators of the account's locale (ex. "1,234.56" or "1.234,56"). Returns `None` for empty
+// values, and otherwise the value with its separators removed and a `.` as the decimal separator.
+// Values that are not numbers with separators are returned unchanged.
+fn normalize_account_value(value: &str) -> Option<std::borrow::Cow<'_, str>> {
+    const SEPARATORS: [char; 4] = [',', '.', ' ', '\''];
+
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit())
+        || !unsigned
+            .chars()
+            .all(|c| c.is_ascii_digit() || SEPARATORS.contains(&c))
+    {
+        return Some(value.into());
+    }
+    let last_separator = |sep| unsigned.rfind(sep);
+    // The decimal separator is whichever of `.` and `,` comes last, unless it is repeated or, for
+    // a lone `,` after a nonzero digit, is followed by a group of exactly three digits.
+    let decimal = match (last_separator('.'), last_separator(',')) {
+        (Some(dot), Some(comma)) => Some(dot.max(comma)),
+        (Some(dot), None) => (unsigned.matches('.').count() == 1).then_some(dot),
+        (None, Some(comma)) => (unsigned.matches(',').count() == 1
+            && (unsigned.len() - comma - 1 != 3 || unsigned.starts_with('0')))
+        .then_some(comma),
+        (None, None) => None,
+    };
+    let (whole, fraction) = match decimal {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    // Thousands separators must separate groups of exactly three digits.
+    let mut groups = whole.split(SEPARATORS);
+    let leading = groups.next().unwrap_or_default();
+    if leading != whole && !(1..=3).contains(&leading.len()) {
+        return Some(value.into());
+    }
+    let mut digits = String::with_capacity(value.len());
+    digits.push_str(&value[..value.len() - unsigned.len()]);
+    digits.push_str(leading);
+    for group in groups {
+        if group.len() != 3 {
+            return Some(value.into());
+        }
+        digits.push_str(group);
+    }
+    if let Some(fraction) = fraction {
+        if !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Some(value.into());
+        }
+        digits.push('.');
+        digits.push_str(fraction);
+    }
+    Some(digits.into())
+}
+
+#[inline]
+fn decode_account_attribute(
+    name: String,
+    value: String,
+    currency: String,
+) -> Result<Option<account::Attribute>, DecodeError> {
+    if normalize_account_value(&value).is_some() || name.starts_with("SegmentTitle") {
+        return decode_known_account_attribute(name, value, currency);
+    }
+    // An empty value cannot be parsed into a dedicated variant, so the attribute is passed on
+    // unparsed rather than dropped.
+    let unknown = account::Attribute::Unknown {
+        name: name.clone(),
+        value: value.clone(),
+        currency: currency.clone(),
+    };
+    Ok(Some(
+        decode_known_account_attribute(name, value, currency)?.unwrap_or(unknown),
+    ))
+}
+
+#[inline]
+#[allow(clippy::too_many_lines)]
+fn decode_known_account_attribute(
+    name: String,
+    value: String,
+    currency: String,
+) -> Result<Option<account::Attribute>, DecodeError> {
+    Ok(Some(match name.as_str() {
+        "AccountCode" => account::Attribute::AccountCode(value),
+        "AccountOrGroup" => decode_account_attr!(AccountOrGroup, value, currency),
+        "AccountReady" => decode_account_attr!(AccountReady, value),
+        "AccountType" => account::Attribute::AccountType(value),
+        expand_seg_variants!("AccruedCash") => {
+            impl_seg_variants!("AccruedCash", AccruedCash, name, value, currency)
+        }
+        expand_seg_variants!("AccruedDividend") => {
+            impl_seg_variants!("AccruedDividend", AccruedDividend, name, value, currency)
+        }
+        expand_seg_variants!("AvailableFunds") => {
+            impl_seg_variants!("AvailableFunds", AvailableFunds, name, value, currency)
+        }
+        expand_seg_variants!("Billable") => {
+            impl_seg_variants!("Billable", Billable, name, value, currency)
+        }
+        "BuyingPower" => decode_account_attr!(BuyingPower, value, currency),
+        "CashBalance" => decode_account_attr!(CashBalance, value, currency),
+        expand_seg_variants!("ColumnPrio") => {
+            impl_seg_variants!("ColumnPrio", ColumnPrio, name, value)
+        }
+        "CorporateBondValue" => decode_account_attr!(CorporateBondValue, value, currency),
+        "Cryptocurrency" => decode_account_attr!(Cryptocurrency, value, currency),
+        "Currency" => decode_account_attr!(Currency, value),
+        "Cushion" => decode_account_attr!(Cushion, value),
+        "DayTradesRemaining" => decode_account_attr!(DayTradesRemaining, value),
+        "DayTradesRemainingT+1" => decode_account_attr!(DayTradesRemainingTPlus1, value),
+        "DayTradesRemainingT+2" => decode_account_attr!(DayTradesRemainingTPlus2, value),
+        "DayTradesRemainingT+3" => decode_account_attr!(DayTradesRemainingTPlus3, value),
+        "DayTradesRemainingT+4" => decode_account_attr!(DayTradesRemainingTPlus4, value),
+        "DayTradingStatus-S" => account::Attribute::DayTradingStatus(value),
+        expand_seg_variants!("EquityWithLoanValue") => impl_seg_variants!(
+            "EquityWithLoanValue",
+            EquityWithLoanValue,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("ExcessLiquidity") => {
+            impl_seg_variants!("ExcessLiquidity", ExcessLiquidity, name, value, currency)
+        }
+        "ExchangeRate" => decode_account_attr!(ExchangeRate, value, currency),
+        expand_seg_variants!("FullAvailableFunds") => impl_seg_variants!(
+            "FullAvailableFunds",
+            FullAvailableFunds,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("FullExcessLiquidity") => impl_seg_variants!(
+            "FullExcessLiquidity",
+            FullExcessLiquidity,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("FullInitMarginReq") => impl_seg_variants!(
+            "FullInitMarginReq",
+            FullInitMarginReq,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("FullMaintMarginReq") => impl_seg_variants!(
+            "FullMaintMarginReq",
+            FullMaintenanceMarginReq,
+            name,
+            value,
+            currency
+        ),
+        "FundValue" => decode_account_attr!(FundValue, value, currency),
+        "FutureOptionValue" => decode_account_attr!(FutureOptionValue, value, currency),
+        "FuturesPNL" => decode_account_attr!(FuturesPnl, value, currency),
+        "FxCashBalance" => decode_account_attr!(FxCashBalance, value, currency),
+        "GrossPositionValue" => decode_account_attr!(GrossPositionValue, value, currency),
+        "GrossPositionValue-S" => {
+            decode_account_attr!(GrossPositionValueSecurity, value, currency)
+        }
+        expand_seg_variants!("Guarantee") => {
+            impl_seg_variants!("Guarantee", Guarantee, name, value, currency)
+        }
+        expand_seg_variants!("IncentiveCoupons") => {
+            impl_seg_variants!("IncentiveCoupons", IncentiveCoupons, name, value, currency)
+        }
+        expand_seg_variants!("IndianStockHaircut") => impl_seg_variants!(
+            "IndianStockHaircut",
+            IndianStockHaircut,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("InitMarginReq") => {
+            impl_seg_variants!("InitMarginReq", InitMarginReq, name, value, currency)
+        }
+        "IssuerOptionValue" => decode_account_attr!(IssuerOptionValue, value, currency),
+        "Leverage-S" => decode_account_attr!(LeverageSecurity, value),
+        "LookAheadNextChange" => decode_account_attr!(LookAheadNextChange, value),
+        expand_seg_variants!("LookAheadAvailableFunds") => impl_seg_variants!(
+            "LookAheadAvailableFunds",
+            LookAheadAvailableFunds,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("LookAheadExcessLiquidity") => impl_seg_variants!(
+            "LookAheadExcessLiquidity",
+            LookAheadExcessLiquidity,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("LookAheadInitMarginReq") => impl_seg_variants!(
+            "LookAheadInitMarginReq",
+            LookAheadInitMarginReq,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("LookAheadMaintMarginReq") => impl_seg_variants!(
+            "LookAheadMaintMarginReq",
+            LookAheadMaintenanceMarginReq,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("MaintMarginReq") => impl_seg_variants!(
+            "MaintMarginReq",
+            MaintenanceMarginReq,
+            name,
+            value,
+            currency
+        ),
+        "MoneyMarketFundValue" => {
+            decode_account_attr!(MoneyMarketFundValue, value, currency)
+        }
+        "MutualFundValue" => decode_account_attr!(MutualFundValue, value, currency),
+        "NLVAndMarginInReview" => decode_account_attr!(NlvAndMarginInReview, value),
+        "NetDividend" => decode_account_attr!(NetDividend, value, currency),
+        expand_seg_variants!("NetLiquidation") => {
+            impl_seg_variants!("NetLiquidation", NetLiquidation, name, value, currency)
+        }
+        "NetLiquidationByCurrency" => {
+            decode_account_attr!(NetLiquidationByCurrency, value, currency)
+        }
+        "NetLiquidationUncertainty" => {
+            decode_account_attr!(NetLiquidationUncertainty, value, currency)
+        }
+        "OptionMarketValue" => decode_account_attr!(OptionMarketValue, value, currency),
+        expand_seg_variants!("PASharesValue") => {
+            impl_seg_variants!("PASharesValue", PaSharesValue, name, value, currency)
+        }
+        expand_seg_variants!("PhysicalCertificateValue") => impl_seg_variants!(
+            "PhysicalCertificateValue",
+            PhysicalCertificateValue,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("PostExpirationExcess") => impl_seg_variants!(
+            "PostExpirationExcess",
+            PostExpirationExcess,
+            name,
+            value,
+            currency
+        ),
+        expand_seg_variants!("PostExpirationMargin") => impl_seg_variants!(
+            "PostExpirationMargin",
+            PostExpirationMargin,
+            name,
+            value,
+            currency
+        ),
+        "PreviousDayEquityWithLoanValue" => {
+            decode_account_attr!(PreviousDayEquityWithLoanValue, value, currency)
+        }
+        "PreviousDayEquityWithLoanValue-S" => {
+            decode_account_attr!(PreviousDayEquityWithLoanValueSecurity, value, currency)
+        }
+        "RealCurrency" => decode_account_attr!(RealCurrency, value),
+        "RealizedPnL" => decode_account_attr!(RealizedPnL, value, currency),
+        "RegTEquity" => decode_account_attr!(RegTEquity, value, currency),
+        "RegTEquity-S" => decode_account_attr!(RegTEquitySecurity, value, currency),
+        "RegTMargin" => decode_account_attr!(RegTMargin, value, currency),
+        "RegTMargin-S" => decode_account_attr!(RegTMarginSecurity, value, currency),
+        "SMA" => decode_account_attr!(Sma, value, currency),
+        "SMA-S" => decode_account_attr!(SmaSecurity, value, currency),
+        "StockMarketValue" => decode_account_attr!(StockMarketValue, value, currency),
+        "TBillValue" => decode_account_attr!(TBillValue, value, currency),
+        "TBondValue" => decode_account_attr!(TBondValue, value, currency),
+        "TotalCashBalance" => decode_account_attr!(TotalCashBalance, value, currency),
+        expand_seg_variants!("TotalCashValue") => {
+            impl_seg_variants!("TotalCashValue", TotalCashValue, name, value, currency)
+        }
+        expand_seg_variants!("TotalDebitCardPendingCharges") => impl_seg_variants!(
+            "TotalDebitCardPendingCharges",
+            TotalDebitCardPendingCharges,
+            name,
+            value,
+            currency
+        ),
+        "TradingType-S" => account::Attribute::TradingTypeSecurity(value),
+        "UnrealizedPnL" => decode_account_attr!(UnrealizedPnL, value, currency),
+        "WarrantValue" => decode_account_attr!(WarrantValue, value, currency),
+        "WhatIfPMEnabled" => decode_account_attr!(WhatIfPMEnabled, value),
+        "SegmentTitle-C" | "SegmentTitle-P" | "SegmentTitle-S" => return Ok(None),
+        _ => account::Attribute::Unknown {
+            name,
+            value,
+            currency,
+        },
+    }))
+}
+
+#[inline]
+// A contract awaited by `Client::recv_contract_query` is sent to the client. Any other contract is
+// returned so that it can be passed to the wrapper.
+pub(crate) async fn decode_contract_no_wrapper(
+    fields: &mut Fields<'_>,
+    tx: &mut Tx,
+    rx: &mut Rx,
+) -> Result<Option<(i64, Contract)>, DecodeError> {
+    decode_fields!(fields => req_id @ 1: i64);
+    let contract = deserialize_contract_data(fields);
+    if !rx.awaits_contract_data(req_id) {
+        return contract.map(|contract| Some((req_id, contract)));
+    }
+    // A contract that fails to decode is still reported to the client, so that the other
+    // contracts received for the same query are unaffected.
+    let (response, status) = match contract {
+        Ok(contract) => (Ok(contract), Ok(None)),
+        Err(e) => (Err(ContractError::Decode(e.to_string())), Err(e)),
+    };
+    tx.send(ToClient::ContractData(req_id, response))
+        .await
+        .map_err(Box::new)?;
+    status
+}
+
+#[inline]
+fn deserialize_contract_data(fields: &mut Fields<'_>) -> Result<Contract, DecodeError> {
+    decode_fields!(
+        fields =>
             symbol @ 0: String,
             sec_type @ 0: ContractType,
             expiration_date @ 0: String,
@@ -2368,8 +2914,13 @@ pub(crate) async fn decode_contract_no_wrapper(
             long_name @ 0: String,
             primary_exchange @ 0: String,
             sector @ 1: String,
-            security_id_count @ 7: usize
+            time_zone @ 2: String,
+            trading_hours @ 0: String,
+            liquid_hours @ 0: String
     );
+    let security_id_count = nth_count(fields, 2, "security_id_count", 2)?;
+    let (trading_hours, liquid_hours) =
+        parse_trading_hours(&trading_hours, &liquid_hours, &time_zone)?;
 
     let order_types = order_types
         .split(',')
@@ -2383,10 +2934,10 @@ pub(crate) async fn decode_contract_no_wrapper(
     let security_ids = (0..security_id_count)
         .map(
             |_| match nth(fields, 0, "security_ids")?.to_uppercase().as_str() {
-                "CUSIP" => Ok(SecurityId::Cusip(nth(fields, 0, "security_id")?)),
-                "SEDOL" => Ok(SecurityId::Sedol(nth(fields, 0, "security_id")?)),
-                "ISIN" => Ok(SecurityId::Isin(nth(fields, 0, "security_id")?)),
-                "RIC" => Ok(SecurityId::Ric(nth(fields, 0, "security_id")?)),
+                "CUSIP" => Ok(SecurityId::Cusip(nth(fields, 0, "security_id")?.to_owned())),
+                "SEDOL" => Ok(SecurityId::Sedol(nth(fields, 0, "security_id")?.to_owned())),
+                "ISIN" => Ok(SecurityId::Isin(nth(fields, 0, "security_id")?.to_owned())),
+                "RIC" => Ok(SecurityId::Ric(nth(fields, 0, "security_id")?.to_owned())),
                 _ => Err(DecodeError::UnexpectedData(
                     "Invalid security_id type found in STK contract_data_msg",
                 )),
@@ -2394,143 +2945,384 @@ pub(crate) async fn decode_contract_no_wrapper(
         )
         .collect::<Result<_, _>>()?;
 
-    if let Ok(ToWrapper::ContractQuery((query_client, req_id_client))) = rx.try_recv() {
-        if let crate::contract::Query::IbContractId(con_id_client, routing_client) = query_client {
-            if con_id_client != contract_id {
-                return Err(DecodeError::UnexpectedData("Unexpected contract ID"));
-            }
-            if exchange != routing_client {
-                return Err(DecodeError::UnexpectedData("Unexpected routing exchange"));
-            }
-        }
-        if req_id_client != req_id {
-            return Err(DecodeError::UnexpectedData("Unexpected request ID"));
-        }
-        let contract = match sec_type {
-            ContractType::Stock => Some(Contract::Stock(Stock {
-                symbol,
-                exchange,
-                currency,
-                local_symbol,
-                trading_class,
-                contract_id,
-                min_tick,
-                primary_exchange: primary_exchange
-                    .parse()
-                    .map_err(|e| ("primary_exchange", e))?,
-                long_name,
-                sector,
-                order_types,
-                valid_exchanges,
-                security_ids,
-                stock_type: nth(fields, 5, "stock_type")?,
-            })),
-            ContractType::SecOption => {
-                let inner = SecOptionInner {
-                    contract_id,
-                    min_tick,
-                    symbol,
-                    exchange,
-                    strike,
-                    multiplier: multiplier.parse().map_err(|e| ("multiplier", e))?,
-                    expiration_date: NaiveDate::parse_and_remainder(
-                        expiration_date.as_str(),
-                        "%Y%m%d",
-                    )
-                    .map_err(|e| ("expiration_date", ParseDateTimeError::Parse(e)))?
-                    .0,
-                    underlying_contract_id,
-                    sector,
-                    trading_class,
-                    currency,
-                    local_symbol,
-                    long_name,
-                    order_types,
-                    valid_exchanges,
-                };
-                match class.as_str() {
-                    "C" => Some(Contract::SecOption(SecOption::Call(inner))),
-                    "P" => Some(Contract::SecOption(SecOption::Put(inner))),
-                    _ => return Err(DecodeError::UnexpectedData("Unexpected option class")),
-                }
-            }
-            ContractType::Crypto => Some(Contract::Crypto(Crypto {
-                contract_id,
-                min_tick,
-                symbol,
-                trading_class,
-                currency,
-                local_symbol,
-                long_name,
-                order_types,
-                valid_exchanges,
-            })),
-            ContractType::Forex => Some(Contract::Forex(Forex {
-                contract_id,
-                min_tick,
-                symbol,
-                exchange,
-                trading_class,
-                currency,
-                local_symbol,
-                long_name,
-                order_types,
-                valid_exchanges,
-            })),
-            ContractType::Index => Some(Contract::Index(Index {
-                contract_id,
-                min_tick,
-                symbol,
-                exchange,
-                currency,
-                local_symbol,
-                long_name,
-                order_types,
-                valid_exchanges,
-            })),
-            ContractType::SecFuture => Some(Contract::SecFuture(SecFuture {
+    let contract = match sec_type {
+        ContractType::Stock => Some(Contract::Stock(Stock {
+            symbol,
+            exchange,
+            currency,
+            local_symbol,
+            trading_class,
+            contract_id,
+            min_tick,
+            primary_exchange: primary_exchange
+                .parse()
+                .map_err(|e| ("primary_exchange", e))?,
+            long_name,
+            sector,
+            order_types,
+            valid_exchanges,
+            trading_hours,
+            liquid_hours,
+            security_ids,
+            stock_type: nth(fields, 5, "stock_type")?.to_owned(),
+        })),
+        ContractType::SecOption => {
+            let inner = SecOptionInner {
                 contract_id,
                 min_tick,
                 symbol,
                 exchange,
-                multiplier: multiplier.parse().map_err(|e| ("multiplier", e))?,
+                strike,
+                multiplier: parse_multiplier(&multiplier)?,
                 expiration_date: NaiveDate::parse_and_remainder(expiration_date.as_str(), "%Y%m%d")
                     .map_err(|e| ("expiration_date", ParseDateTimeError::Parse(e)))?
                     .0,
-                trading_class,
                 underlying_contract_id,
-                currency,
-                local_symbol,
-                long_name,
-                order_types,
-                valid_exchanges,
-            })),
-            ContractType::Commodity => Some(Contract::Commodity(Commodity {
-                contract_id,
-                min_tick,
-                symbol,
-                exchange,
+                sector,
                 trading_class,
                 currency,
                 local_symbol,
                 long_name,
                 order_types,
                 valid_exchanges,
-            })),
-        };
+                trading_hours,
+                liquid_hours,
+            };
+            match class.as_str() {
+                "C" => Some(Contract::SecOption(SecOption::Call(inner))),
+                "P" => Some(Contract::SecOption(SecOption::Put(inner))),
+                _ => return Err(DecodeError::UnexpectedData("Unexpected option class")),
+            }
+        }
+        ContractType::Crypto => Some(Contract::Crypto(Crypto {
+            contract_id,
+            min_tick,
+            symbol,
+            exchange,
+            trading_class,
+            currency,
+            local_symbol,
+            long_name,
+            order_types,
+            valid_exchanges,
+            trading_hours,
+            liquid_hours,
+        })),
+        ContractType::Forex => Some(Contract::Forex(Forex {
+            contract_id,
+            min_tick,
+            symbol,
+            exchange,
+            trading_class,
+            currency,
+            local_symbol,
+            long_name,
+            order_types,
+            valid_exchanges,
+            trading_hours,
+            liquid_hours,
+        })),
+        ContractType::Index => Some(Contract::Index(Index {
+            contract_id,
+            min_tick,
+            symbol,
+            exchange,
+            currency,
+            local_symbol,
+            long_name,
+            order_types,
+            valid_exchanges,
+            trading_hours,
+            liquid_hours,
+        })),
+        ContractType::SecFuture => Some(Contract::SecFuture(SecFuture {
+            contract_id,
+            min_tick,
+            symbol,
+            exchange,
+            multiplier: parse_multiplier(&multiplier)?,
+            expiration_date: NaiveDate::parse_and_remainder(expiration_date.as_str(), "%Y%m%d")
+                .map_err(|e| ("expiration_date", ParseDateTimeError::Parse(e)))?
+                .0,
+            trading_class,
+            underlying_contract_id,
+            currency,
+            local_symbol,
+            long_name,
+            order_types,
+            valid_exchanges,
+            trading_hours,
+            liquid_hours,
+        })),
+        ContractType::Commodity => Some(Contract::Commodity(Commodity {
+            contract_id,
+            min_tick,
+            symbol,
+            exchange,
+            trading_class,
+            currency,
+            local_symbol,
+            long_name,
+            order_types,
+            valid_exchanges,
+            trading_hours,
+            liquid_hours,
+        })),
+        unsupported => {
+            return Err((
+                "sec_type",
+                crate::contract::ParseContractTypeError(unsupported.to_string()),
+            )
+                .into())
+        }
+    };
 
-        tx.send(ToClient::NewContract(contract.ok_or(
-            DecodeError::UnexpectedData("No contract was created"),
-        )?))
-        .await
-        .map_err(Box::new)?;
+    contract.ok_or(DecodeError::UnexpectedData("No contract was created"))
+}
+
+#[inline]
+// The trading and liquid hours are only parsed when they are present, since some contracts are
+// sent without them (and without a timezone).
+fn parse_trading_hours(
+    trading_hours: &str,
+    liquid_hours: &str,
+    time_zone: &str,
+) -> Result<(Vec<TradingSession>, Vec<TradingSession>), DecodeError> {
+    if trading_hours.is_empty() && liquid_hours.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let time_zone = time_zone
+        .parse::<chrono_tz::Tz>()
+        .map_err(|e| ("time_zone", ParseDateTimeError::Timezone(e)))?;
+    Ok((
+        parse_sessions(trading_hours, time_zone).map_err(|e| ("trading_hours", e))?,
+        parse_sessions(liquid_hours, time_zone).map_err(|e| ("liquid_hours", e))?,
+    ))
+}
+
+// The hours are listed one day at a time, separated by semicolons, as either
+// "20240102:0930-20240102:1600" or, in older versions, "20240102:0930-1600,1700-1800". Days on
+// which the contract does not trade are listed as "20240106:CLOSED".
+fn parse_sessions(
+    hours: &str,
+    time_zone: chrono_tz::Tz,
+) -> Result<Vec<TradingSession>, ParseDateTimeError> {
+    let localize = |datetime: NaiveDateTime| {
+        datetime
+            .and_local_timezone(time_zone)
+            .earliest()
+            .ok_or(ParseDateTimeError::Single)
+            .map(|dt| dt.to_utc())
+    };
+    let mut sessions = Vec::new();
+    for day in hours.split(';').filter(|day| !day.is_empty()) {
+        let (date, ranges) = day.split_once(':').unwrap_or((day, "CLOSED"));
+        if ranges == "CLOSED" {
+            continue;
+        }
+        for range in ranges.split(',') {
+            let (open, close) = range.split_once('-').unwrap_or((range, ""));
+            let open = NaiveDateTime::parse_from_str(&format!("{date}{open}"), "%Y%m%d%H%M")?;
+            let close = match close.split_once(':') {
+                Some((close_date, close)) => NaiveDateTime::parse_from_str(
+                    &format!("{close_date}{close}"),
+                    "%Y%m%d%H%M",
+                )?,
+                None => {
+                    let close =
+                        NaiveDateTime::parse_from_str(&format!("{date}{close}"), "%Y%m%d%H%M")?;
+                    if close <= open {
+                        close + chrono::TimeDelta::days(1)
+                    } else {
+                        close
+                    }
+                }
+            };
+            sessions.push(TradingSession {
+                open: localize(open)?,
+                close: localize(close)?,
+                date: close.date(),
+            });
+        }
+    }
+    Ok(sessions)
+}
+
+#[inline]
+// The end of a query awaited by `Client::recv_contract_query` is sent to the client. The request
+// ID of any other query is returned so that its end can be passed to the wrapper.
+pub(crate) async fn decode_contract_end_no_wrapper(
+    fields: &mut Fields<'_>,
+    tx: &mut Tx,
+    rx: &mut Rx,
+) -> Result<Option<i64>, DecodeError> {
+    decode_fields!(fields => req_id @ 2: i64);
+    if rx.take_awaited_contract_data(req_id) {
+        tx.send(ToClient::ContractEnd(req_id))
+            .await
+            .map_err(Box::new)?;
+        return Ok(None);
+    }
+    Ok(Some(req_id))
+}
+
+// Skip the variable-length sections of an open order message that follow the parent ID, up to
+// the cash quantity, which is returned if it is set.
+fn decode_open_order_cash_quantity(
+    fields: &mut Fields<'_>,
+    order_type: &str,
+) -> Result<Option<f64>, DecodeError> {
+    fn skip(fields: &mut Fields<'_>, n: usize) -> Result<(), DecodeError> {
+        if n > 0 {
+            nth(fields, n - 1, "open_order")?;
+        }
+        Ok(())
+    }
+
+    decode_fields!(
+        fields =>
+            delta_neutral_order_type @ 3: String
+    );
+    // The delta neutral auxiliary price and attributes, the continuous update flag, the reference
+    // price type, the trailing parameters, the basis points, and the combo legs description.
+    skip(fields, if delta_neutral_order_type.is_empty() { 8 } else { 16 })?;
+    let combo_legs = nth_count(fields, 0, "combo_legs", 8)?;
+    skip(fields, 8 * combo_legs)?;
+    let order_combo_legs = nth_count(fields, 0, "order_combo_legs", 1)?;
+    skip(fields, order_combo_legs)?;
+    let smart_combo_routing_params = nth_count(fields, 0, "smart_combo_routing_params", 2)?;
+    skip(fields, 2 * smart_combo_routing_params)?;
+    decode_fields!(
+        fields =>
+            scale_price_increment @ 2: Option<f64>
+    );
+    if scale_price_increment.is_some_and(|increment| increment > 0.0 && increment != f64::MAX) {
+        skip(fields, 7)?;
+    }
+    decode_fields!(
+        fields =>
+            hedge_type @ 0: String
+    );
+    skip(fields, usize::from(!hedge_type.is_empty()))?;
+    // The opt-out SMART routing flag, the clearing parameters, and the not held flag.
+    decode_fields!(
+        fields =>
+            delta_neutral_contract @ 4: i64
+    );
+    skip(fields, if delta_neutral_contract == 0 { 0 } else { 3 })?;
+    decode_fields!(
+        fields =>
+            algo_strategy @ 0: String
+    );
+    if !algo_strategy.is_empty() {
+        let algo_params = nth_count(fields, 0, "algo_params", 2)?;
+        skip(fields, 2 * algo_params)?;
+    }
+    // The solicited flag, the what-if flag and order state, and the randomize flags.
+    skip(fields, 19)?;
+    if order_type == "PEG BENCH" {
+        skip(fields, 5)?;
+    }
+    let conditions = nth_count(fields, 0, "conditions", 1)?;
+    for _ in 0..conditions {
+        decode_fields!(
+            fields =>
+                condition_type @ 0: i64
+        );
+        // The fields of price, time, margin, execution, volume, and percent change conditions.
+        skip(
+            fields,
+            match condition_type {
+                1 => 6,
+                3 | 4 => 3,
+                5 => 4,
+                _ => 5,
+            },
+        )?;
+    }
+    if conditions > 0 {
+        skip(fields, 2)?;
+    }
+    // The adjusted order parameters and the soft dollar tier.
+    decode_fields!(
+        fields =>
+            cash_quantity @ 11: Option<f64>
+    );
+    Ok(cash_quantity.filter(|&cash| cash != f64::MAX))
+}
+
+#[inline]
+// The IB dividends are sent as "trailing_year,forward_year,date,amount". Sums that are empty are
+// zero, and the date and amount are empty if no dividend is scheduled.
+fn decode_dividends(value: &str) -> Result<Dividends, DecodeError> {
+    let sum = |field_name, s: Option<&str>| match s {
+        Some("") => Ok(0.0),
+        Some(s) => s.parse().map_err(|e| DecodeError::from((field_name, e))),
+        None => Err(DecodeError::MissingData { field_name }),
+    };
+    let mut divs = value.split(',');
+    let trailing_year = sum("trailing_year", divs.next())?;
+    let forward_year = sum("forward_year", divs.next())?;
+    let next_dividend = match (divs.next().unwrap_or_default(), divs.next()) {
+        ("", _) => None,
+        (date, amount) => Some(Dividend {
+            date: NaiveDate::parse_and_remainder(date, "%Y%m%d")
+                .map_err(|e| ("next_dividend", ParseDateTimeError::Parse(e)))?
+                .0,
+            amount: sum("next_dividend", amount)?,
+        }),
+    };
+    Ok(Dividends {
+        trailing_year,
+        forward_year,
+        next_dividend,
+    })
+}
+
+#[inline]
+// The fundamental ratios are sent as "CODE=value;" pairs, one of which is the currency. IBKR
+// reports a ratio that is unavailable as -99999.99.
+fn decode_fundamental_ratios(value: &str) -> Result<FundamentalRatios, DecodeError> {
+    let mut ratios = FundamentalRatios::default();
+    for pair in value.split(';').filter(|pair| !pair.is_empty()) {
+        let (code, value) = pair.split_once('=').ok_or(DecodeError::UnexpectedData(
+            "Fundamental ratio without a value",
+        ))?;
+        match (code, value) {
+            ("CURRENCY", currency) => ratios.currency = Some(currency.to_owned()),
+            (_, "" | "-99999.99") => (),
+            (code, value) => {
+                ratios.ratios.insert(
+                    code.to_owned(),
+                    value.parse().map_err(|e| ("fundamental_ratio", e))?,
+                );
+            }
+        }
+    }
+    Ok(ratios)
+}
+
+#[inline]
+// Multipliers are whole numbers, but some messages (ex. the positions of options) send them as
+// decimals such as "100.0".
+fn parse_multiplier(s: &str) -> Result<u32, DecodeError> {
+    if let Ok(multiplier) = s.parse() {
+        return Ok(multiplier);
+    }
+    let multiplier = s.parse::<f64>().map_err(|e| ("multiplier", e))?;
+    if multiplier.fract() == 0.0 && (0.0..=f64::from(u32::MAX)).contains(&multiplier) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(multiplier as u32)
+    } else {
+        Err(DecodeError::Other(format!(
+            "Unexpected multiplier. Expected a whole number. Found {s}."
+        )))
     }
-    Ok(())
 }
 
 #[inline]
 fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
-    fields: &mut Fields,
+    fields: &mut Fields<'_>,
 ) -> Result<Proxy<Contract, E>, DecodeError> {
     decode_fields!(
         fields =>
@@ -2564,17 +3356,22 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
             long_name: String::default(),
             order_types: Vec::default(),
             valid_exchanges: Vec::default(),
+            trading_hours: Vec::default(),
+            liquid_hours: Vec::default(),
         }),
         ContractType::Crypto => Contract::Crypto(Crypto {
             contract_id,
             min_tick: f64::default(),
             symbol,
+            exchange,
             trading_class,
             currency,
             local_symbol,
             long_name: String::default(),
             order_types: Vec::default(),
             valid_exchanges: Vec::default(),
+            trading_hours: Vec::default(),
+            liquid_hours: Vec::default(),
         }),
         ContractType::Index => Contract::Index(Index {
             contract_id,
@@ -2586,6 +3383,8 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
             long_name: String::default(),
             order_types: Vec::default(),
             valid_exchanges: Vec::default(),
+            trading_hours: Vec::default(),
+            liquid_hours: Vec::default(),
         }),
         ContractType::Commodity => Contract::Commodity(Commodity {
             contract_id,
@@ -2598,6 +3397,8 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
             long_name: String::default(),
             order_types: Vec::default(),
             valid_exchanges: Vec::default(),
+            trading_hours: Vec::default(),
+            liquid_hours: Vec::default(),
         }),
         ContractType::Forex => Contract::Forex(Forex {
             contract_id,
@@ -2610,13 +3411,15 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
             long_name: String::default(),
             order_types: Vec::default(),
             valid_exchanges: Vec::default(),
+            trading_hours: Vec::default(),
+            liquid_hours: Vec::default(),
         }),
         ContractType::SecFuture => Contract::SecFuture(SecFuture {
             contract_id,
             min_tick: f64::default(),
             symbol,
             exchange,
-            multiplier: multiplier.parse().map_err(|e| ("multiplier", e))?,
+            multiplier: parse_multiplier(&multiplier)?,
             expiration_date: NaiveDate::parse_and_remainder(expiration_date.as_str(), "%Y%m%d")
                 .map_err(|e| ("expiration_date", ParseDateTimeError::Parse(e)))?
                 .0,
@@ -2627,6 +3430,8 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
             long_name: String::default(),
             order_types: Vec::default(),
             valid_exchanges: Vec::default(),
+            trading_hours: Vec::default(),
+            liquid_hours: Vec::default(),
         }),
         ContractType::SecOption => {
             let op_inner = SecOptionInner {
@@ -2635,7 +3440,7 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
                 symbol,
                 exchange,
                 strike: strike.parse().map_err(|e| ("strike", e))?,
-                multiplier: multiplier.parse().map_err(|e| ("multiplier", e))?,
+                multiplier: parse_multiplier(&multiplier)?,
                 expiration_date: NaiveDate::parse_and_remainder(expiration_date.as_str(), "%Y%m%d")
                     .map_err(|e| ("expiration_date", ParseDateTimeError::Parse(e)))?
                     .0,
@@ -2647,6 +3452,8 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
                 long_name: String::default(),
                 order_types: Vec::default(),
                 valid_exchanges: Vec::default(),
+                trading_hours: Vec::default(),
+                liquid_hours: Vec::default(),
             };
             let op_outer = match right.as_str() {
                 "C" => SecOption::Call(op_inner),
@@ -2659,6 +3466,13 @@ fn deserialize_contract_proxy<E: crate::contract::ProxyExchange + Clone>(
             };
             Contract::SecOption(op_outer)
         }
+        unsupported => {
+            return Err((
+                "sec_type",
+                crate::contract::ParseContractTypeError(unsupported.to_string()),
+            )
+                .into())
+        }
     };
 
     Ok(Proxy {
@@ -2690,6 +3504,12 @@ pub(crate) enum DecodeError {
         field_name: &'static str,
         float_error: std::num::ParseFloatError,
     },
+    #[error("Failed to parse quantity field {field_name}. Cause: {quantity_error}")]
+    /// Failed to parse [`Quantity`] field
+    ParseQuantityError {
+        field_name: &'static str,
+        quantity_error: ParseQuantityError,
+    },
     #[error("Failed to parse currency field {field_name}. Cause: {currency_error}")]
     /// Failed to parse [`Currency`] field
     ParseCurrencyError {
@@ -2748,6 +3568,13 @@ pub(crate) enum DecodeError {
     },
     #[error("{0}")]
     UnexpectedData(&'static str),
+    #[error("Count field {field_name} of {count} items exceeds the {remaining} remaining fields.")]
+    /// A count of repeated items that the rest of the message cannot hold
+    CountExceedsMessage {
+        field_name: &'static str,
+        count: usize,
+        remaining: usize,
+    },
     #[error("Error when sending data {0}")]
     SendError(#[from] Box<tokio::sync::mpsc::error::SendError<ToClient>>),
     #[error("{0}")]
@@ -2771,6 +3598,17 @@ impl DecodeError {
     }
 }
 
+impl DecodeContext {
+    #[inline]
+    pub(crate) fn into_report(self, fields: Vec<String>) -> crate::payload::DecodeErrorReport {
+        crate::payload::DecodeErrorReport {
+            kind: self.function_name.to_owned(),
+            error: self.decode_error.to_string(),
+            fields,
+        }
+    }
+}
+
 impl From<(&'static str, std::num::ParseIntError)> for DecodeError {
     fn from(value: (&'static str, std::num::ParseIntError)) -> Self {
         Self::ParseIntError {
@@ -2798,6 +3636,15 @@ impl From<(&'static str, std::num::ParseFloatError)> for DecodeError {
     }
 }
 
+impl From<(&'static str, ParseQuantityError)> for DecodeError {
+    fn from(value: (&'static str, ParseQuantityError)) -> Self {
+        Self::ParseQuantityError {
+            field_name: value.0,
+            quantity_error: value.1,
+        }
+    }
+}
+
 impl From<(&'static str, crate::currency::ParseCurrencyError)> for DecodeError {
     fn from(value: (&'static str, crate::currency::ParseCurrencyError)) -> Self {
         Self::ParseCurrencyError {
@@ -2908,24 +3755,874 @@ pub enum ParseDateTimeError {
     Single,
 }
 
-fn parse_historical_datetime(s: &str) -> Result<DateTime<chrono::Utc>, ParseDateTimeError> {
+// Parse a datetime in any of the formats of a historical bar. A datetime that is sent without a
+// timezone, which is the case for the dates of daily and longer bars, is interpreted in `default`.
+// Returns the datetime along with the timezone in which it was sent.
+fn parse_historical_datetime(
+    s: &str,
+    default: chrono_tz::Tz,
+) -> Result<(DateTime<chrono::Utc>, chrono_tz::Tz), ParseDateTimeError> {
+    // Epoch seconds, as sent for intraday bars with `DateFormat::Epoch`
+    if s.len() > 8 && s.bytes().all(|b| b.is_ascii_digit()) {
+        let timestamp = s.parse().map_err(|_| ParseDateTimeError::Timestamp)?;
+        return Ok((
+            DateTime::from_timestamp(timestamp, 0).ok_or(ParseDateTimeError::Timestamp)?,
+            default,
+        ));
+    }
+    let (datetime, tz) = match split_fixed_datetime(s) {
+        Some(parsed) => parsed,
+        None => split_historical_datetime(s)?,
+    };
+    let tz = match tz.map(str::trim) {
+        None | Some("") => default,
+        Some(tz) => tz.parse::<chrono_tz::Tz>()?,
+    };
+    Ok((
+        datetime
+            .and_local_timezone(tz)
+            .single()
+            .ok_or(ParseDateTimeError::Single)?
+            .to_utc(),
+        tz,
+    ))
+}
+
+fn split_historical_datetime(s: &str) -> Result<(NaiveDateTime, Option<&str>), ParseDateTimeError> {
     // Option 1: UTC datetime YYYYmmdd-HH:MM:SS
     if s.get(8..9).is_some_and(|c| c.eq("-")) {
-        return Ok(NaiveDateTime::parse_from_str(s, "%Y%m%d-%T").map(|ref dt| dt.and_utc())?);
+        return Ok((NaiveDateTime::parse_from_str(s, "%Y%m%d-%T")?, Some("UTC")));
     }
 
     let (date, rem) = NaiveDate::parse_and_remainder(s, "%Y%m%d")?;
-    let (time, tz) = if rem.is_empty() {
+    if rem.is_empty() {
         // Option 2: Date with no time
-        (NaiveTime::default(), chrono_tz::UTC)
+        Ok((NaiveDateTime::new(date, NaiveTime::default()), None))
     } else {
         // Option 3: Datetime w/ timezone YYYYmmdd HH:MM:SS TZ
-        let (tm, rem) = NaiveTime::parse_and_remainder(rem, " %T")?;
-        (tm, rem.trim().parse::<chrono_tz::Tz>()?)
+        let (time, tz) = NaiveTime::parse_and_remainder(rem, " %T")?;
+        Ok((NaiveDateTime::new(date, time), Some(tz)))
+    }
+}
+
+#[inline]
+// Historical bars arrive in the thousands, so the fixed-width layouts that TWS actually sends are
+// parsed by hand. Anything else (ex. a leap second) falls back to `split_historical_datetime`.
+fn split_fixed_datetime(s: &str) -> Option<(NaiveDateTime, Option<&str>)> {
+    let b = s.as_bytes();
+    let date = NaiveDate::from_ymd_opt(
+        i32::try_from(parse_digits(b.get(..4)?)?).ok()?,
+        parse_digits(b.get(4..6)?)?,
+        parse_digits(b.get(6..8)?)?,
+    )?;
+    let (sep, rest) = match b.get(8) {
+        None => return Some((NaiveDateTime::new(date, NaiveTime::default()), None)),
+        Some(sep) => (*sep, b.get(9..)?),
+    };
+    let time = match rest.get(..8)? {
+        [h0, h1, b':', m0, m1, b':', s0, s1] => NaiveTime::from_hms_opt(
+            parse_digits(&[*h0, *h1])?,
+            parse_digits(&[*m0, *m1])?,
+            parse_digits(&[*s0, *s1])?,
+        )?,
+        _ => return None,
     };
-    Ok(NaiveDateTime::new(date, time)
+    let datetime = NaiveDateTime::new(date, time);
+    match (sep, rest.len()) {
+        (b'-', 8) => Some((datetime, Some("UTC"))),
+        (b' ', len) if len > 9 && rest[8] == b' ' => Some((datetime, s.get(18..))),
+        _ => None,
+    }
+}
+
+#[inline]
+fn parse_digits(b: &[u8]) -> Option<u32> {
+    b.iter().try_fold(0, |acc, &d| {
+        d.is_ascii_digit().then(|| acc * 10 + u32::from(d - b'0'))
+    })
+}
+
+#[inline]
+fn parse_schedule_datetime(
+    s: &str,
+    tz: chrono_tz::Tz,
+) -> Result<DateTime<chrono::Utc>, ParseDateTimeError> {
+    NaiveDateTime::parse_from_str(s, "%Y%m%d-%T")?
         .and_local_timezone(tz)
         .single()
-        .ok_or(ParseDateTimeError::Single)?
-        .to_utc())
+        .ok_or(ParseDateTimeError::Single)
+        .map(|dt| dt.to_utc())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct HistoricalRecorder {
+    bars: Vec<(i64, Vec<Bar>)>,
+    schedules: Vec<(i64, HistoricalSchedule)>,
+}
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for HistoricalRecorder {
+    fn historical_bars(
+        &mut self,
+        req_id: i64,
+        _start_datetime: DateTime<chrono::Utc>,
+        _end_datetime: DateTime<chrono::Utc>,
+        bars: Vec<Bar>,
+    ) -> impl Future {
+        self.bars.push((req_id, bars));
+        async {}
+    }
+
+    fn historical_schedule(&mut self, req_id: i64, schedule: HistoricalSchedule) -> impl Future {
+        self.schedules.push((req_id, schedule));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_historical_data_types() -> Result<(), DecodeError> {
+    use chrono::TimeZone;
+
+    let mut wrapper = HistoricalRecorder::default();
+
+    // Volatility and fee rate bars carry no volume, WAP, or trade count.
+    let frame = crate::frame::Frame::from_fields(&[
+        "17",
+        "7",
+        "20240102 00:00:00 US/Eastern",
+        "20240104 00:00:00 US/Eastern",
+        "2",
+        "20240102",
+        "0.25",
+        "0.26",
+        "0.24",
+        "0.255",
+        "-1",
+        "-1",
+        "-1",
+        "20240103",
+        "0.255",
+        "0.27",
+        "0.25",
+        "0.265",
+        "-1",
+        "-1",
+        "-1",
+    ]);
+    Local::historical_data_msg(&mut frame.fields(), &mut wrapper).await?;
+    let (req_id, bars) = &wrapper.bars[0];
+    assert_eq!(*req_id, 7);
+    assert_eq!(bars.len(), 2);
+    assert!(matches!(bars[1], Bar::Ordinary(core) if core.close == 0.265));
+
+    // Intraday bars requested with `DateFormat::Epoch` (ex. for a continuous future) are sent as
+    // Unix timestamps.
+    let frame = crate::frame::Frame::from_fields(&[
+        "17",
+        "9",
+        "20240102 09:30:00 US/Eastern",
+        "20240102 09:31:00 US/Eastern",
+        "1",
+        "1704205800",
+        "4750.25",
+        "4751",
+        "4750",
+        "4750.5",
+        "120",
+        "4750.4",
+        "35",
+    ]);
+    Local::historical_data_msg(&mut frame.fields(), &mut wrapper).await?;
+    let (req_id, bars) = &wrapper.bars[1];
+    assert_eq!(*req_id, 9);
+    let open = chrono::Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+    assert!(matches!(
+        bars[..],
+        [Bar::Trades(trade)] if trade.bar.datetime == open && trade.trade_count == 35
+    ));
+
+    let frame = crate::frame::Frame::from_fields(&[
+        "106",
+        "8",
+        "20240102-09:30:00",
+        "20240103-16:00:00",
+        "US/Eastern",
+        "2",
+        "20240102-09:30:00",
+        "20240102-16:00:00",
+        "20240102",
+        "20240103-09:30:00",
+        "20240103-16:00:00",
+        "20240103",
+    ]);
+    Local::historical_schedule_msg(&mut frame.fields(), &mut wrapper).await?;
+    let (req_id, schedule) = &wrapper.schedules[0];
+    assert_eq!(*req_id, 8);
+    assert_eq!(schedule.time_zone, chrono_tz::US::Eastern);
+    assert_eq!(schedule.sessions.len(), 2);
+    assert_eq!(
+        schedule.sessions[1],
+        ScheduleSession {
+            start_datetime: chrono::Utc.with_ymd_and_hms(2024, 1, 3, 14, 30, 0).unwrap(),
+            end_datetime: chrono::Utc.with_ymd_and_hms(2024, 1, 3, 21, 0, 0).unwrap(),
+            reference_date: NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+        }
+    );
+
+    // The session count must match the sessions that follow it.
+    let frame = crate::frame::Frame::from_fields(&[
+        "106",
+        "9",
+        "20240102-09:30:00",
+        "20240103-16:00:00",
+        "US/Eastern",
+        "2",
+        "20240102-09:30:00",
+        "20240102-16:00:00",
+        "20240102",
+    ]);
+    assert!(matches!(
+        Local::historical_schedule_msg(&mut frame.fields(), &mut wrapper).await,
+        Err(DecodeError::CountExceedsMessage {
+            field_name: "count",
+            count: 2,
+            remaining: 3,
+        })
+    ));
+    assert_eq!(wrapper.schedules.len(), 1);
+    Ok(())
+}
+
+#[cfg(all(test, feature = "scanner"))]
+#[derive(Debug, Default)]
+struct ScannerRecorder(Vec<(i64, Vec<ScannerRow>)>);
+
+#[cfg(all(test, feature = "scanner"))]
+impl wrapper::LocalWrapper for ScannerRecorder {
+    fn scanner_data(&mut self, req_id: i64, rows: Vec<ScannerRow>) -> impl Future {
+        self.0.push((req_id, rows));
+        async {}
+    }
+}
+
+#[cfg(feature = "scanner")]
+#[tokio::test]
+async fn test_scanner_data() -> Result<(), DecodeError> {
+    let mut wrapper = ScannerRecorder::default();
+    let frame = crate::frame::Frame::from_fields(&[
+        "20", "3", "4", "2", "0", "265598", "AAPL", "STK", "", "0", "", "SMART", "USD", "AAPL",
+        "NMS", "NMS", "", "", "", "", "1", "272093", "MSFT", "STK", "", "0", "", "SMART", "USD",
+        "MSFT", "NMS", "NMS", "", "", "", "",
+    ]);
+    Local::scanner_data_msg(&mut frame.fields(), &mut wrapper).await?;
+    let (req_id, rows) = &wrapper.0[0];
+    assert_eq!(*req_id, 4);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].rank, 1);
+    assert_eq!(rows[1].contract_id, ContractId(272_093));
+    assert_eq!(rows[1].exchange, "SMART");
+    assert_eq!(rows[1].trading_class, "NMS");
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct OpenOrderRecorder(Vec<(i64, OrderAmount)>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for OpenOrderRecorder {
+    fn open_order(
+        &mut self,
+        order_id: i64,
+        _proxy: crate::contract::ExchangeProxy<Contract>,
+        amount: OrderAmount,
+        _client_id: i64,
+        _parent_id: Option<i64>,
+        _permanent_id: i64,
+    ) -> impl Future {
+        self.0.push((order_id, amount));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_open_order_amount() -> Result<(), DecodeError> {
+    fn open_order(order_id: &str, quantity: &str, sections: &[&str], cash: &str) -> Vec<String> {
+        let mut fields = vec!["5", order_id, "265598", "AAPL", "STK", "", "0", "", "", "SMART"];
+        fields.extend(["USD", "AAPL", "NMS", "BUY", quantity, "MKT", "", "", "DAY", "", "DU1"]);
+        fields.extend(["", "0", "", "0", "1234"]);
+        fields.extend(std::iter::repeat_n("", 31));
+        // The parent ID, trigger method, volatility, volatility type, and delta neutral order type.
+        fields.extend(["0", "0", "", "0", ""]);
+        fields.extend(std::iter::repeat_n("", 8));
+        fields.extend(sections);
+        fields.extend(std::iter::repeat_n("", 11));
+        fields.push(cash);
+        fields.extend(["0", "0", "", "0"]);
+        fields.into_iter().map(str::to_owned).collect()
+    }
+
+    let mut wrapper = OpenOrderRecorder::default();
+    let mut sections = vec!["0", "0", "0", "", "", "", "", "0", "", "", "0", "0", ""];
+    sections.extend(std::iter::repeat_n("", 19));
+    sections.push("0");
+    let frame = crate::frame::Frame::from_fields(&open_order("7", "0", &sections, "500"));
+    Local::open_order_msg(&mut frame.fields(), &mut wrapper).await?;
+    let frame = crate::frame::Frame::from_fields(&open_order("8", "0.5", &sections, ""));
+    Local::open_order_msg(&mut frame.fields(), &mut wrapper).await?;
+
+    // Combo legs, routing parameters, an algo, and a price condition are skipped.
+    let mut sections = vec!["1", "8314", "1", "BUY", "SMART", "0", "0", "", "-1", "1", "1.5"];
+    sections.extend(["1", "NonGuaranteed", "1", "", "", "", "", "0", "", "", "0", "1"]);
+    sections.extend(["8314", "0.5", "100", "Adaptive", "1", "adaptivePriority", "Normal"]);
+    sections.extend(std::iter::repeat_n("", 19));
+    sections.extend(["1", "1", "a", "1", "190", "265598", "SMART", "2", "0", "0"]);
+    let frame = crate::frame::Frame::from_fields(&open_order("9", "0", &sections, "1.7976931348623157E308"));
+    Local::open_order_msg(&mut frame.fields(), &mut wrapper).await?;
+    let frame = crate::frame::Frame::from_fields(&open_order("10", "0", &sections, "250.5"));
+    Local::open_order_msg(&mut frame.fields(), &mut wrapper).await?;
+
+    assert_eq!(
+        wrapper.0,
+        vec![
+            (7, OrderAmount::Cash(500.0)),
+            (8, OrderAmount::Quantity("0.5".parse().unwrap())),
+            (9, OrderAmount::Quantity(Quantity::ZERO)),
+            (10, OrderAmount::Cash(250.5)),
+        ]
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct PositionRecorder(Vec<PositionSummary>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for PositionRecorder {
+    fn position_summary(&mut self, summary: PositionSummary) -> impl Future {
+        self.0.push(summary);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_position_data() -> Result<(), DecodeError> {
+    // Positions as sent by TWS, which writes the multipliers of options and futures as decimals.
+    let mut wrapper = PositionRecorder::default();
+    for fields in [
+        [
+            "61", "3", "DU1234567", "265598", "AAPL", "STK", "", "0.0", "", "", "NASDAQ", "USD",
+            "AAPL", "NMS", "100", "189.47",
+        ],
+        [
+            "61", "3", "DU1234567", "684437071", "AAPL", "OPT", "20250117", "180.0", "C", "100.0",
+            "CBOE", "USD", "AAPL  250117C00180000", "AAPL", "2", "1523.4",
+        ],
+        [
+            "61", "3", "DU1234567", "495512563", "ES", "FUT", "20250321", "0.0", "", "50.0", "CME",
+            "USD", "ESH5", "ES", "-1", "295012.5",
+        ],
+    ] {
+        let frame = crate::frame::Frame::from_fields(&fields);
+        Local::position_data_msg(&mut frame.fields(), &mut wrapper).await?;
+    }
+    let multipliers = wrapper
+        .0
+        .iter()
+        .map(|summary| summary.contract.inner.multiplier())
+        .collect::<Vec<_>>();
+    assert_eq!(multipliers, [None, Some(100), Some(50)]);
+    assert_eq!(wrapper.0[1].position, Quantity::from(2));
+
+    for multiplier in ["100.5", "-1", "1e10", "x"] {
+        assert!(parse_multiplier(multiplier).is_err());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct AttributeRecorder(Vec<account::Attribute>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for AttributeRecorder {
+    fn account_attribute(&mut self, attribute: account::Attribute, _: String) -> impl Future {
+        self.0.push(attribute);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_account_value_data() -> Result<(), DecodeError> {
+    // Account values as sent by TWS, some of which are formatted for the account's locale.
+    let mut wrapper = AttributeRecorder::default();
+    for fields in [
+        ["6", "2", "NetLiquidation", "1,234,567.89", "USD", "DU1234567"],
+        ["6", "2", "NetLiquidation-S", "1.234.567,89", "USD", "DU1234567"],
+        ["6", "2", "Cushion", "0,452", "", "DU1234567"],
+        ["6", "2", "DayTradesRemaining", "-1", "", "DU1234567"],
+        ["6", "2", "BuyingPower", "", "USD", "DU1234567"],
+        ["6", "2", "ExchangeRate", "1.00", "BASE", "DU1234567"],
+        ["6", "2", "CashBalance", "1,234.56", "USD", "DU1234567"],
+        ["6", "2", "SegmentTitle-S", "", "", "DU1234567"],
+    ] {
+        let frame = crate::frame::Frame::from_fields(&fields);
+        Local::acct_value_msg(&mut frame.fields(), &mut wrapper).await?;
+    }
+    assert_eq!(wrapper.0.len(), 7);
+    assert_eq!(
+        wrapper.0[0],
+        account::Attribute::NetLiquidation(
+            account::Segment::Total(1_234_567.89),
+            account::Denomination::Specific(crate::currency::Currency::UsDollar)
+        )
+    );
+    assert_eq!(
+        wrapper.0[1],
+        account::Attribute::NetLiquidation(
+            account::Segment::Security(1_234_567.89),
+            account::Denomination::Specific(crate::currency::Currency::UsDollar)
+        )
+    );
+    assert_eq!(wrapper.0[2], account::Attribute::Cushion(0.452));
+    assert_eq!(
+        wrapper.0[4],
+        account::Attribute::Unknown {
+            name: "BuyingPower".to_owned(),
+            value: String::new(),
+            currency: "USD".to_owned(),
+        }
+    );
+    assert_eq!(
+        wrapper.0[6],
+        account::Attribute::CashBalance(
+            1_234.56,
+            account::Denomination::Specific(crate::currency::Currency::UsDollar)
+        )
+    );
+
+    for (value, normalized) in [
+        ("12.5", "12.5"),
+        ("1,234", "1234"),
+        ("-12'345.5", "-12345.5"),
+        ("1 234,5", "1234.5"),
+        ("1,23,4", "1,23,4"),
+        ("USD", "USD"),
+    ] {
+        assert_eq!(normalize_account_value(value).as_deref(), Some(normalized));
+    }
+    assert_eq!(normalize_account_value(" "), None);
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct SummaryRecorder(Vec<account::TagValue>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for SummaryRecorder {
+    fn account_summary(&mut self, _: i64, _: String, summary: account::TagValue) -> impl Future {
+        self.0.push(summary);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_account_summary_ledger() -> Result<(), DecodeError> {
+    // Summary rows as sent by TWS for the tags and ledger of a request with `$LEDGER:ALL`.
+    let mut wrapper = SummaryRecorder::default();
+    for fields in [
+        ["63", "1", "5", "DU1234567", "NetLiquidation", "1234.5", "USD"],
+        ["63", "1", "5", "DU1234567", "Cushion", "0.45", ""],
+        ["63", "1", "5", "DU1234567", "CashBalance", "1000.25", "BASE"],
+        ["63", "1", "5", "DU1234567", "AccruedCash", "12.5", "EUR"],
+        ["63", "1", "5", "DU1234567", "RealCurrency", "EUR", "EUR"],
+    ] {
+        let frame = crate::frame::Frame::from_fields(&fields);
+        Local::account_summary_msg(&mut frame.fields(), &mut wrapper).await?;
+    }
+    assert_eq!(
+        wrapper.0,
+        [
+            account::TagValue::Currency(
+                account::Tag::NetLiquidation,
+                1234.5,
+                crate::currency::Currency::UsDollar
+            ),
+            account::TagValue::Float(account::Tag::Cushion, 0.45),
+            account::TagValue::Ledger {
+                tag: "CashBalance".to_owned(),
+                value: "1000.25".to_owned(),
+                denomination: account::Denomination::Base,
+            },
+            account::TagValue::Currency(
+                account::Tag::AccruedCash,
+                12.5,
+                crate::currency::Currency::Euro
+            ),
+            account::TagValue::Ledger {
+                tag: "RealCurrency".to_owned(),
+                value: "EUR".to_owned(),
+                denomination: account::Denomination::Specific(crate::currency::Currency::Euro),
+            },
+        ]
+    );
+
+    // An unknown tag outside of a ledger is an error.
+    let frame =
+        crate::frame::Frame::from_fields(&["63", "1", "5", "DU1234567", "NotATag", "1", ""]);
+    assert!(Local::account_summary_msg(&mut frame.fields(), &mut wrapper)
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct ExecutionRecorder(Vec<Execution>, Vec<CommissionReport>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for ExecutionRecorder {
+    fn execution(&mut self, _: i64, execution: Execution) -> impl Future {
+        self.0.push(execution);
+        async {}
+    }
+
+    fn commission_report(&mut self, commission_report: CommissionReport) -> impl Future {
+        self.1.push(commission_report);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_execution_data() -> Result<(), DecodeError> {
+    // Executions as sent by TWS, whose tails hold the economic value fields, the model code, the
+    // liquidity flag, and whether a price revision is pending.
+    let mut wrapper = ExecutionRecorder::default();
+    for fields in [
+        [
+            "11", "-1", "7", "265598", "AAPL", "STK", "", "0.0", "", "", "ISLAND", "USD", "AAPL",
+            "NMS", "0000e0d5.65f1a6b2.01.01", "20240102 09:30:01 US/Eastern", "DU1234567",
+            "ISLAND", "BOT", "100", "189.47", "1234567890", "0", "0", "100", "189.47", "", "",
+            "", "", "2", "0",
+        ],
+        [
+            "11", "3", "8", "684437071", "AAPL", "OPT", "20250117", "180.0", "C", "100", "CBOE",
+            "USD", "AAPL  250117C00180000", "AAPL", "0000e0d5.65f1a6b3.01.01",
+            "20240102 09:31:00 US/Eastern", "DU1234567", "CBOE", "SLD", "2", "15.2",
+            "1234567891", "0", "0", "2", "15.2", "ref", "aMF", "100", "Growth", "1", "1",
+        ],
+    ] {
+        let frame = crate::frame::Frame::from_fields(&fields);
+        Local::execution_data_msg(&mut frame.fields(), &mut wrapper).await?;
+    }
+    let [Execution::Bought(bought), Execution::Sold(sold)] = wrapper.0.as_slice() else {
+        panic!("Expected a purchase and a sale, got {:?}", wrapper.0);
+    };
+    assert_eq!(bought.last_liquidity, Some(Liquidity::Removed));
+    assert_eq!(bought.model_code, None);
+    assert_eq!(bought.ev_rule, None);
+    assert_eq!(bought.ev_multiplier, None);
+    assert!(!bought.pending_price_revision);
+    assert_eq!(sold.last_liquidity, Some(Liquidity::Added));
+    assert_eq!(sold.model_code.as_deref(), Some("Growth"));
+    assert_eq!(sold.ev_rule.as_deref(), Some("aMF"));
+    assert_eq!(sold.ev_multiplier, Some(100.0));
+    assert!(sold.pending_price_revision);
+
+    let frame = crate::frame::Frame::from_fields(&[
+        "59",
+        "1",
+        "0000e0d5.65f1a6b3.01.01",
+        "1.05",
+        "EUR",
+        "1.7976931348623157E308",
+        "1.7976931348623157E308",
+        "",
+    ]);
+    Local::commission_report_msg(&mut frame.fields(), &mut wrapper).await?;
+    assert_eq!(wrapper.1[0].currency, crate::currency::Currency::Euro);
+    assert_eq!(wrapper.1[0].commission, 1.05);
+    assert_eq!(wrapper.1[0].yld, None);
+    Ok(())
+}
+
+#[test]
+fn test_decode_managed_accounts() -> Result<(), DecodeError> {
+    let frame = crate::frame::Frame::from_fields(&["15", "1", "DU1234567,DU7654321,"]);
+    let accounts = decode_managed_accounts(&mut frame.fields())?;
+    assert_eq!(accounts.len(), 2);
+    assert!(accounts.contains("DU1234567") && accounts.contains("DU7654321"));
+    Ok(())
+}
+
+#[test]
+fn test_decode_fundamental_ticks() -> Result<(), DecodeError> {
+    let dividends = decode_dividends("0.83,0.92,20240209,0.24")?;
+    assert_eq!(dividends.trailing_year, 0.83);
+    assert_eq!(
+        dividends.next_dividend,
+        Some(Dividend {
+            date: NaiveDate::from_ymd_opt(2024, 2, 9).unwrap(),
+            amount: 0.24,
+        })
+    );
+    let dividends = decode_dividends(",,,")?;
+    assert_eq!(dividends.forward_year, 0.0);
+    assert_eq!(dividends.next_dividend, None);
+
+    let ratios = decode_fundamental_ratios(
+        "TTMNPMGN=25.31;NLOW=124.17;CURRENCY=USD;PEEXCLXOR=29.52;YIELD=-99999.99;BETA=;",
+    )?;
+    assert_eq!(ratios.currency.as_deref(), Some("USD"));
+    assert_eq!(ratios.get("PEEXCLXOR"), Some(29.52));
+    assert_eq!(ratios.get("YIELD"), None);
+    assert_eq!(ratios.ratios.len(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_parse_trading_hours() -> Result<(), DecodeError> {
+    let at = |s: &str| s.parse::<DateTime<chrono::Utc>>().unwrap();
+    let (trading, liquid) = parse_trading_hours(
+        "20240105:1700-20240106:1600;20240106:CLOSED;20240107:1700-20240108:1600",
+        "20240105:0930-20240105:1600,1800-0200",
+        "US/Central",
+    )?;
+    assert_eq!(trading.len(), 2);
+    assert_eq!(trading[0].open, at("2024-01-05T23:00:00Z"));
+    assert_eq!(trading[0].close, at("2024-01-06T22:00:00Z"));
+    assert_eq!(trading[0].date, NaiveDate::from_ymd_opt(2024, 1, 6).unwrap());
+    assert!(trading[1].contains(at("2024-01-08T12:00:00Z")));
+    assert_eq!(liquid[1].close, at("2024-01-06T08:00:00Z"));
+    assert_eq!(parse_trading_hours("", "", "")?, (Vec::new(), Vec::new()));
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct QuoteRecorder {
+    prices: Vec<(i64, Class<Price>)>,
+    quotes: Vec<(i64, crate::tick::QuoteEvent)>,
+}
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for QuoteRecorder {
+    fn price_data(&mut self, req_id: i64, price: Class<Price>) -> impl Future {
+        self.prices.push((req_id, price));
+        async {}
+    }
+
+    fn bid_ask_quote(&mut self, req_id: i64, quote: crate::tick::QuoteEvent) -> impl Future {
+        self.quotes.push((req_id, quote));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_combined_quotes() -> Result<(), DecodeError> {
+    let (_tx, rx) = tokio::sync::mpsc::channel(8);
+    let mut rx = Rx::new(rx, std::sync::Arc::default()).with_combined_quotes(true);
+    let mut wrapper = QuoteRecorder::default();
+
+    let frame = crate::frame::Frame::from_fields(&["1", "6", "3", "1", "101.5", "200", "0"]);
+    Local::tick_price_msg(&mut frame.fields(), &mut wrapper, &mut rx).await?;
+    let frame = crate::frame::Frame::from_fields(&["1", "6", "3", "67", "101.75", "", "0"]);
+    Local::tick_price_msg(&mut frame.fields(), &mut wrapper, &mut rx).await?;
+    let frame = crate::frame::Frame::from_fields(&["1", "6", "3", "4", "101.6", "100", "0"]);
+    Local::tick_price_msg(&mut frame.fields(), &mut wrapper, &mut rx).await?;
+
+    assert_eq!(
+        wrapper.quotes,
+        vec![
+            (
+                3,
+                Class::Live(Quote::Bid {
+                    price: 101.5,
+                    size: Some(200.0)
+                })
+            ),
+            (
+                3,
+                Class::Delayed(Quote::Ask {
+                    price: 101.75,
+                    size: None
+                })
+            ),
+        ]
+    );
+    assert_eq!(wrapper.prices, vec![(3, Class::Live(Price::Last(101.6)))]);
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct ShortBorrowRecorder(Vec<(i64, crate::tick::ShortBorrow)>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for ShortBorrowRecorder {
+    fn short_borrow(
+        &mut self,
+        req_id: i64,
+        short_borrow: crate::tick::ShortBorrow,
+    ) -> impl Future {
+        self.0.push((req_id, short_borrow));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_short_borrow() -> Result<(), DecodeError> {
+    use crate::tick::{ShortBorrow, ShortableTier};
+
+    let (_tx, rx) = tokio::sync::mpsc::channel(8);
+    let mut rx = Rx::new(rx, std::sync::Arc::default());
+    let mut wrapper = ShortBorrowRecorder::default();
+
+    for fields in [
+        ["45", "6", "3", "89", "30000"],
+        ["45", "6", "4", "46", "3.0"],
+        // A halted tick is not a stock-loan update.
+        ["45", "6", "3", "49", "0"],
+        ["45", "6", "3", "46", "2.0"],
+    ] {
+        let frame = crate::frame::Frame::from_fields(&fields);
+        Local::tick_generic_msg(&mut frame.fields(), &mut wrapper, &mut rx).await?;
+    }
+    // The state of a cancelled request is dropped.
+    rx.shared().finish_request(3);
+    let frame = crate::frame::Frame::from_fields(&["45", "6", "3", "46", "1.0"]);
+    Local::tick_generic_msg(&mut frame.fields(), &mut wrapper, &mut rx).await?;
+
+    let borrow = |shares_available, tier| ShortBorrow {
+        shares_available,
+        tier,
+    };
+    assert_eq!(
+        wrapper.0,
+        vec![
+            (3, borrow(Some(30_000.0), None)),
+            (4, borrow(None, Some(ShortableTier::Easy))),
+            (3, borrow(Some(30_000.0), Some(ShortableTier::Hard))),
+            (3, borrow(None, Some(ShortableTier::Unavailable))),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_historical_datetime() -> Result<(), ParseDateTimeError> {
+    for s in [
+        "20240102",
+        "20240102-14:30:00",
+        "20240102 09:30:00 US/Eastern",
+        "20240102 09:30:00  US/Eastern ",
+        "20161231 23:59:60 UTC",
+    ] {
+        let (datetime, tz) = split_historical_datetime(s)?;
+        if let Some(fixed) = split_fixed_datetime(s) {
+            assert_eq!(
+                (fixed.0, fixed.1.map(str::trim)),
+                (datetime, tz.map(str::trim)),
+                "{s}"
+            );
+        }
+    }
+    let utc = |s: &str| s.parse::<DateTime<chrono::Utc>>().unwrap();
+    let (datetime, tz) =
+        parse_historical_datetime("20240102 09:30:00 US/Eastern", chrono_tz::UTC)?;
+    assert_eq!((datetime, tz), (utc("2024-01-02T14:30:00Z"), chrono_tz::US::Eastern));
+    assert_eq!(
+        parse_historical_datetime("20240102-14:30:00", tz)?,
+        (datetime, chrono_tz::UTC)
+    );
+    assert_eq!(
+        parse_historical_datetime("1704205800", tz)?,
+        (datetime, chrono_tz::US::Eastern)
+    );
+    assert_eq!(
+        parse_historical_datetime("20240102", tz)?.0,
+        utc("2024-01-02T05:00:00Z")
+    );
+    assert_eq!(
+        parse_historical_datetime("20240102 09:30:00", chrono_tz::UTC)?.0,
+        utc("2024-01-02T09:30:00Z")
+    );
+    assert!(split_fixed_datetime("2024010209:30:00").is_none());
+    assert!(
+        parse_historical_datetime("20240102 09:30:00 Mars/Olympus", chrono_tz::UTC).is_err()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct DecodeErrorRecorder(Vec<crate::payload::DecodeErrorReport>);
+
+#[cfg(test)]
+impl wrapper::LocalWrapper for DecodeErrorRecorder {
+    fn decode_error(&mut self, report: crate::payload::DecodeErrorReport) -> impl Future {
+        self.0.push(report);
+        async {}
+    }
+}
+
+// Messages as sent by TWS, from which the malformed messages of `test_decode_mutated_messages`
+// are derived. The same messages seed the corpus of the `decode` target in `fuzz/`.
+#[cfg(test)]
+const CAPTURED_MESSAGES: &str = include_str!("../fuzz/seeds.txt");
+
+#[tokio::test]
+async fn test_decode_mutated_messages() {
+    const REPLACEMENTS: [&str; 11] = [
+        "",
+        "-1",
+        "0",
+        "99",
+        "1e400",
+        "NaN",
+        "18446744073709551615",
+        "-9223372036854775808",
+        "20240102 09:30:00 Mars/Olympus",
+        "\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}",
+        "\u{fffd}",
+    ];
+
+    let seeds = CAPTURED_MESSAGES
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(crate::replay::parse_message)
+        .collect::<Vec<_>>();
+    let mut wrapper = DecodeErrorRecorder::default();
+    crate::replay::replay_local(&mut wrapper, seeds.clone()).await;
+    assert_eq!(wrapper.0, Vec::new(), "captured messages must decode cleanly");
+
+    // Each message is truncated, loses or repeats a field, or has a field replaced. None of them
+    // may panic, no matter how far decoding gets before the message is rejected.
+    let mut mutations = Vec::new();
+    for seed in &seeds {
+        for len in 0..seed.len() {
+            mutations.push(seed[..len].to_vec());
+        }
+        for i in 0..seed.len() {
+            let mut removed = seed.clone();
+            removed.remove(i);
+            mutations.push(removed);
+            let mut repeated = seed.clone();
+            repeated.insert(i, seed[i].clone());
+            mutations.push(repeated);
+            for replacement in REPLACEMENTS {
+                let mut replaced = seed.clone();
+                replaced[i] = replacement.to_owned();
+                mutations.push(replaced);
+            }
+        }
+    }
+    // Rejected messages leave nothing behind that affects the messages that follow them.
+    let count = mutations.len() + seeds.len();
+    mutations.extend(seeds.iter().cloned());
+    assert_eq!(crate::replay::replay_local(&mut wrapper, mutations).await, count);
+    assert!(wrapper.0.iter().all(|report| !seeds.contains(&report.fields)));
 }
@@ -448,6 +448,8 @@ pub enum Primary {
     WellsFargoForex,
     #[serde(rename = "WSE")]
     WarsawStockExchange,
+    #[serde(rename = "ZEROHASH")]
+    ZeroHashCryptoExchange,
 }
 
 // === Type implementations ===
@@ -457,7 +459,7 @@ impl FromStr for Routing {
 
     #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.to_uppercase().as_str() {
+        Ok(match to_ascii_uppercase(s).as_ref() {
             "SMART" => Self::Smart,
             prim => Self::Primary(prim.parse()?),
         })
@@ -469,7 +471,7 @@ impl FromStr for Primary {
 
     #[allow(clippy::too_many_lines)]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.to_uppercase().as_str() {
+        Ok(match to_ascii_uppercase(s).as_ref() {
             "AEB" => Self::AmsterdamseEffectenbeurs,
             "ALPHA" => Self::AlphaTradingSystems,
             "AMEX" => Self::AmericanStockExchange,
@@ -671,6 +673,7 @@ impl FromStr for Primary {
             "VSE" => Self::ViennaStockExchange,
             "WFFX" => Self::WellsFargoForex,
             "WSE" => Self::WarsawStockExchange,
+            "ZEROHASH" => Self::ZeroHashCryptoExchange,
             s => return Err(ParseExchangeError(s.to_owned())),
         })
     }
@@ -881,6 +884,7 @@ impl std::fmt::Display for Primary {
             Self::ViennaStockExchange => "VSE",
             Self::WellsFargoForex => "WFFX",
             Self::WarsawStockExchange => "WSE",
+            Self::ZeroHashCryptoExchange => "ZEROHASH",
         };
         write!(f, "{s}")
     }
@@ -895,3 +899,13 @@ impl std::fmt::Display for Routing {
         }
     }
 }
+
+#[inline]
+// Exchange codes arrive in upper case from TWS, so the common case borrows instead of allocating.
+fn to_ascii_uppercase(s: &str) -> std::borrow::Cow<'_, str> {
+    if s.bytes().any(|b| b.is_ascii_lowercase()) {
+        std::borrow::Cow::Owned(s.to_ascii_uppercase())
+    } else {
+        std::borrow::Cow::Borrowed(s)
+    }
+}
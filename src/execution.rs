@@ -1,51 +1,188 @@
+use std::collections::HashMap;
+
 use chrono::serde::ts_seconds;
-use chrono::Utc;
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::contract::{Contract, ContractType, ExchangeProxy};
 use crate::currency::Currency;
-use crate::exchange::Primary;
+use crate::exchange::{Primary, Routing};
+use crate::payload::OrderStatus;
+use crate::quantity::Quantity;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 /// A filter for requesting executions that meet only these criteria.
+///
+/// Empty / `None` fields do not filter any executions. A [`Filter`] can be constructed directly or
+/// with the validating [`FilterBuilder`] returned by [`Filter::builder`].
 pub struct Filter {
     /// Filter by API client id that placed the order.
     pub client_id: i64,
     /// Filter by account number to which the order was allocated
     pub account_number: String,
-    #[serde(with = "serde_filter_datetime")]
+    #[serde(default, with = "serde_filter_datetime")]
     /// Filter by orders placed after this date and time
-    pub datetime: Option<chrono::NaiveDateTime>,
+    pub datetime: Option<DateTime<Utc>>,
     /// Filter by contract symbol.
     pub symbol: String,
     /// Filter by contract type.
     pub contract_type: Option<ContractType>,
     /// Filter by the exchange at which the execution was produced.
-    pub exchange: Option<Primary>,
+    pub exchange: Option<Routing>,
     /// Filter by order side.
     pub side: Option<OrderSide>,
 }
 
+impl Filter {
+    #[inline]
+    #[must_use]
+    /// Create a new [`FilterBuilder`] with which to construct a [`Filter`].
+    pub fn builder() -> FilterBuilder {
+        FilterBuilder::default()
+    }
+}
+
 mod serde_filter_datetime {
+    use chrono::{DateTime, NaiveDateTime, Utc};
     use serde::{Serializer, Deserializer, Deserialize};
     use serde::de::Error;
 
-    pub fn serialize<S: Serializer>(datetime: &Option<chrono::NaiveDateTime>, ser: S) -> Result<S::Ok, S::Error> {
+    const FORMAT: &str = "%Y%m%d-%T";
+
+    pub fn serialize<S: Serializer>(datetime: &Option<DateTime<Utc>>, ser: S) -> Result<S::Ok, S::Error> {
         match datetime {
-            Some(dt) => ser.serialize_str(&dt.format("%Y%m%d %T").to_string()),
+            Some(dt) => ser.serialize_str(&dt.format(FORMAT).to_string()),
             None => ser.serialize_none()
         }
     }
 
-    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Option<chrono::NaiveDateTime>, D::Error> {
-        let s = <&'_ str>::deserialize(de)?;
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let s = Option::<String>::deserialize(de)?.unwrap_or_default();
         if s.is_empty() {
             Ok(None)
         } else {
-            Ok(Some(chrono::NaiveDateTime::parse_from_str(s, "%Y%m%d %T").map_err(Error::custom)?))
+            Ok(Some(NaiveDateTime::parse_from_str(&s, FORMAT).map_err(Error::custom)?.and_utc()))
+        }
+    }
+
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+/// A builder for an execution [`Filter`], which validates each of the criteria before the filter
+/// is created.
+pub struct FilterBuilder {
+    client_id: Option<i64>,
+    account_number: Option<String>,
+    datetime: Option<DateTime<Utc>>,
+    symbol: Option<String>,
+    contract_type: Option<ContractType>,
+    exchange: Option<Routing>,
+    side: Option<OrderSide>,
+}
+
+impl FilterBuilder {
+    #[inline]
+    #[must_use]
+    /// Only return executions for orders placed by the API client with ID `client_id`.
+    pub fn client_id(mut self, client_id: i64) -> Self {
+        self.client_id = Some(client_id);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only return executions allocated to the account `account_number`.
+    pub fn account_number(mut self, account_number: impl Into<String>) -> Self {
+        self.account_number = Some(account_number.into());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only return executions that occurred after `datetime`.
+    pub fn after<Tz: TimeZone>(mut self, datetime: DateTime<Tz>) -> Self {
+        self.datetime = Some(datetime.with_timezone(&Utc));
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only return executions on contracts with the symbol `symbol`.
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only return executions on contracts of type `contract_type`.
+    pub fn contract_type(mut self, contract_type: ContractType) -> Self {
+        self.contract_type = Some(contract_type);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only return executions that were produced at `exchange`.
+    pub fn exchange(mut self, exchange: Routing) -> Self {
+        self.exchange = Some(exchange);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// Only return executions for orders on the `side` of the market.
+    pub fn side(mut self, side: OrderSide) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    /// Validate the criteria and construct the [`Filter`].
+    ///
+    /// # Errors
+    /// Returns an error if an empty account number or symbol was provided, or if the datetime
+    /// after which to return executions is in the future.
+    ///
+    /// # Returns
+    /// The validated [`Filter`].
+    pub fn build(self) -> Result<Filter, InvalidFilter> {
+        if self.account_number.as_ref().is_some_and(String::is_empty) {
+            return Err(InvalidFilter::EmptyAccountNumber);
+        }
+        if self.symbol.as_ref().is_some_and(String::is_empty) {
+            return Err(InvalidFilter::EmptySymbol);
         }
+        if let Some(dt) = self.datetime {
+            if dt > Utc::now() {
+                return Err(InvalidFilter::FutureDatetime(dt));
+            }
+        }
+
+        Ok(Filter {
+            client_id: self.client_id.unwrap_or_default(),
+            account_number: self.account_number.unwrap_or_default(),
+            datetime: self.datetime,
+            symbol: self.symbol.unwrap_or_default(),
+            contract_type: self.contract_type,
+            exchange: self.exchange,
+            side: self.side,
+        })
     }
+}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+/// An error returned when a [`FilterBuilder`] contains invalid criteria.
+pub enum InvalidFilter {
+    #[error("Invalid execution filter: the account number cannot be empty.")]
+    /// An empty account number was provided.
+    EmptyAccountNumber,
+    #[error("Invalid execution filter: the symbol cannot be empty.")]
+    /// An empty symbol was provided.
+    EmptySymbol,
+    #[error("Invalid execution filter: the datetime {0} is in the future.")]
+    /// The datetime after which to return executions is in the future.
+    FutureDatetime(DateTime<Utc>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -60,7 +197,7 @@ pub enum OrderSide {
 }
 
 #[derive(Debug, Default, Clone, thiserror::Error)]
-#[error("Invalid value encountered when attempting to parse an order side. No such order side: {0}. Valid order sides \"BOT\" or \"SLD\".")]
+#[error("Invalid value encountered when attempting to parse an order side. No such order side: {0}. Valid order sides \"BOT\", \"BUY\", \"SLD\", \"SELL\", \"SSHORT\" or \"SSHORTX\".")]
 /// An error returned when parsing an [`OrderSide`] fails.
 pub struct ParseOrderSideError(String);
 
@@ -68,13 +205,47 @@ impl std::str::FromStr for OrderSide {
     type Err = ParseOrderSideError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "BOT" => Ok(Self::Buy),
-            "SLD" => Ok(Self::Sell),
+            "BOT" | "BUY" => Ok(Self::Buy),
+            "SLD" | "SELL" | "SSHORT" | "SSHORTX" => Ok(Self::Sell),
             other => Err(ParseOrderSideError(other.to_owned())),
         }
     }
 }
 
+impl OrderSide {
+    #[inline]
+    #[must_use]
+    /// Return `true` if a side string sent by TWS denotes a short sale.
+    pub fn is_short_sale(side: &str) -> bool {
+        matches!(side, "SSHORT" | "SSHORTX")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The effect that an execution had on the liquidity of the market.
+pub enum Liquidity {
+    /// The execution added liquidity.
+    Added,
+    /// The execution removed liquidity.
+    Removed,
+    /// The order was routed out to another venue.
+    RoutedOut,
+}
+
+impl Liquidity {
+    #[inline]
+    #[must_use]
+    /// Convert the integer code sent by TWS into a [`Liquidity`], if the code denotes one.
+    pub fn from_code(code: i64) -> Option<Self> {
+        match code {
+            1 => Some(Self::Added),
+            2 => Some(Self::Removed),
+            3 => Some(Self::RoutedOut),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// Contains the core fields relating to an [`Execution`]. which occurs when a trade is made.
 pub struct Exec {
@@ -92,7 +263,7 @@ pub struct Exec {
     /// The exchange on which the trade was made.
     pub exchange: Primary,
     /// The number of contracts traded.
-    pub quantity: f64,
+    pub quantity: Quantity,
     /// The price at which the trade was made.
     pub price: f64,
     /// The permanent ID of the order that produced the execution.
@@ -102,11 +273,22 @@ pub struct Exec {
     /// Whether the execution was caused by an IBKR-initiated liquidation.
     pub liquidation: bool,
     /// The cumulative number of contracts traded for the underlying order after this execution.
-    pub cumulative_quantity: f64,
+    pub cumulative_quantity: Quantity,
     /// The average price at which contracts for the underlying order after this execution.
     pub average_price: f64,
     /// Whether the execution is pending a price revision.
     pub pending_price_revision: bool,
+    #[serde(default)]
+    /// Whether the execution was a short sale.
+    pub short_sale: bool,
+    /// The economic value rule used to compute the execution's economic value.
+    pub ev_rule: Option<String>,
+    /// The multiplier used to compute the execution's economic value.
+    pub ev_multiplier: Option<f64>,
+    /// The model code of the account to which the execution was allocated.
+    pub model_code: Option<String>,
+    /// The effect that the execution had on the liquidity of the market.
+    pub last_liquidity: Option<Liquidity>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -193,3 +375,404 @@ pub struct CommissionReport {
     /// The redemption date for the yield
     pub yld_redemption_date: Option<chrono::NaiveDate>,
 }
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// The fills, commissions, and latest status of a single order, as reconstructed by a
+/// [`FillTracker`].
+pub struct OrderFills {
+    status: Option<OrderStatus>,
+    executions: Vec<Execution>,
+    commission_reports: Vec<CommissionReport>,
+}
+
+impl OrderFills {
+    #[inline]
+    #[must_use]
+    /// Return the most recent status of the order, if one has been received.
+    pub fn status(&self) -> Option<&OrderStatus> {
+        self.status.as_ref()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return every execution of the order in the order it was received. A corrected execution
+    /// replaces the one it corrects.
+    pub fn executions(&self) -> &[Execution] {
+        &self.executions
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the commission report of every execution for which one has been received.
+    pub fn commission_reports(&self) -> &[CommissionReport] {
+        &self.commission_reports
+    }
+
+    #[must_use]
+    /// Return the total number of contracts filled across every execution.
+    pub fn filled_quantity(&self) -> Quantity {
+        self.executions.iter().map(|e| e.as_exec().quantity).sum()
+    }
+
+    #[must_use]
+    /// Return the quantity-weighted average price of every execution, or `None` if nothing has
+    /// been filled.
+    pub fn average_price(&self) -> Option<f64> {
+        let quantity = self.filled_quantity();
+        if quantity.is_zero() {
+            return None;
+        }
+        Some(
+            self.executions
+                .iter()
+                .map(|e| e.as_exec().quantity.to_f64() * e.as_exec().price)
+                .sum::<f64>()
+                / quantity.to_f64(),
+        )
+    }
+
+    #[must_use]
+    /// Return the total commission paid across every commission report.
+    pub fn total_commission(&self) -> f64 {
+        self.commission_reports.iter().map(|r| r.commission).sum()
+    }
+
+    #[must_use]
+    /// Return the total realized P&L across every commission report. Executions that open a
+    /// position report no realized P&L and are ignored.
+    pub fn realized_pnl(&self) -> f64 {
+        self.commission_reports
+            .iter()
+            .map(|r| r.realized_pnl)
+            .filter(|pnl| *pnl != f64::MAX)
+            .sum()
+    }
+
+    #[must_use]
+    /// Return `true` if the most recent status reports that the order is completely filled.
+    pub fn is_filled(&self) -> bool {
+        matches!(self.status, Some(OrderStatus::Filled(_)))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Reconstructs the fills of each order from the `order_status`, `execution`, and
+/// `commission_report` callbacks.
+///
+/// Pass each of [`crate::wrapper::LocalWrapper::order_status`],
+/// [`crate::wrapper::LocalWrapper::execution`], and
+/// [`crate::wrapper::LocalWrapper::commission_report`] to the method of the same name, then query
+/// the state of any order by its ID. Orders placed manually in TWS all share the order ID 0.
+pub struct FillTracker {
+    orders: HashMap<i64, OrderFills>,
+    execution_orders: HashMap<String, i64>,
+    pending_reports: HashMap<String, CommissionReport>,
+}
+
+impl FillTracker {
+    #[inline]
+    #[must_use]
+    /// Create a new [`FillTracker`] that has not seen any orders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the latest status of an order.
+    pub fn order_status(&mut self, status: OrderStatus) {
+        let order_id = status.as_core().order_id;
+        self.orders.entry(order_id).or_default().status = Some(status);
+    }
+
+    /// Record an execution.
+    ///
+    /// The same execution may be received more than once, for instance when executions are also
+    /// requested with [`crate::client::Client::req_executions`], and IBKR may later correct an
+    /// execution by sending it again with an incremented final component of its execution ID. In
+    /// either case, the new execution replaces the old one, and any commission report already
+    /// received for the old one is kept under the new execution ID so that it is counted once.
+    pub fn execution(&mut self, execution: Execution) {
+        let exec = execution.as_exec();
+        let (order_id, execution_id) = (exec.order_id, exec.execution_id.clone());
+        let fills = self.orders.entry(order_id).or_default();
+        let base = base_execution_id(&execution_id);
+        match fills
+            .executions
+            .iter_mut()
+            .find(|e| base_execution_id(&e.as_exec().execution_id) == base)
+        {
+            Some(existing) => *existing = execution,
+            None => fills.executions.push(execution),
+        }
+        for report in &mut fills.commission_reports {
+            if base_execution_id(&report.exec_id) == base {
+                report.exec_id.clone_from(&execution_id);
+            }
+        }
+        self.execution_orders.insert(execution_id.clone(), order_id);
+        let pending = self
+            .pending_reports
+            .keys()
+            .filter(|exec_id| base_execution_id(exec_id) == base)
+            .cloned()
+            .collect::<Vec<_>>();
+        for exec_id in pending {
+            if let Some(report) = self.pending_reports.remove(&exec_id) {
+                self.commission_report(report);
+            }
+        }
+    }
+
+    /// Record the commission report of an execution. A report that arrives before its execution
+    /// is held until the execution is recorded. A report for an execution that has since been
+    /// corrected is kept under the corrected execution ID, unless the correction already has one.
+    pub fn commission_report(&mut self, mut report: CommissionReport) {
+        let Some(fills) = self
+            .execution_orders
+            .get(&report.exec_id)
+            .and_then(|order_id| self.orders.get_mut(order_id))
+        else {
+            self.pending_reports.insert(report.exec_id.clone(), report);
+            return;
+        };
+        let base = base_execution_id(&report.exec_id);
+        let mut superseded = false;
+        if let Some(execution) = fills
+            .executions
+            .iter()
+            .find(|e| base_execution_id(&e.as_exec().execution_id) == base)
+        {
+            superseded = report.exec_id != execution.as_exec().execution_id;
+            report.exec_id.clone_from(&execution.as_exec().execution_id);
+        }
+        match fills
+            .commission_reports
+            .iter_mut()
+            .find(|r| r.exec_id == report.exec_id)
+        {
+            Some(existing) if !superseded => *existing = report,
+            Some(_) => (),
+            None => fills.commission_reports.push(report),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the reconstructed state of the order with ID `order_id`.
+    pub fn get(&self, order_id: i64) -> Option<&OrderFills> {
+        self.orders.get(&order_id)
+    }
+
+    #[inline]
+    /// Iterate over the ID and reconstructed state of every order, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &OrderFills)> {
+        self.orders.iter().map(|(id, fills)| (*id, fills))
+    }
+
+    /// Stop tracking the order with ID `order_id`.
+    ///
+    /// # Returns
+    /// The reconstructed state of the order, if it was being tracked.
+    pub fn remove(&mut self, order_id: i64) -> Option<OrderFills> {
+        let fills = self.orders.remove(&order_id)?;
+        self.execution_orders.retain(|_, id| *id != order_id);
+        self.pending_reports.retain(|exec_id, _| {
+            let base = base_execution_id(exec_id);
+            !fills
+                .executions
+                .iter()
+                .any(|e| base_execution_id(&e.as_exec().execution_id) == base)
+        });
+        Some(fills)
+    }
+}
+
+// IBKR execution IDs end with a revision number that is incremented when an execution is corrected.
+fn base_execution_id(execution_id: &str) -> &str {
+    execution_id
+        .rsplit_once('.')
+        .map_or(execution_id, |(base, _)| base)
+}
+
+#[test]
+fn test_filter_builder() -> Result<(), Box<dyn std::error::Error>> {
+    let datetime = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+    let filter = Filter::builder()
+        .client_id(7)
+        .account_number("DU1234567")
+        .after(datetime.with_timezone(&chrono_tz::America::New_York))
+        .symbol("AAPL")
+        .contract_type(ContractType::Stock)
+        .exchange(Routing::Primary(Primary::Island))
+        .side(OrderSide::Sell)
+        .build()?;
+    assert_eq!(filter.datetime, Some(datetime));
+
+    assert_eq!(
+        crate::comm::fields(&filter)?,
+        ["7", "DU1234567", "20240102-14:30:00", "AAPL", "STK", "ISLAND", "SELL"]
+    );
+    assert_eq!(
+        crate::comm::fields(Filter::default())?,
+        ["0", "", "", "", "", "", ""]
+    );
+
+    assert_eq!(Filter::builder().build()?, Filter::default());
+    assert!(matches!(
+        Filter::builder().symbol("").build(),
+        Err(InvalidFilter::EmptySymbol)
+    ));
+    assert!(matches!(
+        Filter::builder().account_number("").build(),
+        Err(InvalidFilter::EmptyAccountNumber)
+    ));
+    assert!(matches!(
+        Filter::builder()
+            .after(Utc::now() + chrono::Duration::days(1))
+            .build(),
+        Err(InvalidFilter::FutureDatetime(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_fill_tracker() -> Result<(), Box<dyn std::error::Error>> {
+    fn execution(execution_id: &str, quantity: i64, price: f64) -> Execution {
+        let contract = toml::from_str(crate::contract::AAPL).unwrap();
+        Execution::Bought(Exec {
+            contract,
+            order_id: 7,
+            execution_id: execution_id.to_owned(),
+            datetime: Utc::now(),
+            account_number: "DU1234567".to_owned(),
+            exchange: Primary::Island,
+            quantity: quantity.into(),
+            price,
+            perm_id: 42,
+            client_id: 0,
+            liquidation: false,
+            cumulative_quantity: quantity.into(),
+            average_price: price,
+            pending_price_revision: false,
+            short_sale: false,
+            ev_rule: None,
+            ev_multiplier: None,
+            model_code: None,
+            last_liquidity: None,
+        })
+    }
+    fn report(exec_id: &str, commission: f64, realized_pnl: f64) -> CommissionReport {
+        CommissionReport {
+            exec_id: exec_id.to_owned(),
+            commission,
+            currency: Currency::UsDollar,
+            realized_pnl,
+            yld: None,
+            yld_redemption_date: None,
+        }
+    }
+
+    let mut tracker = FillTracker::new();
+    tracker.commission_report(report("0001.01", 1.0, f64::MAX));
+    tracker.execution(execution("0001.01", 100, 10.0));
+    tracker.execution(execution("0002.01", 100, 11.0));
+    tracker.execution(execution("0002.02", 300, 12.0));
+    tracker.commission_report(report("0002.02", 2.0, 5.0));
+
+    let fills = tracker.get(7).ok_or("order 7 should be tracked")?;
+    assert_eq!(fills.executions().len(), 2);
+    assert_eq!(fills.filled_quantity(), Quantity::from(400));
+    assert_eq!(fills.average_price(), Some(11.5));
+    assert!((fills.total_commission() - 3.0).abs() < 1e-12);
+    assert!((fills.realized_pnl() - 5.0).abs() < 1e-12);
+    assert!(!fills.is_filled());
+
+    // A correction keeps the commission of the execution it corrects until its own report arrives.
+    tracker.execution(execution("0003.01", 100, 13.0));
+    tracker.commission_report(report("0003.01", 1.5, f64::MAX));
+    tracker.execution(execution("0003.02", 100, 12.5));
+    let fills = tracker.get(7).ok_or("order 7 should be tracked")?;
+    assert_eq!(fills.commission_reports().len(), 3);
+    assert!((fills.total_commission() - 4.5).abs() < 1e-12);
+    tracker.commission_report(report("0003.02", 1.25, f64::MAX));
+    tracker.commission_report(report("0003.01", 1.5, f64::MAX));
+    let fills = tracker.get(7).ok_or("order 7 should be tracked")?;
+    assert_eq!(fills.commission_reports().len(), 3);
+    assert!((fills.total_commission() - 4.25).abs() < 1e-12);
+    assert_eq!(fills.commission_reports()[2].exec_id, "0003.02");
+
+    // Reports still awaiting a correction of one of the order's executions are dropped with it.
+    tracker.commission_report(report("0001.02", 1.0, f64::MAX));
+    tracker.commission_report(report("0004.01", 1.0, f64::MAX));
+    assert!(tracker.remove(7).is_some());
+    assert!(tracker.get(7).is_none());
+    assert_eq!(
+        tracker.pending_reports.keys().collect::<Vec<_>>(),
+        ["0004.01"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_order_side() {
+    for side in ["BOT", "BUY"] {
+        assert_eq!(side.parse::<OrderSide>().unwrap(), OrderSide::Buy);
+        assert!(!OrderSide::is_short_sale(side));
+    }
+    for side in ["SLD", "SELL"] {
+        assert_eq!(side.parse::<OrderSide>().unwrap(), OrderSide::Sell);
+        assert!(!OrderSide::is_short_sale(side));
+    }
+    for side in ["SSHORT", "SSHORTX"] {
+        assert_eq!(side.parse::<OrderSide>().unwrap(), OrderSide::Sell);
+        assert!(OrderSide::is_short_sale(side));
+    }
+    assert!("HOLD".parse::<OrderSide>().is_err());
+    assert_eq!(Liquidity::from_code(2), Some(Liquidity::Removed));
+    assert_eq!(Liquidity::from_code(0), None);
+}
+
+#[test]
+fn test_serde_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::payload::serde_round_trip;
+
+    let contract = toml::from_str(crate::contract::AAPL)?;
+    let execution = Execution::Sold(Exec {
+        contract,
+        order_id: 7,
+        execution_id: "0001.01".to_owned(),
+        datetime: Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap(),
+        account_number: "DU1234567".to_owned(),
+        exchange: Primary::Island,
+        quantity: Quantity::from(100),
+        price: 150.0,
+        perm_id: 42,
+        client_id: 0,
+        liquidation: false,
+        cumulative_quantity: Quantity::from(100),
+        average_price: 150.0,
+        pending_price_revision: false,
+        short_sale: true,
+        ev_rule: None,
+        ev_multiplier: None,
+        model_code: Some("Growth".to_owned()),
+        last_liquidity: Some(Liquidity::Removed),
+    });
+    assert_eq!(serde_round_trip(&execution), execution);
+
+    let report = CommissionReport {
+        exec_id: "0001.01".to_owned(),
+        commission: 1.0,
+        currency: Currency::UsDollar,
+        realized_pnl: 25.0,
+        yld: None,
+        yld_redemption_date: None,
+    };
+    assert_eq!(serde_round_trip(&report), report);
+
+    let mut tracker = FillTracker::new();
+    tracker.execution(execution);
+    tracker.commission_report(report);
+    let fills = tracker.get(7).ok_or("order 7 should be tracked")?;
+    assert_eq!(&serde_round_trip(fills), fills);
+    Ok(())
+}
@@ -0,0 +1,1004 @@
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampSecondArray,
+    UInt64Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::payload::market_depth::{CompleteEntry, Entry};
+use crate::payload::{Bar, TickData};
+
+// === Type definitions ===
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// The columns of a set of [`Bar`]s, which convert into an Arrow [`RecordBatch`] with
+/// [`BarColumns::into_record_batch`].
+///
+/// Datetimes are Unix timestamps in seconds. The trade columns are `None` for
+/// [`Bar::Ordinary`] bars.
+pub struct BarColumns {
+    /// The ending datetime of each bar.
+    pub datetime: Vec<i64>,
+    /// The open price of each bar.
+    pub open: Vec<f64>,
+    /// The high price of each bar.
+    pub high: Vec<f64>,
+    /// The low price of each bar.
+    pub low: Vec<f64>,
+    /// The close price of each bar.
+    pub close: Vec<f64>,
+    /// The traded volume of each bar.
+    pub volume: Vec<Option<f64>>,
+    /// The Weighted Average Price of each bar.
+    pub wap: Vec<Option<f64>>,
+    /// The number of trades during each bar.
+    pub trade_count: Vec<Option<u64>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// The columns of a set of historical or live [`TickData`], which convert into an Arrow
+/// [`RecordBatch`] with [`TickColumns::into_record_batch`].
+///
+/// Datetimes are Unix timestamps in seconds. Columns that do not apply to a tick's kind are
+/// `None` (ex. `bid_price` for a [`TickData::Last`] tick).
+pub struct TickColumns {
+    /// The timestamp of each tick.
+    pub datetime: Vec<i64>,
+    /// The kind of each tick: "midpoint", "bid_ask" or "last".
+    pub kind: Vec<&'static str>,
+    /// The midpoint or last traded price.
+    pub price: Vec<Option<f64>>,
+    /// The last traded size.
+    pub size: Vec<Option<f64>>,
+    /// The bid price.
+    pub bid_price: Vec<Option<f64>>,
+    /// The ask price.
+    pub ask_price: Vec<Option<f64>>,
+    /// The bid size.
+    pub bid_size: Vec<Option<f64>>,
+    /// The ask size.
+    pub ask_size: Vec<Option<f64>>,
+    /// The last traded exchange.
+    pub exchange: Vec<Option<String>>,
+    /// Whether each trade occurred outside the limits of the order that produced it.
+    pub past_limit: Vec<bool>,
+    /// Whether each trade was not reported to the tape.
+    pub unreported: Vec<bool>,
+    /// Whether each bid price is lower than the day's lowest value.
+    pub bid_past_low: Vec<bool>,
+    /// Whether each ask price is higher than the day's highest value.
+    pub ask_past_high: Vec<bool>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// The columns of a snapshot of a limit order book, which convert into an Arrow [`RecordBatch`]
+/// with [`DepthColumns::into_record_batch`].
+pub struct DepthColumns {
+    /// The side of each row: "bid" or "ask".
+    pub side: Vec<&'static str>,
+    /// The position of each row in the order book.
+    pub position: Vec<u64>,
+    /// The price of each row.
+    pub price: Vec<f64>,
+    /// The size of each row.
+    pub size: Vec<f64>,
+    /// The exchange from which each row was sourced, for SMART depth.
+    pub exchange: Vec<Option<String>>,
+    /// The market maker that posted each row, for market maker depth.
+    pub market_maker: Vec<Option<String>>,
+}
+
+// === Type implementations ===
+
+impl BarColumns {
+    #[inline]
+    #[must_use]
+    /// Return the number of rows.
+    pub fn len(&self) -> usize {
+        self.datetime.len()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return `true` if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.datetime.is_empty()
+    }
+
+    /// Append a single bar.
+    pub fn push(&mut self, bar: &Bar) {
+        let (core, trade) = match bar {
+            Bar::Ordinary(core) => (core, None),
+            Bar::Trades(trade) => (&trade.bar, Some(trade)),
+        };
+        self.datetime.push(core.datetime.timestamp());
+        self.open.push(core.open);
+        self.high.push(core.high);
+        self.low.push(core.low);
+        self.close.push(core.close);
+        self.volume.push(trade.map(|t| t.volume));
+        self.wap.push(trade.map(|t| t.wap));
+        self.trade_count.push(trade.map(|t| t.trade_count));
+    }
+
+    #[must_use]
+    /// Return the schema of the record batches produced by [`BarColumns::into_record_batch`].
+    pub fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field("datetime"),
+            Field::new("open", DataType::Float64, false),
+            Field::new("high", DataType::Float64, false),
+            Field::new("low", DataType::Float64, false),
+            Field::new("close", DataType::Float64, false),
+            Field::new("volume", DataType::Float64, true),
+            Field::new("wap", DataType::Float64, true),
+            Field::new("trade_count", DataType::UInt64, true),
+        ]))
+    }
+
+    /// Convert the columns into a record batch with the schema [`BarColumns::schema`].
+    ///
+    /// # Errors
+    /// Returns an error if the columns do not all have the same length.
+    pub fn into_record_batch(self) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                timestamps(self.datetime),
+                Arc::new(Float64Array::from(self.open)),
+                Arc::new(Float64Array::from(self.high)),
+                Arc::new(Float64Array::from(self.low)),
+                Arc::new(Float64Array::from(self.close)),
+                Arc::new(Float64Array::from(self.volume)),
+                Arc::new(Float64Array::from(self.wap)),
+                Arc::new(UInt64Array::from(self.trade_count)),
+            ],
+        )
+    }
+}
+
+impl<'b> FromIterator<&'b Bar> for BarColumns {
+    fn from_iter<T: IntoIterator<Item = &'b Bar>>(iter: T) -> Self {
+        let mut columns = Self::default();
+        for bar in iter {
+            columns.push(bar);
+        }
+        columns
+    }
+}
+
+impl TickColumns {
+    #[inline]
+    #[must_use]
+    /// Return the number of rows.
+    pub fn len(&self) -> usize {
+        self.datetime.len()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return `true` if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.datetime.is_empty()
+    }
+
+    /// Append a single tick.
+    pub fn push(&mut self, tick: &TickData) {
+        let (datetime, attributes) = match tick {
+            TickData::Midpoint(t) => (t.datetime, t.attributes),
+            TickData::BidAsk(t) => (t.datetime, t.attributes),
+            TickData::Last(t) => (t.datetime, t.attributes),
+        };
+        self.datetime.push(datetime.timestamp());
+        self.kind.push(match tick {
+            TickData::Midpoint(_) => "midpoint",
+            TickData::BidAsk(_) => "bid_ask",
+            TickData::Last(_) => "last",
+        });
+        self.price.push(match tick {
+            TickData::Midpoint(t) => Some(t.price),
+            TickData::Last(t) => Some(t.price),
+            TickData::BidAsk(_) => None,
+        });
+        self.size.push(match tick {
+            TickData::Last(t) => Some(t.size),
+            _ => None,
+        });
+        let bid_ask = match tick {
+            TickData::BidAsk(t) => Some(t),
+            _ => None,
+        };
+        self.bid_price.push(bid_ask.map(|t| t.bid_price));
+        self.ask_price.push(bid_ask.map(|t| t.ask_price));
+        self.bid_size.push(bid_ask.map(|t| t.bid_size));
+        self.ask_size.push(bid_ask.map(|t| t.ask_size));
+        self.exchange.push(match tick {
+            TickData::Last(t) => Some(t.exchange.to_string()),
+            _ => None,
+        });
+        self.past_limit.push(attributes.past_limit);
+        self.unreported.push(attributes.unreported);
+        self.bid_past_low.push(attributes.bid_past_low);
+        self.ask_past_high.push(attributes.ask_past_high);
+    }
+
+    #[must_use]
+    /// Return the schema of the record batches produced by [`TickColumns::into_record_batch`].
+    pub fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field("datetime"),
+            Field::new("kind", DataType::Utf8, false),
+            Field::new("price", DataType::Float64, true),
+            Field::new("size", DataType::Float64, true),
+            Field::new("bid_price", DataType::Float64, true),
+            Field::new("ask_price", DataType::Float64, true),
+            Field::new("bid_size", DataType::Float64, true),
+            Field::new("ask_size", DataType::Float64, true),
+            Field::new("exchange", DataType::Utf8, true),
+            Field::new("past_limit", DataType::Boolean, false),
+            Field::new("unreported", DataType::Boolean, false),
+            Field::new("bid_past_low", DataType::Boolean, false),
+            Field::new("ask_past_high", DataType::Boolean, false),
+        ]))
+    }
+
+    /// Convert the columns into a record batch with the schema [`TickColumns::schema`].
+    ///
+    /// # Errors
+    /// Returns an error if the columns do not all have the same length.
+    pub fn into_record_batch(self) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                timestamps(self.datetime),
+                Arc::new(StringArray::from(self.kind)),
+                Arc::new(Float64Array::from(self.price)),
+                Arc::new(Float64Array::from(self.size)),
+                Arc::new(Float64Array::from(self.bid_price)),
+                Arc::new(Float64Array::from(self.ask_price)),
+                Arc::new(Float64Array::from(self.bid_size)),
+                Arc::new(Float64Array::from(self.ask_size)),
+                Arc::new(StringArray::from(self.exchange)),
+                Arc::new(BooleanArray::from(self.past_limit)),
+                Arc::new(BooleanArray::from(self.unreported)),
+                Arc::new(BooleanArray::from(self.bid_past_low)),
+                Arc::new(BooleanArray::from(self.ask_past_high)),
+            ],
+        )
+    }
+}
+
+impl<'t> FromIterator<&'t TickData> for TickColumns {
+    fn from_iter<T: IntoIterator<Item = &'t TickData>>(iter: T) -> Self {
+        let mut columns = Self::default();
+        for tick in iter {
+            columns.push(tick);
+        }
+        columns
+    }
+}
+
+impl DepthColumns {
+    #[inline]
+    #[must_use]
+    /// Return the number of rows.
+    pub fn len(&self) -> usize {
+        self.side.len()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return `true` if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.side.is_empty()
+    }
+
+    /// Append a single entry of the order book.
+    pub fn push(&mut self, entry: &CompleteEntry) {
+        let (entry, exchange, market_maker) = match entry {
+            CompleteEntry::SmartDepth { exchange, entry } => {
+                (entry, Some(exchange.to_string()), None)
+            }
+            CompleteEntry::MarketMaker {
+                market_maker,
+                entry,
+            } => (entry, None, Some(market_maker.iter().collect())),
+            CompleteEntry::Ordinary(entry) => (entry, None, None),
+        };
+        let (side, row) = match entry {
+            Entry::Bid(row) => ("bid", row),
+            Entry::Ask(row) => ("ask", row),
+        };
+        self.side.push(side);
+        self.position.push(row.position);
+        self.price.push(row.price);
+        self.size.push(row.size);
+        self.exchange.push(exchange);
+        self.market_maker.push(market_maker);
+    }
+
+    #[must_use]
+    /// Return the schema of the record batches produced by [`DepthColumns::into_record_batch`].
+    pub fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("side", DataType::Utf8, false),
+            Field::new("position", DataType::UInt64, false),
+            Field::new("price", DataType::Float64, false),
+            Field::new("size", DataType::Float64, false),
+            Field::new("exchange", DataType::Utf8, true),
+            Field::new("market_maker", DataType::Utf8, true),
+        ]))
+    }
+
+    /// Convert the columns into a record batch with the schema [`DepthColumns::schema`].
+    ///
+    /// # Errors
+    /// Returns an error if the columns do not all have the same length.
+    pub fn into_record_batch(self) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                Arc::new(StringArray::from(self.side)),
+                Arc::new(UInt64Array::from(self.position)),
+                Arc::new(Float64Array::from(self.price)),
+                Arc::new(Float64Array::from(self.size)),
+                Arc::new(StringArray::from(self.exchange)),
+                Arc::new(StringArray::from(self.market_maker)),
+            ],
+        )
+    }
+}
+
+impl<'e> FromIterator<&'e CompleteEntry> for DepthColumns {
+    fn from_iter<T: IntoIterator<Item = &'e CompleteEntry>>(iter: T) -> Self {
+        let mut columns = Self::default();
+        for entry in iter {
+            columns.push(entry);
+        }
+        columns
+    }
+}
+
+// A column of Unix timestamps in seconds, in UTC.
+fn timestamp_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Second, Some("UTC".into())),
+        false,
+    )
+}
+
+fn timestamps(datetimes: Vec<i64>) -> ArrayRef {
+    Arc::new(TimestampSecondArray::from(datetimes).with_timezone("UTC"))
+}
+
+#[cfg(feature = "parquet")]
+/// Contains a writer that stores the record batches of this module in Parquet files, when the
+/// `parquet` feature is enabled.
+pub mod parquet {
+    use std::io::Write;
+
+    use arrow_array::RecordBatch;
+    use arrow_schema::SchemaRef;
+    use parquet::arrow::ArrowWriter;
+    use parquet::errors::ParquetError;
+
+    /// Write record batches to a Parquet file (or any other [`Write`]) and return the destination.
+    ///
+    /// # Arguments
+    /// * `inner` - The destination of the file.
+    /// * `schema` - The schema of every batch, such as [`super::BarColumns::schema`].
+    /// * `batches` - The batches to write, in order.
+    ///
+    /// # Errors
+    /// Returns an error if a batch does not have the given schema, or if the file cannot be
+    /// written.
+    pub fn write<W: Write + Send>(
+        inner: W,
+        schema: SchemaRef,
+        batches: impl IntoIterator<Item = RecordBatch>,
+    ) -> Result<W, ParquetError> {
+        let mut writer = ArrowWriter::try_new(inner, schema, None)?;
+        for batch in batches {
+            writer.write(&batch)?;
+        }
+        writer.into_inner()
+    }
+}
+
+/// Contains writers that dump [`Bar`], [`TickData`], and [`crate::execution::Execution`] streams
+/// to CSV files.
+pub mod csv {
+    use std::io::Write;
+    use std::marker::PhantomData;
+
+    use chrono::format::{Item, StrftimeItems};
+    use chrono::{DateTime, Utc};
+    use thiserror::Error;
+
+    use crate::execution::Execution;
+    use crate::payload::{Bar, TickData};
+
+    // === Type definitions ===
+
+    #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+    /// How datetimes are written.
+    pub enum TimestampFormat {
+        #[default]
+        /// Unix timestamps in seconds.
+        Unix,
+        /// RFC 3339 datetimes in UTC (ex. "2024-01-02T14:30:00+00:00").
+        Rfc3339,
+        /// Datetimes in UTC formatted with a validated [`chrono::format::strftime`] format string.
+        Custom(CustomFormat),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    /// A [`chrono::format::strftime`] format string that has been checked to be valid, so that
+    /// formatting a datetime with it cannot fail.
+    pub struct CustomFormat(String);
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+    #[error("Invalid strftime format string {0:?}")]
+    /// An error returned when a [`CustomFormat`] contains an invalid or unsupported specifier.
+    pub struct InvalidCustomFormat(pub String);
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    /// Options that control how a [`Writer`] writes its rows.
+    pub struct Options {
+        /// Whether a header row is written before the first record.
+        pub header: bool,
+        /// How datetimes are written.
+        pub timestamp_format: TimestampFormat,
+        /// The character that separates fields.
+        pub delimiter: char,
+    }
+
+    /// A record that a [`Writer`] can write as a single CSV row.
+    pub trait Record {
+        /// The name of each column.
+        const HEADER: &'static [&'static str];
+
+        /// Append the value of each column, in the order of [`Record::HEADER`].
+        fn fields(&self, timestamp_format: &TimestampFormat, fields: &mut Vec<String>);
+    }
+
+    #[derive(Debug)]
+    /// Writes records of type `R` to a CSV file (or any other [`Write`]).
+    pub struct Writer<W: Write, R: Record> {
+        inner: W,
+        options: Options,
+        header_written: bool,
+        fields: Vec<String>,
+        record: PhantomData<R>,
+    }
+
+    // === Type implementations ===
+
+    impl Default for Options {
+        fn default() -> Self {
+            Self {
+                header: true,
+                timestamp_format: TimestampFormat::default(),
+                delimiter: ',',
+            }
+        }
+    }
+
+    impl TimestampFormat {
+        #[must_use]
+        /// Format a datetime.
+        pub fn format(&self, datetime: DateTime<Utc>) -> String {
+            match self {
+                Self::Unix => datetime.timestamp().to_string(),
+                Self::Rfc3339 => datetime.to_rfc3339(),
+                Self::Custom(format) => datetime.format(&format.0).to_string(),
+            }
+        }
+    }
+
+    impl CustomFormat {
+        /// Create a new format string.
+        ///
+        /// # Arguments
+        /// * `format` - The [`chrono::format::strftime`] format string.
+        ///
+        /// # Errors
+        /// Returns an error if `format` contains an invalid or unsupported specifier.
+        ///
+        /// # Returns
+        /// The validated format string.
+        pub fn new(format: impl Into<String>) -> Result<Self, InvalidCustomFormat> {
+            let format = format.into();
+            if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+                Err(InvalidCustomFormat(format))
+            } else {
+                Ok(Self(format))
+            }
+        }
+
+        #[must_use]
+        #[inline]
+        /// The format string.
+        pub fn as_str(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl std::str::FromStr for CustomFormat {
+        type Err = InvalidCustomFormat;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Self::new(s)
+        }
+    }
+
+    impl<W: Write, R: Record> Writer<W, R> {
+        #[inline]
+        #[must_use]
+        /// Create a new writer.
+        ///
+        /// # Arguments
+        /// * `inner` - The destination of the rows.
+        /// * `options` - Options that control how rows are written.
+        pub fn new(inner: W, options: Options) -> Self {
+            Self {
+                inner,
+                options,
+                header_written: false,
+                fields: Vec::with_capacity(R::HEADER.len()),
+                record: PhantomData,
+            }
+        }
+
+        /// Write a single record, preceded by the header row if this is the first record and
+        /// [`Options::header`] is set.
+        ///
+        /// # Errors
+        /// Returns any error encountered while writing to the destination.
+        pub fn write(&mut self, record: &R) -> std::io::Result<()> {
+            if self.options.header && !self.header_written {
+                self.fields.clear();
+                self.fields.extend(R::HEADER.iter().map(|&h| h.to_owned()));
+                self.write_row()?;
+            }
+            self.header_written = true;
+            self.fields.clear();
+            record.fields(&self.options.timestamp_format, &mut self.fields);
+            self.write_row()
+        }
+
+        /// Write every record in `records`.
+        ///
+        /// # Errors
+        /// Returns any error encountered while writing to the destination.
+        pub fn write_all<'r>(
+            &mut self,
+            records: impl IntoIterator<Item = &'r R>,
+        ) -> std::io::Result<()>
+        where
+            R: 'r,
+        {
+            for record in records {
+                self.write(record)?;
+            }
+            Ok(())
+        }
+
+        /// Flush the destination.
+        ///
+        /// # Errors
+        /// Returns any error encountered while flushing the destination.
+        pub fn flush(&mut self) -> std::io::Result<()> {
+            self.inner.flush()
+        }
+
+        /// Flush the destination and return it.
+        ///
+        /// # Errors
+        /// Returns any error encountered while flushing the destination.
+        pub fn into_inner(mut self) -> std::io::Result<W> {
+            self.inner.flush()?;
+            Ok(self.inner)
+        }
+
+        fn write_row(&mut self) -> std::io::Result<()> {
+            let delimiter = self.options.delimiter;
+            for (i, field) in self.fields.iter().enumerate() {
+                if i > 0 {
+                    write!(self.inner, "{delimiter}")?;
+                }
+                if field.contains([delimiter, '"', '\n', '\r']) {
+                    write!(self.inner, "\"{}\"", field.replace('"', "\"\""))?;
+                } else {
+                    self.inner.write_all(field.as_bytes())?;
+                }
+            }
+            writeln!(self.inner)
+        }
+    }
+
+    fn optional<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    impl Record for Bar {
+        const HEADER: &'static [&'static str] = &[
+            "datetime",
+            "open",
+            "high",
+            "low",
+            "close",
+            "volume",
+            "wap",
+            "trade_count",
+        ];
+
+        fn fields(&self, timestamp_format: &TimestampFormat, fields: &mut Vec<String>) {
+            let (core, trade) = match self {
+                Bar::Ordinary(core) => (core, None),
+                Bar::Trades(trade) => (&trade.bar, Some(trade)),
+            };
+            fields.extend([
+                timestamp_format.format(core.datetime),
+                core.open.to_string(),
+                core.high.to_string(),
+                core.low.to_string(),
+                core.close.to_string(),
+                optional(trade.map(|t| t.volume)),
+                optional(trade.map(|t| t.wap)),
+                optional(trade.map(|t| t.trade_count)),
+            ]);
+        }
+    }
+
+    impl Record for TickData {
+        const HEADER: &'static [&'static str] = &[
+            "datetime",
+            "kind",
+            "price",
+            "size",
+            "bid_price",
+            "ask_price",
+            "bid_size",
+            "ask_size",
+            "exchange",
+            "past_limit",
+            "unreported",
+            "bid_past_low",
+            "ask_past_high",
+        ];
+
+        fn fields(&self, timestamp_format: &TimestampFormat, fields: &mut Vec<String>) {
+            let (datetime, attributes) = match self {
+                TickData::Midpoint(t) => (t.datetime, t.attributes),
+                TickData::BidAsk(t) => (t.datetime, t.attributes),
+                TickData::Last(t) => (t.datetime, t.attributes),
+            };
+            fields.push(timestamp_format.format(datetime));
+            match self {
+                TickData::Midpoint(t) => fields.extend([
+                    "midpoint".to_owned(),
+                    t.price.to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ]),
+                TickData::BidAsk(t) => fields.extend([
+                    "bid_ask".to_owned(),
+                    String::new(),
+                    String::new(),
+                    t.bid_price.to_string(),
+                    t.ask_price.to_string(),
+                    t.bid_size.to_string(),
+                    t.ask_size.to_string(),
+                    String::new(),
+                ]),
+                TickData::Last(t) => fields.extend([
+                    "last".to_owned(),
+                    t.price.to_string(),
+                    t.size.to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    t.exchange.to_string(),
+                ]),
+            }
+            fields.extend([
+                attributes.past_limit.to_string(),
+                attributes.unreported.to_string(),
+                attributes.bid_past_low.to_string(),
+                attributes.ask_past_high.to_string(),
+            ]);
+        }
+    }
+
+    impl Record for Execution {
+        const HEADER: &'static [&'static str] = &[
+            "datetime",
+            "side",
+            "execution_id",
+            "order_id",
+            "account_number",
+            "contract_id",
+            "symbol",
+            "exchange",
+            "quantity",
+            "price",
+            "cumulative_quantity",
+            "average_price",
+            "perm_id",
+            "client_id",
+            "liquidation",
+            "short_sale",
+        ];
+
+        fn fields(&self, timestamp_format: &TimestampFormat, fields: &mut Vec<String>) {
+            let exec = self.as_exec();
+            fields.extend([
+                timestamp_format.format(exec.datetime),
+                if self.is_buy() { "buy" } else { "sell" }.to_owned(),
+                exec.execution_id.clone(),
+                exec.order_id.to_string(),
+                exec.account_number.clone(),
+                exec.contract.contract_id().0.to_string(),
+                exec.contract.symbol().to_owned(),
+                exec.exchange.to_string(),
+                exec.quantity.to_string(),
+                exec.price.to_string(),
+                exec.cumulative_quantity.to_string(),
+                exec.average_price.to_string(),
+                exec.perm_id.to_string(),
+                exec.client_id.to_string(),
+                exec.liquidation.to_string(),
+                exec.short_sale.to_string(),
+            ]);
+        }
+    }
+}
+
+#[test]
+fn test_columns() {
+    use chrono::DateTime;
+
+    use crate::payload::market_depth::Row;
+    use crate::payload::{BarCore, BidAsk, Last, TickAttributes, Trade};
+
+    let datetime = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let core = BarCore {
+        datetime,
+        open: 100.0,
+        high: 101.0,
+        low: 99.5,
+        close: 100.5,
+    };
+    let bars = [
+        Bar::Ordinary(core),
+        Bar::Trades(Trade {
+            bar: core,
+            volume: 1_000.0,
+            wap: 100.25,
+            trade_count: 42,
+        }),
+    ];
+    let columns = bars.iter().collect::<BarColumns>();
+    assert_eq!(columns.len(), 2);
+    assert_eq!(columns.datetime, vec![1_704_205_800; 2]);
+    assert_eq!(columns.trade_count, vec![None, Some(42)]);
+
+    let ticks = [
+        TickData::BidAsk(BidAsk {
+            datetime,
+            bid_price: 100.0,
+            ask_price: 100.5,
+            bid_size: 300.0,
+            ask_size: 200.0,
+            attributes: TickAttributes::default(),
+        }),
+        TickData::Last(Last {
+            datetime,
+            price: 100.25,
+            size: 50.0,
+            exchange: crate::exchange::Primary::Island,
+            attributes: TickAttributes::from_last_mask(2),
+        }),
+    ];
+    let columns = ticks.iter().collect::<TickColumns>();
+    assert_eq!(columns.kind, vec!["bid_ask", "last"]);
+    assert_eq!(columns.price, vec![None, Some(100.25)]);
+    assert_eq!(columns.bid_price, vec![Some(100.0), None]);
+    assert_eq!(columns.unreported, vec![false, true]);
+
+    let entries = [
+        CompleteEntry::Ordinary(Entry::Bid(Row {
+            position: 0,
+            price: 100.0,
+            size: 300.0,
+        })),
+        CompleteEntry::MarketMaker {
+            market_maker: ['N', 'S', 'D', 'Q'],
+            entry: Entry::Ask(Row {
+                position: 0,
+                price: 100.5,
+                size: 200.0,
+            }),
+        },
+    ];
+    let columns = entries.iter().collect::<DepthColumns>();
+    assert_eq!(columns.side, vec!["bid", "ask"]);
+    assert_eq!(columns.market_maker, vec![None, Some("NSDQ".to_owned())]);
+}
+
+#[test]
+fn test_csv_writer() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::DateTime;
+
+    use crate::payload::{BarCore, Last, Midpoint, TickAttributes, Trade};
+
+    let datetime = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let core = BarCore {
+        datetime,
+        open: 100.0,
+        high: 101.0,
+        low: 99.5,
+        close: 100.5,
+    };
+    let bars = [
+        Bar::Ordinary(core),
+        Bar::Trades(Trade {
+            bar: core,
+            volume: 1_000.0,
+            wap: 100.25,
+            trade_count: 42,
+        }),
+    ];
+    let mut writer = csv::Writer::new(Vec::new(), csv::Options::default());
+    writer.write_all(&bars)?;
+    assert_eq!(
+        String::from_utf8(writer.into_inner()?).unwrap(),
+        "datetime,open,high,low,close,volume,wap,trade_count\n\
+         1704205800,100,101,99.5,100.5,,,\n\
+         1704205800,100,101,99.5,100.5,1000,100.25,42\n"
+    );
+
+    let ticks = [
+        TickData::Midpoint(Midpoint {
+            datetime,
+            price: 100.25,
+            attributes: TickAttributes::default(),
+        }),
+        TickData::Last(Last {
+            datetime,
+            price: 100.25,
+            size: 50.0,
+            exchange: crate::exchange::Primary::Island,
+            attributes: TickAttributes::from_last_mask(2),
+        }),
+    ];
+    let options = csv::Options {
+        header: false,
+        timestamp_format: csv::TimestampFormat::Custom(csv::CustomFormat::new(
+            "%Y-%m-%d %H:%M:%S",
+        )?),
+        delimiter: ';',
+    };
+    let mut writer = csv::Writer::new(Vec::new(), options);
+    writer.write_all(&ticks)?;
+    let out = String::from_utf8(writer.into_inner()?).unwrap();
+    let rows = out.lines().collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        rows[0],
+        "2024-01-02 14:30:00;midpoint;100.25;;;;;;;false;false;false;false"
+    );
+    assert!(rows[1].starts_with("2024-01-02 14:30:00;last;100.25;50;;;;;"));
+    assert!(rows[1].ends_with(";false;true;false;false"));
+
+    // Invalid format strings are rejected when the format is built rather than when it is used.
+    assert!(csv::CustomFormat::new("%Y-%Q").is_err());
+    assert_eq!(
+        "%Y-%m-%d %".parse::<csv::CustomFormat>(),
+        Err(csv::InvalidCustomFormat("%Y-%m-%d %".to_owned()))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_record_batches() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow_array::Array;
+    use chrono::DateTime;
+
+    use crate::payload::market_depth::Row;
+    use crate::payload::{BarCore, Midpoint, TickAttributes, Trade};
+
+    let datetime = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let core = BarCore {
+        datetime,
+        open: 100.0,
+        high: 101.0,
+        low: 99.5,
+        close: 100.5,
+    };
+    let bars = [
+        Bar::Ordinary(core),
+        Bar::Trades(Trade {
+            bar: core,
+            volume: 1_000.0,
+            wap: 100.25,
+            trade_count: 42,
+        }),
+    ];
+    let batch = bars.iter().collect::<BarColumns>().into_record_batch()?;
+    assert_eq!(batch.schema(), BarColumns::schema());
+    assert_eq!(batch.num_rows(), 2);
+    let trade_count = batch
+        .column_by_name("trade_count")
+        .and_then(|column| column.as_any().downcast_ref::<UInt64Array>())
+        .ok_or("trade_count should be a UInt64 column")?;
+    assert!(trade_count.is_null(0));
+    assert_eq!(trade_count.value(1), 42);
+
+    let ticks = [TickData::Midpoint(Midpoint {
+        datetime,
+        price: 100.25,
+        attributes: TickAttributes::default(),
+    })];
+    let batch = ticks.iter().collect::<TickColumns>().into_record_batch()?;
+    assert_eq!(batch.schema(), TickColumns::schema());
+    assert_eq!(batch.num_rows(), 1);
+
+    let entries = [CompleteEntry::Ordinary(Entry::Bid(Row {
+        position: 0,
+        price: 100.0,
+        size: 300.0,
+    }))];
+    let batch = entries
+        .iter()
+        .collect::<DepthColumns>()
+        .into_record_batch()?;
+    assert_eq!(batch.schema(), DepthColumns::schema());
+
+    // Columns of different lengths cannot form a batch.
+    let mut columns = bars.iter().collect::<BarColumns>();
+    columns.open.pop();
+    assert!(columns.into_record_batch().is_err());
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet() -> Result<(), Box<dyn std::error::Error>> {
+    use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use chrono::DateTime;
+
+    use crate::payload::{BarCore, Trade};
+
+    let core = BarCore {
+        datetime: DateTime::from_timestamp(1_704_205_800, 0).unwrap(),
+        open: 100.0,
+        high: 101.0,
+        low: 99.5,
+        close: 100.5,
+    };
+    let bars = [
+        Bar::Ordinary(core),
+        Bar::Trades(Trade {
+            bar: core,
+            volume: 1_000.0,
+            wap: 100.25,
+            trade_count: 42,
+        }),
+    ];
+    let batch = bars.iter().collect::<BarColumns>().into_record_batch()?;
+    let file = parquet::write(Vec::new(), BarColumns::schema(), [batch.clone()])?;
+    let read = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(file))?
+        .build()?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(read, [batch]);
+    Ok(())
+}
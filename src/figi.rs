@@ -272,6 +272,14 @@ impl TryFrom<String> for Figi {
     }
 }
 
+impl TryFrom<&str> for Figi {
+    type Error = InvalidFigi;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
 impl std::str::FromStr for Figi {
     type Err = InvalidFigi;
 
@@ -286,7 +294,7 @@ impl std::str::FromStr for Figi {
     }
 }
 
-impl<'a> From<&'a Figi> for String {
+impl From<&Figi> for String {
     fn from(value: &Figi) -> Self {
         let mut s = String::with_capacity(12);
         s.push(value.pos_1.into());
@@ -343,6 +351,59 @@ impl Figi {
     /// # Errors
     /// Will error if the provided characters are not a valid FIGI code.
     pub fn from_chars(s: &[char; 12]) -> Result<Self, InvalidFigi> {
+        let out = Self::from_chars_unchecked(s)?;
+        if out.is_valid() {
+            Ok(out)
+        } else {
+            Err(InvalidFigi::Checksum(s.iter().collect()))
+        }
+    }
+
+    #[inline]
+    /// Compute the check digit (the twelfth character) for the first eleven characters of a FIGI.
+    ///
+    /// # Arguments
+    /// * `s` - The first eleven characters of a FIGI.
+    ///
+    /// # Returns
+    /// The check digit that makes `s` a valid FIGI.
+    ///
+    /// # Errors
+    /// Will error if `s` is not eleven characters long, or if any of its characters are invalid.
+    pub fn check_digit_for(s: &str) -> Result<char, InvalidFigi> {
+        let b: [u8; 11] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| InvalidFigi::Length(s.to_owned()))?;
+        let mut chars = ['0'; 12];
+        for (c, b) in chars.iter_mut().zip(b) {
+            *c = b as char;
+        }
+        let expected = Self::from_chars_unchecked(&chars)?.expected_check_digit();
+        Ok(char::from(b'0' + expected))
+    }
+
+    #[must_use]
+    #[inline]
+    /// The check digit, which is the final character of the FIGI.
+    pub fn check_digit(&self) -> char {
+        self.pos_4_12[self.pos_4_12.len() - 1].into()
+    }
+
+    #[must_use]
+    #[inline]
+    /// The two-character prefix (ex. `BB` for Bloomberg) that identifies the certified provider
+    /// that issued the FIGI.
+    ///
+    /// Note that whether a FIGI identifies a composite, share class, or exchange-level instrument
+    /// is not encoded in the identifier itself; that classification is only available from the
+    /// provider's metadata.
+    pub fn provider(&self) -> [char; 2] {
+        [self.pos_1.into(), self.pos_2.into()]
+    }
+
+    #[inline]
+    fn from_chars_unchecked(s: &[char; 12]) -> Result<Self, InvalidFigi> {
         let (pos_1, pos_2) = match (s[0], s[1]) {
             ('B', 'S' | 'M') | ('G', 'G' | 'B' | 'H') | ('K', 'Y') | ('V', 'G') => {
                 return Err(InvalidFigi::FirstTwo(s.iter().collect()))
@@ -378,21 +439,21 @@ impl Figi {
                 .map_err(|_| InvalidFigi::ConsonantOrNumeric(s.iter().collect()))?,
         ];
 
-        let out = Self {
+        Ok(Self {
             pos_1,
             pos_2,
             pos_3,
             pos_4_12,
-        };
-        if out.is_valid() {
-            Ok(out)
-        } else {
-            Err(InvalidFigi::Checksum(s.iter().collect()))
-        }
+        })
     }
 
     #[inline]
     fn is_valid(&self) -> bool {
+        self.pos_4_12[self.pos_4_12.len() - 1] as u8 == self.expected_check_digit()
+    }
+
+    #[inline]
+    fn expected_check_digit(&self) -> u8 {
         let mut sum = sum_digits_sub_100(self.pos_1 as u8)
             + sum_digits_sub_100(self.pos_2 as u8 * 2)
             + sum_digits_sub_100(G.into());
@@ -404,7 +465,69 @@ impl Figi {
                 sum += sum_digits_sub_100(*c as u8);
             }
         }
-        self.pos_4_12[self.pos_4_12.len() - 1] as u8 == (10 - sum % 10) % 10
+        (10 - sum % 10) % 10
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Resolves FIGIs to fully-defined contracts, remembering every FIGI it has already resolved so
+/// that each one is only sent to the API once.
+pub struct Resolver {
+    cache: crate::contract::Cache,
+}
+
+impl Resolver {
+    #[must_use]
+    #[inline]
+    /// Create a new resolver whose mappings never expire.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Create a new resolver whose mappings expire after `ttl`.
+    ///
+    /// # Arguments
+    /// * `ttl` - How long a resolved contract remains valid.
+    pub fn with_ttl(ttl: std::time::Duration) -> Self {
+        Self {
+            cache: crate::contract::Cache::with_ttl(ttl),
+        }
+    }
+
+    #[cfg(feature = "client")]
+    /// Resolve a FIGI to a contract, querying the API with a [`crate::contract::Query::Figi`]
+    /// only if the FIGI has not already been resolved.
+    ///
+    /// # Arguments
+    /// * `client` - The client with which to send the query.
+    /// * `figi` - The FIGI to resolve.
+    ///
+    /// # Errors
+    /// Returns any error encountered by [`crate::contract::new`].
+    ///
+    /// # Returns
+    /// The contract identified by the FIGI.
+    pub async fn resolve(
+        &mut self,
+        client: &mut crate::client::ActiveClient,
+        figi: Figi,
+    ) -> Result<crate::contract::Contract, crate::contract::NewSecurityError> {
+        if let Some(contract) = self.cache.get_by_figi(&figi) {
+            return Ok(contract.clone());
+        }
+        let contract: crate::contract::Contract =
+            crate::contract::new(client, crate::contract::Query::Figi(figi)).await?;
+        self.cache.insert_figi(figi, contract.clone());
+        Ok(contract)
+    }
+
+    #[must_use]
+    #[inline]
+    /// The contracts that have been resolved so far.
+    pub const fn cache(&self) -> &crate::contract::Cache {
+        &self.cache
     }
 }
 
@@ -420,5 +543,13 @@ fn test_figi() -> Result<(), InvalidFigi> {
     let tsm = "BBG000BD8ZK0".parse::<Figi>()?; // TSM US Equity
     assert!(aapl.is_valid());
     assert!(tsm.is_valid());
+    assert_eq!(aapl.check_digit(), '6');
+    assert_eq!(aapl.provider(), ['B', 'B']);
+    assert_eq!(Figi::check_digit_for("BBG000BD8ZK")?, '0');
+    assert_eq!(Figi::try_from("BBG000N88V36")?, aapl);
+    assert!(matches!(
+        Figi::try_from("BBG000N88V37"),
+        Err(InvalidFigi::Checksum(_))
+    ));
     Ok(())
 }
@@ -0,0 +1,115 @@
+use bytes::Bytes;
+
+/// A single incoming message, held as the NUL-separated bytes read from the socket.
+///
+/// Fields are borrowed from the underlying buffer as they are decoded, so only the fields that
+/// end up in an owned payload (ex. a [`String`]) are ever allocated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Frame(Bytes);
+
+impl Frame {
+    /// Wrap the body of a message (without its length prefix).
+    ///
+    /// The API only ever sends UTF-8, so validation is a single pass over the buffer. Should
+    /// invalid data arrive anyway, it is replaced with U+FFFD rather than being dropped.
+    pub(crate) fn new(buf: Bytes) -> Self {
+        if std::str::from_utf8(&buf).is_ok() {
+            Self(buf)
+        } else {
+            Self(Bytes::from(String::from_utf8_lossy(&buf).into_owned()))
+        }
+    }
+
+    /// Build a frame from owned fields, as they would have been split from the wire.
+    pub(crate) fn from_fields<S: AsRef<str>>(fields: &[S]) -> Self {
+        let mut buf = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                buf.push('\0');
+            }
+            buf.push_str(field.as_ref());
+        }
+        Self(Bytes::from(buf))
+    }
+
+    #[inline]
+    fn as_str(&self) -> &str {
+        // `new` and `from_fields` guarantee valid UTF-8, so the default is never used.
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+
+    #[inline]
+    /// Iterate over the fields of the message, including the message identifier.
+    pub(crate) fn fields(&self) -> Fields<'_> {
+        Fields {
+            rest: Some(self.as_str()),
+        }
+    }
+
+    #[inline]
+    /// The message identifier, which is always the first field.
+    pub(crate) fn first(&self) -> Option<&str> {
+        self.fields().next()
+    }
+
+    /// Copy every field into an owned vector.
+    pub(crate) fn to_vec(&self) -> Vec<String> {
+        self.fields().map(ToOwned::to_owned).collect()
+    }
+}
+
+/// A borrowing iterator over the fields of a [`Frame`].
+#[derive(Debug, Clone)]
+pub(crate) struct Fields<'a> {
+    rest: Option<&'a str>,
+}
+
+impl Fields<'_> {
+    #[inline]
+    /// The number of fields that have yet to be consumed.
+    ///
+    /// Decoders check this before allocating for a count read from the message, so that a
+    /// malformed count is rejected instead of reserving memory for fields that never arrive.
+    pub(crate) fn remaining(&self) -> usize {
+        self.rest
+            .map_or(0, |rest| rest.bytes().filter(|&b| b == b'\0').count() + 1)
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        match rest.split_once('\0') {
+            Some((field, tail)) => {
+                self.rest = Some(tail);
+                Some(field)
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_frame_fields() {
+    let frame = Frame::new(Bytes::from_static(b"49\x001\x001700000000\x00"));
+    assert_eq!(frame.first(), Some("49"));
+    assert_eq!(
+        frame.fields().collect::<Vec<_>>(),
+        vec!["49", "1", "1700000000", ""]
+    );
+    assert_eq!(Frame::from_fields(&frame.to_vec()), frame);
+
+    let mut fields = frame.fields();
+    assert_eq!(fields.remaining(), 4);
+    assert_eq!(fields.nth(1), Some("1"));
+    assert_eq!(fields.remaining(), 2);
+    assert_eq!(fields.clone().collect::<Vec<_>>(), vec!["1700000000", ""]);
+    assert_eq!(fields.nth(1), Some(""));
+    assert_eq!(fields.remaining(), 0);
+}
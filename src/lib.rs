@@ -1,5 +1,16 @@
 //! A Rust port of the Interactive Brokers (IBKR) TWS API. Its goal is to be as expressive,
 //! performant, and as safe as possible.
+//!
+//! The async client, and everything that depends on `tokio`, is behind the default `client`
+//! feature. Without it, the crate contains only the plain data types in [`account`],
+//! [`contract`], [`currency`], [`exchange`], [`figi`], [`quantity`], and [`tick`], so that they
+//! can be shared with analysis tools, WebAssembly frontends, and other crates that never connect
+//! to the API.
+//!
+//! The `depth`, `fa`, `news`, `scanner`, and `wsh` features, all enabled by default, compile in
+//! the handling of market depth, financial advisor, news, market scanner, and Wall Street Horizon
+//! messages. Disabling those that go unused shortens the client's message dispatch, and any such
+//! message that does arrive is dropped. The callbacks in [`wrapper::Wrapper`] are unaffected.
 
 #![warn(missing_docs)]
 #![allow(
@@ -12,13 +23,19 @@
     clippy::single_char_lifetime_names
 )]
 
+// Allows macros that refer to `::ibapi` to be used within this crate.
+extern crate self as ibapi;
+
 /// Contains types related to account information.
 pub mod account;
+#[cfg(feature = "client")]
 /// Contains the all-important [`client::Client`] struct and its methods, which facilitate
 /// communication with the IBKR. Also contains a [`client::Builder`] struct to manage the
 /// creation of new connections.
 pub mod client;
+#[cfg(feature = "client")]
 mod comm;
+#[cfg(feature = "client")]
 mod constants;
 /// Contains the definitions of all [`contract::Security`] implementors, which represent tradable
 /// contracts.
@@ -30,6 +47,7 @@ pub mod contract;
 /// Contains the definition of a [`currency::Currency`] enum, which represents the possible trading
 /// currencies available in the API.
 pub mod currency;
+#[cfg(feature = "client")]
 #[allow(
     unused_variables,
     clippy::print_stdout,
@@ -41,24 +59,83 @@ pub mod currency;
 mod decode;
 /// Contains types related to security exchanges and trading venues available in the API.
 pub mod exchange;
+#[cfg(feature = "client")]
 /// Contains types related to executions, which are produced after a trade is made.
 pub mod execution;
+#[cfg(feature = "export")]
+/// Contains conversions of historical data, ticks, and order book snapshots into Apache Arrow
+/// record batches for use in dataframes, when the `export` feature is enabled. The batches can be
+/// written to Parquet files when the `parquet` feature is also enabled.
+pub mod export;
 /// Contains an implementation of the [FIGI  alphanumeric identifier](https://www.openfigi.com/about/figi#!) for use in contract specification.
 pub mod figi;
+#[cfg(feature = "client")]
+mod frame;
+#[cfg(feature = "client")]
+/// Contains the [`limits::Limits`] of an account's market data entitlements, which a
+/// [`client::Client`] enforces before sending market data, depth, and tick-by-tick requests.
+pub mod limits;
+#[cfg(feature = "client")]
 /// Contains modules that each relate to different market data requests. In particular, each module
 /// defines: 1) General types used in a given market data query and 2) Optionally, a private
 /// indicator trait that defines whether a given [`contract::Security`] allows for the data request
 /// and 3) Any types associated with implementors of the indicator types.
 pub mod market_data;
+#[cfg(feature = "client")]
 mod message;
+#[cfg(feature = "metrics")]
+/// Contains counters of incoming messages, decode failures, reader queue depth, and request
+/// latency, which are maintained for each client by its client loop when the `metrics` feature
+/// is enabled.
+pub mod metrics;
+#[cfg(feature = "news")]
+/// Contains the typed providers and the [`news::Query`] of a
+/// [`client::Client::req_historical_news`] request, whose headlines are returned a page at a time.
+pub mod news;
+#[cfg(feature = "client")]
 /// Contains types and traits related to orders.
 pub mod order;
+#[cfg(feature = "client")]
 /// Contains the types that are parsed from API callbacks. They are used in the [`wrapper::LocalWrapper`] and
 /// [`wrapper::Wrapper`] callback functions.
 pub mod payload;
+#[cfg(feature = "client")]
+/// Contains a [`portfolio::Portfolio`] type that aggregates position, P&L, and account attribute
+/// callbacks into a single queryable view of an account.
+pub mod portfolio;
+#[cfg(feature = "client")]
 /// Convenience module containing commonly-used types, functions, and modules.
 pub mod prelude;
+/// Contains [`quantity::Quantity`], an exact decimal representation of the quantities of
+/// orders, executions, and positions.
+pub mod quantity;
+#[cfg(feature = "client")]
+mod queue;
+#[cfg(feature = "client")]
 mod reader;
+#[cfg(feature = "client")]
+/// Contains the kinds of requests that a [`client::Client`] matches to their callbacks, and the
+/// [`requests::UnknownPolicy`] for callbacks that match no outstanding request.
+pub mod requests;
+#[cfg(feature = "client")]
+/// Contains a [`replay::Recorder`] that archives live sessions and functions to replay recorded
+/// messages through a [`wrapper::LocalWrapper`] or [`wrapper::Wrapper`] without a connection to
+/// the API, which is useful for testing wrapper implementations offline.
+pub mod replay;
+#[cfg(feature = "client")]
+/// Contains the restart [`restart::Window`] of TWS or IB Gateway, during which a
+/// [`client::Client`] pauses its requests and expects to be disconnected.
+pub mod restart;
+#[cfg(feature = "scanner")]
+/// Contains the typed settings of a [`client::Client::req_scanner_subscription`] request and a
+/// typed catalog of the available scanner settings, parsed from the scanner parameters, against
+/// which they can be validated.
+pub mod scanner;
+#[cfg(feature = "sim")]
+/// Contains a [`sim::Simulator`] that fills orders against supplied market data and invokes
+/// [`wrapper::LocalWrapper`] callbacks, for backtesting without a connection to the API, when the
+/// `sim` feature is enabled.
+pub mod sim;
 /// Contains modules, types, and functions related to live data subscriptions, namely those
 /// that are created in [`client::Client::req_market_data`].
 ///
@@ -72,10 +149,27 @@ mod reader;
 ///this module, each of our groups gets its own submodule and corresponds one-to-one with a
 /// [`wrapper::LocalWrapper`] or [`wrapper::Wrapper`] method.
 pub mod tick;
+#[cfg(all(feature = "client", any(test, feature = "testing")))]
+/// Contains a [`testing::MockServer`] that stands in for TWS or IB Gateway, so that connections and
+/// request serialization can be tested end to end, when the `testing` feature is enabled.
+pub mod testing;
+#[cfg(feature = "client")]
+/// Contains the [`transport::Transport`] trait, which abstracts the byte stream over which a
+/// [`client::Client`] communicates, and an in-memory transport for tests.
+pub mod transport;
+#[cfg(feature = "client")]
 /// Contains the definition of the [`wrapper::LocalWrapper`] and [`wrapper::Wrapper`] traits. Implementing these traits for a
 /// type allows users to customize callback behavior.
 pub mod wrapper;
 
+#[doc(hidden)]
+// The crates used by the code that `ibapi_macros` generates, so that it also compiles in crates
+// that do not depend on them directly.
+pub mod __private {
+    pub use chrono;
+    pub use serde;
+}
+
 #[macro_export]
 /// Match across typed variant values
 macro_rules! match_poly {
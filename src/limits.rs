@@ -0,0 +1,212 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The maximum number of concurrent subscriptions allowed by an account's market data
+/// entitlements, which are installed with [`crate::client::Builder::with_limits`].
+///
+/// IBKR grants 100 market data lines by default, and more with commissions or quote booster
+/// packs. Depth and tick-by-tick subscriptions are limited separately. A limit of [`None`] is not
+/// enforced.
+pub struct Limits {
+    /// The maximum number of streaming [`crate::client::Client::req_market_data`]
+    /// subscriptions. Snapshots are not counted.
+    pub market_data_lines: Option<usize>,
+    /// The maximum number of [`crate::client::Client::req_market_depth`] subscriptions.
+    pub depth_subscriptions: Option<usize>,
+    /// The maximum number of [`crate::client::Client::req_tick_by_tick_data`] subscriptions.
+    pub tick_by_tick_streams: Option<usize>,
+}
+
+impl Limits {
+    #[must_use]
+    #[inline]
+    /// Create a new [`Limits`] that enforces no limits.
+    pub const fn new() -> Self {
+        Self {
+            market_data_lines: None,
+            depth_subscriptions: None,
+            tick_by_tick_streams: None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Limit the number of streaming market data subscriptions.
+    ///
+    /// # Arguments
+    /// * `lines` - The number of market data lines of the account.
+    pub const fn with_market_data_lines(mut self, lines: usize) -> Self {
+        self.market_data_lines = Some(lines);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Limit the number of market depth subscriptions.
+    ///
+    /// # Arguments
+    /// * `subscriptions` - The number of depth subscriptions allowed for the account.
+    pub const fn with_depth_subscriptions(mut self, subscriptions: usize) -> Self {
+        self.depth_subscriptions = Some(subscriptions);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Limit the number of tick-by-tick subscriptions.
+    ///
+    /// # Arguments
+    /// * `streams` - The number of tick-by-tick subscriptions allowed for the account.
+    pub const fn with_tick_by_tick_streams(mut self, streams: usize) -> Self {
+        self.tick_by_tick_streams = Some(streams);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the limit for a kind of subscription, if any.
+    pub const fn get(&self, kind: Kind) -> Option<usize> {
+        match kind {
+            Kind::MarketData => self.market_data_lines,
+            Kind::Depth => self.depth_subscriptions,
+            Kind::TickByTick => self.tick_by_tick_streams,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// The kinds of subscriptions that are limited by [`Limits`].
+pub enum Kind {
+    /// A streaming market data subscription.
+    MarketData,
+    /// A market depth subscription.
+    Depth,
+    /// A tick-by-tick subscription.
+    TickByTick,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::MarketData => "market data",
+            Self::Depth => "market depth",
+            Self::TickByTick => "tick-by-tick",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[error("The limit of {limit} concurrent {kind} subscriptions has been reached.")]
+/// An error indicating that a subscription would exceed the [`Limits`] of the client.
+///
+/// Requests that fail for this reason return a [`std::io::Error`] of kind
+/// [`std::io::ErrorKind::QuotaExceeded`] that wraps this error. No request is sent to IBKR.
+pub struct LimitExceeded {
+    /// The kind of subscription.
+    pub kind: Kind,
+    /// The maximum number of concurrent subscriptions of this kind.
+    pub limit: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The number of active subscriptions of one [`Kind`], as reported by
+/// [`crate::client::Client::get_subscription_usage`].
+pub struct Usage {
+    /// The number of active subscriptions.
+    pub active: usize,
+    /// The maximum number of concurrent subscriptions, if limited.
+    pub limit: Option<usize>,
+}
+
+// The request IDs of the active subscriptions of each kind. A subscription stays active until it
+// is cancelled through the client, even if TWS rejects it.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    limits: Limits,
+    market_data: BTreeSet<i64>,
+    depth: BTreeSet<i64>,
+    tick_by_tick: BTreeSet<i64>,
+}
+
+impl Tracker {
+    #[inline]
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    fn active(&self, kind: Kind) -> &BTreeSet<i64> {
+        match kind {
+            Kind::MarketData => &self.market_data,
+            Kind::Depth => &self.depth,
+            Kind::TickByTick => &self.tick_by_tick,
+        }
+    }
+
+    #[inline]
+    fn active_mut(&mut self, kind: Kind) -> &mut BTreeSet<i64> {
+        match kind {
+            Kind::MarketData => &mut self.market_data,
+            Kind::Depth => &mut self.depth,
+            Kind::TickByTick => &mut self.tick_by_tick,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn check(&self, kind: Kind) -> Result<(), LimitExceeded> {
+        match self.limits.get(kind) {
+            Some(limit) if self.active(kind).len() >= limit => Err(LimitExceeded { kind, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn track(&mut self, kind: Kind, req_id: i64) {
+        self.active_mut(kind).insert(req_id);
+    }
+
+    #[inline]
+    pub(crate) fn release(&mut self, kind: Kind, req_id: i64) {
+        self.active_mut(kind).remove(&req_id);
+    }
+
+    #[inline]
+    pub(crate) fn usage(&self, kind: Kind) -> Usage {
+        Usage {
+            active: self.active(kind).len(),
+            limit: self.limits.get(kind),
+        }
+    }
+}
+
+#[test]
+fn test_tracker() {
+    let mut tracker = Tracker::new(Limits::new().with_depth_subscriptions(2));
+    for req_id in 0..2 {
+        assert_eq!(tracker.check(Kind::Depth), Ok(()));
+        tracker.track(Kind::Depth, req_id);
+    }
+    assert_eq!(
+        tracker.check(Kind::Depth),
+        Err(LimitExceeded {
+            kind: Kind::Depth,
+            limit: 2
+        })
+    );
+    assert_eq!(tracker.check(Kind::MarketData), Ok(()));
+    tracker.release(Kind::Depth, 0);
+    assert_eq!(tracker.check(Kind::Depth), Ok(()));
+    assert_eq!(
+        tracker.usage(Kind::Depth),
+        Usage {
+            active: 1,
+            limit: Some(2)
+        }
+    );
+}
@@ -188,6 +188,20 @@ pub mod historical_bar {
         Eight = 8,
     }
 
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    /// The format in which IBKR sends the datetime of each bar. Either way, the datetimes of bars
+    /// are passed to the wrapper in UTC.
+    pub enum DateFormat {
+        #[default]
+        /// Each datetime is sent with its timezone, ex. "20240102 09:30:00 US/Eastern". Daily and
+        /// longer bars are sent as a date, which is interpreted in the timezone of the request's
+        /// start and end datetimes.
+        Local,
+        /// Each intraday datetime is sent as a Unix timestamp, which avoids any timezone lookup.
+        /// Daily and longer bars are still sent as a date.
+        Epoch,
+    }
+
     // === Type implementations ===
 
     impl Serialize for EndDateTime {
@@ -257,6 +271,19 @@ pub mod historical_bar {
         }
     }
 
+    impl Serialize for DateFormat {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match *self {
+                Self::Local => 1_u8,
+                Self::Epoch => 2,
+            }
+            .serialize(serializer)
+        }
+    }
+
     // === Data types ===
 
     #[typed_variants]
@@ -285,6 +312,18 @@ pub mod historical_bar {
         #[serde(rename = "OPTION_IMPLIED_VOLATILITY")]
         /// The options market implied volatility during the bar interval.
         SecOptionImpliedVolatility,
+        #[serde(rename = "FEE_RATE")]
+        /// The stock borrow fee rate during the bar interval.
+        FeeRate,
+        #[serde(rename = "ADJUSTED_LAST")]
+        /// The traded prices during the bar interval, adjusted for splits and dividends. Because
+        /// past bars change whenever a new adjustment occurs, this is only available from
+        /// [`crate::client::Client::req_historical_bar`] and never from an updating request.
+        AdjustedLast,
+        #[serde(rename = "SCHEDULE")]
+        /// The trading schedule over the requested duration. Rather than bars, this is returned
+        /// through [`crate::wrapper::LocalWrapper::historical_schedule`].
+        Schedule,
     }
 
     make_valid!(
@@ -295,11 +334,26 @@ pub mod historical_bar {
         BidAsk,
         HistoricalVolatility,
         SecOptionImpliedVolatility,
+        FeeRate,
+        AdjustedLast,
+        Schedule,
         Data
     );
 
     impl_data_type!(
-        (Trades, Midpoint, Bid, Ask, BidAsk, HistoricalVolatility, SecOptionImpliedVolatility, Data);
+        (
+            Trades,
+            Midpoint,
+            Bid,
+            Ask,
+            BidAsk,
+            HistoricalVolatility,
+            SecOptionImpliedVolatility,
+            FeeRate,
+            AdjustedLast,
+            Schedule,
+            Data
+        );
         (Stock)
     );
 
@@ -323,6 +377,11 @@ pub mod historical_bar {
         MidBidAskData;
         NotMidBidAskError
     );
+
+    impl_data_type!(
+        (Schedule);
+        (Index, SecOption, SecFuture, Crypto, Forex, Commodity)
+    );
 }
 
 /// Contains types and traits used by [`crate::client::Client::req_updating_historical_bar`].
@@ -391,10 +450,19 @@ pub mod updating_historical_bar {
 /// Contains types and traits used by [`crate::client::Client::req_historical_ticks`] and
 /// [`crate::client::Client::req_head_timestamp`].
 pub mod historical_ticks {
+    use chrono::{DateTime, TimeDelta, Utc};
     use ibapi_macros::typed_variants;
     use serde::{Deserialize, Serialize, Serializer};
 
-    use crate::contract::{Commodity, Contract, Crypto, Forex, Index, SecFuture, SecOption, Stock};
+    use crate::contract::{
+        Commodity, Contract, Crypto, Forex, Index, SecFuture, SecOption, Security, Stock,
+    };
+    use crate::payload::TickData;
+
+    // The defaults of `HistoricalTickIterator::with_pacing` and
+    // `HistoricalTickIterator::with_timeout`.
+    const DEFAULT_PACING: std::time::Duration = std::time::Duration::from_secs(10);
+    const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
     // === Type definitions ===
 
@@ -412,6 +480,38 @@ pub mod historical_ticks {
     /// A simple struct to ensure that the number of ticks to return never exceeds 1,000.
     pub struct NumberOfTicks(u16);
 
+    #[derive(Debug, Clone, PartialEq)]
+    /// Requests every historical tick in a period page by page, since a single
+    /// [`crate::client::Client::req_historical_ticks`] request returns at most 1,000 ticks.
+    ///
+    /// Each page starts at the datetime of the last tick of the previous page. Since historical
+    /// ticks are timestamped to the second, the ticks of that second are returned again and are
+    /// skipped.
+    ///
+    /// A page cannot be continued from partway through a second. If all 1,000 ticks of a page
+    /// fall within a single second, the next page starts at the following second, and any further
+    /// ticks of that second are never returned. Every such second is listed by
+    /// [`HistoricalTickIterator::truncated_seconds`], so that the loss can be detected and, for
+    /// example, the second requested again with another data type or a narrower filter.
+    ///
+    /// Consecutive requests are spaced by a pacing interval so that a long download
+    /// stays within IBKR's limit of 60 historical data requests in 10 minutes. The ticks are not
+    /// passed to [`crate::wrapper::LocalWrapper::historical_ticks`].
+    pub struct HistoricalTickIterator<S, D> {
+        security: S,
+        data: D,
+        regular_trading_hours_only: bool,
+        next: DateTime<Utc>,
+        end: DateTime<Utc>,
+        pacing: std::time::Duration,
+        timeout: std::time::Duration,
+        last_request: Option<tokio::time::Instant>,
+        // The ticks of the previous page at `next`, which the next page repeats.
+        boundary: Vec<TickData>,
+        truncated: Vec<DateTime<Utc>>,
+        done: bool,
+    }
+
     // === Type implementations ===
 
     impl NumberOfTicks {
@@ -434,6 +534,226 @@ pub mod historical_ticks {
         }
     }
 
+    impl<S, D> HistoricalTickIterator<S, D>
+    where
+        S: Security,
+        D: DataType<S>,
+    {
+        #[must_use]
+        /// Create a new [`HistoricalTickIterator`]. No request is sent until the first page is
+        /// requested.
+        ///
+        /// # Arguments
+        /// * `security` - The security for which to request ticks.
+        /// * `data` - The type of data to return (Trades, `BidAsk`, etc.).
+        /// * `start` - The first datetime for which ticks are returned.
+        /// * `end` - The last datetime for which ticks are returned.
+        /// * `regular_trading_hours_only` - When [`true`], only return ticks from regular trading
+        ///   hours.
+        pub fn new(
+            security: S,
+            data: D,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+            regular_trading_hours_only: bool,
+        ) -> Self {
+            Self {
+                security,
+                data,
+                regular_trading_hours_only,
+                next: start,
+                end,
+                pacing: DEFAULT_PACING,
+                timeout: DEFAULT_TIMEOUT,
+                last_request: None,
+                boundary: Vec::new(),
+                truncated: Vec::new(),
+                done: start > end,
+            }
+        }
+
+        #[must_use]
+        #[inline]
+        /// Set the minimum time between consecutive requests. By default, requests are spaced by
+        /// 10 seconds.
+        ///
+        /// # Arguments
+        /// * `pacing` - The minimum time between consecutive requests.
+        pub fn with_pacing(mut self, pacing: std::time::Duration) -> Self {
+            self.pacing = pacing;
+            self
+        }
+
+        #[must_use]
+        #[inline]
+        /// Set how long to wait for each page. By default, a page must arrive within 30 seconds.
+        ///
+        /// # Arguments
+        /// * `timeout` - How long to wait for each page.
+        pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+
+        #[must_use]
+        #[inline]
+        /// Return `true` once every tick up to the end of the period has been returned.
+        pub fn is_done(&self) -> bool {
+            self.done
+        }
+
+        #[must_use]
+        #[inline]
+        /// The seconds, in chronological order, for which a full page of 1,000 ticks was
+        /// returned. Any further ticks in these seconds could not be requested, so they are
+        /// missing from the returned pages.
+        pub fn truncated_seconds(&self) -> &[DateTime<Utc>] {
+            &self.truncated
+        }
+
+        /// Request the next page of ticks, waiting first if the previous request was made less
+        /// than one pacing interval ago.
+        ///
+        /// # Arguments
+        /// * `client` - The client with which to send the requests.
+        ///
+        /// # Errors
+        /// Returns any error encountered while writing the outgoing messages or communicating with
+        /// the loop that decodes incoming messages. Returns an error of kind
+        /// [`std::io::ErrorKind::TimedOut`] if a page is not received within the timeout (ex.
+        /// because IBKR reported an error for it), in which case the same page can be requested
+        /// again.
+        ///
+        /// # Returns
+        /// The ticks of the page in chronological order, each of which is returned once, or
+        /// [`None`] once every tick up to the end of the period has been returned. Ticks of a
+        /// second in [`HistoricalTickIterator::truncated_seconds`] may be missing.
+        pub async fn next_page(
+            &mut self,
+            client: &mut crate::client::ActiveClient,
+        ) -> Result<Option<Vec<TickData>>, std::io::Error> {
+            while !self.done {
+                if let Some(last_request) = self.last_request {
+                    tokio::time::sleep_until(last_request + self.pacing).await;
+                }
+                self.last_request = Some(tokio::time::Instant::now());
+                let ticks = client
+                    .req_historical_ticks_page(
+                        &self.security,
+                        TimeStamp::StartDateTime(self.next),
+                        NumberOfTicks::new(1_000),
+                        self.data,
+                        self.regular_trading_hours_only,
+                        self.timeout,
+                    )
+                    .await?;
+                let page = self.advance(ticks);
+                if !page.is_empty() {
+                    return Ok(Some(page));
+                }
+            }
+            Ok(None)
+        }
+
+        /// Request every remaining page of ticks.
+        ///
+        /// # Arguments
+        /// * `client` - The client with which to send the requests.
+        ///
+        /// # Errors
+        /// Returns any error encountered while requesting a page. See
+        /// [`HistoricalTickIterator::next_page`].
+        ///
+        /// # Returns
+        /// Every remaining tick in the period, in chronological order, except for any ticks of
+        /// truncated seconds (see [`HistoricalTickIterator::truncated_seconds`]). To detect such
+        /// a loss, use [`HistoricalTickIterator::next_page`] instead.
+        pub async fn collect(
+            mut self,
+            client: &mut crate::client::ActiveClient,
+        ) -> Result<Vec<TickData>, std::io::Error> {
+            let mut ticks = Vec::new();
+            while let Some(page) = self.next_page(client).await? {
+                ticks.extend(page);
+            }
+            Ok(ticks)
+        }
+
+        /// Request every remaining page of ticks and send each tick on `ticks` as its page
+        /// arrives.
+        ///
+        /// # Arguments
+        /// * `client` - The client with which to send the requests.
+        /// * `ticks` - The channel on which ticks are sent, in chronological order.
+        ///
+        /// # Errors
+        /// Returns any error encountered while requesting a page. See
+        /// [`HistoricalTickIterator::next_page`].
+        ///
+        /// # Returns
+        /// The iterator, once every tick has been sent or `ticks` is closed.
+        pub async fn forward(
+            mut self,
+            client: &mut crate::client::ActiveClient,
+            ticks: tokio::sync::mpsc::Sender<TickData>,
+        ) -> Result<Self, std::io::Error> {
+            while let Some(page) = self.next_page(client).await? {
+                for tick in page {
+                    if ticks.send(tick).await.is_err() {
+                        return Ok(self);
+                    }
+                }
+            }
+            Ok(self)
+        }
+
+        // Record a page as received and return its ticks that were not already returned and do
+        // not fall after the end of the period.
+        fn advance(&mut self, ticks: Vec<TickData>) -> Vec<TickData> {
+            let Some(last) = ticks.last().map(tick_datetime) else {
+                self.done = true;
+                return Vec::new();
+            };
+            let full = ticks.len() >= 1_000;
+            let mut repeated = std::mem::take(&mut self.boundary);
+            if last > self.next {
+                self.boundary = ticks
+                    .iter()
+                    .filter(|t| tick_datetime(t) == last)
+                    .copied()
+                    .collect();
+                self.next = last;
+            } else {
+                // A full page within a single second cannot be continued from that second, so
+                // any further ticks of the second are skipped.
+                if full {
+                    tracing::warn!("Historical ticks after the first 1,000 at {last} were skipped");
+                    self.truncated.push(last);
+                }
+                self.next = last + TimeDelta::seconds(1);
+            }
+            self.done = !full || self.next > self.end;
+            ticks
+                .into_iter()
+                .filter(|tick| match repeated.iter().position(|r| r == tick) {
+                    Some(i) => {
+                        repeated.swap_remove(i);
+                        false
+                    }
+                    None => tick_datetime(tick) <= self.end,
+                })
+                .collect()
+        }
+    }
+
+    fn tick_datetime(tick: &TickData) -> DateTime<Utc> {
+        match tick {
+            TickData::Midpoint(t) => t.datetime,
+            TickData::BidAsk(t) => t.datetime,
+            TickData::Last(t) => t.datetime,
+        }
+    }
+
     impl Serialize for TimeStamp {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
@@ -548,12 +868,16 @@ pub mod live_bar {
 /// Contains types and traits used by [`crate::client::Client::req_market_data`] and
 /// [`crate::client::Client::req_market_data_type`].
 pub mod live_data {
+    use std::collections::HashMap;
     use std::fmt::Formatter;
 
     use ibapi_macros::typed_variants;
     use serde::{Deserialize, Serialize};
 
-    use crate::contract::{Commodity, Crypto, Forex, Index, SecFuture, SecOption, Stock};
+    use crate::contract::{
+        Commodity, ContractId, Crypto, Forex, Index, SecFuture, SecOption, Stock,
+    };
+    use crate::requests::{category, RequestId};
 
     // === Type definitions ===
 
@@ -589,8 +913,76 @@ pub mod live_data {
     /// An error type that represents an invalid [`Class`] has been received.
     pub struct ParseClassError(String);
 
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    /// The subscriptions made by [`crate::client::Client::req_market_data_bulk`], which maps the
+    /// contract ID of each security to the ID of its request.
+    ///
+    /// Every subscription is cancelled at once by
+    /// [`crate::client::Client::cancel_market_data_bulk`].
+    pub struct Watchlist {
+        req_ids: HashMap<ContractId, RequestId<category::MarketData>>,
+    }
+
     // === Type implementations ===
 
+    impl Watchlist {
+        #[inline]
+        pub(crate) fn insert(
+            &mut self,
+            contract_id: ContractId,
+            req_id: RequestId<category::MarketData>,
+        ) {
+            self.req_ids.insert(contract_id, req_id);
+        }
+
+        #[must_use]
+        #[inline]
+        /// Return the ID of the request for a contract, if it is in the watchlist.
+        ///
+        /// # Arguments
+        /// * `contract_id` - The contract ID of the security.
+        pub fn req_id(&self, contract_id: ContractId) -> Option<RequestId<category::MarketData>> {
+            self.req_ids.get(&contract_id).copied()
+        }
+
+        #[must_use]
+        #[inline]
+        /// Return the contract ID of the security to which a request belongs, which is useful to
+        /// route the callbacks of the watchlist, if the request is in the watchlist.
+        ///
+        /// # Arguments
+        /// * `req_id` - The ID of the request.
+        pub fn contract_id(&self, req_id: i64) -> Option<ContractId> {
+            self.req_ids
+                .iter()
+                .find_map(|(&contract_id, &id)| (id == req_id).then_some(contract_id))
+        }
+
+        #[inline]
+        /// Return an iterator over the contract ID and request ID of every subscription.
+        pub fn iter(
+            &self,
+        ) -> impl Iterator<Item = (ContractId, RequestId<category::MarketData>)> + '_ {
+            self.req_ids
+                .iter()
+                .map(|(&contract_id, &req_id)| (contract_id, req_id))
+        }
+
+        #[must_use]
+        #[inline]
+        /// Return the number of subscriptions in the watchlist.
+        pub fn len(&self) -> usize {
+            self.req_ids.len()
+        }
+
+        #[must_use]
+        #[inline]
+        /// Return whether the watchlist has no subscriptions.
+        pub fn is_empty(&self) -> bool {
+            self.req_ids.is_empty()
+        }
+    }
+
     impl std::str::FromStr for Class {
         type Err = ParseClassError;
 
@@ -641,29 +1033,45 @@ pub mod live_data {
         /// Miscellaneous statistics associated with the stock.
         MiscellaneousStats,
         #[serde(rename = "221")]
-        /// The mark-to-market price used for margin at IBKR.
+        /// The mark-to-market price used for margin at IBKR, sent through
+        /// [`crate::wrapper::LocalWrapper::mark_price`].
         MarkPrice,
         #[serde(rename = "225")]
-        /// The volume, price, and imbalance of an auction.
+        /// The volume, price, and imbalance of an auction, which are also combined into a single
+        /// [`crate::tick::Auction`].
         AuctionValues,
         #[serde(rename = "233")]
-        /// Last trade's price, size, and time.
+        /// Last trade's price, size, and time, sent through
+        /// [`crate::wrapper::LocalWrapper::real_time_volume`] as [`crate::tick::RealTimeVolume::All`].
         RealTimeVolume,
+        #[serde(rename = "375")]
+        /// Last trade's price, size, and time, excluding unreportable trades, sent through
+        /// [`crate::wrapper::LocalWrapper::real_time_volume`] as
+        /// [`crate::tick::RealTimeVolume::Trades`].
+        RealTimeTradeVolume,
         #[serde(rename = "236")]
-        /// The level of difficulty associated with short-selling a security.
+        /// The level of difficulty associated with short-selling a security and the number of
+        /// shares available to short, which are also combined into a single
+        /// [`crate::tick::ShortBorrow`].
         Shortable,
         #[serde(rename = "256")]
         /// Available inventory for short-selling.
         Inventory,
         #[serde(rename = "258")]
-        /// Fundamental stock ratios.
+        /// Fundamental stock ratios, sent through
+        /// [`crate::wrapper::LocalWrapper::fundamental_ratios`].
         FundamentalRatios,
         #[serde(rename = "411")]
         /// 30-day real time historical volatility.
         RealtimeHistoricalVolatility,
         #[serde(rename = "456")]
-        /// Information about past and future dividends.
+        /// Information about past and future dividends, sent through
+        /// [`crate::wrapper::LocalWrapper::dividends`].
         IBDividends,
+        #[serde(rename = "586")]
+        /// The estimated and final prices of an IPO, sent through
+        /// [`crate::wrapper::LocalWrapper::ipo`].
+        IpoPrices,
         #[serde(rename = "")]
         /// No additional data
         Empty,
@@ -680,11 +1088,13 @@ pub mod live_data {
         MarkPrice,
         AuctionValues,
         RealTimeVolume,
+        RealTimeTradeVolume,
         Shortable,
         Inventory,
         FundamentalRatios,
         RealtimeHistoricalVolatility,
         IBDividends,
+        IpoPrices,
         Empty,
         Data
     );
@@ -701,11 +1111,13 @@ pub mod live_data {
             MarkPrice,
             AuctionValues,
             RealTimeVolume,
+            RealTimeTradeVolume,
             Shortable,
             Inventory,
             FundamentalRatios,
             RealtimeHistoricalVolatility,
             IBDividends,
+            IpoPrices,
             Empty,
             Data
         );
@@ -719,11 +1131,10 @@ pub mod live_data {
             MarkPrice,
             AuctionValues,
             RealTimeVolume,
+            RealTimeTradeVolume,
             Shortable,
             Inventory,
-            FundamentalRatios,
             RealtimeHistoricalVolatility,
-            IBDividends,
             Empty
         );
         (Forex, SecOption, SecFuture, Crypto, Index, Commodity);
@@ -769,3 +1180,1545 @@ pub mod live_ticks {
         (Stock, Forex, SecFuture, Crypto, Index, Commodity)
     );
 }
+
+/// Contains a helper that subscribes to the model option computations of many options and keeps
+/// the latest greeks for each.
+pub mod greeks {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use crate::contract::{ContractId, SecOption, SecOptionClass};
+    use crate::tick::{
+        SecOptionCalculationResults, SecOptionCalculationSource, SecOptionCalculations,
+    };
+
+    use crate::requests::RequestId;
+
+    use super::live_data;
+
+    // === Type definitions ===
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    /// Identifies a single option by its underlying and its position within that underlying's
+    /// chain.
+    pub struct Key {
+        /// The contract ID of the option's underlying.
+        pub underlying_contract_id: ContractId,
+        /// The option's expiration date.
+        pub expiration_date: NaiveDate,
+        /// The option's strike price.
+        pub strike: f64,
+        /// Whether the option is a call or a put.
+        pub class: SecOptionClass,
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq)]
+    /// Tracks the model option computations for a set of options, so that a single view of the
+    /// greeks across a chain is available without managing each request ID.
+    ///
+    /// After subscribing with [`Greeks::subscribe`], pass every
+    /// [`crate::wrapper::LocalWrapper::sec_option_computation`] callback to [`Greeks::update`].
+    pub struct Greeks {
+        keys: HashMap<i64, Key>,
+        results: HashMap<i64, SecOptionCalculationResults>,
+    }
+
+    // === Type implementations ===
+
+    impl From<&SecOption> for Key {
+        fn from(value: &SecOption) -> Self {
+            Self {
+                underlying_contract_id: value.underlying_contract_id(),
+                expiration_date: value.expiration_date(),
+                strike: value.strike(),
+                class: value.class(),
+            }
+        }
+    }
+
+    impl Greeks {
+        #[must_use]
+        #[inline]
+        /// Create a new [`Greeks`] with no subscriptions.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Request streaming market data, which includes the model option computations, for each
+        /// option.
+        ///
+        /// # Arguments
+        /// * `client` - The client with which to send the requests.
+        /// * `options` - The options to which to subscribe.
+        ///
+        /// # Errors
+        /// Returns any error encountered while writing the outgoing messages. Any subscription
+        /// made before the error remains in place.
+        pub async fn subscribe<'o, I>(
+            &mut self,
+            client: &mut crate::client::ActiveClient,
+            options: I,
+        ) -> Result<(), std::io::Error>
+        where
+            I: IntoIterator<Item = &'o SecOption>,
+        {
+            for option in options {
+                let req_id = client
+                    .req_market_data(
+                        option,
+                        vec![live_data::Empty],
+                        live_data::RefreshType::Streaming,
+                        false,
+                    )
+                    .await?;
+                self.keys.insert(req_id.get(), Key::from(option));
+            }
+            Ok(())
+        }
+
+        /// Cancel every subscription and discard the greeks received so far.
+        ///
+        /// # Arguments
+        /// * `client` - The client with which to send the cancellations.
+        ///
+        /// # Errors
+        /// Returns any error encountered while writing the outgoing messages.
+        pub async fn cancel(
+            &mut self,
+            client: &mut crate::client::ActiveClient,
+        ) -> Result<(), std::io::Error> {
+            for (req_id, _) in self.keys.drain() {
+                client.cancel_market_data(RequestId::new(req_id)).await?;
+            }
+            self.results.clear();
+            Ok(())
+        }
+
+        /// Record the results of an option computation. Only computations based on the IBKR
+        /// options model are kept.
+        ///
+        /// # Arguments
+        /// * `req_id` - The request ID of the computation.
+        /// * `calc` - The computation.
+        ///
+        /// # Returns
+        /// `true` if `req_id` belongs to one of this helper's subscriptions.
+        pub fn update(&mut self, req_id: i64, calc: SecOptionCalculationSource) -> bool {
+            if !self.keys.contains_key(&req_id) {
+                return false;
+            }
+            if let SecOptionCalculationSource::Model(
+                SecOptionCalculations::PriceBased(results)
+                | SecOptionCalculations::ReturnBased(results),
+            ) = calc
+            {
+                self.results.insert(req_id, results);
+            }
+            true
+        }
+
+        #[must_use]
+        /// Get the latest greeks for a single option.
+        ///
+        /// # Arguments
+        /// * `key` - The option's underlying, expiration date, strike, and class.
+        ///
+        /// # Returns
+        /// The latest model computation, or `None` if the option has no subscription or no
+        /// computation has been received for it.
+        pub fn get(&self, key: &Key) -> Option<&SecOptionCalculationResults> {
+            self.keys
+                .iter()
+                .find(|(_, k)| *k == key)
+                .and_then(|(req_id, _)| self.results.get(req_id))
+        }
+
+        #[must_use]
+        /// Get the latest greeks for every subscribed option.
+        ///
+        /// # Returns
+        /// Each option's key and latest model computation (`None` if nothing has been received
+        /// yet), sorted by underlying contract ID, then expiration date, then strike, then class.
+        pub fn snapshot(&self) -> Vec<(Key, Option<SecOptionCalculationResults>)> {
+            let mut snapshot = self
+                .keys
+                .iter()
+                .map(|(req_id, key)| (*key, self.results.get(req_id).copied()))
+                .collect::<Vec<_>>();
+            snapshot.sort_by(|(a, _), (b, _)| {
+                (a.underlying_contract_id.0)
+                    .cmp(&b.underlying_contract_id.0)
+                    .then(a.expiration_date.cmp(&b.expiration_date))
+                    .then(a.strike.total_cmp(&b.strike))
+                    .then((a.class == SecOptionClass::Put).cmp(&(b.class == SecOptionClass::Put)))
+            });
+            snapshot
+        }
+    }
+}
+
+/// Contains a helper that remembers how far historical bars have been downloaded and resumes a
+/// backfill from that point.
+pub mod backfill {
+    use std::collections::{BTreeMap, HashMap, VecDeque};
+    use std::path::{Path, PathBuf};
+
+    use chrono::{DateTime, TimeDelta, Utc};
+    use serde::{Deserialize, Serialize};
+    use thiserror::Error;
+
+    use crate::contract::Security;
+    use crate::payload::Bar;
+
+    use super::{historical_bar, historical_ticks};
+
+    // === Type definitions ===
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[serde(transparent)]
+    /// Identifies a single series of bars by its contract ID, bar size, and data type
+    /// (ex. "265598:1 min:TRADES").
+    pub struct Key(String);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// A single [`crate::client::Client::req_historical_bar`] request in a backfill.
+    pub struct Chunk {
+        /// The last datetime for which bars are requested.
+        pub end: DateTime<Utc>,
+        /// The span of datetimes ending at `end` for which bars are requested.
+        pub duration: historical_bar::Duration,
+    }
+
+    #[derive(Debug, Error)]
+    /// Error type representing the ways that a backfill can fail.
+    pub enum BackfillError {
+        #[error("Failed to read or write the backfill file. Cause: {0}")]
+        /// The OS failed to read or write the file, or an outgoing message failed to send.
+        Io(#[from] std::io::Error),
+        #[error("Failed to parse the backfill file. Cause: {0}")]
+        /// The file contained invalid `TOML` data.
+        Parse(#[from] toml::de::Error),
+        #[error("Failed to serialize the backfill file. Cause: {0}")]
+        /// The resume points could not be written as `TOML`.
+        Serialize(#[from] toml::ser::Error),
+        #[error("No resume point was stored and no head timestamp was received for {0:?}")]
+        /// There was nothing from which to start the backfill.
+        NoStart(Key),
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct ResumePoints {
+        last: BTreeMap<Key, DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    // A chunk requested by `Backfiller::resume` whose series has an earlier chunk outstanding.
+    struct Requested {
+        req_id: i64,
+        complete: bool,
+        // The datetime of the last bar received for the chunk.
+        last: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    /// Persists the datetime up to which every bar has been received for each series and splits
+    /// the remaining history into chunked [`crate::client::Client::req_historical_bar`] requests.
+    ///
+    /// After starting a backfill with [`Backfiller::resume`], pass every
+    /// [`crate::wrapper::LocalWrapper::historical_bars`] callback to [`Backfiller::update`], then
+    /// call [`Backfiller::save`] so that the next run picks up where this one stopped.
+    pub struct Backfiller {
+        path: PathBuf,
+        points: ResumePoints,
+        requests: HashMap<i64, Key>,
+        chunks: HashMap<Key, VecDeque<Requested>>,
+    }
+
+    // === Type implementations ===
+
+    impl Key {
+        #[must_use]
+        /// Create the key that identifies a series of bars.
+        ///
+        /// # Arguments
+        /// * `security` - The security whose bars make up the series.
+        /// * `bar_size` - The size of each bar.
+        /// * `data` - The type of data in each bar.
+        pub fn new<S, D>(security: &S, bar_size: historical_bar::Size, data: D) -> Self
+        where
+            S: Security,
+            D: historical_bar::DataType<S>,
+        {
+            // Both a typed variant (ex. `Trades`) and its enum form (ex. `Data::Trades(Trades)`)
+            // are sent to IBKR with the same name, by which they are keyed.
+            let data = crate::comm::fields(data)
+                .ok()
+                .and_then(|fields| fields.into_iter().next())
+                .unwrap_or_default();
+            Self(format!(
+                "{}:{}:{data}",
+                security.contract_id().0,
+                as_str(&bar_size)
+            ))
+        }
+    }
+
+    impl std::fmt::Display for Key {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    fn as_str<T: Serialize>(value: &T) -> String {
+        match toml::Value::try_from(value) {
+            Ok(toml::Value::String(s)) => s,
+            _ => String::new(),
+        }
+    }
+
+    // The longest span that IBKR will return in a single request for each bar size.
+    fn max_span(bar_size: historical_bar::Size) -> TimeDelta {
+        use historical_bar::{SecondSize, Size};
+
+        match bar_size {
+            Size::Seconds(SecondSize::One) => TimeDelta::minutes(30),
+            Size::Seconds(SecondSize::Five) => TimeDelta::hours(2),
+            Size::Seconds(SecondSize::Ten | SecondSize::Fifteen) => TimeDelta::hours(4),
+            Size::Seconds(SecondSize::Thirty) => TimeDelta::hours(8),
+            Size::Minutes(_) => TimeDelta::weeks(1),
+            Size::Hours(_) => TimeDelta::days(30),
+            Size::Day | Size::Week | Size::Month => TimeDelta::days(365),
+        }
+    }
+
+    #[must_use]
+    /// Split the span between two datetimes into requests that IBKR will accept.
+    ///
+    /// # Arguments
+    /// * `start` - The first datetime for which bars are needed.
+    /// * `end` - The last datetime for which bars are needed.
+    /// * `bar_size` - The size of each bar.
+    ///
+    /// # Returns
+    /// The requests, in chronological order. Spans of one day or more are requested in whole
+    /// days, so the first request may reach back before `start`.
+    pub fn chunks(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        bar_size: historical_bar::Size,
+    ) -> Vec<Chunk> {
+        let span = max_span(bar_size);
+        let mut chunks = Vec::new();
+        let mut chunk_end = end;
+        while chunk_end > start {
+            let length = (chunk_end - start).min(span);
+            let duration = if length < TimeDelta::days(1) {
+                historical_bar::Duration::Second(
+                    u32::try_from(length.num_seconds())
+                        .unwrap_or(u32::MAX)
+                        .max(1),
+                )
+            } else {
+                historical_bar::Duration::Day(
+                    u32::try_from((length + TimeDelta::days(1) - TimeDelta::seconds(1)).num_days())
+                        .unwrap_or(u32::MAX),
+                )
+            };
+            chunks.push(Chunk {
+                end: chunk_end,
+                duration,
+            });
+            chunk_end -= length;
+        }
+        chunks.reverse();
+        chunks
+    }
+
+    impl Backfiller {
+        /// Load the resume points stored at `path`. A missing file is treated as empty.
+        ///
+        /// # Arguments
+        /// * `path` - The `TOML` file in which resume points are stored.
+        ///
+        /// # Errors
+        /// Returns any error encountered while reading or parsing the file.
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, BackfillError> {
+            let path = path.as_ref().to_path_buf();
+            let points = match std::fs::read_to_string(&path) {
+                Ok(s) => toml::from_str(&s)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => ResumePoints::default(),
+                Err(e) => return Err(e.into()),
+            };
+            Ok(Self {
+                path,
+                points,
+                requests: HashMap::new(),
+                chunks: HashMap::new(),
+            })
+        }
+
+        /// Write the resume points back to the file from which they were loaded.
+        ///
+        /// # Errors
+        /// Returns any error encountered while serializing or writing the file.
+        pub fn save(&self) -> Result<(), BackfillError> {
+            std::fs::write(&self.path, toml::to_string(&self.points)?)?;
+            Ok(())
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the datetime of the last bar received for a series.
+        pub fn last(&self, key: &Key) -> Option<DateTime<Utc>> {
+            self.points.last.get(key).copied()
+        }
+
+        /// Record that bars up to `datetime` have been received for a series. Earlier datetimes
+        /// than the stored one are ignored.
+        pub fn record(&mut self, key: Key, datetime: DateTime<Utc>) {
+            let last = self.points.last.entry(key).or_insert(datetime);
+            *last = (*last).max(datetime);
+        }
+
+        /// Record the bars received for one of this helper's requests.
+        ///
+        /// The resume point of a series only advances past a chunk once every earlier chunk of
+        /// the series has been received, so that a chunk that completes out of order, or an
+        /// earlier one that never completes, cannot leave a gap behind the resume point.
+        ///
+        /// # Arguments
+        /// * `req_id` - The request ID of the bars.
+        /// * `bars` - The bars.
+        ///
+        /// # Returns
+        /// `true` if `req_id` belongs to one of this helper's requests.
+        pub fn update(&mut self, req_id: i64, bars: &[Bar]) -> bool {
+            let Some(key) = self.requests.remove(&req_id) else {
+                return false;
+            };
+            let Some(chunks) = self.chunks.get_mut(&key) else {
+                return true;
+            };
+            if let Some(chunk) = chunks.iter_mut().find(|chunk| chunk.req_id == req_id) {
+                chunk.complete = true;
+                chunk.last = bars.iter().map(bar_datetime).max();
+            }
+            let mut last = None;
+            while let Some(chunk) = chunks.front().filter(|chunk| chunk.complete).copied() {
+                chunks.pop_front();
+                last = last.max(chunk.last);
+            }
+            if chunks.is_empty() {
+                self.chunks.remove(&key);
+            }
+            if let Some(datetime) = last {
+                self.record(key, datetime);
+            }
+            true
+        }
+
+        /// Request every bar after the stored resume point for a series. If there is no resume
+        /// point, the backfill starts at the earliest available data point, which is fetched with
+        /// [`crate::client::Client::req_head_timestamps`].
+        ///
+        /// # Arguments
+        /// * `client` - The client with which to send the requests.
+        /// * `security` - The security for which to request bars.
+        /// * `bar_size` - The size of each bar.
+        /// * `data` - The type of data in each bar.
+        /// * `head_data` - The type of data used to find the earliest available data point.
+        /// * `regular_trading_hours_only` - When [`true`], only return bars from regular trading
+        ///   hours.
+        /// * `timeout` - How long to wait for the earliest available data point.
+        ///
+        /// # Returns
+        /// The request ID of each chunk, in chronological order.
+        ///
+        /// # Errors
+        /// Returns [`BackfillError::NoStart`] if there is no resume point and no head timestamp
+        /// arrives before `timeout`, or any error encountered while writing the outgoing
+        /// messages.
+        #[allow(clippy::too_many_arguments)]
+        pub async fn resume<S, D, H>(
+            &mut self,
+            client: &mut crate::client::ActiveClient,
+            security: &S,
+            bar_size: historical_bar::Size,
+            data: D,
+            head_data: H,
+            regular_trading_hours_only: bool,
+            timeout: std::time::Duration,
+        ) -> Result<Vec<i64>, BackfillError>
+        where
+            S: Security,
+            D: historical_bar::DataType<S>,
+            H: historical_ticks::DataType<S> + Copy,
+        {
+            let key = Key::new(security, bar_size, data);
+            let start = match self.last(&key) {
+                Some(last) => last + TimeDelta::seconds(1),
+                None => client
+                    .req_head_timestamps(
+                        std::slice::from_ref(security),
+                        head_data,
+                        regular_trading_hours_only,
+                        timeout,
+                    )
+                    .await?
+                    .remove(&security.contract_id())
+                    .ok_or_else(|| BackfillError::NoStart(key.clone()))?,
+            };
+
+            let mut req_ids = Vec::new();
+            for chunk in chunks(start, Utc::now(), bar_size) {
+                let req_id = client
+                    .req_historical_bar(
+                        security,
+                        historical_bar::EndDateTime::Past(chunk.end.with_timezone(&chrono_tz::UTC)),
+                        chunk.duration,
+                        bar_size,
+                        data,
+                        regular_trading_hours_only,
+                        historical_bar::DateFormat::default(),
+                    )
+                    .await?;
+                self.requests.insert(req_id, key.clone());
+                self.chunks
+                    .entry(key.clone())
+                    .or_default()
+                    .push_back(Requested {
+                        req_id,
+                        complete: false,
+                        last: None,
+                    });
+                req_ids.push(req_id);
+            }
+            Ok(req_ids)
+        }
+    }
+
+    fn bar_datetime(bar: &Bar) -> DateTime<Utc> {
+        match bar {
+            Bar::Ordinary(core) => core.datetime,
+            Bar::Trades(trade) => trade.bar.datetime,
+        }
+    }
+}
+
+/// Contains a helper that builds bars of any size from live trade ticks, since IBKR's real-time
+/// bars are only available in 5-second intervals.
+pub mod aggregate {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use crate::payload::{Bar, BarCore, Last, TickData, Trade};
+
+    // === Type definitions ===
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    /// The rule that decides when a bar is complete.
+    pub enum Interval {
+        /// Each bar covers a fixed span of time. Bars are aligned to the Unix epoch, so one-minute
+        /// bars start on the minute.
+        Time(TimeDelta),
+        /// Each bar closes on the first trade at which its total volume reaches this amount.
+        Volume(f64),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// Builds OHLCV bars from a stream of [`TickData::Last`] ticks, such as those passed to
+    /// [`crate::wrapper::LocalWrapper::live_tick`] by a
+    /// [`crate::client::Client::req_tick_by_tick_data`] request for trades.
+    ///
+    /// Each bar's datetime is the start of its interval for time bars, and the datetime of its
+    /// first trade for volume bars.
+    pub struct Aggregator {
+        interval: Interval,
+        current: Option<Partial>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Partial {
+        start: DateTime<Utc>,
+        open: f64,
+        high: f64,
+        low: f64,
+        close: f64,
+        volume: f64,
+        notional: f64,
+        trade_count: u64,
+    }
+
+    // === Type implementations ===
+
+    impl Partial {
+        fn new(start: DateTime<Utc>, tick: &Last) -> Self {
+            Self {
+                start,
+                open: tick.price,
+                high: tick.price,
+                low: tick.price,
+                close: tick.price,
+                volume: tick.size,
+                notional: tick.price * tick.size,
+                trade_count: 1,
+            }
+        }
+
+        fn add(&mut self, tick: &Last) {
+            self.high = self.high.max(tick.price);
+            self.low = self.low.min(tick.price);
+            self.close = tick.price;
+            self.volume += tick.size;
+            self.notional += tick.price * tick.size;
+            self.trade_count += 1;
+        }
+
+        fn into_bar(self) -> Bar {
+            Bar::Trades(Trade {
+                bar: BarCore {
+                    datetime: self.start,
+                    open: self.open,
+                    high: self.high,
+                    low: self.low,
+                    close: self.close,
+                },
+                volume: self.volume,
+                wap: if self.volume > 0.0 {
+                    self.notional / self.volume
+                } else {
+                    self.close
+                },
+                trade_count: self.trade_count,
+            })
+        }
+    }
+
+    impl Aggregator {
+        #[must_use]
+        /// Create a new [`Aggregator`].
+        ///
+        /// # Arguments
+        /// * `interval` - The rule that decides when a bar is complete.
+        ///
+        /// # Returns
+        /// The aggregator, or `None` if `interval` is not positive.
+        pub fn new(interval: Interval) -> Option<Self> {
+            let valid = match interval {
+                Interval::Time(span) => span > TimeDelta::zero(),
+                Interval::Volume(volume) => volume > 0.0,
+            };
+            valid.then_some(Self {
+                interval,
+                current: None,
+            })
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the rule that decides when a bar is complete.
+        pub fn interval(&self) -> Interval {
+            self.interval
+        }
+
+        /// Add a tick to the bar in progress. Ticks other than [`TickData::Last`] are ignored.
+        ///
+        /// # Arguments
+        /// * `tick` - The tick to add.
+        ///
+        /// # Returns
+        /// The bar completed by this tick, if any.
+        pub fn push(&mut self, tick: &TickData) -> Option<Bar> {
+            let TickData::Last(tick) = tick else {
+                return None;
+            };
+            match self.interval {
+                Interval::Time(span) => {
+                    let start = bucket_start(tick.datetime, span);
+                    match &mut self.current {
+                        Some(current) if current.start == start => {
+                            current.add(tick);
+                            None
+                        }
+                        current => current
+                            .replace(Partial::new(start, tick))
+                            .map(Partial::into_bar),
+                    }
+                }
+                Interval::Volume(volume) => {
+                    let current = match &mut self.current {
+                        Some(current) => {
+                            current.add(tick);
+                            current
+                        }
+                        current => current.insert(Partial::new(tick.datetime, tick)),
+                    };
+                    if current.volume >= volume {
+                        self.current.take().map(Partial::into_bar)
+                    } else {
+                        None
+                    }
+                }
+            }
+        }
+
+        /// Add a tick and pass the bar it completes, if any, to a callback.
+        ///
+        /// # Arguments
+        /// * `tick` - The tick to add.
+        /// * `on_bar` - The callback that receives each completed bar.
+        pub fn push_with(&mut self, tick: &TickData, on_bar: impl FnOnce(Bar)) {
+            if let Some(bar) = self.push(tick) {
+                on_bar(bar);
+            }
+        }
+
+        /// Complete the bar in progress, regardless of its interval.
+        ///
+        /// # Returns
+        /// The bar in progress, or `None` if no trades have been added since the last bar.
+        pub fn flush(&mut self) -> Option<Bar> {
+            self.current.take().map(Partial::into_bar)
+        }
+
+        /// Build bars from every tick received on `ticks` and send each completed bar on `bars`.
+        /// The bar in progress is sent once `ticks` closes.
+        ///
+        /// # Arguments
+        /// * `ticks` - The channel on which ticks are received.
+        /// * `bars` - The channel on which completed bars are sent.
+        ///
+        /// # Returns
+        /// The aggregator, once `ticks` or `bars` is closed.
+        pub async fn forward(
+            mut self,
+            mut ticks: tokio::sync::mpsc::Receiver<TickData>,
+            bars: tokio::sync::mpsc::Sender<Bar>,
+        ) -> Self {
+            while let Some(tick) = ticks.recv().await {
+                if let Some(bar) = self.push(&tick) {
+                    if bars.send(bar).await.is_err() {
+                        return self;
+                    }
+                }
+            }
+            if let Some(bar) = self.flush() {
+                let _ = bars.send(bar).await;
+            }
+            self
+        }
+    }
+
+    fn bucket_start(datetime: DateTime<Utc>, span: TimeDelta) -> DateTime<Utc> {
+        let span = span.num_milliseconds().max(1);
+        let millis = datetime.timestamp_millis();
+        DateTime::from_timestamp_millis(millis - millis.rem_euclid(span)).unwrap_or(datetime)
+    }
+}
+
+/// Contains a helper that computes running statistics, such as VWAP and a rolling volume profile,
+/// from live trades.
+pub mod analytics {
+    use std::collections::{BTreeMap, HashMap, VecDeque};
+
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use crate::payload::TickData;
+    use crate::tick::RealTimeVolume;
+
+    // === Type definitions ===
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// Computes running statistics for each request from the trades passed to it.
+    ///
+    /// Pass every [`crate::wrapper::LocalWrapper::real_time_volume`] callback to
+    /// [`Analytics::update_volume`] and every [`crate::wrapper::LocalWrapper::live_tick`] callback
+    /// to [`Analytics::update_tick`]. The statistics cover every trade received since the first
+    /// one for a request (or since [`Analytics::reset`]), except for the volume profile, which
+    /// only covers a rolling window.
+    pub struct Analytics {
+        bucket_size: f64,
+        window: TimeDelta,
+        series: HashMap<i64, Series>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// The running statistics for a single request.
+    pub struct Series {
+        volume: f64,
+        notional: f64,
+        high: f64,
+        low: f64,
+        last: Option<(DateTime<Utc>, f64)>,
+        first_time: DateTime<Utc>,
+        time_weighted: f64,
+        trade_count: u64,
+        bucket_size: f64,
+        window: TimeDelta,
+        recent: VecDeque<(DateTime<Utc>, i64, f64)>,
+        profile: BTreeMap<i64, f64>,
+    }
+
+    // === Type implementations ===
+
+    impl Analytics {
+        #[must_use]
+        /// Create a new [`Analytics`] with no series.
+        ///
+        /// # Arguments
+        /// * `bucket_size` - The width of each price level in the volume profile.
+        /// * `window` - The span of time covered by the volume profile.
+        ///
+        /// # Returns
+        /// The helper, or `None` if `bucket_size` or `window` is not positive.
+        pub fn new(bucket_size: f64, window: TimeDelta) -> Option<Self> {
+            (bucket_size > 0.0 && window > TimeDelta::zero()).then(|| Self {
+                bucket_size,
+                window,
+                series: HashMap::new(),
+            })
+        }
+
+        /// Record a real-time volume update.
+        ///
+        /// # Arguments
+        /// * `req_id` - The request ID of the update.
+        /// * `volume` - The update.
+        pub fn update_volume(&mut self, req_id: i64, volume: RealTimeVolume) {
+            let (RealTimeVolume::All(base) | RealTimeVolume::Trades(base)) = volume;
+            self.add(req_id, base.last_time, base.last_price, base.last_size);
+        }
+
+        /// Record a tick. Ticks other than [`TickData::Last`] are ignored.
+        ///
+        /// # Arguments
+        /// * `req_id` - The request ID of the tick.
+        /// * `tick` - The tick.
+        pub fn update_tick(&mut self, req_id: i64, tick: &TickData) {
+            if let TickData::Last(last) = tick {
+                self.add(req_id, last.datetime, last.price, last.size);
+            }
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the statistics for a single request.
+        ///
+        /// # Returns
+        /// The statistics, or `None` if no trades have been received for `req_id`.
+        pub fn get(&self, req_id: i64) -> Option<&Series> {
+            self.series.get(&req_id)
+        }
+
+        /// Discard the statistics for a single request, so that they restart with its next trade
+        /// (ex. at the start of a new session).
+        ///
+        /// # Returns
+        /// The discarded statistics, if any.
+        pub fn reset(&mut self, req_id: i64) -> Option<Series> {
+            self.series.remove(&req_id)
+        }
+
+        fn add(&mut self, req_id: i64, datetime: DateTime<Utc>, price: f64, size: f64) {
+            self.series
+                .entry(req_id)
+                .or_insert_with(|| Series::new(datetime, price, self.bucket_size, self.window))
+                .add(datetime, price, size);
+        }
+    }
+
+    impl Series {
+        fn new(datetime: DateTime<Utc>, price: f64, bucket_size: f64, window: TimeDelta) -> Self {
+            Self {
+                volume: 0.0,
+                notional: 0.0,
+                high: price,
+                low: price,
+                last: None,
+                first_time: datetime,
+                time_weighted: 0.0,
+                trade_count: 0,
+                bucket_size,
+                window,
+                recent: VecDeque::new(),
+                profile: BTreeMap::new(),
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        fn add(&mut self, datetime: DateTime<Utc>, price: f64, size: f64) {
+            if let Some((last_time, last_price)) = self.last {
+                let elapsed = (datetime - last_time).num_milliseconds().max(0);
+                self.time_weighted += last_price * elapsed as f64;
+            }
+            self.last = Some((datetime, price));
+            self.high = self.high.max(price);
+            self.low = self.low.min(price);
+            self.trade_count += 1;
+            if size <= 0.0 {
+                return;
+            }
+            self.volume += size;
+            self.notional += price * size;
+
+            let level = (price / self.bucket_size).floor() as i64;
+            *self.profile.entry(level).or_insert(0.0) += size;
+            self.recent.push_back((datetime, level, size));
+            while let Some(&(time, level, size)) = self.recent.front() {
+                if datetime - time <= self.window {
+                    break;
+                }
+                self.recent.pop_front();
+                if let Some(volume) = self.profile.get_mut(&level) {
+                    *volume -= size;
+                    if *volume <= f64::EPSILON {
+                        self.profile.remove(&level);
+                    }
+                }
+            }
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the volume-weighted average price, or `None` if no volume has traded.
+        pub fn vwap(&self) -> Option<f64> {
+            (self.volume > 0.0).then(|| self.notional / self.volume)
+        }
+
+        #[must_use]
+        #[allow(clippy::cast_precision_loss)]
+        /// Get the time-weighted average price, in which each trade's price is weighted by the
+        /// time until the next trade.
+        ///
+        /// # Returns
+        /// The average, or the last price if all trades share the same datetime.
+        pub fn twap(&self) -> f64 {
+            let (last_time, last_price) = self.last.unwrap_or((self.first_time, self.low));
+            let elapsed = (last_time - self.first_time).num_milliseconds();
+            if elapsed > 0 {
+                self.time_weighted / elapsed as f64
+            } else {
+                last_price
+            }
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the total traded volume.
+        pub fn volume(&self) -> f64 {
+            self.volume
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the number of trades received.
+        pub fn trade_count(&self) -> u64 {
+            self.trade_count
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the highest traded price.
+        pub fn high(&self) -> f64 {
+            self.high
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the lowest traded price.
+        pub fn low(&self) -> f64 {
+            self.low
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the most recent traded price.
+        pub fn last_price(&self) -> f64 {
+            self.last.map_or(self.low, |(_, price)| price)
+        }
+
+        #[must_use]
+        #[allow(clippy::cast_precision_loss)]
+        /// Get the volume traded at each price level during the rolling window.
+        ///
+        /// # Returns
+        /// The lower bound of each price level and its volume, in ascending order of price.
+        pub fn volume_profile(&self) -> Vec<(f64, f64)> {
+            self.profile
+                .iter()
+                .map(|(&level, &volume)| (level as f64 * self.bucket_size, volume))
+                .collect()
+        }
+    }
+}
+
+#[cfg(feature = "depth")]
+/// Contains a helper that maintains the order books of [`crate::client::Client::req_market_depth`]
+/// requests and samples their top levels, along with the imbalance and microprice, at a fixed
+/// interval.
+pub mod depth {
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, TimeDelta, Utc};
+    use serde::{Deserialize, Serialize};
+
+    use crate::payload::market_depth::{CompleteEntry, Entry, Operation, Row};
+
+    // === Type definitions ===
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    /// A single price level of an [`OrderBook`].
+    pub struct Level {
+        /// The price of the level.
+        pub price: f64,
+        /// The total size resting at the level.
+        pub size: f64,
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+    /// A limit order book maintained from the operations passed to
+    /// [`crate::wrapper::LocalWrapper::update_market_depth`]. Levels are ordered from the best
+    /// price outwards.
+    pub struct OrderBook {
+        bids: Vec<Level>,
+        asks: Vec<Level>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// The top levels of an [`OrderBook`] at a point in time.
+    pub struct Snapshot {
+        /// The time at which the snapshot was taken.
+        pub datetime: DateTime<Utc>,
+        /// The top bid levels, from the highest price down.
+        pub bids: Vec<Level>,
+        /// The top ask levels, from the lowest price up.
+        pub asks: Vec<Level>,
+        /// The imbalance of the sizes of [`Snapshot::bids`] and [`Snapshot::asks`], which is
+        /// between -1 (only asks) and 1 (only bids), or `None` if both sides are empty.
+        pub imbalance: Option<f64>,
+        /// The microprice, which is the average of the best bid and ask prices weighted by the
+        /// size on the opposite side, or `None` if either side is empty.
+        pub microprice: Option<f64>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// Maintains an [`OrderBook`] for each request and takes a [`Snapshot`] of its top levels at
+    /// most once per interval, so that a strategy need not process every depth update.
+    ///
+    /// Pass every [`crate::wrapper::LocalWrapper::update_market_depth`] callback to
+    /// [`Sampler::update_with`], or send them to [`Sampler::forward`], which delivers snapshots on
+    /// a fixed timer. A snapshot is only taken of a book that changed since its last snapshot.
+    pub struct Sampler {
+        levels: usize,
+        interval: TimeDelta,
+        books: HashMap<i64, Book>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Book {
+        book: OrderBook,
+        last_snapshot: Option<DateTime<Utc>>,
+        changed: bool,
+    }
+
+    // === Type implementations ===
+
+    impl OrderBook {
+        /// Apply a single operation to the book.
+        ///
+        /// # Returns
+        /// Whether the book changed.
+        pub fn apply(&mut self, operation: Operation) -> bool {
+            let (Operation::Insert(entry) | Operation::Update(entry) | Operation::Delete(entry)) =
+                operation;
+            let (side, row) = match entry {
+                CompleteEntry::SmartDepth { entry, .. }
+                | CompleteEntry::MarketMaker { entry, .. }
+                | CompleteEntry::Ordinary(entry) => match entry {
+                    Entry::Bid(row) => (&mut self.bids, row),
+                    Entry::Ask(row) => (&mut self.asks, row),
+                },
+            };
+            let Row {
+                position,
+                price,
+                size,
+            } = row;
+            let level = Level { price, size };
+            let position = usize::try_from(position).unwrap_or(usize::MAX);
+            match operation {
+                Operation::Insert(_) => side.insert(position.min(side.len()), level),
+                Operation::Update(_) => match side.get_mut(position) {
+                    Some(current) if *current == level => return false,
+                    Some(current) => *current = level,
+                    // IBKR occasionally updates a row just past the end of the book.
+                    None => side.push(level),
+                },
+                Operation::Delete(_) => {
+                    if position >= side.len() {
+                        return false;
+                    }
+                    side.remove(position);
+                }
+            }
+            true
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get every bid level, from the highest price down.
+        pub fn bids(&self) -> &[Level] {
+            &self.bids
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get every ask level, from the lowest price up.
+        pub fn asks(&self) -> &[Level] {
+            &self.asks
+        }
+
+        #[must_use]
+        /// Get the imbalance of the total bid and ask sizes of the top levels of the book.
+        ///
+        /// # Arguments
+        /// * `levels` - The number of levels on each side to include.
+        ///
+        /// # Returns
+        /// The imbalance, which is between -1 (only asks) and 1 (only bids), or `None` if the top
+        /// levels hold no size.
+        pub fn imbalance(&self, levels: usize) -> Option<f64> {
+            let bid: f64 = self.bids.iter().take(levels).map(|l| l.size).sum();
+            let ask: f64 = self.asks.iter().take(levels).map(|l| l.size).sum();
+            (bid + ask > 0.0).then(|| (bid - ask) / (bid + ask))
+        }
+
+        #[must_use]
+        /// Get the microprice, which is the average of the best bid and ask prices weighted by the
+        /// size on the opposite side.
+        ///
+        /// # Returns
+        /// The microprice, or `None` if either side of the book is empty.
+        pub fn microprice(&self) -> Option<f64> {
+            let (bid, ask) = (self.bids.first()?, self.asks.first()?);
+            (bid.size + ask.size > 0.0)
+                .then(|| (bid.price * ask.size + ask.price * bid.size) / (bid.size + ask.size))
+        }
+
+        #[must_use]
+        /// Take a snapshot of the top levels of the book.
+        ///
+        /// # Arguments
+        /// * `levels` - The number of levels on each side to include.
+        /// * `datetime` - The time at which the snapshot is taken.
+        pub fn snapshot(&self, levels: usize, datetime: DateTime<Utc>) -> Snapshot {
+            Snapshot {
+                datetime,
+                bids: self.bids.iter().take(levels).copied().collect(),
+                asks: self.asks.iter().take(levels).copied().collect(),
+                imbalance: self.imbalance(levels),
+                microprice: self.microprice(),
+            }
+        }
+    }
+
+    impl Sampler {
+        #[must_use]
+        /// Create a new [`Sampler`] with no order books.
+        ///
+        /// # Arguments
+        /// * `levels` - The number of levels on each side of the book to include in each
+        ///   snapshot.
+        /// * `interval` - The minimum time between consecutive snapshots of the same book.
+        ///
+        /// # Returns
+        /// The sampler, or `None` if `levels` or `interval` is not positive.
+        pub fn new(levels: usize, interval: TimeDelta) -> Option<Self> {
+            (levels > 0 && interval > TimeDelta::zero()).then(|| Self {
+                levels,
+                interval,
+                books: HashMap::new(),
+            })
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the number of levels on each side of the book included in each snapshot.
+        pub fn levels(&self) -> usize {
+            self.levels
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the minimum time between consecutive snapshots of the same book.
+        pub fn interval(&self) -> TimeDelta {
+            self.interval
+        }
+
+        /// Apply a market depth update to the order book of its request.
+        ///
+        /// # Returns
+        /// Whether the book changed.
+        pub fn update(&mut self, req_id: i64, operation: Operation) -> bool {
+            let book = self.books.entry(req_id).or_insert_with(|| Book {
+                book: OrderBook::default(),
+                last_snapshot: None,
+                changed: false,
+            });
+            let changed = book.book.apply(operation);
+            book.changed |= changed;
+            changed
+        }
+
+        /// Apply a market depth update and pass a snapshot of the book to a callback if the book
+        /// has changed and at least one interval has passed since its last snapshot.
+        ///
+        /// # Arguments
+        /// * `req_id` - The request ID of the update.
+        /// * `operation` - The update.
+        /// * `now` - The current time.
+        /// * `on_snapshot` - The callback that receives the snapshot.
+        pub fn update_with(
+            &mut self,
+            req_id: i64,
+            operation: Operation,
+            now: DateTime<Utc>,
+            on_snapshot: impl FnOnce(Snapshot),
+        ) {
+            self.update(req_id, operation);
+            let (levels, interval) = (self.levels, self.interval);
+            if let Some(book) = self.books.get_mut(&req_id) {
+                if book.changed && book.last_snapshot.is_none_or(|last| now - last >= interval) {
+                    on_snapshot(book.take_snapshot(levels, now));
+                }
+            }
+        }
+
+        /// Take a snapshot of every book that has changed since its last snapshot.
+        ///
+        /// # Arguments
+        /// * `now` - The time at which the snapshots are taken.
+        ///
+        /// # Returns
+        /// The request ID and snapshot of each changed book.
+        pub fn poll(&mut self, now: DateTime<Utc>) -> Vec<(i64, Snapshot)> {
+            let levels = self.levels;
+            self.books
+                .iter_mut()
+                .filter(|(_, book)| book.changed)
+                .map(|(req_id, book)| (*req_id, book.take_snapshot(levels, now)))
+                .collect()
+        }
+
+        #[must_use]
+        #[inline]
+        /// Get the order book of a single request.
+        ///
+        /// # Returns
+        /// The book, or `None` if no updates have been received for `req_id`.
+        pub fn book(&self, req_id: i64) -> Option<&OrderBook> {
+            self.books.get(&req_id).map(|book| &book.book)
+        }
+
+        /// Discard the order book of a single request, ex. once it has been cancelled.
+        ///
+        /// # Returns
+        /// The discarded book, if any.
+        pub fn remove(&mut self, req_id: i64) -> Option<OrderBook> {
+            self.books.remove(&req_id).map(|book| book.book)
+        }
+
+        /// Apply every update received on `updates` and, once per interval, send a snapshot of
+        /// each book that changed during the interval on `snapshots`.
+        ///
+        /// # Arguments
+        /// * `updates` - The channel on which request IDs and their updates are received.
+        /// * `snapshots` - The channel on which request IDs and their snapshots are sent.
+        ///
+        /// # Returns
+        /// The sampler, once `updates` or `snapshots` is closed.
+        pub async fn forward(
+            mut self,
+            mut updates: tokio::sync::mpsc::Receiver<(i64, Operation)>,
+            snapshots: tokio::sync::mpsc::Sender<(i64, Snapshot)>,
+        ) -> Self {
+            let period = self
+                .interval
+                .to_std()
+                .unwrap_or(std::time::Duration::from_millis(1));
+            let mut timer = tokio::time::interval(period);
+            timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    update = updates.recv() => match update {
+                        Some((req_id, operation)) => {
+                            self.update(req_id, operation);
+                        }
+                        None => return self,
+                    },
+                    _ = timer.tick() => {
+                        for snapshot in self.poll(Utc::now()) {
+                            if snapshots.send(snapshot).await.is_err() {
+                                return self;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    impl Book {
+        fn take_snapshot(&mut self, levels: usize, now: DateTime<Utc>) -> Snapshot {
+            self.changed = false;
+            self.last_snapshot = Some(now);
+            self.book.snapshot(levels, now)
+        }
+    }
+}
+
+#[test]
+fn test_backfill() -> Result<(), backfill::BackfillError> {
+    use chrono::{DateTime, TimeDelta};
+    use historical_bar::{Duration, MinuteSize, SecondSize, Size};
+
+    let end = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let chunks = backfill::chunks(
+        end - TimeDelta::minutes(45),
+        end,
+        Size::Seconds(SecondSize::One),
+    );
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|c| (c.end, c.duration))
+            .collect::<Vec<_>>(),
+        vec![
+            (end - TimeDelta::minutes(30), Duration::Second(900)),
+            (end, Duration::Second(1_800)),
+        ]
+    );
+    let chunks = backfill::chunks(
+        end - TimeDelta::days(10),
+        end,
+        Size::Minutes(MinuteSize::One),
+    );
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].duration, Duration::Day(3));
+    assert_eq!(chunks[1].duration, Duration::Day(7));
+    assert!(backfill::chunks(end, end, Size::Day).is_empty());
+
+    let path = std::env::temp_dir().join(format!("ibapi_backfill_{}.toml", std::process::id()));
+    let mut backfiller = backfill::Backfiller::open(&path)?;
+    let stock: crate::contract::ExchangeProxy<crate::contract::Stock> =
+        toml::from_str(crate::contract::AAPL)?;
+    let key = backfill::Key::new(
+        &stock.inner,
+        Size::Minutes(MinuteSize::One),
+        historical_bar::Trades,
+    );
+    assert_eq!(key.to_string(), "265598:1 min:TRADES");
+    assert_eq!(
+        backfill::Key::new(
+            &stock.inner,
+            Size::Minutes(MinuteSize::One),
+            historical_bar::Data::Trades(historical_bar::Trades),
+        ),
+        key
+    );
+    assert_eq!(backfiller.last(&key), None);
+    backfiller.record(key.clone(), end);
+    backfiller.record(key.clone(), end - TimeDelta::days(1));
+    backfiller.save()?;
+    let reopened = backfill::Backfiller::open(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(reopened.last(&key), Some(end));
+    Ok(())
+}
+
+#[test]
+fn test_aggregate() {
+    use aggregate::{Aggregator, Interval};
+    use chrono::{DateTime, TimeDelta};
+
+    use crate::payload::{Bar, BarCore, Last, Midpoint, TickAttributes, TickData, Trade};
+
+    let start = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let last = |seconds: i64, price: f64, size: f64| {
+        TickData::Last(Last {
+            datetime: start + TimeDelta::seconds(seconds),
+            price,
+            size,
+            exchange: crate::exchange::Primary::Island,
+            attributes: TickAttributes::default(),
+        })
+    };
+
+    assert!(Aggregator::new(Interval::Time(TimeDelta::zero())).is_none());
+    let mut aggregator = Aggregator::new(Interval::Time(TimeDelta::seconds(15))).unwrap();
+    assert_eq!(aggregator.push(&last(1, 100.0, 10.0)), None);
+    assert_eq!(
+        aggregator.push(&TickData::Midpoint(Midpoint {
+            datetime: start,
+            price: 200.0,
+            attributes: TickAttributes::default(),
+        })),
+        None
+    );
+    assert_eq!(aggregator.push(&last(5, 102.0, 10.0)), None);
+    assert_eq!(aggregator.push(&last(14, 99.0, 20.0)), None);
+    assert_eq!(
+        aggregator.push(&last(16, 101.0, 5.0)),
+        Some(Bar::Trades(Trade {
+            bar: BarCore {
+                datetime: start,
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 99.0,
+            },
+            volume: 40.0,
+            wap: 100.0,
+            trade_count: 3,
+        }))
+    );
+    let mut bars = Vec::new();
+    aggregator.push_with(&last(45, 103.0, 5.0), |bar| bars.push(bar));
+    assert_eq!(bars.len(), 1);
+    let Some(Bar::Trades(trade)) = aggregator.flush() else {
+        panic!("Expected a bar in progress");
+    };
+    assert_eq!(trade.bar.datetime, start + TimeDelta::seconds(45));
+    assert_eq!(aggregator.flush(), None);
+
+    let mut aggregator = Aggregator::new(Interval::Volume(25.0)).unwrap();
+    assert_eq!(aggregator.push(&last(1, 100.0, 10.0)), None);
+    let Some(Bar::Trades(trade)) = aggregator.push(&last(2, 101.0, 20.0)) else {
+        panic!("Expected a completed volume bar");
+    };
+    assert_eq!(trade.bar.datetime, start + TimeDelta::seconds(1));
+    assert_eq!(trade.volume, 30.0);
+    assert_eq!(trade.trade_count, 2);
+    assert_eq!(aggregator.flush(), None);
+}
+
+#[test]
+fn test_analytics() {
+    use analytics::Analytics;
+    use chrono::{DateTime, TimeDelta};
+
+    use crate::payload::{Last, TickAttributes, TickData};
+
+    let start = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let last = |seconds: i64, price: f64, size: f64| {
+        TickData::Last(Last {
+            datetime: start + TimeDelta::seconds(seconds),
+            price,
+            size,
+            exchange: crate::exchange::Primary::Island,
+            attributes: TickAttributes::default(),
+        })
+    };
+
+    assert!(Analytics::new(0.0, TimeDelta::minutes(1)).is_none());
+    let mut analytics = Analytics::new(0.5, TimeDelta::seconds(30)).unwrap();
+    assert!(analytics.get(1).is_none());
+    analytics.update_tick(1, &last(0, 100.0, 10.0));
+    analytics.update_tick(1, &last(10, 101.2, 30.0));
+    analytics.update_tick(1, &last(40, 99.0, 10.0));
+    analytics.update_tick(2, &last(0, 50.0, 1.0));
+
+    let series = analytics.get(1).unwrap();
+    assert_eq!(series.trade_count(), 3);
+    assert_eq!(series.volume(), 50.0);
+    assert_eq!(series.high(), 101.2);
+    assert_eq!(series.low(), 99.0);
+    assert_eq!(series.last_price(), 99.0);
+    assert!((series.vwap().unwrap() - 100.52).abs() < 1e-9);
+    assert!((series.twap() - (100.0 * 10.0 + 101.2 * 30.0) / 40.0).abs() < 1e-9);
+    assert_eq!(series.volume_profile(), vec![(99.0, 10.0), (101.0, 30.0)]);
+
+    assert_eq!(analytics.get(2).unwrap().twap(), 50.0);
+    assert!(analytics.reset(1).is_some());
+    assert!(analytics.get(1).is_none());
+}
+
+#[cfg(feature = "depth")]
+#[test]
+fn test_depth() {
+    use chrono::{DateTime, TimeDelta};
+    use depth::{Level, Sampler};
+
+    use crate::payload::market_depth::{CompleteEntry, Entry, Operation, Row};
+
+    let start = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let bid = |position: u64, price: f64, size: f64| {
+        CompleteEntry::Ordinary(Entry::Bid(Row {
+            position,
+            price,
+            size,
+        }))
+    };
+    let ask = |position: u64, price: f64, size: f64| {
+        CompleteEntry::Ordinary(Entry::Ask(Row {
+            position,
+            price,
+            size,
+        }))
+    };
+
+    assert!(Sampler::new(0, TimeDelta::seconds(1)).is_none());
+    let mut sampler = Sampler::new(2, TimeDelta::seconds(1)).unwrap();
+    let mut snapshots = Vec::new();
+    sampler.update_with(1, Operation::Insert(bid(0, 99.0, 100.0)), start, |s| {
+        snapshots.push(s);
+    });
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].microprice, None);
+    assert_eq!(snapshots[0].imbalance, Some(1.0));
+
+    let soon = start + TimeDelta::milliseconds(500);
+    for operation in [
+        Operation::Insert(bid(0, 99.5, 100.0)),
+        Operation::Insert(bid(2, 98.0, 500.0)),
+        Operation::Insert(ask(0, 100.0, 300.0)),
+        Operation::Update(ask(1, 100.5, 300.0)),
+    ] {
+        sampler.update_with(1, operation, soon, |s| snapshots.push(s));
+    }
+    assert_eq!(snapshots.len(), 1);
+    assert!(!sampler.update(1, Operation::Update(ask(1, 100.5, 300.0))));
+    assert!(!sampler.update(1, Operation::Delete(ask(5, 0.0, 0.0))));
+
+    let later = start + TimeDelta::seconds(1);
+    sampler.update_with(1, Operation::Delete(bid(2, 98.0, 500.0)), later, |s| {
+        snapshots.push(s);
+    });
+    let snapshot = &snapshots[1];
+    assert_eq!(snapshot.datetime, later);
+    assert_eq!(
+        snapshot.bids,
+        vec![
+            Level {
+                price: 99.5,
+                size: 100.0
+            },
+            Level {
+                price: 99.0,
+                size: 100.0
+            },
+        ]
+    );
+    assert_eq!(snapshot.asks.len(), 2);
+    assert_eq!(snapshot.imbalance, Some(-0.5));
+    assert_eq!(snapshot.microprice, Some(99.625));
+    assert!(sampler.poll(later).is_empty());
+
+    sampler.update(2, Operation::Insert(ask(0, 10.0, 1.0)));
+    let polled = sampler.poll(later);
+    assert_eq!(polled.len(), 1);
+    assert_eq!(polled[0].0, 2);
+    assert_eq!(sampler.remove(2).map(|b| b.asks().len()), Some(1));
+    assert!(sampler.book(2).is_none());
+    assert_eq!(sampler.book(1).map(|b| b.bids().len()), Some(2));
+}
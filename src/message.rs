@@ -1,6 +1,15 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard};
 
-use crate::contract::{Contract, Query};
+use tokio::sync::{mpsc, watch};
+
+use crate::contract::{Contract, ContractError};
+use crate::frame::Frame;
+use crate::payload::OrderStatus;
+use crate::requests::{Kind, Registry, UnknownPolicy};
+use crate::tick::{Accessibility, Auction, AuctionData, ShortBorrow};
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct InvalidInMsg(pub String);
@@ -369,13 +378,536 @@ pub enum Out {
     ReqUserInfo,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+// Messages from the client to the decoding loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ToWrapper {
-    ContractQuery((Query, i64)),
+    // The tick-by-tick subscription with this request ID asked for historical ticks, so the next
+    // batch of historical ticks with the same ID is its prefix rather than a standalone response.
+    TickByTickHistory(i64),
+    // The option parameters with this request ID are awaited by `contract::option_chain`, so they
+    // are sent to the client rather than the wrapper.
+    AwaitOptionParameters(i64),
+    // The client stopped waiting for the option parameters with this request ID.
+    ReleaseOptionParameters(i64),
+    // The historical ticks with this request ID are awaited by
+    // `historical_ticks::HistoricalTickIterator`, so they are sent to the client rather than the
+    // wrapper.
+    AwaitHistoricalTicks(i64),
+    // The client stopped waiting for the historical ticks with this request ID.
+    ReleaseHistoricalTicks(i64),
+    #[cfg(feature = "news")]
+    // The historical news with this request ID is awaited by `Client::req_historical_news_until`,
+    // so its headlines are sent to the client rather than the wrapper.
+    AwaitHistoricalNews(i64),
+    #[cfg(feature = "news")]
+    // The client stopped waiting for the historical news with this request ID.
+    ReleaseHistoricalNews(i64),
+}
+
+// Responses that the client awaits itself, so that the decoding loop sends them to the client
+// rather than the wrapper. Unlike instructions sent over the channel to the loop, these are
+// registered without waiting for the loop, so a client can await any number of responses at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Awaited {
+    // A head timestamp awaited by `Client::req_head_timestamps`.
+    HeadTimestamp,
+    // The contracts that answer a query sent by `Client::send_contract_query`.
+    ContractData,
+}
+
+// State that is read and written by both the client and the decoding loop.
+#[derive(Debug, Default)]
+pub(crate) struct Shared {
+    order_id: AtomicI64,
+    managed_accounts: RwLock<HashSet<String>>,
+    order_statuses: Mutex<HashMap<i64, watch::Sender<Option<OrderStatus>>>>,
+    requests: Mutex<Registry>,
+    awaited: Mutex<HashSet<(Awaited, i64)>>,
+    retries: Mutex<HashMap<i64, Vec<String>>>,
+    auctions: Mutex<HashMap<i64, Auction>>,
+    short_borrows: Mutex<HashMap<i64, ShortBorrow>>,
+    clock_offset: RwLock<Option<chrono::TimeDelta>>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<crate::metrics::Registry>,
 }
 
-#[allow(clippy::redundant_pub_crate)]
+impl Shared {
+    pub(crate) fn new(order_id: i64, managed_accounts: HashSet<String>) -> Self {
+        Self {
+            order_id: AtomicI64::new(order_id),
+            managed_accounts: RwLock::new(managed_accounts),
+            order_statuses: Mutex::default(),
+            requests: Mutex::default(),
+            awaited: Mutex::default(),
+            retries: Mutex::default(),
+            auctions: Mutex::default(),
+            short_borrows: Mutex::default(),
+            clock_offset: RwLock::default(),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn with_metrics(mut self, metrics: Arc<crate::metrics::Registry>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics(&self) -> &crate::metrics::Registry {
+        &self.metrics
+    }
+
+    pub(crate) fn with_unknown_requests(self, policy: UnknownPolicy) -> Self {
+        *self
+            .requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Registry::new(policy);
+        self
+    }
+
+    pub(crate) fn next_order_id(&self) -> i64 {
+        self.order_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    // Advance the client's order ID counter to a next valid ID reported by the API. The counter
+    // never moves backwards, so IDs that the client has already handed out are not reused.
+    pub(crate) fn sync_order_id(&self, valid_id: i64) {
+        self.order_id.fetch_max(valid_id, Ordering::SeqCst);
+    }
+
+    pub(crate) fn managed_accounts(&self) -> RwLockReadGuard<'_, HashSet<String>> {
+        self.managed_accounts
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Replace the client's set of managed accounts with the accounts reported by the API.
+    pub(crate) fn sync_managed_accounts(&self, accounts: HashSet<String>) {
+        *self
+            .managed_accounts
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = accounts;
+    }
+
+    pub(crate) fn clock_offset(&self) -> Option<chrono::TimeDelta> {
+        *self
+            .clock_offset
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Estimate the offset of the server's clock from the local clock if the message is a current
+    // time reply. The server's time is truncated to the second, so it is taken to be the middle of
+    // its second, and it was read halfway through the round trip if that is known.
+    pub(crate) fn sync_clock(
+        &self,
+        frame: &Frame,
+        received: chrono::DateTime<chrono::Utc>,
+        round_trip: Option<std::time::Duration>,
+    ) {
+        let mut fields = frame.fields();
+        if fields.next().and_then(|t| t.parse().ok()) != Some(In::CurrentTime) {
+            return;
+        }
+        let Some(server) = fields
+            .nth(1)
+            .and_then(|t| t.parse().ok())
+            .and_then(|t| chrono::DateTime::from_timestamp(t, 500_000_000))
+        else {
+            return;
+        };
+        let local = received
+            - round_trip
+                .and_then(|round_trip| chrono::TimeDelta::from_std(round_trip / 2).ok())
+                .unwrap_or_default();
+        *self
+            .clock_offset
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(server - local);
+    }
+
+    // Start publishing the status of an order, which must happen before the order is sent so that
+    // no status is missed.
+    pub(crate) fn track_order(&self, order_id: i64) -> watch::Receiver<Option<OrderStatus>> {
+        let (tx, rx) = watch::channel(None);
+        self.order_statuses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(order_id, tx);
+        rx
+    }
+
+    // Stop publishing the status of an order that was never sent.
+    pub(crate) fn untrack_order(&self, order_id: i64) {
+        self.order_statuses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&order_id);
+    }
+
+    // Publish the latest status of a tracked order. An order stops being tracked once it is no
+    // longer working or nobody is watching it.
+    pub(crate) fn update_order_status(&self, status: &OrderStatus) {
+        let order_id = status.as_core().order_id;
+        let mut order_statuses = self
+            .order_statuses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(tx) = order_statuses.get(&order_id) {
+            tx.send_replace(Some(status.clone()));
+            if crate::order::is_terminal(status) || tx.receiver_count() == 0 {
+                order_statuses.remove(&order_id);
+            }
+        }
+    }
+
+    fn requests(&self) -> MutexGuard<'_, Registry> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Record a request before it is sent, so that its callbacks are matched to it.
+    pub(crate) fn register_request(&self, req_id: i64, kind: Kind) {
+        self.requests().register(req_id, kind);
+    }
+
+    // Record that a request was cancelled, so that any later callbacks are reported as such.
+    pub(crate) fn finish_request(&self, req_id: i64) {
+        self.requests().finish(req_id);
+        self.retries().remove(&req_id);
+        self.auctions().remove(&req_id);
+        self.short_borrows().remove(&req_id);
+    }
+
+    pub(crate) fn outstanding_requests(&self) -> BTreeMap<i64, Kind> {
+        self.requests().outstanding()
+    }
+
+    // Whether an incoming message should be passed to the wrapper, given the policy for callbacks
+    // to unknown or finished requests.
+    pub(crate) fn observe_request(&self, frame: &Frame) -> bool {
+        self.requests().observe(frame)
+    }
+
+    fn retries(&self) -> MutexGuard<'_, HashMap<i64, Vec<String>>> {
+        self.retries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Keep the fields of a market data request, so that it can be sent again with delayed data if
+    // it fails for lack of a subscription.
+    pub(crate) fn cache_retry(&self, req_id: i64, fields: Vec<String>) {
+        self.retries().insert(req_id, fields);
+    }
+
+    // Take the fields of a request kept by `cache_retry`, which is only ever retried once.
+    pub(crate) fn take_retry(&self, req_id: i64) -> Option<Vec<String>> {
+        self.retries().remove(&req_id)
+    }
+
+    fn auctions(&self) -> MutexGuard<'_, HashMap<i64, Auction>> {
+        self.auctions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Fold an auction tick into the latest auction state for its market data request, which is
+    // kept until the request is finished.
+    pub(crate) fn update_auction(&self, req_id: i64, data: AuctionData) -> Auction {
+        let mut auctions = self.auctions();
+        let auction = auctions.entry(req_id).or_default();
+        auction.update(data);
+        *auction
+    }
+
+    fn short_borrows(&self) -> MutexGuard<'_, HashMap<i64, ShortBorrow>> {
+        self.short_borrows
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Fold a shortable tick into the latest stock-loan state for its market data request, which
+    // is kept until the request is finished.
+    pub(crate) fn update_short_borrow(&self, req_id: i64, access: Accessibility) -> ShortBorrow {
+        let mut short_borrows = self.short_borrows();
+        let short_borrow = short_borrows.entry(req_id).or_default();
+        short_borrow.update(access);
+        *short_borrow
+    }
+
+    fn awaited(&self) -> MutexGuard<'_, HashSet<(Awaited, i64)>> {
+        self.awaited.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Record that the client awaits a response to a request, which must happen before the request
+    // is sent so that the response is not passed to the wrapper.
+    pub(crate) fn await_response(&self, awaited: Awaited, req_id: i64) {
+        self.awaited().insert((awaited, req_id));
+    }
+
+    // Whether the client awaits a response.
+    pub(crate) fn awaits_response(&self, awaited: Awaited, req_id: i64) -> bool {
+        self.awaited().contains(&(awaited, req_id))
+    }
+
+    // Stop awaiting a response, returning whether it was awaited.
+    pub(crate) fn release_response(&self, awaited: Awaited, req_id: i64) -> bool {
+        self.awaited().remove(&(awaited, req_id))
+    }
+}
+
+// The decoding loop's end of the channel from the client, along with any state that the loop
+// keeps between messages.
+#[derive(Debug)]
+pub(crate) struct Inbox {
+    rx: mpsc::Receiver<ToWrapper>,
+    shared: Arc<Shared>,
+    combined_quotes: bool,
+    tick_by_tick_history: HashSet<i64>,
+    option_parameters: HashSet<i64>,
+    historical_ticks: HashSet<i64>,
+    #[cfg(feature = "news")]
+    historical_news: HashSet<i64>,
+}
+
+impl Inbox {
+    pub(crate) fn new(rx: mpsc::Receiver<ToWrapper>, shared: Arc<Shared>) -> Self {
+        Self {
+            rx,
+            shared,
+            combined_quotes: false,
+            tick_by_tick_history: HashSet::new(),
+            option_parameters: HashSet::new(),
+            historical_ticks: HashSet::new(),
+            #[cfg(feature = "news")]
+            historical_news: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self) {
+        while let Ok(msg) = self.rx.try_recv() {
+            match msg {
+                ToWrapper::TickByTickHistory(req_id) => {
+                    self.tick_by_tick_history.insert(req_id);
+                }
+                ToWrapper::AwaitOptionParameters(req_id) => {
+                    self.option_parameters.insert(req_id);
+                }
+                ToWrapper::ReleaseOptionParameters(req_id) => {
+                    self.option_parameters.remove(&req_id);
+                }
+                ToWrapper::AwaitHistoricalTicks(req_id) => {
+                    self.historical_ticks.insert(req_id);
+                }
+                ToWrapper::ReleaseHistoricalTicks(req_id) => {
+                    self.historical_ticks.remove(&req_id);
+                }
+                #[cfg(feature = "news")]
+                ToWrapper::AwaitHistoricalNews(req_id) => {
+                    self.historical_news.insert(req_id);
+                }
+                #[cfg(feature = "news")]
+                ToWrapper::ReleaseHistoricalNews(req_id) => {
+                    self.historical_news.remove(&req_id);
+                }
+            }
+        }
+    }
+
+    // Whether a batch of historical ticks is the prefix of a tick-by-tick subscription. Each
+    // subscription has a single prefix, so this returns `true` at most once per request ID.
+    pub(crate) fn take_tick_by_tick_history(&mut self, req_id: i64) -> bool {
+        self.update();
+        self.tick_by_tick_history.remove(&req_id)
+    }
+
+    // Whether the client awaits the head timestamp with this request ID. Each request has a
+    // single response, so this returns `true` at most once per request ID.
+    pub(crate) fn take_awaited_head_timestamp(&self, req_id: i64) -> bool {
+        self.shared.release_response(Awaited::HeadTimestamp, req_id)
+    }
+
+    // Whether the client awaits the contracts with this request ID.
+    pub(crate) fn awaits_contract_data(&self, req_id: i64) -> bool {
+        self.shared.awaits_response(Awaited::ContractData, req_id)
+    }
+
+    // Whether the client awaits the end of the contracts with this request ID. Each query ends
+    // once, so this returns `true` at most once per request ID.
+    pub(crate) fn take_awaited_contract_data(&self, req_id: i64) -> bool {
+        self.shared.release_response(Awaited::ContractData, req_id)
+    }
+
+    // Whether the client awaits the option parameters with this request ID.
+    pub(crate) fn awaits_option_parameters(&mut self, req_id: i64) -> bool {
+        self.update();
+        self.option_parameters.contains(&req_id)
+    }
+
+    // Whether the client awaits the end of the option parameters with this request ID. Each
+    // request ends once, so this returns `true` at most once per request ID.
+    pub(crate) fn take_awaited_option_parameters(&mut self, req_id: i64) -> bool {
+        self.update();
+        self.option_parameters.remove(&req_id)
+    }
+
+    // Whether the client awaits the historical ticks with this request ID. Each request has a
+    // single response, so this returns `true` at most once per request ID.
+    pub(crate) fn take_awaited_historical_ticks(&mut self, req_id: i64) -> bool {
+        self.update();
+        self.historical_ticks.remove(&req_id)
+    }
+
+    #[cfg(feature = "news")]
+    // Whether the client awaits the historical news with this request ID.
+    pub(crate) fn awaits_historical_news(&mut self, req_id: i64) -> bool {
+        self.update();
+        self.historical_news.contains(&req_id)
+    }
+
+    #[cfg(feature = "news")]
+    // Whether the client awaits the end of the historical news with this request ID. Each request
+    // ends once, so this returns `true` at most once per request ID.
+    pub(crate) fn take_awaited_historical_news(&mut self, req_id: i64) -> bool {
+        self.update();
+        self.historical_news.remove(&req_id)
+    }
+
+    pub(crate) fn shared(&self) -> &Shared {
+        &self.shared
+    }
+
+    pub(crate) fn with_combined_quotes(mut self, combined_quotes: bool) -> Self {
+        self.combined_quotes = combined_quotes;
+        self
+    }
+
+    // Whether bid and ask prices are passed to the wrapper together with their sizes.
+    pub(crate) fn combined_quotes(&self) -> bool {
+        self.combined_quotes
+    }
+}
+
+#[allow(clippy::redundant_pub_crate, clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum ToClient {
-    NewContract(Contract),
+    ContractData(i64, Result<Contract, ContractError>),
+    ContractEnd(i64),
+    HeadTimestamp(i64, chrono::DateTime<chrono::Utc>),
+    OptionParameters(i64, crate::payload::OptionParameters),
+    OptionParametersEnd(i64),
+    HistoricalTicks(i64, Vec<crate::payload::TickData>),
+    #[cfg(feature = "news")]
+    HistoricalNews(i64, crate::payload::Headline),
+    #[cfg(feature = "news")]
+    HistoricalNewsEnd(i64, bool),
+}
+
+#[test]
+fn test_inbox_tick_by_tick_history() {
+    let (tx, rx) = mpsc::channel(8);
+    let mut inbox = Inbox::new(rx, Arc::default());
+    tx.try_send(ToWrapper::TickByTickHistory(5)).unwrap();
+    assert!(!inbox.take_tick_by_tick_history(4));
+    assert!(inbox.take_tick_by_tick_history(5));
+    assert!(!inbox.take_tick_by_tick_history(5));
+}
+
+#[test]
+fn test_shared_auction() {
+    let shared = Shared::new(1, HashSet::new());
+    shared.update_auction(1, AuctionData::Volume(1_000.));
+    let auction = shared.update_auction(1, AuctionData::Imbalance(-250.));
+    assert_eq!(auction.matched_quantity, Some(1_000.));
+    assert_eq!(
+        auction.imbalance_side(),
+        Some(crate::tick::ImbalanceSide::Sell)
+    );
+    let auction = shared.update_auction(2, AuctionData::Price(10.));
+    assert_eq!(auction.matched_quantity, None);
+
+    // The state of a cancelled request is dropped.
+    shared.finish_request(1);
+    assert!(!shared.auctions().contains_key(&1));
+    let auction = shared.update_auction(1, AuctionData::Price(10.));
+    assert_eq!(auction.matched_quantity, None);
+}
+
+#[test]
+fn test_shared_sync_order_id() {
+    let shared = Shared::new(10, HashSet::new());
+    shared.sync_order_id(25);
+    assert_eq!(shared.next_order_id(), 25);
+    shared.sync_order_id(12);
+    assert_eq!(shared.next_order_id(), 26);
+}
+
+#[test]
+fn test_shared_sync_clock() {
+    let shared = Shared::new(1, HashSet::new());
+    let received = "2024-01-02T15:00:00Z".parse().unwrap();
+    shared.sync_clock(&Frame::from_fields(&["9", "1", "17"]), received, None);
+    assert_eq!(shared.clock_offset(), None);
+    shared.sync_clock(
+        &Frame::from_fields(&["49", "1", "1704207602"]),
+        received,
+        None,
+    );
+    assert_eq!(
+        shared.clock_offset(),
+        Some(chrono::TimeDelta::milliseconds(2_500))
+    );
+    shared.sync_clock(
+        &Frame::from_fields(&["49", "1", "1704207600"]),
+        received,
+        Some(std::time::Duration::from_secs(1)),
+    );
+    assert_eq!(shared.clock_offset(), Some(chrono::TimeDelta::seconds(1)));
+}
+
+#[test]
+fn test_shared_order_status() {
+    use crate::payload::OrderStatusCore;
+
+    let shared = Shared::new(1, HashSet::new());
+    let rx = shared.track_order(7);
+    let core = OrderStatusCore {
+        order_id: 7,
+        fill: None,
+        remaining: crate::quantity::Quantity::from(100),
+        permanent_id: 42,
+        parent_id: None,
+        client_id: 0,
+        why_held: None,
+        market_cap_price: None,
+    };
+    shared.update_order_status(&OrderStatus::Submitted(core));
+    assert_eq!(*rx.borrow(), Some(OrderStatus::Submitted(core)));
+    shared.update_order_status(&OrderStatus::Cancelled(core));
+    assert_eq!(*rx.borrow(), Some(OrderStatus::Cancelled(core)));
+    shared.update_order_status(&OrderStatus::Submitted(core));
+    assert_eq!(*rx.borrow(), Some(OrderStatus::Cancelled(core)));
+
+    // An untracked order is forgotten.
+    let rx = shared.track_order(8);
+    shared.untrack_order(8);
+    assert!(rx.has_changed().is_err());
+}
+
+#[test]
+fn test_inbox_head_timestamp() {
+    let (tx, rx) = mpsc::channel(8);
+    let shared = Arc::new(Shared::default());
+    let mut inbox = Inbox::new(rx, Arc::clone(&shared));
+    shared.await_response(Awaited::HeadTimestamp, 3);
+    shared.await_response(Awaited::HeadTimestamp, 4);
+    assert!(shared.release_response(Awaited::HeadTimestamp, 4));
+    assert!(inbox.take_awaited_head_timestamp(3));
+    assert!(!inbox.take_awaited_head_timestamp(3));
+    assert!(!inbox.take_awaited_head_timestamp(4));
+
+    tx.try_send(ToWrapper::AwaitHistoricalTicks(5)).unwrap();
+    tx.try_send(ToWrapper::AwaitHistoricalTicks(6)).unwrap();
+    tx.try_send(ToWrapper::ReleaseHistoricalTicks(6)).unwrap();
+    assert!(inbox.take_awaited_historical_ticks(5));
+    assert!(!inbox.take_awaited_historical_ticks(5));
+    assert!(!inbox.take_awaited_historical_ticks(6));
 }
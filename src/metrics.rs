@@ -0,0 +1,491 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::frame::Frame;
+use crate::message::In;
+
+// The number of most recent round trips per request type from which percentiles are computed.
+const LATENCY_SAMPLES: usize = 1024;
+// The time after which a request that has not been answered is no longer timed, so that requests
+// that never receive a response do not accumulate.
+const PENDING_TIMEOUT: Duration = Duration::from_secs(600);
+// The number of message identifiers that are counted without taking a lock. Every identifier of
+// an incoming message is below this.
+const MESSAGE_IDS: usize = 256;
+
+type LatencyCallback = Box<dyn Fn(&'static str, Duration) + Send + Sync>;
+
+// Requests that have been sent but not yet answered, keyed by request ID or order ID, which are
+// counted separately by the client.
+#[derive(Debug, Default)]
+struct Pending {
+    requests: BTreeMap<i64, (&'static str, Instant)>,
+    orders: BTreeMap<i64, (&'static str, Instant)>,
+    swept: Option<Instant>,
+}
+
+impl Pending {
+    // Start timing a request, first dropping the requests that have waited longer than
+    // `PENDING_TIMEOUT` if they have not been checked within the timeout.
+    fn insert(&mut self, id: i64, request: &'static str, order: bool, now: Instant) {
+        if self
+            .swept
+            .is_none_or(|swept| now.duration_since(swept) >= PENDING_TIMEOUT)
+        {
+            let fresh = |(_, sent): &mut (&'static str, Instant)| {
+                now.duration_since(*sent) < PENDING_TIMEOUT
+            };
+            self.requests.retain(|_, pending| fresh(pending));
+            self.orders.retain(|_, pending| fresh(pending));
+            self.swept = Some(now);
+        }
+        let pending = if order {
+            &mut self.orders
+        } else {
+            &mut self.requests
+        };
+        pending.insert(id, (request, now));
+    }
+
+    fn len(&self) -> usize {
+        self.requests.len() + self.orders.len()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    count: u64,
+    recent: VecDeque<Duration>,
+}
+
+impl Samples {
+    fn push(&mut self, latency: Duration) {
+        self.count += 1;
+        if self.recent.len() == LATENCY_SAMPLES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(latency);
+    }
+
+    fn summary(&self) -> Latency {
+        let mut sorted = self.recent.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        let percentile = |p: usize| {
+            sorted
+                .get(sorted.len().saturating_sub(1) * p / 100)
+                .copied()
+                .unwrap_or_default()
+        };
+        Latency {
+            count: self.count,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The time between sending requests of one type and receiving the first message with the same
+/// request ID (or order ID, for orders).
+///
+/// The percentiles and maximum are computed from the most recent 1024 requests of the type. A
+/// request that is not answered within ten minutes is no longer timed.
+pub struct Latency {
+    /// The number of requests of this type that received a response.
+    pub count: u64,
+    /// The median latency.
+    pub p50: Duration,
+    /// The 90th percentile latency.
+    pub p90: Duration,
+    /// The 99th percentile latency.
+    pub p99: Duration,
+    /// The largest latency.
+    pub max: Duration,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A point-in-time copy of the counters maintained by the client loop.
+pub struct Snapshot {
+    /// The number of incoming messages received, keyed by the API's message identifier.
+    pub messages: BTreeMap<String, u64>,
+    /// The number of incoming messages that could not be decoded.
+    pub decode_failures: u64,
+    /// The number of messages waiting in the reader queue the last time a message was taken
+    /// from it.
+    pub queue_depth: usize,
+    /// The largest reader queue depth observed.
+    pub max_queue_depth: usize,
+    /// The latency of each type of request that carries a request ID or order ID, keyed by the
+    /// name of the outgoing message (ex. "ReqMktData").
+    pub latencies: BTreeMap<String, Latency>,
+}
+
+impl Snapshot {
+    #[must_use]
+    #[inline]
+    /// Return the total number of incoming messages received across all message types.
+    pub fn total_messages(&self) -> u64 {
+        self.messages.values().sum()
+    }
+}
+
+/// The counters and request latencies of a single client, which are maintained by its client
+/// loop and returned by [`crate::client::Client::metrics`].
+pub struct Registry {
+    messages: [AtomicU64; MESSAGE_IDS],
+    // Messages whose identifier is not a number below `MESSAGE_IDS`, which are not expected.
+    other_messages: Mutex<BTreeMap<String, u64>>,
+    decode_failures: AtomicU64,
+    queue_depth: AtomicUsize,
+    max_queue_depth: AtomicUsize,
+    pending: Mutex<Pending>,
+    // The number of requests in `pending`, so that incoming messages are only matched against
+    // them while there are any.
+    pending_len: AtomicUsize,
+    latencies: Mutex<BTreeMap<&'static str, Samples>>,
+    latency_callback: RwLock<Option<LatencyCallback>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            messages: std::array::from_fn(|_| AtomicU64::new(0)),
+            other_messages: Mutex::default(),
+            decode_failures: AtomicU64::new(0),
+            queue_depth: AtomicUsize::new(0),
+            max_queue_depth: AtomicUsize::new(0),
+            pending: Mutex::default(),
+            pending_len: AtomicUsize::new(0),
+            latencies: Mutex::default(),
+            latency_callback: RwLock::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry")
+            .field("decode_failures", &self.decode_failures)
+            .field("queue_depth", &self.queue_depth)
+            .field("max_queue_depth", &self.max_queue_depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Registry {
+    #[must_use]
+    /// Return a copy of the current values of all counters.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut messages = self
+            .other_messages
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for (id, count) in self.messages.iter().enumerate() {
+            let count = count.load(Ordering::Relaxed);
+            if count > 0 {
+                messages.insert(id.to_string(), count);
+            }
+        }
+        Snapshot {
+            messages,
+            decode_failures: self.decode_failures.load(Ordering::Relaxed),
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+            max_queue_depth: self.max_queue_depth.load(Ordering::Relaxed),
+            latencies: self
+                .latencies
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .map(|(request, samples)| ((*request).to_owned(), samples.summary()))
+                .collect(),
+        }
+    }
+
+    /// Call a function with the name of the outgoing message (ex. "ReqMktData") and the latency
+    /// each time a request receives its first response. This replaces any previously installed
+    /// callback.
+    ///
+    /// The callback is called from the client loop, so it should return quickly.
+    ///
+    /// # Arguments
+    /// * `callback` - The function to call.
+    pub fn on_latency<F: Fn(&'static str, Duration) + Send + Sync + 'static>(&self, callback: F) {
+        *self
+            .latency_callback
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(callback));
+    }
+
+    /// Remove the callback installed with [`Registry::on_latency`].
+    pub fn clear_latency_callback(&self) {
+        *self
+            .latency_callback
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Reset all counters to zero.
+    pub fn reset(&self) {
+        for count in &self.messages {
+            count.store(0, Ordering::Relaxed);
+        }
+        self.other_messages
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.decode_failures.store(0, Ordering::Relaxed);
+        self.queue_depth.store(0, Ordering::Relaxed);
+        self.max_queue_depth.store(0, Ordering::Relaxed);
+        *self.pending.lock().unwrap_or_else(PoisonError::into_inner) = Pending::default();
+        self.pending_len.store(0, Ordering::Relaxed);
+        self.latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    #[inline]
+    pub(crate) fn record_message(&self, message_id: &str) {
+        match message_id
+            .parse::<usize>()
+            .ok()
+            .and_then(|id| self.messages.get(id))
+        {
+            Some(count) => {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                *self
+                    .other_messages
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .entry(message_id.to_owned())
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn record_decode_failure(&self) {
+        self.decode_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn record_queue_depth(&self, depth: usize) {
+        self.queue_depth.store(depth, Ordering::Relaxed);
+        self.max_queue_depth.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    #[inline]
+    // Start timing an outgoing message, given its null-terminated fields without the length
+    // prefix, if it is a request whose response can be timed.
+    pub(crate) fn record_request(&self, msg: &[u8]) {
+        let mut fields = msg
+            .split(|&b| b == 0)
+            .map(|field| std::str::from_utf8(field).unwrap_or_default());
+        let Some((request, index, order)) = fields.next().and_then(timed_request) else {
+            return;
+        };
+        let Some(id) = fields.nth(index - 1).and_then(|id| id.parse().ok()) else {
+            return;
+        };
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        pending.insert(id, request, order, Instant::now());
+        self.pending_len.store(pending.len(), Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn record_response(&self, msg: &Frame) {
+        if self.pending_len.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let Some((id, order)) = response_id(msg) else {
+            return;
+        };
+        let Some((request, sent)) = ({
+            let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+            let sent = match order {
+                Some(true) => pending.orders.remove(&id),
+                Some(false) => pending.requests.remove(&id),
+                None => pending
+                    .requests
+                    .remove(&id)
+                    .or_else(|| pending.orders.remove(&id)),
+            };
+            self.pending_len.store(pending.len(), Ordering::Relaxed);
+            sent
+        }) else {
+            return;
+        };
+        let latency = sent.elapsed();
+        self.latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(request)
+            .or_default()
+            .push(latency);
+        if let Some(callback) = self
+            .latency_callback
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            callback(request, latency);
+        }
+    }
+}
+
+/// For outgoing messages whose response can be timed, returns the name of the message, the index
+/// of its request ID or order ID, and whether it is an order.
+fn timed_request(message_id: &str) -> Option<(&'static str, usize, bool)> {
+    Some(match message_id {
+        "1" => ("ReqMktData", 2, false),
+        "3" => ("PlaceOrder", 1, true),
+        "7" => ("ReqExecutions", 2, false),
+        "9" => ("ReqContractData", 2, false),
+        "10" => ("ReqMktDepth", 2, false),
+        "20" => ("ReqHistoricalData", 1, false),
+        "50" => ("ReqRealTimeBars", 2, false),
+        "62" => ("ReqAccountSummary", 2, false),
+        "74" => ("ReqPositionsMulti", 2, false),
+        "76" => ("ReqAccountUpdatesMulti", 2, false),
+        "83" => ("ReqSmartComponents", 1, false),
+        "87" => ("ReqHeadTimestamp", 1, false),
+        "88" => ("ReqHistogramData", 1, false),
+        "92" => ("ReqPnl", 1, false),
+        "94" => ("ReqPnlSingle", 1, false),
+        "96" => ("ReqHistoricalTicks", 1, false),
+        "97" => ("ReqTickByTickData", 1, false),
+        "104" => ("ReqUserInfo", 1, false),
+        _ => return None,
+    })
+}
+
+/// For incoming messages that answer a request, returns the request ID or order ID and whether
+/// it may be an order ID. Errors may refer to either.
+fn response_id(msg: &Frame) -> Option<(i64, Option<bool>)> {
+    let mut fields = msg.fields();
+    let (index, order) = match fields.next()?.parse().ok()? {
+        In::OrderStatus | In::OpenOrder => (0, Some(true)),
+        In::ErrMsg => (1, None),
+        In::TickPrice
+        | In::TickSize
+        | In::MarketDepthL2
+        | In::TickGeneric
+        | In::TickString
+        | In::RealTimeBars
+        | In::ContractDataEnd
+        | In::ExecutionDataEnd
+        | In::TickSnapshotEnd
+        | In::MarketDataType
+        | In::AccountSummary
+        | In::AccountSummaryEnd
+        | In::PositionMulti
+        | In::PositionMultiEnd
+        | In::AccountUpdateMulti
+        | In::AccountUpdateMultiEnd => (1, Some(false)),
+        In::ContractData
+        | In::ExecutionData
+        | In::MarketDepth
+        | In::HistoricalData
+        | In::TickOptionComputation
+        | In::TickReqParams
+        | In::SmartComponents
+        | In::HeadTimestamp
+        | In::HistogramData
+        | In::HistoricalDataUpdate
+        | In::Pnl
+        | In::PnlSingle
+        | In::HistoricalTicks
+        | In::HistoricalTicksBidAsk
+        | In::HistoricalTicksLast
+        | In::TickByTick
+        | In::HistoricalSchedule
+        | In::UserInfo => (0, Some(false)),
+        _ => return None,
+    };
+    Some((fields.nth(index)?.parse().ok()?, order))
+}
+
+#[test]
+fn test_metrics() {
+    let registry = Registry::default();
+    registry.record_message("4");
+    registry.record_message("4");
+    registry.record_message("unexpected");
+    registry.record_decode_failure();
+    registry.record_queue_depth(3);
+    registry.record_queue_depth(1);
+    let snapshot = registry.snapshot();
+    assert_eq!(snapshot.messages.get("4"), Some(&2));
+    assert_eq!(snapshot.messages.get("unexpected"), Some(&1));
+    assert_eq!(snapshot.total_messages(), 3);
+    assert_eq!(snapshot.decode_failures, 1);
+    assert_eq!((snapshot.queue_depth, snapshot.max_queue_depth), (1, 3));
+
+    // Each client has its own registry.
+    assert_eq!(Registry::default().snapshot(), Snapshot::default());
+    registry.reset();
+    assert_eq!(registry.snapshot(), Snapshot::default());
+}
+
+#[test]
+fn test_latency() {
+    let registry = Registry::default();
+    let (tx, rx) = std::sync::mpsc::channel();
+    registry.on_latency(move |request, _| {
+        let _ = tx.send(request);
+    });
+    registry.record_request(b"1\x0011\x001\x000\x00");
+    registry.record_request(b"3\x001\x000\x00");
+    // Requests without a response are not timed.
+    registry.record_request(b"49\x001\x00");
+    registry.record_response(&Frame::from_fields(&["1", "6", "1", "1", "1.5"]));
+    registry.record_response(&Frame::from_fields(&["4", "2", "1", "201", "Rejected"]));
+    // Only the first response is timed.
+    registry.record_response(&Frame::from_fields(&["1", "6", "1", "1", "1.5"]));
+
+    let snapshot = registry.snapshot();
+    assert_eq!(snapshot.latencies["ReqMktData"].count, 1);
+    assert_eq!(snapshot.latencies["PlaceOrder"].count, 1);
+    let latency = snapshot.latencies["ReqMktData"];
+    assert!(latency.p50 <= latency.p99 && latency.p99 <= latency.max);
+    assert_eq!(
+        rx.try_iter().collect::<Vec<_>>(),
+        ["ReqMktData", "PlaceOrder"]
+    );
+}
+
+#[test]
+fn test_pending_timeout() {
+    let now = Instant::now();
+    let mut pending = Pending::default();
+    pending.insert(1, "ReqMktData", false, now);
+    pending.insert(2, "PlaceOrder", true, now + PENDING_TIMEOUT / 2);
+    assert_eq!(pending.len(), 2);
+    // Only the requests that have waited longer than the timeout are dropped.
+    pending.insert(3, "ReqMktData", false, now + PENDING_TIMEOUT);
+    assert_eq!(pending.requests.keys().collect::<Vec<_>>(), [&3]);
+    assert_eq!(pending.orders.keys().collect::<Vec<_>>(), [&2]);
+}
+
+#[test]
+fn test_latency_summary() {
+    let mut samples = Samples::default();
+    for ms in (1..=100).rev() {
+        samples.push(Duration::from_millis(ms));
+    }
+    let latency = samples.summary();
+    assert_eq!(latency.count, 100);
+    assert_eq!(latency.p50, Duration::from_millis(50));
+    assert_eq!(latency.p90, Duration::from_millis(90));
+    assert_eq!(latency.p99, Duration::from_millis(99));
+    assert_eq!(latency.max, Duration::from_millis(100));
+}
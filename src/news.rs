@@ -0,0 +1,248 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::contract::ContractId;
+use crate::payload::Headline;
+
+/// The maximum number of headlines that IBKR returns for a single
+/// [`crate::client::Client::req_historical_news`] request.
+pub const MAX_HEADLINES: u16 = 300;
+
+// The format of the start and end of a historical news request.
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S.0";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A news provider, identified by the code that IBKR uses for it.
+///
+/// The providers to which an account is subscribed are listed by TWS. Providers without a
+/// dedicated variant can be used with [`Provider::Unknown`].
+pub enum Provider {
+    /// Briefing.com General Market Columns.
+    BriefingGeneral,
+    /// Briefing.com Analyst Actions.
+    BriefingAnalystActions,
+    /// Dow Jones Newsletters.
+    DowJonesNewsletters,
+    /// Benzinga Pro.
+    Benzinga,
+    /// The Fly.
+    TheFly,
+    /// A code without a dedicated variant.
+    Unknown(String),
+}
+
+impl Provider {
+    #[must_use]
+    #[inline]
+    /// Return the code that IBKR uses for this provider.
+    pub fn code(&self) -> &str {
+        match self {
+            Self::BriefingGeneral => "BRFG",
+            Self::BriefingAnalystActions => "BRFUPDN",
+            Self::DowJonesNewsletters => "DJNL",
+            Self::Benzinga => "BZ",
+            Self::TheFly => "FLY",
+            Self::Unknown(code) => code,
+        }
+    }
+}
+
+impl FromStr for Provider {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "BRFG" => Self::BriefingGeneral,
+            "BRFUPDN" => Self::BriefingAnalystActions,
+            "DJNL" => Self::DowJonesNewsletters,
+            "BZ" => Self::Benzinga,
+            "FLY" => Self::TheFly,
+            s => Self::Unknown(s.to_owned()),
+        })
+    }
+}
+
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for Provider {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Provider {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Headline {
+    #[must_use]
+    #[inline]
+    /// Return the provider of the headline.
+    pub fn provider(&self) -> Provider {
+        let Ok(provider) = self.provider_code.parse();
+        provider
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The parameters of a [`crate::client::Client::req_historical_news`] request.
+///
+/// IBKR returns at most [`MAX_HEADLINES`] headlines per request, starting with the most recent.
+/// Older headlines are requested page by page, by continuing each request with
+/// [`Query::next_page`] while IBKR reports that more headlines are available.
+pub struct Query {
+    contract_id: ContractId,
+    providers: Vec<Provider>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    max_headlines: u16,
+}
+
+impl Query {
+    #[must_use]
+    #[inline]
+    /// Create a new [`Query`] for the most recent headlines about a contract.
+    ///
+    /// # Arguments
+    /// * `contract_id` - The ID of the contract whose news to request.
+    /// * `providers` - The providers whose headlines to include.
+    pub fn new(contract_id: ContractId, providers: impl IntoIterator<Item = Provider>) -> Self {
+        Self {
+            contract_id,
+            providers: providers.into_iter().collect(),
+            start: None,
+            end: None,
+            max_headlines: MAX_HEADLINES,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Only request headlines published after `start`.
+    ///
+    /// # Arguments
+    /// * `start` - The exclusive start of the requested period.
+    pub const fn with_start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Only request headlines published before `end`.
+    ///
+    /// # Arguments
+    /// * `end` - The end of the requested period.
+    pub const fn with_end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Limit the number of headlines returned by each request. Limits outside of `1` to
+    /// [`MAX_HEADLINES`] are clamped to that range.
+    ///
+    /// # Arguments
+    /// * `max_headlines` - The maximum number of headlines.
+    pub fn with_max_headlines(mut self, max_headlines: u16) -> Self {
+        self.max_headlines = max_headlines.clamp(1, MAX_HEADLINES);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the exclusive start of the requested period, if any.
+    pub const fn start(&self) -> Option<DateTime<Utc>> {
+        self.start
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the end of the requested period, if any.
+    pub const fn end(&self) -> Option<DateTime<Utc>> {
+        self.end
+    }
+
+    #[must_use]
+    /// Return the query for the page of headlines that precedes `page`, which ends at the
+    /// earliest headline of `page`.
+    ///
+    /// Headlines published at that time may be returned again, so they should be told apart by
+    /// their [`Headline::article_id`].
+    ///
+    /// # Arguments
+    /// * `page` - The headlines returned by the previous request.
+    ///
+    /// # Returns
+    /// The next query, or [`None`] if `page` is empty or reaches the start of the requested
+    /// period.
+    pub fn next_page(&self, page: &[Headline]) -> Option<Self> {
+        let earliest = page.iter().map(|headline| headline.time).min()?;
+        if self.start.is_some_and(|start| earliest <= start) {
+            return None;
+        }
+        Some(self.clone().with_end(earliest))
+    }
+
+    pub(crate) fn as_out_msg(&self) -> impl Serialize + '_ {
+        let providers = self
+            .providers
+            .iter()
+            .map(Provider::code)
+            .collect::<Vec<_>>()
+            .join("+");
+        let format = |datetime: Option<DateTime<Utc>>| {
+            datetime.map_or_else(String::new, |d| d.format(DATETIME_FORMAT).to_string())
+        };
+        (
+            self.contract_id,
+            providers,
+            format(self.start),
+            format(self.end),
+            self.max_headlines,
+            "",
+        )
+    }
+}
+
+#[test]
+fn test_query() {
+    let start = DateTime::from_timestamp(1_704_067_200, 0).unwrap();
+    let query = Query::new(
+        ContractId(265_598),
+        [Provider::BriefingGeneral, "DJ-N".parse().unwrap()],
+    )
+    .with_start(start)
+    .with_max_headlines(1_000);
+    assert_eq!(query.max_headlines, MAX_HEADLINES);
+
+    let headline = |seconds, article_id: &str| Headline {
+        time: DateTime::from_timestamp(seconds, 0).unwrap(),
+        provider_code: "BRFG".to_owned(),
+        article_id: article_id.to_owned(),
+        headline: String::new(),
+    };
+    let page = [headline(1_704_240_000, "a"), headline(1_704_153_600, "b")];
+    assert_eq!(page[0].provider(), Provider::BriefingGeneral);
+    let next = query.next_page(&page).unwrap();
+    assert_eq!(next.end(), Some(page[1].time));
+    assert_eq!(next.start(), Some(start));
+    assert_eq!(query.next_page(&[]), None);
+    assert_eq!(next.next_page(&[headline(1_704_067_200, "c")]), None);
+}
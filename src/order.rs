@@ -1,11 +1,29 @@
 use std::collections::HashMap;
 use std::fmt::Formatter;
+use std::marker::PhantomData;
 use std::str::FromStr;
 
+use chrono::{DateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
 use serde::{Serialize, Serializer};
 use serde::ser::SerializeTuple;
+use thiserror::Error;
+use tokio::sync::watch;
 
-use crate::contract::{Commodity, Crypto, Forex, Index, SecFuture, SecOption, Security, Stock};
+use crate::client::indicators::Active;
+use crate::client::Client;
+
+use crate::contract::{
+    Commodity, ContractId, ContractType, Crypto, Forex, Index, InvalidPrice, SecFuture, SecOption,
+    Security, Stock,
+};
+use crate::currency::Currency;
+use crate::exchange::{Primary, Routing};
+use crate::payload::OrderStatus;
+use crate::quantity::Quantity;
+
+// The order types that IBKR accepts for fractional quantities of stocks.
+const FRACTIONAL_ORDER_TYPES: [&str; 4] = ["MKT", "LMT", "STP", "STP LMT"];
 
 // ==============================================
 // === Core Order Types (Market, Limit, etc.) ===
@@ -13,37 +31,46 @@ use crate::contract::{Commodity, Crypto, Forex, Index, SecFuture, SecOption, Sec
 
 // === Type definitions ===
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 /// The time periods for which an order is active and can be executed against.
 pub enum TimeInForce {
     #[default]
-    #[serde(rename(serialize = "DAY"))]
     /// Valid for the day only.
     Day,
-    #[serde(rename(serialize = "GTC"))]
     /// Good until canceled. The order will continue to work within the system and in the marketplace until it executes or is canceled. GTC orders will be automatically be cancelled under the following conditions:
     /// If a corporate action on a security results in a stock split (forward or reverse), exchange for shares, or distribution of shares. If you do not log into your IB account for 90 days.
     /// At the end of the calendar quarter following the current quarter. For example, an order placed during the third quarter of 2011 will be canceled at the end of the first quarter of 2012. If the last day is a non-trading day, the cancellation will occur at the close of the final trading day of that quarter. For example, if the last day of the quarter is Sunday, the orders will be cancelled on the preceding Friday.
     /// Orders that are modified will be assigned a new “Auto Expire” date consistent with the end of the calendar quarter following the current quarter.
     /// Orders submitted to IB that remain in force for more than one day will not be reduced for dividends. To allow adjustment to your order price on ex-dividend date, consider using a Good-Til-Date/Time (GTD) or Good-after-Time/Date (GAT) order type, or a combination of the two.
     Gtc,
-    #[serde(rename(serialize = "IOC"))]
     /// Immediate or Cancel. Any portion that is not filled as soon as it becomes available in the market is canceled.
     Ioc,
-    // #[serde(rename(serialize="GTD"))]
-    // /// Good until Date. It will remain working within the system and in the marketplace until it executes or until the close of the market on the date specified
-    // Gtd,
-    // #[serde(rename(serialize="OPG"))]
-    // /// Use OPG to send a market-on-open (MOO) or limit-on-open (LOO) order.
-    // Opg,
-    #[serde(rename(serialize = "FOK"))]
+    /// Good until Date. It will remain working within the system and in the marketplace until it executes or until the close of the market on the date and time specified.
+    GoodTillDate(DateTime<Tz>),
+    /// Good after Time. The order is held until the date and time specified, after which it is
+    /// valid for the day.
+    GoodAfterTime(DateTime<Tz>),
+    /// Use OPG to send a market-on-open (MOO) or limit-on-open (LOO) order.
+    Opg,
+    /// Participate in the pre-market opening auction, as with an [`AtAuction`] order.
+    Auction,
     /// If the entire Fill-or-Kill order does not execute as soon as it becomes available, the entire order is canceled.
     Fok,
-    #[serde(rename(serialize = "DTC"))]
     /// Day until canceled.
     Dtc,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+/// The action to take on an option position with [`crate::client::Client::req_exercise_options`].
+pub enum ExerciseAction {
+    #[serde(rename(serialize = "1"))]
+    /// Exercise the options.
+    Exercise,
+    #[serde(rename(serialize = "2"))]
+    /// Let the options lapse.
+    Lapse,
+}
+
 #[derive(Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 /// A basic error type that represents an invalid [`TimeInForce`]
 pub struct ParseTimeInForceError(String);
@@ -56,6 +83,67 @@ impl std::fmt::Display for ParseTimeInForceError {
 
 impl std::error::Error for ParseTimeInForceError {}
 
+impl TimeInForce {
+    #[inline]
+    const fn code(self) -> &'static str {
+        match self {
+            Self::Day => "DAY",
+            Self::Gtc => "GTC",
+            Self::Ioc => "IOC",
+            Self::Fok => "FOK",
+            Self::Dtc => "DTC",
+            Self::Opg => "OPG",
+            Self::Auction => "AUC",
+            Self::GoodTillDate(_) => "GTD",
+            Self::GoodAfterTime(_) => "DAY",
+        }
+    }
+
+    #[inline]
+    // The code of the time in force in a security's list of supported order types.
+    const fn order_type_code(self) -> &'static str {
+        match self {
+            Self::GoodAfterTime(_) => "GAT",
+            _ => self.code(),
+        }
+    }
+
+    #[inline]
+    fn good_after_time(self) -> Option<String> {
+        match self {
+            Self::GoodAfterTime(datetime) => Some(format_datetime(datetime)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn good_till_date(self) -> Option<String> {
+        match self {
+            Self::GoodTillDate(datetime) => Some(format_datetime(datetime)),
+            _ => None,
+        }
+    }
+}
+
+// Format a datetime as `yyyyMMdd HH:mm:ss {Timezone}`, as expected by TWS.
+#[inline]
+fn format_datetime(datetime: DateTime<Tz>) -> String {
+    format!(
+        "{} {}",
+        datetime.format("%Y%m%d %H:%M:%S"),
+        datetime.timezone().name()
+    )
+}
+
+impl Serialize for TimeInForce {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.code())
+    }
+}
+
 impl FromStr for TimeInForce {
     type Err = ParseTimeInForceError;
 
@@ -64,9 +152,10 @@ impl FromStr for TimeInForce {
             "DAY" => Self::Day,
             "GTC" => Self::Gtc,
             "IOC" => Self::Ioc,
-            // "GTD" => Self::Gtd,
             "FOK" => Self::Fok,
             "DTC" => Self::Dtc,
+            "OPG" => Self::Opg,
+            "AUC" => Self::Auction,
             _ => return Err(ParseTimeInForceError(s.to_owned())),
         })
     }
@@ -128,26 +217,529 @@ impl<S: Security, E: Executable<S>> Order<'_, S, E> {
             Self::Buy { execute_method, .. } | Self::Sell { execute_method, .. } => execute_method,
         }
     }
+
+    /// Check the order against the details of its security before it is sent to the API.
+    ///
+    /// The checks are necessarily a subset of those made by IBKR's trading systems, so an order
+    /// that passes may still be rejected. In particular, exchange-specific size increments and
+    /// market rules are not available to the client and are not checked.
+    ///
+    /// # Errors
+    /// Returns the first problem found with the order: a quantity that is not positive or has more
+    /// decimal places than [`ContractType::max_quantity_decimals`] allows, a cash quantity that is
+    /// not positive or is combined with a nonzero quantity, a fractional quantity of a [`Stock`]
+    /// with an order type that does not support fractional shares, a volatility that is not
+    /// positive, a limit or auxiliary price that is not a multiple of the security's minimum tick,
+    /// an order type or time in force that the security does not support, a routing exchange that
+    /// is not one of the security's valid exchanges, or [`OrderAttributes`] that are not accepted
+    /// for the order.
+    pub fn validate(&self) -> Result<(), OrderValidationError> {
+        let (security, exec) = (self.get_security(), self.get_execute_method());
+
+        let (quantity, contract_type) = (exec.get_quantity(), security.contract_type());
+        let too_precise = contract_type
+            .max_quantity_decimals()
+            .is_some_and(|max| quantity.decimal_places() > max);
+        if let Some(cash_quantity) = exec.get_cash_quantity() {
+            if !cash_quantity.is_finite() || cash_quantity <= 0.0 || !quantity.is_zero() {
+                return Err(OrderValidationError::InvalidCashQuantity(cash_quantity));
+            }
+        } else if !quantity.is_positive() || too_precise {
+            return Err(OrderValidationError::InvalidQuantity(quantity));
+        }
+        let order_type = exec.get_order_type();
+        if contract_type == ContractType::Stock
+            && !quantity.is_integer()
+            && !FRACTIONAL_ORDER_TYPES.contains(&order_type)
+        {
+            return Err(OrderValidationError::UnsupportedFractionalOrderType(
+                order_type,
+            ));
+        }
+
+        if let Some(volatility) = exec.get_volatility_quote() {
+            if !volatility.is_finite() || volatility <= 0.0 {
+                return Err(OrderValidationError::InvalidVolatility(volatility));
+            }
+        }
+        for price in [exec.get_limit_price(), exec.get_auxiliary_price()]
+            .into_iter()
+            .flatten()
+        {
+            security.check_price(price)?;
+        }
+
+        // An empty list means that the security's supported order types are unknown.
+        let order_types = security.order_types();
+        let supports = |code: &str| order_types.is_empty() || order_types.iter().any(|t| t == code);
+        if !supports(order_type) {
+            return Err(OrderValidationError::UnsupportedOrderType(order_type));
+        }
+        let time_in_force = exec.get_time_in_force();
+        let crypto_market = security.contract_type() == ContractType::Crypto && order_type == "MKT";
+        if (crypto_market && time_in_force != TimeInForce::Ioc)
+            || (time_in_force != TimeInForce::Day && !supports(time_in_force.order_type_code()))
+        {
+            return Err(OrderValidationError::UnsupportedTimeInForce {
+                order_type,
+                time_in_force,
+            });
+        }
+
+        let exchange = security.as_out_msg().exchange;
+        let valid_exchanges = security.valid_exchanges();
+        if !valid_exchanges.is_empty() && !valid_exchanges.contains(&exchange) {
+            return Err(OrderValidationError::UnsupportedExchange(exchange));
+        }
+
+        let hidden = exec.get_is_hidden_on_nasdaq_market_depth();
+        let nasdaq = matches!(
+            exchange,
+            Routing::Primary(Primary::Island | Primary::NationalAssociationOfSecurityDealers)
+        );
+        for (attribute, set, accepted) in [
+            (
+                "outside_rth",
+                exec.get_can_fill_outside_regular_trading_hours(),
+                !(contract_type == ContractType::Stock && order_type == "MKT"),
+            ),
+            (
+                "hidden",
+                hidden,
+                order_type == "LMT" && nasdaq && supports("HID"),
+            ),
+            (
+                "sweep_to_fill",
+                exec.get_is_sweep_to_fill(),
+                order_type == "LMT" && exchange == Routing::Smart && supports("SWEEP"),
+            ),
+            (
+                "block_order",
+                exec.get_is_block_order(),
+                contract_type == ContractType::SecOption
+                    && exchange == Routing::Primary(Primary::InternationalSecuritiesExchange),
+            ),
+            (
+                "adaptive_priority",
+                exec.get_algo_strategy() == Some(AlgoStrategy::Adaptive),
+                exchange == Routing::Smart && matches!(order_type, "MKT" | "LMT"),
+            ),
+        ] {
+            if set && !accepted {
+                return Err(OrderValidationError::UnsupportedAttribute {
+                    attribute,
+                    order_type,
+                });
+            }
+        }
+        let display_size = exec.get_iceberg_order_size();
+        if display_size > 0 {
+            if order_type != "LMT" || !supports("ICE") {
+                return Err(OrderValidationError::UnsupportedAttribute {
+                    attribute: "display_size",
+                    order_type,
+                });
+            }
+            if hidden || Quantity::from(display_size) >= quantity {
+                return Err(OrderValidationError::InvalidDisplaySize(display_size));
+            }
+        }
+        let discretionary_amount = exec.get_discretionary_amount();
+        if discretionary_amount != 0.0 {
+            if order_type != "LMT" {
+                return Err(OrderValidationError::UnsupportedAttribute {
+                    attribute: "discretionary_amount",
+                    order_type,
+                });
+            }
+            if !discretionary_amount.is_finite() || discretionary_amount < 0.0 {
+                return Err(OrderValidationError::InvalidDiscretionaryAmount(
+                    discretionary_amount,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the order as with [`Order::validate`], and also check that an auction order is
+    /// submitted at `datetime` before the cutoff of its auction.
+    ///
+    /// The cutoffs are only known for US stocks, which are given by
+    /// [`Auction::us_equity_cutoff`], so the time of auction orders for other securities is not
+    /// checked.
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Order::validate`], or
+    /// [`OrderValidationError::AuctionCutoff`] if `datetime` falls between the auction cutoff and
+    /// the auction.
+    pub fn validate_at<Tz: TimeZone>(
+        &self,
+        datetime: DateTime<Tz>,
+    ) -> Result<(), OrderValidationError> {
+        self.validate()?;
+        let (security, exec) = (self.get_security(), self.get_execute_method());
+        let Some(auction) = exec.get_auction() else {
+            return Ok(());
+        };
+        if security.contract_type() == ContractType::Stock
+            && security.currency() == Currency::UsDollar
+        {
+            let (cutoff, start) = auction.us_equity_cutoff();
+            let time = datetime.with_timezone(&chrono_tz::America::New_York).time();
+            if (cutoff..start).contains(&time) {
+                return Err(OrderValidationError::AuctionCutoff {
+                    order_type: exec.get_order_type(),
+                    auction,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    // The body of the message that places, or modifies, the order with the given ID.
+    pub(crate) fn as_out_msg(&self, id: i64) -> impl Serialize + '_ {
+        (
+            crate::message::Out::PlaceOrder,
+            id,
+            self.get_security().as_out_msg(),
+            None::<()>,
+            None::<()>,
+            self,
+        )
+    }
+
+    /// Return the fields of the message that [`Client::req_place_order`] or
+    /// [`Client::req_modify_order`] would send for the order, without sending it.
+    ///
+    /// The fields are exactly those written to the socket, in order and without their NUL
+    /// terminators, so that order construction can be unit tested and compared against the
+    /// messages of the official API when debugging a rejected order.
+    ///
+    /// # Arguments
+    /// * `id` - The ID with which the order would be placed or modified.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`std::io::ErrorKind::InvalidData`] if the order cannot be
+    /// serialized.
+    pub fn to_wire_fields(&self, id: i64) -> Result<Vec<String>, std::io::Error> {
+        Ok(crate::comm::fields(self.as_out_msg(id))?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+/// An error type representing the ways that [`Order::validate`] can reject an order.
+pub enum OrderValidationError {
+    /// The quantity is not positive, or has more decimal places than the security accepts.
+    #[error("Invalid order quantity {0}.")]
+    InvalidQuantity(Quantity),
+    /// The cash quantity is not positive, or the order also has a nonzero quantity.
+    #[error("Invalid order cash quantity {0}.")]
+    InvalidCashQuantity(f64),
+    /// The quantity of a stock is fractional, but the order type does not support fractional
+    /// shares.
+    #[error("Order type {0} does not support fractional shares.")]
+    UnsupportedFractionalOrderType(&'static str),
+    /// A limit or auxiliary price is not a multiple of the security's minimum tick.
+    #[error("{0}")]
+    InvalidPrice(#[from] InvalidPrice),
+    /// The security does not support the order type.
+    #[error("Order type {0} is not supported by the security.")]
+    UnsupportedOrderType(&'static str),
+    /// The time in force is not supported for the order type and security.
+    #[error("Time in force {time_in_force:?} is not supported for a {order_type} order on the security.")]
+    UnsupportedTimeInForce {
+        /// The order type
+        order_type: &'static str,
+        /// The time in force
+        time_in_force: TimeInForce,
+    },
+    /// The security's routing exchange is not one of its valid exchanges.
+    #[error("Exchange {0:?} is not one of the security's valid exchanges.")]
+    UnsupportedExchange(Routing),
+    /// An [`OrderAttributes`] instruction is not accepted for the order type, security, or
+    /// routing exchange.
+    #[error("The {attribute} attribute is not supported for a {order_type} order on the security and exchange.")]
+    UnsupportedAttribute {
+        /// The name of the attribute
+        attribute: &'static str,
+        /// The order type
+        order_type: &'static str,
+    },
+    /// The display size of an iceberg order is not positive, is not less than the order quantity,
+    /// or is set on a hidden order.
+    #[error("Invalid iceberg display size {0}.")]
+    InvalidDisplaySize(u64),
+    /// The discretionary amount is negative or not finite.
+    #[error("Invalid discretionary amount {0}.")]
+    InvalidDiscretionaryAmount(f64),
+    /// The volatility of a volatility order is not positive.
+    #[error("Invalid volatility {0}.")]
+    InvalidVolatility(f64),
+    /// The auction order was submitted after the cutoff for its auction.
+    #[error("{order_type} orders are not accepted between the {auction} auction cutoff and the auction.")]
+    AuctionCutoff {
+        /// The order type
+        order_type: &'static str,
+        /// The auction
+        auction: Auction,
+    },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// Instructions that modify how a [`Market`] or [`Limit`] order is displayed and executed.
+///
+/// IBKR accepts each attribute for only some order types and exchanges, which
+/// [`Order::validate`] checks.
+pub struct OrderAttributes {
+    /// Allow the order to trigger or fill outside of regular trading hours. Not accepted for
+    /// market orders on stocks.
+    pub outside_rth: bool,
+    /// Hide the order from the market depth. Only accepted for limit orders routed directly to
+    /// NASDAQ.
+    pub hidden: bool,
+    /// The publicly displayed size of an iceberg order, which must be positive and less than the
+    /// order quantity. Only accepted for limit orders.
+    pub display_size: Option<u64>,
+    /// Fill the order immediately against all available liquidity, across price levels. Only
+    /// accepted for limit orders with SMART routing.
+    pub sweep_to_fill: bool,
+    /// Send the order as an ISE block order. Only accepted for options routed directly to ISE.
+    pub block_order: bool,
+    /// Route the order with IBKR's Adaptive algorithm, which works the order between the bid and
+    /// ask with the given priority. Only accepted for market and limit orders with SMART routing.
+    pub adaptive_priority: Option<AdaptivePriority>,
+    /// Give the broker time and price discretion when working the order, which may not be held
+    /// to the current quote. Used for orders routed to IBDARK.
+    pub not_held: bool,
+    /// The amount by which the order may fill beyond its displayed limit price. Only accepted for
+    /// limit orders, and must not be negative.
+    pub discretionary_amount: Option<f64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+/// The priority with which IBKR's Adaptive algorithm works an order, trading off the speed of the
+/// fill against its price.
+pub enum AdaptivePriority {
+    /// Fill the order quickly, at the expense of price.
+    Urgent,
+    #[default]
+    /// Balance the speed of the fill against its price.
+    Normal,
+    /// Wait for a better price, at the expense of speed.
+    Patient,
+}
+
+impl AdaptivePriority {
+    #[inline]
+    const fn code(self) -> &'static str {
+        match self {
+            Self::Urgent => "Urgent",
+            Self::Normal => "Normal",
+            Self::Patient => "Patient",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// A market order: Buy or sell at the best available price for a given quantity. Sensitive to price fluctuations.
 pub struct Market {
     /// The number of shares/units to execute.
-    pub quantity: f64,
+    pub quantity: Quantity,
+    /// The amount of the security's currency to spend or receive instead of a number of
+    /// shares/units, in which case `quantity` must be zero. Cash quantity orders are accepted for
+    /// stocks that trade in fractional shares, crypto, and forex.
+    pub cash_quantity: Option<f64>,
     /// The time for which the order will remain valid
     pub time_in_force: TimeInForce,
+    /// Additional instructions for how the order is displayed and executed.
+    pub attributes: OrderAttributes,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// A market order: Buy or sell at a price as good or better than the limit price. May not be filled.
 pub struct Limit {
     /// The number of shares/units to buy.
-    pub quantity: f64,
+    pub quantity: Quantity,
+    /// The amount of the security's currency to spend or receive instead of a number of
+    /// shares/units, in which case `quantity` must be zero. Cash quantity orders are accepted for
+    /// stocks that trade in fractional shares, crypto, and forex.
+    pub cash_quantity: Option<f64>,
     /// The limit price, which sets the upper / lower bound on the price per unit.
     pub price: f64,
     /// The time for which the order will remain valid
     pub time_in_force: TimeInForce,
+    /// Additional instructions for how the order is displayed and executed.
+    pub attributes: OrderAttributes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A midprice order: Buy or sell at the midpoint of the NBBO or better, subject to an optional
+/// price cap. Only available for stocks with SMART routing.
+pub struct MidPrice {
+    /// The number of shares to execute.
+    pub quantity: Quantity,
+    /// The highest price to pay when buying or the lowest price to accept when selling. If
+    /// [`None`], the order may fill at any midpoint.
+    pub price_cap: Option<f64>,
+    /// The time for which the order will remain valid
+    pub time_in_force: TimeInForce,
+    /// Additional instructions for how the order is displayed and executed.
+    pub attributes: OrderAttributes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A relative (pegged-to-primary) order: Buy or sell at a price that tracks the NBBO, offset to
+/// be more aggressive than the bid when buying or the ask when selling, subject to an optional
+/// price cap.
+pub struct Relative {
+    /// The number of shares/units to execute.
+    pub quantity: Quantity,
+    /// The amount by which the order improves on the bid when buying or the ask when selling. An
+    /// offset of zero pegs the order to the bid or ask.
+    pub offset: f64,
+    /// The highest price to pay when buying or the lowest price to accept when selling. If
+    /// [`None`], the order tracks the bid or ask without limit.
+    pub price_cap: Option<f64>,
+    /// The time for which the order will remain valid
+    pub time_in_force: TimeInForce,
+    /// Additional instructions for how the order is displayed and executed.
+    pub attributes: OrderAttributes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A market-on-close (MOC) order: Buy or sell at the official closing price in the closing
+/// auction.
+pub struct MarketOnClose {
+    /// The number of shares/units to execute.
+    pub quantity: Quantity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A limit-on-close (LOC) order: Buy or sell at the official closing price in the closing auction,
+/// if the closing price is as good or better than the limit price.
+pub struct LimitOnClose {
+    /// The number of shares/units to execute.
+    pub quantity: Quantity,
+    /// The limit price, which sets the upper / lower bound on the closing price.
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A market-on-open (MOO) order: Buy or sell at the official opening price in the opening
+/// auction.
+pub struct MarketOnOpen {
+    /// The number of shares/units to execute.
+    pub quantity: Quantity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A limit-on-open (LOO) order: Buy or sell at the official opening price in the opening auction,
+/// if the opening price is as good or better than the limit price.
+pub struct LimitOnOpen {
+    /// The number of shares/units to execute.
+    pub quantity: Quantity,
+    /// The limit price, which sets the upper / lower bound on the opening price.
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An at-auction order: Buy or sell at the calculated opening price in the pre-market opening
+/// auction. Any quantity that is not filled at the open becomes a limit order at the opening
+/// price.
+pub struct AtAuction {
+    /// The number of shares/units to execute.
+    pub quantity: Quantity,
+    /// The limit price, which sets the upper / lower bound on the opening price.
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The auctions in which auction orders like [`MarketOnClose`] participate.
+pub enum Auction {
+    /// The auction that sets the opening price of the trading day.
+    Opening,
+    /// The auction that sets the closing price of the trading day.
+    Closing,
+}
+
+impl Auction {
+    #[must_use]
+    #[inline]
+    /// Return the period before the auction of a US stock, in US Eastern time, during which IBKR
+    /// does not accept new auction orders.
+    ///
+    /// # Returns
+    /// The time at which orders stop being accepted and the time of the auction.
+    pub fn us_equity_cutoff(self) -> (NaiveTime, NaiveTime) {
+        match self {
+            Self::Opening => (
+                NaiveTime::from_hms_opt(9, 28, 0).unwrap_or_default(),
+                NaiveTime::from_hms_opt(9, 30, 0).unwrap_or_default(),
+            ),
+            Self::Closing => (
+                NaiveTime::from_hms_opt(15, 50, 0).unwrap_or_default(),
+                NaiveTime::from_hms_opt(16, 0, 0).unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Auction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Opening => "opening",
+            Self::Closing => "closing",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A volatility (VOL) order: Buy or sell an option at a limit price that TWS calculates from a
+/// volatility, and optionally hedge the resulting delta.
+pub struct Volatility {
+    /// The number of contracts to execute.
+    pub quantity: Quantity,
+    /// The volatility from which the limit price is calculated, as a percent.
+    pub volatility: f64,
+    /// Whether the volatility is daily or annualized.
+    pub volatility_type: VolatilityType,
+    /// Update the limit price as the price of the underlying moves.
+    pub continuous_update: bool,
+    /// The price of the underlying that is used to calculate the limit price.
+    pub reference_price_type: ReferencePriceType,
+    /// The order with which to hedge the delta of each execution, if any.
+    pub hedge: Option<DeltaNeutralHedge>,
+    /// The time for which the order will remain valid
+    pub time_in_force: TimeInForce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An order that TWS submits to hedge the delta of each execution of a [`Volatility`] order.
+pub struct DeltaNeutralHedge {
+    /// The type of the hedge order.
+    pub order_type: DeltaNeutralOrderType,
+    /// The contract with which to hedge. If [`None`], TWS hedges with the option's underlying.
+    pub contract_id: Option<ContractId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The types of order with which a [`DeltaNeutralHedge`] is submitted.
+pub enum DeltaNeutralOrderType {
+    /// A market order.
+    Market,
+    /// A relative order with the given offset from the bid or ask.
+    Relative(f64),
+}
+
+impl DeltaNeutralOrderType {
+    #[inline]
+    const fn code(self) -> &'static str {
+        match self {
+            Self::Market => "MKT",
+            Self::Relative(_) => "REL",
+        }
+    }
 }
 
 // ==================================================
@@ -156,7 +748,7 @@ pub struct Limit {
 
 /// Represents the data that will be serialized for BAG contracts (which are not currently supported).
 pub type BagRequestContent<'a> = (u64, &'a str, u64, &'a str, u64, HashMap<&'a str, &'a str>);
-/// Represents the data that will be serialized for delta neutral orders (which are not currently implemented).
+/// Represents the data that will be serialized for the delta neutral hedge of a [`Volatility`] order.
 pub type DeltaNeutralOrderContent<'a> =
     (i64, &'a str, &'a str, &'a str, &'a str, bool, i64, &'a str);
 /// Represents the data that will be serialized for scale orders (which are not currently implemented).
@@ -169,7 +761,7 @@ pub type OrderConditionsContent<'a> = (usize, HashMap<&'a str, &'a str>, bool, b
 /// if a type `O` implements [`Executable<S>`], then `O` is a valid order for `S`.
 pub trait Executable<S: Security>: Send + Sync {
     /// Return the total number of contracts being bought/sold.
-    fn get_quantity(&self) -> f64;
+    fn get_quantity(&self) -> Quantity;
 
     /// Return the order's type
     fn get_order_type(&self) -> &'static str;
@@ -190,6 +782,12 @@ pub trait Executable<S: Security>: Send + Sync {
     /// Return the order's time in force, which specifies how long the order will be active.
     fn get_time_in_force(&self) -> TimeInForce;
 
+    #[inline]
+    /// Return the auction in which the order participates, if any.
+    fn get_auction(&self) -> Option<Auction> {
+        None
+    }
+
     #[inline]
     /// Return the One-Cancels-All group identifier.
     fn get_one_cancels_all_group(&self) -> Option<&str> {
@@ -277,7 +875,7 @@ pub trait Executable<S: Security>: Send + Sync {
 
     #[inline]
     /// Return the BAG request and combo leg content, if it exists.
-    fn get_bag_request_content(&self) -> ConditionalField<(), BagRequestContent> {
+    fn get_bag_request_content(&self) -> ConditionalField<(), BagRequestContent<'_>> {
         ConditionalField::default()
     }
 
@@ -290,7 +888,8 @@ pub trait Executable<S: Security>: Send + Sync {
     #[inline]
     /// Return the date and time after which the order will be active.
     ///
-    /// Format: `yyyymmdd hh:mm:ss {optional Timezone}`.
+    /// Format: `yyyymmdd hh:mm:ss {optional Timezone}`. Ignored if the time in force is
+    /// [`TimeInForce::GoodAfterTime`].
     fn get_good_after_time(&self) -> Option<&str> {
         None
     }
@@ -299,7 +898,8 @@ pub trait Executable<S: Security>: Send + Sync {
     /// Return the date and time until the order will be active.
     ///
     /// You must enter GTD as the time in force to use this string. The trade's "Good Till Date,"
-    /// format "`yyyyMMdd HH:mm:ss` (optional time zone)" or UTC "yyyyMMdd-HH:mm:ss".
+    /// format "`yyyyMMdd HH:mm:ss` (optional time zone)" or UTC "yyyyMMdd-HH:mm:ss". Ignored if
+    /// the time in force is [`TimeInForce::GoodTillDate`], which sets the date directly.
     fn get_good_until_date(&self) -> Option<&str> {
         None
     }
@@ -358,7 +958,6 @@ pub trait Executable<S: Security>: Send + Sync {
     /// Return the auction's starting price.
     ///
     /// For BOX orders only.
-
     fn get_box_starting_price(&self) -> Option<f64> {
         None
     }
@@ -442,7 +1041,7 @@ pub trait Executable<S: Security>: Send + Sync {
 
     #[inline]
     /// Return the delta neutral order content if it exists.
-    fn get_delta_neutral_order_content(&self) -> ConditionalField<(), DeltaNeutralOrderContent> {
+    fn get_delta_neutral_order_content(&self) -> ConditionalField<(), DeltaNeutralOrderContent<'_>> {
         ConditionalField::default()
     }
 
@@ -655,7 +1254,7 @@ pub trait Executable<S: Security>: Send + Sync {
 
     #[inline]
     /// Return order conditions content.
-    fn get_order_conditions_content(&self) -> ConditionalField<usize, OrderConditionsContent> {
+    fn get_order_conditions_content(&self) -> ConditionalField<usize, OrderConditionsContent<'_>> {
         ConditionalField::Missing(0)
     }
 
@@ -733,9 +1332,10 @@ pub trait Executable<S: Security>: Send + Sync {
     }
 
     #[inline]
-    /// Return the cash quantity
-    fn get_cash_quantity(&self) -> f64 {
-        f64::MAX
+    /// Return the amount of the security's currency to spend or receive, for a cash quantity
+    /// order.
+    fn get_cash_quantity(&self) -> Option<f64> {
+        None
     }
 
     #[inline]
@@ -869,8 +1469,19 @@ where
     ser.serialize_element(&exec.get_bag_request_content())?;
     ser.serialize_element(&None::<()>)?;
     ser.serialize_element(&exec.get_discretionary_amount())?;
-    ser.serialize_element(&exec.get_good_after_time())?;
-    ser.serialize_element(&exec.get_good_until_date())?;
+    let time_in_force = exec.get_time_in_force();
+    ser.serialize_element(
+        &time_in_force
+            .good_after_time()
+            .as_deref()
+            .or(exec.get_good_after_time()),
+    )?;
+    ser.serialize_element(
+        &time_in_force
+            .good_till_date()
+            .as_deref()
+            .or(exec.get_good_until_date()),
+    )?;
     ser.serialize_element(&[None::<()>; 3])?;
     ser.serialize_element(&exec.get_model_code())?;
     ser.serialize_element(&0)?;
@@ -1122,6 +1733,8 @@ pub enum ClearingIntent {
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, Serialize)]
 /// The potential execution algorithms for algo orders.
 pub enum AlgoStrategy {
+    /// Adaptive algorithm, which is configured with an [`AdaptivePriority`].
+    Adaptive,
     #[serde(rename(serialize = "ArrivalPx"))]
     /// Arrival price algorithm.
     ArrivalPrice,
@@ -1166,6 +1779,52 @@ impl<T: Default, U> Default for ConditionalField<T, U> {
     }
 }
 
+// The `Executable` methods of an order type with an `attributes: OrderAttributes` field.
+macro_rules! impl_attributes {
+    () => {
+        fn get_can_fill_outside_regular_trading_hours(&self) -> bool {
+            self.attributes.outside_rth
+        }
+
+        fn get_is_hidden_on_nasdaq_market_depth(&self) -> bool {
+            self.attributes.hidden
+        }
+
+        fn get_iceberg_order_size(&self) -> u64 {
+            self.attributes.display_size.unwrap_or(0)
+        }
+
+        fn get_is_sweep_to_fill(&self) -> bool {
+            self.attributes.sweep_to_fill
+        }
+
+        fn get_is_block_order(&self) -> bool {
+            self.attributes.block_order
+        }
+
+        fn get_algo_strategy(&self) -> Option<AlgoStrategy> {
+            self.attributes.adaptive_priority.map(|_| AlgoStrategy::Adaptive)
+        }
+
+        fn get_algo_strategy_content(&self) -> ConditionalField<(), (u64, HashMap<&str, &str>)> {
+            self.attributes
+                .adaptive_priority
+                .map_or(ConditionalField::default(), |priority| {
+                    let params = HashMap::from([("adaptivePriority", priority.code())]);
+                    ConditionalField::Present((1, params))
+                })
+        }
+
+        fn get_is_not_held(&self) -> bool {
+            self.attributes.not_held
+        }
+
+        fn get_discretionary_amount(&self) -> f64 {
+            self.attributes.discretionary_amount.unwrap_or(0.0)
+        }
+    };
+}
+
 macro_rules! impl_executable {
     ($o_name: ident; $($s_name: ident),*; $executable_impl: tt) => {
         $(
@@ -1176,10 +1835,14 @@ macro_rules! impl_executable {
 }
 
 impl_executable!(Market; Forex, Crypto, Stock, Index, SecFuture, SecOption, Commodity; {
-    fn get_quantity(&self) -> f64 {
+    fn get_quantity(&self) -> Quantity {
         self.quantity
     }
 
+    fn get_cash_quantity(&self) -> Option<f64> {
+        self.cash_quantity
+    }
+
     fn get_order_type(&self) -> &'static str {
         "MKT"
     }
@@ -1187,12 +1850,18 @@ impl_executable!(Market; Forex, Crypto, Stock, Index, SecFuture, SecOption, Comm
     fn get_time_in_force(&self) -> TimeInForce {
         self.time_in_force
     }
+
+    impl_attributes!();
 });
 impl_executable!(Limit; Forex, Crypto, Stock, Index, SecFuture, SecOption, Commodity; {
-    fn get_quantity(&self) -> f64 {
+    fn get_quantity(&self) -> Quantity {
         self.quantity
     }
 
+    fn get_cash_quantity(&self) -> Option<f64> {
+        self.cash_quantity
+    }
+
     fn get_order_type(&self) -> &'static str {
         "LMT"
     }
@@ -1201,7 +1870,983 @@ impl_executable!(Limit; Forex, Crypto, Stock, Index, SecFuture, SecOption, Commo
         self.time_in_force
     }
 
+    impl_attributes!();
+
+    fn get_limit_price(&self) -> Option<f64> {
+        Some(self.price)
+    }
+});
+impl_executable!(MarketOnClose; Stock, SecFuture; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "MOC"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        TimeInForce::Day
+    }
+
+    fn get_auction(&self) -> Option<Auction> {
+        Some(Auction::Closing)
+    }
+});
+impl_executable!(LimitOnClose; Stock, SecFuture; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "LOC"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        TimeInForce::Day
+    }
+
+    fn get_auction(&self) -> Option<Auction> {
+        Some(Auction::Closing)
+    }
+
     fn get_limit_price(&self) -> Option<f64> {
         Some(self.price)
     }
 });
+impl_executable!(MarketOnOpen; Stock, SecOption; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "MKT"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        TimeInForce::Opg
+    }
+
+    fn get_auction(&self) -> Option<Auction> {
+        Some(Auction::Opening)
+    }
+});
+impl_executable!(LimitOnOpen; Stock, SecOption; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "LMT"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        TimeInForce::Opg
+    }
+
+    fn get_auction(&self) -> Option<Auction> {
+        Some(Auction::Opening)
+    }
+
+    fn get_limit_price(&self) -> Option<f64> {
+        Some(self.price)
+    }
+});
+impl_executable!(AtAuction; Stock, SecFuture; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "MTL"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        TimeInForce::Auction
+    }
+
+    fn get_auction(&self) -> Option<Auction> {
+        Some(Auction::Opening)
+    }
+
+    fn get_limit_price(&self) -> Option<f64> {
+        Some(self.price)
+    }
+});
+impl_executable!(Volatility; SecOption; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "VOL"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        self.time_in_force
+    }
+
+    fn get_volatility_quote(&self) -> Option<f64> {
+        Some(self.volatility)
+    }
+
+    fn get_volatility_type(&self) -> Option<VolatilityType> {
+        Some(self.volatility_type)
+    }
+
+    fn get_delta_neutral_order_type(&self) -> Option<&str> {
+        self.hedge.map(|hedge| hedge.order_type.code())
+    }
+
+    fn get_delta_neutral_auxiliary_price(&self) -> Option<f64> {
+        match self.hedge?.order_type {
+            DeltaNeutralOrderType::Relative(offset) => Some(offset),
+            DeltaNeutralOrderType::Market => None,
+        }
+    }
+
+    fn get_delta_neutral_order_content(&self) -> ConditionalField<(), DeltaNeutralOrderContent<'_>> {
+        self.hedge.map_or(ConditionalField::default(), |hedge| {
+            let contract_id = hedge.contract_id.map_or(0, |contract_id| contract_id.0);
+            ConditionalField::Present((contract_id, "", "", "", "", false, 0, ""))
+        })
+    }
+
+    fn get_continuous_update(&self) -> bool {
+        self.continuous_update
+    }
+
+    fn get_reference_price_type(&self) -> Option<ReferencePriceType> {
+        Some(self.reference_price_type)
+    }
+});
+impl_executable!(MidPrice; Stock; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "MIDPRICE"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        self.time_in_force
+    }
+
+    impl_attributes!();
+
+    fn get_limit_price(&self) -> Option<f64> {
+        self.price_cap
+    }
+});
+impl_executable!(Relative; Stock, SecFuture, SecOption; {
+    fn get_quantity(&self) -> Quantity {
+        self.quantity
+    }
+
+    fn get_order_type(&self) -> &'static str {
+        "REL"
+    }
+
+    fn get_time_in_force(&self) -> TimeInForce {
+        self.time_in_force
+    }
+
+    impl_attributes!();
+
+    fn get_limit_price(&self) -> Option<f64> {
+        self.price_cap
+    }
+
+    fn get_auxiliary_price(&self) -> Option<f64> {
+        Some(self.offset)
+    }
+});
+
+// ===========================
+// === Placed Order States ===
+// ===========================
+
+/// Whether an order status means that the order is no longer working.
+pub(crate) fn is_terminal(status: &OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::Filled(_)
+            | OrderStatus::Cancelled(_)
+            | OrderStatus::ApiCancelled(_)
+            | OrderStatus::Inactive(_)
+    )
+}
+
+/// The states through which a [`PlacedOrder`] moves.
+pub mod state {
+    mod sealed {
+        pub trait Sealed {}
+    }
+
+    /// A state of a [`super::PlacedOrder`].
+    pub trait State: sealed::Sealed + Send + Sync {}
+
+    /// A state in which a [`super::PlacedOrder`] may still be modified, cancelled or filled.
+    pub trait Working: State {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// The order has been sent, but nothing has been filled.
+    pub struct Submitted;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// Some, but not all, of the order has been filled.
+    pub struct PartiallyFilled;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// The order has been completely filled.
+    pub struct Filled;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// The order was cancelled, rejected or otherwise made inactive.
+    pub struct Cancelled;
+
+    impl sealed::Sealed for Submitted {}
+    impl sealed::Sealed for PartiallyFilled {}
+    impl sealed::Sealed for Filled {}
+    impl sealed::Sealed for Cancelled {}
+
+    impl State for Submitted {}
+    impl State for PartiallyFilled {}
+    impl State for Filled {}
+    impl State for Cancelled {}
+
+    impl Working for Submitted {}
+    impl Working for PartiallyFilled {}
+}
+
+#[derive(Debug, Clone)]
+/// An order that has been placed with [`Client::req_place_order`], whose state `S` follows the
+/// order status messages received for it.
+///
+/// The status of the order is still delivered to [`crate::wrapper::LocalWrapper::order_status`];
+/// a [`PlacedOrder`] only observes it.
+pub struct PlacedOrder<S: state::State> {
+    id: i64,
+    status: watch::Receiver<Option<OrderStatus>>,
+    state: PhantomData<S>,
+}
+
+#[derive(Debug, Clone)]
+/// A [`PlacedOrder`] in whichever state its latest status indicates.
+pub enum Lifecycle {
+    /// The order has been sent, but nothing has been filled.
+    Submitted(PlacedOrder<state::Submitted>),
+    /// Some, but not all, of the order has been filled.
+    PartiallyFilled(PlacedOrder<state::PartiallyFilled>),
+    /// The order has been completely filled.
+    Filled(PlacedOrder<state::Filled>),
+    /// The order was cancelled, rejected or otherwise made inactive.
+    Cancelled(PlacedOrder<state::Cancelled>),
+}
+
+impl<S: state::State> PlacedOrder<S> {
+    #[inline]
+    #[must_use]
+    /// Get the order's ID.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the latest status received for the order, if any.
+    pub fn status(&self) -> Option<OrderStatus> {
+        self.status.borrow().clone()
+    }
+
+    fn into_state<T: state::State>(self) -> PlacedOrder<T> {
+        PlacedOrder {
+            id: self.id,
+            status: self.status,
+            state: PhantomData,
+        }
+    }
+
+    fn into_lifecycle(self) -> Lifecycle {
+        let status = self.status();
+        match status {
+            Some(OrderStatus::Filled(_)) => Lifecycle::Filled(self.into_state()),
+            Some(ref status) if is_terminal(status) => Lifecycle::Cancelled(self.into_state()),
+            Some(ref status) if status.as_core().fill.is_some_and(|f| f.filled.is_positive()) => {
+                Lifecycle::PartiallyFilled(self.into_state())
+            }
+            _ => Lifecycle::Submitted(self.into_state()),
+        }
+    }
+}
+
+impl PlacedOrder<state::Submitted> {
+    pub(crate) fn new(id: i64, status: watch::Receiver<Option<OrderStatus>>) -> Self {
+        Self {
+            id,
+            status,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<S: state::Working> PlacedOrder<S> {
+    #[must_use]
+    /// Move the order into the state indicated by its latest status.
+    pub fn refresh(self) -> Lifecycle {
+        self.into_lifecycle()
+    }
+
+    /// Wait until the order is filled or cancelled, or until `timeout` has elapsed.
+    ///
+    /// # Arguments
+    /// * `timeout` - The maximum amount of time to wait.
+    ///
+    /// # Returns
+    /// The order in the state indicated by its latest status, which is still a working state if
+    /// the timeout elapsed.
+    pub async fn await_fill(mut self, timeout: std::time::Duration) -> Lifecycle {
+        // If the decoding loop stops, no further statuses can arrive, so the latest one stands.
+        let _ = tokio::time::timeout(
+            timeout,
+            self.status
+                .wait_for(|status| status.as_ref().is_some_and(is_terminal)),
+        )
+        .await;
+        self.into_lifecycle()
+    }
+
+    /// Modify the order.
+    ///
+    /// # Arguments
+    /// * `client` - The client with which the order was placed.
+    /// * `order` - The modified order.
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Client::req_modify_order`].
+    pub async fn modify<Sec, E>(
+        self,
+        client: &mut Client<Active>,
+        order: &Order<'_, Sec, E>,
+    ) -> Result<Self, std::io::Error>
+    where
+        Sec: Security,
+        E: Executable<Sec>,
+    {
+        client.req_modify_order(order, self.id).await?;
+        Ok(self)
+    }
+
+    /// Request that the order be cancelled. The order is cancelled once its status says so.
+    ///
+    /// # Arguments
+    /// * `client` - The client with which the order was placed.
+    ///
+    /// # Errors
+    /// Returns any error returned by [`Client::cancel_order`].
+    pub async fn cancel(&self, client: &mut Client<Active>) -> Result<(), std::io::Error> {
+        client.cancel_order(self.id).await
+    }
+}
+
+#[test]
+fn test_quantity_precision() -> Result<(), Box<dyn std::error::Error>> {
+    fn validate<S: Security, E: Executable<S>>(
+        security: &S,
+        execute_method: &E,
+    ) -> Result<(), OrderValidationError> {
+        Order::Buy {
+            security,
+            execute_method,
+        }
+        .validate()
+    }
+
+    // A trailing stop does not support fractional shares.
+    struct Trail(Quantity);
+    impl Executable<Stock> for Trail {
+        fn get_quantity(&self) -> Quantity {
+            self.0
+        }
+
+        fn get_order_type(&self) -> &'static str {
+            "TRAIL"
+        }
+
+        fn get_time_in_force(&self) -> TimeInForce {
+            TimeInForce::Day
+        }
+    }
+
+    let bitcoin: crate::contract::ExchangeProxy<Crypto> = toml::from_str(
+        r#"
+        contract_type = "CRYPTO"
+        contract_id = 479624278
+        symbol = "BTC"
+        currency = "USD"
+        local_symbol = "BTC.USD"
+        exchange = { Primary = "PAXOS" }
+        trading_class = "BTC"
+        "#,
+    )?;
+    let stock: crate::contract::ExchangeProxy<Stock> = toml::from_str(crate::contract::AAPL)?;
+    let limit = |quantity: &str| -> Result<Limit, crate::quantity::ParseQuantityError> {
+        Ok(Limit {
+            quantity: quantity.parse()?,
+            cash_quantity: None,
+            price: 40_000.0,
+            time_in_force: TimeInForce::Day,
+            attributes: OrderAttributes::default(),
+        })
+    };
+
+    assert!(validate(&bitcoin.inner, &limit("0.00000001")?).is_ok());
+    assert_eq!(
+        validate(&bitcoin.inner, &limit("0.000000015")?),
+        Err(OrderValidationError::InvalidQuantity("0.000000015".parse()?))
+    );
+    assert!(validate(&stock.inner, &limit("0.0001")?).is_ok());
+    assert!(validate(&stock.inner, &limit("0.00001")?).is_err());
+    assert!(validate(&stock.inner, &Trail(Quantity::from(5))).is_ok());
+    assert_eq!(
+        validate(&stock.inner, &Trail("0.5".parse()?)),
+        Err(OrderValidationError::UnsupportedFractionalOrderType("TRAIL"))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_order_attributes() -> Result<(), Box<dyn std::error::Error>> {
+    fn validate<S: Security, E: Executable<S>>(
+        security: &S,
+        execute_method: &E,
+    ) -> Result<(), OrderValidationError> {
+        Order::Buy {
+            security,
+            execute_method,
+        }
+        .validate()
+    }
+
+    let stock = |exchange: &str| -> Result<Stock, toml::de::Error> {
+        let stock: crate::contract::ExchangeProxy<Stock> = toml::from_str(
+            &crate::contract::AAPL.replace(r#""SMART""#, exchange),
+        )?;
+        Ok(stock.inner)
+    };
+    let (smart, nasdaq) = (stock(r#""SMART""#)?, stock(r#"{ Primary = "ISLAND" }"#)?);
+    let limit = |attributes| Limit {
+        quantity: Quantity::from(100),
+        cash_quantity: None,
+        price: 180.0,
+        time_in_force: TimeInForce::Day,
+        attributes,
+    };
+    let market = |attributes| Market {
+        quantity: Quantity::from(100),
+        cash_quantity: None,
+        time_in_force: TimeInForce::Day,
+        attributes,
+    };
+    let unsupported = |attribute, order_type| {
+        Err(OrderValidationError::UnsupportedAttribute {
+            attribute,
+            order_type,
+        })
+    };
+
+    let outside_rth = OrderAttributes {
+        outside_rth: true,
+        ..OrderAttributes::default()
+    };
+    assert!(validate(&smart, &limit(outside_rth)).is_ok());
+    assert_eq!(
+        validate(&smart, &market(outside_rth)),
+        unsupported("outside_rth", "MKT")
+    );
+
+    let hidden = OrderAttributes {
+        hidden: true,
+        ..OrderAttributes::default()
+    };
+    assert!(validate(&nasdaq, &limit(hidden)).is_ok());
+    assert_eq!(validate(&smart, &limit(hidden)), unsupported("hidden", "LMT"));
+    assert_eq!(validate(&nasdaq, &market(hidden)), unsupported("hidden", "MKT"));
+
+    let iceberg = |display_size| OrderAttributes {
+        display_size: Some(display_size),
+        ..OrderAttributes::default()
+    };
+    assert!(validate(&smart, &limit(iceberg(10))).is_ok());
+    assert_eq!(
+        validate(&smart, &limit(iceberg(100))),
+        Err(OrderValidationError::InvalidDisplaySize(100))
+    );
+    assert_eq!(
+        validate(&smart, &market(iceberg(10))),
+        unsupported("display_size", "MKT")
+    );
+    assert_eq!(
+        validate(
+            &nasdaq,
+            &limit(OrderAttributes {
+                hidden: true,
+                ..iceberg(10)
+            })
+        ),
+        Err(OrderValidationError::InvalidDisplaySize(10))
+    );
+
+    let sweep_to_fill = OrderAttributes {
+        sweep_to_fill: true,
+        ..OrderAttributes::default()
+    };
+    assert!(validate(&smart, &limit(sweep_to_fill)).is_ok());
+    assert_eq!(
+        validate(&nasdaq, &limit(sweep_to_fill)),
+        unsupported("sweep_to_fill", "LMT")
+    );
+
+    let block_order = OrderAttributes {
+        block_order: true,
+        ..OrderAttributes::default()
+    };
+    assert_eq!(
+        validate(&smart, &limit(block_order)),
+        unsupported("block_order", "LMT")
+    );
+
+    let adaptive = OrderAttributes {
+        adaptive_priority: Some(AdaptivePriority::Patient),
+        ..OrderAttributes::default()
+    };
+    assert!(validate(&smart, &market(adaptive)).is_ok());
+    assert_eq!(
+        validate(&nasdaq, &limit(adaptive)),
+        unsupported("adaptive_priority", "LMT")
+    );
+
+    let discretionary = |amount| OrderAttributes {
+        discretionary_amount: Some(amount),
+        ..OrderAttributes::default()
+    };
+    assert!(validate(&smart, &limit(discretionary(0.05))).is_ok());
+    assert_eq!(
+        validate(&smart, &market(discretionary(0.05))),
+        unsupported("discretionary_amount", "MKT")
+    );
+    assert_eq!(
+        validate(&smart, &limit(discretionary(-0.05))),
+        Err(OrderValidationError::InvalidDiscretionaryAmount(-0.05))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_limit_price() -> Result<(), Box<dyn std::error::Error>> {
+    let mut stock = toml::from_str::<crate::contract::ExchangeProxy<Stock>>(crate::contract::AAPL)?
+        .inner;
+    stock.min_tick = 0.01;
+    let limit = |price| Limit {
+        quantity: Quantity::from(100),
+        cash_quantity: None,
+        price,
+        time_in_force: TimeInForce::Day,
+        attributes: OrderAttributes::default(),
+    };
+    let validate = |price| {
+        Order::Buy {
+            security: &stock,
+            execute_method: &limit(price),
+        }
+        .validate()
+    };
+
+    // The sum is 0.30000000000000004, which is still a multiple of the tick.
+    assert_eq!(validate(0.1 + 0.2), Ok(()));
+    assert_eq!(validate(180.0), Ok(()));
+    assert_eq!(
+        validate(180.005),
+        Err(OrderValidationError::InvalidPrice(InvalidPrice {
+            price: 180.005,
+            min_tick: 0.01,
+            below: 180.0,
+            above: 180.01,
+        }))
+    );
+    assert!(matches!(
+        validate(f64::INFINITY),
+        Err(OrderValidationError::InvalidPrice(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_auction_orders() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono_tz::America::New_York;
+
+    let stock: crate::contract::ExchangeProxy<Stock> = toml::from_str(crate::contract::AAPL)?;
+    let at = |hour, minute| {
+        New_York
+            .with_ymd_and_hms(2024, 1, 3, hour, minute, 0)
+            .single()
+            .ok_or("the datetime should be unambiguous")
+    };
+    let market_on_close = MarketOnClose {
+        quantity: Quantity::from(100),
+    };
+    let order = Order::Buy {
+        security: &stock.inner,
+        execute_method: &market_on_close,
+    };
+    assert!(order.validate_at(at(15, 49)?).is_ok());
+    assert!(order.validate_at(at(16, 0)?).is_ok());
+    assert_eq!(
+        order.validate_at(at(15, 55)?),
+        Err(OrderValidationError::AuctionCutoff {
+            order_type: "MOC",
+            auction: Auction::Closing
+        })
+    );
+    // The cutoff is checked in US Eastern time, whatever the time zone of the datetime.
+    assert!(order
+        .validate_at(at(15, 55)?.with_timezone(&chrono::Utc))
+        .is_err());
+
+    let limit_on_open = LimitOnOpen {
+        quantity: Quantity::from(100),
+        price: 180.0,
+    };
+    let order = Order::Sell {
+        security: &stock.inner,
+        execute_method: &limit_on_open,
+    };
+    assert!(order.validate_at(at(9, 0)?).is_ok());
+    assert!(order.validate_at(at(15, 55)?).is_ok());
+    assert_eq!(
+        order.validate_at(at(9, 29)?),
+        Err(OrderValidationError::AuctionCutoff {
+            order_type: "LMT",
+            auction: Auction::Opening
+        })
+    );
+    assert_eq!(
+        Executable::<Stock>::get_time_in_force(&limit_on_open),
+        TimeInForce::Opg
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+// Positions of the fields that every order message sends at a fixed offset, counted from the
+// order's action.
+mod position {
+    pub const ACTION: usize = 0;
+    pub const QUANTITY: usize = 1;
+    pub const ORDER_TYPE: usize = 2;
+    pub const LIMIT_PRICE: usize = 3;
+    pub const AUX_PRICE: usize = 4;
+    pub const TIME_IN_FORCE: usize = 5;
+    pub const BLOCK_ORDER: usize = 13;
+    pub const SWEEP_TO_FILL: usize = 14;
+    pub const DISPLAY_SIZE: usize = 15;
+    pub const TRIGGER_METHOD: usize = 16;
+    pub const OUTSIDE_RTH: usize = 17;
+    pub const HIDDEN: usize = 18;
+    pub const DISCRETIONARY_AMOUNT: usize = 20;
+    pub const GOOD_AFTER_TIME: usize = 21;
+    pub const GOOD_TILL_DATE: usize = 22;
+    pub const VOLATILITY: usize = 46;
+    pub const VOLATILITY_TYPE: usize = 47;
+    pub const DELTA_NEUTRAL_ORDER_TYPE: usize = 48;
+    pub const DELTA_NEUTRAL_AUX_PRICE: usize = 49;
+}
+
+#[cfg(test)]
+// Return the fields of the order's message from its action onwards, so that they can be indexed
+// by the constants in `position`.
+fn order_fields<S: Security, E: Executable<S>>(
+    order: &Order<'_, S, E>,
+) -> Result<Vec<String>, std::io::Error> {
+    let mut fields = order.to_wire_fields(1)?;
+    // The message code and order ID, then the security, then two unused fields.
+    Ok(fields.split_off(4 + order.get_security().to_wire_fields()?.len()))
+}
+
+#[test]
+fn test_to_wire_fields() -> Result<(), Box<dyn std::error::Error>> {
+    let stock = toml::from_str::<crate::contract::ExchangeProxy<Stock>>(crate::contract::AAPL)?;
+    let limit = Limit {
+        quantity: Quantity::from(100),
+        cash_quantity: None,
+        price: 180.0,
+        time_in_force: TimeInForce::Gtc,
+        attributes: OrderAttributes::default(),
+    };
+    let order = Order::Sell {
+        security: &stock.inner,
+        execute_method: &limit,
+    };
+    let fields = order.to_wire_fields(7)?;
+    let contract = stock.inner.to_wire_fields()?;
+    assert_eq!(fields[..2], ["3", "7"]);
+    assert_eq!(fields[2..2 + contract.len()], contract);
+
+    let fields = order_fields(&order)?;
+    assert_eq!(fields[position::ACTION], "SELL");
+    assert_eq!(fields[position::QUANTITY], "100");
+    assert_eq!(fields[position::ORDER_TYPE], "LMT");
+    assert_eq!(fields[position::LIMIT_PRICE], "180.0");
+    assert_eq!(fields[position::AUX_PRICE], "");
+    assert_eq!(fields[position::TIME_IN_FORCE], "GTC");
+    for flag in [
+        position::BLOCK_ORDER,
+        position::SWEEP_TO_FILL,
+        position::OUTSIDE_RTH,
+        position::HIDDEN,
+    ] {
+        assert_eq!(fields[flag], "0");
+    }
+    assert_eq!(fields[position::DISPLAY_SIZE], "0");
+    assert_eq!(fields[position::TRIGGER_METHOD], "0");
+    assert_eq!(fields[position::DISCRETIONARY_AMOUNT], "0.0");
+    for unset in [
+        position::GOOD_AFTER_TIME,
+        position::GOOD_TILL_DATE,
+        position::VOLATILITY,
+        position::VOLATILITY_TYPE,
+        position::DELTA_NEUTRAL_ORDER_TYPE,
+        position::DELTA_NEUTRAL_AUX_PRICE,
+    ] {
+        assert_eq!(fields[unset], "");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_time_in_force_datetimes() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono_tz::America::New_York;
+
+    let stock = toml::from_str::<crate::contract::ExchangeProxy<Stock>>(crate::contract::AAPL)?;
+    let datetime = New_York
+        .with_ymd_and_hms(2024, 12, 31, 15, 30, 0)
+        .single()
+        .ok_or("the datetime should be unambiguous")?;
+    for (time_in_force, code, set, unset) in [
+        (
+            TimeInForce::GoodTillDate(datetime),
+            "GTD",
+            position::GOOD_TILL_DATE,
+            position::GOOD_AFTER_TIME,
+        ),
+        (
+            TimeInForce::GoodAfterTime(datetime),
+            "DAY",
+            position::GOOD_AFTER_TIME,
+            position::GOOD_TILL_DATE,
+        ),
+    ] {
+        let limit = Limit {
+            quantity: Quantity::from(10),
+            cash_quantity: None,
+            price: 180.0,
+            time_in_force,
+            attributes: OrderAttributes::default(),
+        };
+        let fields = order_fields(&Order::Buy {
+            security: &stock.inner,
+            execute_method: &limit,
+        })?;
+        assert_eq!(fields[position::TIME_IN_FORCE], code);
+        assert_eq!(fields[set], "20241231 15:30:00 America/New_York");
+        assert!(fields[unset].is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_order_attribute_fields() -> Result<(), Box<dyn std::error::Error>> {
+    let stock = toml::from_str::<crate::contract::ExchangeProxy<Stock>>(crate::contract::AAPL)?;
+    let limit = Limit {
+        quantity: Quantity::from(100),
+        cash_quantity: None,
+        price: 180.0,
+        time_in_force: TimeInForce::Day,
+        attributes: OrderAttributes {
+            outside_rth: true,
+            display_size: Some(10),
+            sweep_to_fill: true,
+            adaptive_priority: Some(AdaptivePriority::Urgent),
+            not_held: true,
+            discretionary_amount: Some(0.05),
+            ..OrderAttributes::default()
+        },
+    };
+    let fields = order_fields(&Order::Buy {
+        security: &stock.inner,
+        execute_method: &limit,
+    })?;
+    assert_eq!(fields[position::BLOCK_ORDER], "0");
+    assert_eq!(fields[position::SWEEP_TO_FILL], "1");
+    assert_eq!(fields[position::DISPLAY_SIZE], "10");
+    assert_eq!(fields[position::TRIGGER_METHOD], "0");
+    assert_eq!(fields[position::OUTSIDE_RTH], "1");
+    assert_eq!(fields[position::HIDDEN], "0");
+    assert_eq!(fields[position::DISCRETIONARY_AMOUNT], "0.05");
+    // The algo strategy follows sections of variable length. Not held precedes the delta neutral
+    // contract flag, which precedes the strategy and its parameters.
+    let algo = fields
+        .iter()
+        .position(|field| field == "Adaptive")
+        .ok_or("the algo strategy should be sent")?;
+    assert_eq!(fields[algo - 2..algo], ["1", "0"]);
+    assert_eq!(
+        fields[algo + 1..=algo + 3],
+        ["1", "adaptivePriority", "Urgent"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pegged_order_types() -> Result<(), Box<dyn std::error::Error>> {
+    let stock = toml::from_str::<crate::contract::ExchangeProxy<Stock>>(crate::contract::AAPL)?;
+    let mid_price = |price_cap| MidPrice {
+        quantity: Quantity::from(100),
+        price_cap,
+        time_in_force: TimeInForce::Day,
+        attributes: OrderAttributes::default(),
+    };
+    for (execute_method, price_cap) in [(mid_price(Some(180.5)), "180.5"), (mid_price(None), "")] {
+        let fields = order_fields(&Order::Buy {
+            security: &stock.inner,
+            execute_method: &execute_method,
+        })?;
+        assert_eq!(fields[position::QUANTITY], "100");
+        assert_eq!(fields[position::ORDER_TYPE], "MIDPRICE");
+        assert_eq!(fields[position::LIMIT_PRICE], price_cap);
+        assert_eq!(fields[position::AUX_PRICE], "");
+        assert_eq!(fields[position::TIME_IN_FORCE], "DAY");
+    }
+
+    // The price cap is sent as the limit price and the offset as the auxiliary price.
+    let relative = Relative {
+        quantity: Quantity::from(100),
+        offset: 0.01,
+        price_cap: Some(181.0),
+        time_in_force: TimeInForce::Gtc,
+        attributes: OrderAttributes::default(),
+    };
+    let fields = order_fields(&Order::Sell {
+        security: &stock.inner,
+        execute_method: &relative,
+    })?;
+    assert_eq!(fields[position::ORDER_TYPE], "REL");
+    assert_eq!(fields[position::LIMIT_PRICE], "181.0");
+    assert_eq!(fields[position::AUX_PRICE], "0.01");
+    assert_eq!(fields[position::TIME_IN_FORCE], "GTC");
+    Ok(())
+}
+
+#[test]
+fn test_auction_order_fields() -> Result<(), Box<dyn std::error::Error>> {
+    // Return the quantity, order type, limit price, auxiliary price, and time in force of a buy
+    // order.
+    fn prices<E: Executable<Stock>>(
+        stock: &Stock,
+        execute_method: &E,
+    ) -> Result<Vec<String>, std::io::Error> {
+        Ok(order_fields(&Order::Buy {
+            security: stock,
+            execute_method,
+        })?[position::QUANTITY..=position::TIME_IN_FORCE]
+            .to_vec())
+    }
+
+    let stock = toml::from_str::<crate::contract::ExchangeProxy<Stock>>(crate::contract::AAPL)?;
+    let quantity = Quantity::from(100);
+    assert_eq!(
+        prices(&stock.inner, &MarketOnClose { quantity })?,
+        ["100", "MOC", "", "", "DAY"]
+    );
+    let limit_on_close = LimitOnClose {
+        quantity,
+        price: 180.0,
+    };
+    assert_eq!(
+        prices(&stock.inner, &limit_on_close)?,
+        ["100", "LOC", "180.0", "", "DAY"]
+    );
+    assert_eq!(
+        prices(&stock.inner, &MarketOnOpen { quantity })?,
+        ["100", "MKT", "", "", "OPG"]
+    );
+    let at_auction = AtAuction {
+        quantity,
+        price: 180.0,
+    };
+    assert_eq!(
+        prices(&stock.inner, &at_auction)?,
+        ["100", "MTL", "180.0", "", "AUC"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_volatility_order() -> Result<(), Box<dyn std::error::Error>> {
+    let option: crate::contract::ExchangeProxy<SecOption> = toml::from_str(
+        r#"
+        contract_type = "OPT"
+        contract_id = 684437071
+        symbol = "AAPL"
+        currency = "USD"
+        local_symbol = "AAPL  250117C00180000"
+        exchange = "SMART"
+        trading_class = "AAPL"
+        expiration_date = "2025-01-17"
+        multiplier = 100
+        option_type = "Call"
+        strike = 180.0
+        "#,
+    )?;
+    let mut volatility = Volatility {
+        quantity: Quantity::from(5),
+        volatility: 27.5,
+        volatility_type: VolatilityType::Annual,
+        continuous_update: true,
+        reference_price_type: ReferencePriceType::BidOrAsk,
+        hedge: Some(DeltaNeutralHedge {
+            order_type: DeltaNeutralOrderType::Relative(0.01),
+            contract_id: Some(ContractId(265_598)),
+        }),
+        time_in_force: TimeInForce::Day,
+    };
+    let fields = order_fields(&Order::Buy {
+        security: &option.inner,
+        execute_method: &volatility,
+    })?;
+    assert_eq!(fields[position::ORDER_TYPE], "VOL");
+    assert_eq!(fields[position::LIMIT_PRICE], "");
+    assert_eq!(fields[position::AUX_PRICE], "");
+    assert_eq!(fields[position::VOLATILITY], "27.5");
+    assert_eq!(fields[position::VOLATILITY_TYPE], "2");
+    assert_eq!(fields[position::DELTA_NEUTRAL_ORDER_TYPE], "REL");
+    assert_eq!(fields[position::DELTA_NEUTRAL_AUX_PRICE], "0.01");
+    // The hedge's contract ID and clearing details follow, then the continuous update flag and
+    // reference price type.
+    assert_eq!(
+        fields[position::DELTA_NEUTRAL_AUX_PRICE + 1..=position::DELTA_NEUTRAL_AUX_PRICE + 10],
+        ["265598", "", "", "", "", "0", "0", "", "1", "2"]
+    );
+
+    volatility.volatility = -1.0;
+    assert_eq!(
+        Order::Buy {
+            security: &option.inner,
+            execute_method: &volatility,
+        }
+        .validate(),
+        Err(OrderValidationError::InvalidVolatility(-1.0))
+    );
+    Ok(())
+}
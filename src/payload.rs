@@ -2,12 +2,13 @@ use std::convert::Infallible;
 use std::fmt::Formatter;
 use std::str::FromStr;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use chrono::serde::ts_seconds;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::contract::{Contract, ExchangeProxy};
+use crate::quantity::Quantity;
 
 #[derive(Debug, Clone, Error)]
 #[error("Invalid value encountered when attempting to parse a payload value.")]
@@ -180,6 +181,95 @@ pub struct HistogramEntry {
     pub size: f64,
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// A complete histogram, whose entries are sorted by ascending price.
+pub struct Histogram(Vec<HistogramEntry>);
+
+impl From<Vec<HistogramEntry>> for Histogram {
+    fn from(mut entries: Vec<HistogramEntry>) -> Self {
+        entries.sort_by(|a, b| a.price.total_cmp(&b.price));
+        Self(entries)
+    }
+}
+
+impl<'a> IntoIterator for &'a Histogram {
+    type Item = &'a HistogramEntry;
+    type IntoIter = std::slice::Iter<'a, HistogramEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Histogram {
+    #[must_use]
+    #[inline]
+    /// Get the entries of the histogram, sorted by ascending price.
+    pub fn entries(&self) -> &[HistogramEntry] {
+        &self.0
+    }
+
+    #[must_use]
+    #[inline]
+    /// Get the sum of the sizes of every entry in the histogram.
+    pub fn total_size(&self) -> f64 {
+        self.0.iter().map(|entry| entry.size).sum()
+    }
+
+    #[must_use]
+    /// Get the cumulative distribution of the histogram.
+    ///
+    /// # Returns
+    /// One entry per price, in ascending order, whose size is the fraction of the total size
+    /// that occurred at or below that price. If the total size is zero, every fraction is zero.
+    pub fn cumulative(&self) -> Vec<HistogramEntry> {
+        let total = self.total_size();
+        let mut running = 0.0;
+        self.0
+            .iter()
+            .map(|entry| {
+                running += entry.size;
+                HistogramEntry {
+                    price: entry.price,
+                    size: if total > 0.0 { running / total } else { 0.0 },
+                }
+            })
+            .collect()
+    }
+
+    #[must_use]
+    /// Get the fraction of the total size that occurred at or below `price`.
+    pub fn fraction_at_or_below(&self, price: f64) -> f64 {
+        let total = self.total_size();
+        if total > 0.0 {
+            self.0
+                .iter()
+                .take_while(|entry| entry.price <= price)
+                .map(|entry| entry.size)
+                .sum::<f64>()
+                / total
+        } else {
+            0.0
+        }
+    }
+
+    #[must_use]
+    /// Get the lowest price at which the cumulative fraction of the total size reaches `quantile`.
+    ///
+    /// # Returns
+    /// The price, or [`None`] if the histogram is empty, its total size is zero, or `quantile` is
+    /// not between 0 and 1.
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        if !(0.0..=1.0).contains(&quantile) {
+            return None;
+        }
+        self.cumulative()
+            .into_iter()
+            .find(|entry| entry.size >= quantile && entry.size > 0.0)
+            .map(|entry| entry.price)
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
 /// A single historical bar
 pub struct BarCore {
@@ -274,6 +364,49 @@ impl From<Trade> for TradeSerDeHelper {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The trading schedule returned from a [`crate::market_data::historical_bar::Schedule`] request.
+pub struct HistoricalSchedule {
+    /// The beginning of the requested period.
+    #[serde(with = "ts_seconds")]
+    pub start_datetime: DateTime<Utc>,
+    /// The end of the requested period.
+    #[serde(with = "ts_seconds")]
+    pub end_datetime: DateTime<Utc>,
+    /// The time zone in which the security's exchange operates.
+    #[serde(with = "serde_time_zone")]
+    pub time_zone: chrono_tz::Tz,
+    /// Each trading session within the requested period.
+    pub sessions: Vec<ScheduleSession>,
+}
+
+mod serde_time_zone {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(tz: &chrono_tz::Tz, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_str(tz.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<chrono_tz::Tz, D::Error> {
+        String::deserialize(de)?.parse().map_err(Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// A single trading session in a [`HistoricalSchedule`].
+pub struct ScheduleSession {
+    /// The datetime at which the session opens.
+    #[serde(with = "ts_seconds")]
+    pub start_datetime: DateTime<Utc>,
+    /// The datetime at which the session closes.
+    #[serde(with = "ts_seconds")]
+    pub end_datetime: DateTime<Utc>,
+    /// The trading date to which the session belongs, which may differ from the calendar date
+    /// of an overnight session.
+    pub reference_date: NaiveDate,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "tick")]
 /// A historical or live tick.
@@ -286,6 +419,45 @@ pub enum TickData {
     Last(Last),
 }
 
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+/// The attributes attached to a historical or live tick.
+pub struct TickAttributes {
+    /// Whether the trade occurred at a price outside the limits of the order that produced it.
+    pub past_limit: bool,
+    /// Whether the trade was not reported to the tape, as with odd lots.
+    pub unreported: bool,
+    /// Whether the bid price is lower than the day's lowest value.
+    pub bid_past_low: bool,
+    /// Whether the ask price is higher than the day's highest value.
+    pub ask_past_high: bool,
+}
+
+impl TickAttributes {
+    #[inline]
+    #[must_use]
+    /// Decode the attribute mask of a last-trade tick.
+    pub fn from_last_mask(mask: u32) -> Self {
+        Self {
+            past_limit: mask & 1 != 0,
+            unreported: mask & 2 != 0,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Decode the attribute mask of a bid/ask tick.
+    pub fn from_bid_ask_mask(mask: u32) -> Self {
+        Self {
+            bid_past_low: mask & 1 != 0,
+            ask_past_high: mask & 2 != 0,
+            ..Self::default()
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
 /// A tick representing the midpoint.
 pub struct Midpoint {
@@ -294,6 +466,9 @@ pub struct Midpoint {
     pub datetime: DateTime<Utc>,
     /// The midpoint price.
     pub price: f64,
+    /// The tick's attributes. Midpoint ticks carry no attributes, so this is always the default.
+    #[serde(default)]
+    pub attributes: TickAttributes,
 }
 
 #[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
@@ -310,6 +485,9 @@ pub struct BidAsk {
     pub bid_size: f64,
     /// The ask size.
     pub ask_size: f64,
+    /// The tick's attributes.
+    #[serde(default)]
+    pub attributes: TickAttributes,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -324,6 +502,9 @@ pub struct Last {
     pub size: f64,
     /// The last traded exchange.
     pub exchange: crate::exchange::Primary,
+    /// The tick's attributes.
+    #[serde(default)]
+    pub attributes: TickAttributes,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -332,7 +513,7 @@ pub struct Position {
     /// The ID of the underlying contract.
     pub contract: ExchangeProxy<Contract>,
     /// The number of contracts owned.
-    pub position: f64,
+    pub position: Quantity,
     /// The current market price of each contract.
     pub market_price: f64,
     /// The current market value of the entire position.
@@ -353,7 +534,7 @@ pub struct PositionSummary {
     /// The underlying contract
     pub contract: ExchangeProxy<Contract>,
     /// The number of contracts owned.
-    pub position: f64,
+    pub position: Quantity,
     /// The average cost per contract for the entire position.
     pub average_cost: f64,
     /// The account number holding the position.
@@ -386,6 +567,149 @@ pub struct PnlSingle {
     pub market_value: f64,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A single row of a market scan from [`crate::client::Client::req_scanner_subscription`].
+///
+/// Only the identifying details of the contract are included. The full contract can be
+/// requested with its contract ID.
+pub struct ScannerRow {
+    /// The position of the contract in the scan, starting from 0.
+    pub rank: u32,
+    /// The ID of the contract.
+    pub contract_id: crate::contract::ContractId,
+    /// The symbol of the contract.
+    pub symbol: String,
+    /// The security type of the contract (ex. "STK").
+    pub security_type: String,
+    /// The exchange of the contract.
+    pub exchange: String,
+    /// The currency of the contract.
+    pub currency: String,
+    /// The local symbol of the contract.
+    pub local_symbol: String,
+    /// The name of the market in which the contract trades.
+    pub market_name: String,
+    /// The trading class of the contract.
+    pub trading_class: String,
+    /// The value of the scanned statistic, if the scan code reports one.
+    pub distance: String,
+    /// The benchmark against which the statistic is measured, if any.
+    pub benchmark: String,
+    /// The projection of the statistic, if any.
+    pub projection: String,
+    /// A description of the legs of a combination contract, if any.
+    pub legs: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A single headline from [`crate::client::Client::req_historical_news`].
+pub struct Headline {
+    /// The time at which the headline was published.
+    pub time: DateTime<Utc>,
+    /// The code of the news provider that published the headline (ex. "BRFG").
+    pub provider_code: String,
+    /// The ID of the article, which identifies it among the articles of its provider.
+    pub article_id: String,
+    /// The headline itself.
+    pub headline: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The expirations and strikes of the options on an underlying that are listed on a single
+/// exchange under a single trading class, from [`crate::client::Client::req_option_parameters`].
+pub struct OptionParameters {
+    /// The exchange on which the options are listed (ex. "SMART" or "CBOE").
+    pub exchange: String,
+    /// The contract ID of the underlying.
+    pub underlying_contract_id: crate::contract::ContractId,
+    /// The trading class of the options (ex. "SPXW").
+    pub trading_class: String,
+    /// The multiplier of the options.
+    pub multiplier: String,
+    /// Every expiration date, from the nearest to the furthest.
+    pub expirations: Vec<NaiveDate>,
+    /// Every strike price, from the lowest to the highest.
+    pub strikes: Vec<f64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A report describing an incoming message that could not be decoded.
+pub struct DecodeErrorReport {
+    /// The kind of message that failed to decode (ex. "tick price msg"). If the message
+    /// identifier itself was not recognized, this is "invalid in msg".
+    pub kind: String,
+    /// A description of the error that occurred.
+    pub error: String,
+    /// The raw fields of the message, including the message identifier.
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The kinds of data farms to which TWS maintains connections.
+pub enum DataFarm {
+    /// A farm that serves live and delayed market data.
+    MarketData,
+    /// A farm that serves historical market data (HMDS).
+    Historical,
+    /// A farm that serves security definitions.
+    SecurityDefinition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The state of a connection between TWS and a data farm.
+pub enum FarmConnection {
+    /// The connection is working.
+    Ok,
+    /// The connection is broken.
+    Broken,
+    /// The connection is inactive, but should be available upon demand.
+    Inactive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A notice about the connection between TWS and a data farm, which TWS sends as an error
+/// message with one of the codes 2103 to 2108, 2157 or 2158.
+pub struct DataFarmStatus {
+    /// The kind of data farm.
+    pub farm: DataFarm,
+    /// The state of the connection.
+    pub connection: FarmConnection,
+    /// The name of the data farm (ex. "usfarm"), if one was given.
+    pub name: String,
+}
+
+impl DataFarmStatus {
+    #[must_use]
+    /// Classify an error message sent by TWS as a [`DataFarmStatus`], if its code denotes one.
+    ///
+    /// # Arguments
+    /// * `error_code` - The code of the error message.
+    /// * `error_string` - The text of the error message, which ends with the farm's name.
+    pub fn from_error(error_code: i64, error_string: &str) -> Option<Self> {
+        let (farm, connection) = match error_code {
+            2103 => (DataFarm::MarketData, FarmConnection::Broken),
+            2104 => (DataFarm::MarketData, FarmConnection::Ok),
+            2105 => (DataFarm::Historical, FarmConnection::Broken),
+            2106 => (DataFarm::Historical, FarmConnection::Ok),
+            2107 => (DataFarm::Historical, FarmConnection::Inactive),
+            2108 => (DataFarm::MarketData, FarmConnection::Inactive),
+            2157 => (DataFarm::SecurityDefinition, FarmConnection::Broken),
+            2158 => (DataFarm::SecurityDefinition, FarmConnection::Ok),
+            _ => return None,
+        };
+        let name = error_string
+            .rsplit_once(':')
+            .or_else(|| error_string.rsplit_once('.'))
+            .map_or("", |(_, name)| name.trim())
+            .to_owned();
+        Some(Self {
+            farm,
+            connection,
+            name,
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "order_status")]
 /// The possible statuses for a given order.
@@ -410,6 +734,25 @@ pub enum OrderStatus {
     Inactive(OrderStatusCore),
 }
 
+impl OrderStatus {
+    #[inline]
+    #[must_use]
+    /// Return a reference to the inner [`OrderStatusCore`].
+    pub fn as_core(&self) -> &OrderStatusCore {
+        match self {
+            Self::ApiPending(c)
+            | Self::PendingSubmit(c)
+            | Self::PendingCancel(c)
+            | Self::PreSubmitted(c)
+            | Self::Submitted(c)
+            | Self::ApiCancelled(c)
+            | Self::Cancelled(c)
+            | Self::Filled(c)
+            | Self::Inactive(c) => c,
+        }
+    }
+}
+
 impl TryFrom<(&str, OrderStatusCore)> for OrderStatus {
     type Error = ParsePayloadError;
 
@@ -429,6 +772,15 @@ impl TryFrom<(&str, OrderStatusCore)> for OrderStatus {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The amount of a security that an open order buys or sells.
+pub enum OrderAmount {
+    /// A number of shares/units.
+    Quantity(Quantity),
+    /// An amount of the security's currency, for a cash quantity order.
+    Cash(f64),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 /// The core fields of an Order's Status
 pub struct OrderStatusCore {
@@ -437,7 +789,7 @@ pub struct OrderStatusCore {
     /// The details of how many contracts have been filled.
     pub fill: Option<Fill>,
     /// The remnant positions.
-    pub remaining: f64,
+    pub remaining: Quantity,
     /// The order’s permId used by the TWS to identify orders.
     pub permanent_id: i64,
     /// Parent’s id. Used for bracket and auto trailing stop orders.
@@ -454,7 +806,7 @@ pub struct OrderStatusCore {
 /// Contains the details of an order's filled positions.
 pub struct Fill {
     /// Number of filled positions.
-    pub filled: f64,
+    pub filled: Quantity,
     /// Average filling price.
     pub average_price: f64,
     /// Price at which the last positions were filled.
@@ -475,3 +827,184 @@ impl FromStr for Locate {
         }
     }
 }
+
+#[test]
+fn test_histogram() {
+    let histogram = Histogram::from(vec![
+        HistogramEntry {
+            price: 101.0,
+            size: 30.0,
+        },
+        HistogramEntry {
+            price: 100.0,
+            size: 10.0,
+        },
+        HistogramEntry {
+            price: 102.0,
+            size: 60.0,
+        },
+    ]);
+    let prices = histogram.into_iter().map(|e| e.price).collect::<Vec<_>>();
+    assert_eq!(prices, vec![100.0, 101.0, 102.0]);
+    assert!((histogram.total_size() - 100.0).abs() < f64::EPSILON);
+    assert!((histogram.fraction_at_or_below(101.5) - 0.4).abs() < 1e-12);
+    assert_eq!(histogram.quantile(0.5), Some(102.0));
+    assert_eq!(histogram.quantile(0.1), Some(100.0));
+    assert_eq!(histogram.quantile(1.5), None);
+    assert_eq!(Histogram::default().quantile(0.5), None);
+    let cdf = histogram.cumulative();
+    assert!((cdf[2].size - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_tick_attributes() {
+    let last = TickAttributes::from_last_mask(2);
+    assert!(!last.past_limit && last.unreported);
+    assert!(!last.bid_past_low && !last.ask_past_high);
+    let bid_ask = TickAttributes::from_bid_ask_mask(3);
+    assert!(bid_ask.bid_past_low && bid_ask.ask_past_high);
+    assert!(!bid_ask.past_limit && !bid_ask.unreported);
+    assert_eq!(TickAttributes::from_last_mask(0), TickAttributes::default());
+}
+
+#[test]
+fn test_data_farm_status() {
+    let status =
+        DataFarmStatus::from_error(2104, "Market data farm connection is OK:usfarm").unwrap();
+    assert_eq!(status.farm, DataFarm::MarketData);
+    assert_eq!(status.connection, FarmConnection::Ok);
+    assert_eq!(status.name, "usfarm");
+    let status = DataFarmStatus::from_error(
+        2107,
+        "HMDS data farm connection is inactive but should be available upon demand.ushmds",
+    )
+    .unwrap();
+    assert_eq!(status.farm, DataFarm::Historical);
+    assert_eq!(status.connection, FarmConnection::Inactive);
+    assert_eq!(status.name, "ushmds");
+    assert_eq!(DataFarmStatus::from_error(200, "No security definition"), None);
+}
+
+#[cfg(test)]
+// Serialize a value to TOML and back, wrapping it in a table because TOML documents must be
+// tables.
+pub(crate) fn serde_round_trip<T>(value: &T) -> T
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    #[derive(Serialize, Deserialize)]
+    struct Doc<T> {
+        value: T,
+    }
+
+    let doc: Doc<T> = toml::from_str(&toml::to_string(&Doc { value }).unwrap()).unwrap();
+    doc.value
+}
+
+#[test]
+fn test_serde_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::TimeZone;
+
+    let datetime = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap();
+    let core = BarCore {
+        datetime,
+        open: 100.0,
+        high: 101.0,
+        low: 99.5,
+        close: 100.5,
+    };
+    for bar in [
+        Bar::Ordinary(core),
+        Bar::Trades(Trade {
+            bar: core,
+            volume: 1_000.0,
+            wap: 100.25,
+            trade_count: 42,
+        }),
+    ] {
+        assert_eq!(serde_round_trip(&bar), bar);
+    }
+
+    let attributes = TickAttributes::from_last_mask(2);
+    for tick in [
+        TickData::Midpoint(Midpoint {
+            datetime,
+            price: 100.25,
+            attributes: TickAttributes::default(),
+        }),
+        TickData::BidAsk(BidAsk {
+            datetime,
+            bid_price: 100.0,
+            ask_price: 100.5,
+            bid_size: 300.0,
+            ask_size: 200.0,
+            attributes: TickAttributes::from_bid_ask_mask(1),
+        }),
+        TickData::Last(Last {
+            datetime,
+            price: 100.25,
+            size: 50.0,
+            exchange: crate::exchange::Primary::Island,
+            attributes,
+        }),
+    ] {
+        assert_eq!(serde_round_trip(&tick), tick);
+    }
+
+    let contract: ExchangeProxy<Contract> = toml::from_str(crate::contract::AAPL)?;
+    let position = Position {
+        contract,
+        position: Quantity::from(100),
+        market_price: 150.0,
+        market_value: 15_000.0,
+        average_cost: 140.0,
+        unrealized_pnl: 1_000.0,
+        realized_pnl: 0.0,
+        account_number: "DU1234567".to_owned(),
+    };
+    assert_eq!(serde_round_trip(&position), position);
+
+    let pnl = PnlSingle {
+        daily: 12.5,
+        unrealized: 1_000.0,
+        realized: 0.0,
+        position_size: 100.0,
+        market_value: 15_000.0,
+    };
+    assert_eq!(serde_round_trip(&pnl), pnl);
+
+    let status = OrderStatus::Filled(OrderStatusCore {
+        order_id: 7,
+        fill: Some(Fill {
+            filled: Quantity::from(100),
+            average_price: 150.0,
+            last_price: 150.0,
+        }),
+        remaining: Quantity::ZERO,
+        permanent_id: 42,
+        parent_id: None,
+        client_id: 0,
+        why_held: None,
+        market_cap_price: None,
+    });
+    assert_eq!(serde_round_trip(&status), status);
+
+    let schedule = HistoricalSchedule {
+        start_datetime: datetime,
+        end_datetime: datetime + chrono::Duration::hours(6),
+        time_zone: chrono_tz::US::Eastern,
+        sessions: vec![ScheduleSession {
+            start_datetime: datetime,
+            end_datetime: datetime + chrono::Duration::hours(6),
+            reference_date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+        }],
+    };
+    assert_eq!(serde_round_trip(&schedule), schedule);
+
+    let quote = crate::tick::QuoteEvent::Live(crate::tick::Quote::Bid {
+        price: 100.0,
+        size: Some(300.0),
+    });
+    assert_eq!(serde_round_trip(&quote), quote);
+    Ok(())
+}
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::account::{Attribute, Denomination, Segment};
+use crate::contract::{Contract, ContractId, ExchangeProxy};
+use crate::currency::{Converter, Currency};
+use crate::payload::{Pnl, PnlSingle, Position, PositionSummary};
+use crate::quantity::Quantity;
+
+// === Type definitions ===
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A single position held in a [`Portfolio`], combining every callback that describes it.
+///
+/// Fields that are only reported by some callbacks are `None` until one of those callbacks has
+/// been received.
+pub struct Holding {
+    /// The contract held.
+    pub contract: ExchangeProxy<Contract>,
+    /// The number of contracts owned.
+    pub position: Quantity,
+    /// The average cost per contract for the entire position.
+    pub average_cost: f64,
+    /// The current market price of each contract.
+    pub market_price: Option<f64>,
+    /// The current market value of the entire position.
+    pub market_value: Option<f64>,
+    /// The unrealized P&L of the position.
+    pub unrealized_pnl: Option<f64>,
+    /// The realized P&L of the position.
+    pub realized_pnl: Option<f64>,
+    /// The daily P&L of the position.
+    pub daily_pnl: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Describes the part of a [`Portfolio`] that changed as the result of a single update.
+pub enum Change {
+    /// A position was opened or its details changed.
+    Position(ContractId),
+    /// A position was closed and is no longer held.
+    PositionClosed(ContractId),
+    /// The cash balance in a given denomination changed.
+    Cash(Denomination),
+    /// The net liquidation value of the account changed.
+    NetLiquidation,
+    /// One of the margin values of the account changed.
+    Margin,
+    /// The aggregate P&L of the account changed.
+    Pnl,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Aggregates the position, P&L, and account attribute callbacks of a single account into one
+/// queryable view.
+///
+/// Pass each of [`crate::wrapper::LocalWrapper::portfolio_value`],
+/// [`crate::wrapper::LocalWrapper::position_summary`], [`crate::wrapper::LocalWrapper::pnl`],
+/// [`crate::wrapper::LocalWrapper::single_position_pnl`], and
+/// [`crate::wrapper::LocalWrapper::account_attribute`] to the method of the same name. Each method
+/// returns the [`Change`] it made, if any, so that a wrapper can react only to meaningful updates.
+/// Updates for other accounts are ignored. Since a [`Portfolio`] is only modified through these
+/// methods, a clone of it is a consistent snapshot.
+pub struct Portfolio {
+    account_number: String,
+    holdings: HashMap<ContractId, Holding>,
+    single_position_pnl_ids: HashMap<i64, ContractId>,
+    cash: HashMap<Denomination, f64>,
+    net_liquidation: Option<f64>,
+    net_liquidation_currency: Option<Currency>,
+    excess_liquidity: Option<f64>,
+    available_funds: Option<f64>,
+    init_margin_req: Option<f64>,
+    maintenance_margin_req: Option<f64>,
+    pnl: Option<Pnl>,
+    is_loaded: bool,
+}
+
+// === Type implementations ===
+
+impl From<Position> for Holding {
+    fn from(value: Position) -> Self {
+        Self {
+            contract: value.contract,
+            position: value.position,
+            average_cost: value.average_cost,
+            market_price: Some(value.market_price),
+            market_value: Some(value.market_value),
+            unrealized_pnl: Some(value.unrealized_pnl),
+            realized_pnl: Some(value.realized_pnl),
+            daily_pnl: None,
+        }
+    }
+}
+
+impl From<PositionSummary> for Holding {
+    fn from(value: PositionSummary) -> Self {
+        Self {
+            contract: value.contract,
+            position: value.position,
+            average_cost: value.average_cost,
+            market_price: None,
+            market_value: None,
+            unrealized_pnl: None,
+            realized_pnl: None,
+            daily_pnl: None,
+        }
+    }
+}
+
+impl Portfolio {
+    #[inline]
+    #[must_use]
+    /// Create a new, empty [`Portfolio`] for a single account.
+    ///
+    /// # Arguments
+    /// * `account_number` - The account whose updates are aggregated.
+    pub fn new(account_number: String) -> Self {
+        Self {
+            account_number,
+            holdings: HashMap::new(),
+            single_position_pnl_ids: HashMap::new(),
+            cash: HashMap::new(),
+            net_liquidation: None,
+            net_liquidation_currency: None,
+            excess_liquidity: None,
+            available_funds: None,
+            init_margin_req: None,
+            maintenance_margin_req: None,
+            pnl: None,
+            is_loaded: false,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the account whose updates are aggregated.
+    pub fn account_number(&self) -> &str {
+        &self.account_number
+    }
+
+    /// Record a position from [`crate::client::Client::req_account_updates`].
+    pub fn portfolio_value(&mut self, position: Position) -> Option<Change> {
+        if position.account_number != self.account_number {
+            return None;
+        }
+        let daily_pnl = self
+            .holdings
+            .get(&position.contract.contract_id())
+            .and_then(|h| h.daily_pnl);
+        self.update_holding(Holding {
+            daily_pnl,
+            ..Holding::from(position)
+        })
+    }
+
+    /// Record a position from [`crate::client::Client::req_positions`]. Market values and P&L
+    /// already known for the position are kept.
+    pub fn position_summary(&mut self, summary: PositionSummary) -> Option<Change> {
+        if summary.account_number != self.account_number {
+            return None;
+        }
+        let holding = match self.holdings.get(&summary.contract.contract_id()) {
+            Some(existing) => Holding {
+                position: summary.position,
+                average_cost: summary.average_cost,
+                ..existing.clone()
+            },
+            None => Holding::from(summary),
+        };
+        self.update_holding(holding)
+    }
+
+    /// Associate a [`crate::client::Client::req_single_position_pnl`] subscription with the
+    /// position to which it belongs. Updates from unregistered subscriptions are ignored.
+    ///
+    /// # Arguments
+    /// * `req_id` - The ID of the subscription.
+    /// * `contract_id` - The contract ID of the position.
+    pub fn register_single_position_pnl(&mut self, req_id: i64, contract_id: ContractId) {
+        self.single_position_pnl_ids.insert(req_id, contract_id);
+    }
+
+    /// Record the P&L of a single position. Values that IBKR reports as unset are ignored.
+    pub fn single_position_pnl(&mut self, req_id: i64, pnl: PnlSingle) -> Option<Change> {
+        let contract_id = *self.single_position_pnl_ids.get(&req_id)?;
+        let existing = self.holdings.get(&contract_id)?;
+        let set = |value: f64, old: Option<f64>| if value == f64::MAX { old } else { Some(value) };
+        let holding = Holding {
+            // An unset position size is too large to be a quantity.
+            position: Quantity::try_from(pnl.position_size).unwrap_or(existing.position),
+            market_value: set(pnl.market_value, existing.market_value),
+            unrealized_pnl: set(pnl.unrealized, existing.unrealized_pnl),
+            realized_pnl: set(pnl.realized, existing.realized_pnl),
+            daily_pnl: set(pnl.daily, existing.daily_pnl),
+            ..existing.clone()
+        };
+        self.update_holding(holding)
+    }
+
+    /// Record the aggregate P&L of the account from [`crate::client::Client::req_pnl`].
+    pub fn pnl(&mut self, pnl: Pnl) -> Option<Change> {
+        (self.pnl.replace(pnl) != Some(pnl)).then_some(Change::Pnl)
+    }
+
+    /// Record an account attribute. Only cash balances, the net liquidation value, and the
+    /// account-wide margin values are kept.
+    pub fn account_attribute(
+        &mut self,
+        attribute: Attribute,
+        account_number: &str,
+    ) -> Option<Change> {
+        if account_number != self.account_number {
+            return None;
+        }
+        match attribute {
+            Attribute::CashBalance(value, denomination) => (self.cash.insert(denomination, value)
+                != Some(value))
+            .then_some(Change::Cash(denomination)),
+            Attribute::NetLiquidation(Segment::Total(value), denomination) => {
+                if let Denomination::Specific(currency) = denomination {
+                    self.net_liquidation_currency = Some(currency);
+                }
+                replace(&mut self.net_liquidation, value, Change::NetLiquidation)
+            }
+            Attribute::ExcessLiquidity(Segment::Total(value), _) => {
+                replace(&mut self.excess_liquidity, value, Change::Margin)
+            }
+            Attribute::AvailableFunds(Segment::Total(value), _) => {
+                replace(&mut self.available_funds, value, Change::Margin)
+            }
+            Attribute::InitMarginReq(Segment::Total(value), _) => {
+                replace(&mut self.init_margin_req, value, Change::Margin)
+            }
+            Attribute::MaintenanceMarginReq(Segment::Total(value), _) => {
+                replace(&mut self.maintenance_margin_req, value, Change::Margin)
+            }
+            _ => None,
+        }
+    }
+
+    /// Record that every account update for an account has been received.
+    pub fn account_download_end(&mut self, account_number: &str) {
+        if account_number == self.account_number {
+            self.is_loaded = true;
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return `true` once the initial download of account updates has completed.
+    pub fn is_loaded(&self) -> bool {
+        self.is_loaded
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the position in a given contract, if one is held.
+    pub fn position(&self, contract_id: ContractId) -> Option<&Holding> {
+        self.holdings.get(&contract_id)
+    }
+
+    #[inline]
+    /// Iterate over every position held, in no particular order.
+    pub fn positions(&self) -> impl Iterator<Item = &Holding> {
+        self.holdings.values()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the cash balance in a given denomination. [`Denomination::Base`] is the total
+    /// across every currency, expressed in the base currency of the account.
+    pub fn cash(&self, denomination: Denomination) -> Option<f64> {
+        self.cash.get(&denomination).copied()
+    }
+
+    #[inline]
+    /// Iterate over the cash balance in every denomination, in no particular order.
+    pub fn cash_balances(&self) -> impl Iterator<Item = (Denomination, f64)> + '_ {
+        self.cash.iter().map(|(d, v)| (*d, *v))
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the net liquidation value of the account.
+    pub fn net_liquidation(&self) -> Option<f64> {
+        self.net_liquidation
+    }
+
+    #[must_use]
+    /// Return the net liquidation value of the account in the base currency of a [`Converter`].
+    ///
+    /// # Returns
+    /// The converted value, or [`None`] if the value, its currency, or the rate of its currency
+    /// is not yet known.
+    pub fn net_liquidation_in(&self, converter: &Converter) -> Option<f64> {
+        converter.convert(self.net_liquidation?, self.net_liquidation_currency?)
+    }
+
+    #[must_use]
+    /// Return the total market value of every position held in the base currency of a
+    /// [`Converter`], converting each position from the currency of its contract.
+    ///
+    /// # Returns
+    /// The converted value, or [`None`] if the market value or the rate of any position is not
+    /// yet known.
+    pub fn market_value_in(&self, converter: &Converter) -> Option<f64> {
+        self.holdings
+            .values()
+            .map(|h| converter.convert(h.market_value?, h.contract.currency()))
+            .sum()
+    }
+
+    /// Return every currency in which the account holds cash, positions, or its net liquidation
+    /// value, ex. to pass to [`Converter::subscribe`].
+    pub fn currencies(&self) -> std::collections::BTreeSet<Currency> {
+        self.cash
+            .keys()
+            .filter_map(|d| match d {
+                Denomination::Specific(currency) => Some(*currency),
+                Denomination::Base => None,
+            })
+            .chain(self.holdings.values().map(|h| h.contract.currency()))
+            .chain(self.net_liquidation_currency)
+            .collect()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the excess liquidity of the account, which is its margin headroom before
+    /// liquidation.
+    pub fn excess_liquidity(&self) -> Option<f64> {
+        self.excess_liquidity
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the funds available for opening new positions.
+    pub fn available_funds(&self) -> Option<f64> {
+        self.available_funds
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the initial margin requirement of the account.
+    pub fn init_margin_req(&self) -> Option<f64> {
+        self.init_margin_req
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the maintenance margin requirement of the account.
+    pub fn maintenance_margin_req(&self) -> Option<f64> {
+        self.maintenance_margin_req
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the aggregate P&L of the account.
+    pub fn pnl_summary(&self) -> Option<Pnl> {
+        self.pnl
+    }
+
+    fn update_holding(&mut self, holding: Holding) -> Option<Change> {
+        let contract_id = holding.contract.contract_id();
+        if holding.position.is_zero() {
+            return self
+                .holdings
+                .remove(&contract_id)
+                .map(|_| Change::PositionClosed(contract_id));
+        }
+        (self.holdings.insert(contract_id, holding.clone()) != Some(holding))
+            .then_some(Change::Position(contract_id))
+    }
+}
+
+fn replace(slot: &mut Option<f64>, value: f64, change: Change) -> Option<Change> {
+    (slot.replace(value) != Some(value)).then_some(change)
+}
+
+#[test]
+fn test_portfolio() -> Result<(), Box<dyn std::error::Error>> {
+    let contract: ExchangeProxy<Contract> = toml::from_str(crate::contract::AAPL)?;
+    let contract_id = contract.contract_id();
+    let mut portfolio = Portfolio::new("DU1234567".to_owned());
+
+    let summary = PositionSummary {
+        contract: contract.clone(),
+        position: Quantity::from(100),
+        average_cost: 150.0,
+        account_number: "DU1234567".to_owned(),
+    };
+    assert_eq!(
+        portfolio.position_summary(summary.clone()),
+        Some(Change::Position(contract_id))
+    );
+    assert_eq!(portfolio.position_summary(summary.clone()), None);
+    assert_eq!(
+        portfolio.position_summary(PositionSummary {
+            account_number: "DU7654321".to_owned(),
+            ..summary.clone()
+        }),
+        None
+    );
+
+    portfolio.register_single_position_pnl(3, contract_id);
+    let pnl = PnlSingle {
+        daily: 12.0,
+        unrealized: 500.0,
+        realized: f64::MAX,
+        position_size: 100.0,
+        market_value: 15_500.0,
+    };
+    assert_eq!(
+        portfolio.single_position_pnl(3, pnl),
+        Some(Change::Position(contract_id))
+    );
+    assert_eq!(portfolio.single_position_pnl(4, pnl), None);
+    let holding = portfolio
+        .position(contract_id)
+        .ok_or("position should be held")?;
+    assert_eq!(holding.daily_pnl, Some(12.0));
+    assert_eq!(holding.realized_pnl, None);
+    assert_eq!(holding.market_value, Some(15_500.0));
+
+    let usd = Denomination::Specific(Currency::UsDollar);
+    assert_eq!(
+        portfolio.account_attribute(Attribute::CashBalance(1_000.0, usd), "DU1234567"),
+        Some(Change::Cash(usd))
+    );
+    assert_eq!(
+        portfolio.account_attribute(Attribute::CashBalance(1_000.0, usd), "DU1234567"),
+        None
+    );
+    assert_eq!(
+        portfolio.account_attribute(
+            Attribute::ExcessLiquidity(Segment::Total(8_000.0), usd),
+            "DU1234567"
+        ),
+        Some(Change::Margin)
+    );
+    assert_eq!(
+        portfolio.account_attribute(
+            Attribute::NetLiquidation(Segment::Security(9_000.0), usd),
+            "DU1234567"
+        ),
+        None
+    );
+    assert_eq!(portfolio.cash(usd), Some(1_000.0));
+    assert_eq!(portfolio.excess_liquidity(), Some(8_000.0));
+    assert_eq!(portfolio.net_liquidation(), None);
+
+    let eur = Denomination::Specific(Currency::Euro);
+    portfolio.account_attribute(Attribute::CashBalance(500.0, eur), "DU1234567");
+    portfolio.account_attribute(
+        Attribute::NetLiquidation(Segment::Total(20_000.0), usd),
+        "DU1234567",
+    );
+    assert_eq!(
+        portfolio.currencies().into_iter().collect::<Vec<_>>(),
+        vec![Currency::Euro, Currency::UsDollar]
+    );
+    let mut converter = Converter::new(Currency::Euro);
+    assert_eq!(portfolio.net_liquidation_in(&converter), None);
+    converter.register(crate::requests::RequestId::new(5), Currency::UsDollar, true);
+    converter.price_data(5, crate::tick::Class::Live(crate::tick::Price::Bid(1.0)));
+    converter.price_data(5, crate::tick::Class::Live(crate::tick::Price::Ask(1.5)));
+    assert_eq!(portfolio.net_liquidation_in(&converter), Some(16_000.0));
+    assert_eq!(portfolio.market_value_in(&converter), Some(12_400.0));
+
+    assert_eq!(
+        portfolio.position_summary(PositionSummary {
+            position: Quantity::ZERO,
+            ..summary
+        }),
+        Some(Change::PositionClosed(contract_id))
+    );
+    assert!(portfolio.position(contract_id).is_none());
+    Ok(())
+}
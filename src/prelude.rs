@@ -1,21 +1,25 @@
 pub use crate::account::{Attribute, Tag, TagValue};
-pub use crate::client::{ActiveClient, Builder, Client, Host, Mode};
+pub use crate::client::{
+    ActiveClient, Builder, Client, Dispatch, Host, Mode, OutgoingLog, OverflowPolicy, Session,
+};
 pub use crate::contract::{
     self, Commodity, Contract, ContractId, ContractType, Crypto, ExchangeProxy, Forex, Index,
     NoExchangeProxy, Query, SecFuture, SecOption, SecOptionClass, SecOptionInner, Security, Stock,
+    TickRounding,
 };
 pub use crate::currency::Currency;
 pub use crate::exchange;
-pub use crate::execution::{Exec, Execution, Filter, OrderSide};
+pub use crate::execution::{Exec, Execution, Filter, FilterBuilder, Liquidity, OrderSide};
 pub use crate::figi::Figi;
 pub use crate::market_data::{
     histogram, historical_bar, historical_ticks, live_bar, live_data, live_ticks,
     updating_historical_bar,
 };
-pub use crate::order::{Limit, Market, Order, TimeInForce};
+pub use crate::order::{Limit, Market, MidPrice, Order, OrderAttributes, Relative, TimeInForce};
 pub use crate::payload::{
-    Bar, BarCore, BidAsk, ExchangeId, Fill, HistogramEntry, Last, Midpoint, OrderStatus,
-    OrderStatusCore, Pnl, PnlSingle, Position, PositionSummary, TickData, Trade,
+    Bar, BarCore, BidAsk, ExchangeId, Fill, Histogram, HistogramEntry, Last, Midpoint,
+    OrderStatus, OrderStatusCore, Pnl, PnlSingle, Position, PositionSummary, TickAttributes,
+    TickData, Trade,
 };
 pub use crate::payload::market_depth::{CompleteEntry, Entry, Mpid, Operation, Row};
 pub use crate::tick;
@@ -0,0 +1,377 @@
+use core::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+// The number of decimal places that a `Quantity` stores exactly.
+const SCALE: u32 = 18;
+const ONE: i128 = 10_i128.pow(SCALE);
+
+// === Type definitions ===
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// An exact decimal number of shares, contracts, or units, such as the quantity of an order, an
+/// execution, or a position.
+///
+/// Unlike [`f64`], a [`Quantity`] represents fractional shares and crypto amounts such as `0.1`
+/// exactly, so that sums of executions and positions are free of rounding artifacts. It stores up
+/// to 18 decimal places. Quantities are written to and read from IBKR as plain decimal strings.
+pub struct Quantity(i128);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// An error returned when a [`Quantity`] cannot be created from a string or [`f64`].
+pub enum ParseQuantityError {
+    #[error("Invalid value encountered when attempting to parse quantity: {0}")]
+    /// The value is not a decimal number.
+    Invalid(String),
+    #[error("Quantity {0} has more than 18 decimal places or is too large to represent.")]
+    /// The value is a decimal number that cannot be represented exactly.
+    OutOfRange(String),
+}
+
+// === Type implementations ===
+
+impl Quantity {
+    /// A quantity of zero.
+    pub const ZERO: Self = Self(0);
+
+    #[must_use]
+    #[inline]
+    /// Return `true` if the quantity is zero.
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return `true` if the quantity is greater than zero.
+    pub const fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return `true` if the quantity is less than zero.
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return `true` if the quantity has no fractional part.
+    pub const fn is_integer(self) -> bool {
+        self.0 % ONE == 0
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the number of decimal places needed to write the quantity exactly, which is zero for
+    /// a whole number.
+    pub const fn decimal_places(self) -> u32 {
+        let mut fraction = (self.0 % ONE).unsigned_abs();
+        if fraction == 0 {
+            return 0;
+        }
+        let mut places = SCALE;
+        while fraction.is_multiple_of(10) {
+            fraction /= 10;
+            places -= 1;
+        }
+        places
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the absolute value of the quantity.
+    pub const fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    #[must_use]
+    #[inline]
+    /// Add two quantities, returning [`None`] if the sum is too large to represent.
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(sum) => Some(Self(sum)),
+            None => None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Subtract two quantities, returning [`None`] if the difference is too large to represent.
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(difference) => Some(Self(difference)),
+            None => None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the nearest [`f64`] to the quantity.
+    pub fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = ParseQuantityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseQuantityError::Invalid(s.to_owned());
+        let out_of_range = || ParseQuantityError::OutOfRange(s.to_owned());
+
+        let (number, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i32>().map_err(|_| invalid())?),
+            None => (s, 0),
+        };
+        let (negative, number) = match number.as_bytes().first() {
+            Some(b'-') => (true, &number[1..]),
+            Some(b'+') => (false, &number[1..]),
+            _ => (false, number),
+        };
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        // The value is `digits * 10^(exponent - fraction.len())`, which is stored in units of
+        // 10^-SCALE, so any digits beyond the last representable place must be zeros.
+        let digits = whole.bytes().chain(fraction.bytes());
+        let shift = i64::from(exponent) - fraction.len() as i64 + i64::from(SCALE);
+        let kept = (whole.len() + fraction.len()) as i64 + shift.min(0);
+        let mut units = 0_i128;
+        for (i, digit) in digits.enumerate() {
+            let digit = i128::from(digit - b'0');
+            if (i as i64) < kept {
+                units = units
+                    .checked_mul(10)
+                    .and_then(|u| u.checked_add(digit))
+                    .ok_or_else(out_of_range)?;
+            } else if digit != 0 {
+                return Err(out_of_range());
+            }
+        }
+        if shift > 0 && units != 0 {
+            units = u32::try_from(shift)
+                .ok()
+                .and_then(|shift| 10_i128.checked_pow(shift))
+                .and_then(|scale| units.checked_mul(scale))
+                .ok_or_else(out_of_range)?;
+        }
+        Ok(Self(if negative { -units } else { units }))
+    }
+}
+
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let (whole, fraction) = (
+            self.0.unsigned_abs() / ONE.unsigned_abs(),
+            self.0.unsigned_abs() % ONE.unsigned_abs(),
+        );
+        if fraction == 0 {
+            write!(f, "{sign}{whole}")
+        } else {
+            let fraction = format!("{fraction:018}");
+            write!(f, "{sign}{whole}.{}", fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+impl TryFrom<f64> for Quantity {
+    type Error = ParseQuantityError;
+
+    /// Convert the shortest decimal representation of `value`, so that `0.1_f64` becomes exactly
+    /// `0.1`. Digits beyond the 18th decimal place are rounded.
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if !value.is_finite() {
+            return Err(ParseQuantityError::Invalid(value.to_string()));
+        }
+        ryu::Buffer::new()
+            .format_finite(value)
+            .parse()
+            .or_else(|_| format!("{value:.18}").parse())
+    }
+}
+
+impl From<Quantity> for f64 {
+    #[inline]
+    fn from(value: Quantity) -> Self {
+        value.to_f64()
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($int: ty),*) => {
+        $(
+            impl From<$int> for Quantity {
+                #[inline]
+                fn from(value: $int) -> Self {
+                    Self(i128::from(value) * ONE)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl core::ops::Add for Quantity {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("attempt to add quantities with overflow")
+    }
+}
+
+impl core::ops::AddAssign for Quantity {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl core::ops::Sub for Quantity {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("attempt to subtract quantities with overflow")
+    }
+}
+
+impl core::ops::SubAssign for Quantity {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl core::ops::Neg for Quantity {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+impl core::iter::Sum for Quantity {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |total, q| total + q)
+    }
+}
+
+impl<'a> core::iter::Sum<&'a Quantity> for Quantity {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Quantity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Quantity;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a decimal quantity as a string or number")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(v.into())
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(v.into())
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Quantity::try_from(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[test]
+fn test_quantity() -> Result<(), ParseQuantityError> {
+    for s in [
+        "0",
+        "100",
+        "-3",
+        "0.1",
+        "0.00012345",
+        "-12.5",
+        "123456789.000000000000000001",
+    ] {
+        assert_eq!(s.parse::<Quantity>()?.to_string(), s);
+    }
+    assert_eq!("1.50".parse::<Quantity>()?, "1.5".parse()?);
+    assert_eq!("+.5".parse::<Quantity>()?.to_string(), "0.5");
+    assert_eq!("1.2E3".parse::<Quantity>()?, Quantity::from(1200));
+    assert_eq!("1e-7".parse::<Quantity>()?.to_string(), "0.0000001");
+    assert_eq!("-0".parse::<Quantity>()?, Quantity::ZERO);
+    for s in ["", ".", "-", "1.2.3", "abc", "1e", "1,5"] {
+        assert!(matches!(
+            s.parse::<Quantity>(),
+            Err(ParseQuantityError::Invalid(_))
+        ));
+    }
+    for s in ["0.0000000000000000001", "1e40", "1e-19"] {
+        assert!(matches!(
+            s.parse::<Quantity>(),
+            Err(ParseQuantityError::OutOfRange(_))
+        ));
+    }
+    assert_eq!(
+        "1e-30000".parse::<Quantity>(),
+        Err(ParseQuantityError::OutOfRange("1e-30000".to_owned()))
+    );
+    assert_eq!("0e-30000".parse::<Quantity>()?, Quantity::ZERO);
+    assert_eq!("0e40".parse::<Quantity>()?, Quantity::ZERO);
+
+    // Repeated fractional fills sum exactly, unlike their `f64` equivalents.
+    let fill = Quantity::try_from(0.1)?;
+    let total: Quantity = std::iter::repeat_n(fill, 3).sum();
+    assert_eq!(total, "0.3".parse()?);
+    assert_ne!(0.1 + 0.1 + 0.1, 0.3);
+    assert_eq!(total.to_f64(), 0.3);
+    assert!(Quantity::try_from(f64::NAN).is_err());
+    assert_eq!(Quantity::try_from(1e-20)?, Quantity::ZERO);
+
+    assert!(Quantity::from(5).is_integer() && !fill.is_integer());
+    assert_eq!("-0.00000001".parse::<Quantity>()?.decimal_places(), 8);
+    assert_eq!(Quantity::from(-3).decimal_places(), 0);
+    assert!((-fill).is_negative() && (fill - total).abs() == "0.2".parse()?);
+    assert!(fill < total && -total < Quantity::ZERO);
+    Ok(())
+}
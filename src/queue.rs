@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::Notify;
+
+use crate::client::OverflowPolicy;
+use crate::frame::Frame;
+use crate::message::In;
+
+#[derive(Debug)]
+struct Shared {
+    queue: Mutex<VecDeque<Frame>>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    not_empty: Notify,
+    not_full: Notify,
+    tx_closed: AtomicBool,
+    rx_closed: AtomicBool,
+}
+
+impl Shared {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, VecDeque<Frame>> {
+        // A panic while holding the lock cannot leave the queue itself in an invalid state, so
+        // a poisoned lock is still safe to use.
+        self.queue
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// The error returned when the receiving half of the queue has been dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Closed(pub(crate) Frame);
+
+impl std::fmt::Display for Closed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reader queue closed")
+    }
+}
+
+/// Create a bounded queue of incoming messages that applies `policy` when it is full.
+pub(crate) fn channel(capacity: usize, policy: OverflowPolicy) -> (Sender, Receiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::with_capacity(capacity.max(1))),
+        capacity: capacity.max(1),
+        policy,
+        not_empty: Notify::new(),
+        not_full: Notify::new(),
+        tx_closed: AtomicBool::new(false),
+        rx_closed: AtomicBool::new(false),
+    });
+    (Sender(Arc::clone(&shared)), Receiver(shared))
+}
+
+#[derive(Debug)]
+pub(crate) struct Sender(Arc<Shared>);
+
+impl Sender {
+    /// Push a message onto the queue, waiting for room only if the overflow policy requires it.
+    pub(crate) async fn send(&self, msg: Frame) -> Result<(), Closed> {
+        let mut msg = Some(msg);
+        loop {
+            {
+                let mut queue = self.0.lock();
+                let Some(m) = msg.take() else {
+                    unreachable!("The message is only taken when the loop returns")
+                };
+                if self.0.rx_closed.load(Ordering::Acquire) {
+                    return Err(Closed(m));
+                }
+                if queue.len() < self.0.capacity {
+                    queue.push_back(m);
+                    drop(queue);
+                    self.0.not_empty.notify_one();
+                    return Ok(());
+                }
+                match self.0.policy {
+                    OverflowPolicy::Block => msg = Some(m),
+                    OverflowPolicy::DropOldest => {
+                        queue.pop_front();
+                        queue.push_back(m);
+                        drop(queue);
+                        self.0.not_empty.notify_one();
+                        return Ok(());
+                    }
+                    OverflowPolicy::CoalesceMarketData => {
+                        let existing = coalesce_key(&m).and_then(|key| {
+                            queue
+                                .iter_mut()
+                                .find(|queued| coalesce_key(queued) == Some(key))
+                        });
+                        match existing {
+                            Some(queued) => {
+                                *queued = m;
+                                return Ok(());
+                            }
+                            None => msg = Some(m),
+                        }
+                    }
+                }
+            }
+            self.0.not_full.notified().await;
+        }
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.0.tx_closed.store(true, Ordering::Release);
+        self.0.not_empty.notify_one();
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Receiver(Arc<Shared>);
+
+impl Receiver {
+    /// Take the oldest message from the queue, waiting until one arrives.
+    ///
+    /// Returns `None` once the queue is empty and the sending half has been dropped.
+    pub(crate) async fn recv(&mut self) -> Option<Frame> {
+        loop {
+            {
+                let mut queue = self.0.lock();
+                if let Some(msg) = queue.pop_front() {
+                    drop(queue);
+                    self.0.not_full.notify_one();
+                    return Some(msg);
+                }
+                if self.0.tx_closed.load(Ordering::Acquire) {
+                    return None;
+                }
+            }
+            self.0.not_empty.notified().await;
+        }
+    }
+
+    /// Take the oldest message from the queue without waiting.
+    pub(crate) fn try_recv(&mut self) -> Option<Frame> {
+        let msg = self.0.lock().pop_front();
+        if msg.is_some() {
+            self.0.not_full.notify_one();
+        }
+        msg
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    /// The number of messages currently waiting in the queue.
+    pub(crate) fn len(&self) -> usize {
+        self.0.lock().len()
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        self.0.rx_closed.store(true, Ordering::Release);
+        self.0.not_full.notify_one();
+    }
+}
+
+/// For tick messages that carry a single latest value, returns the message type, request ID,
+/// and tick type that identify which queued message a newer one supersedes.
+fn coalesce_key(msg: &Frame) -> Option<(&str, &str, &str)> {
+    let mut fields = msg.fields();
+    let id = fields.next()?;
+    match id.parse().ok()? {
+        In::TickPrice | In::TickSize | In::TickGeneric | In::TickString => {
+            Some((id, fields.nth(1)?, fields.next()?))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+fn message(fields: &str) -> Frame {
+    Frame::from_fields(&fields.split('|').collect::<Vec<_>>())
+}
+
+#[tokio::test]
+async fn test_overflow_policy() {
+    let (tx, mut rx) = channel(2, OverflowPolicy::DropOldest);
+    for i in 0..3 {
+        tx.send(message(&format!("49|1|{i}"))).await.unwrap();
+    }
+    assert_eq!(rx.recv().await, Some(message("49|1|1")));
+    assert_eq!(rx.recv().await, Some(message("49|1|2")));
+    assert_eq!(rx.try_recv(), None);
+
+    let (tx, mut rx) = channel(2, OverflowPolicy::CoalesceMarketData);
+    tx.send(message("1|6|7|1|100.0|1|0")).await.unwrap();
+    tx.send(message("1|6|8|1|50.0|1|0")).await.unwrap();
+    tx.send(message("1|6|7|1|101.0|1|0")).await.unwrap();
+    assert_eq!(rx.len(), 2);
+    assert_eq!(rx.recv().await, Some(message("1|6|7|1|101.0|1|0")));
+    drop(tx);
+    assert_eq!(rx.recv().await, Some(message("1|6|8|1|50.0|1|0")));
+    assert_eq!(rx.recv().await, None);
+}
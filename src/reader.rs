@@ -1,24 +1,31 @@
-use bytes::{Buf, BytesMut};
-use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
+use bytes::BytesMut;
+use tokio::io::AsyncReadExt;
 use tracing::{error, info, warn};
 
+use crate::client::{Direction, MessageTap};
+use crate::frame::Frame;
+use crate::transport::ReadHalf;
+
 #[derive(Debug)]
 pub struct Reader {
-    inner: OwnedReadHalf,
-    tx: tokio::sync::mpsc::Sender<Vec<String>>,
+    inner: ReadHalf,
+    tx: crate::queue::Sender,
     disconnect: tokio_util::sync::CancellationToken,
+    tap: Option<MessageTap>,
 }
 
 impl Reader {
     pub fn new(
-        r_reader: OwnedReadHalf,
-        tx: tokio::sync::mpsc::Sender<Vec<String>>,
+        r_reader: ReadHalf,
+        tx: crate::queue::Sender,
         r_disconnect: tokio_util::sync::CancellationToken,
+        tap: Option<MessageTap>,
     ) -> Self {
         Self {
             inner: r_reader,
             tx,
             disconnect: r_disconnect,
+            tap,
         }
     }
 
@@ -38,10 +45,10 @@ impl Reader {
                                 Err(e) => error!(error=%e, "IO Error when receiving message.")
                             }
                         }
-                        let msg = buf.chunk()
-                        .split(|b| *b == 0)
-                        .map(|s| core::str::from_utf8(s).unwrap_or("").to_owned())
-                        .collect::<Vec<String>>();
+                        let msg = Frame::new(buf.freeze());
+                        if let Some(tap) = &self.tap {
+                            tap.observe(Direction::Inbound, &msg.to_vec());
+                        }
                         match self.tx.send(msg).await {
                             Ok(()) => (),
                             Err(e) => error!(%e, "IO Error when sending message. Client receiver may have dropped."),
@@ -0,0 +1,435 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc;
+
+use crate::{
+    client::{decode_msg_local, decode_msg_remote, Direction, MessageTap},
+    constants::{TO_CLIENT_CHANNEL_SIZE, TO_WRAPPER_CHANNEL_SIZE},
+    frame::Frame,
+    message::{Inbox, ToClient, ToWrapper},
+    wrapper::{LocalWrapper, Wrapper},
+};
+
+/// Split a single recorded message into its fields.
+///
+/// Recorded messages are expected to use the same NUL-separated layout that the API uses on the
+/// wire (without the four-byte length prefix). For convenience, messages recorded in a
+/// human-readable format may instead use `|` as the separator.
+///
+/// # Arguments
+/// * `message` - The recorded message
+///
+/// # Returns
+/// The fields of the message, in order, suitable for [`replay_local`] or [`replay_remote`].
+#[must_use]
+pub fn parse_message(message: &str) -> Vec<String> {
+    let separator = if message.contains('\0') { '\0' } else { '|' };
+    message
+        .split(separator)
+        .map(std::borrow::ToOwned::to_owned)
+        .collect()
+}
+
+/// Holds the internal channels that the decoding functions require, so that recorded messages can
+/// be decoded without a connection to the API.
+#[derive(Debug)]
+struct Channels {
+    client_tx: mpsc::Sender<ToClient>,
+    client_rx: mpsc::Receiver<ToClient>,
+    wrapper_rx: Inbox,
+    _wrapper_tx: mpsc::Sender<ToWrapper>,
+}
+
+impl Channels {
+    fn new() -> Self {
+        let (client_tx, client_rx) = mpsc::channel(TO_CLIENT_CHANNEL_SIZE);
+        let (_wrapper_tx, wrapper_rx) = mpsc::channel(TO_WRAPPER_CHANNEL_SIZE);
+        Self {
+            client_tx,
+            client_rx,
+            wrapper_rx: Inbox::new(wrapper_rx, std::sync::Arc::default()),
+            _wrapper_tx,
+        }
+    }
+
+    // Nobody is listening on the client side of a replay, so anything sent there is discarded to
+    // keep the channel from filling up.
+    fn drain(&mut self) {
+        while self.client_rx.try_recv().is_ok() {}
+    }
+}
+
+/// Feed a sequence of recorded messages through the same decoding logic used by a live
+/// [`crate::client::ActiveClient`], invoking the callbacks of a [`LocalWrapper`].
+///
+/// No TCP connection is made. Messages that fail to decode are logged exactly as they would
+/// be on a live connection, and replay continues with the next message.
+///
+/// # Arguments
+/// * `wrapper` - The wrapper whose callbacks will be invoked
+/// * `messages` - The recorded messages, each of which is a vector of fields (see
+///   [`parse_message`])
+///
+/// # Returns
+/// The number of messages that were replayed.
+pub async fn replay_local<W, I>(wrapper: &mut W, messages: I) -> usize
+where
+    W: LocalWrapper,
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let mut channels = Channels::new();
+    let mut count = 0;
+    for fields in messages {
+        decode_msg_local(
+            Frame::from_fields(&fields),
+            wrapper,
+            &mut channels.client_tx,
+            &mut channels.wrapper_rx,
+        )
+        .await;
+        channels.drain();
+        count += 1;
+    }
+    count
+}
+
+/// Feed a sequence of recorded messages through the same decoding logic used by a live
+/// [`crate::client::ActiveClient`], invoking the callbacks of a [`Wrapper`].
+///
+/// No TCP connection is made. Messages that fail to decode are logged exactly as they would
+/// be on a live connection, and replay continues with the next message.
+///
+/// # Arguments
+/// * `wrapper` - The wrapper whose callbacks will be invoked
+/// * `messages` - The recorded messages, each of which is a vector of fields (see
+///   [`parse_message`])
+///
+/// # Returns
+/// The number of messages that were replayed.
+pub async fn replay_remote<W, I>(wrapper: &mut W, messages: I) -> usize
+where
+    W: Wrapper,
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let mut channels = Channels::new();
+    let mut count = 0;
+    for fields in messages {
+        decode_msg_remote(
+            Frame::from_fields(&fields),
+            wrapper,
+            &mut channels.client_tx,
+            &mut channels.wrapper_rx,
+        )
+        .await;
+        channels.drain();
+        count += 1;
+    }
+    count
+}
+
+/// A single inbound message archived by a [`Recorder`], along with the time at which it arrived.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordedFrame {
+    /// The date and time at which the message was received.
+    pub datetime: DateTime<Utc>,
+    /// The fields of the message.
+    pub fields: Vec<String>,
+}
+
+/// Archives every inbound message of a live session to a file, so that the session can later be
+/// replayed with [`replay_session_local`] or [`replay_session_remote`].
+///
+/// Install the recorder by passing [`Recorder::tap`] to
+/// [`crate::client::Builder::with_message_tap`]. Each message is written as one line holding its
+/// arrival time in microseconds since the Unix epoch, a tab, and its NUL-separated fields, with
+/// backslashes and line breaks escaped. Lines are flushed as they are written, so a session is
+/// preserved up to the last message even if the process exits abruptly.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<std::io::LineWriter<std::fs::File>>>,
+}
+
+/// The pace at which recorded messages are replayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Speed {
+    #[default]
+    /// Replay every message immediately.
+    Instant,
+    /// Wait between messages for as long as elapsed between them when they were recorded.
+    Original,
+    /// Wait between messages for the recorded gap divided by this factor (ex. `10.0` replays ten
+    /// times faster than real time).
+    Accelerated(f64),
+}
+
+impl Recorder {
+    /// Create a new recorder that writes to a file, replacing any existing contents.
+    ///
+    /// # Arguments
+    /// * `path` - The file to which messages will be written.
+    ///
+    /// # Errors
+    /// Returns any error encountered while creating the file.
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            file: Arc::new(Mutex::new(std::io::LineWriter::new(std::fs::File::create(
+                path,
+            )?))),
+        })
+    }
+
+    #[must_use]
+    /// Create a [`MessageTap`] that writes every inbound message to this recorder's file.
+    /// Failures to write are logged and otherwise ignored, so that they never interrupt the
+    /// session.
+    pub fn tap(&self) -> MessageTap {
+        let recorder = self.clone();
+        MessageTap::new(move |direction, fields| {
+            if direction == Direction::Inbound {
+                if let Err(e) = recorder.record(Utc::now(), fields) {
+                    tracing::warn!("Failed to record inbound message: {e}");
+                }
+            }
+        })
+    }
+
+    /// Write a single message to the file.
+    ///
+    /// # Arguments
+    /// * `datetime` - The date and time at which the message was received.
+    /// * `fields` - The fields of the message.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing to the file.
+    pub fn record(&self, datetime: DateTime<Utc>, fields: &[String]) -> std::io::Result<()> {
+        let mut line = datetime.timestamp_micros().to_string();
+        line.push('\t');
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                line.push('\0');
+            }
+            for c in field.chars() {
+                match c {
+                    '\\' => line.push_str("\\\\"),
+                    '\n' => line.push_str("\\n"),
+                    '\r' => line.push_str("\\r"),
+                    c => line.push(c),
+                }
+            }
+        }
+        line.push('\n');
+        self.file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_all(line.as_bytes())
+    }
+}
+
+/// Read a session archived by a [`Recorder`].
+///
+/// # Arguments
+/// * `path` - The file to which the session was written.
+///
+/// # Returns
+/// The recorded messages, in the order in which they arrived.
+///
+/// # Errors
+/// Returns any error encountered while reading the file, or an error of kind
+/// [`std::io::ErrorKind::InvalidData`] if a line is not a recorded message.
+pub fn read_session(path: impl AsRef<Path>) -> std::io::Result<Vec<RecordedFrame>> {
+    let invalid = |line: usize| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Line {line} is not a recorded message."),
+        )
+    };
+    std::fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let (micros, message) = line.split_once('\t').ok_or_else(|| invalid(i + 1))?;
+            let datetime = micros
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or_else(|| invalid(i + 1))?;
+            let fields = message.split('\0').map(unescape).collect();
+            Ok(RecordedFrame { datetime, fields })
+        })
+        .collect()
+}
+
+fn unescape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some(c) => out.push(c),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Wait for the gap between two recorded messages at the given speed.
+async fn pace(speed: Speed, previous: Option<DateTime<Utc>>, next: DateTime<Utc>) {
+    let Some(gap) = previous.and_then(|previous| (next - previous).to_std().ok()) else {
+        return;
+    };
+    let gap = match speed {
+        Speed::Instant => return,
+        Speed::Original => gap,
+        Speed::Accelerated(factor) if factor > 0.0 && factor.is_finite() => gap.div_f64(factor),
+        Speed::Accelerated(_) => return,
+    };
+    tokio::time::sleep(gap).await;
+}
+
+/// Replay a session archived by a [`Recorder`] through the decoding logic used by a live
+/// [`crate::client::ActiveClient`], invoking the callbacks of a [`LocalWrapper`].
+///
+/// # Arguments
+/// * `wrapper` - The wrapper whose callbacks will be invoked
+/// * `frames` - The recorded messages (see [`read_session`])
+/// * `speed` - The pace at which to replay the messages
+///
+/// # Returns
+/// The number of messages that were replayed.
+pub async fn replay_session_local<W, I>(wrapper: &mut W, frames: I, speed: Speed) -> usize
+where
+    W: LocalWrapper,
+    I: IntoIterator<Item = RecordedFrame>,
+{
+    let mut channels = Channels::new();
+    let (mut count, mut previous) = (0, None);
+    for frame in frames {
+        pace(speed, previous, frame.datetime).await;
+        previous = Some(frame.datetime);
+        decode_msg_local(
+            Frame::from_fields(&frame.fields),
+            wrapper,
+            &mut channels.client_tx,
+            &mut channels.wrapper_rx,
+        )
+        .await;
+        channels.drain();
+        count += 1;
+    }
+    count
+}
+
+/// Replay a session archived by a [`Recorder`] through the decoding logic used by a live
+/// [`crate::client::ActiveClient`], invoking the callbacks of a [`Wrapper`].
+///
+/// # Arguments
+/// * `wrapper` - The wrapper whose callbacks will be invoked
+/// * `frames` - The recorded messages (see [`read_session`])
+/// * `speed` - The pace at which to replay the messages
+///
+/// # Returns
+/// The number of messages that were replayed.
+pub async fn replay_session_remote<W, I>(wrapper: &mut W, frames: I, speed: Speed) -> usize
+where
+    W: Wrapper,
+    I: IntoIterator<Item = RecordedFrame>,
+{
+    let mut channels = Channels::new();
+    let (mut count, mut previous) = (0, None);
+    for frame in frames {
+        pace(speed, previous, frame.datetime).await;
+        previous = Some(frame.datetime);
+        decode_msg_remote(
+            Frame::from_fields(&frame.fields),
+            wrapper,
+            &mut channels.client_tx,
+            &mut channels.wrapper_rx,
+        )
+        .await;
+        channels.drain();
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct BoundOrders(Vec<(i64, i64, i64)>, Vec<crate::payload::DecodeErrorReport>);
+
+#[cfg(test)]
+impl LocalWrapper for BoundOrders {
+    fn order_bound(
+        &mut self,
+        api_client_id: i64,
+        api_order_id: i64,
+        permanent_id: i64,
+    ) -> impl std::future::Future {
+        self.0.push((api_client_id, api_order_id, permanent_id));
+        async {}
+    }
+
+    fn decode_error(
+        &mut self,
+        report: crate::payload::DecodeErrorReport,
+    ) -> impl std::future::Future {
+        self.1.push(report);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_replay_local() {
+    let mut wrapper = BoundOrders::default();
+    let messages = [
+        parse_message("100|123456|7|42|"),
+        parse_message("100\x00654321\x007\x0043\x00"),
+        parse_message("not a message"),
+    ];
+    assert_eq!(replay_local(&mut wrapper, messages).await, 3);
+    assert_eq!(wrapper.0, vec![(7, 42, 123_456), (7, 43, 654_321)]);
+    assert_eq!(wrapper.1.len(), 1);
+    assert_eq!(wrapper.1[0].kind, "invalid in msg");
+    assert_eq!(wrapper.1[0].fields, vec!["not a message"]);
+}
+
+#[tokio::test]
+async fn test_record_session() -> std::io::Result<()> {
+    let path = std::env::temp_dir().join(format!("ibapi_session_{}.txt", std::process::id()));
+    let recorder = Recorder::create(&path)?;
+    let tap = recorder.tap();
+    tap.observe(Direction::Inbound, &parse_message("100|123456|7|42|"));
+    tap.observe(Direction::Outbound, &parse_message("49|1|"));
+    let start = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    recorder.record(start, &["odd\\field\nwith breaks".to_owned()])?;
+    recorder.record(
+        start + chrono::TimeDelta::milliseconds(20),
+        &parse_message("100|654321|7|43|"),
+    )?;
+    drop((tap, recorder));
+
+    let frames = read_session(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].fields, parse_message("100|123456|7|42|"));
+    assert_eq!(frames[1].fields, vec!["odd\\field\nwith breaks"]);
+    assert_eq!(
+        frames[2].datetime - frames[1].datetime,
+        chrono::TimeDelta::milliseconds(20)
+    );
+
+    let mut wrapper = BoundOrders::default();
+    let replayed = replay_session_local(&mut wrapper, frames, Speed::Accelerated(1_000.0)).await;
+    assert_eq!(replayed, 3);
+    assert_eq!(wrapper.0, vec![(7, 42, 123_456), (7, 43, 654_321)]);
+    assert_eq!(wrapper.1.len(), 1);
+    Ok(())
+}
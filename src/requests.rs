@@ -0,0 +1,523 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::frame::Frame;
+use crate::message::In;
+
+// The number of completed or cancelled request IDs that are remembered, so that a late callback
+// for one of them can be told apart from a callback for a request that was never made.
+const MAX_FINISHED: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// The kinds of requests whose callbacks are matched to their request ID, as reported by
+/// [`crate::client::Client::get_outstanding_requests`].
+pub enum Kind {
+    /// A [`crate::client::Client::req_market_data`] subscription.
+    MarketData,
+    /// A [`crate::client::Client::req_market_data`] snapshot.
+    MarketDataSnapshot,
+    /// A [`crate::client::Client::req_market_depth`] subscription.
+    MarketDepth,
+    /// A [`crate::client::Client::req_tick_by_tick_data`] subscription.
+    TickByTick,
+    /// A [`crate::client::Client::req_real_time_bars`] subscription.
+    RealTimeBars,
+    /// A [`crate::client::Client::req_historical_bar`] or
+    /// [`crate::client::Client::req_continuous_future_historical_bar`] request.
+    HistoricalBar,
+    /// A [`crate::client::Client::req_updating_historical_bar`] subscription.
+    UpdatingHistoricalBar,
+    /// A [`crate::client::Client::req_head_timestamp`] request.
+    HeadTimestamp,
+    /// A [`crate::client::Client::req_histogram_data`] request.
+    HistogramData,
+    /// A [`crate::client::Client::req_historical_ticks`] request.
+    HistoricalTicks,
+    /// A [`crate::client::Client::req_historical_news`] request.
+    HistoricalNews,
+    /// A [`crate::client::Client::req_smart_components`] request.
+    SmartComponents,
+    /// A [`crate::client::Client::req_option_parameters`] request.
+    OptionParameters,
+    /// A [`crate::client::Client::req_scanner_subscription`] subscription.
+    ScannerSubscription,
+    /// A [`crate::client::Client::req_account_summary`] subscription.
+    AccountSummary,
+    /// A [`crate::client::Client::req_positions_multi`] subscription.
+    PositionsMulti,
+    /// A [`crate::client::Client::req_account_updates_multi`] subscription.
+    AccountUpdatesMulti,
+    /// A [`crate::client::Client::req_pnl`] subscription.
+    Pnl,
+    /// A [`crate::client::Client::req_single_position_pnl`] subscription.
+    SinglePositionPnl,
+    /// A [`crate::client::Client::req_executions`] request.
+    Executions,
+    /// A request for contract details, made when a contract is created.
+    ContractData,
+    /// A [`crate::client::Client::req_user_info`] request.
+    UserInfo,
+}
+
+impl Kind {
+    #[inline]
+    // Whether the message is the last response to a request of this kind. Subscriptions are only
+    // finished when they are cancelled.
+    fn is_finished_by(self, msg: In) -> bool {
+        matches!(
+            (self, msg),
+            (Self::MarketDataSnapshot, In::TickSnapshotEnd)
+                | (
+                    Self::HistoricalBar,
+                    In::HistoricalData | In::HistoricalSchedule
+                )
+                | (Self::HeadTimestamp, In::HeadTimestamp)
+                | (Self::HistogramData, In::HistogramData)
+                | (
+                    Self::HistoricalTicks,
+                    In::HistoricalTicks | In::HistoricalTicksBidAsk | In::HistoricalTicksLast
+                )
+                | (Self::HistoricalNews, In::HistoricalNewsEnd)
+                | (Self::SmartComponents, In::SmartComponents)
+                | (
+                    Self::OptionParameters,
+                    In::SecurityDefinitionOptionParameterEnd
+                )
+                | (Self::Executions, In::ExecutionDataEnd)
+                | (Self::ContractData, In::ContractDataEnd)
+                | (Self::UserInfo, In::UserInfo)
+        )
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::MarketData => "market data",
+            Self::MarketDataSnapshot => "market data snapshot",
+            Self::MarketDepth => "market depth",
+            Self::TickByTick => "tick-by-tick",
+            Self::RealTimeBars => "real time bars",
+            Self::HistoricalBar => "historical bar",
+            Self::UpdatingHistoricalBar => "updating historical bar",
+            Self::HeadTimestamp => "head timestamp",
+            Self::HistogramData => "histogram data",
+            Self::HistoricalTicks => "historical ticks",
+            Self::HistoricalNews => "historical news",
+            Self::SmartComponents => "smart components",
+            Self::OptionParameters => "option parameters",
+            Self::ScannerSubscription => "scanner subscription",
+            Self::AccountSummary => "account summary",
+            Self::PositionsMulti => "positions multi",
+            Self::AccountUpdatesMulti => "account updates multi",
+            Self::Pnl => "P&L",
+            Self::SinglePositionPnl => "single position P&L",
+            Self::Executions => "executions",
+            Self::ContractData => "contract data",
+            Self::UserInfo => "user info",
+        })
+    }
+}
+
+/// Marker types for the categories of [`RequestId`], one for each kind of request that can be
+/// cancelled.
+pub mod category {
+    use super::Kind;
+
+    mod sealed {
+        pub trait Sealed {}
+    }
+
+    /// A category of request, which is carried by a [`super::RequestId`] at compile time.
+    pub trait Category: sealed::Sealed {
+        /// The kind of request that is made with an ID of this category.
+        const KIND: Kind;
+    }
+
+    macro_rules! make_category {
+        ($($(#[doc = $doc:literal])* $name:ident;)*) => {
+            $(
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+                $(#[doc = $doc])*
+                pub enum $name {}
+
+                impl sealed::Sealed for $name {}
+
+                impl Category for $name {
+                    const KIND: Kind = Kind::$name;
+                }
+            )*
+        };
+    }
+
+    make_category!(
+        /// A [`crate::client::Client::req_market_data`] subscription or snapshot.
+        MarketData;
+        /// A [`crate::client::Client::req_market_depth`] subscription.
+        MarketDepth;
+        /// A [`crate::client::Client::req_tick_by_tick_data`] subscription.
+        TickByTick;
+        /// A [`crate::client::Client::req_real_time_bars`] subscription.
+        RealTimeBars;
+        /// A [`crate::client::Client::req_updating_historical_bar`] subscription.
+        UpdatingHistoricalBar;
+        /// A [`crate::client::Client::req_head_timestamp`] request.
+        HeadTimestamp;
+        /// A [`crate::client::Client::req_histogram_data`] request.
+        HistogramData;
+        /// A [`crate::client::Client::req_scanner_subscription`] subscription.
+        ScannerSubscription;
+        /// A [`crate::client::Client::req_account_summary`] subscription.
+        AccountSummary;
+        /// A [`crate::client::Client::req_positions_multi`] subscription.
+        PositionsMulti;
+        /// A [`crate::client::Client::req_account_updates_multi`] subscription.
+        AccountUpdatesMulti;
+        /// A [`crate::client::Client::req_pnl`] subscription.
+        Pnl;
+        /// A [`crate::client::Client::req_single_position_pnl`] subscription.
+        SinglePositionPnl;
+    );
+}
+
+/// The ID of a request of category `C`, which is returned when the request is made and is
+/// required to cancel it, so that the ID of one kind of request cannot be used to cancel another.
+///
+/// The raw ID, which is the `req_id` passed to the wrapper's callbacks, is available with
+/// [`RequestId::get`], and an ID compares equal to its raw value. A raw ID that was stored
+/// elsewhere can only be converted back explicitly with [`RequestId::new`].
+///
+/// ```no_run
+/// use ibapi::requests::{category, RequestId};
+///
+/// async fn cancel(
+///     client: &mut ibapi::client::ActiveClient,
+///     req_id: RequestId<category::MarketData>,
+/// ) -> std::io::Result<()> {
+///     client.cancel_market_data(req_id).await
+/// }
+/// ```
+///
+/// The ID of a market data request cannot cancel real time bars:
+///
+/// ```compile_fail
+/// use ibapi::requests::{category, RequestId};
+///
+/// async fn cancel(
+///     client: &mut ibapi::client::ActiveClient,
+///     req_id: RequestId<category::MarketData>,
+/// ) -> std::io::Result<()> {
+///     client.cancel_real_time_bars(req_id).await
+/// }
+/// ```
+///
+/// Nor can a bare integer be converted into an ID implicitly:
+///
+/// ```compile_fail
+/// use ibapi::requests::{category, RequestId};
+///
+/// async fn cancel(client: &mut ibapi::client::ActiveClient) -> std::io::Result<()> {
+///     client.cancel_real_time_bars(1.into()).await
+/// }
+/// ```
+pub struct RequestId<C> {
+    id: i64,
+    category: PhantomData<fn() -> C>,
+}
+
+impl<C> RequestId<C> {
+    #[inline]
+    #[must_use]
+    /// Create the ID of a request of category `C` from its raw ID, ex. one that was stored
+    /// elsewhere. It is the caller's responsibility that the request is of category `C`.
+    pub const fn new(id: i64) -> Self {
+        Self {
+            id,
+            category: PhantomData,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Return the raw request ID.
+    pub const fn get(self) -> i64 {
+        self.id
+    }
+}
+
+impl<C> Clone for RequestId<C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for RequestId<C> {}
+
+impl<C> PartialEq for RequestId<C> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<C> Eq for RequestId<C> {}
+
+impl<C> PartialOrd for RequestId<C> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C> Ord for RequestId<C> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<C> std::hash::Hash for RequestId<C> {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<C> PartialEq<i64> for RequestId<C> {
+    #[inline]
+    fn eq(&self, other: &i64) -> bool {
+        self.id == *other
+    }
+}
+
+impl<C> PartialEq<RequestId<C>> for i64 {
+    #[inline]
+    fn eq(&self, other: &RequestId<C>) -> bool {
+        *self == other.id
+    }
+}
+
+impl<C> From<RequestId<C>> for i64 {
+    #[inline]
+    fn from(id: RequestId<C>) -> Self {
+        id.id
+    }
+}
+
+impl<C: category::Category> std::fmt::Debug for RequestId<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestId")
+            .field("id", &self.id)
+            .field("kind", &C::KIND)
+            .finish()
+    }
+}
+
+impl<C> std::fmt::Display for RequestId<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+impl<C> Serialize for RequestId<C> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.id.serialize(serializer)
+    }
+}
+
+impl<'de, C> Deserialize<'de> for RequestId<C> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Determines what happens when a callback arrives for a request ID that the client does not
+/// know, or whose request has already been cancelled or completed. Such callbacks usually mean
+/// that a subscription was mismatched, which otherwise shows up as data that silently goes
+/// missing.
+pub enum UnknownPolicy {
+    /// Pass the callback to the wrapper without comment.
+    Dispatch,
+    #[default]
+    /// Emit a [`tracing`] warning and pass the callback to the wrapper.
+    Warn,
+    /// Emit a [`tracing`] error and discard the callback, so that the wrapper only sees
+    /// callbacks for outstanding requests.
+    Reject,
+}
+
+// The outstanding requests of a client, which are registered as they are sent and removed when
+// they are cancelled or their last response arrives.
+#[derive(Debug)]
+pub(crate) struct Registry {
+    policy: UnknownPolicy,
+    outstanding: BTreeMap<i64, Kind>,
+    finished: BTreeMap<i64, Kind>,
+}
+
+impl Default for Registry {
+    // Without a client to register requests, such as when messages are replayed, every request
+    // ID is unknown, so callbacks are passed on without comment.
+    fn default() -> Self {
+        Self::new(UnknownPolicy::Dispatch)
+    }
+}
+
+impl Registry {
+    #[inline]
+    pub(crate) fn new(policy: UnknownPolicy) -> Self {
+        Self {
+            policy,
+            outstanding: BTreeMap::new(),
+            finished: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn register(&mut self, req_id: i64, kind: Kind) {
+        self.finished.remove(&req_id);
+        self.outstanding.insert(req_id, kind);
+    }
+
+    #[inline]
+    pub(crate) fn finish(&mut self, req_id: i64) {
+        if let Some(kind) = self.outstanding.remove(&req_id) {
+            self.finished.insert(req_id, kind);
+            if self.finished.len() > MAX_FINISHED {
+                self.finished.pop_first();
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn outstanding(&self) -> BTreeMap<i64, Kind> {
+        self.outstanding.clone()
+    }
+
+    // Match an incoming message to its request, finishing the request if this is its last
+    // response. Returns whether the message should be passed to the wrapper.
+    pub(crate) fn observe(&mut self, frame: &Frame) -> bool {
+        let Some((msg, req_id)) = request_id(frame) else {
+            return true;
+        };
+        if let Some(&kind) = self.outstanding.get(&req_id) {
+            if kind.is_finished_by(msg) {
+                self.finish(req_id);
+            }
+            return true;
+        }
+        let finished = self.finished.get(&req_id);
+        match (self.policy, finished) {
+            (UnknownPolicy::Dispatch, _) => true,
+            (UnknownPolicy::Warn, Some(kind)) => {
+                warn!(req_id, ?msg, %kind, "Callback received for a finished request");
+                true
+            }
+            (UnknownPolicy::Warn, None) => {
+                warn!(req_id, ?msg, "Callback received for an unknown request");
+                true
+            }
+            (UnknownPolicy::Reject, Some(kind)) => {
+                error!(req_id, ?msg, %kind, "Discarded callback for a finished request");
+                false
+            }
+            (UnknownPolicy::Reject, None) => {
+                error!(req_id, ?msg, "Discarded callback for an unknown request");
+                false
+            }
+        }
+    }
+}
+
+// For incoming messages that answer a request of a registered kind, returns the message and its
+// request ID. Executions that are not requested arrive with a request ID of -1, and are ignored.
+fn request_id(frame: &Frame) -> Option<(In, i64)> {
+    let mut fields = frame.fields();
+    let msg = fields.next()?.parse().ok()?;
+    let index = match msg {
+        In::TickPrice
+        | In::TickSize
+        | In::MarketDepthL2
+        | In::TickGeneric
+        | In::TickString
+        | In::RealTimeBars
+        | In::ScannerData
+        | In::ContractDataEnd
+        | In::ExecutionDataEnd
+        | In::TickSnapshotEnd
+        | In::MarketDataType
+        | In::AccountSummary
+        | In::AccountSummaryEnd
+        | In::PositionMulti
+        | In::PositionMultiEnd
+        | In::AccountUpdateMulti
+        | In::AccountUpdateMultiEnd => 1,
+        In::ContractData
+        | In::ExecutionData
+        | In::MarketDepth
+        | In::HistoricalData
+        | In::TickOptionComputation
+        | In::TickReqParams
+        | In::SmartComponents
+        | In::HeadTimestamp
+        | In::HistogramData
+        | In::HistoricalDataUpdate
+        | In::Pnl
+        | In::PnlSingle
+        | In::HistoricalTicks
+        | In::HistoricalTicksBidAsk
+        | In::HistoricalTicksLast
+        | In::TickByTick
+        | In::HistoricalSchedule
+        | In::HistoricalNews
+        | In::HistoricalNewsEnd
+        | In::SecurityDefinitionOptionParameter
+        | In::SecurityDefinitionOptionParameterEnd
+        | In::UserInfo => 0,
+        _ => return None,
+    };
+    let req_id = fields.nth(index)?.parse().ok()?;
+    (req_id >= 0).then_some((msg, req_id))
+}
+
+#[test]
+fn test_registry() {
+    let mut registry = Registry::new(UnknownPolicy::Reject);
+    registry.register(1, Kind::MarketData);
+    registry.register(2, Kind::HistoricalBar);
+    assert!(registry.observe(&Frame::from_fields(&["1", "6", "1", "1", "1.5"])));
+    assert!(registry.observe(&Frame::from_fields(&["17", "2", "0"])));
+    assert_eq!(
+        registry.outstanding(),
+        BTreeMap::from([(1, Kind::MarketData)])
+    );
+
+    // Callbacks for finished and unknown requests are discarded.
+    assert!(!registry.observe(&Frame::from_fields(&["17", "2", "0"])));
+    registry.finish(1);
+    assert!(!registry.observe(&Frame::from_fields(&["1", "6", "1", "1", "1.5"])));
+    assert!(!registry.observe(&Frame::from_fields(&["1", "6", "3", "1", "1.5"])));
+
+    // Messages without a request ID, and unrequested executions, are always passed on.
+    assert!(registry.observe(&Frame::from_fields(&["49", "1", "1700000000"])));
+    assert!(registry.observe(&Frame::from_fields(&["11", "-1", "5"])));
+    assert!(registry.outstanding().is_empty());
+}
+
+#[test]
+fn test_request_id() {
+    let req_id = RequestId::<category::MarketData>::new(7);
+    assert_eq!(req_id, 7);
+    assert_eq!(7, req_id);
+    assert_eq!(i64::from(req_id), 7);
+    assert_eq!(req_id.to_string(), "7");
+    assert_eq!(
+        format!("{req_id:?}"),
+        "RequestId { id: 7, kind: MarketData }"
+    );
+}
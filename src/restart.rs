@@ -0,0 +1,269 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeDelta, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// The defaults of `Window::with_duration` and `Window::with_lead`.
+const DEFAULT_DURATION: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+const DEFAULT_LEAD: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// How often TWS or IB Gateway restarts.
+pub enum Schedule {
+    /// Every day, which is how TWS restarts by default.
+    Daily,
+    /// Once a week, on the given day, which is how IB Gateway restarts by default.
+    Weekly(Weekday),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Spec", into = "Spec")]
+/// The recurring window during which TWS or IB Gateway restarts, as configured in its settings
+/// and installed with [`crate::client::Builder::with_restart_window`] or the `[Restart]` table of
+/// `config.toml`.
+///
+/// Requests are paused from shortly before each restart until the end of the window, and a client
+/// created by [`crate::client::Builder::local_with_restarts`] disconnects when the restart
+/// begins and reconnects once the window is over.
+pub struct Window {
+    schedule: Schedule,
+    time: NaiveTime,
+    timezone: Tz,
+    duration: std::time::Duration,
+    lead: std::time::Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A single occurrence of a restart [`Window`].
+pub struct Period {
+    /// The time from which requests are paused.
+    pub pause: DateTime<Utc>,
+    /// The time at which the restart begins.
+    pub start: DateTime<Utc>,
+    /// The time after which the platform is expected to accept connections again.
+    pub end: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[error("Requests are paused for a restart of TWS or IB Gateway until {resumes}.")]
+/// An error indicating that a request was made during a restart [`Period`].
+///
+/// Requests that fail for this reason return a [`std::io::Error`] of kind
+/// [`std::io::ErrorKind::WouldBlock`] that wraps this error. No request is sent to IBKR.
+pub struct Paused {
+    /// The end of the restart period, after which requests can be made again.
+    pub resumes: DateTime<Utc>,
+}
+
+// The representation of a `Window` in `config.toml`, ex.
+//
+// [Restart.Tws]
+// time = "23:45:00"
+// timezone = "US/Eastern"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Spec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    day: Option<Weekday>,
+    time: NaiveTime,
+    timezone: String,
+    #[serde(default = "default_duration_minutes")]
+    duration_minutes: u64,
+    #[serde(default = "default_lead_minutes")]
+    lead_minutes: u64,
+}
+
+// === Type implementations ===
+
+impl Window {
+    #[must_use]
+    #[inline]
+    /// Create a new [`Window`] for a platform that restarts every day.
+    ///
+    /// # Arguments
+    /// * `time` - The local time at which the platform restarts.
+    /// * `timezone` - The timezone of `time`.
+    pub const fn daily(time: NaiveTime, timezone: Tz) -> Self {
+        Self {
+            schedule: Schedule::Daily,
+            time,
+            timezone,
+            duration: DEFAULT_DURATION,
+            lead: DEFAULT_LEAD,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Create a new [`Window`] for a platform that restarts once a week.
+    ///
+    /// # Arguments
+    /// * `day` - The day of the week on which the platform restarts.
+    /// * `time` - The local time at which the platform restarts.
+    /// * `timezone` - The timezone of `time`.
+    pub const fn weekly(day: Weekday, time: NaiveTime, timezone: Tz) -> Self {
+        Self {
+            schedule: Schedule::Weekly(day),
+            ..Self::daily(time, timezone)
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Set how long the platform takes to restart. By default, a restart is expected to take 10
+    /// minutes.
+    ///
+    /// # Arguments
+    /// * `duration` - The time from the start of a restart until the platform accepts connections.
+    pub const fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Set how long before each restart requests are paused, so that no request is lost when the
+    /// connection drops. By default, requests are paused 1 minute before a restart.
+    ///
+    /// # Arguments
+    /// * `lead` - The time between pausing requests and the start of a restart.
+    pub const fn with_lead(mut self, lead: std::time::Duration) -> Self {
+        self.lead = lead;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return how often the platform restarts.
+    pub const fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    #[must_use]
+    /// Return the restart period that is in progress at `now`, or else the next one.
+    ///
+    /// # Arguments
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    /// The period, or [`None`] if its times are out of range.
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<Period> {
+        let today = now.with_timezone(&self.timezone).date_naive();
+        (-1..=7)
+            .filter_map(|days| today.checked_add_signed(TimeDelta::days(days)))
+            .filter(|date| match self.schedule {
+                Schedule::Daily => true,
+                Schedule::Weekly(day) => date.weekday() == day,
+            })
+            .filter_map(|date| self.period_on(date))
+            .find(|period| period.end > now)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return whether requests are paused at `now`.
+    ///
+    /// # Arguments
+    /// * `now` - The current time.
+    pub fn is_paused(&self, now: DateTime<Utc>) -> bool {
+        self.next_after(now)
+            .is_some_and(|period| period.pause <= now)
+    }
+
+    // The restart period that starts on `date`. A start that falls into the gap of a daylight
+    // saving time transition is moved past the gap, as the platform's clock is.
+    fn period_on(&self, date: NaiveDate) -> Option<Period> {
+        let naive = date.and_time(self.time);
+        let start = naive
+            .and_local_timezone(self.timezone)
+            .earliest()
+            .or_else(|| {
+                (naive + TimeDelta::hours(1))
+                    .and_local_timezone(self.timezone)
+                    .earliest()
+            })?
+            .with_timezone(&Utc);
+        Some(Period {
+            pause: start - TimeDelta::from_std(self.lead).ok()?,
+            start,
+            end: start + TimeDelta::from_std(self.duration).ok()?,
+        })
+    }
+}
+
+impl TryFrom<Spec> for Window {
+    type Error = chrono_tz::ParseError;
+
+    fn try_from(spec: Spec) -> Result<Self, Self::Error> {
+        let timezone = spec.timezone.parse()?;
+        let window = match spec.day {
+            Some(day) => Self::weekly(day, spec.time, timezone),
+            None => Self::daily(spec.time, timezone),
+        };
+        Ok(window
+            .with_duration(std::time::Duration::from_secs(spec.duration_minutes * 60))
+            .with_lead(std::time::Duration::from_secs(spec.lead_minutes * 60)))
+    }
+}
+
+impl From<Window> for Spec {
+    fn from(window: Window) -> Self {
+        Self {
+            day: match window.schedule {
+                Schedule::Daily => None,
+                Schedule::Weekly(day) => Some(day),
+            },
+            time: window.time,
+            timezone: window.timezone.name().to_owned(),
+            duration_minutes: window.duration.as_secs() / 60,
+            lead_minutes: window.lead.as_secs() / 60,
+        }
+    }
+}
+
+#[inline]
+const fn default_duration_minutes() -> u64 {
+    DEFAULT_DURATION.as_secs() / 60
+}
+
+#[inline]
+const fn default_lead_minutes() -> u64 {
+    DEFAULT_LEAD.as_secs() / 60
+}
+
+#[test]
+fn test_window() {
+    let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+    let window = Window::daily(
+        NaiveTime::from_hms_opt(23, 45, 0).unwrap(),
+        chrono_tz::US::Eastern,
+    );
+    let period = window.next_after(at("2024-01-02T12:00:00Z")).unwrap();
+    assert_eq!(period.start, at("2024-01-03T04:45:00Z"));
+    assert_eq!(period.pause, at("2024-01-03T04:44:00Z"));
+    assert_eq!(period.end, at("2024-01-03T04:55:00Z"));
+    assert!(!window.is_paused(at("2024-01-03T04:43:59Z")));
+    assert!(window.is_paused(at("2024-01-03T04:50:00Z")));
+    assert_eq!(window.next_after(at("2024-01-03T04:50:00Z")), Some(period));
+
+    let window: Window = toml::from_str(
+        r#"
+        day = "Sun"
+        time = "01:00:00"
+        timezone = "Europe/Zurich"
+        duration_minutes = 30
+        "#,
+    )
+    .unwrap();
+    assert_eq!(window.schedule(), Schedule::Weekly(Weekday::Sun));
+    let period = window.next_after(at("2024-03-27T12:00:00Z")).unwrap();
+    assert_eq!(period.start, at("2024-03-31T00:00:00Z"));
+    assert_eq!(period.end, at("2024-03-31T00:30:00Z"));
+    assert_eq!(
+        toml::to_string(&window)
+            .unwrap()
+            .parse::<toml::Table>()
+            .unwrap()["day"]
+            .as_str(),
+        Some("Sun")
+    );
+}
@@ -0,0 +1,1125 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+// Defines an enum of the codes that IBKR uses to identify a scanner setting, with an `Unknown`
+// variant for the codes without a dedicated variant.
+macro_rules! scanner_code {
+    (
+        $(#[$attr:meta])*
+        $name: ident {
+            $($(#[$var_attr:meta])* $variant: ident => $code: literal,)*
+        }
+    ) => {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        $(#[$attr])*
+        pub enum $name {
+            $($(#[$var_attr])* $variant,)*
+            /// A code without a dedicated variant.
+            Unknown(String),
+        }
+
+        impl $name {
+            #[must_use]
+            #[inline]
+            /// Return the code that IBKR uses for this setting.
+            pub fn code(&self) -> &str {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Unknown(code) => code,
+                }
+            }
+
+            #[must_use]
+            #[inline]
+            /// Return `true` if the code is not one of the codes with a dedicated variant.
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, Self::Unknown(_))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(match s {
+                    $($code => Self::$variant,)*
+                    s => Self::Unknown(s.to_owned()),
+                })
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.code())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(self.code())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+scanner_code!(
+    /// The ranking performed by a market scanner.
+    ScanCode {
+        /// The largest percentage gain since the previous close.
+        TopPercentGain => "TOP_PERC_GAIN",
+        /// The largest percentage loss since the previous close.
+        TopPercentLose => "TOP_PERC_LOSE",
+        /// The largest percentage gain since the open.
+        TopOpenPercentGain => "TOP_OPEN_PERC_GAIN",
+        /// The largest percentage loss since the open.
+        TopOpenPercentLose => "TOP_OPEN_PERC_LOSE",
+        /// The largest gap up at the open.
+        HighOpenGap => "HIGH_OPEN_GAP",
+        /// The largest gap down at the open.
+        LowOpenGap => "LOW_OPEN_GAP",
+        /// The largest volume.
+        MostActive => "MOST_ACTIVE",
+        /// The largest dollar volume.
+        MostActiveUsd => "MOST_ACTIVE_USD",
+        /// The largest volume relative to the average volume.
+        HotByVolume => "HOT_BY_VOLUME",
+        /// The largest price change relative to the average price change.
+        HotByPrice => "HOT_BY_PRICE",
+        /// The largest price range relative to the average price range.
+        HotByPriceRange => "HOT_BY_PRICE_RANGE",
+        /// The largest price range.
+        TopPriceRange => "TOP_PRICE_RANGE",
+        /// The largest number of trades.
+        TopTradeCount => "TOP_TRADE_COUNT",
+        /// The largest number of trades per minute.
+        TopTradeRate => "TOP_TRADE_RATE",
+        /// The largest volume per minute.
+        TopVolumeRate => "TOP_VOLUME_RATE",
+        /// The prices closest to their 13-week high.
+        HighVs13WeekHighLow => "HIGH_VS_13W_HL",
+        /// The prices closest to their 13-week low.
+        LowVs13WeekHighLow => "LOW_VS_13W_HL",
+        /// The prices closest to their 26-week high.
+        HighVs26WeekHighLow => "HIGH_VS_26W_HL",
+        /// The prices closest to their 26-week low.
+        LowVs26WeekHighLow => "LOW_VS_26W_HL",
+        /// The prices closest to their 52-week high.
+        HighVs52WeekHighLow => "HIGH_VS_52W_HL",
+        /// The prices closest to their 52-week low.
+        LowVs52WeekHighLow => "LOW_VS_52W_HL",
+        /// The largest dividend yield.
+        HighDividendYield => "HIGH_DIVIDEND_YIELD_IB",
+        /// The contracts that are halted.
+        Halted => "HALTED",
+        /// The largest option implied volatility.
+        HighOptionImpliedVolatility => "HIGH_OPT_IMP_VOLAT",
+        /// The smallest option implied volatility.
+        LowOptionImpliedVolatility => "LOW_OPT_IMP_VOLAT",
+        /// The largest percentage gain in option implied volatility.
+        TopOptionImpliedVolatilityGain => "TOP_OPT_IMP_VOLAT_GAIN",
+        /// The largest percentage loss in option implied volatility.
+        TopOptionImpliedVolatilityLose => "TOP_OPT_IMP_VOLAT_LOSE",
+        /// The largest option volume.
+        OptionVolumeMostActive => "OPT_VOLUME_MOST_ACTIVE",
+        /// The largest option volume relative to the average option volume.
+        HotByOptionVolume => "HOT_BY_OPT_VOLUME",
+    }
+);
+
+scanner_code!(
+    /// The type of instrument ranked by a market scanner.
+    Instrument {
+        /// US stocks.
+        Stock => "STK",
+        /// European stocks.
+        StockEurope => "STOCK.EU",
+        /// Asian stocks.
+        StockAsia => "STOCK.HK",
+        /// North American stocks outside the US.
+        StockNorthAmerica => "STOCK.NA",
+        /// US futures.
+        FutureUs => "FUT.US",
+        /// European futures.
+        FutureEurope => "FUT.EU",
+        /// Asian futures.
+        FutureAsia => "FUT.HK",
+        /// US indices.
+        IndexUs => "IND.US",
+        /// European indices.
+        IndexEurope => "IND.EU",
+        /// Asian indices.
+        IndexAsia => "IND.HK",
+        /// US bonds.
+        Bond => "BOND",
+    }
+);
+
+scanner_code!(
+    /// The market in which a market scanner ranks instruments.
+    LocationCode {
+        /// All US stocks.
+        StockUs => "STK.US",
+        /// US stocks listed on a major exchange.
+        StockUsMajor => "STK.US.MAJOR",
+        /// US stocks traded over the counter.
+        StockUsMinor => "STK.US.MINOR",
+        /// Stocks listed on NASDAQ.
+        StockNasdaq => "STK.NASDAQ",
+        /// Stocks listed on the NYSE.
+        StockNyse => "STK.NYSE",
+        /// Stocks listed on NYSE American.
+        StockAmex => "STK.AMEX",
+        /// Stocks listed on NYSE Arca.
+        StockArca => "STK.ARCA",
+        /// All European stocks.
+        StockEurope => "STK.EU",
+        /// All Asian stocks.
+        StockAsia => "STK.HK",
+        /// All North American stocks outside the US.
+        StockNorthAmerica => "STK.NA",
+        /// All US futures.
+        FutureUs => "FUT.US",
+        /// All US indices.
+        IndexUs => "IND.US",
+        /// All US bonds.
+        BondUs => "BOND.US",
+    }
+);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The parameters of a [`crate::client::Client::req_scanner_subscription`] request.
+///
+/// Filters are passed as tag/value pairs, where the valid tags (ex. "priceAbove" or
+/// "volumeAbove") are listed in the scanner parameters returned by
+/// [`crate::client::Client::req_scanner_parameters`]. A subscription can be checked against those
+/// parameters with [`Subscription::validate`] before it is sent.
+pub struct Subscription {
+    instrument: Instrument,
+    location: LocationCode,
+    scan_code: ScanCode,
+    rows: Option<u32>,
+    filters: Vec<(String, String)>,
+}
+
+impl Subscription {
+    #[must_use]
+    #[inline]
+    /// Create a new [`Subscription`] without filters.
+    ///
+    /// # Arguments
+    /// * `instrument` - The type of instrument to rank.
+    /// * `location` - The market in which to rank instruments.
+    /// * `scan_code` - The ranking to perform.
+    pub const fn new(instrument: Instrument, location: LocationCode, scan_code: ScanCode) -> Self {
+        Self {
+            instrument,
+            location,
+            scan_code,
+            rows: None,
+            filters: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Limit the number of rows in each scan. By default, IBKR returns up to 50 rows.
+    ///
+    /// # Arguments
+    /// * `rows` - The maximum number of rows.
+    pub const fn with_rows(mut self, rows: u32) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Add a filter to the scan, replacing any previous filter with the same tag.
+    ///
+    /// # Arguments
+    /// * `tag` - The tag of the filter, ex. "priceAbove".
+    /// * `value` - The value of the filter, ex. 5.
+    pub fn with_filter(mut self, tag: impl Into<String>, value: impl ToString) -> Self {
+        let (tag, value) = (tag.into(), value.to_string());
+        match self.filters.iter_mut().find(|(t, _)| *t == tag) {
+            Some(filter) => filter.1 = value,
+            None => self.filters.push((tag, value)),
+        }
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the type of instrument ranked by the scan.
+    pub const fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the market in which the scan ranks instruments.
+    pub const fn location(&self) -> &LocationCode {
+        &self.location
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the ranking performed by the scan.
+    pub const fn scan_code(&self) -> &ScanCode {
+        &self.scan_code
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the filters of the scan as tag/value pairs.
+    pub fn filters(&self) -> &[(String, String)] {
+        &self.filters
+    }
+
+    /// Check that the instrument, location code, scan code, and filters of the subscription are
+    /// offered by IBKR and compatible with each other, and that the value of each filter is
+    /// valid for its field.
+    ///
+    /// # Arguments
+    /// * `parameters` - The scanner parameters returned by
+    ///   [`crate::client::Client::req_scanner_parameters`].
+    ///
+    /// # Errors
+    /// Returns the first setting of the subscription that is not offered or is incompatible.
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), InvalidSubscription> {
+        let instrument = parameters
+            .instrument(&self.instrument)
+            .ok_or_else(|| InvalidSubscription::Instrument(self.instrument.clone()))?;
+        let location = parameters
+            .location(&self.location)
+            .ok_or_else(|| InvalidSubscription::Location(self.location.clone()))?;
+        if !location.supports(&self.instrument) {
+            return Err(InvalidSubscription::UnsupportedLocation {
+                location: self.location.clone(),
+                instrument: self.instrument.clone(),
+            });
+        }
+        let scan_type = parameters
+            .scan_type(&self.scan_code)
+            .ok_or_else(|| InvalidSubscription::ScanCode(self.scan_code.clone()))?;
+        if !scan_type.supports(&self.instrument) {
+            return Err(InvalidSubscription::Unsupported {
+                scan_code: self.scan_code.clone(),
+                instrument: self.instrument.clone(),
+            });
+        }
+        for (tag, value) in &self.filters {
+            let (filter, field) = parameters
+                .filter_field(tag)
+                .ok_or_else(|| InvalidSubscription::Filter(tag.clone()))?;
+            if !instrument.filters.is_empty() && !instrument.filters.contains(&filter.id) {
+                return Err(InvalidSubscription::UnsupportedFilter {
+                    tag: tag.clone(),
+                    instrument: self.instrument.clone(),
+                });
+            }
+            if !field.accepts(value) {
+                return Err(InvalidSubscription::FilterValue {
+                    tag: tag.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // The fixed filter fields that precede the generic filters are left empty, since each of
+    // them is also available as a filter tag.
+    pub(crate) fn as_out_msg(&self) -> impl Serialize + '_ {
+        let filters = self
+            .filters
+            .iter()
+            .map(|(tag, value)| format!("{tag}={value};"))
+            .collect::<String>();
+        (
+            (
+                self.rows.map_or(-1, i64::from),
+                &self.instrument,
+                &self.location,
+                &self.scan_code,
+            ),
+            (None::<()>, None::<()>, None::<()>, None::<()>, None::<()>),
+            ("", "", "", "", "", ""),
+            (None::<()>, None::<()>, false, None::<()>, "", ""),
+            filters,
+            "",
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+/// An error returned by [`Subscription::validate`] when a setting is not offered by IBKR.
+pub enum InvalidSubscription {
+    #[error("Invalid scanner subscription: No such instrument: {0}.")]
+    /// The instrument is not listed in the scanner parameters.
+    Instrument(Instrument),
+    #[error("Invalid scanner subscription: No such location code: {0}.")]
+    /// The location code is not listed in the scanner parameters.
+    Location(LocationCode),
+    #[error("Invalid scanner subscription: Location code {location} does not support instrument {instrument}.")]
+    /// The location does not list the instrument.
+    UnsupportedLocation {
+        /// The location code of the subscription.
+        location: LocationCode,
+        /// The instrument of the subscription.
+        instrument: Instrument,
+    },
+    #[error("Invalid scanner subscription: No such scan code: {0}.")]
+    /// The scan code is not listed in the scanner parameters.
+    ScanCode(ScanCode),
+    #[error("Invalid scanner subscription: Scan code {scan_code} does not support instrument {instrument}.")]
+    /// The scan code does not rank the instrument.
+    Unsupported {
+        /// The scan code of the subscription.
+        scan_code: ScanCode,
+        /// The instrument of the subscription.
+        instrument: Instrument,
+    },
+    #[error("Invalid scanner subscription: No such filter tag: {0}.")]
+    /// The filter tag is not listed in the scanner parameters.
+    Filter(String),
+    #[error(
+        "Invalid scanner subscription: Filter tag {tag} does not apply to instrument {instrument}."
+    )]
+    /// The filter does not apply to the instrument.
+    UnsupportedFilter {
+        /// The filter tag.
+        tag: String,
+        /// The instrument of the subscription.
+        instrument: Instrument,
+    },
+    #[error("Invalid scanner subscription: Invalid value {value} for filter tag {tag}.")]
+    /// The value cannot be parsed as the type of the filter field, is negative when the field
+    /// does not accept negative values, or is not one of the field's choices.
+    FilterValue {
+        /// The filter tag.
+        tag: String,
+        /// The value of the filter.
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("Failed to parse scanner parameters: {0}")]
+/// An error returned when parsing the scanner parameters XML document fails.
+pub struct ParseParametersError(pub String);
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// A catalog of the instruments, locations, scan types, and filters offered by IBKR's market
+/// scanners, as parsed from the XML document passed to
+/// [`crate::wrapper::LocalWrapper::scanner_parameters`].
+///
+/// The catalog can be serialized, so that it need not be requested again by every program.
+pub struct Parameters {
+    /// The instruments that can be ranked.
+    pub instruments: Vec<InstrumentDefinition>,
+    /// The top level of the tree of locations in which instruments can be ranked.
+    pub locations: Vec<Location>,
+    /// The rankings that can be performed.
+    pub scan_types: Vec<ScanType>,
+    /// The filters that can be applied.
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// An instrument that can be ranked by a market scanner.
+pub struct InstrumentDefinition {
+    /// The instrument.
+    pub instrument: Instrument,
+    /// The name of the instrument (ex. "US Stocks").
+    pub name: String,
+    /// The IDs of the filters that apply to the instrument. If empty, any filter may apply.
+    pub filters: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A market in which a market scanner ranks instruments, which may contain narrower markets.
+pub struct Location {
+    /// The location code.
+    pub location_code: LocationCode,
+    /// The name of the location (ex. "US Stocks").
+    pub display_name: String,
+    /// The instruments that can be ranked in the location. If empty, any instrument may be
+    /// ranked.
+    pub instruments: Vec<Instrument>,
+    /// The exchange to which orders for the ranked instruments are routed.
+    pub route_exchange: String,
+    /// Whether only delayed data is available for the location.
+    pub delayed_only: bool,
+    /// The narrower locations within this location.
+    pub children: Vec<Location>,
+}
+
+impl Location {
+    #[must_use]
+    #[inline]
+    /// Return `true` if the instrument can be ranked in the location.
+    pub fn supports(&self, instrument: &Instrument) -> bool {
+        self.instruments.is_empty() || self.instruments.contains(instrument)
+    }
+
+    #[inline]
+    /// Return an iterator over this location and all the locations nested within it.
+    pub fn iter(&self) -> impl Iterator<Item = &Self> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let location = stack.pop()?;
+            stack.extend(location.children.iter().rev());
+            Some(location)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A ranking that can be performed by a market scanner.
+pub struct ScanType {
+    /// The scan code.
+    pub scan_code: ScanCode,
+    /// The name of the ranking (ex. "Top % Gainers").
+    pub display_name: String,
+    /// The instruments that can be ranked. If empty, any instrument may be ranked.
+    pub instruments: Vec<Instrument>,
+}
+
+impl ScanType {
+    #[must_use]
+    #[inline]
+    /// Return `true` if the ranking can be applied to the instrument.
+    pub fn supports(&self, instrument: &Instrument) -> bool {
+        self.instruments.is_empty() || self.instruments.contains(instrument)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The shape of a [`Filter`].
+pub enum FilterKind {
+    /// A filter with a lower and an upper bound, ex. "priceAbove" and "priceBelow".
+    Range,
+    /// A filter with a single value.
+    Simple,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A filter that can be applied to a market scanner.
+pub struct Filter {
+    /// The ID of the filter, which is listed by the instruments to which it applies.
+    pub id: String,
+    /// The category of the filter (ex. "Price").
+    pub category: String,
+    /// The shape of the filter.
+    pub kind: FilterKind,
+    /// The fields of the filter. A range filter has a field for each bound.
+    pub fields: Vec<FilterField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The type of value accepted by a [`FilterField`].
+pub enum FieldType {
+    /// A floating point number.
+    Double,
+    /// An integer.
+    Int,
+    /// One of the field's choices.
+    Combo,
+    /// A string.
+    String,
+    /// A type without a dedicated variant.
+    Unknown(String),
+}
+
+impl FromStr for FieldType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DoubleField" => Self::Double,
+            "IntField" => Self::Int,
+            "ComboField" => Self::Combo,
+            "StringField" => Self::String,
+            s => Self::Unknown(s.to_owned()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A field of a [`Filter`], whose code is used as the tag of
+/// [`Subscription::with_filter`].
+pub struct FilterField {
+    /// The code of the field (ex. "priceAbove").
+    pub code: String,
+    /// The name of the field (ex. "Price Above").
+    pub display_name: String,
+    /// The type of value accepted by the field.
+    pub field_type: FieldType,
+    /// Whether the field accepts negative values.
+    pub accepts_negatives: bool,
+    /// The codes and names of the values accepted by a [`FieldType::Combo`] field.
+    pub choices: Vec<(String, String)>,
+}
+
+impl FilterField {
+    #[must_use]
+    /// Return `true` if the value is valid for the field.
+    pub fn accepts(&self, value: &str) -> bool {
+        match self.field_type {
+            FieldType::Double => value
+                .parse::<f64>()
+                .is_ok_and(|v| self.accepts_negatives || v >= 0.0),
+            FieldType::Int => value
+                .parse::<i64>()
+                .is_ok_and(|v| self.accepts_negatives || v >= 0),
+            FieldType::Combo => {
+                self.choices.is_empty() || self.choices.iter().any(|(code, _)| code == value)
+            }
+            FieldType::String | FieldType::Unknown(_) => true,
+        }
+    }
+}
+
+impl Parameters {
+    /// Parse the scanner parameters XML document.
+    ///
+    /// # Arguments
+    /// * `xml` - The XML document.
+    ///
+    /// # Errors
+    /// Returns an error if the document is not well-formed XML or has no `ScanParameterResponse`
+    /// element.
+    pub fn parse(xml: &str) -> Result<Self, ParseParametersError> {
+        let document = Element::parse(xml)?;
+        let root = document
+            .children("ScanParameterResponse")
+            .next()
+            .ok_or_else(|| ParseParametersError("No ScanParameterResponse element.".to_owned()))?;
+
+        let mut parameters = Self::default();
+        for instrument in root
+            .children("InstrumentList")
+            .flat_map(|list| list.children("Instrument"))
+        {
+            let instrument = InstrumentDefinition {
+                instrument: instrument
+                    .text_of("type")
+                    .parse()
+                    .unwrap_or_else(|e| match e {}),
+                name: instrument.text_of("name").to_owned(),
+                filters: split_list(instrument.text_of("filters")),
+            };
+            if !parameters
+                .instruments
+                .iter()
+                .any(|i| i.instrument == instrument.instrument)
+            {
+                parameters.instruments.push(instrument);
+            }
+        }
+        parameters.locations = root
+            .children("LocationTree")
+            .flat_map(Location::from_tree)
+            .collect();
+        for scan_type in root
+            .children("ScanTypeList")
+            .flat_map(|list| list.children("ScanType"))
+        {
+            let scan_type = ScanType {
+                scan_code: scan_type
+                    .text_of("scanCode")
+                    .parse()
+                    .unwrap_or_else(|e| match e {}),
+                display_name: scan_type.text_of("displayName").to_owned(),
+                instruments: split_list(scan_type.text_of("instruments")),
+            };
+            if parameters.scan_type(&scan_type.scan_code).is_none() {
+                parameters.scan_types.push(scan_type);
+            }
+        }
+        for filter in root
+            .children("FilterList")
+            .flat_map(|list| list.elements.iter())
+        {
+            let kind = match filter.name {
+                "RangeFilter" => FilterKind::Range,
+                "SimpleFilter" => FilterKind::Simple,
+                _ => continue,
+            };
+            let filter = Filter {
+                id: filter.text_of("id").to_owned(),
+                category: filter.text_of("category").to_owned(),
+                kind,
+                fields: filter
+                    .children("AbstractField")
+                    .map(FilterField::from_element)
+                    .collect(),
+            };
+            if !parameters.filters.iter().any(|f| f.id == filter.id) {
+                parameters.filters.push(filter);
+            }
+        }
+        Ok(parameters)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the definition of an instrument, if it is offered.
+    pub fn instrument(&self, instrument: &Instrument) -> Option<&InstrumentDefinition> {
+        self.instruments
+            .iter()
+            .find(|i| i.instrument == *instrument)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the location with a location code at any level of the tree, if it is offered.
+    pub fn location(&self, location_code: &LocationCode) -> Option<&Location> {
+        self.locations
+            .iter()
+            .flat_map(Location::iter)
+            .find(|l| l.location_code == *location_code)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the scan type with a scan code, if it is offered.
+    pub fn scan_type(&self, scan_code: &ScanCode) -> Option<&ScanType> {
+        self.scan_types.iter().find(|s| s.scan_code == *scan_code)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Return the filter field with a code (ex. "priceAbove") and the filter to which it belongs,
+    /// if it is offered.
+    pub fn filter_field(&self, code: &str) -> Option<(&Filter, &FilterField)> {
+        self.filters.iter().find_map(|filter| {
+            filter
+                .fields
+                .iter()
+                .find(|field| field.code == code)
+                .map(|field| (filter, field))
+        })
+    }
+
+    #[inline]
+    /// Return an iterator over the scan types that can rank an instrument in a location.
+    pub fn scan_types_for<'p>(
+        &'p self,
+        instrument: &'p Instrument,
+        location_code: &LocationCode,
+    ) -> impl Iterator<Item = &'p ScanType> {
+        let supported = self
+            .location(location_code)
+            .is_some_and(|l| l.supports(instrument));
+        self.scan_types
+            .iter()
+            .filter(move |s| supported && s.supports(instrument))
+    }
+}
+
+impl FromStr for Parameters {
+    type Err = ParseParametersError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Location {
+    fn from_tree<'e>(tree: &'e Element<'_>) -> impl Iterator<Item = Self> + 'e {
+        tree.children("Location").map(|location| Self {
+            location_code: location
+                .text_of("locationCode")
+                .parse()
+                .unwrap_or_else(|e| match e {}),
+            display_name: location.text_of("displayName").to_owned(),
+            instruments: split_list(location.text_of("instruments")),
+            route_exchange: location.text_of("routeExchange").to_owned(),
+            delayed_only: location.text_of("delayedOnly") == "true",
+            children: location
+                .children("LocationTree")
+                .flat_map(Self::from_tree)
+                .collect(),
+        })
+    }
+}
+
+impl FilterField {
+    fn from_element(field: &Element<'_>) -> Self {
+        Self {
+            code: field.text_of("code").to_owned(),
+            display_name: field.text_of("displayName").to_owned(),
+            field_type: field
+                .attribute("type")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or_else(|e| match e {}),
+            accepts_negatives: field.text_of("acceptNegatives") == "true",
+            choices: field
+                .children("ComboValues")
+                .flat_map(|values| values.children("ComboValue"))
+                .map(|value| {
+                    (
+                        value.text_of("code").to_owned(),
+                        value.text_of("displayName").to_owned(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+fn split_list<T: FromStr<Err = std::convert::Infallible>>(list: &str) -> Vec<T> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().unwrap_or_else(|e| match e {}))
+        .collect()
+}
+
+// A minimal XML element tree, which supports the subset of XML used by the scanner parameters:
+// elements, attributes without a '>' in their values, text, character references, CDATA
+// sections, comments, and processing instructions.
+#[derive(Debug, Default)]
+struct Element<'x> {
+    name: &'x str,
+    attributes: &'x str,
+    text: String,
+    elements: Vec<Element<'x>>,
+}
+
+impl<'x> Element<'x> {
+    // Parse a document into an unnamed element that contains its top-level elements.
+    fn parse(xml: &'x str) -> Result<Self, ParseParametersError> {
+        let malformed = |reason: &str| ParseParametersError(format!("Malformed XML: {reason}."));
+        let mut stack = vec![Element::default()];
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            let top = stack
+                .last_mut()
+                .ok_or_else(|| malformed("unbalanced tags"))?;
+            unescape(&rest[..start], &mut top.text);
+            rest = &rest[start..];
+            let (skip, end) = if rest.starts_with("<?") {
+                (0, "?>")
+            } else if rest.starts_with("<!--") {
+                (0, "-->")
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let len = cdata
+                    .find("]]>")
+                    .ok_or_else(|| malformed("unclosed CDATA"))?;
+                top.text.push_str(&cdata[..len]);
+                (0, "]]>")
+            } else if rest.starts_with("<!") {
+                (0, ">")
+            } else {
+                (1, ">")
+            };
+            let len = rest.find(end).ok_or_else(|| malformed("unclosed tag"))?;
+            let tag = &rest[skip..len];
+            rest = &rest[len + end.len()..];
+            if skip == 0 {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                let element = stack.pop().filter(|e| e.name == name.trim());
+                let (Some(element), Some(parent)) = (element, stack.last_mut()) else {
+                    return Err(malformed(&format!("unexpected closing tag {name}")));
+                };
+                parent.elements.push(element);
+                continue;
+            }
+            let (tag, empty) = tag
+                .strip_suffix('/')
+                .map_or((tag, false), |tag| (tag, true));
+            let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let element = Element {
+                name,
+                attributes,
+                ..Element::default()
+            };
+            if empty {
+                top.elements.push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+        match (stack.pop(), stack.is_empty()) {
+            (Some(document), true) => Ok(document),
+            _ => Err(malformed("unclosed element")),
+        }
+    }
+
+    #[inline]
+    fn children<'e>(&'e self, name: &'e str) -> impl Iterator<Item = &'e Element<'x>> {
+        self.elements.iter().filter(move |e| e.name == name)
+    }
+
+    // The trimmed text of the first child with the given name, or an empty string.
+    #[inline]
+    fn text_of(&self, name: &str) -> &str {
+        self.elements
+            .iter()
+            .find(|e| e.name == name)
+            .map_or("", |e| e.text.trim())
+    }
+
+    fn attribute(&self, name: &str) -> Option<&'x str> {
+        let mut rest = self.attributes;
+        loop {
+            let (key, value) = rest.trim_start().split_once('=')?;
+            let value = value.trim_start();
+            let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let (value, after) = value[1..].split_once(quote)?;
+            if key.trim() == name {
+                return Some(value);
+            }
+            rest = after;
+        }
+    }
+}
+
+// Append text to a buffer, replacing the predefined entities and character references.
+fn unescape(mut text: &str, buf: &mut String) {
+    while let Some(start) = text.find('&') {
+        buf.push_str(&text[..start]);
+        text = &text[start..];
+        let Some(end) = text.find(';') else {
+            break;
+        };
+        let c = match &text[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            reference => reference
+                .strip_prefix("#x")
+                .map_or_else(
+                    || reference.strip_prefix('#').and_then(|n| n.parse().ok()),
+                    |n| u32::from_str_radix(n, 16).ok(),
+                )
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                buf.push(c);
+                text = &text[end + 1..];
+            }
+            None => {
+                buf.push('&');
+                text = &text[1..];
+            }
+        }
+    }
+    buf.push_str(text);
+}
+
+#[test]
+fn test_parameters() -> Result<(), ParseParametersError> {
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ScanParameterResponse>
+    <InstrumentList varName="fullInstrumentList">
+        <Instrument>
+            <name>US Stocks</name>
+            <type>STK</type>
+            <filters>PRICE,VOLUME,SECTYPE</filters>
+        </Instrument>
+        <Instrument>
+            <name>US Futures</name>
+            <type>FUT.US</type>
+            <filters>VOLUME</filters>
+        </Instrument>
+    </InstrumentList>
+    <LocationTree varName="locationTree">
+        <Location>
+            <displayName>US Stocks</displayName>
+            <locationCode>STK.US</locationCode>
+            <instruments>STK</instruments>
+            <routeExchange>SMART</routeExchange>
+            <delayedOnly>false</delayedOnly>
+            <LocationTree>
+                <Location>
+                    <displayName>Listed/NASDAQ</displayName>
+                    <locationCode>STK.US.MAJOR</locationCode>
+                    <instruments>STK</instruments>
+                </Location>
+            </LocationTree>
+        </Location>
+        <Location>
+            <locationCode>FUT.US</locationCode>
+            <instruments>FUT.US</instruments>
+        </Location>
+    </LocationTree>
+    <ScanTypeList>
+        <ScanType>
+            <displayName>Top % Gainers</displayName>
+            <scanCode>TOP_PERC_GAIN</scanCode>
+            <instruments>STK,STOCK.NA,FUT.US</instruments>
+        </ScanType>
+        <ScanType>
+            <displayName>Hot Contracts by Price &amp; Volume</displayName>
+            <scanCode>NEW_SCAN</scanCode>
+            <instruments/>
+        </ScanType>
+    </ScanTypeList>
+    <!-- Filters -->
+    <FilterList varName="filterList">
+        <RangeFilter>
+            <id>PRICE</id>
+            <category>Price</category>
+            <AbstractField type="DoubleField">
+                <code>priceAbove</code>
+                <displayName>Price Above</displayName>
+                <acceptNegatives>false</acceptNegatives>
+            </AbstractField>
+            <AbstractField type="DoubleField"><code>priceBelow</code></AbstractField>
+        </RangeFilter>
+        <RangeFilter>
+            <id>VOLUME</id>
+            <AbstractField type="IntField"><code>volumeAbove</code></AbstractField>
+        </RangeFilter>
+        <SimpleFilter>
+            <id>SECTYPE</id>
+            <AbstractField type='ComboField'>
+                <code>stkTypes</code>
+                <ComboValues>
+                    <ComboValue><code>inc:CORP</code><displayName>Corporation</displayName></ComboValue>
+                    <ComboValue><code>inc:ETF</code><displayName><![CDATA[ETF & ETN]]></displayName></ComboValue>
+                </ComboValues>
+            </AbstractField>
+        </SimpleFilter>
+    </FilterList>
+</ScanParameterResponse>"#;
+
+    let parameters = XML.parse::<Parameters>()?;
+    assert_eq!(parameters.instruments.len(), 2);
+    assert_eq!(
+        parameters.instruments[0].filters,
+        ["PRICE", "VOLUME", "SECTYPE"]
+    );
+    assert_eq!(parameters.locations.len(), 2);
+    let major = parameters
+        .location(&LocationCode::StockUsMajor)
+        .map(|l| l.display_name.as_str());
+    assert_eq!(major, Some("Listed/NASDAQ"));
+    assert_eq!(parameters.locations[0].route_exchange, "SMART");
+    let new_scan = parameters.scan_type(&ScanCode::Unknown("NEW_SCAN".to_owned()));
+    assert_eq!(
+        new_scan.map(|s| s.display_name.as_str()),
+        Some("Hot Contracts by Price & Volume")
+    );
+    assert!(new_scan.is_some_and(|s| s.instruments.is_empty()));
+    assert_eq!(
+        parameters
+            .scan_types_for(&Instrument::FutureUs, &LocationCode::StockUs)
+            .count(),
+        0
+    );
+    assert_eq!(
+        parameters
+            .scan_types_for(&Instrument::Stock, &LocationCode::StockUsMajor)
+            .count(),
+        2
+    );
+    let (filter, field) = parameters
+        .filter_field("stkTypes")
+        .ok_or_else(|| ParseParametersError("Missing filter field.".to_owned()))?;
+    assert_eq!(filter.kind, FilterKind::Simple);
+    assert_eq!(field.field_type, FieldType::Combo);
+    assert_eq!(field.choices[1].1, "ETF & ETN");
+
+    let subscription = Subscription::new(
+        Instrument::Stock,
+        LocationCode::StockUsMajor,
+        ScanCode::TopPercentGain,
+    )
+    .with_rows(10)
+    .with_filter("priceAbove", 5)
+    .with_filter("priceAbove", 10);
+    assert_eq!(
+        subscription.filters(),
+        [("priceAbove".to_owned(), "10".to_owned())]
+    );
+    assert_eq!(subscription.validate(&parameters), Ok(()));
+    assert_eq!(
+        subscription
+            .clone()
+            .with_filter("stkTypes", "inc:CORP")
+            .validate(&parameters),
+        Ok(())
+    );
+    assert_eq!(
+        subscription
+            .clone()
+            .with_filter("priceBelow", -1)
+            .validate(&parameters),
+        Err(InvalidSubscription::FilterValue {
+            tag: "priceBelow".to_owned(),
+            value: "-1".to_owned()
+        })
+    );
+    assert_eq!(
+        subscription
+            .clone()
+            .with_filter("marketCapAbove", 1)
+            .validate(&parameters),
+        Err(InvalidSubscription::Filter("marketCapAbove".to_owned()))
+    );
+    assert_eq!(
+        Subscription::new(
+            Instrument::FutureUs,
+            LocationCode::FutureUs,
+            ScanCode::TopPercentGain
+        )
+        .with_filter("priceAbove", 1)
+        .validate(&parameters),
+        Err(InvalidSubscription::UnsupportedFilter {
+            tag: "priceAbove".to_owned(),
+            instrument: Instrument::FutureUs
+        })
+    );
+    assert_eq!(
+        Subscription::new(
+            Instrument::FutureUs,
+            LocationCode::StockUs,
+            ScanCode::TopPercentGain
+        )
+        .validate(&parameters),
+        Err(InvalidSubscription::UnsupportedLocation {
+            location: LocationCode::StockUs,
+            instrument: Instrument::FutureUs
+        })
+    );
+    assert_eq!(
+        Subscription::new(
+            Instrument::Bond,
+            LocationCode::StockUs,
+            ScanCode::TopPercentGain
+        )
+        .validate(&parameters),
+        Err(InvalidSubscription::Instrument(Instrument::Bond))
+    );
+    assert!("<ScanParameterResponse><a></b></ScanParameterResponse>"
+        .parse::<Parameters>()
+        .is_err());
+    assert!("<Other/>".parse::<Parameters>().is_err());
+    Ok(())
+}
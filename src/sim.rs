@@ -0,0 +1,871 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+
+use crate::contract::{Contract, ContractId, ExchangeProxy, Proxy, Security};
+use crate::exchange::{Primary, Routing};
+use crate::execution::{Exec, Execution, Filter, OrderSide};
+use crate::message::Shared;
+use crate::order::{state, Executable, Order, OrderValidationError, PlacedOrder};
+use crate::payload::{
+    Bar, Fill, OrderAmount, OrderStatus, OrderStatusCore, PositionSummary, TickData,
+};
+use crate::quantity::Quantity;
+use crate::wrapper::LocalWrapper;
+
+// The error codes that IBKR uses for the same conditions.
+const ORDER_REJECTED: i64 = 201;
+const CANNOT_MODIFY: i64 = 104;
+const CANCEL_NOT_FOUND: i64 = 10147;
+
+// === Type definitions ===
+
+#[derive(Debug)]
+/// A simulator that fills orders against market data supplied by the caller and invokes the
+/// callbacks of a [`LocalWrapper`], so that the order handling of a strategy written against
+/// [`crate::client::ActiveClient`] can be backtested without a connection to the API.
+///
+/// The order, position and execution methods share the names of their
+/// [`crate::client::ActiveClient`] counterparts, but a [`Simulator`] is a separate type rather
+/// than a drop-in replacement for a client:
+/// * No trait is shared with [`crate::client::ActiveClient`], so a strategy that should run
+///   against both must be generic over its own abstraction of the methods it uses.
+/// * There are no market data requests. Market data is supplied with [`Simulator::feed_tick`]
+///   and [`Simulator::feed_bar`], each of which may fill working orders.
+/// * [`Simulator::req_place_order`] and [`Simulator::req_modify_order`] require the security to
+///   be [`Clone`] and convertible to a [`Contract`], and return an [`OrderValidationError`]
+///   rather than a [`crate::client::PlaceOrderError`].
+///
+/// The simulation is deliberately simple:
+/// * Market, limit, stop and stop-limit orders are supported; any other order type, including
+///   auction orders, is rejected.
+/// * Each order is filled in full at once; there is no queue position, partial fill or slippage
+///   beyond the quoted price.
+/// * Buy orders fill against the ask and sell orders against the bid. Until a quote has been
+///   seen, the last trade (or midpoint) price is used instead.
+/// * No commissions are charged and contract multipliers are ignored.
+/// * The time in force of an order is ignored: every order works until it is filled or
+///   cancelled.
+pub struct Simulator<W: LocalWrapper> {
+    wrapper: W,
+    client_id: i64,
+    account_number: String,
+    validate_orders: bool,
+    shared: Shared,
+    next_req_id: i64,
+    next_execution_id: u64,
+    now: DateTime<Utc>,
+    quotes: HashMap<ContractId, Quote>,
+    orders: BTreeMap<i64, Working>,
+    holdings: BTreeMap<i64, Holding>,
+    executions: Vec<Execution>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Quote {
+    bid: Option<f64>,
+    ask: Option<f64>,
+    last: Option<f64>,
+}
+
+// The prices reached over a span of market data, which is a single price for a tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Range {
+    open: f64,
+    high: f64,
+    low: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Working {
+    contract: ExchangeProxy<Contract>,
+    exchange: Primary,
+    side: OrderSide,
+    quantity: Quantity,
+    limit: Option<f64>,
+    stop: Option<f64>,
+    triggered: bool,
+    perm_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Holding {
+    contract: ExchangeProxy<Contract>,
+    position: Quantity,
+    average_cost: f64,
+}
+
+// === Type implementations ===
+
+impl Quote {
+    fn range(&self, side: OrderSide) -> Option<Range> {
+        let price = match side {
+            OrderSide::Buy => self.ask.or(self.last),
+            OrderSide::Sell => self.bid.or(self.last),
+        }?;
+        Some(Range {
+            open: price,
+            high: price,
+            low: price,
+        })
+    }
+}
+
+impl Working {
+    // The price at which the order fills over a range of prices, if it fills at all. A stop order
+    // that is triggered but not filled stays triggered.
+    fn fill_price(&mut self, range: Range) -> Option<f64> {
+        let buy = self.side == OrderSide::Buy;
+        let start = match self.stop {
+            Some(stop) if !self.triggered => {
+                if buy && range.high >= stop {
+                    range.open.max(stop)
+                } else if !buy && range.low <= stop {
+                    range.open.min(stop)
+                } else {
+                    return None;
+                }
+            }
+            _ => range.open,
+        };
+        self.triggered = true;
+        match self.limit {
+            None => Some(start),
+            Some(limit) if buy => {
+                if start <= limit {
+                    Some(start)
+                } else {
+                    (range.low <= limit).then_some(limit)
+                }
+            }
+            Some(limit) => {
+                if start >= limit {
+                    Some(start)
+                } else {
+                    (range.high >= limit).then_some(limit)
+                }
+            }
+        }
+    }
+
+    fn status_core(&self, order_id: i64, client_id: i64, fill: Option<Fill>) -> OrderStatusCore {
+        OrderStatusCore {
+            order_id,
+            fill,
+            remaining: if fill.is_some() {
+                Quantity::ZERO
+            } else {
+                self.quantity
+            },
+            permanent_id: self.perm_id,
+            parent_id: None,
+            client_id,
+            why_held: None,
+            market_cap_price: None,
+        }
+    }
+}
+
+impl Holding {
+    fn add(&mut self, quantity: Quantity, price: f64) {
+        let position = self.position + quantity;
+        if self.position.is_zero() || self.position.is_negative() == quantity.is_negative() {
+            self.average_cost = (self.position.to_f64() * self.average_cost
+                + quantity.to_f64() * price)
+                / position.to_f64();
+        } else if position.is_negative() != self.position.is_negative() && !position.is_zero() {
+            self.average_cost = price;
+        }
+        self.position = position;
+        if self.position.is_zero() {
+            self.average_cost = 0.0;
+        }
+    }
+}
+
+impl<W: LocalWrapper> Simulator<W> {
+    #[must_use]
+    /// Create a new simulator.
+    ///
+    /// # Arguments
+    /// * `wrapper` - The wrapper whose callbacks will be invoked.
+    /// * `start` - The simulated date and time before any market data has been supplied.
+    pub fn new(wrapper: W, start: DateTime<Utc>) -> Self {
+        const ACCOUNT_NUMBER: &str = "DU0000000";
+
+        Self {
+            wrapper,
+            client_id: 0,
+            account_number: ACCOUNT_NUMBER.to_owned(),
+            validate_orders: false,
+            shared: Shared::new(1, HashSet::from([ACCOUNT_NUMBER.to_owned()])),
+            next_req_id: 0,
+            next_execution_id: 0,
+            now: start,
+            quotes: HashMap::new(),
+            orders: BTreeMap::new(),
+            holdings: BTreeMap::new(),
+            executions: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    /// Set the client ID reported in order statuses and executions.
+    pub fn with_client_id(mut self, client_id: i64) -> Self {
+        self.client_id = client_id;
+        self
+    }
+
+    #[must_use]
+    /// Set the account number reported in positions and executions.
+    pub fn with_account_number(mut self, account_number: impl Into<String>) -> Self {
+        self.account_number = account_number.into();
+        self.shared
+            .sync_managed_accounts(HashSet::from([self.account_number.clone()]));
+        self
+    }
+
+    #[must_use]
+    /// Check each order with [`Order::validate`] before it is accepted, as
+    /// [`crate::client::Builder::with_order_validation`] does for a live client.
+    pub fn with_order_validation(mut self) -> Self {
+        self.validate_orders = true;
+        self
+    }
+
+    #[inline]
+    /// Get a reference to the wrapper.
+    pub fn wrapper(&self) -> &W {
+        &self.wrapper
+    }
+
+    #[inline]
+    /// Get a mutable reference to the wrapper.
+    pub fn wrapper_mut(&mut self) -> &mut W {
+        &mut self.wrapper
+    }
+
+    #[inline]
+    /// Consume the client and return its wrapper.
+    pub fn into_wrapper(self) -> W {
+        self.wrapper
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the simulated date and time, which is that of the latest market data supplied.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    #[inline]
+    /// Get the next valid order ID and advance the internal counter.
+    pub fn get_next_order_id(&mut self) -> i64 {
+        self.shared.next_order_id()
+    }
+
+    #[inline]
+    /// Get the next valid request ID and advance the internal counter.
+    pub fn get_next_req_id(&mut self) -> i64 {
+        self.next_req_id += 1;
+        self.next_req_id
+    }
+
+    #[inline]
+    #[must_use]
+    /// Get the simulated position in a contract.
+    pub fn position(&self, contract_id: ContractId) -> Quantity {
+        self.holdings
+            .get(&contract_id.0)
+            .map_or(Quantity::ZERO, |holding| holding.position)
+    }
+
+    // === Market Data ===
+
+    /// Supply a tick of market data. Bid/ask ticks update the quote; trade and midpoint ticks
+    /// update the last price. Any working order for the contract that can now be filled is
+    /// filled.
+    ///
+    /// # Arguments
+    /// * `contract_id` - The contract to which the tick belongs.
+    /// * `tick` - The tick.
+    pub async fn feed_tick(&mut self, contract_id: ContractId, tick: &TickData) {
+        let quote = self.quotes.entry(contract_id).or_default();
+        self.now = match tick {
+            TickData::Midpoint(t) => {
+                quote.last = Some(t.price);
+                t.datetime
+            }
+            TickData::BidAsk(t) => {
+                quote.bid = Some(t.bid_price);
+                quote.ask = Some(t.ask_price);
+                t.datetime
+            }
+            TickData::Last(t) => {
+                quote.last = Some(t.price);
+                t.datetime
+            }
+        };
+        let quote = *quote;
+        self.match_orders(contract_id, |side| quote.range(side))
+            .await;
+    }
+
+    /// Supply a bar of market data. Working orders for the contract are matched against the
+    /// bar's open, high and low, after which the bar's close becomes the last price and clears
+    /// the quote.
+    ///
+    /// # Arguments
+    /// * `contract_id` - The contract to which the bar belongs.
+    /// * `bar` - The bar.
+    pub async fn feed_bar(&mut self, contract_id: ContractId, bar: &Bar) {
+        let core = match bar {
+            Bar::Ordinary(core) => *core,
+            Bar::Trades(trade) => trade.bar,
+        };
+        self.now = core.datetime;
+        let range = Range {
+            open: core.open,
+            high: core.high,
+            low: core.low,
+        };
+        self.match_orders(contract_id, |_| Some(range)).await;
+        self.quotes.insert(
+            contract_id,
+            Quote {
+                last: Some(core.close),
+                ..Quote::default()
+            },
+        );
+    }
+
+    // === Orders ===
+
+    /// Place an order, which is filled as soon as the market data allows.
+    ///
+    /// # Arguments
+    /// * `order` - The order to place.
+    ///
+    /// # Returns
+    /// The placed order, which observes the simulated order statuses.
+    ///
+    /// # Errors
+    /// Returns an [`OrderValidationError`] if order validation is enabled and the order is invalid.
+    pub async fn req_place_order<S, E>(
+        &mut self,
+        order: &Order<'_, S, E>,
+    ) -> Result<PlacedOrder<state::Submitted>, OrderValidationError>
+    where
+        S: Security + Clone + Into<Contract>,
+        E: Executable<S>,
+    {
+        self.check_order(order)?;
+        let id = self.get_next_order_id();
+        let status = self.shared.track_order(id);
+        self.submit(id, order, id).await;
+        Ok(PlacedOrder::new(id, status))
+    }
+
+    /// Modify a working order. An order that is no longer working cannot be modified, which is
+    /// reported to [`LocalWrapper::error`].
+    ///
+    /// # Arguments
+    /// * `order` - The modified order.
+    /// * `id` - The ID of the order to modify.
+    ///
+    /// # Returns
+    /// The ID of the order.
+    ///
+    /// # Errors
+    /// Returns an [`OrderValidationError`] if order validation is enabled and the order is invalid.
+    pub async fn req_modify_order<S, E>(
+        &mut self,
+        order: &Order<'_, S, E>,
+        id: i64,
+    ) -> Result<i64, OrderValidationError>
+    where
+        S: Security + Clone + Into<Contract>,
+        E: Executable<S>,
+    {
+        self.check_order(order)?;
+        match self.orders.get(&id) {
+            Some(working) => {
+                let perm_id = working.perm_id;
+                self.submit(id, order, perm_id).await;
+            }
+            None => {
+                self.wrapper
+                    .error(
+                        id,
+                        CANNOT_MODIFY,
+                        format!("Can't modify a filled order. Order {id} is not working."),
+                        String::new(),
+                    )
+                    .await;
+            }
+        }
+        Ok(id)
+    }
+
+    /// Cancel a working order.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the order to cancel.
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches [`crate::client::Client::cancel_order`].
+    pub async fn cancel_order(&mut self, id: i64) -> Result<(), std::io::Error> {
+        match self.orders.remove(&id) {
+            Some(working) => {
+                let core = working.status_core(id, self.client_id, None);
+                self.report_status(OrderStatus::Cancelled(core)).await;
+            }
+            None => {
+                self.wrapper
+                    .error(
+                        id,
+                        CANCEL_NOT_FOUND,
+                        format!("OrderId {id} that needs to be cancelled is not found."),
+                        String::new(),
+                    )
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancel all working orders.
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches [`crate::client::Client::cancel_all_orders`].
+    pub async fn cancel_all_orders(&mut self) -> Result<(), std::io::Error> {
+        for id in self.orders.keys().copied().collect::<Vec<_>>() {
+            self.cancel_order(id).await?;
+        }
+        Ok(())
+    }
+
+    /// Report every working order to [`LocalWrapper::open_order`], followed by
+    /// [`LocalWrapper::open_order_end`].
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches [`crate::client::Client::req_open_orders`].
+    pub async fn req_open_orders(&mut self) -> Result<(), std::io::Error> {
+        for (&id, working) in &self.orders {
+            self.wrapper
+                .open_order(
+                    id,
+                    working.contract.clone(),
+                    OrderAmount::Quantity(working.quantity),
+                    self.client_id,
+                    None,
+                    working.perm_id,
+                )
+                .await;
+        }
+        self.wrapper.open_order_end().await;
+        Ok(())
+    }
+
+    #[inline]
+    /// Identical to [`Simulator::req_open_orders`], since a simulator is the only client.
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches
+    /// [`crate::client::Client::req_all_open_orders`].
+    pub async fn req_all_open_orders(&mut self) -> Result<(), std::io::Error> {
+        self.req_open_orders().await
+    }
+
+    // === Account and Executions ===
+
+    /// Report every open position to [`LocalWrapper::position_summary`], followed by
+    /// [`LocalWrapper::position_end`].
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches [`crate::client::Client::req_positions`].
+    pub async fn req_positions(&mut self) -> Result<(), std::io::Error> {
+        for holding in self.holdings.values() {
+            self.wrapper
+                .position_summary(PositionSummary {
+                    contract: holding.contract.clone(),
+                    position: holding.position,
+                    average_cost: holding.average_cost,
+                    account_number: self.account_number.clone(),
+                })
+                .await;
+        }
+        self.wrapper.position_end().await;
+        Ok(())
+    }
+
+    /// Report the simulated executions that match a filter to [`LocalWrapper::execution`],
+    /// followed by [`LocalWrapper::execution_details_end`].
+    ///
+    /// # Arguments
+    /// * `filter` - The criteria that executions must meet. A client ID of 0 matches every
+    ///   execution.
+    ///
+    /// # Returns
+    /// The unique ID associated with the request.
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches [`crate::client::Client::req_executions`].
+    pub async fn req_executions(&mut self, filter: Filter) -> Result<i64, std::io::Error> {
+        let req_id = self.get_next_req_id();
+        for execution in &self.executions {
+            if matches_filter(execution, &filter) {
+                self.wrapper.execution(req_id, execution.clone()).await;
+            }
+        }
+        self.wrapper.execution_details_end(req_id).await;
+        Ok(req_id)
+    }
+
+    /// Report the simulated date and time to [`LocalWrapper::current_time`].
+    ///
+    /// # Errors
+    /// Never returns an error; the signature matches [`crate::client::Client::req_current_time`].
+    pub async fn req_current_time(&mut self) -> Result<(), std::io::Error> {
+        // The live API sends the message version in place of a request ID.
+        self.wrapper.current_time(1, self.now).await;
+        Ok(())
+    }
+
+    // === Internals ===
+
+    fn check_order<S, E>(&self, order: &Order<'_, S, E>) -> Result<(), OrderValidationError>
+    where
+        S: Security,
+        E: Executable<S>,
+    {
+        if self.validate_orders {
+            order.validate()?;
+        }
+        Ok(())
+    }
+
+    async fn submit<S, E>(&mut self, id: i64, order: &Order<'_, S, E>, perm_id: i64)
+    where
+        S: Security + Clone + Into<Contract>,
+        E: Executable<S>,
+    {
+        let security = order.get_security();
+        let exec = order.get_execute_method();
+        let contract: ExchangeProxy<Contract> = Proxy {
+            inner: security.clone().into(),
+            _exch: std::marker::PhantomData,
+        };
+        let side = match order {
+            Order::Buy { .. } => OrderSide::Buy,
+            Order::Sell { .. } => OrderSide::Sell,
+        };
+        let prices = match exec.get_order_type() {
+            _ if exec.get_cash_quantity().is_some() => Err("cash quantity".to_owned()),
+            _ if exec.get_auction().is_some() => Err("auction".to_owned()),
+            "MKT" => Ok((None, None)),
+            "LMT" => Ok((exec.get_limit_price(), None)),
+            "STP" => Ok((None, exec.get_auxiliary_price())),
+            "STP LMT" => Ok((exec.get_limit_price(), exec.get_auxiliary_price())),
+            order_type => Err(order_type.to_owned()),
+        };
+        let (limit, stop) = match prices {
+            Ok(prices) => prices,
+            Err(unsupported) => {
+                self.orders.remove(&id);
+                self.wrapper
+                    .error(
+                        id,
+                        ORDER_REJECTED,
+                        format!("Order rejected - reason: The simulator does not support {unsupported} orders."),
+                        String::new(),
+                    )
+                    .await;
+                let core = OrderStatusCore {
+                    order_id: id,
+                    fill: None,
+                    remaining: exec.get_quantity(),
+                    permanent_id: perm_id,
+                    parent_id: None,
+                    client_id: self.client_id,
+                    why_held: None,
+                    market_cap_price: None,
+                };
+                self.report_status(OrderStatus::Inactive(core)).await;
+                return;
+            }
+        };
+        let out_msg = security.as_out_msg();
+        let working = Working {
+            contract,
+            exchange: match out_msg.exchange {
+                Routing::Primary(primary) => primary,
+                Routing::Smart => out_msg
+                    .primary_exchange
+                    .unwrap_or(Primary::InteractiveBrokersDealingSystem),
+            },
+            side,
+            quantity: exec.get_quantity(),
+            limit,
+            stop,
+            triggered: false,
+            perm_id,
+        };
+
+        self.wrapper
+            .open_order(
+                id,
+                working.contract.clone(),
+                OrderAmount::Quantity(working.quantity),
+                self.client_id,
+                None,
+                perm_id,
+            )
+            .await;
+        let core = working.status_core(id, self.client_id, None);
+        let quote = self.quotes.get(&security.contract_id()).copied();
+        self.orders.insert(id, working);
+        let status = if stop.is_some() {
+            OrderStatus::PreSubmitted(core)
+        } else {
+            OrderStatus::Submitted(core)
+        };
+        self.report_status(status).await;
+
+        if let Some(quote) = quote {
+            self.match_orders(security.contract_id(), |side| quote.range(side))
+                .await;
+        }
+    }
+
+    async fn match_orders(
+        &mut self,
+        contract_id: ContractId,
+        range: impl Fn(OrderSide) -> Option<Range>,
+    ) {
+        let mut fills = Vec::new();
+        for (&id, working) in &mut self.orders {
+            if working.contract.inner.contract_id() != contract_id {
+                continue;
+            }
+            if let Some(price) = range(working.side).and_then(|r| working.fill_price(r)) {
+                fills.push((id, price));
+            }
+        }
+        for (id, price) in fills {
+            if let Some(working) = self.orders.remove(&id) {
+                self.fill(id, working, price).await;
+            }
+        }
+    }
+
+    async fn fill(&mut self, id: i64, working: Working, price: f64) {
+        self.next_execution_id += 1;
+        let exec = Exec {
+            contract: working.contract.clone(),
+            order_id: id,
+            execution_id: format!("sim.{:08}", self.next_execution_id),
+            datetime: self.now,
+            account_number: self.account_number.clone(),
+            exchange: working.exchange,
+            quantity: working.quantity,
+            price,
+            perm_id: working.perm_id,
+            client_id: self.client_id,
+            liquidation: false,
+            cumulative_quantity: working.quantity,
+            average_price: price,
+            pending_price_revision: false,
+            short_sale: false,
+            ev_rule: None,
+            ev_multiplier: None,
+            model_code: None,
+            last_liquidity: None,
+        };
+        let execution = Execution::from_exec_tuple(exec, working.side);
+
+        let signed = match working.side {
+            OrderSide::Buy => working.quantity,
+            OrderSide::Sell => -working.quantity,
+        };
+        self.holdings
+            .entry(working.contract.inner.contract_id().0)
+            .or_insert_with(|| Holding {
+                contract: working.contract.clone(),
+                position: Quantity::ZERO,
+                average_cost: 0.0,
+            })
+            .add(signed, price);
+        self.executions.push(execution.clone());
+
+        // Executions that were not requested are delivered with a request ID of -1.
+        self.wrapper.execution(-1, execution).await;
+        let core = working.status_core(
+            id,
+            self.client_id,
+            Some(Fill {
+                filled: working.quantity,
+                average_price: price,
+                last_price: price,
+            }),
+        );
+        self.report_status(OrderStatus::Filled(core)).await;
+    }
+
+    async fn report_status(&mut self, status: OrderStatus) {
+        self.shared.update_order_status(&status);
+        self.wrapper.order_status(status).await;
+    }
+}
+
+fn matches_filter(execution: &Execution, filter: &Filter) -> bool {
+    let exec = execution.as_exec();
+    let side = if execution.is_buy() {
+        OrderSide::Buy
+    } else {
+        OrderSide::Sell
+    };
+    (filter.client_id == 0 || filter.client_id == exec.client_id)
+        && (filter.account_number.is_empty() || filter.account_number == exec.account_number)
+        && filter
+            .datetime
+            .is_none_or(|datetime| exec.datetime >= datetime)
+        && (filter.symbol.is_empty() || filter.symbol == exec.contract.symbol())
+        && filter
+            .contract_type
+            .is_none_or(|contract_type| contract_type == exec.contract.contract_type())
+        && filter
+            .exchange
+            .is_none_or(|exchange| exchange == Routing::Primary(exec.exchange))
+        && filter.side.is_none_or(|s| s == side)
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct SimRecorder {
+    statuses: Vec<OrderStatus>,
+    executions: Vec<(i64, Execution)>,
+    positions: Vec<PositionSummary>,
+    errors: Vec<(i64, i64)>,
+}
+
+#[cfg(test)]
+impl LocalWrapper for SimRecorder {
+    fn error(
+        &mut self,
+        req_id: i64,
+        error_code: i64,
+        _error_string: String,
+        _advanced_order_reject_json: String,
+    ) -> impl std::future::Future {
+        self.errors.push((req_id, error_code));
+        async {}
+    }
+
+    fn order_status(&mut self, status: OrderStatus) -> impl std::future::Future {
+        self.statuses.push(status);
+        async {}
+    }
+
+    fn execution(&mut self, req_id: i64, execution: Execution) -> impl std::future::Future {
+        self.executions.push((req_id, execution));
+        async {}
+    }
+
+    fn position_summary(&mut self, summary: PositionSummary) -> impl std::future::Future {
+        self.positions.push(summary);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_sim() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::TimeDelta;
+
+    use crate::contract::Stock;
+    use crate::order::{Limit, Market, OrderAttributes, TimeInForce};
+    use crate::payload::{BarCore, BidAsk, TickAttributes};
+
+    let stock: ExchangeProxy<Stock> = toml::from_str(crate::contract::AAPL)?;
+    let (stock, contract_id) = (stock.inner, ContractId(265_598));
+    let start = DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let mut client = Simulator::new(SimRecorder::default(), start);
+
+    // A limit order rests until the market reaches its price.
+    let limit = Limit {
+        quantity: Quantity::from(10),
+        cash_quantity: None,
+        price: 99.0,
+        time_in_force: TimeInForce::Day,
+        attributes: OrderAttributes::default(),
+    };
+    let placed = client
+        .req_place_order(&Order::Buy {
+            security: &stock,
+            execute_method: &limit,
+        })
+        .await?;
+    client
+        .feed_tick(
+            contract_id,
+            &TickData::BidAsk(BidAsk {
+                datetime: start,
+                bid_price: 99.5,
+                ask_price: 100.0,
+                bid_size: 100.0,
+                ask_size: 100.0,
+                attributes: TickAttributes::default(),
+            }),
+        )
+        .await;
+    assert!(matches!(placed.status(), Some(OrderStatus::Submitted(_))));
+    let bar = Bar::Ordinary(BarCore {
+        datetime: start + TimeDelta::minutes(1),
+        open: 99.8,
+        high: 100.2,
+        low: 98.5,
+        close: 99.2,
+    });
+    client.feed_bar(contract_id, &bar).await;
+    assert!(matches!(placed.status(), Some(OrderStatus::Filled(_))));
+    assert_eq!(client.position(contract_id), Quantity::from(10));
+
+    // A market order fills immediately against the last price.
+    let market = Market {
+        quantity: Quantity::from(4),
+        cash_quantity: None,
+        time_in_force: TimeInForce::Day,
+        attributes: OrderAttributes::default(),
+    };
+    client
+        .req_place_order(&Order::Sell {
+            security: &stock,
+            execute_method: &market,
+        })
+        .await?;
+    assert_eq!(client.position(contract_id), Quantity::from(6));
+    client.cancel_order(placed.id()).await?;
+    client.req_positions().await?;
+    client.req_executions(Filter::default()).await?;
+
+    let wrapper = client.into_wrapper();
+    let prices = wrapper
+        .executions
+        .iter()
+        .map(|(req_id, e)| (*req_id, e.is_buy(), e.as_exec().price))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        prices,
+        vec![
+            (-1, true, 99.0),
+            (-1, false, 99.2),
+            (1, true, 99.0),
+            (1, false, 99.2)
+        ]
+    );
+    assert_eq!(wrapper.errors, vec![(placed.id(), CANCEL_NOT_FOUND)]);
+    assert_eq!(wrapper.positions.len(), 1);
+    assert_eq!(wrapper.positions[0].position, Quantity::from(6));
+    assert_eq!(wrapper.positions[0].average_cost, 99.0);
+    Ok(())
+}
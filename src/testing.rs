@@ -0,0 +1,2283 @@
+use std::net::Ipv4Addr;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::constants::MAX_CLIENT_VERSION;
+use crate::transport::Transport;
+
+const API_PREFIX: &[u8] = b"API\0";
+
+// === Type definitions ===
+
+#[derive(Debug)]
+/// A fake TWS / IB Gateway that listens on a local TCP port, so that
+/// [`crate::client::Builder::connect`] and the messages sent by a [`crate::client::Client`] can
+/// be tested end to end without a connection to IBKR.
+///
+/// [`MockServer::accept`] performs the API handshake (server version and connection time,
+/// followed by the managed accounts and next valid order ID that every client waits for) and
+/// returns a [`MockConnection`] with which a test sends canned messages and checks the requests
+/// that it receives. A server created with [`MockServer::new`] does not listen on a port, and
+/// instead performs the handshake over any [`Transport`] with [`MockServer::handshake`].
+pub struct MockServer {
+    listener: Option<TcpListener>,
+    server_version: u32,
+    connection_time: String,
+    managed_accounts: Vec<String>,
+    next_valid_id: i64,
+}
+
+/// A single client connection accepted by a [`MockServer`].
+pub struct MockConnection {
+    stream: Box<dyn Transport>,
+    client_versions: String,
+    client_id: i64,
+}
+
+// === Type implementations ===
+
+impl Default for MockServer {
+    fn default() -> Self {
+        Self {
+            listener: None,
+            server_version: u32::from(MAX_CLIENT_VERSION),
+            connection_time: "20240102 09:30:00 US/Eastern".to_owned(),
+            managed_accounts: vec!["DU1234567".to_owned()],
+            next_valid_id: 1,
+        }
+    }
+}
+
+impl std::fmt::Debug for MockConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockConnection")
+            .field("client_versions", &self.client_versions)
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MockServer {
+    #[must_use]
+    #[inline]
+    /// Create a server that does not listen on a port, for use with [`MockServer::handshake`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start listening on an unused port of the loopback address.
+    ///
+    /// # Errors
+    /// Returns any error encountered while binding the port.
+    pub async fn bind() -> std::io::Result<Self> {
+        Ok(Self {
+            listener: Some(TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?),
+            ..Self::default()
+        })
+    }
+
+    #[must_use]
+    /// Set the server version reported during the handshake.
+    pub fn with_server_version(mut self, server_version: u32) -> Self {
+        self.server_version = server_version;
+        self
+    }
+
+    #[must_use]
+    /// Set the connection time reported during the handshake, in the API's
+    /// "YYYYMMDD hh:mm:ss Time/Zone" format.
+    pub fn with_connection_time(mut self, connection_time: impl Into<String>) -> Self {
+        self.connection_time = connection_time.into();
+        self
+    }
+
+    #[must_use]
+    /// Set the accounts reported as managed by every client.
+    pub fn with_managed_accounts<I, S>(mut self, accounts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.managed_accounts = accounts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    #[must_use]
+    /// Set the next valid order ID reported to every client.
+    pub fn with_next_valid_id(mut self, next_valid_id: i64) -> Self {
+        self.next_valid_id = next_valid_id;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Get the port on which the server is listening, for use with
+    /// [`crate::client::Builder::manual`], or 0 for a server created with [`MockServer::new`].
+    pub fn port(&self) -> u16 {
+        self.listener
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+            .map_or(0, |addr| addr.port())
+    }
+
+    /// Accept the next client connection and perform the API handshake.
+    ///
+    /// # Errors
+    /// Returns any error encountered while accepting the connection, an error of kind
+    /// [`std::io::ErrorKind::NotConnected`] if the server was created with [`MockServer::new`],
+    /// or an error of kind [`std::io::ErrorKind::InvalidData`] if the client does not follow the
+    /// handshake.
+    pub async fn accept(&self) -> std::io::Result<MockConnection> {
+        let listener = self.listener.as_ref().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "The server is not listening on a port.",
+            )
+        })?;
+        let (stream, _) = listener.accept().await?;
+        self.handshake(stream).await
+    }
+
+    /// Perform the API handshake with a client that is connected over `transport` (ex. one end
+    /// of [`crate::transport::in_memory`]).
+    ///
+    /// # Errors
+    /// Returns any error encountered while reading or writing, or an error of kind
+    /// [`std::io::ErrorKind::InvalidData`] if the client does not follow the handshake.
+    pub async fn handshake<T: Transport>(&self, transport: T) -> std::io::Result<MockConnection> {
+        let mut stream: Box<dyn Transport> = Box::new(transport);
+
+        let mut prefix = [0; API_PREFIX.len()];
+        stream.read_exact(&mut prefix).await?;
+        if prefix != API_PREFIX {
+            return Err(invalid_data(format!(
+                "Expected the API prefix. Found {prefix:?}."
+            )));
+        }
+        let client_versions = read_message(&mut stream)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        write_message(
+            &mut stream,
+            &[
+                self.server_version.to_string(),
+                self.connection_time.clone(),
+            ],
+        )
+        .await?;
+
+        let start_api = read_message(&mut stream).await?;
+        let client_id = match start_api.as_slice() {
+            [message_id, _, client_id, ..] if message_id == "71" => client_id
+                .parse()
+                .map_err(|_| invalid_data(format!("Invalid client ID {client_id}.")))?,
+            _ => {
+                return Err(invalid_data(format!(
+                    "Expected a start API message. Found {start_api:?}."
+                )))
+            }
+        };
+
+        let mut connection = MockConnection {
+            stream,
+            client_versions,
+            client_id,
+        };
+        connection
+            .send(&["15", "1", &self.managed_accounts.join(",")])
+            .await?;
+        connection
+            .send(&["9", "1", &self.next_valid_id.to_string()])
+            .await?;
+        Ok(connection)
+    }
+}
+
+impl MockConnection {
+    #[must_use]
+    #[inline]
+    /// Get the client ID sent by the client during the handshake.
+    pub fn client_id(&self) -> i64 {
+        self.client_id
+    }
+
+    #[must_use]
+    #[inline]
+    /// Get the range of client versions sent by the client during the handshake
+    /// (ex. "v180..180").
+    pub fn client_versions(&self) -> &str {
+        &self.client_versions
+    }
+
+    /// Send a single message to the client.
+    ///
+    /// # Arguments
+    /// * `fields` - The fields of the message, starting with the incoming message ID.
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing to the connection.
+    pub async fn send<S: AsRef<str>>(&mut self, fields: &[S]) -> std::io::Result<()> {
+        write_message(&mut self.stream, fields).await
+    }
+
+    /// Send a sequence of messages to the client, in order.
+    ///
+    /// # Arguments
+    /// * `messages` - The messages, each of which is a list of fields (see
+    ///   [`crate::replay::parse_message`]).
+    ///
+    /// # Errors
+    /// Returns any error encountered while writing to the connection.
+    pub async fn send_all<I, M, S>(&mut self, messages: I) -> std::io::Result<()>
+    where
+        I: IntoIterator<Item = M>,
+        M: AsRef<[S]>,
+        S: AsRef<str>,
+    {
+        for message in messages {
+            self.send(message.as_ref()).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the next request from the client.
+    ///
+    /// # Returns
+    /// The fields of the request, starting with the outgoing message ID.
+    ///
+    /// # Errors
+    /// Returns any error encountered while reading from the connection, including an error of
+    /// kind [`std::io::ErrorKind::UnexpectedEof`] if the client disconnects.
+    pub async fn recv(&mut self) -> std::io::Result<Vec<String>> {
+        read_message(&mut self.stream).await
+    }
+
+    /// Wait for the next request from the client and check that it matches `expected`.
+    ///
+    /// # Arguments
+    /// * `expected` - The expected fields of the request, starting with the outgoing message ID.
+    ///
+    /// # Errors
+    /// Returns any error returned by [`MockConnection::recv`].
+    ///
+    /// # Panics
+    /// Panics if the request does not match `expected`.
+    pub async fn expect_request(&mut self, expected: &[&str]) -> std::io::Result<Vec<String>> {
+        let request = self.recv().await?;
+        assert_eq!(request, expected, "Unexpected request");
+        Ok(request)
+    }
+
+    /// Close the connection.
+    ///
+    /// # Errors
+    /// Returns any error encountered while shutting down the connection.
+    pub async fn shutdown(mut self) -> std::io::Result<()> {
+        self.stream.shutdown().await
+    }
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+async fn read_message<R: AsyncRead + Unpin>(stream: &mut R) -> std::io::Result<Vec<String>> {
+    let len = usize::try_from(stream.read_u32().await?)
+        .map_err(|_| invalid_data("Message length exceeds usize::MAX.".to_owned()))?;
+    let mut buf = vec![0; len];
+    stream.read_exact(&mut buf).await?;
+    let message = String::from_utf8(buf).map_err(|e| invalid_data(e.to_string()))?;
+    let mut fields = message
+        .split('\0')
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+    // Every field is terminated by a NUL, which leaves an empty string after the last one.
+    if fields.last().is_some_and(String::is_empty) {
+        fields.pop();
+    }
+    Ok(fields)
+}
+
+async fn write_message<W, S>(stream: &mut W, fields: &[S]) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    S: AsRef<str>,
+{
+    let mut body = Vec::new();
+    for field in fields {
+        body.extend_from_slice(field.as_ref().as_bytes());
+        body.push(0);
+    }
+    let len = u32::try_from(body.len())
+        .map_err(|_| invalid_data("Message length exceeds u32::MAX.".to_owned()))?;
+    let mut message = len.to_be_bytes().to_vec();
+    message.extend(body);
+    stream.write_all(&message).await
+}
+
+#[cfg(test)]
+// Connect a client to `server` over an in-memory transport and start its loop with `wrapper`,
+// returning the active client and the server's end of the connection.
+pub(crate) async fn connect_mock<W: crate::wrapper::Wrapper + Send + 'static>(
+    builder: crate::client::Builder,
+    server: &MockServer,
+    wrapper: W,
+) -> Result<(crate::client::ActiveClient, MockConnection), Box<dyn std::error::Error>> {
+    let (client_end, server_end) = crate::transport::in_memory(1 << 16);
+    let (client, connection) = tokio::join!(
+        builder.connect_with(client_end, 0),
+        server.handshake(server_end)
+    );
+    let (client, connection) = (client?, connection?);
+    Ok((client.disaggregated(wrapper).await, connection))
+}
+
+#[cfg(test)]
+// Connect a client with default options to a default `server`, ignoring its callbacks.
+pub(crate) async fn connect_default_mock(
+) -> Result<(crate::client::ActiveClient, MockConnection), Box<dyn std::error::Error>> {
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    connect_mock(
+        crate::client::Builder::manual(4002, None),
+        &MockServer::new(),
+        TimeWrapper(tx),
+    )
+    .await
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct TimeWrapper(tokio::sync::mpsc::UnboundedSender<chrono::DateTime<chrono::Utc>>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for TimeWrapper {
+    fn current_time(
+        &mut self,
+        _req_id: i64,
+        datetime: chrono::DateTime<chrono::Utc>,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send(datetime);
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_mock_server() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::bind()
+        .await?
+        .with_managed_accounts(["DU1", "DU2"]);
+    let builder = crate::client::Builder::manual(server.port(), None);
+    let (client, connection) = tokio::join!(builder.connect(7), server.accept());
+    let (client, mut connection) = (client?, connection?);
+    assert_eq!(connection.client_id(), 7);
+    assert_eq!(connection.client_versions(), "v180..180");
+    assert_eq!(client.get_server_version(), 180);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut client = client.disaggregated(TimeWrapper(tx)).await;
+    assert_eq!(
+        client.get_managed_accounts(),
+        &std::collections::HashSet::from(["DU1".to_owned(), "DU2".to_owned()])
+    );
+    client.req_current_time().await?;
+    connection.expect_request(&["49", "1"]).await?;
+    connection.send(&["15", "1", "DU1,DU2,DU3"]).await?;
+    connection.send(&["49", "1", "1704205800"]).await?;
+    assert_eq!(
+        rx.recv().await,
+        chrono::DateTime::from_timestamp(1_704_205_800, 0)
+    );
+    assert_eq!(client.get_managed_accounts().len(), 2);
+    assert_eq!(
+        client.get_current_managed_accounts(),
+        std::collections::HashSet::from(["DU1".to_owned(), "DU2".to_owned(), "DU3".to_owned()])
+    );
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct BlockingTimeWrapper(crate::client::blocking::EventSender<chrono::DateTime<chrono::Utc>>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for BlockingTimeWrapper {
+    fn current_time(
+        &mut self,
+        _req_id: i64,
+        datetime: chrono::DateTime<chrono::Utc>,
+    ) -> impl std::future::Future + Send {
+        self.0.send(datetime);
+        async {}
+    }
+}
+
+#[test]
+fn test_blocking_client() -> Result<(), Box<dyn std::error::Error>> {
+    // The blocking client owns its runtime, so the server runs on a separate one.
+    let runtime = tokio::runtime::Runtime::new()?;
+    let server = runtime.block_on(MockServer::bind())?;
+    let builder = crate::client::Builder::manual(server.port(), None);
+    let served = runtime.spawn(async move {
+        let mut connection = server.accept().await?;
+        connection.expect_request(&["49", "1"]).await?;
+        connection.send(&["49", "1", "1704205800"]).await?;
+        connection.expect_request(&["61", "1"]).await?;
+        Ok::<_, std::io::Error>(())
+    });
+
+    let mut client = crate::client::blocking::Client::connect(&builder, 0, BlockingTimeWrapper)?;
+    client.req_current_time()?;
+    assert_eq!(
+        client.next_event_timeout(std::time::Duration::from_secs(5)),
+        chrono::DateTime::from_timestamp(1_704_205_800, 0)
+    );
+    client.req_positions()?;
+    runtime.block_on(served)??;
+    assert!(client.try_events().next().is_none());
+    client.disconnect()?;
+    Ok(())
+}
+
+#[cfg(feature = "rustls")]
+#[tokio::test]
+async fn test_tls() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::Arc;
+
+    // A TLS-terminating tunnel with a self-signed certificate for localhost and an internal
+    // name, which the client trusts.
+    let cert = rcgen::generate_simple_self_signed([
+        "localhost".to_owned(),
+        "gateway.internal".to_owned(),
+    ])?;
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(cert.cert.der().clone())?;
+    let client_config = Arc::new(
+        rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    );
+    let server_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![cert.cert.der().clone()],
+            rustls::pki_types::PrivateKeyDer::Pkcs8(cert.key_pair.serialize_der().into()),
+        )?;
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let port = listener.local_addr()?.port();
+    let tunnel = || async {
+        let (stream, _) = listener.accept().await?;
+        MockServer::new()
+            .handshake(acceptor.accept(stream).await?)
+            .await
+    };
+
+    let builder = crate::client::Builder::manual_tls(port, "localhost", Arc::clone(&client_config));
+    let (client, connection) = tokio::join!(builder.connect(3), tunnel());
+    let (client, mut connection) = (client?, connection?);
+    assert_eq!(connection.client_id(), 3);
+    assert_eq!(client.get_address(), Ipv4Addr::LOCALHOST);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut client = client.disaggregated(TimeWrapper(tx)).await;
+    client.req_current_time().await?;
+    connection.expect_request(&["49", "1"]).await?;
+    connection.send(&["49", "1", "1704205800"]).await?;
+    assert_eq!(
+        rx.recv().await,
+        chrono::DateTime::from_timestamp(1_704_205_800, 0)
+    );
+    client.disconnect().await?;
+
+    // The certificate does not match a different host name.
+    let builder = crate::client::Builder::manual_tls(port, "127.0.0.1", Arc::clone(&client_config));
+    let (client, connection) = tokio::join!(builder.connect(3), tunnel());
+    assert!(client.is_err());
+    assert!(connection.is_err());
+
+    // A proxy is asked to connect to the host name, which is not looked up locally.
+    let proxy = crate::transport::Proxy::HttpConnect {
+        host: "127.0.0.1".to_owned(),
+        port,
+        credentials: None,
+    };
+    let proxied_tunnel = async {
+        let (mut stream, _) = listener.accept().await?;
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(stream.read_u8().await?);
+        }
+        assert_eq!(
+            String::from_utf8_lossy(&request),
+            "CONNECT gateway.internal:4003 HTTP/1.1\r\nHost: gateway.internal:4003\r\n\r\n"
+        );
+        stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await?;
+        MockServer::new()
+            .handshake(acceptor.accept(stream).await?)
+            .await
+    };
+    let builder = crate::client::Builder::manual_tls(4003, "gateway.internal", client_config)
+        .with_proxy(proxy);
+    let (client, connection) = tokio::join!(builder.connect(3), proxied_tunnel);
+    assert_eq!(connection?.client_id(), 3);
+    assert_eq!(client?.get_address(), Ipv4Addr::UNSPECIFIED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::client::{ConnectionError, Session};
+
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let server = MockServer::bind().await?;
+    let mut session = Session::new(
+        crate::client::Builder::manual(server.port(), None)
+            .with_contract_cache(crate::contract::Cache::new()),
+    );
+    for client_id in [8, 9] {
+        let (client, connection) = tokio::join!(
+            session.connect(client_id, TimeWrapper(tx.clone())),
+            server.accept()
+        );
+        assert_eq!(client?.get_client_id(), client_id);
+        assert_eq!(connection?.client_id(), client_id);
+    }
+    // A duplicate client ID is rejected without connecting.
+    assert!(matches!(
+        session.connect(9, TimeWrapper(tx.clone())).await,
+        Err(ConnectionError::DuplicateClientId(9))
+    ));
+    assert_eq!(session.client_ids().collect::<Vec<_>>(), [8, 9]);
+
+    // The shared contract cache moves to whichever client is borrowed mutably.
+    assert!(session
+        .client(9)
+        .is_some_and(|client| client.get_contract_cache().is_some()));
+    assert!(session
+        .client_mut(8)
+        .is_some_and(|client| client.get_contract_cache().is_some()));
+    assert!(session
+        .client(9)
+        .is_some_and(|client| client.get_contract_cache().is_none()));
+
+    assert!(session.disconnect_client(8).await?);
+    assert!(!session.disconnect_client(8).await?);
+    assert!(session.get_contract_cache().is_some());
+    assert_eq!(session.client_ids().collect::<Vec<_>>(), [9]);
+    // The builder that is returned keeps the shared contract cache.
+    let builder = session.disconnect().await?;
+    let (client, connection) = tokio::join!(builder.connect(8), server.accept());
+    connection?;
+    assert!(client?.get_contract_cache().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_server_in_memory() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::new().with_next_valid_id(42);
+    assert_eq!(server.port(), 0);
+    assert!(server.accept().await.is_err());
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) = connect_mock(
+        crate::client::Builder::manual(4002, None),
+        &server,
+        TimeWrapper(tx),
+    )
+    .await?;
+    assert_eq!(connection.client_id(), 0);
+    assert_eq!(client.get_port(), 4002);
+    client.req_current_time().await?;
+    connection.expect_request(&["49", "1"]).await?;
+    connection.send(&["49", "1", "1704205800"]).await?;
+    assert_eq!(
+        rx.recv().await,
+        chrono::DateTime::from_timestamp(1_704_205_800, 0)
+    );
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, Eq)]
+enum Liveness {
+    Heartbeat,
+    Stalled(std::time::Duration),
+    CurrentTime,
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct LivenessWrapper(tokio::sync::mpsc::UnboundedSender<Liveness>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for LivenessWrapper {
+    fn current_time(
+        &mut self,
+        _req_id: i64,
+        _datetime: chrono::DateTime<chrono::Utc>,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send(Liveness::CurrentTime);
+        async {}
+    }
+
+    fn heartbeat(&mut self, _round_trip: std::time::Duration) -> impl std::future::Future + Send {
+        let _ = self.0.send(Liveness::Heartbeat);
+        async {}
+    }
+
+    fn connection_stalled(
+        &mut self,
+        silence: std::time::Duration,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send(Liveness::Stalled(silence));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_watchdog() -> Result<(), Box<dyn std::error::Error>> {
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_millis(50);
+
+    let stalled = crate::wrapper::CancelToken::new();
+    let (tap_tx, mut tap_rx) = tokio::sync::mpsc::unbounded_channel();
+    let builder = crate::client::Builder::manual(4002, None)
+        .with_watchdog(
+            crate::client::Watchdog::new(std::time::Duration::from_millis(10), TIMEOUT)
+                .with_cancel(stalled.clone()),
+        )
+        .with_message_tap(crate::client::MessageTap::from_channel(tap_tx));
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (client, mut connection) =
+        connect_mock(builder, &MockServer::new(), LivenessWrapper(tx)).await?;
+    connection.expect_request(&["49", "1"]).await?;
+    connection.send(&["49", "1", "1704205800"]).await?;
+    assert_eq!(rx.recv().await, Some(Liveness::Heartbeat));
+
+    // The next request is left unanswered.
+    connection.expect_request(&["49", "1"]).await?;
+    match rx.recv().await {
+        Some(Liveness::Stalled(silence)) => assert!(silence >= TIMEOUT),
+        other => panic!("Expected a stall, got {other:?}"),
+    }
+    assert!(stalled.is_cancelled());
+
+    // Monitoring continues after the stall.
+    connection.expect_request(&["49", "1"]).await?;
+    connection.send(&["49", "1", "1704205801"]).await?;
+    assert_eq!(rx.recv().await, Some(Liveness::Heartbeat));
+    client.disconnect().await?;
+
+    // The requests are sent through the client's writer, which passes them to the tap.
+    let mut pings = 0;
+    while let Ok((direction, fields)) = tap_rx.try_recv() {
+        if direction == crate::client::Direction::Outbound && fields == ["49", "1", ""] {
+            pings += 1;
+        }
+    }
+    assert!(pings >= 3);
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, Eq)]
+enum Lifecycle {
+    Pending,
+    Started,
+    Complete,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct LifecycleWrapper(tokio::sync::mpsc::UnboundedSender<Lifecycle>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for LifecycleWrapper {
+    fn restart_pending(
+        &mut self,
+        _period: crate::restart::Period,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send(Lifecycle::Pending);
+        async {}
+    }
+
+    fn restart_started(
+        &mut self,
+        _period: crate::restart::Period,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send(Lifecycle::Started);
+        async {}
+    }
+
+    fn restart_complete(
+        &mut self,
+        _period: crate::restart::Period,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send(Lifecycle::Complete);
+        async {}
+    }
+}
+
+#[cfg(test)]
+impl crate::wrapper::Recurring for LifecycleWrapper {
+    async fn cycle(&mut self) {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[cfg(test)]
+impl crate::wrapper::Initializer for LifecycleWrapper {
+    type Wrap<'c> = Self;
+
+    async fn build(
+        self,
+        _client: &mut crate::client::ActiveClient,
+        _cancel_loop: crate::wrapper::CancelToken,
+    ) -> Self {
+        self
+    }
+}
+
+#[tokio::test]
+async fn test_restart_window() -> Result<(), Box<dyn std::error::Error>> {
+    use std::time::Duration;
+
+    use crate::restart::{Paused, Window};
+
+    // Requests are refused while a restart is pending.
+    let start = chrono::Utc::now() + chrono::TimeDelta::seconds(30);
+    let builder = crate::client::Builder::manual(4002, None).with_restart_window(
+        Window::daily(start.time(), chrono_tz::UTC).with_lead(Duration::from_secs(60)),
+    );
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, _connection) =
+        connect_mock(builder, &MockServer::new(), LifecycleWrapper(tx)).await?;
+    assert_eq!(rx.recv().await, Some(Lifecycle::Pending));
+    let e = client.req_current_time().await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::WouldBlock);
+    let paused = e.get_ref().and_then(|e| e.downcast_ref::<Paused>());
+    assert!(paused.is_some_and(|paused| paused.resumes > start));
+    // A refused request is never recorded as outstanding.
+    let e = client.req_user_info().await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::WouldBlock);
+    assert!(client.get_outstanding_requests().is_empty());
+    client.disconnect().await?;
+
+    // A supervised client disconnects when the restart begins, and reconnects after it.
+    let server = MockServer::bind().await?;
+    let start = chrono::Utc::now() + chrono::TimeDelta::milliseconds(300);
+    let builder = crate::client::Builder::manual(server.port(), None).with_restart_window(
+        Window::daily(start.time(), chrono_tz::UTC)
+            .with_lead(Duration::from_millis(200))
+            .with_duration(Duration::from_millis(200)),
+    );
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let disconnect = crate::wrapper::CancelToken::new();
+    let (builder, served) = tokio::join!(
+        builder.local_with_restarts(0, LifecycleWrapper(tx), disconnect.clone()),
+        async {
+            let mut connection = server.accept().await?;
+            assert_eq!(rx.recv().await, Some(Lifecycle::Pending));
+            assert_eq!(rx.recv().await, Some(Lifecycle::Started));
+            assert!(connection.recv().await.is_err());
+            let _connection = server.accept().await?;
+            assert_eq!(rx.recv().await, Some(Lifecycle::Complete));
+            assert!(chrono::Utc::now() >= start + chrono::TimeDelta::milliseconds(200));
+            disconnect.cancel();
+            Ok::<_, std::io::Error>(())
+        }
+    );
+    builder?;
+    served?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limits() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ExchangeProxy, Stock};
+    use crate::limits::{Kind, LimitExceeded, Limits};
+    use crate::market_data::live_data::{Empty, RefreshType};
+
+    let stock = toml::from_str::<ExchangeProxy<Stock>>(crate::contract::AAPL)?.inner;
+
+    let builder = crate::client::Builder::manual(4002, None)
+        .with_limits(Limits::new().with_market_data_lines(1));
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) =
+        connect_mock(builder, &MockServer::new(), TimeWrapper(tx)).await?;
+    let req_id = client
+        .req_market_data(&stock, Vec::<Empty>::new(), RefreshType::Streaming, false)
+        .await?;
+    connection.recv().await?;
+    assert_eq!(client.get_subscription_usage(Kind::MarketData).active, 1);
+
+    // The second line is refused before anything is sent, but snapshots are not counted.
+    let error = client
+        .req_market_data(&stock, Vec::<Empty>::new(), RefreshType::Streaming, false)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::QuotaExceeded);
+    assert_eq!(
+        error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<LimitExceeded>()),
+        Some(&LimitExceeded {
+            kind: Kind::MarketData,
+            limit: 1
+        })
+    );
+    client
+        .req_market_data(&stock, Vec::<Empty>::new(), RefreshType::Snapshot, false)
+        .await?;
+    let snapshot = connection.recv().await?;
+    assert_eq!(snapshot[snapshot.len() - 3], "1");
+
+    client.cancel_market_data(req_id).await?;
+    connection
+        .expect_request(&["2", "2", &req_id.to_string()])
+        .await?;
+    client
+        .req_market_data(&stock, Vec::<Empty>::new(), RefreshType::Streaming, false)
+        .await?;
+    connection.recv().await?;
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_market_data_bulk() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ContractId, ExchangeProxy, Stock};
+    use crate::limits::Limits;
+    use crate::market_data::live_data::{Empty, RefreshType};
+    use crate::requests::RequestId;
+
+    let stock = |contract_id: i64, symbol: &str| -> Result<Stock, toml::de::Error> {
+        let stock: ExchangeProxy<Stock> = toml::from_str(&format!(
+            r#"
+            contract_type = "STK"
+            contract_id = {contract_id}
+            symbol = "{symbol}"
+            currency = "USD"
+            local_symbol = "{symbol}"
+            exchange = "SMART"
+            trading_class = "NMS"
+            "#
+        ))?;
+        Ok(stock.inner)
+    };
+    let watchlist = [
+        stock(265_598, "AAPL")?,
+        stock(272_093, "MSFT")?,
+        stock(265_598, "AAPL")?,
+    ];
+
+    let builder = crate::client::Builder::manual(4002, None)
+        .with_limits(Limits::new().with_market_data_lines(2));
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) =
+        connect_mock(builder, &MockServer::new(), TimeWrapper(tx)).await?;
+
+    // Duplicates are only subscribed once.
+    let (subscribed, requests) = tokio::join!(
+        client.req_market_data_bulk(&watchlist, &[] as &[Empty], RefreshType::Streaming),
+        async { (connection.recv().await, connection.recv().await) }
+    );
+    let subscribed = subscribed?;
+    let (aapl, msft) = (requests.0?, requests.1?);
+    assert_eq!(subscribed.len(), 2);
+    assert_eq!(
+        subscribed.req_id(ContractId(265_598)).map(RequestId::get),
+        aapl[2].parse().ok()
+    );
+    assert_eq!(
+        subscribed.req_id(ContractId(272_093)).map(RequestId::get),
+        msft[2].parse().ok()
+    );
+    assert_eq!(
+        subscribed.contract_id(msft[2].parse()?),
+        Some(ContractId(272_093))
+    );
+
+    // Every subscription is cancelled at once.
+    let (cancelled, requests) =
+        tokio::join!(client.cancel_market_data_bulk(subscribed.clone()), async {
+            (connection.recv().await, connection.recv().await)
+        });
+    cancelled?;
+    let mut cancelled = [requests.0?[2].clone(), requests.1?[2].clone()];
+    cancelled.sort();
+    let mut expected = [aapl[2].clone(), msft[2].clone()];
+    expected.sort();
+    assert_eq!(cancelled, expected);
+
+    // A failed subscription cancels those that were made before it.
+    let watchlist = [
+        stock(265_598, "AAPL")?,
+        stock(272_093, "MSFT")?,
+        stock(76_792_991, "TSLA")?,
+    ];
+    let (subscribed, requests) = tokio::join!(
+        client.req_market_data_bulk(&watchlist, &[] as &[Empty], RefreshType::Streaming),
+        async {
+            let mut requests = Vec::new();
+            for _ in 0..4 {
+                requests.push(connection.recv().await?[0].clone());
+            }
+            Ok::<_, std::io::Error>(requests)
+        }
+    );
+    assert_eq!(
+        subscribed.unwrap_err().kind(),
+        std::io::ErrorKind::QuotaExceeded
+    );
+    assert_eq!(requests?, ["1", "1", "2", "2"]);
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fractional_order_quantity() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ExchangeProxy, Security, Stock};
+    use crate::order::{Limit, Order, OrderAttributes, TimeInForce};
+    use crate::quantity::Quantity;
+
+    let stock = toml::from_str::<ExchangeProxy<Stock>>(crate::contract::AAPL)?.inner;
+    let (mut client, mut connection) = connect_default_mock().await?;
+    // The sum of the equivalent `f64` values is 0.30000000000000004.
+    let limit = Limit {
+        quantity: Quantity::try_from(0.1)? + Quantity::try_from(0.2)?,
+        cash_quantity: None,
+        price: 180.0,
+        time_in_force: TimeInForce::Day,
+        attributes: OrderAttributes::default(),
+    };
+    let order = Order::Buy {
+        security: &stock,
+        execute_method: &limit,
+    };
+    let placed = client.req_place_order(&order).await?;
+    let fields = connection.recv().await?;
+    let order_type = fields
+        .iter()
+        .position(|field| field == "LMT")
+        .ok_or("the order type should be sent")?;
+    assert_eq!(fields[order_type - 1], "0.3");
+    // The fields returned without sending the order are those that were sent.
+    assert_eq!(order.to_wire_fields(placed.id())?, fields);
+    let contract = stock.to_wire_fields()?;
+    assert_eq!(fields[2..2 + contract.len()], contract);
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_time_offset() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::TimeDelta;
+
+    use crate::order::TimeInForce;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) = connect_mock(
+        crate::client::Builder::manual(4002, None),
+        &MockServer::new(),
+        TimeWrapper(tx),
+    )
+    .await?;
+    assert_eq!(client.get_server_time_offset(), None);
+
+    // The server's clock is an hour ahead of the local clock.
+    client.req_current_time().await?;
+    connection.expect_request(&["49", "1"]).await?;
+    let server_time = chrono::Utc::now() + TimeDelta::hours(1);
+    connection
+        .send(&["49", "1", &server_time.timestamp().to_string()])
+        .await?;
+    rx.recv()
+        .await
+        .ok_or("the current time should be received")?;
+    let offset = client
+        .get_server_time_offset()
+        .ok_or("the offset should be estimated")?;
+    assert!((offset - TimeDelta::hours(1)).abs() < TimeDelta::seconds(1));
+
+    let TimeInForce::GoodTillDate(expiry) =
+        client.good_till_date_in(TimeDelta::minutes(5), chrono_tz::America::New_York)
+    else {
+        return Err("the time in force should be good till date".into());
+    };
+    let expected = server_time + TimeDelta::minutes(5);
+    assert!((expiry.to_utc() - expected).abs() < TimeDelta::seconds(2));
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(feature = "scanner")]
+#[tokio::test]
+async fn test_scanner_subscription() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::scanner::{Instrument, LocationCode, ScanCode, Subscription};
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let subscription = Subscription::new(
+        Instrument::Stock,
+        LocationCode::StockUsMajor,
+        ScanCode::TopPercentGain,
+    )
+    .with_rows(10)
+    .with_filter("priceAbove", 5);
+    let req_id = client.req_scanner_subscription(&subscription).await?;
+    connection
+        .expect_request(&[
+            "22",
+            &req_id.to_string(),
+            "10",
+            "STK",
+            "STK.US.MAJOR",
+            "TOP_PERC_GAIN",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "0",
+            "",
+            "",
+            "",
+            "priceAbove=5;",
+            "",
+        ])
+        .await?;
+    client.cancel_scanner_subscription(req_id).await?;
+    connection
+        .expect_request(&["23", "1", &req_id.to_string()])
+        .await?;
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(feature = "news")]
+#[tokio::test]
+async fn test_historical_news() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::ContractId;
+    use crate::news::{Provider, Query};
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let query = Query::new(
+        ContractId(265_598),
+        [Provider::BriefingGeneral, Provider::DowJonesNewsletters],
+    )
+    .with_max_headlines(2);
+    let since = chrono::DateTime::from_timestamp(1_704_067_200, 0).unwrap();
+
+    // The second page continues from the earliest headline of the first, which is repeated.
+    let server = async {
+        let request = connection.recv().await?;
+        assert_eq!(
+            request[2..],
+            ["265598", "BRFG+DJNL", "2024-01-01 00:00:00.0", "", "2", ""]
+        );
+        let id = &request[1];
+        connection
+            .send(&[
+                "86",
+                id,
+                "2024-01-03 12:00:00.0",
+                "BRFG",
+                "BRFG$1",
+                "Newest",
+            ])
+            .await?;
+        connection
+            .send(&[
+                "86",
+                id,
+                "2024-01-02 12:00:00.0",
+                "DJNL",
+                "DJNL$2",
+                "Middle",
+            ])
+            .await?;
+        connection.send(&["87", id, "1"]).await?;
+
+        let request = connection.recv().await?;
+        assert_eq!(request[5], "2024-01-02 12:00:00.0");
+        let id = &request[1];
+        connection
+            .send(&[
+                "86",
+                id,
+                "2024-01-02 12:00:00.0",
+                "DJNL",
+                "DJNL$2",
+                "Middle",
+            ])
+            .await?;
+        connection
+            .send(&[
+                "86",
+                id,
+                "2024-01-01 08:00:00.0",
+                "BRFG",
+                "BRFG$3",
+                "Oldest",
+            ])
+            .await?;
+        connection.send(&["87", id, "0"]).await?;
+        Ok::<_, std::io::Error>(())
+    };
+    let (headlines, served) = tokio::join!(
+        client.req_historical_news_until(&query, since, std::time::Duration::from_secs(5)),
+        server
+    );
+    served?;
+    let headlines = headlines?;
+    assert_eq!(
+        headlines
+            .iter()
+            .map(|headline| headline.headline.as_str())
+            .collect::<Vec<_>>(),
+        ["Newest", "Middle", "Oldest"]
+    );
+    assert_eq!(headlines[1].provider(), Provider::DowJonesNewsletters);
+    assert!(client.get_outstanding_requests().is_empty());
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_historical_tick_iterator() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ExchangeProxy, Stock};
+    use crate::market_data::historical_ticks::{HistoricalTickIterator, Trades};
+    use crate::payload::TickData;
+
+    let stock: ExchangeProxy<Stock> = toml::from_str(crate::contract::AAPL)?;
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let start = chrono::DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let ticks = HistoricalTickIterator::new(
+        stock.inner,
+        Trades,
+        start,
+        start + chrono::TimeDelta::seconds(100),
+        false,
+    )
+    .with_pacing(std::time::Duration::ZERO);
+
+    // Ten trades each second. The first page is full and ends partway through second 99, so the
+    // second page starts at that second and repeats its trades. It also reaches past the end of
+    // the period.
+    let message = |id: &str, trades: std::ops::Range<i64>| {
+        let mut fields = vec![
+            "98".to_owned(),
+            id.to_owned(),
+            trades.clone().count().to_string(),
+        ];
+        for i in trades {
+            fields.extend([
+                (start.timestamp() + i / 10).to_string(),
+                "0".to_owned(),
+                i.to_string(),
+                "1".to_owned(),
+                "ISLAND".to_owned(),
+                String::new(),
+            ]);
+        }
+        fields.push("1".to_owned());
+        fields
+    };
+    let server = async {
+        let request = connection.recv().await?;
+        assert!(request.contains(&"20240102-14:30:00".to_owned()));
+        assert!(request.contains(&"1000".to_owned()));
+        connection.send(&message(&request[1], 0..1_000)).await?;
+
+        let request = connection.recv().await?;
+        assert!(request.contains(&"20240102-14:31:39".to_owned()));
+        connection.send(&message(&request[1], 990..1_020)).await?;
+        Ok::<_, std::io::Error>(())
+    };
+    let (ticks, served) = tokio::join!(ticks.collect(&mut client), server);
+    served?;
+    let prices = ticks?
+        .into_iter()
+        .map(|tick| match tick {
+            TickData::Last(last) => last.price,
+            _ => f64::NAN,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(prices, (0..1_010).map(|i| i as f64).collect::<Vec<_>>());
+    assert!(client.get_outstanding_requests().is_empty());
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_historical_tick_iterator_truncated_second() -> Result<(), Box<dyn std::error::Error>>
+{
+    use crate::contract::{ExchangeProxy, Stock};
+    use crate::market_data::historical_ticks::{HistoricalTickIterator, Trades};
+
+    let stock: ExchangeProxy<Stock> = toml::from_str(crate::contract::AAPL)?;
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let start = chrono::DateTime::from_timestamp(1_704_205_800, 0).unwrap();
+    let mut ticks = HistoricalTickIterator::new(
+        stock.inner,
+        Trades,
+        start,
+        start + chrono::TimeDelta::seconds(100),
+        false,
+    )
+    .with_pacing(std::time::Duration::ZERO);
+
+    // All 1,000 trades of the first page fall in its first second, so the second page starts at
+    // the following second.
+    let message = |id: &str, second: i64, count: usize| {
+        let mut fields = vec!["98".to_owned(), id.to_owned(), count.to_string()];
+        for i in 0..count {
+            fields.extend([
+                (start.timestamp() + second).to_string(),
+                "0".to_owned(),
+                i.to_string(),
+                "1".to_owned(),
+                "ISLAND".to_owned(),
+                String::new(),
+            ]);
+        }
+        fields.push("1".to_owned());
+        fields
+    };
+    let server = async {
+        let request = connection.recv().await?;
+        connection.send(&message(&request[1], 0, 1_000)).await?;
+
+        let request = connection.recv().await?;
+        assert!(request.contains(&"20240102-14:30:01".to_owned()));
+        connection.send(&message(&request[1], 1, 5)).await?;
+        Ok::<_, std::io::Error>(())
+    };
+    let pages = async {
+        let mut count = 0;
+        while let Some(page) = ticks.next_page(&mut client).await? {
+            count += page.len();
+        }
+        Ok::<_, std::io::Error>(count)
+    };
+    let (count, served) = tokio::join!(pages, server);
+    served?;
+    assert_eq!(count?, 1_005);
+    assert_eq!(ticks.truncated_seconds(), [start]);
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(feature = "depth")]
+#[tokio::test]
+async fn test_market_depth_venues() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ExchangeProxy, Stock};
+    use crate::exchange::Primary;
+
+    let stock = toml::from_str::<ExchangeProxy<Stock>>(crate::contract::AAPL)?.inner;
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let smart = client.req_market_depth(&stock, 10, true, None).await?;
+    let request = connection.recv().await?;
+    assert_eq!(request[10], "SMART");
+    assert_eq!(request[request.len() - 2], "1");
+
+    let island = client
+        .req_market_depth(&stock, 10, false, Some(Primary::Island))
+        .await?;
+    let request = connection.recv().await?;
+    assert_eq!(request[10], "ISLAND");
+    assert_eq!(request[request.len() - 2], "0");
+
+    let error = client
+        .req_market_depth(&stock, 10, true, Some(Primary::Island))
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+    // Cancellations repeat whether the subscription was for SMART depth.
+    client.cancel_market_depth(smart).await?;
+    connection
+        .expect_request(&["11", "1", &smart.to_string(), "1"])
+        .await?;
+    client.cancel_market_depth(island).await?;
+    connection
+        .expect_request(&["11", "1", &island.to_string(), "0"])
+        .await?;
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_option_chain() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{option_chain, ChainFilter, ExchangeProxy, SecOptionClass, Stock};
+
+    let stock = toml::from_str::<ExchangeProxy<Stock>>(crate::contract::AAPL)?.inner;
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+
+    let option = |id: &str, expiration: &str, strike: &str, right: &str, class: &str| {
+        [
+            "10",
+            id,
+            "AAPL",
+            "OPT",
+            expiration,
+            strike,
+            right,
+            "SMART",
+            "USD",
+            "",
+            class,
+            class,
+            "1",
+            "0.01",
+            "100",
+            "LMT",
+            "SMART",
+            "1",
+            "265598",
+            "Apple",
+            "",
+            "",
+            "Technology",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "0",
+        ]
+        .map(ToOwned::to_owned)
+    };
+    let server = async {
+        let request = connection.recv().await?;
+        assert_eq!(request, ["78", &request[1], "AAPL", "", "STK", "265598"]);
+        let id = &request[1];
+        let expirations = ["20240126", "20240119", "20240202"];
+        for exchange in ["CBOE", "SMART"] {
+            let mut params = vec!["75", id, exchange, "265598", "AAPL", "100", "3"];
+            params.extend(expirations);
+            params.extend(["4", "180", "185", "190", "200"]);
+            connection.send(&params).await?;
+        }
+        connection.send(&["76", id]).await?;
+
+        // Strikes outside the band and other trading classes are left out.
+        for (expiration, options) in [
+            (
+                "20240119",
+                vec![
+                    ("180", "C", "AAPL"),
+                    ("190", "P", "AAPL"),
+                    ("185", "C", "AAPL"),
+                    ("190", "C", "AAPL"),
+                    ("200", "C", "AAPL"),
+                ],
+            ),
+            (
+                "20240126",
+                vec![("190", "C", "AAPL"), ("190", "C", "AAPL7")],
+            ),
+        ] {
+            let request = connection.recv().await?;
+            assert_eq!(request[0], "9");
+            assert!(request.iter().any(|field| field == expiration));
+            let id = &request[2];
+            for (strike, right, class) in options {
+                connection
+                    .send(&option(id, expiration, strike, right, class))
+                    .await?;
+            }
+            connection.send(&["52", "1", id]).await?;
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let filter = ChainFilter::new(190.0)
+        .with_strike_band(0.05)
+        .with_expirations(2);
+    let (chain, served) = tokio::join!(
+        option_chain(
+            &mut client,
+            &stock,
+            filter,
+            std::time::Duration::from_secs(5)
+        ),
+        server
+    );
+    served?;
+    assert_eq!(
+        chain?
+            .iter()
+            .map(|option| (
+                option.expiration_date().format("%Y%m%d").to_string(),
+                option.strike(),
+                option.class()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("20240119".to_owned(), 185.0, SecOptionClass::Call),
+            ("20240119".to_owned(), 190.0, SecOptionClass::Call),
+            ("20240119".to_owned(), 190.0, SecOptionClass::Put),
+            ("20240126".to_owned(), 190.0, SecOptionClass::Call),
+        ]
+    );
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_account_updates_by_model() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::account::{Attribute, Denomination, Group, Segment};
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+
+    let mut models = client
+        .req_account_updates_by_model(Group::All, ["Growth", "Income"], false)
+        .await?;
+    let growth = connection.recv().await?;
+    assert_eq!(growth[3..], ["", "Growth", "0"]);
+    let income = connection.recv().await?;
+    assert_eq!(income[3..], ["", "Income", "0"]);
+    let (growth, income) = (growth[2].parse()?, income[2].parse()?);
+
+    // The values of a model are summed over its accounts.
+    let net_liquidation =
+        |value| Attribute::NetLiquidation(Segment::Total(value), Denomination::Base);
+    for (req_id, account, value) in [
+        (growth, "DU1111111", 1_000.0),
+        (growth, "DU2222222", 2_500.0),
+        (income, "DU1111111", 400.0),
+    ] {
+        let model = models.account_attribute_multi(
+            req_id,
+            net_liquidation(value),
+            account.to_owned(),
+            None,
+        );
+        assert!(model.is_some());
+    }
+    assert_eq!(
+        models.account_attribute_multi(
+            growth,
+            net_liquidation(2_500.0),
+            "DU2222222".to_owned(),
+            None
+        ),
+        None
+    );
+    assert_eq!(
+        models.account_attribute_multi(
+            growth,
+            Attribute::UnrealizedPnL(-12.5, Denomination::Base),
+            "DU1111111".to_owned(),
+            Some("Growth".to_owned())
+        ),
+        Some("Growth".to_owned())
+    );
+    assert_eq!(models.net_liquidation("Growth"), Some(3_500.0));
+    assert_eq!(models.net_liquidation("Income"), Some(400.0));
+    assert_eq!(models.unrealized_pnl("Growth"), Some(-12.5));
+    assert_eq!(models.unrealized_pnl("Income"), None);
+    assert_eq!(
+        models.accounts("Growth").collect::<Vec<_>>(),
+        ["DU1111111", "DU2222222"]
+    );
+    assert!(!models.is_loaded());
+    models.account_attribute_multi_end(growth);
+    models.account_attribute_multi_end(income);
+    assert!(models.is_loaded());
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pnl_rollup() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::payload::Pnl;
+
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) = connect_mock(
+        crate::client::Builder::manual(4002, None),
+        &MockServer::new().with_managed_accounts(["DU2222222", "DU1111111"]),
+        TimeWrapper(tx),
+    )
+    .await?;
+
+    let mut rollup = client.req_pnl_rollup(None).await?;
+    let first = connection.recv().await?;
+    assert_eq!(first[2..], ["DU1111111", ""]);
+    let second = connection.recv().await?;
+    assert_eq!(second[2..], ["DU2222222", ""]);
+    let (first, second) = (first[1].parse()?, second[1].parse()?);
+
+    // Unset values are left out of the consolidated P&L.
+    let pnl = |daily, unrealized, realized| Pnl {
+        daily,
+        unrealized,
+        realized,
+    };
+    assert_eq!(
+        rollup.pnl(first, pnl(10.0, -5.0, f64::MAX)),
+        Some("DU1111111")
+    );
+    assert!(!rollup.is_loaded());
+    assert_eq!(rollup.pnl(second, pnl(2.5, 7.5, 100.0)), Some("DU2222222"));
+    assert_eq!(rollup.pnl(second, pnl(2.5, 7.5, 100.0)), None);
+    assert_eq!(rollup.pnl(first.max(second) + 1, pnl(1.0, 1.0, 1.0)), None);
+    assert!(rollup.is_loaded());
+    assert_eq!(rollup.daily(), Some(12.5));
+    assert_eq!(rollup.unrealized(), Some(2.5));
+    assert_eq!(rollup.realized(), Some(100.0));
+    assert_eq!(rollup.account("DU2222222"), Some(pnl(2.5, 7.5, 100.0)));
+    assert_eq!(
+        rollup
+            .accounts()
+            .map(|(account, _)| account)
+            .collect::<Vec<_>>(),
+        ["DU1111111", "DU2222222"]
+    );
+
+    client.cancel_pnl_rollup(rollup).await?;
+    connection
+        .expect_request(&["93", &first.to_string()])
+        .await?;
+    connection
+        .expect_request(&["93", &second.to_string()])
+        .await?;
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_head_timestamps() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ContractId, ExchangeProxy, Stock};
+    use crate::market_data::historical_ticks::Trades;
+
+    // More securities than either channel between the client and its loop can hold.
+    const SECURITIES: i64 = 50;
+
+    let securities = (1..=SECURITIES)
+        .map(|contract_id| {
+            let aapl = crate::contract::AAPL.replace("265598", &contract_id.to_string());
+            Ok(toml::from_str::<ExchangeProxy<Stock>>(&aapl)?.inner)
+        })
+        .collect::<Result<Vec<_>, toml::de::Error>>()?;
+    let (mut client, mut connection) = connect_default_mock().await?;
+
+    // Each request is answered as soon as it arrives, except for the last, which is left
+    // unanswered and is cancelled once the timeout elapses.
+    let server = async {
+        let mut ids = Vec::new();
+        for contract_id in 1..=SECURITIES {
+            let request = connection.recv().await?;
+            assert_eq!(request[2], contract_id.to_string());
+            if contract_id < SECURITIES {
+                let timestamp = (1_700_000_000 + contract_id).to_string();
+                connection.send(&["88", &request[1], &timestamp]).await?;
+            }
+            ids.push(request[1].clone());
+        }
+        connection
+            .expect_request(&["90", &ids[ids.len() - 1]])
+            .await?;
+        Ok::<_, std::io::Error>(())
+    };
+    let requested = async {
+        tokio::join!(
+            client.req_head_timestamps(
+                &securities,
+                Trades,
+                false,
+                std::time::Duration::from_millis(500),
+            ),
+            server
+        )
+    };
+    let (timestamps, served) =
+        tokio::time::timeout(std::time::Duration::from_secs(5), requested).await?;
+    served?;
+    let timestamps = timestamps?;
+    assert_eq!(timestamps.len(), SECURITIES as usize - 1);
+    for contract_id in 1..SECURITIES {
+        assert_eq!(
+            timestamps[&ContractId(contract_id)].timestamp(),
+            1_700_000_000 + contract_id
+        );
+    }
+    assert!(client.get_outstanding_requests().is_empty());
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_continuous_future_historical_bar() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ExchangeProxy, SecFuture};
+    use crate::market_data::historical_bar::{DateFormat, Duration, MinuteSize, Size, Trades};
+
+    let future = toml::from_str::<ExchangeProxy<SecFuture>>(
+        r#"
+        contract_type = "FUT"
+        contract_id = 495512563
+        symbol = "ES"
+        currency = "USD"
+        local_symbol = "ESZ4"
+        exchange = { Primary = "CME" }
+        trading_class = "ES"
+        multiplier = 50
+        expiration_date = "2024-12-20"
+        "#,
+    )?
+    .inner;
+
+    // Only the underlying is sent, with the requested date format.
+    let (mut client, mut connection) = connect_default_mock().await?;
+    for (id, date_format, field) in [(0, DateFormat::Local, "1"), (1, DateFormat::Epoch, "2")] {
+        client
+            .req_continuous_future_historical_bar(
+                &future,
+                Duration::Day(1),
+                Size::Minutes(MinuteSize::One),
+                Trades,
+                false,
+                date_format,
+            )
+            .await?;
+        connection
+            .expect_request(&[
+                "20",
+                &id.to_string(),
+                "0",
+                "ES",
+                "CONTFUT",
+                "",
+                "",
+                "",
+                "50",
+                "CME",
+                "",
+                "USD",
+                "",
+                "",
+                "0",
+                "",
+                "1 min",
+                "1 D",
+                "0",
+                "TRADES",
+                field,
+                "0",
+                "",
+            ])
+            .await?;
+    }
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_greeks() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ContractId, ExchangeProxy, SecOption, SecOptionClass};
+    use crate::market_data::greeks::{Greeks, Key};
+    use crate::tick::{
+        CalculationResult, SecOptionCalculationResults, SecOptionCalculationSource,
+        SecOptionCalculations,
+    };
+
+    // Calls with the same expiration and strike on two different underlyings.
+    let option = |symbol: &str, contract_id: i64, underlying: i64| {
+        let mut option = toml::from_str::<ExchangeProxy<SecOption>>(&format!(
+            r#"
+            contract_type = "OPT"
+            contract_id = {contract_id}
+            symbol = "{symbol}"
+            currency = "USD"
+            local_symbol = "{symbol}  240621C00200000"
+            exchange = "SMART"
+            trading_class = "{symbol}"
+            multiplier = 100
+            expiration_date = "2024-06-21"
+            strike = 200.0
+            option_type = "Call"
+            "#
+        ))?
+        .inner;
+        let (SecOption::Call(inner) | SecOption::Put(inner)) = &mut option;
+        inner.underlying_contract_id = ContractId(underlying);
+        Ok::<_, toml::de::Error>(option)
+    };
+    let options = [option("AAPL", 1, 265_598)?, option("MSFT", 2, 272_093)?];
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let mut greeks = Greeks::new();
+    greeks.subscribe(&mut client, &options).await?;
+    let mut req_ids = Vec::new();
+    for symbol in ["AAPL", "MSFT"] {
+        let request = connection.recv().await?;
+        assert_eq!((request[0].as_str(), request[4].as_str()), ("1", symbol));
+        req_ids.push(request[2].parse::<i64>()?);
+    }
+
+    let results = |delta| SecOptionCalculationResults {
+        implied_volatility: CalculationResult::Computed(0.2),
+        delta: CalculationResult::Computed(delta),
+        price: CalculationResult::Computed(5.0),
+        dividend_present_value: CalculationResult::NotComputed,
+        gamma: CalculationResult::NotYetComputed,
+        vega: CalculationResult::NotYetComputed,
+        theta: CalculationResult::NotYetComputed,
+        underlying_price: CalculationResult::Computed(200.0),
+    };
+    let model = |delta| {
+        SecOptionCalculationSource::Model(SecOptionCalculations::PriceBased(results(delta)))
+    };
+    assert!(greeks.update(req_ids[0], model(0.5)));
+    assert!(greeks.update(req_ids[1], model(0.4)));
+    // Only model computations are kept, and unknown requests are ignored.
+    assert!(greeks.update(
+        req_ids[1],
+        SecOptionCalculationSource::Bid(SecOptionCalculations::PriceBased(results(0.9)))
+    ));
+    assert!(!greeks.update(99, model(0.1)));
+
+    let key = |underlying| Key {
+        underlying_contract_id: ContractId(underlying),
+        expiration_date: chrono::NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(),
+        strike: 200.0,
+        class: SecOptionClass::Call,
+    };
+    assert_eq!(greeks.get(&key(265_598)), Some(&results(0.5)));
+    assert_eq!(greeks.get(&key(272_093)), Some(&results(0.4)));
+    assert_eq!(greeks.get(&key(1)), None);
+    assert_eq!(
+        greeks.snapshot(),
+        [
+            (key(265_598), Some(results(0.5))),
+            (key(272_093), Some(results(0.4)))
+        ]
+    );
+
+    greeks.cancel(&mut client).await?;
+    for _ in &options {
+        assert_eq!(connection.recv().await?[0], "2");
+    }
+    assert!(greeks.snapshot().is_empty());
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_backfill_resume() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::TimeDelta;
+
+    use crate::contract::{ExchangeProxy, Stock};
+    use crate::market_data::backfill::{Backfiller, Key};
+    use crate::market_data::historical_bar::{MinuteSize, Size, Trades};
+    use crate::market_data::historical_ticks;
+    use crate::payload::{Bar, BarCore};
+
+    let stock = toml::from_str::<ExchangeProxy<Stock>>(crate::contract::AAPL)?.inner;
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let path = std::env::temp_dir().join(format!("ibapi_resume_{}.toml", std::process::id()));
+    let mut backfiller = Backfiller::open(&path)?;
+    let key = Key::new(&stock, Size::Minutes(MinuteSize::One), Trades);
+
+    // Without a resume point, the backfill starts at the head timestamp, and ten days of one-minute
+    // bars are requested in two chunks.
+    let head = chrono::Utc::now() - TimeDelta::days(10);
+    let server = async {
+        let request = connection.recv().await?;
+        assert_eq!(request[0], "87");
+        connection
+            .send(&["88", &request[1], &head.timestamp().to_string()])
+            .await?;
+        for _ in 0..2 {
+            assert_eq!(connection.recv().await?[0], "20");
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let (req_ids, served) = tokio::join!(
+        backfiller.resume(
+            &mut client,
+            &stock,
+            Size::Minutes(MinuteSize::One),
+            Trades,
+            historical_ticks::Trades,
+            false,
+            std::time::Duration::from_secs(5),
+        ),
+        server
+    );
+    served?;
+    let req_ids = req_ids?;
+    assert_eq!(req_ids.len(), 2);
+
+    // The resume point only advances once the earlier chunk has also been received.
+    let bar = |datetime| {
+        Bar::Ordinary(BarCore {
+            datetime,
+            open: 180.0,
+            high: 180.0,
+            low: 180.0,
+            close: 180.0,
+        })
+    };
+    let (first, last) = (head + TimeDelta::hours(1), head + TimeDelta::days(9));
+    assert!(backfiller.update(req_ids[1], &[bar(last)]));
+    assert_eq!(backfiller.last(&key), None);
+    assert!(backfiller.update(req_ids[0], &[bar(first)]));
+    assert_eq!(backfiller.last(&key), Some(last));
+    assert!(!backfiller.update(req_ids[0], &[bar(last + TimeDelta::days(1))]));
+    assert_eq!(backfiller.last(&key), Some(last));
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+// A contract data message describing a common stock in AAPL.
+fn stock_contract_data(req_id: &str, contract_id: &str, primary_exchange: &str) -> [String; 37] {
+    [
+        "10",
+        req_id,
+        "AAPL",
+        "STK",
+        "",
+        "0",
+        "",
+        "SMART",
+        "USD",
+        "AAPL",
+        "NMS",
+        "NMS",
+        contract_id,
+        "0.01",
+        "",
+        "LMT",
+        "SMART,ISLAND",
+        "1",
+        "0",
+        "Apple",
+        primary_exchange,
+        "",
+        "Technology",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "0",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "COMMON",
+    ]
+    .map(ToOwned::to_owned)
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+enum ContractEvent {
+    Data(i64, crate::contract::ContractId),
+    End(i64),
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct ContractWrapper(tokio::sync::mpsc::UnboundedSender<ContractEvent>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for ContractWrapper {
+    fn contract_data(
+        &mut self,
+        req_id: i64,
+        contract: crate::contract::Contract,
+    ) -> impl std::future::Future + Send {
+        use crate::contract::Security;
+
+        let _ = self
+            .0
+            .send(ContractEvent::Data(req_id, contract.contract_id()));
+        async {}
+    }
+
+    fn contract_data_end(&mut self, req_id: i64) -> impl std::future::Future + Send {
+        let _ = self.0.send(ContractEvent::End(req_id));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_contract_data() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{Contract, ContractId, Query, Security};
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) = connect_mock(
+        crate::client::Builder::manual(4002, None),
+        &MockServer::new(),
+        ContractWrapper(tx),
+    )
+    .await?;
+
+    let server = async {
+        let request = connection.recv().await?;
+        assert_eq!(request[..5], ["9", "8", &request[2], "265598", ""]);
+        let id = &request[2];
+        connection
+            .send(&stock_contract_data(id, "265598", "NASDAQ"))
+            .await?;
+        connection.send(&["52", "1", id]).await?;
+        // Contracts that answer no awaited query are passed to the wrapper.
+        connection
+            .send(&stock_contract_data("99", "265598", "NASDAQ"))
+            .await?;
+        connection.send(&["52", "1", "99"]).await?;
+        connection
+            .send(&stock_contract_data(id, "265598", "NASDAQ"))
+            .await?;
+        Ok::<_, std::io::Error>(id.parse::<i64>())
+    };
+    let (contract, served) = tokio::join!(
+        crate::contract::new::<Contract>(
+            &mut client,
+            Query::IbContractId(ContractId(265598), crate::exchange::Routing::Smart)
+        ),
+        server
+    );
+    let id = served??;
+    assert_eq!(contract?.contract_id(), ContractId(265598));
+    for expected in [
+        (99, Some(ContractId(265598))),
+        (99, None),
+        (id, Some(ContractId(265598))),
+    ] {
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await?
+            .ok_or("The wrapper was dropped")?;
+        match (event, expected) {
+            (ContractEvent::Data(req_id, contract_id), (expected_id, Some(expected_contract))) => {
+                assert_eq!((req_id, contract_id), (expected_id, expected_contract));
+            }
+            (ContractEvent::End(req_id), (expected_id, None)) => assert_eq!(req_id, expected_id),
+            (event, _) => panic!("Unexpected contract event: {event:?}"),
+        }
+    }
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_contract_specification() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ContractId, ContractType, Query, Stock};
+    use crate::currency::Currency;
+    use crate::exchange::Routing;
+
+    let (mut client, mut connection) = connect_default_mock().await?;
+
+    // Every contract that matches a specification is returned.
+    let server = async {
+        let request = connection.recv().await?;
+        let id = &request[2];
+        assert_eq!(
+            request,
+            [
+                "9", "8", id, "", "AAPL", "STK", "", "", "", "", "SMART", "", "USD", "", "", "",
+                "", "", ""
+            ]
+        );
+        for (contract_id, primary_exchange) in [("265598", "NASDAQ"), ("38708077", "MEXI")] {
+            connection
+                .send(&stock_contract_data(id, contract_id, primary_exchange))
+                .await?;
+        }
+        connection.send(&["52", "1", id]).await
+    };
+    let (stocks, served) = tokio::join!(
+        crate::contract::new_all::<Stock>(
+            &mut client,
+            Query::Specification {
+                symbol: "AAPL".to_owned(),
+                contract_type: ContractType::Stock,
+                exchange: Routing::Smart,
+                currency: Some(Currency::UsDollar),
+                expiration_date: None,
+                strike: None,
+                right: None,
+            }
+        ),
+        server
+    );
+    served?;
+    assert_eq!(
+        stocks?
+            .into_iter()
+            .map(|stock| stock.map(|stock| stock.contract_id()))
+            .collect::<Result<Vec<_>, _>>()?,
+        [ContractId(265598), ContractId(38708077)]
+    );
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exercise_options() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ContractId, SecOption, SecOptionInner};
+    use crate::currency::Currency;
+    use crate::exchange::Routing;
+    use crate::order::ExerciseAction;
+
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) = connect_mock(
+        crate::client::Builder::manual(4002, None),
+        &MockServer::new().with_managed_accounts(["DU1234567"]),
+        TimeWrapper(tx),
+    )
+    .await?;
+
+    let option = SecOption::Call(SecOptionInner {
+        contract_id: ContractId(675_404_431),
+        min_tick: 0.01,
+        symbol: "AAPL".to_owned(),
+        exchange: Routing::Smart,
+        strike: 190.0,
+        multiplier: 100,
+        expiration_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 19).ok_or("Invalid date")?,
+        underlying_contract_id: ContractId(265_598),
+        sector: "Technology".to_owned(),
+        trading_class: "AAPL".to_owned(),
+        currency: Currency::UsDollar,
+        local_symbol: "AAPL  240119C00190000".to_owned(),
+        long_name: "Apple".to_owned(),
+        order_types: Vec::new(),
+        valid_exchanges: Vec::new(),
+        trading_hours: Vec::new(),
+        liquid_hours: Vec::new(),
+    });
+    let id = client
+        .req_exercise_options(&option, ExerciseAction::Exercise, 2, "DU1234567", false)
+        .await?;
+    // The trailing manual order time is required by every supported server version.
+    connection
+        .expect_request(&[
+            "21",
+            "2",
+            &id.to_string(),
+            "675404431",
+            "AAPL",
+            "OPT",
+            "20240119",
+            "190.0",
+            "C",
+            "100",
+            "SMART",
+            "USD",
+            "AAPL  240119C00190000",
+            "AAPL",
+            "1",
+            "2",
+            "DU1234567",
+            "0",
+            "",
+        ])
+        .await?;
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct ErrorWrapper(tokio::sync::mpsc::UnboundedSender<(i64, i64)>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for ErrorWrapper {
+    fn error(
+        &mut self,
+        req_id: i64,
+        error_code: i64,
+        _error_string: String,
+        _advanced_order_reject_json: String,
+    ) -> impl std::future::Future + Send {
+        let _ = self.0.send((req_id, error_code));
+        async {}
+    }
+}
+
+#[tokio::test]
+async fn test_delayed_data_retry() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::contract::{ExchangeProxy, Stock};
+    use crate::market_data::live_data::{Empty, RefreshType};
+
+    let stock = toml::from_str::<ExchangeProxy<Stock>>(crate::contract::AAPL)?.inner;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (mut client, mut connection) = connect_mock(
+        crate::client::Builder::manual(4002, None).with_delayed_data_retry(),
+        &MockServer::new(),
+        ErrorWrapper(tx),
+    )
+    .await?;
+
+    let id = client
+        .req_market_data(&stock, Vec::<Empty>::new(), RefreshType::Streaming, false)
+        .await?
+        .get();
+    let request = connection.recv().await?;
+    assert_eq!(request[..3], ["1", "11", &id.to_string()]);
+    let no_subscription = ["4", "2", &id.to_string(), "354", "Not subscribed", ""];
+
+    // The first error is handled by requesting delayed data and sending the request again.
+    connection.send(&no_subscription).await?;
+    connection.expect_request(&["59", "1", "3"]).await?;
+    assert_eq!(connection.recv().await?, request);
+
+    // A request is only retried once.
+    connection.send(&no_subscription).await?;
+    let error = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv()).await?;
+    assert_eq!(error, Some((id, 354)));
+    client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn test_metrics() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let (other, _other_connection) = connect_default_mock().await?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    client.metrics().on_latency(move |request, _| {
+        let _ = tx.send(request);
+    });
+
+    let req_id = client.req_user_info().await?;
+    connection
+        .expect_request(&["104", &req_id.to_string()])
+        .await?;
+    connection
+        .send(&["107", &req_id.to_string(), "branding"])
+        .await?;
+    let request = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv()).await?;
+    assert_eq!(request, Some("ReqUserInfo"));
+    let snapshot = client.metrics().snapshot();
+    assert_eq!(snapshot.latencies["ReqUserInfo"].count, 1);
+    assert_eq!(snapshot.messages.get("107"), Some(&1));
+
+    // Each client keeps its own metrics.
+    assert!(other.metrics().snapshot().latencies.is_empty());
+    client.disconnect().await?;
+    other.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct CycleWrapper(tokio::sync::mpsc::UnboundedSender<()>);
+
+#[cfg(test)]
+impl crate::wrapper::Wrapper for CycleWrapper {}
+
+#[cfg(test)]
+impl crate::wrapper::Recurring for CycleWrapper {
+    async fn cycle(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+#[cfg(test)]
+impl crate::wrapper::Initializer for CycleWrapper {
+    type Wrap<'c> = Self;
+
+    async fn build(
+        self,
+        _client: &mut crate::client::ActiveClient,
+        _cancel_loop: crate::wrapper::CancelToken,
+    ) -> Self {
+        self
+    }
+}
+
+#[tokio::test]
+async fn test_batched_dispatch_cycles_while_idle() -> Result<(), Box<dyn std::error::Error>> {
+    let builder = crate::client::Builder::manual(4002, None)
+        .with_dispatch(crate::client::Dispatch::Batched(16));
+    let (client_end, server_end) = crate::transport::in_memory(1 << 16);
+    let server = MockServer::new();
+    let (client, connection) = tokio::join!(
+        builder.connect_with(client_end, 0),
+        server.handshake(server_end)
+    );
+    let (_connection, (tx, mut rx)) = (connection?, tokio::sync::mpsc::unbounded_channel());
+    let cancel = client?.remote(CycleWrapper(tx)).await;
+
+    // No message arrives, but the wrapper still cycles.
+    for _ in 0..3 {
+        tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await?
+            .ok_or("the loop should still be running")?;
+    }
+    cancel.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_model_code_requests() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut client, mut connection) = connect_default_mock().await?;
+    let account = "DU1234567".to_owned();
+
+    let id = client
+        .req_positions_multi(Some(account.clone()), Some("Growth".to_owned()))
+        .await?;
+    connection
+        .expect_request(&["74", "1", &id.to_string(), "DU1234567", "Growth"])
+        .await?;
+    client.cancel_positions_multi(id).await?;
+    connection
+        .expect_request(&["75", "1", &id.to_string()])
+        .await?;
+
+    let id = client
+        .req_account_updates_multi(None, Some("Growth".to_owned()), true)
+        .await?;
+    connection
+        .expect_request(&["76", "1", &id.to_string(), "", "Growth", "1"])
+        .await?;
+    client.cancel_account_updates_multi(id).await?;
+    connection
+        .expect_request(&["77", "1", &id.to_string()])
+        .await?;
+
+    let id = client.req_pnl(&account, Some("Growth".to_owned())).await?;
+    connection
+        .expect_request(&["92", &id.to_string(), "DU1234567", "Growth"])
+        .await?;
+    let id = client
+        .req_single_position_pnl(&account, crate::contract::ContractId(265_598), None)
+        .await?;
+    connection
+        .expect_request(&["94", &id.to_string(), "DU1234567", "", "265598"])
+        .await?;
+
+    // Accounts that the client does not manage are rejected before anything is sent.
+    assert!(client
+        .req_positions_multi(Some("DU7654321".to_owned()), None)
+        .await
+        .is_err());
+    client.disconnect().await?;
+    Ok(())
+}
@@ -1,10 +1,9 @@
-use std::fmt::Formatter;
 use std::num::ParseFloatError;
 use std::str::FromStr;
 
 use chrono::{DateTime, NaiveDate, Utc};
 use chrono::serde::{ts_milliseconds, ts_seconds};
-use serde::{Deserialize, Deserializer, ser::SerializeTuple, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "etf_nav")]
@@ -62,6 +61,29 @@ pub enum Size {
     Last(f64),
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "quote")]
+/// A top-of-book price together with the size available at that price.
+pub enum Quote {
+    /// The highest priced bid for the contract and the number of contracts or lots bid.
+    Bid {
+        /// The bid price.
+        price: f64,
+        /// The bid size, if one was sent with the price.
+        size: Option<f64>,
+    },
+    /// The lowest priced offer for the contract and the number of contracts or lots offered.
+    Ask {
+        /// The ask price.
+        price: f64,
+        /// The ask size, if one was sent with the price.
+        size: Option<f64>,
+    },
+}
+
+/// A [`Quote`] along with whether it is live or delayed.
+pub type QuoteEvent = Class<Quote>;
+
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "yield")]
 /// The types of ticks related to yield data.
@@ -205,6 +227,63 @@ pub enum AuctionData {
     Regulatory(f64),
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The latest state of an opening or closing auction, which combines every [`AuctionData`] tick
+/// received so far for a single market data request. Each value is `None` until its tick arrives.
+pub struct Auction {
+    /// The number of shares that would be matched if the auction were held now (tick type 34).
+    pub matched_quantity: Option<f64>,
+    /// The indicative price of the auction (tick type 35).
+    pub indicative_price: Option<f64>,
+    /// The number of unmatched shares, which is negative when the imbalance is on the sell side
+    /// (tick type 36).
+    pub imbalance: Option<f64>,
+    /// The regulatory imbalance (tick type 61).
+    pub regulatory_imbalance: Option<f64>,
+}
+
+impl Auction {
+    #[inline]
+    #[must_use]
+    /// The side of the auction with more shares than the other.
+    ///
+    /// # Returns
+    /// The side of the imbalance, or `None` if no imbalance has been received.
+    pub fn imbalance_side(&self) -> Option<ImbalanceSide> {
+        self.imbalance.map(|imbalance| {
+            if imbalance > 0. {
+                ImbalanceSide::Buy
+            } else if imbalance < 0. {
+                ImbalanceSide::Sell
+            } else {
+                ImbalanceSide::Balanced
+            }
+        })
+    }
+
+    #[cfg(feature = "client")]
+    #[inline]
+    pub(crate) fn update(&mut self, data: AuctionData) {
+        match data {
+            AuctionData::Volume(v) => self.matched_quantity = Some(v),
+            AuctionData::Price(p) => self.indicative_price = Some(p),
+            AuctionData::Imbalance(i) => self.imbalance = Some(i),
+            AuctionData::Regulatory(r) => self.regulatory_imbalance = Some(r),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The side of an [`Auction`] imbalance.
+pub enum ImbalanceSide {
+    /// More shares are bid than offered.
+    Buy,
+    /// More shares are offered than bid.
+    Sell,
+    /// The auction is balanced.
+    Balanced,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "mark_price")]
 /// Represents a callback containing to mark prices.
@@ -304,6 +383,55 @@ pub enum Accessibility {
     Halted(f64),
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// The latest stock-loan state of a security, which combines every shortable tick received so far
+/// for a single market data request. Each value is `None` until its tick arrives.
+///
+/// The borrow fee rate is not included, because the API has no live tick for it (there is no
+/// generic tick 237). It is only available from [`crate::market_data::historical_bar::FeeRate`]
+/// bars.
+pub struct ShortBorrow {
+    /// The number of shares available to short (tick type 89).
+    pub shares_available: Option<f64>,
+    /// The level of difficulty with which the security can be sold short (tick type 46).
+    pub tier: Option<ShortableTier>,
+}
+
+impl ShortBorrow {
+    #[cfg(feature = "client")]
+    #[inline]
+    pub(crate) fn update(&mut self, access: Accessibility) {
+        match access {
+            Accessibility::ShortableShares(shares) => self.shares_available = Some(shares),
+            Accessibility::Shortable(value) => self.tier = Some(ShortableTier::from(value)),
+            Accessibility::Halted(_) => (),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The level of difficulty with which a security can be sold short.
+pub enum ShortableTier {
+    /// At least 1,000 shares are available to short.
+    Easy,
+    /// Shares are available to short, but a locate is required first.
+    Hard,
+    /// No shares are available to short.
+    Unavailable,
+}
+
+impl From<f64> for ShortableTier {
+    fn from(value: f64) -> Self {
+        if value > 2.5 {
+            Self::Easy
+        } else if value > 1.5 {
+            Self::Hard
+        } else {
+            Self::Unavailable
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "ipo")]
 /// A callback related to IPO information.
@@ -321,38 +449,42 @@ pub struct Dividends {
     pub trailing_year: f64,
     /// The sum of dividends for the next 12 months.
     pub forward_year: f64,
-    /// The next single dividend date and amount.
-    #[serde(serialize_with = "serialize_dividend_tuple")]
-    #[serde(deserialize_with = "deserialize_dividend_tuple")]
-    pub next_dividend: (NaiveDate, f64),
+    /// The next single dividend, if one is scheduled.
+    pub next_dividend: Option<Dividend>,
 }
 
-fn serialize_dividend_tuple<S: Serializer>(
-    div_tup: &(NaiveDate, f64),
-    serializer: S,
-) -> Result<S::Ok, S::Error> {
-    let mut s = serializer.serialize_tuple(2)?;
-    s.serialize_element(&div_tup.0.format("%Y-%m-%d").to_string())?;
-    s.serialize_element(&div_tup.1)?;
-    s.end()
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+/// A single scheduled dividend.
+pub struct Dividend {
+    /// The ex-dividend date.
+    pub date: NaiveDate,
+    /// The amount paid per share.
+    pub amount: f64,
 }
 
-fn deserialize_dividend_tuple<'de, D: Deserializer<'de>>(
-    deserializer: D,
-) -> Result<(NaiveDate, f64), D::Error> {
-    deserializer.deserialize_tuple(2, TupVisitor)
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// The fundamental ratios of a stock, such as its price-to-earnings ratio or market
+/// capitalization.
+///
+/// Each ratio is keyed by its IBKR code (ex. "PEEXCLXOR" for the price-to-earnings ratio,
+/// excluding extraordinary items, or "MKTCAP" for the market capitalization in millions). Ratios
+/// that IBKR reports as unavailable are omitted.
+pub struct FundamentalRatios {
+    /// The currency in which the monetary ratios are expressed.
+    pub currency: Option<String>,
+    /// The ratios, keyed by their IBKR code.
+    pub ratios: std::collections::BTreeMap<String, f64>,
 }
 
-struct TupVisitor;
-
-impl serde::de::Visitor<'_> for TupVisitor {
-    type Value = (NaiveDate, f64);
-
-    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-        write!(
-            formatter,
-            "either a YYYY-MM-DD date or a floating point number"
-        )
+impl FundamentalRatios {
+    #[must_use]
+    #[inline]
+    /// Return the value of a ratio, if it is available.
+    ///
+    /// # Arguments
+    /// * `code` - The IBKR code of the ratio (ex. "BETA").
+    pub fn get(&self, code: &str) -> Option<f64> {
+        self.ratios.get(code).copied()
     }
 }
 
@@ -377,6 +509,7 @@ pub(crate) mod indicators {
 
     impl Valid for super::Price {}
     impl Valid for super::Size {}
+    impl Valid for super::Quote {}
     impl Valid for super::SecOptionCalculationSource {}
     impl Valid for super::TimeStamp {}
 
@@ -0,0 +1,509 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+// === Type definitions ===
+
+/// A connected, bidirectional byte stream over which a [`crate::client::Client`] speaks the API
+/// protocol, passed to [`crate::client::Builder::connect_with`].
+///
+/// Any type that implements [`AsyncRead`] and [`AsyncWrite`] is a transport, so a WebSocket proxy
+/// or TLS tunnel needs only to expose the raw API bytes as a stream. [`crate::client::Builder::connect`]
+/// uses a [`tokio::net::TcpStream`], and [`in_memory`] creates a pair of transports for tests.
+///
+/// For example, IB Gateway running on another host behind a TLS-terminating tunnel (ex.
+/// stunnel) is reached by wrapping the TCP stream with a TLS client such as `tokio-rustls`, which
+/// `Builder::manual_tls` does when the `rustls` feature is enabled.
+pub trait Transport: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+/// One end of a pair of in-memory transports created by [`in_memory`].
+pub type MemoryTransport = tokio::io::DuplexStream;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A proxy through which [`crate::client::Builder::connect`] reaches TWS or IB Gateway, for
+/// deployments that only allow outbound connections through a proxy. Install one with
+/// [`crate::client::Builder::with_proxy`].
+pub enum Proxy {
+    /// A SOCKS5 proxy (RFC 1928).
+    Socks5 {
+        /// The host name or IP address of the proxy.
+        host: String,
+        /// The port of the proxy.
+        port: u16,
+        /// The username and password, if the proxy requires them (RFC 1929).
+        credentials: Option<Credentials>,
+    },
+    /// An HTTP proxy that supports the `CONNECT` method.
+    HttpConnect {
+        /// The host name or IP address of the proxy.
+        host: String,
+        /// The port of the proxy.
+        port: u16,
+        /// The username and password, if the proxy requires basic authentication.
+        credentials: Option<Credentials>,
+    },
+}
+
+#[cfg(feature = "rustls")]
+#[derive(Debug, Clone)]
+// The server name and configuration with which `crate::client::Builder::connect` wraps its TCP
+// stream in TLS.
+pub(crate) struct Tls {
+    host: String,
+    config: Arc<rustls::ClientConfig>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+/// The username and password with which to authenticate with a [`Proxy`].
+pub struct Credentials {
+    /// The username.
+    pub username: String,
+    /// The password.
+    pub password: String,
+}
+
+// The halves of a transport, which are used by the reader thread and the client respectively.
+// The write half is shared with the client loop, which sends the pings of a
+// `crate::client::Watchdog`.
+pub(crate) struct ReadHalf(Box<dyn AsyncRead + Send + Unpin>);
+
+#[derive(Clone)]
+pub(crate) struct WriteHalf(Arc<Mutex<Box<dyn AsyncWrite + Send + Unpin>>>);
+
+// === Type implementations ===
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> Transport for T {}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Proxy {
+    /// Open a TCP connection to the proxy and ask it to connect to `target` and `port`.
+    ///
+    /// A `target` that is not an IPv4 address is passed to the proxy as a name, which the proxy
+    /// looks up, so that internal or `.onion` names work and no lookup is made locally.
+    ///
+    /// # Errors
+    /// Returns any error encountered while connecting to the proxy, an error of kind
+    /// [`std::io::ErrorKind::PermissionDenied`] if the proxy rejects the credentials, or an error
+    /// of kind [`std::io::ErrorKind::ConnectionRefused`] if the proxy refuses the connection.
+    pub(crate) async fn connect(&self, target: &str, port: u16) -> std::io::Result<TcpStream> {
+        match self {
+            Self::Socks5 {
+                host,
+                port: proxy_port,
+                credentials,
+            } => {
+                let mut stream = TcpStream::connect((host.as_str(), *proxy_port)).await?;
+                socks5_handshake(&mut stream, credentials.as_ref(), target, port).await?;
+                Ok(stream)
+            }
+            Self::HttpConnect {
+                host,
+                port: proxy_port,
+                credentials,
+            } => {
+                let mut stream = TcpStream::connect((host.as_str(), *proxy_port)).await?;
+                http_connect_handshake(&mut stream, credentials.as_ref(), target, port).await?;
+                Ok(stream)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rustls")]
+impl Tls {
+    #[inline]
+    pub(crate) fn new(host: String, config: Arc<rustls::ClientConfig>) -> Self {
+        Self { host, config }
+    }
+
+    #[inline]
+    pub(crate) fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Look up the IPv4 address of the server.
+    ///
+    /// # Errors
+    /// Returns any error encountered during the lookup, or an error of kind
+    /// [`std::io::ErrorKind::AddrNotAvailable`] if the server has no IPv4 address.
+    pub(crate) async fn resolve(&self, port: u16) -> std::io::Result<std::net::Ipv4Addr> {
+        tokio::net::lookup_host((self.host.as_str(), port))
+            .await?
+            .find_map(|addr| match addr.ip() {
+                std::net::IpAddr::V4(address) => Some(address),
+                std::net::IpAddr::V6(_) => None,
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::AddrNotAvailable,
+                    format!("{} has no IPv4 address.", self.host),
+                )
+            })
+    }
+
+    /// Perform the TLS handshake over `stream`, verifying the server's certificate against the
+    /// host name.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`std::io::ErrorKind::InvalidInput`] if the host is not a valid
+    /// server name, or any error encountered during the handshake.
+    pub(crate) async fn connect(
+        &self,
+        stream: TcpStream,
+    ) -> std::io::Result<tokio_rustls::client::TlsStream<TcpStream>> {
+        let domain = rustls::pki_types::ServerName::try_from(self.host.clone())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        tokio_rustls::TlsConnector::from(Arc::clone(&self.config))
+            .connect(domain, stream)
+            .await
+    }
+}
+
+impl std::fmt::Debug for ReadHalf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadHalf").finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for WriteHalf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteHalf").finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for ReadHalf {
+    #[inline]
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.0).poll_read(cx, buf)
+    }
+}
+
+impl WriteHalf {
+    #[inline]
+    fn new<W: AsyncWrite + Send + Unpin + 'static>(writer: W) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    #[inline]
+    pub(crate) async fn write_all(&self, buf: &[u8]) -> std::io::Result<()> {
+        self.0.lock().await.write_all(buf).await
+    }
+
+    #[inline]
+    pub(crate) async fn flush(&self) -> std::io::Result<()> {
+        self.0.lock().await.flush().await
+    }
+
+    #[inline]
+    pub(crate) async fn shutdown(&self) -> std::io::Result<()> {
+        self.0.lock().await.shutdown().await
+    }
+}
+
+// === Functions ===
+
+#[must_use]
+#[inline]
+/// Create a pair of connected in-memory transports, so that a [`crate::client::Client`] can be
+/// tested against a fake server without opening a socket.
+///
+/// # Arguments
+/// * `max_buf_size` - The number of bytes that can be written in each direction before the writer
+///   waits for the reader.
+pub fn in_memory(max_buf_size: usize) -> (MemoryTransport, MemoryTransport) {
+    tokio::io::duplex(max_buf_size)
+}
+
+#[inline]
+// A TCP stream has owned halves, so reads do not contend with writes for the lock that
+// `tokio::io::split` places around the whole of other transports. Writes are still serialized by
+// the lock of `WriteHalf`, which is shared with the client loop.
+pub(crate) fn split_tcp(stream: tokio::net::TcpStream) -> (ReadHalf, WriteHalf) {
+    let (reader, writer) = stream.into_split();
+    (ReadHalf(Box::new(reader)), WriteHalf::new(writer))
+}
+
+#[inline]
+pub(crate) fn split<T: Transport>(transport: T) -> (ReadHalf, WriteHalf) {
+    let (reader, writer) = tokio::io::split(transport);
+    (ReadHalf(Box::new(reader)), WriteHalf::new(writer))
+}
+
+fn proxy_error(kind: std::io::ErrorKind, message: String) -> std::io::Error {
+    std::io::Error::new(kind, message)
+}
+
+async fn socks5_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    credentials: Option<&Credentials>,
+    host: &str,
+    port: u16,
+) -> std::io::Result<()> {
+    const VERSION: u8 = 5;
+    const NO_AUTH: u8 = 0;
+    const USERNAME_PASSWORD: u8 = 2;
+
+    // Method negotiation
+    if credentials.is_some() {
+        stream
+            .write_all(&[VERSION, 2, NO_AUTH, USERNAME_PASSWORD])
+            .await?;
+    } else {
+        stream.write_all(&[VERSION, 1, NO_AUTH]).await?;
+    }
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    match (reply, credentials) {
+        ([VERSION, NO_AUTH], _) => (),
+        ([VERSION, USERNAME_PASSWORD], Some(credentials)) => {
+            let (username, password) = (
+                credentials.username.as_bytes(),
+                credentials.password.as_bytes(),
+            );
+            let too_long = |_| {
+                proxy_error(
+                    std::io::ErrorKind::InvalidInput,
+                    "SOCKS5 usernames and passwords are limited to 255 bytes.".to_owned(),
+                )
+            };
+            let mut request = vec![1, u8::try_from(username.len()).map_err(too_long)?];
+            request.extend_from_slice(username);
+            request.push(u8::try_from(password.len()).map_err(too_long)?);
+            request.extend_from_slice(password);
+            stream.write_all(&request).await?;
+
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0 {
+                return Err(proxy_error(
+                    std::io::ErrorKind::PermissionDenied,
+                    "The SOCKS5 proxy rejected the credentials.".to_owned(),
+                ));
+            }
+        }
+        _ => {
+            return Err(proxy_error(
+                std::io::ErrorKind::PermissionDenied,
+                format!("The SOCKS5 proxy accepted none of the offered methods. Reply: {reply:?}."),
+            ))
+        }
+    }
+
+    // Connect request to an IPv4 address, or to a domain name that the proxy looks up
+    let mut request = vec![VERSION, 1, 0];
+    if let Ok(address) = host.parse::<std::net::Ipv4Addr>() {
+        request.push(1);
+        request.extend_from_slice(&address.octets());
+    } else {
+        request.push(3);
+        request.push(u8::try_from(host.len()).map_err(|_| {
+            proxy_error(
+                std::io::ErrorKind::InvalidInput,
+                "SOCKS5 domain names are limited to 255 bytes.".to_owned(),
+            )
+        })?);
+        request.extend_from_slice(host.as_bytes());
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(proxy_error(
+            std::io::ErrorKind::ConnectionRefused,
+            format!(
+                "The SOCKS5 proxy refused the connection with reply code {}.",
+                reply[1]
+            ),
+        ));
+    }
+    // The bound address, which is not needed, followed by the bound port
+    let address_len = match reply[3] {
+        1 => 4,
+        3 => usize::from(stream.read_u8().await?),
+        4 => 16,
+        atyp => {
+            return Err(proxy_error(
+                std::io::ErrorKind::InvalidData,
+                format!("The SOCKS5 proxy replied with an unknown address type {atyp}."),
+            ))
+        }
+    };
+    let mut bound = vec![0; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+async fn http_connect_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    credentials: Option<&Credentials>,
+    host: &str,
+    port: u16,
+) -> std::io::Result<()> {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some(credentials) = credentials {
+        let token = base64(format!("{}:{}", credentials.username, credentials.password).as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // The response is read one byte at a time so that nothing after its header is consumed.
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        response.push(stream.read_u8().await?);
+        if response.len() > 8192 {
+            return Err(proxy_error(
+                std::io::ErrorKind::InvalidData,
+                "The HTTP proxy's response header is too long.".to_owned(),
+            ));
+        }
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        Some("407") => Err(proxy_error(
+            std::io::ErrorKind::PermissionDenied,
+            format!("The HTTP proxy requires authentication: {status_line}."),
+        )),
+        _ => Err(proxy_error(
+            std::io::ErrorKind::ConnectionRefused,
+            format!("The HTTP proxy refused the connection: {status_line}."),
+        )),
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64(b"a"), "YQ==");
+    assert_eq!(base64(b"ab"), "YWI=");
+    assert_eq!(base64(b"abc"), "YWJj");
+    assert_eq!(
+        base64(b"Aladdin:open sesame"),
+        "QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+    );
+}
+
+#[tokio::test]
+async fn test_proxy() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::testing::MockServer;
+
+    let credentials = Credentials {
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+    };
+
+    // SOCKS5 with username and password authentication
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
+    let proxy = Proxy::Socks5 {
+        host: "127.0.0.1".to_owned(),
+        port: listener.local_addr()?.port(),
+        credentials: Some(credentials.clone()),
+    };
+    let socks5 = async {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, [5, 2, 0, 2]);
+        stream.write_all(&[5, 2]).await?;
+        let mut buf = [0; 11];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"\x01\x04user\x04pass");
+        stream.write_all(&[1, 0]).await?;
+        let mut buf = [0; 10];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(buf, [5, 1, 0, 1, 127, 0, 0, 1, 0x0f, 0xa2]);
+        stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+        MockServer::new().handshake(stream).await
+    };
+    let builder = crate::client::Builder::manual(4002, None).with_proxy(proxy);
+    let (client, connection) = tokio::join!(builder.connect(5), socks5);
+    assert_eq!(connection?.client_id(), 5);
+    assert_eq!(client?.get_server_version(), 180);
+
+    // HTTP CONNECT that rejects the request
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
+    let proxy = Proxy::HttpConnect {
+        host: "127.0.0.1".to_owned(),
+        port: listener.local_addr()?.port(),
+        credentials: Some(credentials),
+    };
+    let http = async {
+        let (mut stream, _) = listener.accept().await?;
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(stream.read_u8().await?);
+        }
+        assert_eq!(
+            String::from_utf8_lossy(&request),
+            "CONNECT 127.0.0.1:4002 HTTP/1.1\r\nHost: 127.0.0.1:4002\r\n\
+             Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"
+        );
+        stream
+            .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+            .await?;
+        std::io::Result::Ok(())
+    };
+    let builder = crate::client::Builder::manual(4002, None).with_proxy(proxy);
+    let (client, proxy_result) = tokio::join!(builder.connect(5), http);
+    proxy_result?;
+    assert!(matches!(
+        client,
+        Err(crate::client::ConnectionError::Io(e)) if e.kind() == std::io::ErrorKind::PermissionDenied
+    ));
+
+    // SOCKS5 to a host name, which the proxy looks up
+    let (mut client_end, mut proxy_end) = tokio::io::duplex(1024);
+    let socks5 = async {
+        let mut buf = [0; 3];
+        proxy_end.read_exact(&mut buf).await?;
+        proxy_end.write_all(&[5, 0]).await?;
+        let mut buf = [0; 23];
+        proxy_end.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"\x05\x01\x00\x03\x10gateway.internal\x0f\xa2");
+        proxy_end.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await
+    };
+    let (handshake, proxy_result) = tokio::join!(
+        socks5_handshake(&mut client_end, None, "gateway.internal", 4002),
+        socks5
+    );
+    proxy_result?;
+    handshake?;
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::future::Future;
 
 use chrono::{DateTime, NaiveTime, Utc};
@@ -8,14 +9,15 @@ use crate::client::ActiveClient;
 use crate::contract::{Contract, ExchangeProxy};
 use crate::execution::{CommissionReport, Execution};
 use crate::payload::{
-    self, Bar, ExchangeId, HistogramEntry, OrderStatus, Pnl, PnlSingle, Position, PositionSummary,
-    TickData,
+    self, Bar, ExchangeId, Histogram, HistoricalSchedule, OrderAmount, OrderStatus, Pnl,
+    PnlSingle, Position, PositionSummary, TickData,
 };
 use crate::tick::{
-    self, Accessibility, AuctionData, Class, Dividends, ExtremeValue, Ipo, MarkPrice, News,
-    OpenInterest, Price, PriceFactor, QuotingExchanges, Rate, RealTimeVolume,
-    SecOptionCalculationSource, SecOptionVolume, Size, SummaryVolume, TimeStamp, TradeCount,
-    Volatility, Volume, Yield,
+    self, Accessibility, Auction, AuctionData, Class, Dividends, ExtremeValue, FundamentalRatios,
+    Ipo, MarkPrice, News, OpenInterest, Price, PriceFactor, QuoteEvent, QuotingExchanges, Rate,
+    RealTimeVolume,
+    SecOptionCalculationSource, SecOptionVolume, ShortBorrow, Size, SummaryVolume, TimeStamp,
+    TradeCount, Volatility, Volume, Yield,
 };
 
 // todo! Updated Bar payload api to make it more clear that BidAsk callback isn't just a "normal Bar"
@@ -27,6 +29,23 @@ pub type CancelToken = tokio_util::sync::CancellationToken;
 #[trait_variant::make(Wrapper: Send)]
 #[debug_trait]
 /// Contains the "callback functions" that correspond to the requests made by a [`crate::client::Client`].
+///
+/// Every callback has a default implementation, which only emits a [`tracing::debug!`] event, so an
+/// `impl` block need only contain the callbacks that a type cares about. A type that cares about
+/// none of them, such as a wrapper for a client that only awaits responses, can use an empty
+/// `impl` block. Implementing [`Wrapper`] also implements [`LocalWrapper`].
+///
+/// ```
+/// use ibapi::wrapper::{LocalWrapper, Wrapper};
+///
+/// struct Quiet;
+///
+/// impl Wrapper for Quiet {}
+///
+/// struct LocalQuiet(std::rc::Rc<i64>);
+///
+/// impl LocalWrapper for LocalQuiet {}
+/// ```
 pub trait LocalWrapper {
     /// The callback that corresponds to any error that encounters after an API request.
     ///
@@ -39,16 +58,68 @@ pub trait LocalWrapper {
         advanced_order_reject_json: String,
     ) -> impl Future {
     }
+    /// The callback that corresponds to an incoming message that could not be decoded.
+    ///
+    /// The [`payload::DecodeErrorReport`] contains the raw fields of the message, so it can be
+    /// logged or persisted instead of being lost.
+    fn decode_error(&mut self, report: payload::DecodeErrorReport) -> impl Future {}
+    /// The callback that corresponds to a notice about the connection between TWS and a data farm.
+    ///
+    /// These routine notices (ex. error codes 2104 and 2158) are received here rather than in
+    /// [`LocalWrapper::error`].
+    fn data_farm_status(&mut self, status: payload::DataFarmStatus) -> impl Future {}
     /// The callback message that corresponds to [`crate::client::Client::req_current_time`].
     ///
     /// This is TWS's current time. TWS is synchronized with the server (not local computer) using NTP and this function will receive the current time in TWS.
     fn current_time(&mut self, req_id: i64, datetime: DateTime<Utc>) -> impl Future {}
+    /// The callback that corresponds to a reply to a request sent by the
+    /// [`crate::client::Watchdog`] installed with [`crate::client::Builder::with_watchdog`].
+    ///
+    /// The `round_trip` is the time between sending the request and receiving the reply.
+    fn heartbeat(&mut self, round_trip: std::time::Duration) -> impl Future {}
+    /// The callback that is called when no message arrives within the timeout of a request sent by
+    /// the [`crate::client::Watchdog`] installed with [`crate::client::Builder::with_watchdog`].
+    ///
+    /// The `silence` is the time since the request was sent or the last message arrived,
+    /// whichever is later. This is called once per stall.
+    fn connection_stalled(&mut self, silence: std::time::Duration) -> impl Future {}
+    /// The callback that is called when requests are paused ahead of a restart expected by the
+    /// [`crate::restart::Window`] installed with [`crate::client::Builder::with_restart_window`].
+    ///
+    /// Until the end of the `period`, requests fail with a [`crate::restart::Paused`] error.
+    fn restart_pending(&mut self, period: crate::restart::Period) -> impl Future {}
+    /// The callback that is called when a restart expected by the [`crate::restart::Window`]
+    /// installed with [`crate::client::Builder::with_restart_window`] begins, after which the
+    /// connection is expected to drop.
+    fn restart_started(&mut self, period: crate::restart::Period) -> impl Future {}
+    /// The callback that is called once the loop of a client that was reconnected after a restart
+    /// by [`crate::client::Builder::local_with_restarts`] has started.
+    fn restart_complete(&mut self, period: crate::restart::Period) -> impl Future {}
+    /// The callback message containing the next valid order ID from [`crate::client::Client::req_ids`].
+    ///
+    /// The client's internal order ID counter has already been advanced to this ID when the
+    /// callback is made. The ID received when the client first connects is not delivered here.
+    fn next_valid_id(&mut self, order_id: i64) -> impl Future {}
+    /// The callback message containing the accounts managed by the client from [`crate::client::Client::req_managed_accounts`].
+    ///
+    /// The set returned by [`crate::client::Client::get_current_managed_accounts`] has already been
+    /// updated when the callback is made. The accounts received when the client first connects are
+    /// not delivered here.
+    fn managed_accounts(&mut self, accounts: HashSet<String>) -> impl Future {}
+    /// The callback message containing the white branding ID of the user from [`crate::client::Client::req_user_info`].
+    ///
+    /// The ID is empty for users that do not belong to a white-branded broker.
+    fn user_info(&mut self, req_id: i64, white_branding_id: String) -> impl Future {}
     /// The callback message that corresponds to ETF Net Asset Value (NAV) data.
     fn etf_nav(&mut self, req_id: i64, nav: tick::EtfNav) -> impl Future {}
     /// The callback message that corresponds to price data from [`crate::client::Client::req_market_data`].
     fn price_data(&mut self, req_id: i64, price: Class<Price>) -> impl Future {}
     /// The callback message that corresponds to size data from [`crate::client::Client::req_market_data`].
     fn size_data(&mut self, req_id: i64, size: Class<Size>) -> impl Future {}
+    /// The callback message that carries a bid or ask price together with its size from [`crate::client::Client::req_market_data`].
+    /// Only called by clients built with [`crate::client::Builder::with_combined_quotes`], in which case bid and ask prices
+    /// are not passed to [`LocalWrapper::price_data`] or [`LocalWrapper::size_data`].
+    fn bid_ask_quote(&mut self, req_id: i64, quote: QuoteEvent) -> impl Future {}
     /// The callback message that corresponds to the price (in yield terms) data from [`crate::client::Client::req_market_data`].
     fn yield_data(&mut self, req_id: i64, yld: Yield) -> impl Future {}
     /// The callback message that corresponds to the high/low prices over a period from [`crate::client::Client::req_market_data`].
@@ -75,14 +146,23 @@ pub trait LocalWrapper {
     fn timestamp(&mut self, req_id: i64, timestamp: Class<TimeStamp>) -> impl Future {}
     /// The callback message that corresponds to auction data from [`crate::client::Client::req_market_data`].
     fn auction(&mut self, req_id: i64, auction: AuctionData) -> impl Future {}
+    /// The callback message containing the latest combined auction state from [`crate::client::Client::req_market_data`],
+    /// which is sent after every [`AuctionData`] tick and follows the corresponding [`LocalWrapper::auction`] callback.
+    fn auction_update(&mut self, req_id: i64, auction: Auction) -> impl Future {}
     /// The callback message associated with mark price data from [`crate::client::Client::req_market_data`].
     fn mark_price(&mut self, req_id: i64, mark: MarkPrice) -> impl Future {}
     /// The callback message associated with factors / multipliers related to prices from [`crate::client::Client::req_market_data`].
     fn price_factor(&mut self, req_id: i64, factor: PriceFactor) -> impl Future {}
     /// The callback message associated with the ability to short or trade a security from [`crate::client::Client::req_market_data`].
     fn accessibility(&mut self, req_id: i64, access: Accessibility) -> impl Future {}
+    /// The callback message containing the latest combined stock-loan state from [`crate::client::Client::req_market_data`],
+    /// which is sent after every shortable [`Accessibility`] tick and follows the corresponding [`LocalWrapper::accessibility`] callback.
+    fn short_borrow(&mut self, req_id: i64, short_borrow: ShortBorrow) -> impl Future {}
     /// The callback message containing information about dividends from [`crate::client::Client::req_market_data`].
     fn dividends(&mut self, req_id: i64, dividends: Dividends) -> impl Future {}
+    /// The callback message containing the fundamental ratios of a stock, which are requested with
+    /// [`crate::market_data::live_data::FundamentalRatios`].
+    fn fundamental_ratios(&mut self, req_id: i64, ratios: FundamentalRatios) -> impl Future {}
     /// The callback message containing news information from [`crate::client::Client::req_market_data`].
     fn news(&mut self, req_id: i64, news: News) -> impl Future {}
     /// The callback message containing information about IPOs from [`crate::client::Client::req_market_data`].
@@ -121,7 +201,7 @@ pub trait LocalWrapper {
     fn histogram(
         &mut self,
         req_id: i64,
-        histogram: std::collections::HashMap<usize, HistogramEntry>,
+        histogram: Histogram,
     ) -> impl Future {
     }
     /// The callback message containing historical bar data from [`crate::client::Client::req_historical_bar`].
@@ -133,14 +213,36 @@ pub trait LocalWrapper {
         bars: Vec<Bar>,
     ) -> impl Future {
     }
+    /// The callback message containing a trading schedule from [`crate::client::Client::req_historical_bar`]
+    /// for [`crate::market_data::historical_bar::Schedule`] data.
+    fn historical_schedule(&mut self, req_id: i64, schedule: HistoricalSchedule) -> impl Future {}
     /// The callback message containing an updated historical bar from [`crate::client::Client::req_updating_historical_bar`].
     fn updating_historical_bar(&mut self, req_id: i64, bar: Bar) -> impl Future {}
     /// The callback message containing a timestamp for the beginning of data for a contract and specified data type from [`crate::client::Client::req_head_timestamp`].
     fn head_timestamp(&mut self, req_id: i64, timestamp: DateTime<Utc>) -> impl Future {}
-    /// The callback message containing a vector of historical ticks from [`crate::client::Client::req_historical_ticks`] for [`crate::client::Client::req_tick_by_tick_data`].
+    /// The callback message containing a vector of historical ticks from [`crate::client::Client::req_historical_ticks`].
     fn historical_ticks(&mut self, req_id: i64, ticks: Vec<TickData>) -> impl Future {}
+    /// The callback message containing the historical ticks requested by the `number_of_historical_ticks`
+    /// argument of [`crate::client::Client::req_tick_by_tick_data`], which arrive before any live ticks.
+    fn tick_by_tick_history(&mut self, req_id: i64, ticks: Vec<TickData>) -> impl Future {}
     /// The callback message containing a single tick from [`crate::client::Client::req_tick_by_tick_data`].
     fn live_tick(&mut self, req_id: i64, tick: TickData) -> impl Future {}
+    /// The callback message containing a single headline from [`crate::client::Client::req_historical_news`].
+    fn historical_news(&mut self, req_id: i64, headline: payload::Headline) -> impl Future {}
+    /// The callback message indicating that every headline from [`crate::client::Client::req_historical_news`]
+    /// has been received. When `has_more` is [`true`], older headlines are available.
+    fn historical_news_end(&mut self, req_id: i64, has_more: bool) -> impl Future {}
+    /// The callback message containing the expirations and strikes of the options on an underlying
+    /// that are listed on one exchange under one trading class, from
+    /// [`crate::client::Client::req_option_parameters`].
+    fn option_parameters(&mut self, req_id: i64, params: payload::OptionParameters) -> impl Future {}
+    /// The callback message indicating that every [`payload::OptionParameters`] from
+    /// [`crate::client::Client::req_option_parameters`] has been received.
+    fn option_parameters_end(&mut self, req_id: i64) -> impl Future {}
+    /// The callback message containing a contract that answers a query which no
+    /// [`crate::contract::Security`] constructor awaits, such as one that was abandoned before its
+    /// end message arrived.
+    fn contract_data(&mut self, req_id: i64, contract: Contract) -> impl Future {}
     /// The callback message containing account attributes from [`crate::client::Client::req_account_updates`].
     fn account_attribute(&mut self, attribute: Attribute, account_number: String) -> impl Future {}
     /// The callback message containing information about a single [`Position`] from [`crate::client::Client::req_account_updates`].
@@ -153,6 +255,13 @@ pub trait LocalWrapper {
     fn pnl(&mut self, req_id: i64, pnl: Pnl) -> impl Future {}
     /// The callback message that contains information about PNL of a single position from ['crate::client::Client::req_pnl_single'].
     fn single_position_pnl(&mut self, req_id: i64, pnl: PnlSingle) -> impl Future {}
+    /// The callback message containing the XML document from [`crate::client::Client::req_scanner_parameters`].
+    ///
+    /// The document can be parsed with [`crate::scanner::Parameters::parse`], if the `scanner`
+    /// feature is enabled.
+    fn scanner_parameters(&mut self, xml: String) -> impl Future {}
+    /// The callback message containing a single scan from [`crate::client::Client::req_scanner_subscription`].
+    fn scanner_data(&mut self, req_id: i64, rows: Vec<payload::ScannerRow>) -> impl Future {}
     /// The callback message indicating that all the information for a given account has been received.
     fn account_download_end(&mut self, account_number: String) -> impl Future {}
     /// The callback message associated with account summary information from [`crate::client::Client::req_account_summary`].
@@ -165,6 +274,27 @@ pub trait LocalWrapper {
     }
     /// The callback message indicating that all the position information has been received.
     fn position_end(&mut self) -> impl Future {}
+    /// The callback message containing information about a single position from [`crate::client::Client::req_positions_multi`].
+    fn position_multi(
+        &mut self,
+        req_id: i64,
+        position: PositionSummary,
+        model_code: Option<String>,
+    ) -> impl Future {
+    }
+    /// The callback message indicating that all the position information from [`crate::client::Client::req_positions_multi`] has been received.
+    fn position_multi_end(&mut self, req_id: i64) -> impl Future {}
+    /// The callback message containing account attributes from [`crate::client::Client::req_account_updates_multi`].
+    fn account_attribute_multi(
+        &mut self,
+        req_id: i64,
+        attribute: Attribute,
+        account_number: String,
+        model_code: Option<String>,
+    ) -> impl Future {
+    }
+    /// The callback message indicating that all the account information from [`crate::client::Client::req_account_updates_multi`] has been received.
+    fn account_attribute_multi_end(&mut self, req_id: i64) -> impl Future {}
     /// The callback message indicating that all the account summary information has been received.
     fn account_summary_end(&mut self, req_id: i64) -> impl Future {}
     /// The callback message indicating that all the contract information has been received.
@@ -180,11 +310,22 @@ pub trait LocalWrapper {
         &mut self,
         order_id: i64,
         proxy: ExchangeProxy<Contract>,
+        amount: OrderAmount,
         client_id: i64,
         parent_id: Option<i64>,
         permanent_id: i64,
     ) -> impl Future {
     }
+    /// The callback message that binds an order placed in TWS (i.e. manually, not through the API)
+    /// to an API client and order ID. Orders placed in TWS are only bound when the client is the
+    /// one that called [`crate::client::Client::req_auto_open_orders`].
+    fn order_bound(
+        &mut self,
+        api_client_id: i64,
+        api_order_id: i64,
+        permanent_id: i64,
+    ) -> impl Future {
+    }
     /// The callback message that contains information about an execution.
     fn execution(&mut self, req_id: i64, execution: Execution) -> impl Future {}
     ///  The callback message indicating the end of an execution details request
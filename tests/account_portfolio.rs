@@ -1,8 +1,8 @@
-use std::future::Future;
+#![allow(clippy::manual_async_fn)]
 
-use tokio;
+use std::future::Future;
 
-use ibapi::account::Tag;
+use ibapi::account::{Group, Tag};
 use ibapi::client::{ActiveClient, Builder, Host, Mode};
 use ibapi::wrapper::{CancelToken, Initializer, Recurring, Wrapper};
 
@@ -12,7 +12,7 @@ impl Wrapper for AccountDataWrapper {}
 
 impl Recurring for AccountDataWrapper {
     fn cycle(&mut self) -> impl Future<Output = ()> + Send {
-        async { () }
+        async {}
     }
 }
 
@@ -28,37 +28,41 @@ impl Initializer for AccountSummaryInitializer {
     ) -> impl Future<Output = Self::Wrap<'_>> + Send {
         async {
             let id = client
-                .req_account_summary(&vec![
-                    Tag::AccountType,
-                    Tag::NetLiquidation,
-                    Tag::TotalCashValue,
-                    Tag::SettledCash,
-                    Tag::AccruedCash,
-                    Tag::BuyingPower,
-                    Tag::AvailableFunds,
-                    Tag::EquityWithLoanValue,
-                    Tag::PreviousEquityWithLoanValue,
-                    Tag::GrossPositionValue,
-                    Tag::RegTEquity,
-                    Tag::RegTMargin,
-                    Tag::Sma,
-                    Tag::InitMarginReq,
-                    Tag::MaintenanceMarginReq,
-                    Tag::AvailableFunds,
-                    Tag::ExcessLiquidity,
-                    Tag::Cushion,
-                    Tag::FullInitMarginReq,
-                    Tag::FullMaintenanceMarginReq,
-                    Tag::FullAvailableFunds,
-                    Tag::FullExcessLiquidity,
-                    Tag::LookAheadNextChange,
-                    Tag::LookAheadMaintenanceMarginReq,
-                    Tag::LookAheadAvailableFunds,
-                    Tag::LookAheadExcessLiquidity,
-                    Tag::HighestSeverity,
-                    Tag::DayTradesRemaining,
-                    Tag::Leverage,
-                ])
+                .req_account_summary(
+                    &Group::All,
+                    &[
+                        Tag::AccountType,
+                        Tag::NetLiquidation,
+                        Tag::TotalCashValue,
+                        Tag::SettledCash,
+                        Tag::AccruedCash,
+                        Tag::BuyingPower,
+                        Tag::AvailableFunds,
+                        Tag::EquityWithLoanValue,
+                        Tag::PreviousEquityWithLoanValue,
+                        Tag::GrossPositionValue,
+                        Tag::RegTEquity,
+                        Tag::RegTMargin,
+                        Tag::Sma,
+                        Tag::InitMarginReq,
+                        Tag::MaintenanceMarginReq,
+                        Tag::AvailableFunds,
+                        Tag::ExcessLiquidity,
+                        Tag::Cushion,
+                        Tag::FullInitMarginReq,
+                        Tag::FullMaintenanceMarginReq,
+                        Tag::FullAvailableFunds,
+                        Tag::FullExcessLiquidity,
+                        Tag::LookAheadNextChange,
+                        Tag::LookAheadMaintenanceMarginReq,
+                        Tag::LookAheadAvailableFunds,
+                        Tag::LookAheadExcessLiquidity,
+                        Tag::HighestSeverity,
+                        Tag::DayTradesRemaining,
+                        Tag::Leverage,
+                    ],
+                    None,
+                )
                 .await
                 .unwrap();
             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
@@ -152,7 +156,10 @@ impl Initializer for PnlInitializer {
     ) -> impl Future<Output = Self::Wrap<'_>> + Send {
         async {
             let id = client
-                .req_pnl(&client.get_managed_accounts().iter().next().unwrap().clone())
+                .req_pnl(
+                    &client.get_managed_accounts().iter().next().unwrap().clone(),
+                    None,
+                )
                 .await
                 .unwrap();
             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
@@ -1,7 +1,7 @@
 use std::future::Future;
 use ibapi::prelude::*;
 
-
+#[allow(clippy::large_enum_variant)]
 enum ExecutionMessage {
     Response(i64, Execution),
     Finished(i64),
@@ -44,6 +44,7 @@ async fn spy_bars() -> Result<(), Box<dyn std::error::Error>> {
             historical_bar::Size::Minutes(historical_bar::MinuteSize::Fifteen),
             historical_bar::Trades,
             false,
+            historical_bar::DateFormat::Local,
         )
         .await?;
     if let Some(msg) = rx
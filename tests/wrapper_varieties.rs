@@ -1,6 +1,8 @@
+#![allow(clippy::manual_async_fn)]
+
 use std::future::Future;
 
-use ibapi::client::{ActiveClient, Builder, Host, Mode};
+use ibapi::client::{ActiveClient, Builder, Host, Mode, Session};
 use ibapi::wrapper::{
     CancelToken, Initializer, LocalInitializer, LocalRecurring, LocalWrapper, Recurring, Wrapper,
 };
@@ -12,7 +14,7 @@ impl Wrapper for SendWrapper {}
 
 impl Recurring for SendWrapper {
     fn cycle(&mut self) -> impl Future<Output = ()> + Send {
-        async { () }
+        async {}
     }
 }
 
@@ -89,6 +91,34 @@ async fn disaggregated_remote() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn session() -> Result<(), Box<dyn std::error::Error>> {
+    let mut session = Session::new(
+        Builder::from_config_file(Mode::Paper, Host::Gateway, &None::<&'static str>)?
+            .with_contract_cache(ibapi::contract::Cache::new()),
+    );
+    session.connect(8, SendWrapper).await?;
+    session.connect(9, SendWrapper).await?;
+    assert!(session.connect(9, SendWrapper).await.is_err());
+
+    let client = session
+        .client_mut(8)
+        .ok_or("client 8 should be connected")?;
+    let aapl: ibapi::contract::Stock =
+        ibapi::contract::new(client, "BBG000B9XRY4".parse()?).await?;
+    let client = session
+        .client_mut(9)
+        .ok_or("client 9 should be connected")?;
+    assert_eq!(client.get_contract_cache().map(|c| c.len()), Some(1));
+    assert_eq!(aapl.symbol(), "AAPL");
+
+    assert!(session.disconnect_client(9).await?);
+    assert_eq!(session.client_ids().collect::<Vec<_>>(), vec![8]);
+    let builder = session.disconnect().await?;
+    assert!(builder.connect(8).await.is_ok());
+    Ok(())
+}
+
 #[tokio::test]
 async fn remote() -> Result<(), Box<dyn std::error::Error>> {
     let cancel_token =